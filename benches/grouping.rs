@@ -0,0 +1,74 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use serde_json::json;
+use std::path::PathBuf;
+
+use row::project::group_directories;
+use row::values::DirectoryValues;
+use row::workflow::Action;
+
+/// Create the values of a parameter sweep with `n` directories.
+fn sweep(n: usize) -> (DirectoryValues, Vec<PathBuf>) {
+    let mut values = DirectoryValues::default();
+    let mut directories = Vec::with_capacity(n);
+    for i in 0..n {
+        let directory = PathBuf::from(format!("{i:016x}"));
+        values
+            .insert(
+                &directory,
+                &json!({
+                    "temperature": (i % 50) as f64 * 0.1,
+                    "pressure": (i / 50 % 100) as f64,
+                    "replicate": i % 7,
+                    "structure": {"name": format!("s{}", i % 13), "n": 4096},
+                }),
+            )
+            .unwrap();
+        directories.push(directory);
+    }
+    (values, directories)
+}
+
+fn grouping(c: &mut Criterion) {
+    let mut group = c.benchmark_group("group_directories");
+    group.sample_size(10);
+
+    for n in [10_000, 100_000] {
+        let (values, directories) = sweep(n);
+
+        let mut action = Action::default();
+        action.group.maximum_size = Some(100);
+        group.bench_function(format!("unsorted/{n}"), |b| {
+            b.iter_batched(
+                || directories.clone(),
+                |directories| group_directories(&values, &action, directories).unwrap(),
+                BatchSize::LargeInput,
+            );
+        });
+
+        action.group.sort_by = Some(vec!["/structure/name".into(), "/temperature".into()]);
+        group.bench_function(format!("sort/{n}"), |b| {
+            b.iter_batched(
+                || directories.clone(),
+                |directories| group_directories(&values, &action, directories).unwrap(),
+                BatchSize::LargeInput,
+            );
+        });
+
+        action.group.split_by_sort_key = Some(true);
+        group.bench_function(format!("sort_and_split/{n}"), |b| {
+            b.iter_batched(
+                || directories.clone(),
+                |directories| group_directories(&values, &action, directories).unwrap(),
+                BatchSize::LargeInput,
+            );
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, grouping);
+criterion_main!(benches);
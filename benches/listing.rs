@@ -0,0 +1,64 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use indicatif::{MultiProgress, ProgressDrawTarget};
+use std::fs;
+use tempfile::TempDir;
+
+use row::workflow::Workflow;
+use row::{workspace, MultiProgressContainer};
+
+/// Create a project with `n` directories and `n / 10` files in the workspace.
+fn project(n: usize) -> TempDir {
+    let temp = TempDir::new().unwrap();
+    fs::write(temp.path().join("workflow.toml"), "").unwrap();
+    let workspace = temp.path().join("workspace");
+    for i in 0..n {
+        fs::create_dir_all(workspace.join(format!("dir{i}"))).unwrap();
+    }
+    for i in 0..n / 10 {
+        fs::write(workspace.join(format!("file{i}")), "").unwrap();
+    }
+    temp
+}
+
+fn listing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("list_directories");
+    group.sample_size(10);
+
+    for n in [10_000, 100_000] {
+        let temp = project(n);
+        let workflow = Workflow::open_in(temp.path()).unwrap();
+        let mut multi_progress = MultiProgressContainer::new(MultiProgress::with_draw_target(
+            ProgressDrawTarget::hidden(),
+        ));
+
+        group.bench_function(format!("row/{n}"), |b| {
+            b.iter(|| workspace::list_directories(&workflow, 8, &mut multi_progress).unwrap());
+        });
+
+        // A single thread that checks the type of each entry in order.
+        let path = temp.path().join("workspace");
+        group.bench_function(format!("read_dir/{n}"), |b| {
+            b.iter(|| {
+                path.read_dir()
+                    .unwrap()
+                    .filter_map(|entry| {
+                        let entry = entry.unwrap();
+                        entry
+                            .file_type()
+                            .unwrap()
+                            .is_dir()
+                            .then(|| entry.file_name())
+                    })
+                    .collect::<Vec<_>>()
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, listing);
+criterion_main!(benches);
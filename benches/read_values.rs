@@ -0,0 +1,112 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use indicatif::{MultiProgress, ProgressDrawTarget};
+use serde_json::{json, Value};
+use std::env;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+use row::project::Project;
+use row::MultiProgressContainer;
+
+/// Create a project with `n` directories that each hold a large value file.
+///
+/// Each value is a statepoint and a document with a long array, similar to a
+/// signac project. The workflow references only `/temperature`.
+///
+fn project(n: usize, value_cache: &str) -> TempDir {
+    let temp = TempDir::new().unwrap();
+    fs::write(
+        temp.path().join("workflow.toml"),
+        format!(
+            r#"
+[workspace]
+value_file = "value.json"
+value_cache = "{value_cache}"
+
+[[action]]
+name = "a"
+command = "c"
+[[action.group.include]]
+condition = ["/temperature", ">", 1.0]
+"#
+        ),
+    )
+    .unwrap();
+
+    let workspace = temp.path().join("workspace");
+    for i in 0..n {
+        let directory = workspace.join(format!("dir{i}"));
+        fs::create_dir_all(&directory).unwrap();
+        let value = json!({
+            "temperature": (i % 50) as f64 * 0.1,
+            "structure": {"name": format!("s{}", i % 13), "n": 4096},
+            "document": {"energy": (0..256).map(|j| (i + j) as f64 * 0.5).collect::<Vec<_>>()},
+        });
+        fs::write(directory.join("value.json"), value.to_string()).unwrap();
+    }
+    temp
+}
+
+/// Open the project in the current directory.
+fn open(io_threads: u16) -> Project {
+    let mut multi_progress =
+        MultiProgressContainer::new(MultiProgress::with_draw_target(ProgressDrawTarget::hidden()));
+    Project::open_offline(
+        Some(io_threads),
+        &Some("none".into()),
+        None,
+        &mut multi_progress,
+    )
+    .unwrap()
+}
+
+/// Remove the cache files of the project.
+fn remove_cache(path: &Path) {
+    let _ = fs::remove_dir_all(path.join(".row"));
+}
+
+fn read_values(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read_values");
+    group.sample_size(10);
+    env::set_var("ROW_HOME", "/not/a/path");
+
+    let n = 10_000;
+    for value_cache in ["all", "referenced"] {
+        let temp = project(n, value_cache);
+        env::set_current_dir(temp.path()).unwrap();
+
+        // Parse every value file in order on one thread.
+        if value_cache == "all" {
+            group.bench_function(format!("serial/{n}"), |b| {
+                b.iter(|| {
+                    (0..n)
+                        .map(|i| {
+                            let bytes = fs::read(format!("workspace/dir{i}/value.json")).unwrap();
+                            serde_json::from_slice::<Value>(&bytes).unwrap()
+                        })
+                        .collect::<Vec<_>>()
+                });
+            });
+        }
+
+        for io_threads in [1, 8] {
+            group.bench_function(format!("{value_cache}/{io_threads}/{n}"), |b| {
+                // Remove the cache so that row reads every value file.
+                b.iter_batched(
+                    || remove_cache(temp.path()),
+                    |()| open(io_threads),
+                    BatchSize::PerIteration,
+                );
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, read_values);
+criterion_main!(benches);
@@ -0,0 +1,63 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+use row::values::DirectoryValues;
+
+/// Create `n` directory names and values in a scrambled order.
+fn directories(n: u64) -> Vec<(PathBuf, Value)> {
+    (0..n)
+        .map(|i| {
+            let hash = i.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+            (
+                PathBuf::from(format!("{hash:016x}")),
+                json!({"i": i, "temperature": (i % 50) as f64 * 0.1}),
+            )
+        })
+        .collect()
+}
+
+fn values(c: &mut Criterion) {
+    let mut group = c.benchmark_group("directory_values");
+    group.sample_size(10);
+
+    for n in [10_000, 100_000] {
+        let entries = directories(n);
+
+        group.bench_function(format!("insert/{n}"), |b| {
+            b.iter(|| {
+                let mut values = DirectoryValues::default();
+                for (directory, value) in &entries {
+                    values.insert(directory, value).unwrap();
+                }
+                values
+            });
+        });
+
+        group.bench_function(format!("try_extend/{n}"), |b| {
+            b.iter_batched(
+                || entries.clone(),
+                |entries| DirectoryValues::try_from_iter(entries).unwrap(),
+                BatchSize::LargeInput,
+            );
+        });
+
+        let values = DirectoryValues::try_from_iter(entries.clone()).unwrap();
+        group.bench_function(format!("get/{n}"), |b| {
+            b.iter(|| {
+                entries
+                    .iter()
+                    .filter(|(directory, _)| values.get(directory).is_some_and(Value::is_object))
+                    .count()
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, values);
+criterion_main!(benches);
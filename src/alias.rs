@@ -0,0 +1,192 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use log::{debug, trace};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::workflow::Workflow;
+use crate::{Error, ALIASES_FILE_NAME};
+
+/// Short names for directories.
+///
+/// `Aliases` maps user-chosen names to directories in the workspace. Commands
+/// that accept directories resolve aliases transparently. The aliases are stored
+/// in `.row/aliases.json`.
+///
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Aliases {
+    /// alias -> directory
+    aliases: BTreeMap<String, PathBuf>,
+
+    /// directory -> alias (the first in sorted order when there are several)
+    names: HashMap<PathBuf, String>,
+}
+
+impl Aliases {
+    /// Read the aliases of the given workflow.
+    ///
+    /// # Errors
+    /// Returns `Err<row::Error>` when the aliases file cannot be read or parsed.
+    ///
+    pub fn open(workflow: &Workflow) -> Result<Self, Error> {
+        let aliases_file = workflow.data_directory().join(ALIASES_FILE_NAME);
+
+        match fs::read(&aliases_file) {
+            Ok(bytes) => {
+                debug!("Reading aliases '{}'.", aliases_file.display());
+                let aliases = serde_json::from_slice(&bytes)
+                    .map_err(|e| Error::JSONParse(aliases_file, e))?;
+                Ok(Self::from_map(aliases))
+            }
+            Err(error) => match error.kind() {
+                io::ErrorKind::NotFound => {
+                    trace!("'{}' not found, using no aliases.", aliases_file.display());
+                    Ok(Self::default())
+                }
+                _ => Err(Error::FileRead(aliases_file, error)),
+            },
+        }
+    }
+
+    /// Save the aliases of the given workflow.
+    ///
+    /// # Errors
+    /// Returns `Err<row::Error>` when the aliases file cannot be written.
+    ///
+    pub fn save(&self, workflow: &Workflow) -> Result<(), Error> {
+        let data_directory = workflow.data_directory();
+        let aliases_file = data_directory.join(ALIASES_FILE_NAME);
+        debug!("Saving aliases '{}'.", aliases_file.display());
+
+        let out_bytes = serde_json::to_vec_pretty(&self.aliases)
+            .map_err(|e| Error::JSONSerialize(aliases_file.clone(), e))?;
+
+        fs::create_dir_all(&data_directory)
+            .map_err(|e| Error::DirectoryCreate(data_directory, e))?;
+        fs::write(&aliases_file, out_bytes).map_err(|e| Error::FileWrite(aliases_file, e))
+    }
+
+    fn from_map(aliases: BTreeMap<String, PathBuf>) -> Self {
+        let mut names = HashMap::with_capacity(aliases.len());
+        for (alias, directory) in &aliases {
+            names
+                .entry(directory.clone())
+                .or_insert_with(|| alias.clone());
+        }
+        Self { aliases, names }
+    }
+
+    /// Add an alias.
+    ///
+    /// # Returns
+    /// The directory previously named by `alias`, if any.
+    ///
+    pub fn add(&mut self, alias: String, directory: PathBuf) -> Option<PathBuf> {
+        let mut aliases = std::mem::take(&mut self.aliases);
+        let previous = aliases.insert(alias, directory);
+        *self = Self::from_map(aliases);
+        previous
+    }
+
+    /// Remove an alias.
+    ///
+    /// # Returns
+    /// The directory named by `alias`, if any.
+    ///
+    pub fn remove(&mut self, alias: &str) -> Option<PathBuf> {
+        let mut aliases = std::mem::take(&mut self.aliases);
+        let previous = aliases.remove(alias);
+        *self = Self::from_map(aliases);
+        previous
+    }
+
+    /// Get the directory named by `directory` (which may be an alias).
+    pub fn resolve(&self, directory: PathBuf) -> PathBuf {
+        match directory.to_str().and_then(|d| self.aliases.get(d)) {
+            Some(resolved) => {
+                trace!(
+                    "Resolved alias '{}' to '{}'.",
+                    directory.display(),
+                    resolved.display()
+                );
+                resolved.clone()
+            }
+            None => directory,
+        }
+    }
+
+    /// Get the alias of a directory.
+    pub fn alias(&self, directory: &Path) -> Option<&str> {
+        self.names.get(directory).map(String::as_str)
+    }
+
+    /// Iterate over the aliases and their directories in sorted order.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &PathBuf)> {
+        self.aliases.iter()
+    }
+
+    /// Check whether there are no aliases.
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_fs::TempDir;
+    use serial_test::parallel;
+
+    use super::*;
+
+    #[test]
+    #[parallel]
+    fn add_remove_resolve() {
+        let mut aliases = Aliases::default();
+        assert!(aliases.is_empty());
+
+        assert_eq!(aliases.add("b".into(), PathBuf::from("dir1")), None);
+        assert_eq!(aliases.add("a".into(), PathBuf::from("dir1")), None);
+        assert_eq!(aliases.add("c".into(), PathBuf::from("dir2")), None);
+        assert_eq!(
+            aliases.add("c".into(), PathBuf::from("dir3")),
+            Some(PathBuf::from("dir2"))
+        );
+
+        assert_eq!(aliases.resolve(PathBuf::from("a")), PathBuf::from("dir1"));
+        assert_eq!(aliases.resolve(PathBuf::from("c")), PathBuf::from("dir3"));
+        assert_eq!(
+            aliases.resolve(PathBuf::from("dir2")),
+            PathBuf::from("dir2")
+        );
+
+        assert_eq!(aliases.alias(Path::new("dir1")), Some("a"));
+        assert_eq!(aliases.alias(Path::new("dir2")), None);
+        assert_eq!(aliases.alias(Path::new("dir3")), Some("c"));
+
+        assert_eq!(aliases.remove("a"), Some(PathBuf::from("dir1")));
+        assert_eq!(aliases.remove("a"), None);
+        assert_eq!(aliases.alias(Path::new("dir1")), Some("b"));
+        assert_eq!(
+            aliases.iter().map(|(a, _)| a.as_str()).collect::<Vec<_>>(),
+            vec!["b", "c"]
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn save_open() {
+        let temp = TempDir::new().unwrap();
+        let workflow = Workflow::open_str(temp.path(), "").unwrap();
+
+        assert_eq!(Aliases::open(&workflow).unwrap(), Aliases::default());
+
+        let mut aliases = Aliases::default();
+        aliases.add("a".into(), PathBuf::from("dir1"));
+        aliases.save(&workflow).unwrap();
+
+        assert_eq!(Aliases::open(&workflow).unwrap(), aliases);
+    }
+}
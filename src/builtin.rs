@@ -15,7 +15,7 @@ impl BuiltIn for launcher::Configuration {
     ///
     fn built_in() -> Self {
         let mut result = Self {
-            launchers: HashMap::with_capacity(2),
+            launchers: HashMap::with_capacity(6),
         };
 
         let mut openmp = HashMap::with_capacity(1);
@@ -37,6 +37,9 @@ impl BuiltIn for launcher::Configuration {
                 processes: Some("--ntasks=".into()),
                 threads_per_process: Some("--cpus-per-task=".into()),
                 gpus_per_process: Some("--tres-per-task=gres/gpu:".into()),
+                cpu_bind: Some("--cpu-bind=".into()),
+                concurrent: Some("--exact".into()),
+                ..Launcher::default()
             },
         );
 
@@ -47,6 +50,9 @@ impl BuiltIn for launcher::Configuration {
                 processes: Some("--ntasks=".into()),
                 threads_per_process: Some("--cpus-per-task=".into()),
                 gpus_per_process: Some("--tres-per-task=gres/gpu:".into()),
+                cpu_bind: Some("--cpu-bind=".into()),
+                concurrent: Some("--exact".into()),
+                ..Launcher::default()
             },
         );
 
@@ -61,6 +67,59 @@ impl BuiltIn for launcher::Configuration {
 
         result.launchers.insert("mpi".into(), mpi);
 
+        let mut srun = HashMap::with_capacity(1);
+        srun.insert(
+            "default".into(),
+            Launcher {
+                executable: Some("srun".into()),
+                processes: Some("--ntasks=".into()),
+                threads_per_process: Some("--cpus-per-task=".into()),
+                gpus_per_process: Some("--tres-per-task=gres/gpu:".into()),
+                cpu_bind: Some("--cpu-bind=".into()),
+                concurrent: Some("--exact".into()),
+                ..Launcher::default()
+            },
+        );
+
+        result.launchers.insert("srun".into(), srun);
+
+        let mut openmpi = HashMap::with_capacity(1);
+        openmpi.insert(
+            "default".into(),
+            Launcher {
+                executable: Some("mpirun".into()),
+                processes: Some("-np ".into()),
+                threads_per_process: Some("--map-by slot:PE=".into()),
+                ..Launcher::default()
+            },
+        );
+
+        result.launchers.insert("openmpi".into(), openmpi);
+
+        let mut mpich = HashMap::with_capacity(1);
+        mpich.insert(
+            "default".into(),
+            Launcher {
+                executable: Some("mpiexec".into()),
+                processes: Some("-n ".into()),
+                ..Launcher::default()
+            },
+        );
+
+        result.launchers.insert("mpich".into(), mpich);
+
+        let mut intelmpi = HashMap::with_capacity(1);
+        intelmpi.insert(
+            "default".into(),
+            Launcher {
+                executable: Some("mpirun".into()),
+                processes: Some("-n ".into()),
+                ..Launcher::default()
+            },
+        );
+
+        result.launchers.insert("intelmpi".into(), intelmpi);
+
         result
     }
 }
@@ -73,6 +132,13 @@ fn andes() -> Cluster {
         identify: IdentificationMethod::ByEnvironment("LMOD_SYSTEM_NAME".into(), "andes".into()),
         scheduler: SchedulerType::Slurm,
         submit_options: Vec::new(),
+        mpi_launcher: None,
+        qos: Vec::new(),
+        bash: None,
+        job_url_template: None,
+        kubernetes: None,
+        max_submissions_per_minute: None,
+        auto_select_strategy: None,
         partition: vec![
             // Auto-detected partitions: batch
             Partition {
@@ -94,6 +160,13 @@ fn anvil() -> Cluster {
         identify: IdentificationMethod::ByEnvironment("RCAC_CLUSTER".into(), "anvil".into()),
         scheduler: SchedulerType::Slurm,
         submit_options: Vec::new(),
+        mpi_launcher: None,
+        qos: Vec::new(),
+        bash: None,
+        job_url_template: None,
+        kubernetes: None,
+        max_submissions_per_minute: None,
+        auto_select_strategy: None,
         partition: vec![
             // Auto-detected partitions: shared | wholenode | gpu
             Partition {
@@ -152,6 +225,13 @@ fn delta() -> Cluster {
         identify: IdentificationMethod::ByEnvironment("LMOD_SYSTEM_NAME".into(), "Delta".into()),
         scheduler: SchedulerType::Slurm,
         submit_options: vec!["--constraint=\"scratch\"".to_string()],
+        mpi_launcher: None,
+        qos: Vec::new(),
+        bash: None,
+        job_url_template: None,
+        kubernetes: None,
+        max_submissions_per_minute: None,
+        auto_select_strategy: None,
         partition: vec![
             // Auto-detected partitions: cpu | gpuA100x4
             Partition {
@@ -210,6 +290,13 @@ fn frontier() -> Cluster {
         identify: IdentificationMethod::ByEnvironment("LMOD_SYSTEM_NAME".into(), "frontier".into()),
         scheduler: SchedulerType::Slurm,
         submit_options: vec!["--constraint=\"nvme\"".to_string()],
+        mpi_launcher: None,
+        qos: Vec::new(),
+        bash: None,
+        job_url_template: None,
+        kubernetes: None,
+        max_submissions_per_minute: None,
+        auto_select_strategy: None,
         partition: vec![
             // Auto-detected partitions: batch
             Partition {
@@ -230,6 +317,13 @@ fn greatlakes() -> Cluster {
         identify: IdentificationMethod::ByEnvironment("CLUSTER_NAME".into(), "greatlakes".into()),
         scheduler: SchedulerType::Slurm,
         submit_options: Vec::new(),
+        mpi_launcher: None,
+        qos: Vec::new(),
+        bash: None,
+        job_url_template: None,
+        kubernetes: None,
+        max_submissions_per_minute: None,
+        auto_select_strategy: None,
         partition: vec![
             // Auto-detected partitions: standard | gpu_mig40,gpu | gpu.
             Partition {
@@ -294,6 +388,103 @@ fn greatlakes() -> Cluster {
     }
 }
 
+fn slurm_cpu() -> Cluster {
+    ////////////////////////////////////////////////////////////////////////////////////////
+    // Fake CPU cluster for tutorials and tests. Select it with `--cluster slurm-cpu`.
+    Cluster {
+        name: "slurm-cpu".into(),
+        identify: IdentificationMethod::Always(false),
+        scheduler: SchedulerType::Slurm,
+        submit_options: vec!["--constraint=\"fake\"".to_string()],
+        mpi_launcher: None,
+        qos: vec!["normal".into(), "debug".into()],
+        bash: None,
+        job_url_template: None,
+        kubernetes: None,
+        max_submissions_per_minute: None,
+        auto_select_strategy: None,
+        partition: vec![
+            Partition {
+                name: "shared".into(),
+                maximum_cpus_per_job: Some(63),
+                maximum_gpus_per_job: Some(0),
+                memory_per_cpu: Some("2G".into()),
+                ..Partition::default()
+            },
+            Partition {
+                name: "wholenode".into(),
+                require_cpus_multiple_of: Some(64),
+                maximum_gpus_per_job: Some(0),
+                cpus_per_node: Some(64),
+                cores_per_socket: Some(32),
+                sockets_per_node: Some(2),
+                ..Partition::default()
+            },
+            Partition {
+                name: "flexible".into(),
+                warn_cpus_not_multiple_of: Some(64),
+                maximum_gpus_per_job: Some(0),
+                cpus_per_node: Some(64),
+                prevent_auto_select: true,
+                ..Partition::default()
+            },
+        ],
+    }
+}
+
+fn slurm_gpu() -> Cluster {
+    ////////////////////////////////////////////////////////////////////////////////////////
+    // Fake GPU cluster for tutorials and tests. Select it with `--cluster slurm-gpu`.
+    Cluster {
+        name: "slurm-gpu".into(),
+        identify: IdentificationMethod::Always(false),
+        scheduler: SchedulerType::Slurm,
+        submit_options: Vec::new(),
+        mpi_launcher: None,
+        qos: Vec::new(),
+        bash: None,
+        job_url_template: None,
+        kubernetes: None,
+        max_submissions_per_minute: None,
+        auto_select_strategy: None,
+        partition: vec![
+            Partition {
+                name: "cpu".into(),
+                maximum_gpus_per_job: Some(0),
+                cpus_per_node: Some(32),
+                account_suffix: Some("-cpu".into()),
+                ..Partition::default()
+            },
+            Partition {
+                name: "gpu-single".into(),
+                minimum_gpus_per_job: Some(1),
+                maximum_gpus_per_job: Some(1),
+                memory_per_gpu: Some("32G".into()),
+                account_suffix: Some("-gpu".into()),
+                ..Partition::default()
+            },
+            Partition {
+                name: "gpu".into(),
+                minimum_gpus_per_job: Some(1),
+                warn_gpus_not_multiple_of: Some(4),
+                memory_per_gpu: Some("32G".into()),
+                gpus_per_node: Some(4),
+                account_suffix: Some("-gpu".into()),
+                ..Partition::default()
+            },
+            Partition {
+                name: "gpu-wholenode".into(),
+                minimum_gpus_per_job: Some(1),
+                require_gpus_multiple_of: Some(4),
+                gpus_per_node: Some(4),
+                account_suffix: Some("-gpu".into()),
+                prevent_auto_select: true,
+                ..Partition::default()
+            },
+        ],
+    }
+}
+
 fn none() -> Cluster {
     // Fallback none cluster.
     Cluster {
@@ -301,6 +492,13 @@ fn none() -> Cluster {
         identify: IdentificationMethod::Always(true),
         scheduler: SchedulerType::Bash,
         submit_options: Vec::new(),
+        mpi_launcher: None,
+        qos: Vec::new(),
+        bash: None,
+        job_url_template: None,
+        kubernetes: None,
+        max_submissions_per_minute: None,
+        auto_select_strategy: None,
         partition: vec![Partition {
             name: "none".into(),
             ..Partition::default()
@@ -310,7 +508,16 @@ fn none() -> Cluster {
 
 impl BuiltIn for cluster::Configuration {
     fn built_in() -> Self {
-        let cluster = vec![andes(), anvil(), delta(), frontier(), greatlakes(), none()];
+        let cluster = vec![
+            andes(),
+            anvil(),
+            delta(),
+            frontier(),
+            greatlakes(),
+            slurm_cpu(),
+            slurm_gpu(),
+            none(),
+        ];
 
         cluster::Configuration { cluster }
     }
@@ -1,20 +1,47 @@
 // Copyright (c) 2024 The Regents of the University of Michigan.
 // Part of row, released under the BSD 3-Clause License.
 
+pub mod alias;
+pub mod check;
 pub mod clean;
 pub mod cluster;
+pub mod config;
+pub mod debug;
 pub mod directories;
+pub mod doctor;
+pub mod efficiency;
+pub mod exec;
+pub mod export;
+pub mod import;
 pub mod init;
 pub mod launchers;
+pub mod mv;
+pub mod plan;
+pub mod progress;
+pub mod rerun;
+pub mod rm;
 pub mod scan;
+pub mod staged;
 pub mod status;
 pub mod submit;
+pub mod sweep;
+pub mod wait;
 
 use clap::{Args, Parser, Subcommand, ValueEnum};
 use clap_verbosity_flag::{Verbosity, WarnLevel};
 use log::trace;
-use std::io;
-use std::path::PathBuf;
+use serde::Serialize;
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Write};
+use std::num::NonZeroU16;
+use std::path::{Path, PathBuf};
+
+use crate::ui::{self, Format, Overflow, Table};
+use row::alias::Aliases;
+use row::config::{Color, Settings};
+use row::format::TimeFormat;
+use row::MultiProgressContainer;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None, subcommand_required = true)]
@@ -32,12 +59,21 @@ pub struct Options {
 #[derive(Args, Debug, Clone)]
 pub struct GlobalOptions {
     /// Number of threads to use for IO intensive operations.
-    #[arg(long, default_value_t=8 as u16, global=true, env="ROW_IO_THREADS", display_order=2)]
-    pub io_threads: u16,
+    ///
+    /// Overrides `default.io_threads` in the workflow [default: 8].
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..), global=true, env="ROW_IO_THREADS", display_order=2)]
+    pub io_threads: Option<u16>,
 
-    /// When to print colored output.
-    #[arg(long, value_name="WHEN", value_enum, default_value_t=ColorMode::Auto, global=true, env="ROW_COLOR", display_order=2)]
-    pub color: ColorMode,
+    /// When to print colored output [default: auto].
+    #[arg(
+        long,
+        value_name = "WHEN",
+        value_enum,
+        global = true,
+        env = "ROW_COLOR",
+        display_order = 2
+    )]
+    pub color: Option<ColorMode>,
 
     /// Disable progress bars.
     #[arg(long, global = true, env = "ROW_NO_PROGRESS", display_order = 2)]
@@ -52,6 +88,68 @@ pub struct GlobalOptions {
     /// Autodetected by default.
     #[arg(long, global = true, env = "ROW_CLUSTER", display_order = 2)]
     cluster: Option<String>,
+
+    /// Operate on the given workspace directory instead of the one in the workflow.
+    #[arg(
+        short,
+        long,
+        value_name = "path",
+        global = true,
+        env = "ROW_WORKSPACE",
+        display_order = 2
+    )]
+    pub workspace: Option<PathBuf>,
+
+    /// Execute as if row was started in the given project directory.
+    #[arg(
+        long,
+        value_name = "path",
+        global = true,
+        env = "ROW_PROJECT",
+        display_order = 2
+    )]
+    pub project: Option<PathBuf>,
+
+    /// Store cache files in the given directory instead of '.row' in the project.
+    #[arg(
+        long,
+        value_name = "path",
+        global = true,
+        env = "ROW_CACHE_DIR",
+        display_order = 2
+    )]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Seconds to wait for each attempt to query the scheduler's queue [default: 30].
+    #[arg(long, value_name = "seconds", value_parser = clap::value_parser!(u64).range(1..), global = true, env = "ROW_QUEUE_TIMEOUT", display_order = 2)]
+    pub queue_timeout: Option<u64>,
+
+    /// How to format durations [default: human].
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_enum,
+        global = true,
+        env = "ROW_TIME_FORMAT",
+        display_order = 2
+    )]
+    pub time_format: Option<TimeFormatMode>,
+
+    /// Suppress all output except errors and requested data (set by `--quiet`).
+    #[arg(skip)]
+    pub quiet: bool,
+}
+
+/// Options that fit text tables in the terminal.
+#[derive(Args, Debug, Clone)]
+pub struct TableArguments {
+    /// Limit text tables to N columns (defaults to the terminal width, 0 for no limit).
+    #[arg(long, value_name = "N", display_order = 1)]
+    max_width: Option<usize>,
+
+    /// Wrap long entries onto multiple lines instead of truncating them.
+    #[arg(long, display_order = 1)]
+    wrap: bool,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -66,6 +164,69 @@ pub enum ColorMode {
     Never,
 }
 
+impl TableArguments {
+    /// Apply the options to a table written to `output` (stdout when `None`).
+    ///
+    /// Tables written to stdout fit in the terminal by default.
+    ///
+    pub(crate) fn apply(&self, table: Table, output: Option<&Path>) -> Table {
+        let max_width = match self.max_width {
+            Some(0) => None,
+            Some(max_width) => Some(max_width),
+            None if output.is_none() => ui::terminal_width(),
+            None => None,
+        };
+
+        table.with_max_width(max_width).with_overflow(if self.wrap {
+            Overflow::Wrap
+        } else {
+            Overflow::Truncate
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum TimeFormatMode {
+    /// Human readable durations (2 hours).
+    Human,
+
+    /// Clock times (02:00:00).
+    Clock,
+}
+
+impl GlobalOptions {
+    /// Get the format of durations.
+    pub fn time_format(&self) -> TimeFormat {
+        match self.time_format {
+            None | Some(TimeFormatMode::Human) => TimeFormat::Human,
+            Some(TimeFormatMode::Clock) => TimeFormat::Clock,
+        }
+    }
+
+    /// Use the settings for options that are not set on the command line.
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        if self.io_threads.is_none() {
+            self.io_threads = settings.io_threads.map(NonZeroU16::get);
+        }
+        if self.color.is_none() {
+            self.color = settings.color.map(|color| match color {
+                Color::Auto => ColorMode::Auto,
+                Color::Always => ColorMode::Always,
+                Color::Never => ColorMode::Never,
+            });
+        }
+        if self.cluster.is_none() {
+            self.cluster.clone_from(&settings.cluster);
+        }
+        if self.time_format.is_none() {
+            self.time_format = settings.time_format.map(|format| match format {
+                TimeFormat::Human => TimeFormatMode::Human,
+                TimeFormat::Clock => TimeFormatMode::Clock,
+            });
+        }
+    }
+}
+
 #[derive(Subcommand, Debug)]
 pub enum ShowCommands {
     /// Show the current state of the workflow.
@@ -94,10 +255,22 @@ pub enum ShowCommands {
     ///
     ///   row show status --action='project*'
     ///
+    /// * Show the status of all actions with the tag `analysis`:
+    ///
+    ///   row show status --tag=analysis
+    ///
+    /// * Refresh the status every 60 seconds:
+    ///
+    ///   row show status --watch 60
+    ///
     /// * Show the status of specific directories in the workspace:
     ///
     ///   row show status directory1 directory2
     ///
+    /// * Write the status to a CSV file:
+    ///
+    ///   row show status --output status.csv
+    ///
     Status(status::Arguments),
 
     /// List directories in the workspace.
@@ -109,10 +282,19 @@ pub enum ShowCommands {
     /// action. You can also show elements from the directory's value, accessed
     /// by JSON pointer. Blank lines separate groups.
     ///
+    /// When verbose (`-v`), `row show directories` also shows the cluster name and
+    /// time when row found each completed directory.
+    ///
     /// By default, `row show status` displays directories with any status. Set one or more
     /// of `--completed`, `--submitted`, `--eligible`, and `--waiting` to show specific
     /// directories that have specific statuses.
     ///
+    /// Combine the selected directories with lists of directories in files
+    /// (one per line, '-' reads stdin): `--union` adds the listed directories,
+    /// `--intersect` keeps only the listed directories, and `--difference`
+    /// removes the listed directories. `row show directories` applies all
+    /// `--union` files first, then `--intersect`, then `--difference`.
+    ///
     /// EXAMPLES
     ///
     /// * Show all the directories for action `one`:
@@ -123,6 +305,10 @@ pub enum ShowCommands {
     ///
     ///   row show directories --action action --value=/value
     ///
+    /// * Show a named column computed from the directory value:
+    ///
+    ///   row show directories --action action --value='volume=/L * /L * /L'
+    ///
     /// * Show specific directories:
     ///
     ///   row show directories --action action directory1 directory2
@@ -139,11 +325,17 @@ pub enum ShowCommands {
     ///
     ///   row show directories --action action --eligible --short
     ///
+    /// * Show the eligible directories listed in a file, except those in another:
+    ///
+    ///   row show directories --action action --eligible --intersect a.txt --difference b.txt
+    ///
     Directories(directories::Arguments),
 
     /// Show the cluster configuration.
     ///
-    /// Print the current cluster configuration in TOML format.
+    /// Print the current cluster configuration in TOML format. With
+    /// `--partitions`, print a table of the cluster's partitions and the
+    /// actions in the current workflow that select each one.
     ///
     /// EXAMPLES
     ///
@@ -159,6 +351,14 @@ pub enum ShowCommands {
     ///
     ///   row show cluster --all
     ///
+    /// * Show the partitions of the autodetected cluster:
+    ///
+    ///   row show cluster --partitions
+    ///
+    /// * Explain why row identified the current cluster:
+    ///
+    ///   row show cluster --why
+    ///
     Cluster(cluster::Arguments),
 
     /// Show launcher configurations.
@@ -186,7 +386,251 @@ pub enum ShowCommands {
     ///* Show only names of all launchers:
     ///
     ///  row show launchers --all --short
+    ///
+    ///* Show the launcher prefix for 4 directories of the action 'simulate':
+    ///
+    ///  row show launchers --resolve -a simulate --group-size 4
     Launchers(launchers::Arguments),
+
+    /// Show completion packs that have not yet been synchronized.
+    ///
+    /// `row scan` and `row submit` jobs stage completed directories in pack
+    /// files. The next command that synchronizes the project merges the packs
+    /// into the completed cache and removes them. `row show staged` prints one
+    /// row per pack with the action it records ("all" when the process scanned
+    /// every action), the job, host, and process that wrote it, the number of
+    /// directories it contains, and when it was written. Pending packs
+    /// (written by `row scan --pack-only`) have not yet been checked for
+    /// products.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Show the staged packs:
+    ///
+    ///   row show staged
+    ///
+    /// * Write the staged packs to a CSV file:
+    ///
+    ///   row show staged --output staged.csv
+    Staged(staged::Arguments),
+
+    /// Show how submitted jobs used the resources they requested.
+    ///
+    /// `row submit` records each job it submits in the job history. Jobs
+    /// record when they start and finish in the completion packs they write.
+    /// `row show efficiency` prints one row per action with finished jobs:
+    /// the number of jobs, the longest runtime, the largest fraction of the
+    /// requested walltime used by any job, the numbers of directories in the
+    /// jobs, and the numbers of GPUs they requested.
+    ///
+    /// `row show efficiency` warns about actions whose jobs all used less
+    /// than `--threshold` of the requested walltime and about actions that
+    /// requested the same number of GPUs for groups of every size. Each
+    /// warning suggests a change to `workflow.toml`.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Show the efficiency of all actions:
+    ///
+    ///   row show efficiency
+    ///
+    /// * Flag actions that used less than a quarter of the requested walltime:
+    ///
+    ///   row show efficiency --threshold 0.25
+    ///
+    Efficiency(efficiency::Arguments),
+    /// Show the weighted progress of the workflow.
+    ///
+    /// `row show progress` prints the fraction of the work in the workflow that
+    /// is complete. Each action contributes the estimated cost of executing it
+    /// on each directory it includes (in CPU-hours or GPU-hours, based on the
+    /// action's resources), so expensive actions count more than cheap ones.
+    ///
+    /// With `--by`, `row show progress` also prints the progress of each group
+    /// of directories that share the same value at the given JSON pointer.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Show the progress of the entire workflow:
+    ///
+    ///   row show progress
+    ///
+    /// * Show the progress of each temperature:
+    ///
+    ///   row show progress --by /temperature
+    ///
+    /// * Show the progress of the actions with the tag `analysis`:
+    ///
+    ///   row show progress --tag=analysis
+    ///
+    Progress(progress::Arguments),
+}
+
+impl ShowCommands {
+    /// Get the file given to `--output`.
+    pub fn output(&self) -> Option<&Path> {
+        match self {
+            ShowCommands::Status(args) => args.output.as_deref(),
+            ShowCommands::Directories(args) => args.output.as_deref(),
+            ShowCommands::Cluster(args) => args.output.as_deref(),
+            ShowCommands::Launchers(args) => args.output.as_deref(),
+            ShowCommands::Staged(args) => args.output.as_deref(),
+            ShowCommands::Efficiency(args) => args.output.as_deref(),
+            ShowCommands::Progress(args) => args.output.as_deref(),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AliasCommands {
+    /// Name a directory.
+    ///
+    /// `row alias add` names DIRECTORY with the short name ALIAS. Commands that
+    /// accept directories also accept ALIAS in place of DIRECTORY.
+    /// `row alias add` replaces any previous directory named ALIAS.
+    ///
+    /// ERRORS
+    ///
+    /// `row alias add` returns an error when DIRECTORY is not in the workspace
+    /// or when ALIAS is the name of a directory in the workspace.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Name a directory:
+    ///
+    ///   row alias add baseline 0b8d5c1d29ea27a8f6f3e6a6b2e0e5a1
+    ///
+    Add(alias::AddArguments),
+
+    /// Remove an alias.
+    ///
+    /// ERRORS
+    ///
+    /// `row alias remove` returns an error when ALIAS does not exist.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Remove an alias:
+    ///
+    ///   row alias remove baseline
+    ///
+    Remove(alias::RemoveArguments),
+
+    /// List the aliases.
+    ///
+    /// `row alias list` prints each alias and the directory it names.
+    ///
+    /// EXAMPLES
+    ///
+    /// * List all aliases:
+    ///
+    ///   row alias list
+    ///
+    List(alias::ListArguments),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    /// Print the value of a setting.
+    ///
+    /// `row config get` prints the value of KEY from the project settings, or
+    /// the user settings when the project does not set it. It prints nothing
+    /// when KEY is not set.
+    ///
+    /// ERRORS
+    ///
+    /// `row config get` returns an error when KEY is not a valid setting.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Print the default cluster:
+    ///
+    ///   row config get cluster
+    ///
+    Get(config::GetArguments),
+
+    /// Change a setting.
+    ///
+    /// `row config set` sets KEY to VALUE in the user settings (or the project
+    /// settings with `--local`). Valid keys are `cluster`, `color`, and
+    /// `io_threads`.
+    ///
+    /// ERRORS
+    ///
+    /// `row config set` returns an error when KEY is not a valid setting, when
+    /// VALUE is not valid for KEY, or when `--local` is given outside of a
+    /// project.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Disable colored output for all projects:
+    ///
+    ///   row config set color never
+    ///
+    /// * Submit the current project's jobs to a specific cluster by default:
+    ///
+    ///   row config set --local cluster greatlakes
+    ///
+    /// * Remove the project's default cluster:
+    ///
+    ///   row config set --local --unset cluster
+    ///
+    Set(config::SetArguments),
+
+    /// List the settings.
+    ///
+    /// `row config list` prints each setting, its value, and the scope that
+    /// sets it.
+    ///
+    /// EXAMPLES
+    ///
+    /// * List the effective settings:
+    ///
+    ///   row config list
+    ///
+    /// * List the user settings:
+    ///
+    ///   row config list --user
+    ///
+    List(config::ListArguments),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DebugCommands {
+    /// Manage a fake Slurm scheduler.
+    #[command(subcommand)]
+    FakeScheduler(FakeSchedulerCommands),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum FakeSchedulerCommands {
+    /// Install fake `sbatch`, `squeue`, and `scancel` commands.
+    ///
+    /// `row debug fake-scheduler install` writes `sbatch`, `squeue`, and
+    /// `scancel` scripts to DIRECTORY. Add DIRECTORY to the front of your
+    /// `PATH` to submit jobs without a Slurm cluster. The fake `sbatch` saves
+    /// each job script in `DIRECTORY/jobs` and appends the job ID to
+    /// `DIRECTORY/queue`. The fake `squeue` lists the IDs in the queue and the
+    /// fake `scancel` removes them. Jobs never execute on their own.
+    ///
+    /// Set `ROW_FAKE_SBATCH_ERROR` or `ROW_FAKE_SQUEUE_ERROR` to make `sbatch`
+    /// or `squeue` fail with the given message.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Submit jobs to the fake scheduler:
+    ///
+    ///   row debug fake-scheduler install /tmp/fake-slurm
+    ///
+    ///   export PATH="/tmp/fake-slurm:$PATH"
+    ///
+    ///   row submit --cluster slurm-cpu
+    ///
+    /// * Execute the first job and remove it from the queue:
+    ///
+    ///   sh /tmp/fake-slurm/jobs/1.sh && scancel 1
+    ///
+    Install(debug::InstallArguments),
 }
 
 #[derive(Subcommand, Debug)]
@@ -246,6 +690,24 @@ pub enum Commands {
     ///
     Scan(scan::Arguments),
 
+    /// Plan the jobs to submit.
+    ///
+    /// `row plan` selects the same jobs as `row submit` and writes their
+    /// actions, job names, directories, partitions, costs, and scripts to a
+    /// JSON file. Review the plan, then submit it with `row submit --plan`.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Plan the jobs for a specific action:
+    ///
+    ///   row plan --action=action -o plan.json
+    ///
+    /// * Plan the first eligible job:
+    ///
+    ///   row plan -n 1 -o plan.json
+    ///
+    Plan(plan::Arguments),
+
     /// Submit workflow actions to the scheduler.
     ///
     /// `row submit` submits jobs to the scheduler. First it determines the
@@ -253,6 +715,13 @@ pub enum Commands {
     /// forms groups and submits one job for each group. Pass `--dry-run` to see
     /// the script(s) that will be submitted.
     ///
+    /// Pass `--plan` to submit the jobs in a file written by `row plan`. `row
+    /// submit --plan` returns an error when the cluster differs, any script has
+    /// changed, or any directory is no longer eligible.
+    ///
+    /// `row submit` refuses to submit jobs to Slurm from inside a Slurm job.
+    /// Pass `--allow-nested` to submit anyway.
+    ///
     /// EXAMPLES
     ///
     /// * Print the job script(s) that will be submitted:
@@ -275,12 +744,238 @@ pub enum Commands {
     ///
     /// row submit --action='project*'
     ///
+    /// * Submit jobs for all actions with the tag `gpu`:
+    ///
+    /// row submit --tag=gpu
+    ///
     /// * Submit jobs on specific directories:
     ///
     /// row submit directory1 directory2
     ///
+    /// * Submit the jobs in a plan:
+    ///
+    /// row submit --plan plan.json
+    ///
     Submit(submit::Arguments),
 
+    /// Execute an action in the current process.
+    ///
+    /// `row exec` executes the given action on each selected directory, one
+    /// at a time (or up to `resources.concurrent_directories` at a time). It
+    /// executes the same script (with the same environment variables and
+    /// launchers) that a job would, but without the scheduler. Use `row exec`
+    /// to debug an action's command before submitting it.
+    ///
+    /// Immediately before executing a directory, `row exec` checks that the
+    /// directory is still eligible: it skips directories that have completed
+    /// the action and directories that wait on previous actions. Pass
+    /// `--force` to execute them anyway. `row exec` ignores the submitted
+    /// status of directories and does not add to the submitted cache.
+    ///
+    /// Jobs submitted with `row submit --aggregate` execute `row exec`.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Print the script that executes an action on a directory:
+    ///
+    ///   row exec --action=action --dry-run directory1
+    ///
+    /// * Execute an action on all eligible directories:
+    ///
+    ///   row exec --action=action
+    ///
+    /// * Execute an action on specific directories:
+    ///
+    ///   row exec --action=action directory1 directory2
+    ///
+    /// * Execute an action again on a completed directory:
+    ///
+    ///   row exec --action=action --force directory1
+    ///
+    Exec(exec::Arguments),
+
+    /// Remove an action's products so that it can be executed again.
+    ///
+    /// `row rerun` removes the product files of the given action in the
+    /// selected directories and removes those directories from the completed
+    /// cache. `row rerun` skips directories that are submitted. Pass
+    /// `--dry-run` to see the files that will be removed and `--submit` to
+    /// submit the selected directories immediately after.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Print the product files that will be removed:
+    ///
+    ///   row rerun --action=action --dry-run directory1
+    ///
+    /// * Rerun an action on specific directories:
+    ///
+    ///   row rerun --action=action directory1 directory2
+    ///
+    /// * Rerun an action on all directories and submit jobs:
+    ///
+    ///   row rerun --action=action --submit
+    ///
+    Rerun(rerun::Arguments),
+
+    /// Wait until actions reach a status.
+    ///
+    /// `row wait` checks the status of the selected actions and directories
+    /// periodically until every directory reaches the status given by
+    /// `--until` (or a later one). Each check synchronizes the project with the
+    /// workspace and queries the scheduler. The time between checks starts at
+    /// `--interval` seconds and doubles (up to 5 minutes) while the status is
+    /// unchanged.
+    ///
+    /// ERRORS
+    ///
+    /// `row wait` returns an error when the status is not reached before
+    /// `--timeout`.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Wait until all directories complete the action:
+    ///
+    ///   row wait --action=action
+    ///
+    /// * Wait up to 2 hours:
+    ///
+    ///   row wait --action=action --timeout 2h
+    ///
+    /// * Wait until no directories are eligible for the action:
+    ///
+    ///   row wait --action=action --until submitted
+    ///
+    Wait(wait::Arguments),
+
+    /// Check the workflow and cluster configuration.
+    ///
+    /// `row check` verifies that the launchers and partitions used by each
+    /// action are defined for the current cluster. Pass `--live` to also query
+    /// the scheduler (`sinfo` and `sacctmgr` on Slurm clusters) and verify that
+    /// the partitions in the cluster configuration and the accounts in each
+    /// action's submit options exist.
+    ///
+    /// ERRORS
+    ///
+    /// `row check` prints each problem found and returns an error when there
+    /// is at least one.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Check the configuration on the current cluster:
+    ///
+    ///   row check
+    ///
+    /// * Check partitions and accounts against the live cluster:
+    ///
+    ///   row check --live
+    ///
+    Check(check::Arguments),
+
+    /// Diagnose common problems with the project and environment.
+    ///
+    /// `row doctor` checks that the workflow and workspace are readable, that
+    /// the `.row` cache directory is writable, that the caches are consistent,
+    /// that row can identify the cluster and find the scheduler's executables,
+    /// and that there are no leftover lock files. It prints a suggestion for
+    /// each warning and problem.
+    ///
+    /// Pass `--repair` to first rebuild the cache from the workspace: remove
+    /// orphaned completed and submitted entries, read every directory value,
+    /// and recompute completion from the products on disk. `row doctor`
+    /// prints each change it makes.
+    ///
+    /// ERRORS
+    ///
+    /// `row doctor` returns an error when it finds at least one problem.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Diagnose the project on the current cluster:
+    ///
+    ///   row doctor
+    ///
+    /// * Rebuild the cache and report the changes:
+    ///
+    ///   row doctor --repair
+    ///
+    Doctor(doctor::Arguments),
+
+    /// Rename a directory in the workspace.
+    ///
+    /// `row mv` renames the directory on disk and updates the cached values,
+    /// completion status, submitted jobs, and aliases to the new name. The new
+    /// name must be a single path component that is not already in use.
+    ///
+    /// ERRORS
+    ///
+    /// `row mv` refuses to rename a directory that is submitted. Wait for the
+    /// job to finish or pass `--force`.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Rename a directory:
+    ///
+    ///   row mv directory1 directory2
+    ///
+    Mv(mv::Arguments),
+
+    /// Create workspace directories from a parameter sweep.
+    ///
+    /// `row sweep` reads SPEC (a TOML file, or JSON when the extension is
+    /// `.json`) and creates one directory with a value file for each value in
+    /// the sweep. The values are the cartesian product of the arrays in the
+    /// `product` table followed by each table in `values`. `name` sets the
+    /// directory names with `{/pointer}` placeholders. Without `name`, row
+    /// names each directory by a hash of its value.
+    ///
+    /// `row sweep` skips directories that already exist with the same value
+    /// and reports how many new directories it created. Pass `--dry-run` to
+    /// print the names of the directories that would be created.
+    ///
+    /// ERRORS
+    ///
+    /// `row sweep` returns an error when the workflow does not set
+    /// `workspace.value_file`, when two values have the same directory name,
+    /// or when a directory already exists with a different value.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Print the directories that would be created:
+    ///
+    ///   row sweep --dry-run sweep.toml
+    ///
+    /// * Create the directories:
+    ///
+    ///   row sweep sweep.toml
+    ///
+    Sweep(sweep::Arguments),
+
+    /// Remove directories from the workspace.
+    ///
+    /// `row rm` deletes the selected directories (and all their contents) from
+    /// disk and removes them from the cache. It also removes the aliases that
+    /// name the removed directories. Pass `--dry-run` to see the directories
+    /// that will be removed.
+    ///
+    /// ERRORS
+    ///
+    /// `row rm` refuses to remove directories that are submitted. Wait for the
+    /// jobs to finish or pass `--force`.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Print the directories that will be removed:
+    ///
+    ///   row rm --dry-run directory1 directory2
+    ///
+    /// * Remove directories listed on stdin without confirmation:
+    ///
+    ///   row show directories --action=action --completed --short | row rm --yes -
+    ///
+    Rm(rm::Arguments),
+
     /// Remove cache files.
     ///
     /// `row clean` safely removes cache files generated by row.
@@ -292,6 +987,80 @@ pub enum Commands {
     ///   row clean --completed
     ///
     Clean(clean::Arguments),
+
+    /// Package the project into a reproducibility bundle.
+    ///
+    /// `row export` writes a tar file that holds the workflow file (and the
+    /// generated workflow), the effective cluster and launcher configurations,
+    /// and the cached directory values, completions, and submitted jobs. Attach
+    /// the bundle to a paper or an issue to archive or debug a campaign.
+    ///
+    /// Pass `--anonymize` to replace each directory name with a salted hash
+    /// of the name, omit the directory values, and mask the submit options
+    /// named in `redact`.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Write the bundle:
+    ///
+    ///   row export campaign.tar
+    ///
+    /// * Write an anonymized bundle:
+    ///
+    ///   row export --anonymize campaign.tar
+    ///
+    Export(export::Arguments),
+
+    /// Restore or merge the state in a bundle written by `row export`.
+    ///
+    /// `row import` adds the completions, submitted jobs, and submitted groups
+    /// in BUNDLE to the cache. Use it to restore a campaign in a fresh checkout
+    /// or to merge the state of a campaign moved from another cluster. It
+    /// skips entries for actions and directories that are not in the project
+    /// and jobs submitted on other clusters.
+    ///
+    /// Pass `--conflict` to choose how to resolve entries that are already in
+    /// the cache: `keep` (the default), `replace`, or `error`.
+    ///
+    /// ERRORS
+    ///
+    /// `row import` returns an error when BUNDLE is anonymized, and when an
+    /// entry conflicts with the cache and `--conflict=error`.
+    ///
+    /// EXAMPLES
+    ///
+    /// * Restore the state in a bundle:
+    ///
+    ///   row import campaign.tar
+    ///
+    /// * Replace cached entries with the entries in the bundle:
+    ///
+    ///   row import --conflict replace campaign.tar
+    ///
+    Import(import::Arguments),
+
+    /// Manage short names for directories.
+    ///
+    /// Aliases are short names for directories stored in `.row/aliases.json`.
+    /// Commands that accept directories also accept aliases, and
+    /// `row show directories` displays the alias in place of the directory
+    /// name (except with `--short`).
+    #[command(subcommand)]
+    Alias(AliasCommands),
+
+    /// Manage user and project settings.
+    ///
+    /// Settings provide defaults for the `--cluster`, `--color`, and
+    /// `--io-threads` options. User settings are stored in
+    /// `$HOME/.config/row/config.toml` and project settings in
+    /// `.row/config.toml`. Project settings take precedence over user settings.
+    /// Command line options and environment variables take precedence over both.
+    #[command(subcommand)]
+    Config(ConfigCommands),
+
+    /// Tools to test row and your configuration.
+    #[command(subcommand)]
+    Debug(DebugCommands),
 }
 
 /// Parse directories passed in on the command line.
@@ -302,10 +1071,14 @@ pub enum Commands {
 /// - One "-" input reads directories from stdin.
 /// - Otherwise, pass through the given directories from the command line.
 ///
+/// Explicitly given directories (on the command line or stdin) that match an
+/// alias are replaced by the aliased directory.
+///
 /// `Err(row::Error)` when there is an error reading from stdin.
 ///
 pub fn parse_directories<F>(
     mut query_directories: Vec<PathBuf>,
+    aliases: &Aliases,
     get_all_directories: F,
 ) -> Result<Vec<PathBuf>, row::Error>
 where
@@ -319,8 +1092,83 @@ where
         }
     } else if query_directories.is_empty() {
         trace!("Checking all directories.");
-        query_directories = get_all_directories()?;
+        return get_all_directories();
     }
 
-    Ok(query_directories)
+    Ok(query_directories
+        .into_iter()
+        .map(|d| aliases.resolve(d))
+        .collect())
+}
+
+/// Ask the user to confirm a destructive operation.
+///
+/// Read the answer from the terminal (`/dev/tty`) so that a redirected stdin
+/// cannot answer the prompt. The default answer is no.
+///
+/// # Returns
+/// `Ok(true)` when the user answers yes.
+///
+/// # Errors
+/// `Err(row::Error)` when the session is not interactive.
+///
+pub fn confirm(
+    multi_progress: &MultiProgressContainer,
+    operation: &str,
+) -> Result<bool, row::Error> {
+    let not_interactive = || {
+        row::Error::ConfirmationRequired(operation.into(), "the session is not interactive".into())
+    };
+
+    if !io::stdout().is_terminal() {
+        return Err(not_interactive());
+    }
+    let mut terminal = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .map_err(|_| not_interactive())?;
+
+    let mut input = String::new();
+    multi_progress.suspend(|| -> Result<(), io::Error> {
+        write!(terminal, "Proceed? [y/N]: ")?;
+        terminal.flush()?;
+        BufReader::new(&terminal).read_line(&mut input)?;
+        Ok(())
+    })?;
+
+    let selection = input.trim().to_lowercase();
+    Ok(selection == "y" || selection == "yes")
+}
+
+/// Create the file given to `--output`.
+///
+/// # Errors
+/// `Err(row::Error)` when the file cannot be created.
+///
+pub fn create_output(path: &Path) -> Result<BufWriter<File>, row::Error> {
+    trace!("Writing output to '{}'.", path.display());
+    let file = File::create(path).map_err(|e| row::Error::FileWrite(path.into(), e))?;
+    Ok(BufWriter::new(file))
+}
+
+/// Serialize a configuration in the format selected by the `--output` file.
+///
+/// Configurations are written in TOML, or in JSON when the output file has
+/// the extension `.json`.
+///
+/// # Errors
+/// `Err(Box<dyn Error>)` when the output file is a CSV or TSV file or serialization fails.
+///
+pub fn to_config_string<T: Serialize>(
+    value: &T,
+    output: Option<&Path>,
+) -> Result<String, Box<dyn Error>> {
+    match Format::from_path(output) {
+        Format::Json => Ok(serde_json::to_string_pretty(value)? + "\n"),
+        Format::Text | Format::Toml => Ok(toml::to_string_pretty(value)?),
+        Format::Csv | Format::Tsv => Err(Box::new(row::Error::UnsupportedOutputFormat(
+            output.unwrap_or(Path::new("")).into(),
+        ))),
+    }
 }
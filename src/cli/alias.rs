@@ -0,0 +1,155 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use clap::Args;
+use console::Style;
+use log::{debug, info, warn};
+use std::collections::HashSet;
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::cli::GlobalOptions;
+use crate::ui::{Item, Row, Table};
+use row::alias::Aliases;
+use row::project::Project;
+use row::workflow::Workflow;
+use row::MultiProgressContainer;
+
+#[derive(Args, Debug)]
+pub struct AddArguments {
+    /// The short name.
+    alias: String,
+
+    /// The directory to name.
+    directory: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct RemoveArguments {
+    /// The alias to remove.
+    alias: String,
+}
+
+#[derive(Args, Debug)]
+pub struct ListArguments {
+    /// Hide the table header.
+    #[arg(long, display_order = 0)]
+    no_header: bool,
+}
+
+/// Add an alias.
+pub fn add(
+    options: &GlobalOptions,
+    args: AddArguments,
+    multi_progress: &mut MultiProgressContainer,
+) -> Result<(), Box<dyn Error>> {
+    debug!("Adding alias '{}'.", args.alias);
+
+    let mut project = Project::open(
+        options.io_threads,
+        &options.cluster,
+        options.workspace.as_deref(),
+        options.queue_timeout,
+        multi_progress,
+    )?;
+    let all_directories: HashSet<PathBuf> = HashSet::from_iter(project.state().list_directories());
+
+    if all_directories.contains(&PathBuf::from(&args.alias)) {
+        return Err(Box::new(row::Error::AliasIsDirectory(args.alias)));
+    }
+    if !all_directories.contains(&args.directory) {
+        return Err(Box::new(
+            project.state().values().directory_not_found(args.directory),
+        ));
+    }
+
+    let mut aliases = Aliases::open(project.workflow())?;
+    info!(
+        "Naming directory '{}' '{}'.",
+        args.directory.display(),
+        args.alias
+    );
+    if let Some(previous) = aliases.add(args.alias.clone(), args.directory) {
+        warn!(
+            "Alias '{}' no longer names directory '{}'.",
+            args.alias,
+            previous.display()
+        );
+    }
+    aliases.save(project.workflow())?;
+
+    project.close(multi_progress)?;
+
+    Ok(())
+}
+
+/// Remove an alias.
+pub fn remove(options: &GlobalOptions, args: &RemoveArguments) -> Result<(), Box<dyn Error>> {
+    debug!("Removing alias '{}'.", args.alias);
+
+    let workflow = Workflow::open_with_workspace(options.workspace.as_deref())?;
+    let mut aliases = Aliases::open(&workflow)?;
+    let directory = aliases
+        .remove(&args.alias)
+        .ok_or_else(|| row::Error::AliasNotFound(args.alias.clone()))?;
+    info!(
+        "Removed alias '{}' of directory '{}'.",
+        args.alias,
+        directory.display()
+    );
+    aliases.save(&workflow)?;
+
+    Ok(())
+}
+
+/// List the aliases.
+///
+/// Print the aliases and the directories they name. Warn about aliases of
+/// directories that are no longer in the workspace.
+///
+pub fn list<W: Write>(
+    options: &GlobalOptions,
+    args: &ListArguments,
+    multi_progress: &mut MultiProgressContainer,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    debug!("Listing aliases.");
+
+    let mut project = Project::open(
+        options.io_threads,
+        &options.cluster,
+        options.workspace.as_deref(),
+        options.queue_timeout,
+        multi_progress,
+    )?;
+    let all_directories: HashSet<PathBuf> = HashSet::from_iter(project.state().list_directories());
+
+    let mut table = Table::new().with_hide_header(args.no_header);
+    let underlined = Style::new().underlined();
+    table.header = vec![
+        Item::new("Alias".to_string(), underlined.clone()),
+        Item::new("Directory".to_string(), underlined),
+    ];
+
+    for (alias, directory) in project.aliases().iter() {
+        if !all_directories.contains(directory) {
+            warn!(
+                "Alias '{alias}' names '{}', which is not in the workspace.",
+                directory.display()
+            );
+        }
+
+        table.rows.push(Row::Items(vec![
+            Item::new(alias.clone(), Style::new().bold()),
+            Item::new(directory.display().to_string(), Style::new()),
+        ]));
+    }
+
+    table.write(output)?;
+    output.flush()?;
+
+    project.close(multi_progress)?;
+
+    Ok(())
+}
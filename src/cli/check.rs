@@ -0,0 +1,115 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use clap::Args;
+use log::{debug, info};
+use std::error::Error;
+use std::io::Write;
+
+use crate::cli::GlobalOptions;
+use row::cluster::{self, SchedulerType};
+use row::launcher;
+use row::scheduler::slurm;
+use row::workflow::Workflow;
+
+#[derive(Args, Debug)]
+pub struct Arguments {
+    /// Also query the scheduler to validate partitions and accounts.
+    #[arg(long, display_order = 0)]
+    live: bool,
+}
+
+/// Check the workflow and cluster configuration.
+///
+/// Print one line to `output` for each problem found.
+///
+pub fn check<W: Write>(
+    options: &GlobalOptions,
+    args: &Arguments,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    debug!("Checking the configuration.");
+
+    let workflow = Workflow::open()?;
+    let cluster = cluster::Configuration::open()?.identify(options.cluster.as_deref())?;
+    let launchers = launcher::Configuration::open()?.for_cluster(&cluster)?;
+
+    let mut problems = Vec::new();
+    let mut accounts = Vec::new();
+
+    for action in &workflow.action {
+        for launcher in action.launchers(&cluster.name) {
+            if !launchers.contains_key(launcher) {
+                problems.push(format!(
+                    "Launcher '{launcher}' used by action '{}' not found.",
+                    action.name()
+                ));
+            }
+        }
+
+        let submit_options = action.submit_options.get(&cluster.name);
+        if let Some(qos) = submit_options.and_then(|o| o.qos.as_ref()) {
+            if let Err(error) = cluster.check_qos(qos) {
+                problems.push(format!("Action '{}': {error}", action.name()));
+            }
+        }
+
+        let user_partition = submit_options.and_then(|o| o.partition.as_deref());
+        if let Err(error) = cluster.find_partition(user_partition, &action.resources, 1) {
+            problems.push(format!("Action '{}': {error}", action.name()));
+        }
+
+        if let Some(account) = submit_options.and_then(|o| o.account.as_ref()) {
+            for partition in cluster.selectable_partitions(
+                user_partition,
+                &action.resources,
+                action.group.maximum_size,
+            ) {
+                let suffix = partition.account_suffix.as_deref().unwrap_or_default();
+                let account = format!("{account}{suffix}");
+                if !accounts.contains(&(account.clone(), action.name())) {
+                    accounts.push((account, action.name()));
+                }
+            }
+        }
+    }
+
+    if args.live {
+        if cluster.scheduler == SchedulerType::Slurm {
+            let live_partitions = slurm::live_partitions()?;
+            for partition in &cluster.partition {
+                if !live_partitions.contains(&partition.name) {
+                    problems.push(format!(
+                        "Partition '{}' in cluster '{}' not found by sinfo.",
+                        partition.name, cluster.name
+                    ));
+                }
+            }
+
+            let live_accounts = slurm::live_accounts()?;
+            for (account, action_name) in &accounts {
+                if !live_accounts.contains(&account.to_lowercase()) {
+                    problems.push(format!(
+                        "Account '{account}' used by action '{action_name}' not found by sacctmgr."
+                    ));
+                }
+            }
+        } else {
+            info!(
+                "Cluster '{}' does not use a scheduler that can be checked live.",
+                cluster.name
+            );
+        }
+    }
+
+    for problem in &problems {
+        writeln!(output, "{problem}")?;
+    }
+
+    if problems.is_empty() {
+        info!("No problems found on cluster '{}'.", cluster.name);
+        Ok(())
+    } else {
+        Err(Box::new(row::Error::CheckFailed(problems.len())))
+    }
+}
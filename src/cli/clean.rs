@@ -10,8 +10,9 @@ use crate::cli::GlobalOptions;
 use row::project::Project;
 use row::MultiProgressContainer;
 use row::{
-    COMPLETED_CACHE_FILE_NAME, DATA_DIRECTORY_NAME, DIRECTORY_CACHE_FILE_NAME,
-    SUBMITTED_CACHE_FILE_NAME,
+    COMPLETED_CACHE_FILE_NAME, COMPLETION_HASHES_FILE_NAME, DIRECTORY_CACHE_FILE_NAME,
+    JOBS_CACHE_FILE_NAME, LEGACY_COMPLETED_CACHE_FILE_NAME, SUBMITTED_CACHE_FILE_NAME,
+    SUBMITTED_GROUPS_CACHE_FILE_NAME,
 };
 
 #[derive(Args, Debug)]
@@ -39,6 +40,10 @@ pub struct Selection {
     /// Remove the completed cache.
     #[arg(long, display_order = 0)]
     completed: bool,
+
+    /// Remove the job history.
+    #[arg(long, display_order = 0)]
+    jobs: bool,
 }
 
 /// Remove row cache files.
@@ -48,7 +53,13 @@ pub fn clean(
     multi_progress: &mut MultiProgressContainer,
 ) -> Result<(), Box<dyn Error>> {
     debug!("Cleaning cache files.");
-    let mut project = Project::open(options.io_threads, &options.cluster, multi_progress)?;
+    let mut project = Project::open(
+        options.io_threads,
+        &options.cluster,
+        options.workspace.as_deref(),
+        options.queue_timeout,
+        multi_progress,
+    )?;
 
     // Delete all existing completion staging files.
     project.close(multi_progress)?;
@@ -57,6 +68,7 @@ pub fn clean(
         directory: true,
         submitted: true,
         completed: true,
+        jobs: true,
     });
 
     let num_submitted = project.state().num_submitted();
@@ -78,20 +90,38 @@ pub fn clean(
         }
     }
 
-    let data_directory = project.workflow().root.join(DATA_DIRECTORY_NAME);
+    let data_directory = project.workflow().data_directory();
 
     if selection.submitted {
-        let path = data_directory.join(SUBMITTED_CACHE_FILE_NAME);
-        info!("Removing '{}'.", path.display());
-        if let Err(error) = fs::remove_file(&path) {
-            match error.kind() {
-                io::ErrorKind::NotFound => (),
-                _ => return Err(Box::new(row::Error::FileRemove(path.clone(), error))),
+        for file_name in [SUBMITTED_CACHE_FILE_NAME, SUBMITTED_GROUPS_CACHE_FILE_NAME] {
+            let path = data_directory.join(file_name);
+            info!("Removing '{}'.", path.display());
+            if let Err(error) = fs::remove_file(&path) {
+                match error.kind() {
+                    io::ErrorKind::NotFound => (),
+                    _ => return Err(Box::new(row::Error::FileRemove(path.clone(), error))),
+                }
             }
         }
     }
     if selection.completed {
-        let path = data_directory.join(COMPLETED_CACHE_FILE_NAME);
+        for file_name in [
+            COMPLETED_CACHE_FILE_NAME,
+            LEGACY_COMPLETED_CACHE_FILE_NAME,
+            COMPLETION_HASHES_FILE_NAME,
+        ] {
+            let path = data_directory.join(file_name);
+            info!("Removing '{}'.", path.display());
+            if let Err(error) = fs::remove_file(&path) {
+                match error.kind() {
+                    io::ErrorKind::NotFound => (),
+                    _ => return Err(Box::new(row::Error::FileRemove(path.clone(), error))),
+                }
+            }
+        }
+    }
+    if selection.jobs {
+        let path = data_directory.join(JOBS_CACHE_FILE_NAME);
         info!("Removing '{}'.", path.display());
         if let Err(error) = fs::remove_file(&path) {
             match error.kind() {
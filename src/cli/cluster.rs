@@ -2,14 +2,19 @@
 // Part of row, released under the BSD 3-Clause License.
 
 use clap::Args;
-use log::{debug, info};
+use console::Style;
+use log::{debug, info, warn};
 use std::error::Error;
 use std::io::Write;
+use std::path::PathBuf;
 
-use crate::cli::GlobalOptions;
-use row::cluster;
+use crate::cli::{self, GlobalOptions};
+use crate::ui::{Format, Item, Row, Table};
+use row::cluster::{self, Cluster, Partition};
+use row::workflow::{Action, Workflow};
 
 #[derive(Args, Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Arguments {
     /// Show all clusters.
     #[arg(long, display_order = 0)]
@@ -18,11 +23,30 @@ pub struct Arguments {
     /// Show only the cluster name(s).
     #[arg(long, display_order = 0)]
     short: bool,
+
+    /// Show a table of the cluster's partitions and the actions that select each one.
+    #[arg(long, display_order = 0, group = "tables", conflicts_with_all = ["all", "short"])]
+    partitions: bool,
+
+    /// Explain why each cluster was or was not identified.
+    #[arg(long, display_order = 0, group = "tables", conflicts_with_all = ["all", "short"])]
+    why: bool,
+
+    /// Hide the table header (with --partitions or --why).
+    #[arg(long, display_order = 0, requires = "tables")]
+    no_header: bool,
+
+    /// Write to a file instead of stdout. The extension selects the format: '.json' or TOML.
+    #[arg(long, value_name = "file", display_order = 0)]
+    pub output: Option<PathBuf>,
+
+    #[command(flatten)]
+    table: cli::TableArguments,
 }
 
 /// Show the cluster.
 ///
-/// Print the cluster to stdout in toml format.
+/// Print the cluster in toml (or json) format or a table of its partitions.
 ///
 pub fn cluster<W: Write>(
     options: &GlobalOptions,
@@ -33,25 +57,262 @@ pub fn cluster<W: Write>(
 
     let clusters = cluster::Configuration::open()?;
 
-    if args.all {
+    if args.why {
+        write_identification(&clusters, options, args, output)?;
+        // Report the error when no cluster matches.
+        clusters.identify(options.cluster.as_deref())?;
+    } else if args.all {
         if args.short {
             for cluster in clusters.cluster {
                 writeln!(output, "{}", cluster.name)?;
             }
         } else {
             info!("All cluster configurations:");
-            write!(output, "{}", &toml::to_string_pretty(&clusters)?)?;
+            write!(
+                output,
+                "{}",
+                &cli::to_config_string(&clusters, args.output.as_deref())?
+            )?;
         }
     } else {
         let cluster = clusters.identify(options.cluster.as_deref())?;
         info!("Cluster configurations for '{}':", cluster.name);
 
-        if args.short {
+        if args.partitions {
+            write_partitions(&cluster, args, output)?;
+        } else if args.short {
             writeln!(output, "{}", cluster.name)?;
         } else {
-            write!(output, "{}", &toml::to_string_pretty(&cluster)?)?;
+            write!(
+                output,
+                "{}",
+                &cli::to_config_string(&cluster, args.output.as_deref())?
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Describe the limits on the number of CPUs or GPUs in a job.
+fn describe_limits(
+    minimum: Option<usize>,
+    maximum: Option<usize>,
+    multiple_of: Option<usize>,
+) -> String {
+    if maximum == Some(0) {
+        return "0".into();
+    }
+
+    let mut limits = Vec::new();
+    if let Some(minimum) = minimum {
+        limits.push(format!("at least {minimum}"));
+    }
+    if let Some(maximum) = maximum {
+        limits.push(format!("at most {maximum}"));
+    }
+    if let Some(multiple_of) = multiple_of {
+        limits.push(format!("multiple of {multiple_of}"));
+    }
+
+    if limits.is_empty() {
+        "any".into()
+    } else {
+        limits.join(", ")
+    }
+}
+
+/// Join the present elements, or "-" when there are none.
+fn join_or_dash(elements: &[Option<String>]) -> String {
+    let present: Vec<&str> = elements.iter().flatten().map(String::as_str).collect();
+    if present.is_empty() {
+        "-".into()
+    } else {
+        present.join(", ")
+    }
+}
+
+/// Find the partitions that the action selects.
+fn select_partitions<'a>(
+    cluster: &'a Cluster,
+    action: &Action,
+) -> Result<Vec<&'a Partition>, row::Error> {
+    let user_partition = action
+        .submit_options
+        .get(&cluster.name)
+        .and_then(|o| o.partition.as_deref());
+    let n_directories = action.group.maximum_size.unwrap_or(1);
+
+    if action.resources.component.is_empty() {
+        Ok(vec![cluster.find_partition(
+            user_partition,
+            &action.resources,
+            n_directories,
+        )?])
+    } else {
+        action
+            .resources
+            .component
+            .iter()
+            .map(|c| cluster.find_partition(user_partition, c, n_directories))
+            .collect()
+    }
+}
+
+/// Write a table of the cluster's partitions.
+///
+/// Include the actions in the current workflow (if any) that select each
+/// partition.
+///
+#[allow(clippy::too_many_lines)]
+fn write_partitions<W: Write>(
+    cluster: &Cluster,
+    args: &Arguments,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    let workflow = match Workflow::open() {
+        Ok(workflow) => Some(workflow),
+        Err(row::Error::WorkflowNotFound) => None,
+        Err(error) => return Err(Box::new(error)),
+    };
+
+    let mut actions: Vec<Vec<&str>> = vec![Vec::new(); cluster.partition.len()];
+    if let Some(workflow) = &workflow {
+        for action in &workflow.action {
+            match select_partitions(cluster, action) {
+                Ok(partitions) => {
+                    for partition in partitions {
+                        let index = cluster
+                            .partition
+                            .iter()
+                            .position(|p| std::ptr::eq(p, partition))
+                            .expect("partition is in the cluster");
+                        if !actions[index].contains(&action.name()) {
+                            actions[index].push(action.name());
+                        }
+                    }
+                }
+                Err(error) => warn!("Action '{}': {error}", action.name()),
+            }
         }
     }
 
+    let mut table = args.table.apply(
+        Table::new()
+            .with_hide_header(args.no_header)
+            .with_format(Format::from_path(args.output.as_deref())),
+        args.output.as_deref(),
+    );
+    let underlined = Style::new().underlined();
+    table.header = vec![
+        Item::new("Partition".to_string(), underlined.clone()),
+        Item::new("CPUs".to_string(), underlined.clone()),
+        Item::new("GPUs".to_string(), underlined.clone()),
+        Item::new("Memory".to_string(), underlined.clone()),
+        Item::new("Per node".to_string(), underlined.clone()),
+        Item::new("Auto-select".to_string(), underlined.clone()),
+        Item::new("Actions".to_string(), underlined),
+    ];
+
+    for (partition, actions) in cluster.partition.iter().zip(actions) {
+        table.rows.push(Row::Items(vec![
+            Item::new(partition.name.clone(), Style::new().bold()),
+            Item::new(
+                describe_limits(
+                    None,
+                    partition.maximum_cpus_per_job,
+                    partition.require_cpus_multiple_of,
+                ),
+                Style::new(),
+            ),
+            Item::new(
+                describe_limits(
+                    partition.minimum_gpus_per_job,
+                    partition.maximum_gpus_per_job,
+                    partition.require_gpus_multiple_of,
+                ),
+                Style::new(),
+            ),
+            Item::new(
+                join_or_dash(&[
+                    partition
+                        .memory_per_cpu
+                        .as_ref()
+                        .map(|m| format!("{m}/CPU")),
+                    partition
+                        .memory_per_gpu
+                        .as_ref()
+                        .map(|m| format!("{m}/GPU")),
+                ]),
+                Style::new(),
+            ),
+            Item::new(
+                join_or_dash(&[
+                    partition.cpus_per_node.map(|n| format!("{n} CPUs")),
+                    partition.gpus_per_node.map(|n| format!("{n} GPUs")),
+                ]),
+                Style::new(),
+            ),
+            Item::new(
+                if partition.prevent_auto_select {
+                    "no".into()
+                } else {
+                    "yes".into()
+                },
+                Style::new(),
+            ),
+            Item::new(
+                if actions.is_empty() {
+                    "-".into()
+                } else {
+                    actions.join(", ")
+                },
+                Style::new().italic(),
+            ),
+        ]));
+    }
+
+    table.write(output)?;
+    output.flush()?;
+
+    Ok(())
+}
+
+/// Write a table that explains the cluster identification.
+fn write_identification<W: Write>(
+    clusters: &cluster::Configuration,
+    options: &GlobalOptions,
+    args: &Arguments,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    let mut table = args.table.apply(
+        Table::new()
+            .with_hide_header(args.no_header)
+            .with_format(Format::from_path(args.output.as_deref())),
+        args.output.as_deref(),
+    );
+    let underlined = Style::new().underlined();
+    table.header = vec![
+        Item::new("Cluster".to_string(), underlined.clone()),
+        Item::new("Identified".to_string(), underlined.clone()),
+        Item::new("Reason".to_string(), underlined),
+    ];
+
+    for identification in clusters.explain_identification(options.cluster.as_deref()) {
+        let (identified, style) = if identification.selected {
+            ("yes", Style::new().green().bold())
+        } else {
+            ("no", Style::new())
+        };
+        table.rows.push(Row::Items(vec![
+            Item::new(identification.cluster, Style::new().bold()),
+            Item::new(identified.to_string(), style),
+            Item::new(identification.reason, Style::new().italic()),
+        ]));
+    }
+
+    table.write(output)?;
+    output.flush()?;
+
     Ok(())
 }
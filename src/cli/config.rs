@@ -0,0 +1,148 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use clap::Args;
+use console::Style;
+use log::{debug, info};
+use std::error::Error;
+use std::io::Write;
+
+use crate::ui::{Item, Row, Table};
+use row::config::{Config, Scope, KEYS};
+
+#[derive(Args, Debug)]
+pub struct GetArguments {
+    /// The setting.
+    key: String,
+
+    /// Read only the user settings.
+    #[arg(long, display_order = 0, conflicts_with = "local")]
+    user: bool,
+
+    /// Read only the project settings.
+    #[arg(long, display_order = 0)]
+    local: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SetArguments {
+    /// The setting.
+    key: String,
+
+    /// The new value.
+    #[arg(required_unless_present = "unset")]
+    value: Option<String>,
+
+    /// Remove the setting instead.
+    #[arg(long, display_order = 0, conflicts_with = "value")]
+    unset: bool,
+
+    /// Write the project settings (instead of the user settings).
+    #[arg(long, display_order = 0)]
+    local: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ListArguments {
+    /// List only the user settings.
+    #[arg(long, display_order = 0, conflicts_with = "local")]
+    user: bool,
+
+    /// List only the project settings.
+    #[arg(long, display_order = 0)]
+    local: bool,
+
+    /// Hide the table header.
+    #[arg(long, display_order = 0)]
+    no_header: bool,
+}
+
+/// Choose the scope selected by `--user` or `--local`.
+fn scope(user: bool, local: bool) -> Option<Scope> {
+    match (user, local) {
+        (true, _) => Some(Scope::User),
+        (_, true) => Some(Scope::Project),
+        _ => None,
+    }
+}
+
+/// Print the value of a setting.
+///
+/// Print nothing when the setting is not set.
+///
+pub fn get<W: Write>(args: &GetArguments, output: &mut W) -> Result<(), Box<dyn Error>> {
+    debug!("Getting setting '{}'.", args.key);
+
+    let config = Config::open()?;
+    let value = match scope(args.user, args.local) {
+        Some(scope) => config.scope(scope).get(&args.key)?,
+        None => config.get(&args.key)?.map(|(value, _)| value),
+    };
+
+    if let Some(value) = value {
+        writeln!(output, "{value}")?;
+        output.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Set or unset a setting.
+pub fn set(args: &SetArguments) -> Result<(), Box<dyn Error>> {
+    let scope = if args.local {
+        Scope::Project
+    } else {
+        Scope::User
+    };
+
+    let mut config = Config::open()?;
+    let value = if args.unset {
+        None
+    } else {
+        args.value.as_deref()
+    };
+    config.set(scope, &args.key, value)?;
+
+    if let Some(value) = value {
+        info!("Set {scope} setting '{}' to '{value}'.", args.key);
+    } else {
+        info!("Unset {scope} setting '{}'.", args.key);
+    }
+
+    Ok(())
+}
+
+/// List the settings.
+pub fn list<W: Write>(args: &ListArguments, output: &mut W) -> Result<(), Box<dyn Error>> {
+    debug!("Listing settings.");
+
+    let config = Config::open()?;
+
+    let mut table = Table::new().with_hide_header(args.no_header);
+    let underlined = Style::new().underlined();
+    table.header = vec![
+        Item::new("Setting".to_string(), underlined.clone()),
+        Item::new("Value".to_string(), underlined.clone()),
+        Item::new("Scope".to_string(), underlined),
+    ];
+
+    for key in KEYS {
+        let entry = match scope(args.user, args.local) {
+            Some(scope) => config.scope(scope).get(key)?.map(|value| (value, scope)),
+            None => config.get(key)?,
+        };
+
+        if let Some((value, scope)) = entry {
+            table.rows.push(Row::Items(vec![
+                Item::new(key.to_string(), Style::new().bold()),
+                Item::new(value, Style::new()),
+                Item::new(scope.to_string(), Style::new().italic()),
+            ]));
+        }
+    }
+
+    table.write(output)?;
+    output.flush()?;
+
+    Ok(())
+}
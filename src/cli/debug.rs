@@ -0,0 +1,162 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use clap::Args;
+use log::{debug, info, trace};
+use path_absolutize::Absolutize;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use row::Error as RowError;
+
+/// Name of the file that lists the queued job IDs.
+const QUEUE_FILE_NAME: &str = "queue";
+
+/// Name of the directory that holds the submitted job scripts.
+const JOBS_DIRECTORY_NAME: &str = "jobs";
+
+/// Fake `sbatch`: store the script and queue a new job ID.
+const SBATCH: &str = r#"#!/bin/sh
+# Fake sbatch installed by `row debug fake-scheduler install`.
+state='{state}'
+
+if [ -n "$ROW_FAKE_SBATCH_ERROR" ]; then
+    echo "sbatch: error: $ROW_FAKE_SBATCH_ERROR" >&2
+    exit 1
+fi
+
+parsable=false
+script=
+for arg in "$@"; do
+    case "$arg" in
+        --parsable) parsable=true ;;
+        -*) ;;
+        *) script="$arg" ;;
+    esac
+done
+
+mkdir -p "$state/jobs" || exit 1
+id=$(( $(ls "$state/jobs" | wc -l) + 1 ))
+if [ -n "$script" ]; then
+    cp "$script" "$state/jobs/$id.sh" || exit 1
+else
+    cat > "$state/jobs/$id.sh" || exit 1
+fi
+echo "$id" >> "$state/queue"
+
+if $parsable; then
+    echo "$id"
+else
+    echo "Submitted batch job $id"
+fi
+"#;
+
+/// Fake `squeue`: print the queued job IDs (optionally only those in `--jobs`).
+const SQUEUE: &str = r#"#!/bin/sh
+# Fake squeue installed by `row debug fake-scheduler install`.
+state='{state}'
+
+if [ -n "$ROW_FAKE_SQUEUE_ERROR" ]; then
+    echo "squeue: error: $ROW_FAKE_SQUEUE_ERROR" >&2
+    exit 1
+fi
+
+jobs=
+while [ $# -gt 0 ]; do
+    case "$1" in
+        --jobs|-j) shift; jobs="$1" ;;
+        --jobs=*) jobs="${1#--jobs=}" ;;
+    esac
+    shift
+done
+
+[ -f "$state/queue" ] || exit 0
+if [ -z "$jobs" ]; then
+    cat "$state/queue"
+    exit 0
+fi
+
+for id in $(echo "$jobs" | tr ',' ' '); do
+    if grep -qx "$id" "$state/queue"; then
+        echo "$id"
+    fi
+done
+exit 0
+"#;
+
+/// Fake `scancel`: remove job IDs from the queue.
+const SCANCEL: &str = r#"#!/bin/sh
+# Fake scancel installed by `row debug fake-scheduler install`.
+state='{state}'
+
+[ -f "$state/queue" ] || exit 0
+for id in "$@"; do
+    case "$id" in
+        -*) continue ;;
+    esac
+    grep -vx "$id" "$state/queue" > "$state/queue.tmp"
+    mv "$state/queue.tmp" "$state/queue" || exit 1
+done
+exit 0
+"#;
+
+#[derive(Args, Debug)]
+pub struct InstallArguments {
+    /// Directory to write the scripts to (created if needed).
+    #[arg(display_order = 0)]
+    directory: PathBuf,
+}
+
+/// Write an executable script, replacing `{state}` with the state directory.
+fn write_script(directory: &Path, name: &str, template: &str) -> Result<(), RowError> {
+    let path = directory.join(name);
+    let contents = template.replace("{state}", &directory.display().to_string());
+    trace!("Writing '{}'.", path.display());
+
+    fs::write(&path, contents).map_err(|e| RowError::FileWrite(path.clone(), e))?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+        .map_err(|e| RowError::FileWrite(path.clone(), e))
+}
+
+/// Install fake Slurm commands.
+///
+/// Write `sbatch`, `squeue`, and `scancel` scripts that share the queue in
+/// `<directory>/queue`. Keep the existing queue when reinstalling.
+///
+pub fn install<W: Write>(args: &InstallArguments, output: &mut W) -> Result<(), Box<dyn Error>> {
+    let directory = args.directory.absolutize()?.to_path_buf();
+    debug!(
+        "Installing the fake scheduler in '{}'.",
+        directory.display()
+    );
+
+    // The scripts quote the directory with single quotes.
+    if directory.to_str().filter(|s| !s.contains('\'')).is_none() {
+        return Err(Box::new(RowError::InvalidDirectoryName(directory)));
+    }
+
+    let jobs = directory.join(JOBS_DIRECTORY_NAME);
+    fs::create_dir_all(&jobs).map_err(|e| RowError::DirectoryCreate(jobs.clone(), e))?;
+
+    let queue = directory.join(QUEUE_FILE_NAME);
+    if !queue.exists() {
+        fs::write(&queue, "").map_err(|e| RowError::FileWrite(queue.clone(), e))?;
+    }
+
+    write_script(&directory, "sbatch", SBATCH)?;
+    write_script(&directory, "squeue", SQUEUE)?;
+    write_script(&directory, "scancel", SCANCEL)?;
+
+    info!("Installed sbatch, squeue, and scancel.");
+    writeln!(
+        output,
+        "Add the fake scheduler to your PATH with:\nexport PATH=\"{}:$PATH\"",
+        directory.display()
+    )?;
+    output.flush()?;
+
+    Ok(())
+}
@@ -3,16 +3,19 @@
 
 use clap::Args;
 use console::Style;
-use log::{debug, warn};
+use log::{debug, trace, warn, LevelFilter};
+use serde_json::Value;
 use std::collections::HashSet;
 use std::error::Error;
-use std::io::Write;
-use std::path::PathBuf;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
 use crate::cli::{self, GlobalOptions};
-use crate::ui::{Alignment, Item, Row, Table};
+use crate::ui::{Alignment, Format, Item, Row, Table};
+use row::format::UtcTimestamp;
 use row::project::Project;
-use row::MultiProgressContainer;
+use row::{MultiProgressContainer, ValueColumn};
 
 #[derive(Args, Debug)]
 #[allow(clippy::struct_excessive_bools)]
@@ -33,9 +36,12 @@ pub struct Arguments {
     no_separate_groups: bool,
 
     /// Show an element of each directory's value (repeat to show multiple elements).
+    ///
+    /// Set a column name with 'NAME=/pointer'. Compute a column with arithmetic
+    /// on JSON pointers and numbers: '/a * 2 + /b'.
     #[arg(
         long,
-        value_name = "JSON POINTER",
+        value_name = "[NAME=]JSON POINTER",
         display_order = 0,
         requires = "action"
     )]
@@ -61,9 +67,154 @@ pub struct Arguments {
     #[arg(long, display_order = 0, requires = "action")]
     waiting: bool,
 
+    /// Show only directories that have not completed the action (submitted, eligible, and waiting).
+    #[arg(
+        long,
+        display_order = 0,
+        requires = "action",
+        conflicts_with = "completed"
+    )]
+    only_incomplete: bool,
+
     /// Show only directory names.
     #[arg(long, default_value_t = false, display_order = 0, requires = "action")]
     short: bool,
+
+    /// Show the submission group, group size, and cost of each eligible directory.
+    #[arg(
+        long,
+        display_order = 0,
+        requires = "action",
+        conflicts_with_all = ["completed", "submitted", "waiting", "only_incomplete", "short"]
+    )]
+    group_preview: bool,
+
+    /// Write to a file instead of stdout. The extension selects the format: '.csv', '.tsv', '.json', or text.
+    #[arg(long, value_name = "file", display_order = 0)]
+    pub output: Option<PathBuf>,
+
+    #[command(flatten)]
+    table: cli::TableArguments,
+
+    /// Do not query the scheduler for the status of submitted jobs.
+    #[arg(long, display_order = 0, requires = "action")]
+    offline: bool,
+
+    #[command(flatten)]
+    sets: SetArguments,
+}
+
+/// Set operations that combine the selected directories with lists in files.
+#[derive(Args, Debug)]
+pub struct SetArguments {
+    /// Add the directories listed in a file ('-' reads stdin). Repeat to add several files.
+    #[arg(long, value_name = "file", display_order = 0)]
+    union: Vec<PathBuf>,
+
+    /// Keep only the directories that are also listed in a file ('-' reads stdin). Repeat to intersect several files.
+    #[arg(long, value_name = "file", display_order = 0)]
+    intersect: Vec<PathBuf>,
+
+    /// Remove the directories listed in a file ('-' reads stdin). Repeat to remove several files.
+    #[arg(long, value_name = "file", display_order = 0)]
+    difference: Vec<PathBuf>,
+}
+
+/// Read a list of directories, one per line, from a file ('-' for stdin).
+///
+/// Skip empty lines. Read stdin at most once and remember the result in `stdin`.
+///
+fn read_directory_list(
+    path: &Path,
+    stdin: &mut Option<Vec<PathBuf>>,
+) -> Result<Vec<PathBuf>, row::Error> {
+    if path == Path::new("-") {
+        if let Some(directories) = stdin {
+            return Ok(directories.clone());
+        }
+
+        trace!("Reading directories from stdin.");
+        let mut directories = Vec::new();
+        for line in io::stdin().lines() {
+            let line = line?;
+            if !line.is_empty() {
+                directories.push(PathBuf::from(line));
+            }
+        }
+        *stdin = Some(directories.clone());
+        return Ok(directories);
+    }
+
+    trace!("Reading directories from '{}'.", path.display());
+    let contents = fs::read_to_string(path).map_err(|e| row::Error::FileRead(path.into(), e))?;
+    Ok(contents
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Select the directories to show.
+///
+/// Start with the directories given on the command line (or all directories
+/// when there are neither directories nor `--union` files), add the directories
+/// in each `--union` file, keep only the directories in every `--intersect`
+/// file, and remove the directories in any `--difference` file.
+///
+/// # Errors
+/// `Err(row::Error)` when a file or stdin cannot be read.
+///
+fn select_directories(
+    directories: Vec<PathBuf>,
+    sets: &SetArguments,
+    project: &Project,
+) -> Result<Vec<PathBuf>, row::Error> {
+    let aliases = project.aliases();
+    let mut stdin = None;
+    let read = |path: &Path, stdin: &mut Option<Vec<PathBuf>>| {
+        read_directory_list(path, stdin).map(|list| {
+            list.into_iter()
+                .map(|d| aliases.resolve(d))
+                .collect::<Vec<_>>()
+        })
+    };
+
+    // The directories on the command line and the set files may share stdin.
+    let stdin_shared = directories.len() == 1
+        && directories[0] == Path::new("-")
+        && [&sets.union, &sets.intersect, &sets.difference]
+            .iter()
+            .any(|paths| paths.iter().any(|p| p == Path::new("-")));
+    let mut selected = if stdin_shared {
+        read(Path::new("-"), &mut stdin)?
+    } else if directories.is_empty() && !sets.union.is_empty() {
+        // The `--union` files are the selection when there are no directories
+        // on the command line. Starting from all directories would ignore them.
+        Vec::new()
+    } else {
+        cli::parse_directories(directories, aliases, || {
+            Ok(project.state().list_directories())
+        })?
+    };
+
+    let mut present: HashSet<PathBuf> = selected.iter().cloned().collect();
+    for path in &sets.union {
+        for directory in read(path, &mut stdin)? {
+            if present.insert(directory.clone()) {
+                selected.push(directory);
+            }
+        }
+    }
+    for path in &sets.intersect {
+        let list: HashSet<PathBuf> = read(path, &mut stdin)?.into_iter().collect();
+        selected.retain(|d| list.contains(d));
+    }
+    for path in &sets.difference {
+        let list: HashSet<PathBuf> = read(path, &mut stdin)?.into_iter().collect();
+        selected.retain(|d| !list.contains(d));
+    }
+
+    Ok(selected)
 }
 
 /// Show directories that match an action.
@@ -96,6 +247,15 @@ pub fn print_matching<W: Write>(
     let mut show_submitted = args.submitted;
     let mut show_eligible = args.eligible;
     let mut show_waiting = args.waiting;
+    if args.only_incomplete {
+        show_submitted = true;
+        show_eligible = true;
+        show_waiting = true;
+    }
+    if args.group_preview {
+        // Groups of eligible directories are submitted together.
+        show_eligible = true;
+    }
     if !show_completed && !show_submitted && !show_eligible && !show_waiting {
         show_completed = true;
         show_submitted = true;
@@ -103,30 +263,91 @@ pub fn print_matching<W: Write>(
         show_waiting = true;
     }
 
-    let mut project = Project::open(options.io_threads, &options.cluster, multi_progress)?;
-
-    let query_directories =
-        cli::parse_directories(args.directories, || Ok(project.state().list_directories()))?;
+    // Show where and when directories completed in verbose mode.
+    let show_completion = show_completed && log::max_level() >= LevelFilter::Info;
+
+    let mut project = if args.offline {
+        Project::open_offline(
+            options.io_threads,
+            &options.cluster,
+            options.workspace.as_deref(),
+            multi_progress,
+        )?
+    } else {
+        Project::open(
+            options.io_threads,
+            &options.cluster,
+            options.workspace.as_deref(),
+            options.queue_timeout,
+            multi_progress,
+        )?
+    };
+    let submitted_status = if project.is_offline() {
+        "unknown"
+    } else {
+        "submitted"
+    };
+
+    let query_directories = select_directories(args.directories, &args.sets, &project)?;
 
     project
         .workflow()
         .action_by_name(action_name)
-        .ok_or_else(|| row::Error::ActionNotFound(action_name.to_string()))?;
-
-    let mut table = Table::new().with_hide_header(if args.short { true } else { args.no_header });
+        .ok_or_else(|| project.workflow().action_not_found(action_name))?;
+
+    let mut table = Table::new()
+        .with_hide_header(if args.short { true } else { args.no_header })
+        .with_format(Format::from_path(args.output.as_deref()));
+    // Show complete directory names with --short.
+    if !args.short {
+        table = args.table.apply(table, args.output.as_deref());
+    }
     table.header = vec![
         Item::new("Directory".to_string(), Style::new().underlined()),
         Item::new("Status".to_string(), Style::new().underlined()),
     ];
+    if args.group_preview {
+        table
+            .header
+            .push(Item::new("Group".to_string(), Style::new().underlined()));
+        table
+            .header
+            .push(Item::new("Size".to_string(), Style::new().underlined()));
+        table
+            .header
+            .push(Item::new("Cost".to_string(), Style::new().underlined()));
+    }
     if show_submitted || show_completed {
         table
             .header
             .push(Item::new("Job ID".to_string(), Style::new().underlined()));
     }
-    for pointer in &args.value {
+    if show_completion {
+        table.header.push(Item::new(
+            "Completed on".to_string(),
+            Style::new().underlined(),
+        ));
+        table.header.push(Item::new(
+            "Completed at".to_string(),
+            Style::new().underlined(),
+        ));
+    }
+    let columns = args
+        .value
+        .iter()
+        .map(|v| v.parse::<ValueColumn>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let pointers: Vec<String> = columns.iter().flat_map(ValueColumn::pointers).collect();
+    for pointer in &pointers {
+        if !pointer.is_empty() && !pointer.starts_with('/') {
+            warn!("The JSON pointer '{pointer}' does not appear valid. Did you mean '/{pointer}'?");
+        }
+    }
+
+    for column in &columns {
         table
             .header
-            .push(Item::new(pointer.clone(), Style::new().underlined()));
+            .push(Item::new(column.name.clone(), Style::new().underlined()));
     }
 
     for action in &project.workflow().action {
@@ -159,6 +380,17 @@ pub fn print_matching<W: Write>(
 
         let groups = project.separate_into_groups(action, selected_directories)?;
 
+        // The cache may hold only some elements of each value.
+        let uncached_values = project.read_uncached_values(
+            &pointers,
+            groups.concat(),
+            project.io_threads(),
+            multi_progress,
+        )?;
+        let values = uncached_values
+            .as_ref()
+            .unwrap_or_else(|| project.state().values());
+
         for (group_idx, group) in groups.iter().enumerate() {
             if let Some(n) = args.n_groups {
                 if group_idx >= n {
@@ -166,12 +398,12 @@ pub fn print_matching<W: Write>(
                 }
             }
 
-            for directory in group {
+            for (directory_idx, directory) in group.iter().enumerate() {
                 // Format the directory status.
                 let status = if completed.contains(directory) {
                     Item::new("completed".to_string(), Style::new().green().italic())
                 } else if submitted.contains(directory) {
-                    Item::new("submitted".to_string(), Style::new().yellow().italic())
+                    Item::new(submitted_status.to_string(), Style::new().yellow().italic())
                 } else if eligible.contains(directory) {
                     Item::new("eligible".to_string(), Style::new().blue().italic())
                 } else if waiting.contains(directory) {
@@ -182,21 +414,48 @@ pub fn print_matching<W: Write>(
 
                 let mut row = Vec::new();
 
-                // The directory name
-                row.push(Item::new(
-                    directory.display().to_string(),
-                    Style::new().bold(),
-                ));
-
                 // Only show directory names when user requests short output.
                 if args.short {
+                    row.push(Item::new(
+                        directory.display().to_string(),
+                        Style::new().bold(),
+                    ));
                     table.rows.push(Row::Items(row));
                     continue;
                 }
 
+                // The directory name (or its alias)
+                let name = project
+                    .aliases()
+                    .alias(directory)
+                    .map_or_else(|| directory.display().to_string(), ToString::to_string);
+                row.push(Item::new(name, Style::new().bold()));
+
                 // Status
                 row.push(status);
 
+                // Group index, size, and cost (shown once per group).
+                if args.group_preview {
+                    if directory_idx == 0 {
+                        row.push(
+                            Item::new(format!("{}", group_idx + 1), Style::new())
+                                .with_alignment(Alignment::Right),
+                        );
+                        row.push(
+                            Item::new(format!("{}", group.len()), Style::new())
+                                .with_alignment(Alignment::Right),
+                        );
+                        row.push(
+                            Item::new(action.resources.cost(group.len()).to_string(), Style::new())
+                                .with_alignment(Alignment::Right),
+                        );
+                    } else {
+                        for _ in 0..3 {
+                            row.push(Item::new(String::new(), Style::new()));
+                        }
+                    }
+                }
+
                 // Job ID
                 if show_submitted || show_completed {
                     let submitted = project.state().submitted();
@@ -205,22 +464,40 @@ pub fn print_matching<W: Write>(
                     if let Some((cluster, job_id)) =
                         submitted.get(action.name()).and_then(|d| d.get(directory))
                     {
-                        row.push(Item::new(format!("{cluster}/{job_id}"), Style::new()));
+                        row.push(
+                            Item::new(format!("{cluster}/{job_id}"), Style::new())
+                                .with_link(project.job_url(cluster, *job_id)),
+                        );
                     } else {
                         row.push(Item::new(String::new(), Style::new()));
                     }
                 }
 
-                for pointer in &args.value {
-                    if !pointer.is_empty() && !pointer.starts_with('/') {
-                        warn!("The JSON pointer '{pointer}' does not appear valid. Did you mean '/{pointer}'?");
-                    }
+                // Completion cluster and time
+                if show_completion {
+                    let completion = project
+                        .state()
+                        .completed()
+                        .get(action.name())
+                        .and_then(|d| d.get(directory));
+                    let cluster = completion
+                        .and_then(|c| c.cluster.clone())
+                        .unwrap_or_default();
+                    let time = completion
+                        .and_then(|c| c.time)
+                        .map(|t| UtcTimestamp(t).to_string())
+                        .unwrap_or_default();
+                    row.push(Item::new(cluster, Style::new()));
+                    row.push(Item::new(time, Style::new()));
+                }
 
-                    let value = project.state().values()[directory]
-                        .pointer(pointer)
-                        .ok_or_else(|| {
-                            row::Error::JSONPointerNotFound(directory.clone(), pointer.clone())
-                        })?;
+                let directory_value = if columns.is_empty() {
+                    Value::Null
+                } else {
+                    values.get(directory).expect("Directory is present").clone()
+                };
+                for column in &columns {
+                    let value = column.evaluate(directory, &directory_value)?;
                     row.push(
                         Item::new(value.to_string(), Style::new()).with_alignment(Alignment::Right),
                     );
@@ -253,24 +530,44 @@ pub fn print_all<W: Write>(
     multi_progress: &mut MultiProgressContainer,
     output: &mut W,
 ) -> Result<(), Box<dyn Error>> {
-    let project = Project::open(options.io_threads, &options.cluster, multi_progress)?;
-
-    let all_directories = project.state().list_directories();
-    let mut query_directories =
-        cli::parse_directories(args.directories, || Ok(all_directories.clone()))?;
+    let project = Project::open(
+        options.io_threads,
+        &options.cluster,
+        options.workspace.as_deref(),
+        options.queue_timeout,
+        multi_progress,
+    )?;
+
+    let mut query_directories = select_directories(args.directories, &args.sets, &project)?;
     query_directories.sort_unstable();
-    let all_directories = HashSet::<PathBuf>::from_iter(all_directories);
+    let all_directories = HashSet::<PathBuf>::from_iter(project.state().list_directories());
+
+    // Show the header only in structured formats.
+    let format = Format::from_path(args.output.as_deref());
+    let mut table = Table::new()
+        .with_hide_header(format == Format::Text)
+        .with_format(format);
+    table.header = vec![Item::new("Directory".to_string(), Style::new())];
 
     for directory in &query_directories {
         if !all_directories.contains(directory) {
             warn!(
-                "Directory '{}' not found in workspace.",
-                directory.display()
+                "{}",
+                project
+                    .state()
+                    .values()
+                    .directory_not_found(directory.clone())
             );
             continue;
         }
-        writeln!(output, "{}", directory.display())?;
+        table.rows.push(Row::Items(vec![Item::new(
+            directory.display().to_string(),
+            Style::new(),
+        )]));
     }
 
+    table.write(output)?;
+    output.flush()?;
+
     Ok(())
 }
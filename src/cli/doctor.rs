@@ -0,0 +1,411 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use clap::Args;
+use console::style;
+use log::{debug, info};
+use std::collections::HashSet;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::cli::GlobalOptions;
+use row::cluster::{self, SchedulerType};
+use row::launcher;
+use row::state::{Repairs, State};
+use row::workflow::{Action, Workflow};
+use row::MultiProgressContainer;
+
+#[derive(Args, Debug)]
+pub struct Arguments {
+    /// Rebuild the cache from the workspace and report every change.
+    #[arg(long, display_order = 0)]
+    repair: bool,
+}
+
+/// The outcome of one diagnostic check.
+enum Diagnosis {
+    /// The check passed.
+    Ok(String),
+
+    /// Row will work, but the user may want to take action.
+    Warning(String, String),
+
+    /// Row will not work until the user takes action.
+    Problem(String, String),
+
+    /// `--repair` changed the cache.
+    Repaired(String),
+}
+
+/// Find an executable in `PATH`.
+fn find_executable(name: &str) -> Option<PathBuf> {
+    let paths = env::var_os("PATH")?;
+    env::split_paths(&paths)
+        .map(|p| p.join(name))
+        .find(|p| p.is_file())
+}
+
+/// Check that the data directory is present and writable.
+fn check_data_directory(data_directory: &Path) -> Diagnosis {
+    if !data_directory.exists() {
+        return Diagnosis::Ok(format!(
+            "'{}' does not exist yet. Row will create it.",
+            data_directory.display()
+        ));
+    }
+
+    if !data_directory.is_dir() {
+        return Diagnosis::Problem(
+            format!("'{}' is not a directory.", data_directory.display()),
+            "Move or remove it so that row can create its cache directory.".into(),
+        );
+    }
+
+    let probe = data_directory.join(format!(".doctor-{}", std::process::id()));
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            Diagnosis::Ok(format!("'{}' is writable.", data_directory.display()))
+        }
+        Err(error) => Diagnosis::Problem(
+            format!("'{}' is not writable: {error}.", data_directory.display()),
+            "Check the permissions and available space of the project directory.".into(),
+        ),
+    }
+}
+
+/// Check that the completed and submitted caches refer only to cached directories.
+fn check_cache(workflow: &Workflow) -> Vec<Diagnosis> {
+    let state = match State::from_cache(workflow) {
+        Ok(state) => state,
+        Err(error) => {
+            return vec![Diagnosis::Problem(
+                format!("Unable to read the cache: {error}"),
+                "Execute 'row clean' to remove the cache and rebuild it.".into(),
+            )]
+        }
+    };
+
+    let values = state.values();
+    let mut result = Vec::new();
+
+    let mut completed: Vec<_> = state.completed().iter().collect();
+    completed.sort_unstable_by_key(|(action_name, _)| *action_name);
+    for (action_name, directories) in completed {
+        let missing = directories
+            .keys()
+            .filter(|d| !values.contains_key(d))
+            .count();
+        if missing > 0 {
+            result.push(Diagnosis::Warning(
+                format!(
+                    "The completed cache has {missing} directories for action '{action_name}' \
+                     that are not in the directory cache."
+                ),
+                "Execute 'row scan' to synchronize the caches.".into(),
+            ));
+        }
+    }
+
+    let mut submitted: Vec<_> = state.submitted().iter().collect();
+    submitted.sort_unstable_by_key(|(action_name, _)| *action_name);
+    for (action_name, directories) in submitted {
+        let missing = directories
+            .keys()
+            .filter(|d| !values.contains_key(d))
+            .count();
+        if missing > 0 {
+            result.push(Diagnosis::Warning(
+                format!(
+                    "The submitted cache has {missing} directories for action '{action_name}' \
+                     that are not in the directory cache."
+                ),
+                "Execute 'row show status' to synchronize the caches.".into(),
+            ));
+        }
+    }
+
+    let action_names: HashSet<&str> = workflow.action.iter().map(Action::name).collect();
+    let mut unknown_actions: Vec<&String> = state
+        .completed()
+        .keys()
+        .chain(state.submitted().keys())
+        .filter(|a| !action_names.contains(a.as_str()))
+        .collect();
+    unknown_actions.sort_unstable();
+    unknown_actions.dedup();
+    for action_name in unknown_actions {
+        result.push(Diagnosis::Warning(
+            format!("The cache refers to action '{action_name}', which is not in the workflow."),
+            "Row will remove it the next time it synchronizes the caches.".into(),
+        ));
+    }
+
+    if result.is_empty() {
+        result.push(Diagnosis::Ok(format!(
+            "The cache is consistent ({} directories).",
+            values.len()
+        )));
+    }
+
+    result
+}
+
+/// Describe each change made by `State::repair`.
+fn describe_repairs(repairs: &Repairs) -> Vec<Diagnosis> {
+    if repairs.is_empty() {
+        return vec![Diagnosis::Ok("The cache needed no repairs.".into())];
+    }
+
+    let mut result = Vec::new();
+    for directory in &repairs.directories_added {
+        result.push(Diagnosis::Repaired(format!(
+            "Added '{}' to the directory cache.",
+            directory.display()
+        )));
+    }
+    for directory in &repairs.directories_removed {
+        result.push(Diagnosis::Repaired(format!(
+            "Removed '{}' from the directory cache.",
+            directory.display()
+        )));
+    }
+    for directory in &repairs.values_changed {
+        result.push(Diagnosis::Repaired(format!(
+            "Updated the cached value of '{}'.",
+            directory.display()
+        )));
+    }
+    if repairs.orphaned_completed > 0 {
+        result.push(Diagnosis::Repaired(format!(
+            "Removed {} orphaned entries from the completed cache.",
+            repairs.orphaned_completed
+        )));
+    }
+    if repairs.orphaned_submitted > 0 {
+        result.push(Diagnosis::Repaired(format!(
+            "Removed {} orphaned entries from the submitted cache.",
+            repairs.orphaned_submitted
+        )));
+    }
+    for (action_name, directory) in &repairs.completions_added {
+        result.push(Diagnosis::Repaired(format!(
+            "Marked '{}' completed for action '{action_name}': all products are present.",
+            directory.display()
+        )));
+    }
+    for (action_name, directory) in &repairs.completions_removed {
+        result.push(Diagnosis::Repaired(format!(
+            "Marked '{}' not completed for action '{action_name}': products are missing.",
+            directory.display()
+        )));
+    }
+
+    result
+}
+
+/// Rebuild the cache from the workspace.
+fn repair(
+    options: &GlobalOptions,
+    workflow: &Workflow,
+    multi_progress: &mut MultiProgressContainer,
+) -> Result<Vec<Diagnosis>, Box<dyn Error>> {
+    let cluster_name = cluster::Configuration::open()?
+        .identify(options.cluster.as_deref())?
+        .name;
+
+    let mut result = Vec::new();
+    let mut state = State::from_cache(workflow).unwrap_or_else(|error| {
+        result.push(Diagnosis::Repaired(format!(
+            "Discarded the unreadable cache: {error}"
+        )));
+        State::default()
+    });
+
+    let repairs = state.repair(
+        workflow,
+        &cluster_name,
+        workflow.io_threads(options.io_threads),
+        multi_progress,
+    )?;
+    state.save_cache(workflow, multi_progress)?;
+
+    result.extend(describe_repairs(&repairs));
+    Ok(result)
+}
+
+/// Check for lock files left behind by row processes that did not exit cleanly.
+fn check_lock_files(data_directory: &Path) -> Vec<Diagnosis> {
+    let Ok(entries) = data_directory.read_dir() else {
+        return Vec::new();
+    };
+
+    let mut lock_files: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "lock"))
+        .collect();
+    lock_files.sort_unstable();
+
+    if lock_files.is_empty() {
+        return vec![Diagnosis::Ok("No lock files found.".into())];
+    }
+
+    lock_files
+        .into_iter()
+        .map(|path| {
+            Diagnosis::Warning(
+                format!("Found the lock file '{}'.", path.display()),
+                "Remove it if no other row process is running in this project.".into(),
+            )
+        })
+        .collect()
+}
+
+/// Check the cluster identification and scheduler binaries.
+fn check_cluster(options: &GlobalOptions) -> Vec<Diagnosis> {
+    let cluster = match cluster::Configuration::open()
+        .and_then(|c| c.identify(options.cluster.as_deref()))
+    {
+        Ok(cluster) => cluster,
+        Err(error) => {
+            return vec![Diagnosis::Problem(
+                format!("Unable to identify the cluster: {error}"),
+                "Pass --cluster (or set ROW_CLUSTER) or add this cluster to clusters.toml.".into(),
+            )]
+        }
+    };
+
+    let mut result = vec![Diagnosis::Ok(format!(
+        "Identified cluster '{}' with the {:?} scheduler.",
+        cluster.name, cluster.scheduler
+    ))];
+
+    if let Err(error) = launcher::Configuration::open().and_then(|l| l.for_cluster(&cluster)) {
+        result.push(Diagnosis::Problem(
+            format!("Unable to read the launcher configuration: {error}"),
+            "Correct the error in launchers.toml.".into(),
+        ));
+    }
+
+    let executables: &[&str] = match cluster.scheduler {
+        SchedulerType::Slurm => &["sbatch", "squeue"],
+        SchedulerType::Bash => &["bash"],
+        SchedulerType::Kubernetes => &["kubectl"],
+    };
+    for executable in executables {
+        match find_executable(executable) {
+            Some(path) => result.push(Diagnosis::Ok(format!(
+                "Found '{executable}' at '{}'.",
+                path.display()
+            ))),
+            None => result.push(Diagnosis::Problem(
+                format!("'{executable}' not found in PATH."),
+                format!(
+                    "Load the module that provides '{executable}' or select a different \
+                     cluster with --cluster."
+                ),
+            )),
+        }
+    }
+
+    result
+}
+
+/// Diagnose common problems with the project and environment.
+///
+/// Print one line to `output` for each check and a suggestion for each
+/// warning and problem. With `--repair`, rebuild the cache first and print
+/// each change.
+///
+pub fn doctor<W: Write>(
+    options: &GlobalOptions,
+    args: &Arguments,
+    multi_progress: &mut MultiProgressContainer,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    debug!("Diagnosing the project.");
+
+    let mut diagnoses = Vec::new();
+
+    match Workflow::open_with_workspace(options.workspace.as_deref()) {
+        Ok(workflow) => {
+            diagnoses.push(Diagnosis::Ok(format!(
+                "Read the workflow in '{}'.",
+                workflow.root.display()
+            )));
+
+            if workflow.workspace.url.is_none() {
+                let workspace_path = workflow.root.join(&workflow.workspace.path);
+                if workspace_path.is_dir() {
+                    diagnoses.push(Diagnosis::Ok(format!(
+                        "Found the workspace '{}'.",
+                        workspace_path.display()
+                    )));
+                } else {
+                    diagnoses.push(Diagnosis::Problem(
+                        format!(
+                            "The workspace '{}' is not a directory.",
+                            workspace_path.display()
+                        ),
+                        "Create the workspace or set workspace.path in workflow.toml.".into(),
+                    ));
+                }
+            }
+
+            let data_directory = workflow.data_directory();
+            diagnoses.push(check_data_directory(&data_directory));
+            if args.repair {
+                match repair(options, &workflow, multi_progress) {
+                    Ok(repairs) => diagnoses.extend(repairs),
+                    Err(error) => diagnoses.push(Diagnosis::Problem(
+                        format!("Unable to repair the cache: {error}"),
+                        "Correct the error and execute 'row doctor --repair' again.".into(),
+                    )),
+                }
+            }
+            diagnoses.extend(check_cache(&workflow));
+            diagnoses.extend(check_lock_files(&data_directory));
+        }
+        Err(error) => diagnoses.push(Diagnosis::Problem(
+            format!("Unable to open the workflow: {error}"),
+            "Execute row in a project directory or correct the error in workflow.toml.".into(),
+        )),
+    }
+
+    diagnoses.extend(check_cluster(options));
+
+    let mut problems = 0;
+    let mut warnings = 0;
+    for diagnosis in &diagnoses {
+        match diagnosis {
+            Diagnosis::Ok(message) => {
+                writeln!(output, "{} {message}", style("ok").green().bold())?;
+            }
+            Diagnosis::Warning(message, suggestion) => {
+                warnings += 1;
+                writeln!(output, "{} {message}", style("warning").yellow().bold())?;
+                writeln!(output, "  {}", style(suggestion).italic())?;
+            }
+            Diagnosis::Problem(message, suggestion) => {
+                problems += 1;
+                writeln!(output, "{} {message}", style("problem").red().bold())?;
+                writeln!(output, "  {}", style(suggestion).italic())?;
+            }
+            Diagnosis::Repaired(message) => {
+                writeln!(output, "{} {message}", style("repaired").cyan().bold())?;
+            }
+        }
+    }
+    output.flush()?;
+
+    if problems > 0 {
+        Err(Box::new(row::Error::DoctorFoundProblems(problems)))
+    } else {
+        info!("Found no problems and {warnings} warning(s).");
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use clap::Args;
+use console::Style;
+use log::{debug, info, trace, warn};
+use serde_json::json;
+use std::collections::{BTreeSet, HashSet};
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+use wildmatch::WildMatch;
+
+use crate::cli::GlobalOptions;
+use crate::ui::{Alignment, Format, Item, Row, Table};
+use row::format::{ClockDuration, FormattedDuration};
+use row::project::{Efficiency, Project};
+use row::workflow::{Action, Processes, Walltime};
+use row::MultiProgressContainer;
+
+/// Minimum number of finished jobs needed to flag an action.
+const MINIMUM_JOBS: usize = 2;
+
+/// Suggest walltimes this much longer than the longest observed runtime.
+const WALLTIME_MARGIN: f64 = 1.5;
+
+#[derive(Args, Debug)]
+pub struct Arguments {
+    /// Select the actions to include with a wildcard pattern.
+    #[arg(short, long, value_name = "pattern", default_value_t=String::from("*"), display_order=0)]
+    action: String,
+
+    /// Select the actions with the given tag (repeat to select any of several tags).
+    #[arg(long, value_name = "tag", display_order = 0)]
+    tag: Vec<String>,
+
+    /// Flag actions whose jobs all used less than this fraction of the requested walltime.
+    #[arg(
+        long,
+        value_name = "fraction",
+        default_value_t = 0.5,
+        display_order = 0
+    )]
+    threshold: f64,
+
+    /// Hide the table header.
+    #[arg(long, display_order = 0)]
+    no_header: bool,
+
+    /// Write to a file instead of stdout. The extension selects the format: '.csv', '.tsv', '.json', or text.
+    #[arg(long, value_name = "file", display_order = 0)]
+    pub output: Option<PathBuf>,
+}
+
+/// Format a set of counts as a range (`4` or `1-8`).
+fn format_range(values: &BTreeSet<usize>) -> String {
+    match (values.first(), values.last()) {
+        (Some(first), Some(last)) if first == last => first.to_string(),
+        (Some(first), Some(last)) => format!("{first}-{last}"),
+        _ => String::new(),
+    }
+}
+
+/// Suggest a walltime for an action whose jobs used at most `fraction` of
+/// the requested walltime.
+///
+/// # Returns
+/// `None` when the suggestion would not reduce the walltime.
+///
+fn suggest_walltime(action: &Action, fraction: f64) -> Option<String> {
+    let (key, walltime) = match action.resources.walltime() {
+        Walltime::PerDirectory(walltime) => ("per_directory", walltime),
+        Walltime::PerSubmission(walltime) => ("per_submission", walltime),
+    };
+    let seconds = u32::try_from(walltime.signed_total_seconds()).ok()?;
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let minutes = (f64::from(seconds) * fraction * WALLTIME_MARGIN / 60.0)
+        .ceil()
+        .max(1.0) as u64;
+    if minutes * 60 >= u64::from(seconds) {
+        return None;
+    }
+
+    Some(format!(
+        "resources.walltime.{key} = \"{}\"",
+        ClockDuration(Duration::from_secs(minutes * 60))
+    ))
+}
+
+/// Warn about the resources that an action requests but does not use.
+fn report(action: &Action, efficiency: &Efficiency, threshold: f64) {
+    if efficiency.jobs < MINIMUM_JOBS {
+        return;
+    }
+
+    if efficiency.walltime_fraction < threshold {
+        let message = format!(
+            "Action '{}' used at most {:.1}% of the requested walltime in {} jobs.",
+            action.name(),
+            efficiency.walltime_fraction * 100.0,
+            efficiency.jobs
+        );
+        if let Some(suggestion) = suggest_walltime(action, efficiency.walltime_fraction) {
+            warn!("{message} Consider setting `{suggestion}`.");
+        } else {
+            warn!("{message}");
+        }
+    }
+
+    if let (Processes::PerSubmission(processes), [gpus]) = (
+        action.resources.processes(),
+        efficiency.gpus.iter().collect::<Vec<_>>().as_slice(),
+    ) {
+        if **gpus > 0 && efficiency.group_sizes.len() > 1 {
+            let largest = efficiency.group_sizes.last().copied().unwrap_or(1);
+            warn!(
+                "Action '{}' requested {gpus} GPUs in every job with {} directories. \
+                 Consider setting `resources.processes.per_directory = {}` so that the \
+                 request scales with the number of directories.",
+                action.name(),
+                format_range(&efficiency.group_sizes),
+                (processes / largest).max(1)
+            );
+        }
+    }
+}
+
+/// Show how the submitted jobs used the resources they requested.
+///
+/// Print a table with one row per action and warn about the actions that
+/// request resources they do not use.
+///
+pub fn efficiency<W: Write>(
+    options: &GlobalOptions,
+    args: &Arguments,
+    multi_progress: &mut MultiProgressContainer,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    debug!("Showing the efficiency of submitted jobs.");
+
+    let mut project = Project::open_offline(
+        options.io_threads,
+        &options.cluster,
+        options.workspace.as_deref(),
+        multi_progress,
+    )?;
+
+    let efficiency = project.efficiency();
+    let action_matcher = WildMatch::new(&args.action);
+
+    let mut table = Table::new()
+        .with_hide_header(args.no_header)
+        .with_format(Format::from_path(args.output.as_deref()));
+    let underlined = Style::new().underlined();
+    table.header = vec![
+        Item::new("Action".to_string(), underlined.clone()),
+        Item::new("Jobs".to_string(), underlined.clone()).with_alignment(Alignment::Right),
+        Item::new("Longest runtime".to_string(), underlined.clone())
+            .with_alignment(Alignment::Right),
+        Item::new("Walltime used".to_string(), underlined.clone()).with_alignment(Alignment::Right),
+        Item::new("Directories".to_string(), underlined.clone()).with_alignment(Alignment::Right),
+        Item::new("GPUs".to_string(), underlined.clone()).with_alignment(Alignment::Right),
+    ];
+
+    let mut reported = HashSet::new();
+    for action in &project.workflow().action {
+        if !action_matcher.matches(action.name()) || !action.has_any_tag(&args.tag) {
+            trace!("Skipping action '{}'.", action.name());
+            continue;
+        }
+        let Some(action_efficiency) = efficiency.get(action.name()) else {
+            continue;
+        };
+        if !reported.insert(action.name()) {
+            continue;
+        }
+
+        table.rows.push(Row::Items(vec![
+            Item::new(action.name().to_string(), Style::new().bold()),
+            Item::new(action_efficiency.jobs.to_string(), Style::new())
+                .with_value(json!(action_efficiency.jobs))
+                .with_alignment(Alignment::Right),
+            Item::new(
+                format!(
+                    "{}",
+                    FormattedDuration(
+                        Duration::from_secs(action_efficiency.longest_runtime),
+                        options.time_format()
+                    )
+                ),
+                Style::new().italic(),
+            )
+            .with_value(json!(action_efficiency.longest_runtime))
+            .with_alignment(Alignment::Right),
+            Item::new(
+                format!("{:.1}%", action_efficiency.walltime_fraction * 100.0),
+                Style::new(),
+            )
+            .with_value(json!(action_efficiency.walltime_fraction * 100.0))
+            .with_alignment(Alignment::Right),
+            Item::new(format_range(&action_efficiency.group_sizes), Style::new())
+                .with_alignment(Alignment::Right),
+            Item::new(format_range(&action_efficiency.gpus), Style::new())
+                .with_alignment(Alignment::Right),
+        ]));
+
+        report(action, action_efficiency, args.threshold);
+    }
+
+    if table.rows.is_empty() {
+        info!("No finished jobs of the selected actions are in the job history.");
+    } else {
+        table.write(output)?;
+        output.flush()?;
+    }
+
+    project.close(multi_progress)?;
+    Ok(())
+}
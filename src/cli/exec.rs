@@ -0,0 +1,238 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use clap::Args;
+use console::style;
+use indicatif::HumanCount;
+use log::{debug, info};
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::flag;
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+use crate::cli::{self, GlobalOptions};
+use row::cluster;
+use row::launcher;
+use row::project::Project;
+use row::scheduler::bash::Bash;
+use row::scheduler::{GroupPosition, Scheduler};
+use row::MultiProgressContainer;
+
+#[derive(Args, Debug)]
+pub struct Arguments {
+    /// The action to execute.
+    #[arg(short, long, display_order = 0)]
+    action: String,
+
+    /// Select directories to execute (defaults to all). Use 'exec -' to read from stdin.
+    directories: Vec<PathBuf>,
+
+    /// Execute directories that are not eligible.
+    #[arg(long, display_order = 0)]
+    force: bool,
+
+    /// Print the scripts instead of executing them.
+    #[arg(long, display_order = 0)]
+    dry_run: bool,
+
+    /// Skip the cluster's preamble and the action's setup commands.
+    ///
+    /// Jobs that execute `row exec` have already executed them.
+    #[arg(long, display_order = 0)]
+    skip_setup: bool,
+}
+
+/// Execute an action on the selected directories in the current process.
+///
+/// Execute the same script that a job would for each directory, but without
+/// the scheduler. Check that each directory is still eligible immediately before
+/// executing it (unless `--force`). Execute up to
+/// `resources.concurrent_directories` directories at a time.
+///
+#[allow(clippy::too_many_lines)]
+pub fn exec<W: Write>(
+    options: &GlobalOptions,
+    args: Arguments,
+    multi_progress: &mut MultiProgressContainer,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    debug!("Executing action '{}'.", args.action);
+
+    // The job that executes `row exec` is in the submitted cache. There is no
+    // need to query the scheduler.
+    let project = Project::open_offline(
+        options.io_threads,
+        &options.cluster,
+        options.workspace.as_deref(),
+        multi_progress,
+    )?;
+    let query_directories = cli::parse_directories(args.directories, project.aliases(), || {
+        Ok(project.state().list_directories())
+    })?;
+
+    // Several actions may share a name. Execute each on the directories it matches.
+    let mut actions = Vec::new();
+    for action in &project.workflow().action {
+        if action.name() != args.action {
+            continue;
+        }
+
+        let mut action = action.clone();
+        if args.skip_setup {
+            if let Some(submit_options) = action.submit_options.get_mut(project.cluster_name()) {
+                submit_options.setup = None;
+            }
+        }
+
+        let directories = project.find_matching_directories(&action, query_directories.clone())?;
+        actions.push((action, directories));
+    }
+    if actions.is_empty() {
+        return Err(Box::new(project.workflow().action_not_found(&args.action)));
+    }
+
+    let mut cluster = cluster::Configuration::open()?.identify(Some(project.cluster_name()))?;
+    let launchers = launcher::Configuration::open()?.for_cluster(&cluster)?;
+    if args.skip_setup {
+        cluster.bash = None;
+    }
+    let bash =
+        Bash::new(cluster, launchers).with_workspace(project.workflow().workspace.path.clone());
+
+    if args.dry_run {
+        info!("Execute without --dry-run to execute the following scripts...");
+        for (action, directories) in &actions {
+            for (index, directory) in directories.iter().enumerate() {
+                if !args.force && !project.recheck_eligible(action, directory, multi_progress)? {
+                    continue;
+                }
+                let group = GroupPosition {
+                    index,
+                    count: directories.len(),
+                };
+                let directories = std::slice::from_ref(directory);
+                let script = bash.make_script(action, directories, action.name(), group)?;
+                let script = project.workflow().redact(project.cluster_name(), &script);
+                write!(output, "{script}")?;
+            }
+        }
+        output.flush()?;
+        return Ok(());
+    }
+
+    multi_progress.clear().unwrap();
+
+    let should_terminate = Arc::new(AtomicBool::new(false));
+    flag::register_conditional_shutdown(SIGINT, 10, Arc::clone(&should_terminate))?;
+    flag::register(SIGINT, Arc::clone(&should_terminate))?;
+    flag::register_conditional_shutdown(SIGTERM, 10, Arc::clone(&should_terminate))?;
+    flag::register(SIGTERM, Arc::clone(&should_terminate))?;
+
+    let root = &project.workflow().root;
+    let mut executed = 0;
+    let mut skipped = 0;
+    for (action, directories) in &actions {
+        let concurrent = action.resources.concurrent_directories.unwrap_or(1);
+        let (sender, receiver) = mpsc::channel();
+        let mut running = 0;
+
+        let result = thread::scope(|scope| {
+            let mut result = Ok(());
+            for (index, directory) in directories.iter().enumerate() {
+                if running >= concurrent {
+                    result = receiver.recv().expect("A thread is running");
+                    running -= 1;
+                }
+                if result.is_err() {
+                    break;
+                }
+
+                match project.recheck_eligible(action, directory, multi_progress) {
+                    _ if args.force => (),
+                    Ok(true) => (),
+                    Ok(false) => {
+                        info!(
+                            "Skipping directory '{}': it is no longer eligible for action '{}'.",
+                            directory.display(),
+                            action.name()
+                        );
+                        skipped += 1;
+                        continue;
+                    }
+                    Err(error) => {
+                        result = Err(error);
+                        break;
+                    }
+                }
+
+                if !options.quiet {
+                    println!(
+                        "Executing action '{}' on directory {}.",
+                        style(action.name()).blue(),
+                        style(directory.display()).bold()
+                    );
+                }
+                executed += 1;
+                running += 1;
+
+                let group = GroupPosition {
+                    index,
+                    count: directories.len(),
+                };
+                let sender = sender.clone();
+                let bash = &bash;
+                let should_terminate = Arc::clone(&should_terminate);
+                scope.spawn(move || {
+                    let result = bash
+                        .submit(
+                            root,
+                            action,
+                            std::slice::from_ref(directory),
+                            action.name(),
+                            group,
+                            should_terminate,
+                        )
+                        .map(|_| ());
+                    sender.send(result).expect("The receiver is waiting");
+                });
+            }
+
+            while running > 0 {
+                let finished = receiver.recv().expect("A thread is running");
+                result = result.and(finished);
+                running -= 1;
+            }
+            result
+        });
+
+        result?;
+    }
+
+    info!(
+        "Executed {} {}.",
+        HumanCount(executed),
+        if executed == 1 {
+            "directory"
+        } else {
+            "directories"
+        }
+    );
+    if skipped > 0 {
+        info!(
+            "Skipped {} {} that {} no longer eligible.",
+            HumanCount(skipped),
+            if skipped == 1 {
+                "directory"
+            } else {
+                "directories"
+            },
+            if skipped == 1 { "is" } else { "are" }
+        );
+    }
+
+    Ok(())
+}
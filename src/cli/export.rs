@@ -0,0 +1,258 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use clap::Args;
+use log::{debug, info};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::cli::GlobalOptions;
+use row::export::{anonymous_name, TarWriter};
+use row::project::Project;
+use row::state::group_id;
+use row::workflow::Workflow;
+use row::{
+    cluster, launcher, MultiProgressContainer, GENERATED_WORKFLOW_PREFIX, TOML_WORKFLOW_FILE_NAME,
+    YAML_WORKFLOW_FILE_NAME,
+};
+
+/// Directory that holds the files in the bundle.
+const BUNDLE_DIRECTORY: &str = "row-export";
+
+#[derive(Args, Debug)]
+pub struct Arguments {
+    /// The tar file to write.
+    #[arg(display_order = 0)]
+    output: PathBuf,
+
+    /// Replace directory names with salted hashes, omit the directory values, and mask the redacted submit options.
+    #[arg(long, display_order = 0)]
+    anonymize: bool,
+}
+
+/// Name a directory in the bundle.
+///
+/// Anonymize the name when given a `salt`.
+///
+fn bundle_name(directory: &Path, salt: Option<&[u8]>) -> String {
+    match salt {
+        Some(salt) => anonymous_name(salt, directory),
+        None => directory.display().to_string(),
+    }
+}
+
+/// Read the workflow file and the generated workflow (when present).
+///
+/// # Returns
+/// `Ok(Vec<(file name, contents)>)`.
+///
+fn read_workflow_files(
+    workflow: &Workflow,
+    anonymize: bool,
+) -> Result<Vec<(String, String)>, row::Error> {
+    let mut result = Vec::new();
+    for file_name in [TOML_WORKFLOW_FILE_NAME, YAML_WORKFLOW_FILE_NAME] {
+        for path in [
+            workflow.root.join(file_name),
+            workflow
+                .data_directory()
+                .join(format!("{GENERATED_WORKFLOW_PREFIX}{file_name}")),
+        ] {
+            let contents = match fs::read_to_string(&path) {
+                Ok(contents) => contents,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(row::Error::FileRead(path, error)),
+            };
+            let name = path
+                .file_name()
+                .expect("Workflow paths end in a file name")
+                .to_string_lossy()
+                .into_owned();
+            result.push((name, contents));
+        }
+    }
+
+    if anonymize {
+        for (name, contents) in &mut result {
+            *contents = workflow.redact_file(Path::new(name.as_str()), contents)?;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Collect the cached state of the project.
+///
+/// Omit the directory values when given a `salt`: they often identify the
+/// directories as well as the names do.
+///
+/// # Returns
+/// `(values, completed, submitted, submitted groups)` as JSON.
+///
+fn collect_state(project: &Project, salt: Option<&[u8]>) -> (Option<Value>, Value, Value, Value) {
+    let state = project.state();
+
+    let values = salt.is_none().then(|| {
+        let values = state.values();
+        Value::Object(
+            values
+                .keys()
+                .map(|d| {
+                    (
+                        d.display().to_string(),
+                        values.get(d).cloned().unwrap_or_default(),
+                    )
+                })
+                .collect::<Map<String, Value>>(),
+        )
+    });
+
+    let completed: BTreeMap<&String, BTreeMap<String, Value>> = state
+        .completed()
+        .iter()
+        .map(|(action, directories)| {
+            (
+                action,
+                directories
+                    .iter()
+                    .map(|(d, c)| (bundle_name(d, salt), json!(c)))
+                    .collect(),
+            )
+        })
+        .collect();
+
+    let submitted: BTreeMap<&String, BTreeMap<String, Value>> = state
+        .submitted()
+        .iter()
+        .map(|(action, directories)| {
+            (
+                action,
+                directories
+                    .iter()
+                    .map(|(d, (cluster, job_id))| {
+                        (
+                            bundle_name(d, salt),
+                            json!({"cluster": cluster, "job_id": job_id}),
+                        )
+                    })
+                    .collect(),
+            )
+        })
+        .collect();
+
+    // Identify each anonymized group by its anonymized names so that the
+    // identifier does not reveal the real names.
+    let groups: BTreeMap<&String, BTreeMap<String, Vec<String>>> = state
+        .submitted_groups()
+        .iter()
+        .map(|(action, groups)| {
+            (
+                action,
+                groups
+                    .iter()
+                    .map(|(id, directories)| {
+                        let names: Vec<String> =
+                            directories.iter().map(|d| bundle_name(d, salt)).collect();
+                        let id = if salt.is_some() {
+                            group_id(&names.iter().map(PathBuf::from).collect::<Vec<_>>())
+                        } else {
+                            id.clone()
+                        };
+                        (id, names)
+                    })
+                    .collect(),
+            )
+        })
+        .collect();
+
+    (values, json!(completed), json!(submitted), json!(groups))
+}
+
+/// Package the project into a reproducibility bundle.
+///
+/// Write the workflow, the effective cluster and launcher configurations,
+/// and the cached directory values, completions, and submitted jobs to a tar
+/// file.
+///
+pub fn export(
+    options: &GlobalOptions,
+    args: &Arguments,
+    multi_progress: &mut MultiProgressContainer,
+) -> Result<(), Box<dyn Error>> {
+    debug!("Exporting the project to '{}'.", args.output.display());
+
+    let mut project = Project::open_offline(
+        options.io_threads,
+        &options.cluster,
+        options.workspace.as_deref(),
+        multi_progress,
+    )?;
+
+    let clusters = cluster::Configuration::open()?;
+    let launchers = launcher::Configuration::open()?;
+    // A new salt for each export prevents matching names between bundles.
+    let salt = args.anonymize.then(|| Uuid::new_v4().into_bytes());
+    let (values, completed, submitted, groups) =
+        collect_state(&project, salt.as_ref().map(|s| &s[..]));
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let manifest = json!({
+        "row_version": env!("CARGO_PKG_VERSION"),
+        "cluster": project.cluster_name(),
+        "time": time,
+        "anonymized": args.anonymize,
+    });
+
+    let mut files: Vec<(String, Vec<u8>)> =
+        read_workflow_files(project.workflow(), args.anonymize)?
+            .into_iter()
+            .map(|(name, contents)| (name, contents.into_bytes()))
+            .collect();
+    files.push((
+        "clusters.toml".into(),
+        toml::to_string_pretty(&clusters)?.into_bytes(),
+    ));
+    files.push((
+        "launchers.toml".into(),
+        toml::to_string_pretty(launchers.full_config())?.into_bytes(),
+    ));
+    if let Some(values) = values {
+        files.push(("values.json".into(), serde_json::to_vec_pretty(&values)?));
+    }
+    for (name, value) in [
+        ("export.json", manifest),
+        ("completed.json", completed),
+        ("submitted.json", submitted),
+        ("submitted_groups.json", groups),
+    ] {
+        files.push((name.into(), serde_json::to_vec_pretty(&value)?));
+    }
+
+    let file =
+        File::create(&args.output).map_err(|e| row::Error::FileWrite(args.output.clone(), e))?;
+    let mut writer = TarWriter::new(BufWriter::new(file), time);
+    for (name, contents) in &files {
+        writer
+            .append(&format!("{BUNDLE_DIRECTORY}/{name}"), contents)
+            .map_err(|e| row::Error::FileWrite(args.output.clone(), e))?;
+    }
+    writer
+        .finish()
+        .map_err(|e| row::Error::FileWrite(args.output.clone(), e))?;
+
+    info!(
+        "Exported {} files to '{}'.",
+        files.len(),
+        args.output.display()
+    );
+
+    project.close(multi_progress)?;
+    Ok(())
+}
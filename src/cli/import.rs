@@ -0,0 +1,167 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use clap::{Args, ValueEnum};
+use log::{debug, info};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::cli::GlobalOptions;
+use row::export::read_tar;
+use row::project::Project;
+use row::state::{Completions, Conflict, Import};
+use row::MultiProgressContainer;
+
+#[derive(Args, Debug)]
+pub struct Arguments {
+    /// The tar file written by `row export`.
+    #[arg(display_order = 0)]
+    bundle: PathBuf,
+
+    /// How to resolve entries that are already in the cache.
+    #[arg(long, value_enum, default_value_t = ConflictMode::Keep, display_order = 0)]
+    conflict: ConflictMode,
+}
+
+/// How to resolve entries that are already in the cache.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ConflictMode {
+    /// Keep the cached entry.
+    Keep,
+
+    /// Replace the cached entry with the imported entry.
+    Replace,
+
+    /// Exit with an error without changing the cache.
+    Error,
+}
+
+impl From<ConflictMode> for Conflict {
+    fn from(mode: ConflictMode) -> Self {
+        match mode {
+            ConflictMode::Keep => Conflict::Keep,
+            ConflictMode::Replace => Conflict::Replace,
+            ConflictMode::Error => Conflict::Error,
+        }
+    }
+}
+
+/// A submitted job in `submitted.json`.
+#[derive(Deserialize)]
+struct SubmittedJob {
+    cluster: String,
+    job_id: u32,
+}
+
+/// Parse the JSON file with the given name in the bundle.
+fn parse<T: DeserializeOwned>(
+    path: &Path,
+    files: &HashMap<String, Vec<u8>>,
+    name: &str,
+) -> Result<T, row::Error> {
+    let contents = files
+        .get(name)
+        .ok_or_else(|| row::Error::InvalidBundle(path.into(), format!("missing {name}")))?;
+    serde_json::from_slice(contents)
+        .map_err(|e| row::Error::InvalidBundle(path.into(), format!("{name}: {e}")))
+}
+
+/// Read the state in a bundle.
+fn read_bundle(path: &Path) -> Result<Import, row::Error> {
+    let bytes = fs::read(path).map_err(|e| row::Error::FileRead(path.into(), e))?;
+    let files: HashMap<String, Vec<u8>> = read_tar(&bytes)
+        .map_err(|e| row::Error::InvalidBundle(path.into(), e.to_string()))?
+        .into_iter()
+        .map(|(name, contents)| {
+            let file_name = name.rsplit('/').next().unwrap_or_default().to_string();
+            (file_name, contents)
+        })
+        .collect();
+
+    let manifest: Value = parse(path, &files, "export.json")?;
+    if manifest["anonymized"] == Value::Bool(true) {
+        return Err(row::Error::InvalidBundle(
+            path.into(),
+            "anonymized bundles cannot be imported".into(),
+        ));
+    }
+
+    let completed: Completions = parse(path, &files, "completed.json")?;
+    let submitted: HashMap<String, HashMap<PathBuf, SubmittedJob>> =
+        parse(path, &files, "submitted.json")?;
+    let submitted_groups = parse(path, &files, "submitted_groups.json")?;
+
+    Ok(Import {
+        completed,
+        submitted: submitted
+            .into_iter()
+            .map(|(action_name, directories)| {
+                (
+                    action_name,
+                    directories
+                        .into_iter()
+                        .map(|(d, job)| (d, (job.cluster, job.job_id)))
+                        .collect(),
+                )
+            })
+            .collect(),
+        submitted_groups,
+    })
+}
+
+/// Restore or merge the state in a bundle written by `row export`.
+///
+/// Print the number of entries imported.
+///
+pub fn import<W: Write>(
+    options: &GlobalOptions,
+    args: &Arguments,
+    multi_progress: &mut MultiProgressContainer,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    debug!("Importing '{}'.", args.bundle.display());
+
+    let bundle = read_bundle(&args.bundle)?;
+
+    let mut project = Project::open(
+        options.io_threads,
+        &options.cluster,
+        options.workspace.as_deref(),
+        options.queue_timeout,
+        multi_progress,
+    )?;
+
+    // Other `row submit` processes may change the submitted jobs. Hold the
+    // lock until the cache is saved.
+    let _lock = project.lock_submissions()?;
+    let imported = project.import(bundle, args.conflict.into())?;
+
+    if imported.kept > 0 {
+        info!(
+            "Kept {} cached entries that are also in the bundle.",
+            imported.kept
+        );
+    }
+    if imported.skipped > 0 {
+        info!(
+            "Skipped {} entries for actions or directories that are not in the project or \
+             jobs on other clusters.",
+            imported.skipped
+        );
+    }
+    writeln!(
+        output,
+        "Imported {} completions, {} submitted directories, and {} submitted groups.",
+        imported.completed, imported.submitted, imported.submitted_groups
+    )?;
+    output.flush()?;
+
+    project.close(multi_progress)?;
+    Ok(())
+}
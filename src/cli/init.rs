@@ -15,13 +15,9 @@ use row::{Error, DATA_DIRECTORY_NAME};
 #[derive(Args, Debug)]
 pub struct Arguments {
     /// Configure `workflow.toml` for signac.
-    #[arg(long, group = "workspace_group", display_order = 0)]
+    #[arg(long, conflicts_with = "workspace", display_order = 0)]
     signac: bool,
 
-    /// The workspace directory name.
-    #[arg(short, long, default_value_t=String::from("workspace"), group="workspace_group", display_order = 0)]
-    workspace: String,
-
     /// Directory to initialize.
     #[arg(display_order = 0)]
     directory: PathBuf,
@@ -53,16 +49,23 @@ fn is_project(path: &Path) -> Result<(bool, PathBuf), Error> {
 }
 
 /// Initialize a new row project directory.
+///
+/// The global `--workspace` option names the workspace directory.
+///
 pub fn init<W: Write>(
-    _options: &GlobalOptions,
+    options: &GlobalOptions,
     args: &Arguments,
     output: &mut W,
 ) -> Result<(), Box<dyn std::error::Error>> {
     debug!("Scanning the workspace for completed actions.");
 
-    if args.workspace.contains(path::MAIN_SEPARATOR_STR) {
+    let workspace = options
+        .workspace
+        .as_deref()
+        .map_or_else(|| "workspace".into(), Path::to_string_lossy);
+    if workspace.contains(path::MAIN_SEPARATOR_STR) {
         return Err(Box::new(row::Error::WorkspacePathNotRelative(
-            args.workspace.clone(),
+            workspace.into(),
         )));
     }
 
@@ -85,7 +88,7 @@ pub fn init<W: Write>(
         warn!("'{}' already exists.", project_directory.display());
     }
 
-    let workspace_directory = project_directory.clone().join(&args.workspace);
+    let workspace_directory = project_directory.clone().join(&*workspace);
     info!("Creating directory '{}'", workspace_directory.display());
     fs::create_dir_all(&workspace_directory)
         .map_err(|e| Error::DirectoryCreate(workspace_directory.clone(), e))?;
@@ -99,12 +102,11 @@ value_file = "signac_statepoint.json""#
         );
     }
 
-    if args.workspace != "workspace" {
+    if workspace != "workspace" {
         let _ = writeln!(
             workflow,
-            r#"[workspace]
-path = '{}'"#,
-            args.workspace
+            r"[workspace]
+path = '{workspace}'"
         );
     }
 
@@ -5,10 +5,12 @@ use clap::Args;
 use log::{debug, info};
 use std::error::Error;
 use std::io::Write;
+use std::path::PathBuf;
 
-use crate::cli::GlobalOptions;
+use crate::cli::{self, GlobalOptions};
 use row::cluster;
 use row::launcher;
+use row::workflow::Workflow;
 
 #[derive(Args, Debug)]
 pub struct Arguments {
@@ -19,11 +21,38 @@ pub struct Arguments {
     /// Show only launcher names.
     #[arg(long, display_order = 0, conflicts_with = "all")]
     short: bool,
+
+    /// Write to a file instead of stdout. The extension selects the format: '.json' or TOML.
+    #[arg(long, value_name = "file", display_order = 0)]
+    pub output: Option<PathBuf>,
+
+    /// Print the launcher prefix that executes the action given in `--action`.
+    #[arg(
+        long,
+        display_order = 0,
+        requires = "action",
+        conflicts_with_all = ["all", "short", "output"]
+    )]
+    resolve: bool,
+
+    /// The action to resolve.
+    #[arg(long, short, display_order = 0, requires = "resolve")]
+    action: Option<String>,
+
+    /// The number of directories in the group to resolve.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 1,
+        display_order = 0,
+        requires = "resolve"
+    )]
+    group_size: usize,
 }
 
 /// Show the launchers.
 ///
-/// Print the launchers to stdout in toml format.
+/// Print the launchers in toml (or json) format.
 ///
 pub fn launchers<W: Write>(
     options: &GlobalOptions,
@@ -34,12 +63,16 @@ pub fn launchers<W: Write>(
 
     let launchers = launcher::Configuration::open()?;
 
+    if let (true, Some(action_name)) = (args.resolve, &args.action) {
+        return resolve(options, &launchers, action_name, args.group_size, output);
+    }
+
     if args.all {
         info!("All launcher configurations:");
         write!(
             output,
             "{}",
-            &toml::to_string_pretty(launchers.full_config())?
+            &cli::to_config_string(launchers.full_config(), args.output.as_deref())?
         )?;
     } else {
         let clusters = cluster::Configuration::open()?;
@@ -54,10 +87,45 @@ pub fn launchers<W: Write>(
             write!(
                 output,
                 "{}",
-                &toml::to_string_pretty(&launchers.by_cluster(&cluster.name))?
+                &cli::to_config_string(&launchers.for_cluster(&cluster)?, args.output.as_deref())?
             )?;
         }
     }
 
     Ok(())
 }
+
+/// Print the launcher prefix that executes an action on the current cluster.
+///
+/// Print the process launcher arguments of each following heterogeneous
+/// component on separate lines that start with ':'.
+///
+fn resolve<W: Write>(
+    options: &GlobalOptions,
+    launchers: &launcher::Configuration,
+    action_name: &str,
+    group_size: usize,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    let workflow = Workflow::open_with_workspace(options.workspace.as_deref())?;
+    let action = workflow
+        .action_by_name(action_name)
+        .ok_or_else(|| workflow.action_not_found(action_name))?;
+
+    let clusters = cluster::Configuration::open()?;
+    let cluster = clusters.identify(options.cluster.as_deref())?;
+    let launchers = launchers.for_cluster(&cluster)?;
+
+    info!(
+        "Launcher prefix for '{action_name}' with {group_size} directories on cluster '{}':",
+        cluster.name
+    );
+    let prefix = launcher::resolve_prefix(&launchers, action, &cluster.name, group_size)?;
+    writeln!(output, "{}", prefix.prefix.trim_end())?;
+    for arguments in &prefix.components {
+        writeln!(output, ": {}", arguments.trim_end())?;
+    }
+    output.flush()?;
+
+    Ok(())
+}
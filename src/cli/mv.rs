@@ -0,0 +1,127 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use clap::Args;
+use log::{debug, info, warn};
+use std::error::Error;
+use std::path::{Component, PathBuf};
+
+use crate::cli::GlobalOptions;
+use row::alias::Aliases;
+use row::project::Project;
+use row::MultiProgressContainer;
+
+#[derive(Args, Debug)]
+pub struct Arguments {
+    /// The directory to rename (or its alias).
+    directory: PathBuf,
+
+    /// The new name of the directory.
+    new_name: PathBuf,
+
+    /// Rename the directory even when it is submitted.
+    #[arg(long, display_order = 0)]
+    force: bool,
+}
+
+/// Rename a directory in the workspace.
+///
+/// Rename the directory on disk and update the caches and aliases.
+///
+pub fn mv(
+    options: &GlobalOptions,
+    args: Arguments,
+    multi_progress: &mut MultiProgressContainer,
+) -> Result<(), Box<dyn Error>> {
+    debug!(
+        "Renaming '{}' to '{}'.",
+        args.directory.display(),
+        args.new_name.display()
+    );
+
+    let mut project = Project::open(
+        options.io_threads,
+        &options.cluster,
+        options.workspace.as_deref(),
+        options.queue_timeout,
+        multi_progress,
+    )?;
+
+    if let Some(url) = &project.workflow().workspace.url {
+        return Err(Box::new(row::Error::ReadOnlyWorkspace(url.clone())));
+    }
+
+    let directory = project.aliases().resolve(args.directory);
+    let new_name = args.new_name;
+
+    if !project.state().values().contains_key(&directory) {
+        return Err(Box::new(
+            project.state().values().directory_not_found(directory),
+        ));
+    }
+
+    let mut components = new_name.components();
+    if !matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    ) {
+        return Err(Box::new(row::Error::InvalidDirectoryName(new_name)));
+    }
+
+    let new_path = project
+        .workflow()
+        .root
+        .join(&project.workflow().workspace.path)
+        .join(&new_name);
+    if project.state().values().contains_key(&new_name) || new_path.symlink_metadata().is_ok() {
+        return Err(Box::new(row::Error::FileExists(new_path)));
+    }
+
+    let mut aliases = Aliases::open(project.workflow())?;
+    if let Some(name) = new_name.to_str() {
+        if aliases.resolve(new_name.clone()) != new_name {
+            return Err(Box::new(row::Error::AliasIsDirectory(name.into())));
+        }
+    }
+
+    for action in &project.workflow().action {
+        if project.state().is_submitted(action.name(), &directory) {
+            if args.force {
+                warn!(
+                    "Renaming '{}', which is submitted for action '{}'.",
+                    directory.display(),
+                    action.name()
+                );
+            } else {
+                return Err(Box::new(row::Error::DirectorySubmitted(
+                    directory,
+                    action.name().into(),
+                )));
+            }
+        }
+    }
+
+    info!(
+        "Renaming '{}' to '{}'.",
+        directory.display(),
+        new_name.display()
+    );
+    project.rename_directory(&directory, &new_name)?;
+
+    let renamed: Vec<String> = aliases
+        .iter()
+        .filter(|(_, d)| **d == directory)
+        .map(|(alias, _)| alias.clone())
+        .collect();
+    if !renamed.is_empty() {
+        for alias in renamed {
+            info!("Alias '{alias}' now names '{}'.", new_name.display());
+            aliases.add(alias, new_name.clone());
+        }
+        aliases.save(project.workflow())?;
+    }
+
+    project.close(multi_progress)?;
+
+    Ok(())
+}
@@ -0,0 +1,228 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use clap::Args;
+use console::style;
+use indicatif::HumanCount;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use crate::cli::submit;
+use crate::cli::GlobalOptions;
+use row::project::Project;
+use row::scheduler::GroupPosition;
+use row::workflow::Action;
+use row::MultiProgressContainer;
+
+#[derive(Args, Debug)]
+pub struct Arguments {
+    /// Select the actions to plan with a wildcard pattern.
+    #[arg(short, long, value_name = "pattern", default_value_t=String::from("*"), display_order=0)]
+    action: String,
+
+    /// Select the actions with the given tag (repeat to select any of several tags).
+    #[arg(long, value_name = "tag", display_order = 0)]
+    tag: Vec<String>,
+
+    /// Select directories to plan (defaults to all).
+    directories: Vec<PathBuf>,
+
+    /// Write the plan to this file.
+    #[arg(short, long, value_name = "file", display_order = 0)]
+    output: PathBuf,
+
+    /// Maximum number of jobs to plan.
+    #[arg(short, display_order = 0)]
+    n: Option<usize>,
+}
+
+/// Jobs to submit later with `row submit --plan`.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Plan {
+    /// The cluster to submit the jobs on.
+    pub cluster: String,
+
+    /// The planned jobs in submission order.
+    pub jobs: Vec<PlannedJob>,
+}
+
+/// One job in a `Plan`.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlannedJob {
+    /// The name of the action.
+    pub action: String,
+
+    /// The name of the job.
+    pub job_name: String,
+
+    /// The directories in the group.
+    pub directories: Vec<PathBuf>,
+
+    /// The steps of the action's command.
+    pub command: Vec<String>,
+
+    /// The resources that the job requests.
+    pub resources: PlannedResources,
+
+    /// The selected partitions.
+    pub partitions: Vec<String>,
+
+    /// The maximum cost of the job (for review only).
+    pub cost: String,
+
+    /// The job script (with the redacted submit options masked).
+    pub script: String,
+}
+
+/// The resources that a `PlannedJob` requests.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct PlannedResources {
+    /// The total number of processes.
+    pub processes: usize,
+
+    /// The total number of CPUs.
+    pub cpus: usize,
+
+    /// The total number of GPUs.
+    pub gpus: usize,
+
+    /// The walltime in seconds.
+    pub walltime: i64,
+}
+
+impl PlannedResources {
+    /// Get the resources that `action` requests for `n` directories.
+    pub fn new(action: &Action, n: usize) -> Self {
+        Self {
+            processes: action.resources.total_processes(n),
+            cpus: action.resources.total_cpus(n),
+            gpus: action.resources.total_gpus(n),
+            walltime: action.resources.total_walltime(n).signed_total_seconds(),
+        }
+    }
+}
+
+impl Plan {
+    /// Read a plan from a JSON file.
+    ///
+    /// # Errors
+    /// Returns `Err(row::Error)` when the file cannot be read or parsed.
+    ///
+    pub fn read(path: &Path) -> Result<Self, row::Error> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| row::Error::FileRead(path.into(), e))?;
+        serde_json::from_str(&contents).map_err(|e| row::Error::JSONParse(path.into(), e))
+    }
+
+    /// Write the plan to a JSON file.
+    ///
+    /// # Errors
+    /// Returns `Err(row::Error)` when the file cannot be written.
+    ///
+    pub fn write(&self, path: &Path) -> Result<(), row::Error> {
+        let file = File::create(path).map_err(|e| row::Error::FileWrite(path.into(), e))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, self)
+            .map_err(|e| row::Error::JSONSerialize(path.into(), e))?;
+        writeln!(writer)
+            .and_then(|()| writer.flush())
+            .map_err(|e| row::Error::FileWrite(path.into(), e))
+    }
+}
+
+/// Write the jobs that `row submit` would submit to a plan file.
+///
+pub fn plan<W: Write>(
+    options: &GlobalOptions,
+    args: Arguments,
+    multi_progress: &mut MultiProgressContainer,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    debug!("Planning the jobs to submit.");
+
+    let mut project = Project::open(
+        options.io_threads,
+        &options.cluster,
+        options.workspace.as_deref(),
+        options.queue_timeout,
+        multi_progress,
+    )?;
+
+    if let Some(url) = &project.workflow().workspace.url {
+        return Err(Box::new(row::Error::ReadOnlyWorkspace(url.clone())));
+    }
+
+    let Some((jobs, total_cost)) =
+        submit::prepare_jobs(&project, &args.action, &args.tag, args.directories, args.n)?
+    else {
+        project.close(multi_progress)?;
+        return submit::nothing_to_do(options);
+    };
+
+    if jobs.is_empty() {
+        warn!("There are no eligible jobs to plan.");
+        project.close(multi_progress)?;
+        return submit::nothing_to_do(options);
+    }
+
+    let scheduler = project.scheduler();
+    let mut planned_jobs = Vec::with_capacity(jobs.len());
+    let groups = GroupPosition::number(jobs.iter().map(|(a, _, _)| a.name()));
+    for ((action, job_name, directories), group) in jobs.iter().zip(groups) {
+        planned_jobs.push(PlannedJob {
+            action: action.name().into(),
+            job_name: job_name.clone(),
+            directories: directories.clone(),
+            command: action.commands().to_vec(),
+            resources: PlannedResources::new(action, directories.len()),
+            partitions: scheduler.partitions(action, directories)?,
+            cost: action.resources.cost(directories.len()).to_string(),
+            script: project.workflow().redact(
+                project.cluster_name(),
+                &scheduler.make_script(action, directories, job_name, group)?,
+            ),
+        });
+    }
+
+    let plan = Plan {
+        cluster: project.cluster_name().into(),
+        jobs: planned_jobs,
+    };
+    plan.write(&args.output)?;
+
+    project.close(multi_progress)?;
+
+    if options.quiet {
+        return Ok(());
+    }
+
+    writeln!(
+        output,
+        "Planned {} that may cost up to {} in '{}'.",
+        style(format!(
+            "{} {}",
+            HumanCount(jobs.len() as u64),
+            if jobs.len() == 1 { "job" } else { "jobs" }
+        ))
+        .yellow()
+        .bold(),
+        style(total_cost).cyan().bold(),
+        args.output.display()
+    )?;
+    writeln!(
+        output,
+        "Review the plan, then execute `row submit --plan {}`.",
+        args.output.display()
+    )?;
+    output.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,182 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use clap::Args;
+use console::Style;
+use log::{debug, trace, warn};
+use serde_json::json;
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+use wildmatch::WildMatch;
+
+use crate::cli::GlobalOptions;
+use crate::ui::{Alignment, Format, Item, Row, Table};
+use row::project::{Progress, Project};
+use row::workflow::Action;
+use row::MultiProgressContainer;
+
+#[derive(Args, Debug)]
+pub struct Arguments {
+    /// Select the actions to include with a wildcard pattern.
+    #[arg(short, long, value_name = "pattern", default_value_t=String::from("*"), display_order=0)]
+    action: String,
+
+    /// Select the actions with the given tag (repeat to select any of several tags).
+    #[arg(long, value_name = "tag", display_order = 0)]
+    tag: Vec<String>,
+
+    /// Show the progress of each group of directories with the same value at this JSON pointer.
+    #[arg(long, value_name = "pointer", display_order = 0)]
+    by: Option<String>,
+
+    /// Hide the table header.
+    #[arg(long, display_order = 0)]
+    no_header: bool,
+
+    /// Write to a file instead of stdout. The extension selects the format: '.csv', '.tsv', '.json', or text.
+    #[arg(long, value_name = "file", display_order = 0)]
+    pub output: Option<PathBuf>,
+}
+
+/// Format one row of the progress table.
+fn make_row(key: Option<Item>, progress: &Progress) -> Vec<Item> {
+    let mut result = Vec::with_capacity(5);
+    result.extend(key);
+    result.push(
+        Item::new(progress.directories.to_string(), Style::new())
+            .with_value(json!(progress.directories))
+            .with_alignment(Alignment::Right),
+    );
+    result.push(
+        Item::new(format!("{}", progress.completed_cost), Style::new().green())
+            .with_alignment(Alignment::Right),
+    );
+    result.push(
+        Item::new(
+            format!("{}", progress.total_cost),
+            Style::new().italic().dim(),
+        )
+        .with_alignment(Alignment::Right),
+    );
+    result.push(
+        match progress.fraction() {
+            Some(fraction) => Item::new(format!("{:.1}%", fraction * 100.0), Style::new().bold())
+                .with_value(json!(fraction * 100.0)),
+            None => Item::new("-".to_string(), Style::new()).with_value(json!(null)),
+        }
+        .with_alignment(Alignment::Right),
+    );
+
+    result
+}
+
+/// Show the weighted progress of the workflow.
+///
+/// Print the completed fraction of the selected actions, weighted by the
+/// estimated cost of each action.
+///
+pub fn progress<W: Write>(
+    options: &GlobalOptions,
+    args: &Arguments,
+    multi_progress: &mut MultiProgressContainer,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    debug!("Showing the workflow's progress.");
+
+    if let Some(pointer) = &args.by {
+        if !pointer.is_empty() && !pointer.starts_with('/') {
+            warn!("The JSON pointer '{pointer}' does not appear valid. Did you mean '/{pointer}'?");
+        }
+    }
+
+    let mut project = Project::open_offline(
+        options.io_threads,
+        &options.cluster,
+        options.workspace.as_deref(),
+        multi_progress,
+    )?;
+
+    let action_matcher = WildMatch::new(&args.action);
+    let actions: Vec<&Action> = project
+        .workflow()
+        .action
+        .iter()
+        .filter(|action| {
+            let selected = action_matcher.matches(action.name()) && action.has_any_tag(&args.tag);
+            if !selected {
+                trace!("Skipping action '{}'.", action.name());
+            }
+            selected
+        })
+        .collect();
+
+    if actions.is_empty() {
+        if args.tag.is_empty() {
+            warn!("No actions match '{}'.", args.action);
+        } else {
+            warn!(
+                "No actions match '{}' with any of the tags {:?}.",
+                args.action, args.tag
+            );
+        }
+        project.close(multi_progress)?;
+        return Ok(());
+    }
+
+    let directories = project.state().list_directories();
+    let total = project.progress(&actions, directories.clone(), None)?;
+
+    let mut table = Table::new()
+        .with_hide_header(args.no_header)
+        .with_format(Format::from_path(args.output.as_deref()));
+    let underlined = Style::new().underlined();
+    if let Some(pointer) = &args.by {
+        table
+            .header
+            .push(Item::new(pointer.clone(), underlined.clone()));
+    }
+    table.header.extend([
+        Item::new("Directories".to_string(), underlined.clone()).with_alignment(Alignment::Right),
+        Item::new("Completed cost".to_string(), underlined.clone())
+            .with_alignment(Alignment::Right),
+        Item::new("Total cost".to_string(), underlined.clone()).with_alignment(Alignment::Right),
+        Item::new("Progress".to_string(), underlined.clone()).with_alignment(Alignment::Right),
+    ]);
+
+    if let Some(pointer) = &args.by {
+        // The cache may hold only some elements of each value.
+        let uncached_values = project.read_uncached_values(
+            std::slice::from_ref(pointer),
+            directories.clone(),
+            project.io_threads(),
+            multi_progress,
+        )?;
+        let values = uncached_values
+            .as_ref()
+            .unwrap_or_else(|| project.state().values());
+
+        for (key, progress) in project.progress(&actions, directories, Some((pointer, values)))? {
+            let key = key.expect("Progress is separated by key");
+            table.rows.push(Row::Items(make_row(
+                Some(Item::new(key.to_string(), Style::new().bold()).with_value(key)),
+                &progress,
+            )));
+        }
+        table.rows.push(Row::Separator);
+    }
+
+    for (_, progress) in &total {
+        let key = args
+            .by
+            .as_ref()
+            .map(|_| Item::new("All".to_string(), Style::new().bold().italic()));
+        table.rows.push(Row::Items(make_row(key, progress)));
+    }
+
+    table.write(output)?;
+    output.flush()?;
+
+    project.close(multi_progress)?;
+    Ok(())
+}
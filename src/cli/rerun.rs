@@ -0,0 +1,181 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use clap::Args;
+use console::style;
+use indicatif::HumanCount;
+use log::{debug, info, trace, warn};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::PathBuf;
+
+use crate::cli::{self, submit, GlobalOptions};
+use row::project::Project;
+use row::MultiProgressContainer;
+
+#[derive(Args, Debug)]
+pub struct Arguments {
+    /// The action to rerun.
+    #[arg(short, long, display_order = 0)]
+    action: String,
+
+    /// Select directories to rerun (defaults to all). Use 'rerun -' to read from stdin.
+    directories: Vec<PathBuf>,
+
+    /// Skip confirmation check.
+    #[arg(long, display_order = 0, env = "ROW_YES", hide_env = true)]
+    yes: bool,
+
+    /// Print the product files instead of removing them.
+    #[arg(long, display_order = 0)]
+    dry_run: bool,
+
+    /// Submit the selected directories after removing their products.
+    #[arg(long, display_order = 0)]
+    submit: bool,
+}
+
+/// Remove an action's products so that it can be executed again.
+///
+/// Remove the product files in the selected directories, remove the
+/// directories from the completed cache, and optionally submit them.
+///
+#[allow(clippy::too_many_lines)]
+pub fn rerun<W: Write>(
+    options: &GlobalOptions,
+    args: Arguments,
+    multi_progress: &mut MultiProgressContainer,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    debug!("Preparing to rerun action '{}'.", args.action);
+
+    let mut project = Project::open(
+        options.io_threads,
+        &options.cluster,
+        options.workspace.as_deref(),
+        options.queue_timeout,
+        multi_progress,
+    )?;
+
+    if let Some(url) = &project.workflow().workspace.url {
+        return Err(Box::new(row::Error::ReadOnlyWorkspace(url.clone())));
+    }
+
+    let query_directories = cli::parse_directories(args.directories, project.aliases(), || {
+        Ok(project.state().list_directories())
+    })?;
+
+    let action = project
+        .workflow()
+        .action_by_name(&args.action)
+        .ok_or_else(|| project.workflow().action_not_found(&args.action))?
+        .clone();
+
+    let matching_directories = project.find_matching_directories(&action, query_directories)?;
+
+    let mut directories = Vec::with_capacity(matching_directories.len());
+    for directory in matching_directories {
+        if project.state().is_submitted(action.name(), &directory) {
+            warn!(
+                "Skipping '{}': it has been submitted for action '{}'.",
+                directory.display(),
+                action.name()
+            );
+        } else {
+            directories.push(directory);
+        }
+    }
+
+    let workspace_path = project
+        .workflow()
+        .root
+        .join(&project.workflow().workspace.path);
+    let mut products = Vec::new();
+    for directory in &directories {
+        let value = project
+            .state()
+            .values()
+            .get(directory)
+            .cloned()
+            .unwrap_or_default();
+        for product in action.product_names(directory, &value)? {
+            let path = workspace_path.join(directory).join(product);
+            if path.symlink_metadata().is_ok() {
+                products.push(path);
+            }
+        }
+    }
+
+    if directories.is_empty() {
+        warn!("There are no directories to rerun.");
+        project.close(multi_progress)?;
+        return Ok(());
+    }
+
+    if args.dry_run {
+        info!("Execute without --dry-run to remove the following files...");
+        for path in &products {
+            writeln!(output, "{}", path.display())?;
+        }
+        output.flush()?;
+        project.close(multi_progress)?;
+        return Ok(());
+    }
+
+    writeln!(
+        output,
+        "Removing {} from {} for action '{}'.",
+        style(format!(
+            "{} product files",
+            HumanCount(products.len() as u64)
+        ))
+        .yellow()
+        .bold(),
+        style(format!(
+            "{} directories",
+            HumanCount(directories.len() as u64)
+        ))
+        .bold(),
+        style(action.name()).blue(),
+    )?;
+    output.flush()?;
+
+    if !args.yes && !cli::confirm(multi_progress, "rerun")? {
+        warn!("Cancelling rerun.");
+        return Ok(());
+    }
+
+    for path in &products {
+        trace!("Removing '{}'.", path.display());
+        let result = if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        };
+
+        if let Err(error) = result {
+            if error.kind() != io::ErrorKind::NotFound {
+                // Some products may already be removed. Save the completed cache.
+                project.remove_completed(action.name(), &directories);
+                project.close(multi_progress)?;
+                return Err(Box::new(row::Error::FileRemove(path.clone(), error)));
+            }
+        }
+    }
+
+    project.remove_completed(action.name(), &directories);
+    project.close(multi_progress)?;
+
+    if args.submit {
+        submit::submit(
+            options,
+            submit::Arguments::new(action.name(), directories, args.yes),
+            multi_progress,
+            output,
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,160 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use clap::Args;
+use console::style;
+use indicatif::HumanCount;
+use log::{debug, info, warn};
+use std::collections::HashSet;
+use std::error::Error;
+use std::io::prelude::*;
+use std::path::PathBuf;
+
+use crate::cli::{self, GlobalOptions};
+use row::alias::Aliases;
+use row::project::Project;
+use row::MultiProgressContainer;
+
+#[derive(Args, Debug)]
+pub struct Arguments {
+    /// The directories to remove. Use 'rm -' to read from stdin.
+    #[arg(required = true)]
+    directories: Vec<PathBuf>,
+
+    /// Remove directories even when they are submitted.
+    #[arg(long, display_order = 0)]
+    force: bool,
+
+    /// Skip confirmation check. Required when reading directories from stdin.
+    #[arg(long, display_order = 0, env = "ROW_YES", hide_env = true)]
+    yes: bool,
+
+    /// Print the directories instead of removing them.
+    #[arg(long, display_order = 0)]
+    dry_run: bool,
+}
+
+/// Remove directories from the workspace.
+///
+/// Remove the directories (and all their contents) on disk and update the
+/// caches and aliases.
+///
+pub fn rm<W: Write>(
+    options: &GlobalOptions,
+    args: Arguments,
+    multi_progress: &mut MultiProgressContainer,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    debug!("Removing directories from the workspace.");
+
+    let mut project = Project::open(
+        options.io_threads,
+        &options.cluster,
+        options.workspace.as_deref(),
+        options.queue_timeout,
+        multi_progress,
+    )?;
+
+    if let Some(url) = &project.workflow().workspace.url {
+        return Err(Box::new(row::Error::ReadOnlyWorkspace(url.clone())));
+    }
+
+    let from_stdin = args.directories == [PathBuf::from("-")];
+    let directories =
+        cli::parse_directories(args.directories, project.aliases(), || Ok(Vec::new()))?;
+
+    for directory in &directories {
+        if !project.state().values().contains_key(directory) {
+            return Err(Box::new(
+                project
+                    .state()
+                    .values()
+                    .directory_not_found(directory.clone()),
+            ));
+        }
+
+        for action in &project.workflow().action {
+            if project.state().is_submitted(action.name(), directory) {
+                if args.force {
+                    warn!(
+                        "Removing '{}', which is submitted for action '{}'.",
+                        directory.display(),
+                        action.name()
+                    );
+                } else {
+                    return Err(Box::new(row::Error::DirectorySubmitted(
+                        directory.clone(),
+                        action.name().into(),
+                    )));
+                }
+            }
+        }
+    }
+
+    if args.dry_run {
+        info!("Execute without --dry-run to remove the following directories...");
+        for directory in &directories {
+            writeln!(output, "{}", directory.display())?;
+        }
+        output.flush()?;
+        project.close(multi_progress)?;
+        return Ok(());
+    }
+
+    writeln!(
+        output,
+        "Removing {} and all their contents.",
+        style(format!(
+            "{} {}",
+            HumanCount(directories.len() as u64),
+            if directories.len() == 1 {
+                "directory"
+            } else {
+                "directories"
+            }
+        ))
+        .yellow()
+        .bold(),
+    )?;
+    output.flush()?;
+
+    if !args.yes {
+        if from_stdin {
+            return Err(Box::new(row::Error::ConfirmationRequired(
+                "removal".into(),
+                "reading directories from stdin".into(),
+            )));
+        }
+        if !cli::confirm(multi_progress, "removal")? {
+            warn!("Cancelling removal.");
+            project.close(multi_progress)?;
+            return Ok(());
+        }
+    }
+
+    let result = project.remove_directories(&directories);
+
+    // Remove the aliases of the removed directories.
+    let mut aliases = Aliases::open(project.workflow())?;
+    let removed: HashSet<&PathBuf> = directories
+        .iter()
+        .filter(|d| !project.state().values().contains_key(d))
+        .collect();
+    let orphaned: Vec<String> = aliases
+        .iter()
+        .filter(|(_, d)| removed.contains(d))
+        .map(|(alias, _)| alias.clone())
+        .collect();
+    if !orphaned.is_empty() {
+        for alias in orphaned {
+            info!("Removing alias '{alias}'.");
+            aliases.remove(&alias);
+        }
+        aliases.save(project.workflow())?;
+    }
+
+    project.close(multi_progress)?;
+    result?;
+
+    Ok(())
+}
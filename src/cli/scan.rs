@@ -3,16 +3,17 @@
 
 use clap::Args;
 use log::{debug, info, trace, warn};
-use postcard;
-use std::fs::{self, File};
-use std::io::prelude::*;
+use std::collections::HashSet;
 use std::path::PathBuf;
-use uuid::Uuid;
 
 use crate::cli::{self, GlobalOptions};
+use row::alias::Aliases;
+use row::cluster;
+use row::state::{self, Completion, CompletionPack, Completions};
 use row::workflow::Workflow;
 use row::{
-    workspace, Error, MultiProgressContainer, COMPLETED_DIRECTORY_NAME, DATA_DIRECTORY_NAME,
+    workspace, Error, MultiProgressContainer, COMPLETED_DIRECTORY_NAME, COMPLETED_PACK_EXTENSION,
+    PENDING_PACK_EXTENSION,
 };
 
 #[derive(Args, Debug)]
@@ -23,6 +24,12 @@ pub struct Arguments {
 
     /// Select directories to scan (defaults to all). Use 'scan -' to read from stdin.
     directories: Vec<PathBuf>,
+
+    /// Stage the directories without checking their products.
+    ///
+    /// The next command that synchronizes the project checks the products.
+    #[arg(long, display_order = 0, requires = "directories")]
+    pack_only: bool,
 }
 
 /// Scan directories and determine whether a given action (or all actions) have completed.
@@ -36,22 +43,38 @@ pub fn scan(
 ) -> Result<(), Box<dyn std::error::Error>> {
     debug!("Scanning the workspace for completed actions.");
 
-    let workflow = Workflow::open()?;
+    let workflow = Workflow::open_with_workspace(options.workspace.as_deref())?;
 
-    let query_directories = cli::parse_directories(args.directories, || {
-        workspace::list_directories(&workflow, multi_progress)
+    let aliases = Aliases::open(&workflow)?;
+    let query_directories = cli::parse_directories(args.directories, &aliases, || {
+        workspace::list_directories(
+            &workflow,
+            workflow.io_threads(options.io_threads),
+            multi_progress,
+        )
     })?;
 
-    let mut complete = workspace::find_completed_directories(
-        &workflow,
-        query_directories,
-        options.io_threads,
-        multi_progress,
-    )
-    .get()?;
+    let mut complete = if args.pack_only {
+        // Claim that every action completed. Synchronization keeps only the
+        // directories with products.
+        let directories: HashSet<PathBuf> = query_directories.into_iter().collect();
+        workflow
+            .action
+            .iter()
+            .map(|action| (action.name().to_string(), directories.clone()))
+            .collect()
+    } else {
+        workspace::find_completed_directories(
+            &workflow,
+            query_directories,
+            workflow.io_threads(options.io_threads),
+            multi_progress,
+        )?
+        .get()?
+    };
 
     let mut matching_action_count = 0;
-    for action in workflow.action {
+    for action in &workflow.action {
         if let Some(selection) = args.action.as_ref() {
             if selection != action.name() {
                 complete.remove(action.name());
@@ -76,50 +99,62 @@ pub fn scan(
         return Ok(());
     }
 
-    debug!("Serializing completed actions.");
-    let bytes = postcard::to_stdvec(&complete)
-        .map_err(|e| Error::PostcardSerialize("completed".into(), e))?;
-
-    let id = Uuid::new_v4();
-
-    let complete_directory = workflow
-        .root
-        .join(DATA_DIRECTORY_NAME)
-        .join(COMPLETED_DIRECTORY_NAME);
-    let filename = complete_directory
-        .join(id.simple().to_string())
-        .with_extension("postcard");
-    let tmp_filename = filename.with_extension("tmp");
-
-    fs::create_dir_all(&complete_directory)
-        .map_err(|e| Error::DirectoryCreate(complete_directory, e))?;
-
-    trace!(
-        "Writing {} bytes to '{}'.",
-        bytes.len(),
-        tmp_filename.display().to_string()
-    );
-    let mut file =
-        File::create_new(&tmp_filename).map_err(|e| Error::FileWrite(tmp_filename.clone(), e))?;
-    file.write_all(&bytes)
-        .map_err(|e| Error::FileWrite(tmp_filename.clone(), e))?;
-    file.sync_all()
-        .map_err(|e| Error::FileWrite(tmp_filename.clone(), e))?;
-    drop(file);
-
-    fs::rename(&tmp_filename, &filename).map_err(|e| Error::FileWrite(filename, e))?;
-
-    for (action, completed_directories) in complete {
-        let word = if completed_directories.len() == 1 {
+    let completion = match cluster::Configuration::open()
+        .and_then(|clusters| clusters.identify(options.cluster.as_deref()))
+    {
+        Ok(cluster) => Completion::now(&cluster.name),
+        Err(error) => {
+            debug!("Not recording the cluster name: {error}");
+            Completion {
+                cluster: None,
+                ..Completion::now("")
+            }
+        }
+    };
+    let complete = state::record_completions(complete, &completion);
+    let counts: Vec<(String, usize)> = complete
+        .iter()
+        .map(|(action, directories)| (action.clone(), directories.len()))
+        .collect();
+
+    write_pack(
+        &workflow,
+        args.action,
+        complete,
+        if args.pack_only {
+            PENDING_PACK_EXTENSION
+        } else {
+            COMPLETED_PACK_EXTENSION
+        },
+    )?;
+
+    for (action, count) in counts {
+        let word = if count == 1 {
             "directory"
         } else {
             "directories"
         };
-        info!(
-            "Found {} completed {word} for action '{action}'.",
-            completed_directories.len()
-        );
+        if args.pack_only {
+            info!("Staged {count} {word} to check for action '{action}'.");
+        } else {
+            info!("Found {count} completed {word} for action '{action}'.");
+        }
     }
 
     Ok(())
 }
+
+/// Write completions to a new pack file with the given extension.
+fn write_pack(
+    workflow: &Workflow,
+    action: Option<String>,
+    complete: Completions,
+    extension: &str,
+) -> Result<(), Error> {
+    debug!("Serializing completed actions.");
+    let complete_directory = workflow.data_directory().join(COMPLETED_DIRECTORY_NAME);
+
+    let path = CompletionPack::new(action, complete).write(&complete_directory, extension)?;
+    debug!("Wrote '{}'.", path.display());
+    Ok(())
+}
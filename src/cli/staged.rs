@@ -0,0 +1,111 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use clap::Args;
+use console::Style;
+use log::{debug, info};
+use std::collections::HashSet;
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::cli::GlobalOptions;
+use crate::ui::{Alignment, Format, Item, Row, Table};
+use row::format::UtcTimestamp;
+use row::state::{self, State};
+use row::workflow::Workflow;
+
+#[derive(Args, Debug)]
+pub struct Arguments {
+    /// Hide the table header.
+    #[arg(long, display_order = 0)]
+    no_header: bool,
+
+    /// Write to a file instead of stdout. The extension selects the format: '.csv', '.tsv', '.json', or text.
+    #[arg(long, value_name = "file", display_order = 0)]
+    pub output: Option<PathBuf>,
+}
+
+/// Show the completion packs that have not yet been synchronized.
+///
+/// Print a table with one row per pack file.
+///
+pub fn staged<W: Write>(
+    options: &GlobalOptions,
+    args: &Arguments,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    debug!("Showing staged completion packs.");
+
+    let workflow = Workflow::open_with_workspace(options.workspace.as_deref())?;
+    let staged = State::read_staged(&workflow)?;
+
+    if staged.is_empty() {
+        info!("There are no staged completion packs.");
+        return Ok(());
+    }
+
+    let mut table = Table::new()
+        .with_hide_header(args.no_header)
+        .with_format(Format::from_path(args.output.as_deref()));
+    let underlined = Style::new().underlined();
+    table.header = vec![
+        Item::new("File".to_string(), underlined.clone()),
+        Item::new("Kind".to_string(), underlined.clone()),
+        Item::new("Action".to_string(), underlined.clone()),
+        Item::new("Job".to_string(), underlined.clone()),
+        Item::new("Host".to_string(), underlined.clone()),
+        Item::new("PID".to_string(), underlined.clone()).with_alignment(Alignment::Right),
+        Item::new("Directories".to_string(), underlined.clone()).with_alignment(Alignment::Right),
+        Item::new("Time".to_string(), underlined.clone()),
+    ];
+
+    for (path, pack) in staged {
+        let directories: HashSet<&PathBuf> = pack
+            .completions
+            .values()
+            .flat_map(|directories| directories.keys())
+            .collect();
+
+        table.rows.push(Row::Items(vec![
+            Item::new(
+                path.file_name()
+                    .unwrap_or(path.as_os_str())
+                    .to_string_lossy()
+                    .to_string(),
+                Style::new().bold(),
+            ),
+            Item::new(
+                if state::is_pending_pack(&path) {
+                    "pending".to_string()
+                } else {
+                    "completed".to_string()
+                },
+                Style::new(),
+            ),
+            Item::new(
+                pack.action.unwrap_or_else(|| "all".to_string()),
+                Style::new(),
+            ),
+            Item::new(pack.job_id.unwrap_or_default(), Style::new()),
+            Item::new(pack.hostname.unwrap_or_default(), Style::new()),
+            Item::new(
+                pack.pid.map(|pid| pid.to_string()).unwrap_or_default(),
+                Style::new(),
+            )
+            .with_alignment(Alignment::Right),
+            Item::new(directories.len().to_string(), Style::new()).with_alignment(Alignment::Right),
+            Item::new(
+                pack.time
+                    .map(|time| UtcTimestamp(time).to_string())
+                    .unwrap_or_default(),
+                Style::new().italic(),
+            ),
+        ]));
+    }
+
+    table.write(output)?;
+    output.flush()?;
+
+    Ok(())
+}
@@ -2,20 +2,29 @@
 // Part of row, released under the BSD 3-Clause License.
 
 use clap::Args;
-use console::Style;
+use console::{Style, Term};
 use indicatif::HumanCount;
 use log::{debug, trace, warn};
+use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::error::Error;
 use std::io::Write;
 use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use wildmatch::WildMatch;
 
 use crate::cli::{self, GlobalOptions};
-use crate::ui::{Alignment, Item, Row, Table};
+use crate::ui::{Alignment, Format, Item, Row, Table};
+use row::alias::Aliases;
+use row::format::UtcTimestamp;
 use row::project::{Project, Status};
 use row::workflow::ResourceCost;
 use row::MultiProgressContainer;
 
+/// Number of completed, submitted, eligible, and waiting directories for each action.
+type Counts = HashMap<String, [usize; 4]>;
+
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Args, Debug)]
 pub struct Arguments {
@@ -23,6 +32,10 @@ pub struct Arguments {
     #[arg(short, long, value_name = "pattern", default_value_t=String::from("*"), display_order=0)]
     action: String,
 
+    /// Select the actions with the given tag (repeat to select any of several tags).
+    #[arg(long, value_name = "tag", display_order = 0)]
+    tag: Vec<String>,
+
     /// Hide the table header.
     #[arg(long, display_order = 0)]
     no_header: bool,
@@ -49,40 +62,76 @@ pub struct Arguments {
     /// Show all actions.
     #[arg(long, display_order = 0)]
     all: bool,
+
+    /// Hide actions whose selected directories are all completed.
+    #[arg(long, display_order = 0, env = "ROW_HIDE_COMPLETE", hide_env = true)]
+    hide_complete: bool,
+
+    /// Redraw the status every N seconds (defaults to 10) until interrupted.
+    #[arg(
+        long,
+        value_name = "seconds",
+        num_args = 0..=1,
+        default_missing_value = "10",
+        value_parser = clap::value_parser!(u64).range(1..),
+        display_order = 0
+    )]
+    watch: Option<u64>,
+
+    /// Write to a file instead of stdout. The extension selects the format: '.csv', '.tsv', '.json', or text.
+    #[arg(long, value_name = "file", display_order = 0, conflicts_with = "watch")]
+    pub output: Option<PathBuf>,
+
+    #[command(flatten)]
+    table: cli::TableArguments,
+
+    /// Do not query the scheduler for the status of submitted jobs.
+    #[arg(long, display_order = 0)]
+    offline: bool,
+
+    /// Print one tab-separated line per action: name, completed, submitted, eligible, and waiting.
+    #[arg(long, display_order = 0, conflicts_with_all = ["watch", "output"])]
+    pub short: bool,
+}
+
+/// Format a count and its change since the previous refresh.
+fn make_count(count: usize, previous: Option<usize>, style: Style) -> Item {
+    match previous {
+        Some(previous) if previous != count => Item::new(
+            if count > previous {
+                format!("{} (+{})", HumanCount(count as u64), count - previous)
+            } else {
+                format!("{} (-{})", HumanCount(count as u64), previous - count)
+            },
+            style.reverse(),
+        ),
+        _ => Item::new(HumanCount(count as u64).to_string(), style),
+    }
+    .with_alignment(Alignment::Right)
 }
 
 /// Format a status string for non-terminal outputs.
-fn make_row(action_name: &str, status: &Status, cost: &ResourceCost) -> Vec<Item> {
+///
+/// Highlight the counts that differ from `previous`.
+///
+fn make_row(
+    action_name: &str,
+    counts: [usize; 4],
+    previous: Option<[usize; 4]>,
+    cost: &ResourceCost,
+) -> Vec<Item> {
+    let styles = [
+        Style::new().green().bold(),
+        Style::new().yellow().bold(),
+        Style::new().blue(),
+        Style::new().cyan().dim(),
+    ];
+
     let mut result = Vec::with_capacity(6);
     result.push(Item::new(action_name.to_string(), Style::new().bold()));
-    result.push(
-        Item::new(
-            HumanCount(status.completed.len() as u64).to_string(),
-            Style::new().green().bold(),
-        )
-        .with_alignment(Alignment::Right),
-    );
-    result.push(
-        Item::new(
-            HumanCount(status.submitted.len() as u64).to_string(),
-            Style::new().yellow().bold(),
-        )
-        .with_alignment(Alignment::Right),
-    );
-    result.push(
-        Item::new(
-            HumanCount(status.eligible.len() as u64).to_string(),
-            Style::new().blue(),
-        )
-        .with_alignment(Alignment::Right),
-    );
-    result.push(
-        Item::new(
-            HumanCount(status.waiting.len() as u64).to_string(),
-            Style::new().cyan().dim(),
-        )
-        .with_alignment(Alignment::Right),
-    );
+    for (i, style) in styles.into_iter().enumerate() {
+        result.push(make_count(counts[i], previous.map(|p| p[i]), style));
+    }
 
     if !cost.is_zero() {
         result.push(
@@ -94,18 +143,118 @@ fn make_row(action_name: &str, status: &Status, cost: &ResourceCost) -> Vec<Item
     result
 }
 
+/// List the job ID and cluster of each submitted directory.
+///
+/// The item's text is the list in compact JSON.
+///
+fn make_jobs(project: &Project, action_name: &str, submitted: &[PathBuf]) -> Item {
+    let jobs = project.state().submitted().get(action_name);
+    let value = Value::Array(
+        submitted
+            .iter()
+            .filter_map(|directory| {
+                let (cluster, job_id) = jobs?.get(directory)?;
+                Some(json!({
+                    "directory": directory,
+                    "job_id": job_id,
+                    "cluster": cluster,
+                }))
+            })
+            .collect(),
+    );
+
+    Item::new(value.to_string(), Style::new()).with_value(value)
+}
+
+/// Count the directories in each status.
+fn count(status: &Status) -> [usize; 4] {
+    [
+        status.completed.len(),
+        status.submitted.len(),
+        status.eligible.len(),
+        status.waiting.len(),
+    ]
+}
+
 /// Show the current state of the workflow.
 ///
 /// Print a human-readable summary of the workflow.
 ///
 pub fn status<W: Write>(
     options: &GlobalOptions,
-    args: Arguments,
+    args: &Arguments,
     multi_progress: &mut MultiProgressContainer,
     output: &mut W,
 ) -> Result<(), Box<dyn Error>> {
     debug!("Showing the workflow's status.");
 
+    // Read directories from stdin only once. Resolve aliases after opening the project.
+    let directories = if args.directories.is_empty() {
+        None
+    } else {
+        Some(cli::parse_directories(
+            args.directories.clone(),
+            &Aliases::default(),
+            || Ok(Vec::new()),
+        )?)
+    };
+
+    let Some(interval) = args.watch else {
+        write_status(options, args, directories, None, multi_progress, output)?;
+        return Ok(());
+    };
+
+    // Append each refresh when stdout is redirected to a file or pipe.
+    let term = Term::stdout();
+    let clear = term.is_term();
+    let mut previous = None;
+    loop {
+        // Render to a buffer first so that the screen is not blank while row
+        // synchronizes the project.
+        let mut buffer = Vec::new();
+        let counts = write_status(
+            options,
+            args,
+            directories.clone(),
+            previous.as_ref(),
+            multi_progress,
+            &mut buffer,
+        )?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        if clear {
+            term.clear_screen()?;
+        }
+        writeln!(
+            output,
+            "Every {interval}s: row show status ({})\n",
+            UtcTimestamp(now)
+        )?;
+        output.write_all(&buffer)?;
+        output.flush()?;
+
+        previous = Some(counts);
+        thread::sleep(Duration::from_secs(interval));
+    }
+}
+
+/// Write the status table once.
+///
+/// # Returns
+/// The counts of directories in each status, to compare with the next refresh.
+///
+#[allow(clippy::too_many_lines)]
+fn write_status<W: Write>(
+    options: &GlobalOptions,
+    args: &Arguments,
+    directories: Option<Vec<PathBuf>>,
+    previous: Option<&Counts>,
+    multi_progress: &mut MultiProgressContainer,
+    output: &mut W,
+) -> Result<Counts, Box<dyn Error>> {
     // Show directories with selected statuses.
     let mut show_completed = args.completed;
     let mut show_submitted = args.submitted;
@@ -120,22 +269,66 @@ pub fn status<W: Write>(
 
     let action_matcher = WildMatch::new(&args.action);
 
-    let mut project = Project::open(options.io_threads, &options.cluster, multi_progress)?;
+    let mut project = if args.offline {
+        Project::open_offline(
+            options.io_threads,
+            &options.cluster,
+            options.workspace.as_deref(),
+            multi_progress,
+        )?
+    } else {
+        Project::open(
+            options.io_threads,
+            &options.cluster,
+            options.workspace.as_deref(),
+            options.queue_timeout,
+            multi_progress,
+        )?
+    };
 
-    let query_directories =
-        cli::parse_directories(args.directories, || Ok(project.state().list_directories()))?;
+    let query_directories = match directories {
+        Some(directories) => directories
+            .into_iter()
+            .map(|d| project.aliases().resolve(d))
+            .collect(),
+        None => project.state().list_directories(),
+    };
 
-    let mut table = Table::new().with_hide_header(args.no_header);
+    let mut counts = Counts::new();
+    let format = Format::from_path(args.output.as_deref());
+    // Structured formats also list the submitted jobs so that other tools can
+    // link to them.
+    let show_jobs = matches!(format, Format::Csv | Format::Tsv | Format::Json);
+    let mut table = args.table.apply(
+        Table::new()
+            .with_hide_header(args.no_header)
+            .with_format(format),
+        args.output.as_deref(),
+    );
     let underlined = Style::new().underlined();
     table.header = vec![
         Item::new("Action".to_string(), underlined.clone()),
         Item::new("Completed".to_string(), underlined.clone()).with_alignment(Alignment::Right),
-        Item::new("Submitted".to_string(), underlined.clone()).with_alignment(Alignment::Right),
+        // The submitted jobs may have finished when the queue was not queried.
+        Item::new(
+            if project.is_offline() {
+                "Unknown".to_string()
+            } else {
+                "Submitted".to_string()
+            },
+            underlined.clone(),
+        )
+        .with_alignment(Alignment::Right),
         Item::new("Eligible".to_string(), underlined.clone()).with_alignment(Alignment::Right),
         Item::new("Waiting".to_string(), underlined.clone()).with_alignment(Alignment::Right),
         Item::new("Remaining cost".to_string(), underlined.clone())
             .with_alignment(Alignment::Right),
     ];
+    if show_jobs {
+        table
+            .header
+            .push(Item::new("Submitted jobs".to_string(), underlined.clone()));
+    }
 
     let mut matching_action_count = 0;
     for action in &project.workflow().action {
@@ -148,6 +341,15 @@ pub fn status<W: Write>(
             continue;
         }
 
+        if !action.has_any_tag(&args.tag) {
+            trace!(
+                "Skipping action '{}'. It does not have any of the tags {:?}.",
+                action.name(),
+                args.tag
+            );
+            continue;
+        }
+
         matching_action_count += 1;
 
         let matching_directories =
@@ -155,39 +357,71 @@ pub fn status<W: Write>(
 
         let status = project.separate_by_status(action, matching_directories)?;
 
-        let mut combined_directories = Vec::with_capacity(
-            status.submitted.len() + status.eligible.len() + status.waiting.len(),
-        );
-        combined_directories.extend(status.submitted.clone());
-        combined_directories.extend(status.eligible.clone());
-        combined_directories.extend(status.waiting.clone());
-
-        let groups = project.separate_into_groups(action, combined_directories.clone())?;
-        let mut cost = ResourceCost::new();
-        for group in groups {
-            cost = cost + action.resources.cost(group.len());
-        }
+        let complete =
+            status.submitted.is_empty() && status.eligible.is_empty() && status.waiting.is_empty();
+        let show = args.all
+            || (!(complete && args.hide_complete)
+                && ((!status.completed.is_empty() && show_completed)
+                    || (!status.submitted.is_empty() && show_submitted)
+                    || (!status.eligible.is_empty() && show_eligible)
+                    || (!status.waiting.is_empty() && show_waiting)));
 
-        if args.all
-            || (!status.completed.is_empty() && show_completed)
-            || (!status.submitted.is_empty() && show_submitted)
-            || (!status.eligible.is_empty() && show_eligible)
-            || (!status.waiting.is_empty() && show_waiting)
-        {
-            table
-                .rows
-                .push(Row::Items(make_row(action.name(), &status, &cost)));
+        if show && args.short {
+            let [completed, submitted, eligible, waiting] = count(&status);
+            writeln!(
+                output,
+                "{}\t{completed}\t{submitted}\t{eligible}\t{waiting}",
+                action.name()
+            )?;
+        } else if show {
+            let mut combined_directories = Vec::with_capacity(
+                status.submitted.len() + status.eligible.len() + status.waiting.len(),
+            );
+            combined_directories.extend(status.submitted.clone());
+            combined_directories.extend(status.eligible.clone());
+            combined_directories.extend(status.waiting.clone());
+
+            let groups = project.separate_into_groups(action, combined_directories)?;
+            let mut cost = ResourceCost::new();
+            for group in groups {
+                cost = cost + action.resources.cost(group.len());
+            }
+
+            let mut row = make_row(
+                action.name(),
+                count(&status),
+                previous.map(|p| p.get(action.name()).copied().unwrap_or_default()),
+                &cost,
+            );
+            if show_jobs {
+                row.resize_with(table.header.len() - 1, || {
+                    Item::new(String::new(), Style::new())
+                });
+                row.push(make_jobs(&project, action.name(), &status.submitted));
+            }
+            table.rows.push(Row::Items(row));
         }
+
+        counts.insert(action.name().into(), count(&status));
     }
 
     if matching_action_count == 0 {
-        warn!("No actions match '{}'.", args.action);
+        if args.tag.is_empty() {
+            warn!("No actions match '{}'.", args.action);
+        } else {
+            warn!(
+                "No actions match '{}' with any of the tags {:?}.",
+                args.action, args.tag
+            );
+        }
     } else {
-        table.write(output)?;
+        if !args.short {
+            table.write(output)?;
+        }
         output.flush()?;
     }
 
     project.close(multi_progress)?;
 
-    Ok(())
+    Ok(counts)
 }
@@ -3,25 +3,40 @@
 
 use clap::Args;
 use console::style;
-use indicatif::HumanCount;
-use log::{debug, info, trace, warn};
+use indicatif::{HumanBytes, HumanCount, ProgressBar};
+use log::{debug, info, log_enabled, trace, warn, Level};
 use signal_hook::consts::{SIGINT, SIGTERM};
 use signal_hook::flag;
 use std::collections::HashSet;
+use std::env;
 use std::error::Error;
 use std::io::prelude::*;
 use std::io::{self, IsTerminal};
-use std::path::PathBuf;
-use std::sync::atomic::AtomicBool;
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::thread;
+use std::time::{Duration, Instant};
 use wildmatch::WildMatch;
 
+use crate::cli::plan::{Plan, PlannedResources};
 use crate::cli::GlobalOptions;
-use row::format::HumanDuration;
-use row::project::Project;
-use row::workflow::{Action, ResourceCost};
-use row::MultiProgressContainer;
+use crate::ui::hyperlink;
+use row::cluster::SchedulerType;
+use row::format::{FormattedDuration, HumanDuration, TimeFormat};
+use row::project::{Leftovers, Project};
+use row::scheduler::{GroupPosition, RateLimiter, Scheduler};
+use row::state::{group_id, JobRecord};
+use row::workflow::{self, Action, ResourceCost};
+use row::workspace;
+use row::{progress_styles, MultiProgressContainer};
+
+/// A job to submit: the action, job name, and directories in the group.
+pub type Job = (Action, String, Vec<PathBuf>);
+
+/// Jobs to submit and their total cost.
+pub type Jobs = (Vec<Job>, ResourceCost);
 
 #[derive(Args, Debug)]
 pub struct Arguments {
@@ -29,6 +44,14 @@ pub struct Arguments {
     #[arg(short, long, value_name = "pattern", default_value_t=String::from("*"), display_order=0)]
     action: String,
 
+    /// Submit the jobs in a plan written by `row plan`.
+    #[arg(long, value_name = "file", display_order = 0, conflicts_with_all = ["action", "tag", "directories", "n"])]
+    plan: Option<PathBuf>,
+
+    /// Select the actions with the given tag (repeat to select any of several tags).
+    #[arg(long, value_name = "tag", display_order = 0)]
+    tag: Vec<String>,
+
     /// Select directories to summarize (defaults to all).
     directories: Vec<PathBuf>,
 
@@ -40,9 +63,44 @@ pub struct Arguments {
     #[arg(long, display_order = 0)]
     dry_run: bool,
 
+    /// Submit to Slurm even when executing inside a Slurm job.
+    #[arg(long, display_order = 0)]
+    allow_nested: bool,
+
     /// Maximum number of jobs to submit.
     #[arg(short, display_order = 0)]
     n: Option<usize>,
+
+    /// Submit one job per action that executes up to N eligible directories with `row exec`.
+    #[arg(long, value_name = "N", display_order = 0, conflicts_with_all = ["plan", "n"])]
+    aggregate: Option<NonZeroUsize>,
+
+    /// Charge the jobs to this account (overrides `submit_options.<cluster>.account`).
+    #[arg(
+        long,
+        value_name = "account",
+        display_order = 0,
+        conflicts_with = "plan"
+    )]
+    account: Option<String>,
+}
+
+impl Arguments {
+    /// Select a single action and the given directories.
+    pub fn new(action: &str, directories: Vec<PathBuf>, yes: bool) -> Self {
+        Self {
+            action: action.into(),
+            plan: None,
+            tag: Vec::new(),
+            directories,
+            yes,
+            dry_run: false,
+            allow_nested: false,
+            n: None,
+            aggregate: None,
+            account: None,
+        }
+    }
 }
 
 /// Submit workflow actions to the scheduler.
@@ -55,33 +113,372 @@ pub fn submit<W: Write>(
     output: &mut W,
 ) -> Result<(), Box<dyn Error>> {
     debug!("Submitting workflow actions to the scheduler.");
-    let action_matcher = WildMatch::new(&args.action);
 
-    let mut project = Project::open(options.io_threads, &options.cluster, multi_progress)?;
+    let mut project = Project::open(
+        options.io_threads,
+        &options.cluster,
+        options.workspace.as_deref(),
+        options.queue_timeout,
+        multi_progress,
+    )?;
 
-    let query_directories = if args.directories.is_empty() {
-        project.state().list_directories()
+    if let Some(url) = &project.workflow().workspace.url {
+        return Err(Box::new(row::Error::ReadOnlyWorkspace(url.clone())));
+    }
+
+    let (mut action_directories, total_cost) = if let Some(path) = &args.plan {
+        read_plan(&project, path)?
     } else {
-        args.directories
+        let Some(jobs) = prepare_jobs(&project, &args.action, &args.tag, args.directories, args.n)?
+        else {
+            project.close(multi_progress)?;
+            return nothing_to_do(options);
+        };
+        match args.aggregate {
+            Some(n) => aggregate_jobs(&project, jobs.0, n.get())?,
+            None => jobs,
+        }
     };
 
-    let mut matching_action_count = 0;
-    let mut action_directory_set = HashSet::new();
-    let mut action_groups: Vec<(&Action, Vec<Vec<PathBuf>>)> =
-        Vec::with_capacity(project.workflow().action.len());
+    if action_directories.is_empty() {
+        warn!("There are no eligible jobs to submit.");
+        project.close(multi_progress)?;
+        return nothing_to_do(options);
+    }
+
+    if let Some(account) = &args.account {
+        debug!("Charging the jobs to account '{account}'.");
+        for (action, _, _) in &mut action_directories {
+            action
+                .submit_options
+                .entry(project.cluster_name().into())
+                .or_default()
+                .account = Some(account.clone());
+        }
+    }
+
+    let groups = GroupPosition::number(action_directories.iter().map(|(a, _, _)| a.name()));
+
+    if args.dry_run {
+        let scheduler = project.scheduler();
+        info!("Execute without --dry-run to submit the following scripts...");
+        for (index, (action, job_name, directories)) in action_directories.iter().enumerate() {
+            info!("Script {}/{}:", index + 1, action_directories.len());
+            info!(
+                "{}",
+                describe_group(scheduler, action, directories, options.time_format())?
+            );
+            let script = scheduler.make_script(action, directories, job_name, groups[index])?;
+            let script = project.workflow().redact(project.cluster_name(), &script);
+
+            write!(output, "{script}")?;
+            output.flush()?;
+        }
+        project.close(multi_progress)?;
+        return Ok(());
+    }
+
+    // Scripts that execute `row submit` would otherwise recursively submit jobs.
+    if *project.scheduler_type() == SchedulerType::Slurm && !args.allow_nested {
+        if let Ok(job_id) = env::var("SLURM_JOB_ID") {
+            project.close(multi_progress)?;
+            return Err(Box::new(row::Error::NestedSubmission(
+                job_id,
+                project.cluster_name().into(),
+            )));
+        }
+    }
+
+    if let Some(minimum_free) = project.workflow().workspace.minimum_free {
+        let free = workspace::free_space(project.workflow())?;
+        debug!(
+            "The workspace has {} free (minimum {}).",
+            HumanBytes(free),
+            HumanBytes(minimum_free)
+        );
+        if free < minimum_free {
+            project.close(multi_progress)?;
+            return Err(Box::new(row::Error::InsufficientFreeSpace(
+                project.workflow().workspace.path.clone(),
+                HumanBytes(free).to_string(),
+                HumanBytes(minimum_free).to_string(),
+            )));
+        }
+    }
+
+    if !options.quiet {
+        write!(output, "Submitting ")?;
+        let jobs = if action_directories.len() == 1 {
+            "job"
+        } else {
+            "jobs"
+        };
+        write!(
+            output,
+            "{} ",
+            style(format!(
+                "{} {}",
+                HumanCount(action_directories.len() as u64),
+                jobs
+            ))
+            .yellow()
+            .bold()
+        )?;
+
+        writeln!(
+            output,
+            "that may cost up to {}.",
+            style(total_cost).cyan().bold()
+        )?;
+        output.flush()?;
+    }
+
+    if std::io::stdout().is_terminal() && !args.yes {
+        let mut input = String::new();
+        multi_progress.suspend(|| {
+            print!("Proceed? [Y/n]: ");
+            io::stdout().flush().expect("Can flush stdout");
+            io::stdin()
+                .read_line(&mut input)
+                .expect("Failed to read line");
+        });
+
+        let selection = input.trim().to_lowercase();
+        if selection != "y" && !selection.is_empty() {
+            warn!("Cancelling submission.");
+            return Ok(());
+        }
+    }
+
+    // Other `row submit` processes may have submitted the same directories
+    // after this one read the cache. Hold the lock until the cache is saved.
+    let _lock = project.lock_submissions()?;
+    for (action, _, directories) in &action_directories {
+        for directory in directories {
+            if let Some((cluster_name, job_id)) =
+                project.state().submitted_job(action.name(), directory)
+            {
+                let error = row::Error::DuplicateSubmission(
+                    directory.clone(),
+                    action.name().into(),
+                    cluster_name.clone(),
+                    *job_id,
+                );
+                project.close(multi_progress)?;
+                return Err(Box::new(error));
+            }
+        }
+    }
+
+    // We are about to spawn child processes with user-defined input and output.
+    // 1) Save the project cache now. Any user input error should not result
+    //    in an out of date cache.
+    // 2) Clear out the progress bars to allow the spawned processes stdout
+    //    and/or stderr to go directly to the terminal.
+    // 3) Stop using the buffered output and sync up all outputs by using
+    //    stdin and stdout directly.
+    project.close(multi_progress)?;
+
+    multi_progress.clear().unwrap();
+
+    // Install the Ctrl-C signal handler to gracefully kill spawned processes
+    // and save the pending scheduled job cache before exiting. Allow the user
+    // to force an immediate shutdown with a 2nd Ctrl-C.
+    // Make sure double CTRL+C and similar kills
+    let should_terminate = Arc::new(AtomicBool::new(false));
+    flag::register_conditional_shutdown(SIGINT, 10, Arc::clone(&should_terminate))?;
+    flag::register(SIGINT, Arc::clone(&should_terminate))?;
+    flag::register_conditional_shutdown(SIGTERM, 10, Arc::clone(&should_terminate))?;
+    flag::register(SIGTERM, Arc::clone(&should_terminate))?;
+    let instant = Instant::now();
+    let mut limiter = project
+        .max_submissions_per_minute()
+        .map(|per_minute| (per_minute, RateLimiter::new(per_minute, instant)));
+
+    for (index, (action, job_name, directories)) in action_directories.iter().enumerate() {
+        if let Some((per_minute, limiter)) = limiter.as_mut() {
+            throttle(limiter, *per_minute, &should_terminate, multi_progress);
+        }
+
+        let scheduler = project.scheduler();
+        let mut message = format!(
+            "[{}/{}] Submitting action '{}' on directory {}",
+            HumanCount((index + 1) as u64),
+            HumanCount(action_directories.len() as u64),
+            style(action.name().to_string()).blue(),
+            style(directories[0].display().to_string()).bold()
+        );
+        if directories.len() > 1 {
+            message += &style(format!(" and {} more", directories.len() - 1))
+                .italic()
+                .to_string();
+        }
+        message += &format!(
+            " ({:#}).",
+            style(FormattedDuration(instant.elapsed(), options.time_format())).dim()
+        );
+        if !options.quiet {
+            println!("{message}");
+        }
+        info!(
+            "{}",
+            describe_group(scheduler, action, directories, options.time_format())?
+        );
+        debug!("Submitting group {}.", group_id(directories));
+        if log_enabled!(Level::Trace) {
+            let script = scheduler.make_script(action, directories, job_name, groups[index])?;
+            trace!(
+                "Job script:\n{}",
+                project.workflow().redact(project.cluster_name(), &script)
+            );
+        }
+
+        let job = JobRecord::new(action, project.cluster_name(), directories.len());
+        let result = scheduler.submit(
+            &project.workflow().root,
+            action,
+            directories,
+            job_name,
+            groups[index],
+            Arc::clone(&should_terminate),
+        );
+
+        match result {
+            Err(error) => {
+                // Save the submitted cache for any jobs submitted so far.
+                project.close(multi_progress)?;
+                return Err(error.into());
+            }
+            Ok(Some(job_id)) => {
+                if !options.quiet {
+                    match project.job_url(project.cluster_name(), job_id) {
+                        Some(url) => {
+                            println!("Row submitted job {job_id} ({}).", hyperlink(&url, &url));
+                        }
+                        None => println!("Row submitted job {job_id}."),
+                    }
+                }
+                project.add_submitted(action.name(), directories, job_id);
+                project.add_job(JobRecord {
+                    job_id: Some(job_id),
+                    ..job
+                });
+                continue;
+            }
+            Ok(None) => {
+                // The scheduler executed the job before returning.
+                project.add_job(job.finished_now());
+            }
+        }
+    }
+
+    project.close(multi_progress)?;
+
+    Ok(())
+}
+
+/// Report that there are no jobs to submit.
+///
+/// Scripts that pass `--quiet` can distinguish this case by the exit code.
+///
+pub fn nothing_to_do(options: &GlobalOptions) -> Result<(), Box<dyn Error>> {
+    if options.quiet {
+        Err(Box::new(row::Error::NothingToDo))
+    } else {
+        Ok(())
+    }
+}
 
+/// Wait until `limiter` allows the next submission.
+///
+/// Show a spinner while waiting. Stop waiting early when `should_terminate`
+/// is set (the next submission reports the interruption).
+///
+fn throttle(
+    limiter: &mut RateLimiter,
+    per_minute: NonZeroU32,
+    should_terminate: &AtomicBool,
+    multi_progress: &mut MultiProgressContainer,
+) {
+    let delay = limiter.delay(Instant::now());
+    if !delay.is_zero() {
+        debug!("Waiting {:#} to submit the next job.", HumanDuration(delay));
+        let progress = multi_progress.add(ProgressBar::new_spinner().with_message(format!(
+            "Waiting to submit (at most {per_minute} jobs per minute)"
+        )));
+        progress.enable_steady_tick(Duration::from_millis(progress_styles::STEADY_TICK));
+        progress.set_style(progress_styles::uncounted_spinner());
+
+        let deadline = Instant::now() + delay;
+        while !should_terminate.load(Ordering::Relaxed) {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            thread::sleep((deadline - now).min(Duration::from_millis(100)));
+        }
+        progress.finish_and_clear();
+    }
+
+    limiter.take(Instant::now());
+}
+
+/// Select the eligible groups of the matching actions and name their jobs.
+///
+/// # Returns
+/// `Ok(None)` when no actions match. Otherwise, `Ok(Some((jobs, total_cost)))`.
+///
+/// # Errors
+/// Returns `Err` when the groups cannot be formed or submitted.
+///
+#[allow(clippy::too_many_lines)]
+pub fn prepare_jobs(
+    project: &Project,
+    action_pattern: &str,
+    tags: &[String],
+    directories: Vec<PathBuf>,
+    n: Option<usize>,
+) -> Result<Option<Jobs>, Box<dyn Error>> {
+    let action_matcher = WildMatch::new(action_pattern);
+
+    let query_directories = if directories.is_empty() {
+        project.state().list_directories()
+    } else {
+        directories
+            .into_iter()
+            .map(|d| project.aliases().resolve(d))
+            .collect()
+    };
+
+    let mut matching_actions = Vec::new();
     for action in &project.workflow().action {
         if !action_matcher.matches(action.name()) {
             trace!(
                 "Skipping action '{}'. It does not match the pattern '{}'.",
                 action.name(),
-                args.action
+                action_pattern
             );
             continue;
         }
 
-        matching_action_count += 1;
+        if !action.has_any_tag(tags) {
+            trace!(
+                "Skipping action '{}'. It does not have any of the tags {:?}.",
+                action.name(),
+                tags
+            );
+            continue;
+        }
+
+        matching_actions.push(action);
+    }
+
+    let mut action_directory_set = HashSet::new();
+    let mut action_groups: Vec<(&Action, Vec<Vec<PathBuf>>)> =
+        Vec::with_capacity(matching_actions.len());
 
+    // Submit prerequisite actions before the actions that depend on them.
+    for action in workflow::order_by_previous_actions(&matching_actions) {
         let matching_directories =
             project.find_matching_directories(action, query_directories.clone())?;
 
@@ -99,9 +496,53 @@ pub fn submit<W: Write>(
                         action.name().into(),
                     )));
                 }
+
+                for (id, common, total) in
+                    project.state().split_submitted_groups(action.name(), group)
+                {
+                    warn!(
+                        "Group {} of action '{}' would split the previously submitted group {id}: \
+                         it includes {common} of that group's {total} directories.",
+                        group_id(group),
+                        action.name()
+                    );
+                }
             }
         }
 
+        let Leftovers {
+            submit: groups,
+            deferred,
+        } = Project::separate_leftover_groups(action, groups)?;
+        if !deferred.is_empty() {
+            for group in &deferred {
+                debug!(
+                    "Deferring group {} of action '{}' with {} directories.",
+                    group_id(group),
+                    action.name(),
+                    group.len()
+                );
+            }
+            let directory_count: usize = deferred.iter().map(Vec::len).sum();
+            warn!(
+                "Deferring {} {} of action '{}' ({} {}) smaller than `minimum_size={}`.",
+                deferred.len(),
+                if deferred.len() == 1 {
+                    "group"
+                } else {
+                    "groups"
+                },
+                action.name(),
+                directory_count,
+                if directory_count == 1 {
+                    "directory"
+                } else {
+                    "directories"
+                },
+                action.group.minimum_size.unwrap_or_default()
+            );
+        }
+
         for group in &groups {
             for directory in group {
                 if !action_directory_set.insert((action.name.clone(), directory.clone())) {
@@ -116,28 +557,42 @@ pub fn submit<W: Write>(
         action_groups.push((action, groups));
     }
 
-    if matching_action_count == 0 {
-        warn!("No actions match '{}'.", args.action);
-        project.close(multi_progress)?;
-        return Ok(());
+    if matching_actions.is_empty() {
+        if tags.is_empty() {
+            warn!("No actions match '{}'.", action_pattern);
+        } else {
+            warn!(
+                "No actions match '{}' with any of the tags {:?}.",
+                action_pattern, tags
+            );
+        }
+        return Ok(None);
     }
 
     info!("Preparing jobs that execute the following actions:");
 
     let mut total_cost = ResourceCost::new();
-    let mut action_directories: Vec<(Action, Vec<PathBuf>)> = Vec::new();
+    let mut action_directories: Vec<Job> = Vec::new();
     for (action, groups) in action_groups {
         let mut cost = ResourceCost::new();
         let mut job_count = 0;
-        for group in groups {
-            if let Some(n) = args.n {
+        for (group_index, group) in groups.iter().enumerate() {
+            if let Some(n) = n {
                 if action_directories.len() >= n {
                     break;
                 }
             }
 
+            let value = project
+                .state()
+                .values()
+                .get(&group[0])
+                .cloned()
+                .unwrap_or_default();
+            let job_name = action.job_name(group, group_index + 1, &value)?;
+
             cost = cost + action.resources.cost(group.len());
-            action_directories.push((action.clone(), group.clone()));
+            action_directories.push((action.clone(), job_name, group.clone()));
             job_count += 1;
         }
 
@@ -152,137 +607,190 @@ pub fn submit<W: Write>(
         }
         total_cost = total_cost + cost;
 
-        if let Some(n) = args.n {
+        if let Some(n) = n {
             if action_directories.len() >= n {
                 break;
             }
         }
     }
 
-    if action_directories.is_empty() {
-        warn!("There are no eligible jobs to submit.");
-        project.close(multi_progress)?;
-        return Ok(());
-    }
-
-    if args.dry_run {
-        let scheduler = project.scheduler();
-        info!("Execute without --dry-run to submit the following scripts...");
-        for (index, (action, directories)) in action_directories.iter().enumerate() {
-            info!("Script {}/{}:", index + 1, action_directories.len());
-            let script = scheduler.make_script(action, directories)?;
+    Ok(Some((action_directories, total_cost)))
+}
 
-            write!(output, "{script}")?;
-            output.flush()?;
+/// Combine the jobs of each action into one job that executes up to `n` directories.
+///
+/// The combined job executes `row exec`, which checks that each directory is
+/// still eligible before executing it.
+///
+fn aggregate_jobs(project: &Project, jobs: Vec<Job>, n: usize) -> Result<Jobs, row::Error> {
+    let mut aggregated: Vec<Job> = Vec::new();
+    for (mut action, job_name, directories) in jobs {
+        match aggregated.last_mut() {
+            Some((last, _, last_directories)) if last.name() == action.name() => {
+                last_directories.extend(directories);
+            }
+            _ => {
+                action.aggregate = true;
+                action.resources = action.resources.aggregated();
+                aggregated.push((action, job_name, directories));
+            }
         }
-        project.close(multi_progress)?;
-        return Ok(());
     }
 
-    write!(output, "Submitting ")?;
-    let jobs = if action_directories.len() == 1 {
-        "job"
-    } else {
-        "jobs"
-    };
-    write!(
-        output,
-        "{} ",
-        style(format!(
-            "{} {}",
-            HumanCount(action_directories.len() as u64),
-            jobs
-        ))
-        .yellow()
-        .bold()
-    )?;
-
-    writeln!(
-        output,
-        "that may cost up to {}.",
-        style(total_cost).cyan().bold()
-    )?;
-    output.flush()?;
-
-    if std::io::stdout().is_terminal() && !args.yes {
-        let mut input = String::new();
-        multi_progress.suspend(|| {
-            print!("Proceed? [Y/n]: ");
-            io::stdout().flush().expect("Can flush stdout");
-            io::stdin()
-                .read_line(&mut input)
-                .expect("Failed to read line");
-        });
+    let mut total_cost = ResourceCost::new();
+    for (action, job_name, directories) in &mut aggregated {
+        directories.truncate(n);
+        debug!(
+            "Aggregating {} directories of action '{}' into one job.",
+            directories.len(),
+            action.name()
+        );
 
-        let selection = input.trim().to_lowercase();
-        if selection != "y" && !selection.is_empty() {
-            warn!("Cancelling submission.");
-            return Ok(());
-        }
+        let value = project
+            .state()
+            .values()
+            .get(&directories[0])
+            .cloned()
+            .unwrap_or_default();
+        *job_name = action.job_name(directories, 1, &value)?;
+        total_cost = total_cost + action.resources.cost(directories.len());
     }
 
-    // We are about to spawn child processes with user-defined input and output.
-    // 1) Save the project cache now. Any user input error should not result
-    //    in an out of date cache.
-    // 2) Clear out the progress bars to allow the spawned processes stdout
-    //    and/or stderr to go directly to the terminal.
-    // 3) Stop using the buffered output and sync up all outputs by using
-    //    stdin and stdout directly.
-    project.close(multi_progress)?;
+    Ok((aggregated, total_cost))
+}
 
-    multi_progress.clear().unwrap();
+/// Read the jobs in a plan and check that they are still valid.
+///
+/// Each job must execute the same command with the same resources, select the
+/// same partitions, and include only eligible directories. The regenerated
+/// (and redacted) script must match the reviewed script in the plan.
+///
+fn read_plan(project: &Project, path: &Path) -> Result<Jobs, row::Error> {
+    let plan = Plan::read(path)?;
+    if plan.cluster != project.cluster_name() {
+        return Err(row::Error::PlanClusterMismatch(
+            path.into(),
+            plan.cluster,
+            project.cluster_name().into(),
+        ));
+    }
 
-    // Install the Ctrl-C signal handler to gracefully kill spawned processes
-    // and save the pending scheduled job cache before exiting. Allow the user
-    // to force an immediate shutdown with a 2nd Ctrl-C.
-    // Make sure double CTRL+C and similar kills
-    let should_terminate = Arc::new(AtomicBool::new(false));
-    flag::register_conditional_shutdown(SIGINT, 10, Arc::clone(&should_terminate))?;
-    flag::register(SIGINT, Arc::clone(&should_terminate))?;
-    flag::register_conditional_shutdown(SIGTERM, 10, Arc::clone(&should_terminate))?;
-    flag::register(SIGTERM, Arc::clone(&should_terminate))?;
-    let instant = Instant::now();
+    let scheduler = project.scheduler();
+    let groups = GroupPosition::number(plan.jobs.iter().map(|j| j.action.as_str()));
+    let mut action_directory_set = HashSet::new();
+    let mut total_cost = ResourceCost::new();
+    let mut jobs = Vec::with_capacity(plan.jobs.len());
+
+    for (planned, group) in plan.jobs.into_iter().zip(groups) {
+        // Several actions may share a name. Find the one that the job executes.
+        let action = project
+            .workflow()
+            .action
+            .iter()
+            .filter(|a| a.name() == planned.action)
+            .find(|a| {
+                a.commands() == planned.command
+                    && PlannedResources::new(a, planned.directories.len()) == planned.resources
+            })
+            .ok_or_else(|| {
+                row::Error::PlanOutdated(
+                    path.into(),
+                    format!(
+                        "the command or resources of job '{}' have changed",
+                        planned.job_name
+                    ),
+                )
+            })?;
+
+        if scheduler.partitions(action, &planned.directories)? != planned.partitions {
+            return Err(row::Error::PlanOutdated(
+                path.into(),
+                format!("the partitions of job '{}' have changed", planned.job_name),
+            ));
+        }
 
-    for (index, (action, directories)) in action_directories.iter().enumerate() {
-        let scheduler = project.scheduler();
-        let mut message = format!(
-            "[{}/{}] Submitting action '{}' on directory {}",
-            HumanCount((index + 1) as u64),
-            HumanCount(action_directories.len() as u64),
-            style(action.name().to_string()).blue(),
-            style(directories[0].display().to_string()).bold()
+        let script = project.workflow().redact(
+            project.cluster_name(),
+            &scheduler.make_script(action, &planned.directories, &planned.job_name, group)?,
         );
-        if directories.len() > 1 {
-            message += &style(format!(" and {} more", directories.len() - 1))
-                .italic()
-                .to_string();
+        if script != planned.script {
+            return Err(row::Error::PlanOutdated(
+                path.into(),
+                format!("the script of job '{}' has changed", planned.job_name),
+            ));
         }
-        message += &format!(" ({:#}).", style(HumanDuration(instant.elapsed())).dim());
-        println!("{message}");
 
-        let result = scheduler.submit(
-            &project.workflow().root,
-            action,
-            directories,
-            Arc::clone(&should_terminate),
-        );
+        let status = project.separate_by_status(action, planned.directories.clone())?;
+        if status.eligible.len() != planned.directories.len() {
+            return Err(row::Error::PlanOutdated(
+                path.into(),
+                format!(
+                    "{} of the directories in job '{}' are no longer eligible",
+                    planned.directories.len() - status.eligible.len(),
+                    planned.job_name
+                ),
+            ));
+        }
 
-        match result {
-            Err(error) => {
-                // Save the submitted cache for any jobs submitted so far.
-                project.close(multi_progress)?;
-                return Err(error.into());
-            }
-            Ok(Some(job_id)) => {
-                println!("Row submitted job {job_id}.");
-                project.add_submitted(action.name(), directories, job_id);
-                continue;
+        for directory in &planned.directories {
+            if !action_directory_set.insert((action.name.clone(), directory.clone())) {
+                return Err(row::Error::WouldSubmitMultipleTimes(
+                    directory.clone(),
+                    action.name().into(),
+                ));
             }
-            Ok(None) => continue,
         }
+
+        total_cost = total_cost + action.resources.cost(planned.directories.len());
+        jobs.push((action.clone(), planned.job_name, planned.directories));
     }
 
-    project.close(multi_progress)?;
+    info!(
+        "Read {} {} from '{}'.",
+        jobs.len(),
+        if jobs.len() == 1 { "job" } else { "jobs" },
+        path.display()
+    );
 
-    Ok(())
+    Ok((jobs, total_cost))
+}
+
+/// Describe the resources that a job requests.
+///
+/// # Returns
+/// The number of directories, total processes, total walltime, selected
+/// partition(s), and maximum cost of the job.
+///
+fn describe_group(
+    scheduler: &dyn Scheduler,
+    action: &Action,
+    directories: &[PathBuf],
+    time_format: TimeFormat,
+) -> Result<String, row::Error> {
+    let n = directories.len();
+    let walltime = u64::try_from(action.resources.total_walltime(n).signed_total_seconds())
+        .unwrap_or_default();
+
+    let mut description = format!(
+        "{} {}, {} {}, {} walltime",
+        n,
+        if n == 1 { "directory" } else { "directories" },
+        action.resources.total_processes(n),
+        if action.resources.total_processes(n) == 1 {
+            "process"
+        } else {
+            "processes"
+        },
+        FormattedDuration(Duration::from_secs(walltime), time_format),
+    );
+
+    let partitions = scheduler.partitions(action, directories)?;
+    match partitions.len() {
+        0 => (),
+        1 => description += &format!(", partition '{}'", partitions[0]),
+        _ => description += &format!(", partitions '{}'", partitions.join("', '")),
+    }
+
+    description += &format!(", costs up to {}.", action.resources.cost(n));
+    Ok(description)
 }
@@ -0,0 +1,59 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use clap::Args;
+use log::{debug, info};
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::cli::GlobalOptions;
+use row::sweep::Sweep;
+use row::workflow::Workflow;
+
+#[derive(Args, Debug)]
+pub struct Arguments {
+    /// The sweep specification file ('.json' or TOML).
+    #[arg(display_order = 0)]
+    spec: PathBuf,
+
+    /// Print the directories that would be created without creating them.
+    #[arg(long, display_order = 0)]
+    dry_run: bool,
+}
+
+/// Create workspace directories from a parameter sweep.
+///
+/// Print the number of new directories, or their names with `--dry-run`.
+///
+pub fn sweep<W: Write>(
+    options: &GlobalOptions,
+    args: &Arguments,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    debug!("Creating directories from '{}'.", args.spec.display());
+
+    let workflow = Workflow::open_with_workspace(options.workspace.as_deref())?;
+    let sweep = Sweep::open(&args.spec)?;
+    let result = sweep.create(&workflow, args.dry_run)?;
+
+    let new = result.created.len();
+    let existing = result.existing.len();
+    let word = if new == 1 { "directory" } else { "directories" };
+
+    if args.dry_run {
+        for name in &result.created {
+            writeln!(output, "{}", name.display())?;
+        }
+        output.flush()?;
+        info!("Would create {new} new {word} ({existing} already exist).");
+    } else {
+        writeln!(
+            output,
+            "Created {new} new {word} ({existing} already exist)."
+        )?;
+        output.flush()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,240 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use clap::{Args, ValueEnum};
+use log::{debug, info, trace};
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+use wildmatch::WildMatch;
+
+use crate::cli::{self, GlobalOptions};
+use row::alias::Aliases;
+use row::format::FormattedDuration;
+use row::project::{Project, Status};
+use row::MultiProgressContainer;
+
+/// The longest time to sleep between checks.
+const MAXIMUM_INTERVAL: Duration = Duration::from_secs(300);
+
+#[derive(Args, Debug)]
+pub struct Arguments {
+    /// Select the actions to wait for with a wildcard pattern.
+    #[arg(short, long, value_name = "pattern", display_order = 0)]
+    action: String,
+
+    /// Select directories to wait for (defaults to all). Use 'wait -' to read from stdin.
+    directories: Vec<PathBuf>,
+
+    /// Wait until every selected directory reaches this status (or a later one).
+    #[arg(long, value_enum, default_value_t = Target::Completed, display_order = 0)]
+    until: Target,
+
+    /// Exit with an error after waiting this long (for example: 90s, 30m, 2h, or 1d).
+    #[arg(long, value_name = "duration", value_parser = parse_duration, display_order = 0)]
+    timeout: Option<Duration>,
+
+    /// Seconds between the first checks. The interval doubles (up to 5 minutes) while the status is unchanged.
+    #[arg(
+        long,
+        value_name = "seconds",
+        default_value_t = 10,
+        value_parser = clap::value_parser!(u64).range(1..),
+        display_order = 0
+    )]
+    interval: u64,
+}
+
+/// The status that `row wait` waits for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Target {
+    /// No directories are waiting.
+    Eligible,
+
+    /// Every directory is submitted or completed.
+    Submitted,
+
+    /// Every directory is completed.
+    Completed,
+}
+
+impl Target {
+    /// Count the directories in `status` that have not reached the target.
+    fn remaining(self, status: &Status) -> usize {
+        match self {
+            Target::Eligible => status.waiting.len(),
+            Target::Submitted => status.eligible.len() + status.waiting.len(),
+            Target::Completed => {
+                status.submitted.len() + status.eligible.len() + status.waiting.len()
+            }
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Eligible => write!(f, "eligible"),
+            Target::Submitted => write!(f, "submitted"),
+            Target::Completed => write!(f, "completed"),
+        }
+    }
+}
+
+/// Parse a duration such as `90s`, `30m`, `2h`, `1d`, or `HH:MM:SS`.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let (number, unit) = value.split_at(
+        value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len()),
+    );
+    let scale = match unit {
+        "" | "s" => Some(1),
+        "m" => Some(60),
+        "h" => Some(60 * 60),
+        "d" => Some(24 * 60 * 60),
+        _ => None,
+    };
+
+    if let (Ok(number), Some(scale)) = (number.parse::<u64>(), scale) {
+        return Ok(Duration::from_secs(number * scale));
+    }
+
+    speedate::Duration::from_str(value)
+        .ok()
+        .and_then(|d| u64::try_from(d.signed_total_seconds()).ok())
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("'{value}' is not a duration (for example: 90s, 30m, 2h, or 1d)"))
+}
+
+/// Wait until the selected actions reach the target status.
+///
+/// Check the status periodically. Each check synchronizes the project with the
+/// workspace and queries the scheduler.
+///
+pub fn wait(
+    options: &GlobalOptions,
+    args: &Arguments,
+    multi_progress: &mut MultiProgressContainer,
+) -> Result<(), Box<dyn Error>> {
+    debug!(
+        "Waiting for actions matching '{}' to reach '{}'.",
+        args.action, args.until
+    );
+
+    // Read directories from stdin only once. Resolve aliases after opening the project.
+    let directories = if args.directories.is_empty() {
+        None
+    } else {
+        Some(cli::parse_directories(
+            args.directories.clone(),
+            &Aliases::default(),
+            || Ok(Vec::new()),
+        )?)
+    };
+
+    let instant = Instant::now();
+    let initial_interval = Duration::from_secs(args.interval);
+    let mut interval = initial_interval;
+    let mut previous = None;
+    loop {
+        let counts = count_remaining(options, args, directories.clone(), multi_progress)?;
+        let remaining: usize = counts.iter().sum();
+        if remaining == 0 {
+            info!(
+                "The selected directories reached '{}' after {}.",
+                args.until,
+                FormattedDuration(instant.elapsed(), options.time_format())
+            );
+            return Ok(());
+        }
+
+        let elapsed = instant.elapsed();
+        if let Some(timeout) = args.timeout {
+            if elapsed >= timeout {
+                return Err(Box::new(row::Error::WaitTimedOut(
+                    FormattedDuration(timeout, options.time_format()).to_string(),
+                    remaining,
+                )));
+            }
+        }
+
+        // Check less often while nothing changes.
+        if previous.as_ref() == Some(&counts) {
+            interval = (interval * 2).min(MAXIMUM_INTERVAL.max(initial_interval));
+        } else {
+            interval = initial_interval;
+        }
+        previous = Some(counts);
+
+        let sleep = args.timeout.map_or(interval, |timeout| {
+            interval.min(timeout.saturating_sub(elapsed))
+        });
+        info!(
+            "{remaining} directories have not reached '{}'. Checking again in {}.",
+            args.until,
+            FormattedDuration(sleep, options.time_format())
+        );
+        thread::sleep(sleep);
+    }
+}
+
+/// Count the directories of each matching action that have not reached the target.
+fn count_remaining(
+    options: &GlobalOptions,
+    args: &Arguments,
+    directories: Option<Vec<PathBuf>>,
+    multi_progress: &mut MultiProgressContainer,
+) -> Result<Vec<usize>, Box<dyn Error>> {
+    let action_matcher = WildMatch::new(&args.action);
+
+    let mut project = Project::open(
+        options.io_threads,
+        &options.cluster,
+        options.workspace.as_deref(),
+        options.queue_timeout,
+        multi_progress,
+    )?;
+
+    let query_directories = match directories {
+        Some(directories) => directories
+            .into_iter()
+            .map(|d| project.aliases().resolve(d))
+            .collect(),
+        None => project.state().list_directories(),
+    };
+
+    let mut result = Vec::new();
+    for action in &project.workflow().action {
+        if !action_matcher.matches(action.name()) {
+            trace!(
+                "Skipping action '{}'. It does not match the pattern '{}'.",
+                action.name(),
+                args.action
+            );
+            continue;
+        }
+
+        let matching_directories =
+            project.find_matching_directories(action, query_directories.clone())?;
+        let status = project.separate_by_status(action, matching_directories)?;
+        let remaining = args.until.remaining(&status);
+        debug!(
+            "{remaining} directories of action '{}' have not reached '{}'.",
+            action.name(),
+            args.until
+        );
+        result.push(remaining);
+    }
+
+    project.close(multi_progress)?;
+
+    if result.is_empty() {
+        return Err(Box::new(project.workflow().action_not_found(&args.action)));
+    }
+
+    Ok(result)
+}
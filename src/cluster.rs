@@ -3,16 +3,25 @@
 
 use log::{debug, info, trace, warn};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 use std::fmt::Write as _;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::{self, BufReader};
+use std::num::NonZeroU32;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 use crate::builtin::BuiltIn;
 use crate::workflow::Resources;
-use crate::Error;
+use crate::{Error, TOML_WORKFLOW_FILE_NAME, YAML_WORKFLOW_FILE_NAME};
+
+/// Name of the cluster configuration file.
+const CLUSTERS_FILE_NAME: &str = "clusters.toml";
+
+/// The name of the cluster that `identify` found automatically in this process.
+static IDENTIFIED_CLUSTER: Mutex<Option<String>> = Mutex::new(None);
 
 /// Cluster configuration
 ///
@@ -50,6 +59,84 @@ pub struct Cluster {
     /// Submit options to include in every job submitted to this cluster.
     #[serde(default)]
     pub submit_options: Vec<String>,
+
+    /// The launcher that implements `mpi` on this cluster (or "detect").
+    pub mpi_launcher: Option<String>,
+
+    /// The quality of service levels that jobs may request (any when empty).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub qos: Vec<String>,
+
+    /// Options for the bash scheduler.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bash: Option<BashOptions>,
+
+    /// URL of a web page that shows a submitted job (`{job_id}` is replaced).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job_url_template: Option<String>,
+
+    /// Options for the Kubernetes scheduler.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kubernetes: Option<KubernetesOptions>,
+
+    /// The maximum number of jobs that `row submit` submits per minute.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_submissions_per_minute: Option<NonZeroU32>,
+
+    /// How to choose among the partitions that match a job.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_select_strategy: Option<AutoSelectStrategy>,
+}
+
+/// Strategies to automatically select a partition.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoSelectStrategy {
+    /// Select the first matching partition in the order listed.
+    #[default]
+    First,
+
+    /// Select the matching partition with the most idle CPUs.
+    MostIdle,
+}
+
+/// Options for clusters that execute jobs with the bash scheduler.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BashOptions {
+    /// Lines to include at the top of every job script.
+    pub preamble: Option<String>,
+}
+
+/// Options for clusters that execute jobs with the Kubernetes scheduler.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct KubernetesOptions {
+    /// The container image that executes jobs.
+    #[serde(default)]
+    pub image: String,
+
+    /// The namespace to submit jobs in.
+    pub namespace: Option<String>,
+
+    /// A persistent volume claim to mount at the project's path.
+    pub volume_claim: Option<String>,
+
+    /// The `row` executable in the container.
+    pub row_executable: Option<String>,
+}
+
+/// The outcome of identifying one cluster.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Identification {
+    /// The cluster's name.
+    pub cluster: String,
+
+    /// Set when `identify` chooses this cluster.
+    pub selected: bool,
+
+    /// Why the cluster was or was not chosen.
+    pub reason: String,
 }
 
 /// Methods to identify clusters.
@@ -70,6 +157,8 @@ pub enum SchedulerType {
     Bash,
     /// Submit jobs to a Slurm queue.
     Slurm,
+    /// Submit jobs to a Kubernetes cluster (experimental).
+    Kubernetes,
 }
 
 /// Partition parameters.
@@ -94,6 +183,12 @@ pub struct Partition {
     /// CPUs per node.
     pub cpus_per_node: Option<usize>,
 
+    /// Cores per socket.
+    pub cores_per_socket: Option<usize>,
+
+    /// Sockets per node.
+    pub sockets_per_node: Option<usize>,
+
     /// Minimum number of GPUs per job.
     pub minimum_gpus_per_job: Option<usize>,
 
@@ -123,7 +218,9 @@ pub struct Partition {
 impl Configuration {
     /// Identify the cluster.
     ///
-    /// Identifying the current cluster consumes the `Configuration`.
+    /// Identifying the current cluster consumes the `Configuration`. The
+    /// automatic identification executes once per process. Later calls return
+    /// the cluster with the same name.
     ///
     /// # Errors
     /// * `row::Error::ClusterNameNotFound` when a cluster by the given name
@@ -131,6 +228,9 @@ impl Configuration {
     /// * `row::Error::ClusterNotFound` when the automatic identification
     ///   fails to find a cluster in the configuration.
     ///
+    /// # Panics
+    /// When the identified cluster mutex is poisoned.
+    ///
     pub fn identify(self, name: Option<&str>) -> Result<Cluster, Error> {
         let cluster = if let Some(name) = name {
             self.cluster
@@ -138,23 +238,77 @@ impl Configuration {
                 .find(|c| c.name == name)
                 .ok_or_else(|| Error::ClusterNameNotFound(name.to_string()))?
         } else {
-            self.cluster
-                .into_iter()
-                .find(Cluster::identity_matches)
-                .ok_or_else(Error::ClusterNotFound)?
+            let mut identified = IDENTIFIED_CLUSTER.lock().expect("Identified cluster mutex");
+            let cached = identified
+                .as_ref()
+                .and_then(|name| self.cluster.iter().position(|c| c.name == *name));
+            let cluster = match cached {
+                Some(index) => {
+                    trace!("Using the cluster identified earlier in this process.");
+                    self.cluster.into_iter().nth(index).expect("Valid index")
+                }
+                None => self.auto_identify()?,
+            };
+            *identified = Some(cluster.name.clone());
+            cluster
         };
 
         info!("Identified cluster '{}'.", cluster.name);
         Ok(cluster)
     }
 
+    /// Find the first cluster whose identity matches the current environment.
+    fn auto_identify(self) -> Result<Cluster, Error> {
+        self.cluster
+            .into_iter()
+            .find(Cluster::identity_matches)
+            .ok_or_else(Error::ClusterNotFound)
+    }
+
+    /// Explain how `identify` chooses a cluster.
+    ///
+    /// Evaluate every cluster in the configuration in order and describe why
+    /// its identification rule matches or fails.
+    ///
+    pub fn explain_identification(&self, name: Option<&str>) -> Vec<Identification> {
+        let mut result = Vec::with_capacity(self.cluster.len());
+        let mut identified: Option<&str> = None;
+
+        for cluster in &self.cluster {
+            let (matches, reason) = cluster.explain_identity();
+            let (selected, reason) = match (name, identified) {
+                (Some(name), _) if cluster.name == name && identified.is_none() => {
+                    (true, format!("selected by --cluster={name}"))
+                }
+                (Some(name), _) => (false, format!("not '{name}' given by --cluster")),
+                (None, Some(identified)) => {
+                    (false, format!("not checked: '{identified}' matched first"))
+                }
+                (None, None) => (matches, reason),
+            };
+
+            if selected {
+                identified = Some(&cluster.name);
+            }
+            result.push(Identification {
+                cluster: cluster.name.clone(),
+                selected,
+                reason,
+            });
+        }
+
+        result
+    }
+
     /// Open the cluster configuration
     ///
-    /// Open `$HOME/.config/row/clusters.toml` if it exists and merge it with
-    /// the built-in configuration.
+    /// Open `$HOME/.config/row/clusters.toml` and `clusters.toml` in the
+    /// project root when they exist. Merge them with the built-in
+    /// configuration: clusters in the project file take precedence over those
+    /// in the user file, which take precedence over the built-in clusters.
     ///
     /// # Errors
-    /// Returns `Err(row::Error)` when the file cannot be read or if there is
+    /// Returns `Err(row::Error)` when a file cannot be read or if there is
     /// as parse error.
     ///
     pub fn open() -> Result<Self, Error> {
@@ -162,24 +316,42 @@ impl Configuration {
             Ok(row_home) => PathBuf::from(row_home),
             Err(_) => home::home_dir().ok_or_else(Error::NoHome)?,
         };
-        let clusters_toml_path = home.join(".config").join("row").join("clusters.toml");
-        Self::open_from_path(clusters_toml_path)
+        let user_path = home.join(".config").join("row").join(CLUSTERS_FILE_NAME);
+
+        let current_dir = env::current_dir()?;
+        let project_path = current_dir
+            .ancestors()
+            .find(|p| {
+                p.join(TOML_WORKFLOW_FILE_NAME).is_file()
+                    || p.join(YAML_WORKFLOW_FILE_NAME).is_file()
+            })
+            .map(|p| p.join(CLUSTERS_FILE_NAME));
+
+        Self::open_from_paths(&user_path, project_path.as_deref())
     }
 
-    fn open_from_path(clusters_toml_path: PathBuf) -> Result<Self, Error> {
+    fn open_from_paths(user_path: &Path, project_path: Option<&Path>) -> Result<Self, Error> {
         let mut clusters = Self::built_in();
 
-        let clusters_file = match File::open(&clusters_toml_path) {
+        for path in [Some(user_path), project_path].into_iter().flatten() {
+            if let Some(config) = Self::read(path)? {
+                clusters.merge(&config);
+            }
+        }
+
+        Ok(clusters)
+    }
+
+    /// Read a `clusters.toml` file if it exists.
+    fn read(clusters_toml_path: &Path) -> Result<Option<Self>, Error> {
+        let clusters_file = match File::open(clusters_toml_path) {
             Ok(file) => file,
             Err(error) => match error.kind() {
                 io::ErrorKind::NotFound => {
-                    trace!(
-                        "'{}' does not exist, using built-in clusters.",
-                        &clusters_toml_path.display()
-                    );
-                    return Ok(clusters);
+                    trace!("'{}' does not exist.", clusters_toml_path.display());
+                    return Ok(None);
                 }
-                _ => return Err(Error::FileRead(clusters_toml_path, error)),
+                _ => return Err(Error::FileRead(clusters_toml_path.into(), error)),
             },
         };
 
@@ -187,12 +359,10 @@ impl Configuration {
         let mut clusters_string = String::new();
         buffer
             .read_to_string(&mut clusters_string)
-            .map_err(|e| Error::FileRead(clusters_toml_path.clone(), e))?;
+            .map_err(|e| Error::FileRead(clusters_toml_path.into(), e))?;
 
-        trace!("Parsing '{}'.", &clusters_toml_path.display());
-        let user_config = Self::parse_str(&clusters_toml_path, &clusters_string)?;
-        clusters.merge(&user_config);
-        Ok(clusters)
+        trace!("Parsing '{}'.", clusters_toml_path.display());
+        Ok(Some(Self::parse_str(clusters_toml_path, &clusters_string)?))
     }
 
     /// Parse a `Configuration` from a TOML string
@@ -225,11 +395,43 @@ impl Cluster {
             self.name,
             self.identify
         );
+        self.explain_identity().0
+    }
+
+    /// Explain whether the cluster's identity matches the current environment.
+    ///
+    /// # Returns
+    /// `(matches, reason)`.
+    ///
+    fn explain_identity(&self) -> (bool, String) {
         match &self.identify {
-            IdentificationMethod::Always(condition) => *condition,
-            IdentificationMethod::ByEnvironment(variable, value) => {
-                env::var(variable).is_ok_and(|x| x == *value)
+            IdentificationMethod::Always(true) => (true, "always = true".into()),
+            IdentificationMethod::Always(false) => {
+                (false, "always = false: select it with --cluster".into())
             }
+            IdentificationMethod::ByEnvironment(variable, value) => match env::var(variable) {
+                Ok(actual) if actual == *value => (true, format!("${variable} is '{actual}'")),
+                Ok(actual) => (false, format!("${variable} is '{actual}', not '{value}'")),
+                Err(_) => (false, format!("${variable} is not set")),
+            },
+        }
+    }
+
+    /// Check that jobs on this cluster may request the given quality of service.
+    ///
+    /// # Errors
+    /// Returns `Err<row::Error>` when the cluster lists allowed `qos` values and
+    /// `qos` is not one of them.
+    ///
+    pub fn check_qos(&self, qos: &str) -> Result<(), Error> {
+        if self.qos.is_empty() || self.qos.iter().any(|q| q == qos) {
+            Ok(())
+        } else {
+            Err(Error::QosNotAllowed(
+                qos.into(),
+                self.name.clone(),
+                self.qos.join(", "),
+            ))
         }
     }
 
@@ -272,6 +474,116 @@ impl Cluster {
 
         Ok(partition)
     }
+
+    /// Find the partition to use for the given job, preferring idle partitions.
+    ///
+    /// When `auto_select_strategy` is `most_idle` and `idle_cpus` is known,
+    /// select the matching partition with the most idle CPUs (the first listed
+    /// among ties). `idle_cpus` maps lowercase partition names to idle CPUs.
+    /// Otherwise, select the partition with `find_partition`.
+    ///
+    /// # Errors
+    /// Returns `Err<row::Error>` when the partition is not found.
+    ///
+    #[allow(clippy::implicit_hasher)]
+    pub fn select_partition(
+        &self,
+        partition_name: Option<&str>,
+        resources: &Resources,
+        n_directories: usize,
+        idle_cpus: Option<&HashMap<String, usize>>,
+    ) -> Result<&Partition, Error> {
+        let (None, AutoSelectStrategy::MostIdle, Some(idle_cpus)) = (
+            partition_name,
+            self.auto_select_strategy.unwrap_or_default(),
+            idle_cpus,
+        ) else {
+            return self.find_partition(partition_name, resources, n_directories);
+        };
+
+        let mut reason = String::new();
+        let mut selected: Option<(&Partition, usize)> = None;
+        for partition in self
+            .partition
+            .iter()
+            .filter(|p| p.matches(resources, n_directories, &mut reason))
+        {
+            let idle = idle_cpus
+                .get(&partition.name.to_lowercase())
+                .copied()
+                .unwrap_or_default();
+            trace!("Partition '{}' has {idle} idle CPUs.", partition.name);
+            if selected.map_or(true, |(_, most)| idle > most) {
+                selected = Some((partition, idle));
+            }
+        }
+
+        selected
+            .map(|(partition, _)| partition)
+            .ok_or_else(|| Error::PartitionNotFound(reason))
+    }
+
+    /// Find every partition that may be selected for groups of 1 to
+    /// `maximum_size` directories.
+    ///
+    /// With the `most_idle` strategy, any matching partition may be selected.
+    /// Otherwise, only the first matching partition for each group size. When `maximum_size` is `None`, check groups up to twice the largest limit
+    /// set on any partition. The matching partitions repeat for larger groups.
+    ///
+    pub fn selectable_partitions(
+        &self,
+        partition_name: Option<&str>,
+        resources: &Resources,
+        maximum_size: Option<usize>,
+    ) -> Vec<&Partition> {
+        if let Some(partition_name) = partition_name {
+            return self
+                .partition
+                .iter()
+                .filter(|p| p.name == partition_name)
+                .collect();
+        }
+
+        let maximum_size = maximum_size.unwrap_or_else(|| {
+            let largest_limit = self
+                .partition
+                .iter()
+                .flat_map(|p| {
+                    [
+                        p.maximum_cpus_per_job,
+                        p.require_cpus_multiple_of,
+                        p.minimum_gpus_per_job,
+                        p.maximum_gpus_per_job,
+                        p.require_gpus_multiple_of,
+                    ]
+                })
+                .flatten()
+                .max()
+                .unwrap_or_default();
+            2 * largest_limit.max(1)
+        });
+
+        let mut result: Vec<&Partition> = Vec::new();
+        for n_directories in 1..=maximum_size {
+            let mut reason = String::new();
+            let matching = self
+                .partition
+                .iter()
+                .filter(|p| p.matches(resources, n_directories, &mut reason));
+            let selected: Vec<&Partition> =
+                if self.auto_select_strategy.unwrap_or_default() == AutoSelectStrategy::MostIdle {
+                    matching.collect()
+                } else {
+                    matching.take(1).collect()
+                };
+            for partition in selected {
+                if !result.iter().any(|p| p.name == partition.name) {
+                    result.push(partition);
+                }
+            }
+        }
+        result
+    }
 }
 
 impl Partition {
@@ -364,6 +676,8 @@ impl Default for Partition {
             maximum_cpus_per_job: None,
             memory_per_cpu: None,
             cpus_per_node: None,
+            cores_per_socket: None,
+            sockets_per_node: None,
             require_cpus_multiple_of: None,
             warn_cpus_not_multiple_of: None,
             minimum_gpus_per_job: None,
@@ -405,6 +719,13 @@ mod tests {
                 scheduler: SchedulerType::Bash,
                 partition: Vec::new(),
                 submit_options: Vec::new(),
+                mpi_launcher: None,
+                qos: Vec::new(),
+                bash: None,
+                job_url_template: None,
+                kubernetes: None,
+                max_submissions_per_minute: None,
+                auto_select_strategy: None,
             },
             Cluster {
                 name: "cluster1".into(),
@@ -412,6 +733,13 @@ mod tests {
                 scheduler: SchedulerType::Bash,
                 partition: Vec::new(),
                 submit_options: Vec::new(),
+                mpi_launcher: None,
+                qos: Vec::new(),
+                bash: None,
+                job_url_template: None,
+                kubernetes: None,
+                max_submissions_per_minute: None,
+                auto_select_strategy: None,
             },
             Cluster {
                 name: "cluster2".into(),
@@ -419,6 +747,13 @@ mod tests {
                 scheduler: SchedulerType::Bash,
                 partition: Vec::new(),
                 submit_options: Vec::new(),
+                mpi_launcher: None,
+                qos: Vec::new(),
+                bash: None,
+                job_url_template: None,
+                kubernetes: None,
+                max_submissions_per_minute: None,
+                auto_select_strategy: None,
             },
             Cluster {
                 name: "cluster3".into(),
@@ -426,6 +761,13 @@ mod tests {
                 scheduler: SchedulerType::Bash,
                 partition: Vec::new(),
                 submit_options: Vec::new(),
+                mpi_launcher: None,
+                qos: Vec::new(),
+                bash: None,
+                job_url_template: None,
+                kubernetes: None,
+                max_submissions_per_minute: None,
+                auto_select_strategy: None,
             },
             Cluster {
                 name: "cluster4".into(),
@@ -433,6 +775,13 @@ mod tests {
                 scheduler: SchedulerType::Bash,
                 partition: Vec::new(),
                 submit_options: Vec::new(),
+                mpi_launcher: None,
+                qos: Vec::new(),
+                bash: None,
+                job_url_template: None,
+                kubernetes: None,
+                max_submissions_per_minute: None,
+                auto_select_strategy: None,
             },
         ];
         let cluster_configuration = Configuration { cluster: clusters };
@@ -452,21 +801,34 @@ mod tests {
 
         env::remove_var("_row_select");
         assert_eq!(
-            cluster_configuration.clone().identify(None).unwrap(),
+            cluster_configuration.clone().auto_identify().unwrap(),
             cluster_configuration.cluster[3]
         );
 
         env::set_var("_row_select", "b");
         assert_eq!(
-            cluster_configuration.clone().identify(None).unwrap(),
+            cluster_configuration.clone().auto_identify().unwrap(),
             cluster_configuration.cluster[2]
         );
 
         env::set_var("_row_select", "a");
+        assert_eq!(
+            cluster_configuration.clone().auto_identify().unwrap(),
+            cluster_configuration.cluster[1]
+        );
+
+        // identify caches the automatically identified cluster.
+        *IDENTIFIED_CLUSTER.lock().unwrap() = None;
         assert_eq!(
             cluster_configuration.clone().identify(None).unwrap(),
             cluster_configuration.cluster[1]
         );
+        env::set_var("_row_select", "b");
+        assert_eq!(
+            cluster_configuration.clone().identify(None).unwrap(),
+            cluster_configuration.cluster[1]
+        );
+        env::set_var("_row_select", "a");
 
         assert_eq!(
             cluster_configuration
@@ -475,6 +837,37 @@ mod tests {
                 .unwrap(),
             cluster_configuration.cluster[0]
         );
+
+        let explanation = cluster_configuration.explain_identification(None);
+        let reasons: Vec<(bool, &str)> = explanation
+            .iter()
+            .map(|i| (i.selected, i.reason.as_str()))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (false, "always = false: select it with --cluster"),
+                (true, "$_row_select is 'a'"),
+                (false, "not checked: 'cluster1' matched first"),
+                (false, "not checked: 'cluster1' matched first"),
+                (false, "not checked: 'cluster1' matched first"),
+            ]
+        );
+
+        env::set_var("_row_select", "c");
+        let explanation = cluster_configuration.explain_identification(None);
+        assert_eq!(explanation[1].reason, "$_row_select is 'c', not 'a'");
+        assert!(explanation[3].selected);
+        assert_eq!(
+            explanation[4].reason,
+            "not checked: 'cluster3' matched first"
+        );
+
+        env::remove_var("_row_select");
+        let explanation = cluster_configuration.explain_identification(Some("cluster4"));
+        assert!(explanation[4].selected);
+        assert!(!explanation[3].selected);
+        assert_eq!(explanation[4].reason, "selected by --cluster=cluster4");
     }
 
     #[test]
@@ -601,6 +994,13 @@ mod tests {
             scheduler: SchedulerType::Bash,
             partition: partitions,
             submit_options: Vec::new(),
+            mpi_launcher: None,
+            qos: Vec::new(),
+            bash: None,
+            job_url_template: None,
+            kubernetes: None,
+            max_submissions_per_minute: None,
+            auto_select_strategy: None,
         };
 
         let cpu_resources = Resources {
@@ -694,22 +1094,255 @@ mod tests {
         ));
     }
 
+    #[test]
+    #[parallel]
+    fn selectable_partitions() {
+        setup();
+
+        let partitions = vec![
+            Partition {
+                name: "cpu".into(),
+                maximum_cpus_per_job: Some(10),
+                maximum_gpus_per_job: Some(0),
+                ..Partition::default()
+            },
+            Partition {
+                name: "gpu".into(),
+                maximum_gpus_per_job: Some(10),
+                minimum_gpus_per_job: Some(1),
+                ..Partition::default()
+            },
+            Partition {
+                name: "other".into(),
+                maximum_cpus_per_job: Some(20),
+                maximum_gpus_per_job: Some(20),
+                ..Partition::default()
+            },
+        ];
+
+        let mut cluster = Cluster {
+            name: "cluster".into(),
+            identify: IdentificationMethod::Always(true),
+            scheduler: SchedulerType::Bash,
+            partition: partitions,
+            submit_options: Vec::new(),
+            mpi_launcher: None,
+            qos: Vec::new(),
+            bash: None,
+            job_url_template: None,
+            kubernetes: None,
+            max_submissions_per_minute: None,
+            auto_select_strategy: None,
+        };
+
+        let cpu_resources = Resources {
+            processes: Some(Processes::PerDirectory(1)),
+            ..Resources::default()
+        };
+
+        let names = |partitions: Vec<&Partition>| {
+            partitions
+                .iter()
+                .map(|p| p.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(cluster.selectable_partitions(None, &cpu_resources, Some(10))),
+            vec!["cpu"]
+        );
+        assert_eq!(
+            names(cluster.selectable_partitions(None, &cpu_resources, None)),
+            vec!["cpu", "other"]
+        );
+        assert_eq!(
+            names(cluster.selectable_partitions(Some("gpu"), &cpu_resources, None)),
+            vec!["gpu"]
+        );
+
+        cluster.auto_select_strategy = Some(AutoSelectStrategy::MostIdle);
+        assert_eq!(
+            names(cluster.selectable_partitions(None, &cpu_resources, Some(1))),
+            vec!["cpu", "other"]
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn select_partition() {
+        setup();
+
+        let cluster: Cluster = toml::from_str(
+            r#"
+name = "cluster"
+identify.always = true
+scheduler = "slurm"
+auto_select_strategy = "most_idle"
+
+[[partition]]
+name = "a"
+
+[[partition]]
+name = "b"
+
+[[partition]]
+name = "c"
+prevent_auto_select = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            cluster.auto_select_strategy,
+            Some(AutoSelectStrategy::MostIdle)
+        );
+
+        let resources = Resources::default();
+        let idle = HashMap::from([
+            ("a".to_string(), 4),
+            ("b".to_string(), 8),
+            ("c".to_string(), 100),
+        ]);
+        let select = |name, idle| {
+            cluster
+                .select_partition(name, &resources, 1, idle)
+                .unwrap()
+                .name
+                .clone()
+        };
+
+        assert_eq!(select(None, Some(&idle)), "b");
+        assert_eq!(select(Some("a"), Some(&idle)), "a");
+        assert_eq!(select(None, None), "a");
+
+        let tied = HashMap::from([("a".to_string(), 8), ("b".to_string(), 8)]);
+        assert_eq!(select(None, Some(&tied)), "a");
+
+        let first = Cluster {
+            auto_select_strategy: None,
+            ..cluster.clone()
+        };
+        assert_eq!(
+            first
+                .select_partition(None, &resources, 1, Some(&idle))
+                .unwrap()
+                .name,
+            "a"
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn built_in_test_clusters() {
+        setup();
+
+        let cpus = |n| Resources {
+            processes: Some(Processes::PerSubmission(n)),
+            ..Resources::default()
+        };
+        let gpus = |n| Resources {
+            processes: Some(Processes::PerSubmission(n)),
+            gpus_per_process: Some(1),
+            ..Resources::default()
+        };
+        let partition = |cluster: &Cluster, resources: &Resources| {
+            cluster
+                .find_partition(None, resources, 1)
+                .map(|p| p.name.clone())
+        };
+
+        let slurm_cpu = Configuration::built_in()
+            .identify(Some("slurm-cpu"))
+            .unwrap();
+        assert_eq!(slurm_cpu.scheduler, SchedulerType::Slurm);
+        assert_eq!(partition(&slurm_cpu, &cpus(1)).unwrap(), "shared");
+        assert_eq!(partition(&slurm_cpu, &cpus(128)).unwrap(), "wholenode");
+        assert!(matches!(
+            partition(&slurm_cpu, &cpus(100)),
+            Err(Error::PartitionNotFound(_))
+        ));
+        assert!(partition(&slurm_cpu, &gpus(1)).is_err());
+
+        let slurm_gpu = Configuration::built_in()
+            .identify(Some("slurm-gpu"))
+            .unwrap();
+        assert_eq!(partition(&slurm_gpu, &cpus(4)).unwrap(), "cpu");
+        assert_eq!(partition(&slurm_gpu, &gpus(1)).unwrap(), "gpu-single");
+        assert_eq!(partition(&slurm_gpu, &gpus(8)).unwrap(), "gpu");
+    }
+
     #[test]
     #[parallel]
     fn open_no_file() {
         setup();
         let temp = TempDir::new().unwrap().child("clusters.json");
-        let clusters = Configuration::open_from_path(temp.path().into()).expect("valid clusters");
+        let clusters = Configuration::open_from_paths(temp.path(), None).expect("valid clusters");
         assert_eq!(clusters, Configuration::built_in());
     }
 
+    #[test]
+    #[parallel]
+    fn open_project_file() {
+        setup();
+        let temp = TempDir::new().unwrap();
+        let user = temp.child("user.toml");
+        user.write_str(
+            r#"
+[[cluster]]
+name = "a"
+identify.always = false
+scheduler = "bash"
+[[cluster.partition]]
+name = "user"
+
+[[cluster]]
+name = "b"
+identify.always = false
+scheduler = "bash"
+[[cluster.partition]]
+name = "user"
+"#,
+        )
+        .unwrap();
+        let project = temp.child("project.toml");
+        project
+            .write_str(
+                r#"
+[[cluster]]
+name = "a"
+identify.always = false
+scheduler = "bash"
+[[cluster.partition]]
+name = "project"
+"#,
+            )
+            .unwrap();
+
+        let clusters = Configuration::open_from_paths(user.path(), Some(project.path())).unwrap();
+        assert_eq!(
+            clusters.cluster.len(),
+            Configuration::built_in().cluster.len() + 3
+        );
+        let a = clusters.clone().identify(Some("a")).unwrap();
+        assert_eq!(a.partition[0].name, "project");
+        let b = clusters.identify(Some("b")).unwrap();
+        assert_eq!(b.partition[0].name, "user");
+
+        let missing = temp.child("missing.toml");
+        let clusters =
+            Configuration::open_from_paths(missing.path(), Some(project.path())).unwrap();
+        assert_eq!(
+            clusters.identify(Some("a")).unwrap().partition[0].name,
+            "project"
+        );
+    }
+
     #[test]
     #[parallel]
     fn open_empty_file() {
         setup();
         let temp = TempDir::new().unwrap().child("clusters.json");
         temp.write_str("").unwrap();
-        let clusters = Configuration::open_from_path(temp.path().into()).expect("valid clusters");
+        let clusters = Configuration::open_from_paths(temp.path(), None).expect("valid clusters");
         assert_eq!(clusters, Configuration::built_in());
     }
 
@@ -730,7 +1363,7 @@ name = "b"
 "#,
         )
         .unwrap();
-        let clusters = Configuration::open_from_path(temp.path().into()).unwrap();
+        let clusters = Configuration::open_from_paths(temp.path(), None).unwrap();
         let built_in_clusters = Configuration::built_in();
         assert_eq!(clusters.cluster.len(), 1 + built_in_clusters.cluster.len());
 
@@ -760,6 +1393,9 @@ name = "a"
 identify.by_environment = ["b", "c"]
 scheduler = "slurm"
 submit_options = ["option1", "option2"]
+qos = ["normal", "high"]
+bash.preamble = "set -e"
+max_submissions_per_minute = 30
 
 [[cluster.partition]]
 name = "d"
@@ -773,12 +1409,14 @@ require_gpus_multiple_of = 32
 warn_gpus_not_multiple_of = 32
 memory_per_gpu = "f"
 cpus_per_node = 10
+cores_per_socket = 5
+sockets_per_node = 2
 gpus_per_node = 11
 account_suffix = "-gpu"
 "#,
         )
         .unwrap();
-        let clusters = Configuration::open_from_path(temp.path().into()).unwrap();
+        let clusters = Configuration::open_from_paths(temp.path(), None).unwrap();
         let built_in_clusters = Configuration::built_in();
         assert_eq!(clusters.cluster.len(), 1 + built_in_clusters.cluster.len());
 
@@ -790,6 +1428,19 @@ account_suffix = "-gpu"
         );
         assert_eq!(cluster.scheduler, SchedulerType::Slurm);
         assert_eq!(cluster.submit_options, vec!["option1", "option2"]);
+        assert_eq!(cluster.qos, vec!["normal", "high"]);
+        assert_eq!(
+            cluster.bash,
+            Some(BashOptions {
+                preamble: Some("set -e".into())
+            })
+        );
+        assert_eq!(cluster.max_submissions_per_minute, NonZeroU32::new(30));
+        assert!(cluster.check_qos("high").is_ok());
+        assert!(matches!(
+            cluster.check_qos("debug"),
+            Err(Error::QosNotAllowed(..))
+        ));
         assert_eq!(
             cluster.partition,
             vec![Partition {
@@ -806,9 +1457,68 @@ account_suffix = "-gpu"
                 memory_per_gpu: Some("f".into()),
                 prevent_auto_select: false,
                 cpus_per_node: Some(10),
+                cores_per_socket: Some(5),
+                sockets_per_node: Some(2),
                 gpus_per_node: Some(11),
                 account_suffix: Some("-gpu".into()),
             }]
         );
     }
+
+    #[test]
+    #[parallel]
+    fn zero_submissions_per_minute() {
+        setup();
+        let temp = TempDir::new().unwrap().child("clusters.json");
+        temp.write_str(
+            r#"
+[[cluster]]
+name = "a"
+identify.always = true
+scheduler = "slurm"
+max_submissions_per_minute = 0
+"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            Configuration::open_from_paths(temp.path(), None),
+            Err(Error::TOMLParse(..))
+        ));
+    }
+
+    #[test]
+    #[parallel]
+    fn kubernetes_cluster() {
+        setup();
+        let temp = TempDir::new().unwrap().child("clusters.json");
+        temp.write_str(
+            r#"
+[[cluster]]
+name = "a"
+identify.always = false
+scheduler = "kubernetes"
+kubernetes.image = "python:3.12"
+kubernetes.namespace = "b"
+kubernetes.volume_claim = "c"
+kubernetes.row_executable = "/opt/bin/row"
+
+[[cluster.partition]]
+name = "d"
+"#,
+        )
+        .unwrap();
+        let clusters = Configuration::open_from_paths(temp.path(), None).unwrap();
+
+        let cluster = clusters.cluster.first().unwrap();
+        assert_eq!(cluster.scheduler, SchedulerType::Kubernetes);
+        assert_eq!(
+            cluster.kubernetes,
+            Some(KubernetesOptions {
+                image: "python:3.12".into(),
+                namespace: Some("b".into()),
+                volume_claim: Some("c".into()),
+                row_executable: Some("/opt/bin/row".into()),
+            })
+        );
+    }
 }
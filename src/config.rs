@@ -0,0 +1,372 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use log::{debug, trace};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::num::NonZeroU16;
+use std::path::{Path, PathBuf};
+
+use crate::format::TimeFormat;
+use crate::{
+    Error, CONFIG_FILE_NAME, DATA_DIRECTORY_NAME, TOML_WORKFLOW_FILE_NAME, YAML_WORKFLOW_FILE_NAME,
+};
+
+/// The names of all settings.
+pub const KEYS: [&str; 4] = ["cluster", "color", "io_threads", "time_format"];
+
+/// When to print colored output.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Color {
+    Auto,
+    Always,
+    Never,
+}
+
+/// Defaults for command line options.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    /// The default for `--cluster`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cluster: Option<String>,
+
+    /// The default for `--color`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<Color>,
+
+    /// The default for `--io-threads`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub io_threads: Option<NonZeroU16>,
+
+    /// The default for `--time-format`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_format: Option<TimeFormat>,
+}
+
+/// The file that stores a setting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scope {
+    /// `$HOME/.config/row/config.toml`.
+    User,
+
+    /// `.row/config.toml` in the project.
+    Project,
+}
+
+/// User and project settings.
+///
+/// Settings in the project file take precedence over those in the user file.
+/// Command line options and environment variables take precedence over both.
+///
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    /// Settings in the user file.
+    pub user: Settings,
+
+    /// Settings in the project file.
+    pub project: Settings,
+
+    /// The path to the user file.
+    user_path: PathBuf,
+
+    /// The path to the project file (when in a project).
+    project_path: Option<PathBuf>,
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Color::Auto => write!(f, "auto"),
+            Color::Always => write!(f, "always"),
+            Color::Never => write!(f, "never"),
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scope::User => write!(f, "user"),
+            Scope::Project => write!(f, "project"),
+        }
+    }
+}
+
+impl Settings {
+    /// Get the value of a setting as a string.
+    ///
+    /// # Errors
+    /// Returns `Err(row::Error)` when `key` is not a setting.
+    ///
+    pub fn get(&self, key: &str) -> Result<Option<String>, Error> {
+        match key {
+            "cluster" => Ok(self.cluster.clone()),
+            "color" => Ok(self.color.map(|c| c.to_string())),
+            "io_threads" => Ok(self.io_threads.map(|n| n.to_string())),
+            "time_format" => Ok(self.time_format.map(|t| t.to_string())),
+            _ => Err(Error::InvalidConfigKey(key.into())),
+        }
+    }
+
+    /// Set (or unset when `value` is `None`) a setting.
+    ///
+    /// # Errors
+    /// Returns `Err(row::Error)` when `key` is not a setting or `value` is not
+    /// valid for it.
+    ///
+    pub fn set(&mut self, key: &str, value: Option<&str>) -> Result<(), Error> {
+        let invalid = |value: &str, expected: &str| {
+            Error::InvalidConfigValue(key.into(), value.into(), expected.into())
+        };
+
+        match key {
+            "cluster" => self.cluster = value.map(Into::into),
+            "color" => {
+                self.color = value
+                    .map(|v| match v {
+                        "auto" => Ok(Color::Auto),
+                        "always" => Ok(Color::Always),
+                        "never" => Ok(Color::Never),
+                        _ => Err(invalid(v, "expected auto, always, or never")),
+                    })
+                    .transpose()?;
+            }
+            "io_threads" => {
+                self.io_threads = value
+                    .map(|v| {
+                        v.parse::<NonZeroU16>()
+                            .map_err(|_| invalid(v, "expected a positive integer"))
+                    })
+                    .transpose()?;
+            }
+            "time_format" => {
+                self.time_format = value
+                    .map(|v| match v {
+                        "human" => Ok(TimeFormat::Human),
+                        "clock" => Ok(TimeFormat::Clock),
+                        _ => Err(invalid(v, "expected human or clock")),
+                    })
+                    .transpose()?;
+            }
+            _ => return Err(Error::InvalidConfigKey(key.into())),
+        }
+
+        Ok(())
+    }
+
+    /// Read settings from a file.
+    ///
+    /// # Returns
+    /// Default settings when the file does not exist.
+    ///
+    fn read(path: &Path) -> Result<Self, Error> {
+        match fs::read_to_string(path) {
+            Ok(contents) => {
+                trace!("Parsing '{}'.", path.display());
+                toml::from_str(&contents).map_err(|e| Error::TOMLParse(path.into(), e))
+            }
+            Err(error) => match error.kind() {
+                io::ErrorKind::NotFound => {
+                    trace!("'{}' does not exist.", path.display());
+                    Ok(Self::default())
+                }
+                _ => Err(Error::FileRead(path.into(), error)),
+            },
+        }
+    }
+
+    /// Write settings to a file, creating its parent directory when needed.
+    fn write(&self, path: &Path) -> Result<(), Error> {
+        debug!("Saving '{}'.", path.display());
+        let contents = toml::to_string(self).map_err(|e| Error::TOMLSerialize(path.into(), e))?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| Error::DirectoryCreate(parent.into(), e))?;
+        }
+        fs::write(path, contents).map_err(|e| Error::FileWrite(path.into(), e))
+    }
+}
+
+impl Config {
+    /// Open the user and project settings.
+    ///
+    /// Read `$HOME/.config/row/config.toml` and `.row/config.toml` in the
+    /// project that contains the current working directory (if any).
+    ///
+    /// # Errors
+    /// Returns `Err(row::Error)` when a file cannot be read or parsed.
+    ///
+    pub fn open() -> Result<Self, Error> {
+        let home = match env::var("ROW_HOME") {
+            Ok(row_home) => PathBuf::from(row_home),
+            Err(_) => home::home_dir().ok_or_else(Error::NoHome)?,
+        };
+        let user_path = home.join(".config").join("row").join(CONFIG_FILE_NAME);
+
+        let current_dir = env::current_dir()?;
+        let project_path = current_dir
+            .ancestors()
+            .find(|p| {
+                p.join(TOML_WORKFLOW_FILE_NAME).is_file()
+                    || p.join(YAML_WORKFLOW_FILE_NAME).is_file()
+            })
+            .map(|p| p.join(DATA_DIRECTORY_NAME).join(CONFIG_FILE_NAME));
+
+        Self::open_from_paths(user_path, project_path)
+    }
+
+    fn open_from_paths(user_path: PathBuf, project_path: Option<PathBuf>) -> Result<Self, Error> {
+        let user = Settings::read(&user_path)?;
+        let project = match &project_path {
+            Some(path) => Settings::read(path)?,
+            None => Settings::default(),
+        };
+
+        Ok(Self {
+            user,
+            project,
+            user_path,
+            project_path,
+        })
+    }
+
+    /// Get the settings in the given scope.
+    pub fn scope(&self, scope: Scope) -> &Settings {
+        match scope {
+            Scope::User => &self.user,
+            Scope::Project => &self.project,
+        }
+    }
+
+    /// Get the effective value of a setting and the scope that sets it.
+    ///
+    /// # Errors
+    /// Returns `Err(row::Error)` when `key` is not a setting.
+    ///
+    pub fn get(&self, key: &str) -> Result<Option<(String, Scope)>, Error> {
+        if let Some(value) = self.project.get(key)? {
+            return Ok(Some((value, Scope::Project)));
+        }
+        Ok(self.user.get(key)?.map(|value| (value, Scope::User)))
+    }
+
+    /// Get the effective settings.
+    pub fn settings(&self) -> Settings {
+        Settings {
+            cluster: self.project.cluster.clone().or(self.user.cluster.clone()),
+            color: self.project.color.or(self.user.color),
+            io_threads: self.project.io_threads.or(self.user.io_threads),
+            time_format: self.project.time_format.or(self.user.time_format),
+        }
+    }
+
+    /// Set (or unset when `value` is `None`) a setting and save the file.
+    ///
+    /// # Errors
+    /// Returns `Err(row::Error)` when `key` is not a setting, `value` is not
+    /// valid, the project scope is selected outside of a project, or the file
+    /// cannot be written.
+    ///
+    pub fn set(&mut self, scope: Scope, key: &str, value: Option<&str>) -> Result<(), Error> {
+        match scope {
+            Scope::User => {
+                self.user.set(key, value)?;
+                self.user.write(&self.user_path)
+            }
+            Scope::Project => {
+                let path = self.project_path.as_ref().ok_or(Error::WorkflowNotFound)?;
+                self.project.set(key, value)?;
+                self.project.write(path)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+    use assert_fs::TempDir;
+    use serial_test::parallel;
+
+    #[test]
+    #[parallel]
+    fn precedence() {
+        let temp = TempDir::new().unwrap();
+        let user_path = temp.child("user.toml");
+        user_path
+            .write_str("cluster = \"a\"\ncolor = \"never\"\ntime_format = \"clock\"\n")
+            .unwrap();
+        let project_path = temp.child("project").child("config.toml");
+
+        let mut config =
+            Config::open_from_paths(user_path.path().into(), Some(project_path.path().into()))
+                .unwrap();
+        assert_eq!(
+            config.get("cluster").unwrap(),
+            Some(("a".into(), Scope::User))
+        );
+        assert_eq!(config.get("io_threads").unwrap(), None);
+
+        config.set(Scope::Project, "cluster", Some("b")).unwrap();
+        config.set(Scope::Project, "io_threads", Some("4")).unwrap();
+        project_path.assert("cluster = \"b\"\nio_threads = 4\n");
+        assert_eq!(
+            config.get("cluster").unwrap(),
+            Some(("b".into(), Scope::Project))
+        );
+
+        let settings = config.settings();
+        assert_eq!(settings.cluster.as_deref(), Some("b"));
+        assert_eq!(settings.color, Some(Color::Never));
+        assert_eq!(settings.io_threads, NonZeroU16::new(4));
+        assert_eq!(settings.time_format, Some(TimeFormat::Clock));
+
+        config.set(Scope::Project, "cluster", None).unwrap();
+        project_path.assert("io_threads = 4\n");
+        assert_eq!(
+            config.get("cluster").unwrap(),
+            Some(("a".into(), Scope::User))
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn invalid() {
+        let temp = TempDir::new().unwrap();
+        let mut config =
+            Config::open_from_paths(temp.child("user.toml").path().into(), None).unwrap();
+
+        assert!(matches!(
+            config.get("colour"),
+            Err(Error::InvalidConfigKey(_))
+        ));
+        assert!(matches!(
+            config.set(Scope::User, "color", Some("sometimes")),
+            Err(Error::InvalidConfigValue(..))
+        ));
+        assert!(matches!(
+            config.set(Scope::User, "io_threads", Some("0")),
+            Err(Error::InvalidConfigValue(..))
+        ));
+        assert!(matches!(
+            config.set(Scope::User, "time_format", Some("hours")),
+            Err(Error::InvalidConfigValue(..))
+        ));
+        assert!(matches!(
+            config.set(Scope::Project, "cluster", Some("a")),
+            Err(Error::WorkflowNotFound)
+        ));
+
+        temp.child("bad.toml").write_str("colour = 1\n").unwrap();
+        assert!(matches!(
+            Config::open_from_paths(temp.child("bad.toml").path().into(), None),
+            Err(Error::TOMLParse(..))
+        ));
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+//! Process exit codes.
+//!
+//! **Row** exits with a distinct code for each class of failure so that scripts
+//! can branch on the result.
+
+use crate::Error;
+
+/// The command completed successfully.
+pub const SUCCESS: u8 = 0;
+
+/// An error that does not belong to another class.
+pub const FAILURE: u8 = 1;
+
+/// The command line arguments are invalid.
+pub const USAGE: u8 = 2;
+
+/// The workflow, cluster, launcher, or other configuration is invalid.
+pub const CONFIG: u8 = 3;
+
+/// The scheduler failed or rejected a job.
+pub const SCHEDULER: u8 = 4;
+
+/// There is nothing to do (reported only with `--quiet`).
+pub const NOTHING_TO_DO: u8 = 5;
+
+/// The user interrupted **row**.
+pub const INTERRUPTED: u8 = 130;
+
+/// Choose the exit code for an error.
+pub fn from_error(error: &Error) -> u8 {
+    match error {
+        Error::TOMLParse(..)
+        | Error::YAMLParse(..)
+        | Error::InvalidComponent(..)
+        | Error::InvalidConcurrentDirectories(..)
+        | Error::InvalidLogOutput(..)
+        | Error::InvalidJobName(..)
+        | Error::SubmitCommandMissingScript(..)
+        | Error::InvalidProduct(..)
+        | Error::PreviousActionNotFound(..)
+        | Error::InvalidPreviousFraction(..)
+        | Error::IncludeActionNotFound(..)
+        | Error::PreviousProjectNotFound(..)
+        | Error::DuplicateProcesses(..)
+        | Error::DuplicateLauncherPlaceholder(..)
+        | Error::ActionContainsMultipleTemplates(..)
+        | Error::ActionContainsNoTemplate(..)
+        | Error::WorkflowNotFound
+        | Error::InvalidConfigKey(..)
+        | Error::InvalidConfigValue(..)
+        | Error::MultipleWorkflowFiles(..)
+        | Error::NestedGenerator(..)
+        | Error::MembershipNotArray(..)
+        | Error::PatternNotString(..)
+        | Error::InvalidPattern(..)
+        | Error::ActionMissingName(..)
+        | Error::ActionMissingCommand(..)
+        | Error::DefaultActionSetsFrom()
+        | Error::FromActionNotFound(..)
+        | Error::RecursiveFrom(..)
+        | Error::ResourcePresetNotFound(..)
+        | Error::NestedResourcePreset(..)
+        | Error::DuplicateActionsDifferentProducts(..)
+        | Error::DuplicateActionsDifferentPreviousActions(..)
+        | Error::UnsupportedWorkspaceUrl(..)
+        | Error::WorkspaceUrlNotEnabled(..)
+        | Error::ConsolidatedValueFileMissing
+        | Error::KubernetesImageMissing(..)
+        | Error::JobTemplate(..)
+        | Error::LauncherMissingDefault(..)
+        | Error::LauncherNotFound(..)
+        | Error::MpiLauncherNotFound(..)
+        | Error::NoProcessLauncher(..)
+        | Error::TooManyProcessLaunchers(..)
+        | Error::CheckFailed(..)
+        | Error::ClusterNameNotFound(..)
+        | Error::ClusterNotFound()
+        | Error::PartitionNameNotFound(..)
+        | Error::PartitionNotFound(..)
+        | Error::QosNotAllowed(..) => CONFIG,
+
+        Error::SubmitAction(..)
+        | Error::SbatchRejected(..)
+        | Error::UnexpectedOutput(..)
+        | Error::ExecuteSqueue(..)
+        | Error::QueueTimeout(..) => SCHEDULER,
+
+        Error::NothingToDo => NOTHING_TO_DO,
+        Error::Interrupted => INTERRUPTED,
+        _ => FAILURE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::parallel;
+
+    use super::*;
+
+    #[test]
+    #[parallel]
+    fn classes() {
+        assert_eq!(from_error(&Error::WorkflowNotFound), CONFIG);
+        assert_eq!(from_error(&Error::ClusterNotFound()), CONFIG);
+        assert_eq!(
+            from_error(&Error::SubmitAction("a".into(), "b".into())),
+            SCHEDULER
+        );
+        assert_eq!(from_error(&Error::NothingToDo), NOTHING_TO_DO);
+        assert_eq!(from_error(&Error::Interrupted), INTERRUPTED);
+        assert_eq!(
+            from_error(&Error::ActionNotFound("a".into(), String::new())),
+            FAILURE
+        );
+    }
+}
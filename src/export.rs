@@ -0,0 +1,232 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+//! Write and read reproducibility bundles.
+//!
+//! A bundle is an uncompressed tar archive (in the POSIX ustar format) that
+//! holds the workflow, the effective configuration, and the cached state of a
+//! project.
+
+use sha2::{Digest, Sha256};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Size of a tar block.
+const BLOCK_SIZE: usize = 512;
+
+/// Maximum length of a file name in a ustar header (without a prefix).
+const MAXIMUM_NAME_LENGTH: usize = 100;
+
+/// Write regular files to a tar archive.
+pub struct TarWriter<W: Write> {
+    inner: W,
+
+    /// Modification time of every file in seconds since the UNIX epoch.
+    mtime: u64,
+}
+
+impl<W: Write> TarWriter<W> {
+    /// Write an archive to `inner`.
+    pub fn new(inner: W, mtime: u64) -> Self {
+        Self { inner, mtime }
+    }
+
+    /// Append a regular file to the archive.
+    ///
+    /// # Errors
+    /// Returns `Err(io::Error)` when `name` is too long for the header or on
+    /// I/O errors.
+    ///
+    pub fn append(&mut self, name: &str, contents: &[u8]) -> io::Result<()> {
+        if name.is_empty() || name.len() >= MAXIMUM_NAME_LENGTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("'{name}' is not a valid tar file name"),
+            ));
+        }
+
+        let mut header = [0u8; BLOCK_SIZE];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        write_octal(&mut header[100..108], 0o644);
+        write_octal(&mut header[108..116], 0);
+        write_octal(&mut header[116..124], 0);
+        write_octal(&mut header[124..136], contents.len() as u64);
+        write_octal(&mut header[136..148], self.mtime);
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+
+        // Compute the checksum with the checksum field set to spaces.
+        header[148..156].fill(b' ');
+        let checksum: u64 = header.iter().map(|b| u64::from(*b)).sum();
+        write_octal(&mut header[148..155], checksum);
+
+        self.inner.write_all(&header)?;
+        self.inner.write_all(contents)?;
+        let padding = (BLOCK_SIZE - contents.len() % BLOCK_SIZE) % BLOCK_SIZE;
+        self.inner.write_all(&[0u8; BLOCK_SIZE][..padding])
+    }
+
+    /// Write the end of archive marker.
+    ///
+    /// # Errors
+    /// Returns `Err(io::Error)` on I/O errors.
+    ///
+    pub fn finish(mut self) -> io::Result<W> {
+        self.inner.write_all(&[0u8; 2 * BLOCK_SIZE])?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Write `value` as a NUL-terminated, zero-padded octal number that fills `field`.
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = field.len() - 1;
+    let text = format!("{value:0digits$o}");
+    field[..digits].copy_from_slice(&text.as_bytes()[text.len() - digits..]);
+    field[digits] = 0;
+}
+
+/// Read the regular files in a tar archive.
+///
+/// Skip entries of other types (such as directories).
+///
+/// # Returns
+/// `Ok(Vec<(name, contents)>)` in the order they appear in the archive.
+///
+/// # Errors
+/// Returns `Err(io::Error)` when `bytes` is not a valid tar archive.
+///
+pub fn read_tar(bytes: &[u8]) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+    let mut result = Vec::new();
+    let mut offset = 0;
+    while offset + BLOCK_SIZE <= bytes.len() {
+        let header = &bytes[offset..offset + BLOCK_SIZE];
+        if header.iter().all(|b| *b == 0) {
+            return Ok(result);
+        }
+
+        let mut unsigned = header.to_vec();
+        unsigned[148..156].fill(b' ');
+        let sum: u64 = unsigned.iter().map(|b| u64::from(*b)).sum();
+        if read_octal(&header[148..156]) != Some(sum) {
+            return Err(invalid("invalid header checksum"));
+        }
+
+        let size = read_octal(&header[124..136]).ok_or_else(|| invalid("invalid file size"))?;
+        let size = usize::try_from(size).map_err(|_| invalid("invalid file size"))?;
+        let start = offset + BLOCK_SIZE;
+        let end = start
+            .checked_add(size)
+            .filter(|end| *end <= bytes.len())
+            .ok_or_else(|| invalid("unexpected end of archive"))?;
+
+        if matches!(header[156], b'0' | 0) {
+            // Only ustar headers have a name prefix.
+            let mut name = if header[257..262] == *b"ustar" {
+                String::from_utf8_lossy(field(&header[345..500])).into_owned()
+            } else {
+                String::new()
+            };
+            if !name.is_empty() {
+                name.push('/');
+            }
+            name.push_str(&String::from_utf8_lossy(field(&header[..100])));
+            result.push((name, bytes[start..end].to_vec()));
+        }
+
+        offset = start + size.div_ceil(BLOCK_SIZE) * BLOCK_SIZE;
+    }
+
+    Err(invalid("missing end of archive marker"))
+}
+
+/// Get the bytes of a NUL-terminated header field.
+fn field(bytes: &[u8]) -> &[u8] {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    &bytes[..end]
+}
+
+/// Parse an octal header field.
+fn read_octal(bytes: &[u8]) -> Option<u64> {
+    let text = std::str::from_utf8(field(bytes)).ok()?;
+    u64::from_str_radix(text.trim_matches(' '), 8).ok()
+}
+
+/// Replace a directory name with a salted hash of the name.
+///
+/// The same name always maps to the same hash with the same `salt`, so the
+/// files in an anonymized bundle remain consistent. Without the salt, the
+/// hashes cannot be matched against a dictionary of likely names.
+///
+pub fn anonymous_name(salt: &[u8], directory: &Path) -> String {
+    let digest = Sha256::new()
+        .chain_update(salt)
+        .chain_update(directory.as_os_str().as_encoded_bytes())
+        .finalize();
+    let mut name = format!("{digest:x}");
+    name.truncate(32);
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::parallel;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    #[parallel]
+    fn archive() {
+        let mut writer = TarWriter::new(Vec::new(), 1_700_000_000);
+        writer.append("bundle/a.txt", b"hello\n").unwrap();
+        writer.append("bundle/empty", b"").unwrap();
+        let bytes = writer.finish().unwrap();
+
+        assert_eq!(bytes.len(), 5 * BLOCK_SIZE);
+
+        let header = &bytes[..BLOCK_SIZE];
+        assert!(header.starts_with(b"bundle/a.txt\0"));
+        assert_eq!(read_octal(&header[124..136]), Some(6));
+        assert_eq!(read_octal(&header[136..148]), Some(1_700_000_000));
+        assert_eq!(&header[257..263], b"ustar\0");
+
+        let mut unsigned = header.to_vec();
+        unsigned[148..156].fill(b' ');
+        let sum: u64 = unsigned.iter().map(|b| u64::from(*b)).sum();
+        assert_eq!(read_octal(&header[148..156]), Some(sum));
+
+        assert_eq!(&bytes[BLOCK_SIZE..BLOCK_SIZE + 6], b"hello\n");
+        assert!(bytes[2 * BLOCK_SIZE..].starts_with(b"bundle/empty\0"));
+        assert!(bytes[3 * BLOCK_SIZE..].iter().all(|b| *b == 0));
+
+        assert_eq!(
+            read_tar(&bytes).unwrap(),
+            vec![
+                ("bundle/a.txt".to_string(), b"hello\n".to_vec()),
+                ("bundle/empty".to_string(), Vec::new()),
+            ]
+        );
+        assert!(read_tar(&bytes[..BLOCK_SIZE + 3]).is_err());
+        let mut corrupt = bytes.clone();
+        corrupt[0] = b'c';
+        assert!(read_tar(&corrupt).is_err());
+
+        let mut writer = TarWriter::new(Vec::new(), 0);
+        assert!(writer.append(&"a".repeat(100), b"").is_err());
+        assert!(writer.append("", b"").is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn names() {
+        let a = anonymous_name(b"salt", &PathBuf::from("dir1"));
+        assert_eq!(a.len(), 32);
+        assert_eq!(a, anonymous_name(b"salt", &PathBuf::from("dir1")));
+        assert_ne!(a, anonymous_name(b"salt", &PathBuf::from("dir2")));
+        assert_ne!(a, anonymous_name(b"pepper", &PathBuf::from("dir1")));
+    }
+}
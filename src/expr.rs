@@ -1,11 +1,17 @@
 // Copyright (c) 2024 The Regents of the University of Michigan.
 // Part of row, released under the BSD 3-Clause License.
 
+use regex::Regex;
 use serde_json::Value;
+use std::cell::RefCell;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::iter;
+use std::path::Path;
+use std::str::FromStr;
 
 use crate::workflow::Comparison;
+use crate::Error;
 
 /// Compares two Values lexicographically.
 ///
@@ -49,16 +55,61 @@ pub(crate) fn partial_cmp_json_values(a: &Value, b: &Value) -> Option<Ordering>
     }
 }
 
-/// Compares two Values lexicographically with the given comparison operator.
+/// Compares two Values lexicographically, ordering `null` before all other values.
 ///
 /// # Returns
 /// `Some(Ordering)` when an ordering can be determined, otherwise `None`.
 ///
+pub(crate) fn partial_cmp_json_values_null_first(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        (Value::Null, _) => Some(Ordering::Less),
+        (_, Value::Null) => Some(Ordering::Greater),
+        (Value::Array(a_array), Value::Array(b_array)) if a_array.len() == b_array.len() => {
+            for (c, d) in iter::zip(a_array, b_array) {
+                match partial_cmp_json_values_null_first(c, d)? {
+                    Ordering::Equal => (),
+                    ordering => return Some(ordering),
+                }
+            }
+            Some(Ordering::Equal)
+        }
+        (_, _) => partial_cmp_json_values(a, b),
+    }
+}
+
+/// Compares two Values lexicographically with the given comparison operator.
+///
+/// `In` and `NotIn` check whether `a` equals any element of the array `b`.
+/// They skip the elements of `b` that cannot be compared with `a`.
+///
+/// # Returns
+/// `Some(bool)` when the comparison can be determined, otherwise `None`.
+///
 pub(crate) fn evaluate_json_comparison(
     comparison: &Comparison,
     a: &Value,
     b: &Value,
 ) -> Option<bool> {
+    if *comparison == Comparison::Matches {
+        return Some(regex_matches(b.as_str()?, a.as_str()?));
+    }
+
+    if matches!(comparison, Comparison::In | Comparison::NotIn) {
+        let mut comparable = false;
+        let mut found = false;
+        for element in b.as_array()? {
+            if let Some(ordering) = partial_cmp_json_values(a, element) {
+                comparable = true;
+                found |= ordering == Ordering::Equal;
+            }
+        }
+        if !comparable {
+            return None;
+        }
+        return Some(found == (*comparison == Comparison::In));
+    }
+
     #[allow(clippy::match_same_arms)]
     match (comparison, partial_cmp_json_values(a, b)) {
         (Comparison::LessThan, Some(Ordering::Less)) => Some(true),
@@ -66,11 +117,353 @@ pub(crate) fn evaluate_json_comparison(
         (Comparison::EqualTo, Some(Ordering::Equal)) => Some(true),
         (Comparison::GreaterThanOrEqualTo, Some(Ordering::Greater | Ordering::Equal)) => Some(true),
         (Comparison::GreaterThan, Some(Ordering::Greater)) => Some(true),
+        (Comparison::NotEqualTo, Some(Ordering::Less | Ordering::Greater)) => Some(true),
         (_, None) => None,
         (_, _) => Some(false),
     }
 }
 
+/// Check whether `text` matches the regular expression `pattern`.
+///
+/// Cache the compiled expressions: conditions apply the same pattern to every
+/// directory in the workspace.
+///
+/// # Returns
+/// `false` when `pattern` is not a valid regular expression.
+///
+fn regex_matches(pattern: &str, text: &str) -> bool {
+    thread_local! {
+        static CACHE: RefCell<HashMap<String, Option<Regex>>> = RefCell::new(HashMap::new());
+    }
+
+    CACHE.with_borrow_mut(|cache| {
+        cache
+            .entry(pattern.to_string())
+            .or_insert_with(|| Regex::new(pattern).ok())
+            .as_ref()
+            .is_some_and(|regex| regex.is_match(text))
+    })
+}
+
+/// A named column computed from each directory's value.
+///
+/// Parse a `ValueColumn` from `NAME=EXPRESSION` or `EXPRESSION`. `EXPRESSION` is
+/// a JSON pointer or an arithmetic expression (`+`, `-`, `*`, `/`, and parentheses)
+/// of JSON pointers and numbers. Separate `-` and `/` operators from JSON pointers
+/// with whitespace.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValueColumn {
+    /// The name shown in the table header.
+    pub name: String,
+
+    /// The expression to evaluate.
+    expression: Expression,
+}
+
+/// Arithmetic expression of JSON pointers.
+#[derive(Clone, Debug, PartialEq)]
+enum Expression {
+    Pointer(String),
+    Number(Number),
+    Negate(Box<Expression>),
+    Binary(Box<Expression>, char, Box<Expression>),
+}
+
+/// Numbers in an arithmetic expression.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Number {
+    Integer(i64),
+    Float(f64),
+}
+
+/// Tokens in an arithmetic expression.
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Operand(String),
+    Pointer(String),
+    Operator(char),
+    Open,
+    Close,
+}
+
+impl FromStr for ValueColumn {
+    type Err = Error;
+
+    fn from_str(column: &str) -> Result<Self, Self::Err> {
+        let (name, expression) = match column.split_once('=') {
+            Some((name, expression)) if !name.starts_with(['/', '"']) => (name, expression),
+            _ => (column, column),
+        };
+
+        let tokens =
+            tokenize(expression).map_err(|e| Error::InvalidValueExpression(column.into(), e))?;
+        let mut position = 0;
+        let parsed = if tokens.is_empty() {
+            Expression::Pointer(String::new())
+        } else {
+            parse_sum(&tokens, &mut position)
+                .map_err(|e| Error::InvalidValueExpression(column.into(), e))?
+        };
+
+        if position < tokens.len() {
+            return Err(Error::InvalidValueExpression(
+                column.into(),
+                "unexpected trailing input".into(),
+            ));
+        }
+
+        Ok(Self {
+            name: name.into(),
+            expression: parsed,
+        })
+    }
+}
+
+impl ValueColumn {
+    /// Get the JSON pointers used by the column.
+    pub fn pointers(&self) -> Vec<String> {
+        let mut result = Vec::new();
+        self.expression.pointers(&mut result);
+        result
+    }
+
+    /// Evaluate the column on the given directory value.
+    ///
+    /// A column with a single JSON pointer evaluates to the element at that
+    /// pointer. All other columns evaluate to a number.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when a JSON pointer is not present or an element used
+    /// in arithmetic is not a number.
+    ///
+    pub fn evaluate(&self, directory: &Path, value: &Value) -> Result<Value, Error> {
+        if let Expression::Pointer(pointer) = &self.expression {
+            return value.pointer(pointer).cloned().ok_or_else(|| {
+                Error::JSONPointerNotFound(directory.to_path_buf(), pointer.clone())
+            });
+        }
+
+        Ok(match self.expression.evaluate(directory, value)? {
+            Number::Integer(i) => Value::from(i),
+            Number::Float(f) => Value::from(f),
+        })
+    }
+}
+
+impl Expression {
+    /// Collect the JSON pointers in the expression.
+    fn pointers(&self, result: &mut Vec<String>) {
+        match self {
+            Expression::Pointer(pointer) => result.push(pointer.clone()),
+            Expression::Number(_) => (),
+            Expression::Negate(a) => a.pointers(result),
+            Expression::Binary(a, _, b) => {
+                a.pointers(result);
+                b.pointers(result);
+            }
+        }
+    }
+
+    /// Evaluate the expression arithmetically.
+    fn evaluate(&self, directory: &Path, value: &Value) -> Result<Number, Error> {
+        match self {
+            Expression::Pointer(pointer) => {
+                let element = value.pointer(pointer).ok_or_else(|| {
+                    Error::JSONPointerNotFound(directory.to_path_buf(), pointer.clone())
+                })?;
+                if let Some(i) = element.as_i64() {
+                    Ok(Number::Integer(i))
+                } else if let Some(f) = element.as_f64() {
+                    Ok(Number::Float(f))
+                } else {
+                    Err(Error::NonNumericValue(
+                        directory.to_path_buf(),
+                        pointer.clone(),
+                    ))
+                }
+            }
+            Expression::Number(number) => Ok(*number),
+            Expression::Negate(a) => Ok(match a.evaluate(directory, value)? {
+                Number::Integer(i) => i
+                    .checked_neg()
+                    .map_or(Number::Float(-(i as f64)), Number::Integer),
+                Number::Float(f) => Number::Float(-f),
+            }),
+            Expression::Binary(a, operator, b) => {
+                let a = a.evaluate(directory, value)?;
+                let b = b.evaluate(directory, value)?;
+                Ok(apply(*operator, a, b))
+            }
+        }
+    }
+}
+
+impl Number {
+    fn as_f64(self) -> f64 {
+        match self {
+            Number::Integer(i) => i as f64,
+            Number::Float(f) => f,
+        }
+    }
+}
+
+/// Apply a binary operator. Integer arithmetic falls back to floats on overflow.
+fn apply(operator: char, a: Number, b: Number) -> Number {
+    if let (Number::Integer(x), Number::Integer(y)) = (a, b) {
+        let result = match operator {
+            '+' => x.checked_add(y),
+            '-' => x.checked_sub(y),
+            '*' => x.checked_mul(y),
+            _ => None,
+        };
+        if let Some(result) = result {
+            return Number::Integer(result);
+        }
+    }
+
+    let (x, y) = (a.as_f64(), b.as_f64());
+    Number::Float(match operator {
+        '+' => x + y,
+        '-' => x - y,
+        '*' => x * y,
+        _ => x / y,
+    })
+}
+
+/// Split an expression into tokens.
+///
+/// A `/` where an operand is expected begins a JSON pointer. JSON pointers
+/// (and other operands) end at whitespace, parentheses, `+`, or `*`. Place a
+/// JSON pointer that contains these characters in double quotes. Escape `"`
+/// and `\` with a backslash in a quoted pointer.
+///
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        let expect_operand = matches!(tokens.last(), None | Some(Token::Operator(_) | Token::Open));
+
+        match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '+' | '*' => {
+                chars.next();
+                tokens.push(Token::Operator(c));
+            }
+            '-' | '/' if !expect_operand => {
+                chars.next();
+                tokens.push(Token::Operator(c));
+            }
+            '-' => {
+                chars.next();
+                tokens.push(Token::Operator('-'));
+            }
+            '"' => {
+                chars.next();
+                let mut pointer = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => pointer.push(c),
+                            _ => return Err("invalid escape in quoted pointer".into()),
+                        },
+                        Some(c) => pointer.push(c),
+                        None => return Err("unterminated quoted pointer".into()),
+                    }
+                }
+                if !pointer.is_empty() && !pointer.starts_with('/') {
+                    return Err(format!("quoted pointer '{pointer}' must start with '/'"));
+                }
+                tokens.push(Token::Pointer(pointer));
+            }
+            _ => {
+                let mut operand = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | '+' | '*') {
+                        break;
+                    }
+                    operand.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Operand(operand));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Parse `term (('+' | '-') term)*`.
+fn parse_sum(tokens: &[Token], position: &mut usize) -> Result<Expression, String> {
+    let mut result = parse_product(tokens, position)?;
+    while let Some(Token::Operator(operator @ ('+' | '-'))) = tokens.get(*position) {
+        *position += 1;
+        let rhs = parse_product(tokens, position)?;
+        result = Expression::Binary(Box::new(result), *operator, Box::new(rhs));
+    }
+    Ok(result)
+}
+
+/// Parse `factor (('*' | '/') factor)*`.
+fn parse_product(tokens: &[Token], position: &mut usize) -> Result<Expression, String> {
+    let mut result = parse_factor(tokens, position)?;
+    while let Some(Token::Operator(operator @ ('*' | '/'))) = tokens.get(*position) {
+        *position += 1;
+        let rhs = parse_factor(tokens, position)?;
+        result = Expression::Binary(Box::new(result), *operator, Box::new(rhs));
+    }
+    Ok(result)
+}
+
+/// Parse `'-' factor | '(' sum ')' | operand`.
+fn parse_factor(tokens: &[Token], position: &mut usize) -> Result<Expression, String> {
+    let token = tokens.get(*position).ok_or("expected an operand")?;
+    *position += 1;
+
+    match token {
+        Token::Operator('-') => Ok(Expression::Negate(Box::new(parse_factor(
+            tokens, position,
+        )?))),
+        Token::Open => {
+            let result = parse_sum(tokens, position)?;
+            if tokens.get(*position) == Some(&Token::Close) {
+                *position += 1;
+                Ok(result)
+            } else {
+                Err("expected ')'".into())
+            }
+        }
+        Token::Operand(operand) => {
+            if operand.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+                if let Ok(i) = operand.parse::<i64>() {
+                    Ok(Expression::Number(Number::Integer(i)))
+                } else if let Ok(f) = operand.parse::<f64>() {
+                    Ok(Expression::Number(Number::Float(f)))
+                } else {
+                    Err(format!("invalid number '{operand}'"))
+                }
+            } else {
+                Ok(Expression::Pointer(operand.clone()))
+            }
+        }
+        Token::Pointer(pointer) => Ok(Expression::Pointer(pointer.clone())),
+        Token::Operator(operator) => Err(format!("unexpected '{operator}'")),
+        Token::Close => Err("unexpected ')'".into()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use serial_test::parallel;
@@ -158,6 +551,34 @@ mod tests {
         assert_eq!(partial_cmp_json_values(&a, &b), None);
     }
 
+    #[test]
+    #[parallel]
+    fn cmp_null_first_json() {
+        assert_eq!(
+            partial_cmp_json_values_null_first(&Value::Null, &Value::from(-1)),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            partial_cmp_json_values_null_first(&Value::from("a"), &Value::Null),
+            Some(Ordering::Greater)
+        );
+
+        let a = Value::Array(vec![Value::from(1), Value::Null]);
+        let b = Value::Array(vec![Value::from(1), Value::from("x")]);
+        assert_eq!(
+            partial_cmp_json_values_null_first(&a, &b),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            partial_cmp_json_values_null_first(&a, &a),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            partial_cmp_json_values_null_first(&Value::from(1), &Value::from("x")),
+            None
+        );
+    }
+
     #[test]
     #[parallel]
     fn eval() {
@@ -226,4 +647,179 @@ mod tests {
             Some(false)
         );
     }
+
+    #[test]
+    #[parallel]
+    fn eval_not_equal_and_membership() {
+        let allowed = Value::from(vec!["npt", "nvt"]);
+        assert_eq!(
+            evaluate_json_comparison(&Comparison::NotEqualTo, &Value::from(5), &Value::from(10)),
+            Some(true)
+        );
+        assert_eq!(
+            evaluate_json_comparison(&Comparison::NotEqualTo, &Value::from(5), &Value::from(5)),
+            Some(false)
+        );
+        assert_eq!(
+            evaluate_json_comparison(&Comparison::NotEqualTo, &Value::from(5), &Value::from("a")),
+            None
+        );
+        assert_eq!(
+            evaluate_json_comparison(&Comparison::In, &Value::from("nvt"), &allowed),
+            Some(true)
+        );
+        assert_eq!(
+            evaluate_json_comparison(&Comparison::In, &Value::from("nve"), &allowed),
+            Some(false)
+        );
+        assert_eq!(
+            evaluate_json_comparison(&Comparison::NotIn, &Value::from("nve"), &allowed),
+            Some(true)
+        );
+        assert_eq!(
+            evaluate_json_comparison(&Comparison::NotIn, &Value::from("npt"), &allowed),
+            Some(false)
+        );
+        assert_eq!(
+            evaluate_json_comparison(
+                &Comparison::In,
+                &Value::from(1),
+                &Value::from(vec![1.0, 2.0])
+            ),
+            Some(true)
+        );
+        assert_eq!(
+            evaluate_json_comparison(&Comparison::In, &Value::from(1), &allowed),
+            None
+        );
+        let mixed = Value::from(vec![Value::from(1), Value::from("auto")]);
+        assert_eq!(
+            evaluate_json_comparison(&Comparison::In, &Value::from("auto"), &mixed),
+            Some(true)
+        );
+        assert_eq!(
+            evaluate_json_comparison(&Comparison::In, &Value::from(1), &mixed),
+            Some(true)
+        );
+        assert_eq!(
+            evaluate_json_comparison(&Comparison::NotIn, &Value::from(2), &mixed),
+            Some(true)
+        );
+        assert_eq!(
+            evaluate_json_comparison(&Comparison::In, &Value::from(true), &mixed),
+            None
+        );
+        assert_eq!(
+            evaluate_json_comparison(&Comparison::In, &Value::from("npt"), &Value::from("npt")),
+            None
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn eval_matches() {
+        let pattern = Value::from("^lj_.*");
+        assert_eq!(
+            evaluate_json_comparison(&Comparison::Matches, &Value::from("lj_fluid"), &pattern),
+            Some(true)
+        );
+        assert_eq!(
+            evaluate_json_comparison(&Comparison::Matches, &Value::from("wca_lj_"), &pattern),
+            Some(false)
+        );
+        assert_eq!(
+            evaluate_json_comparison(&Comparison::Matches, &Value::from(1), &pattern),
+            None
+        );
+        assert_eq!(
+            evaluate_json_comparison(&Comparison::Matches, &Value::from("a"), &Value::from(1)),
+            None
+        );
+        assert!(!regex_matches("(", "("));
+    }
+
+    fn column(s: &str) -> ValueColumn {
+        ValueColumn::from_str(s).unwrap()
+    }
+
+    #[test]
+    #[parallel]
+    fn value_column_pointer() {
+        let value = serde_json::json!({"a": {"b": "text"}, "c": [1, 2]});
+
+        let c = column("/a/b");
+        assert_eq!(c.name, "/a/b");
+        assert_eq!(c.pointers(), vec!["/a/b".to_string()]);
+        assert_eq!(c.evaluate(Path::new("d"), &value).unwrap(), "text");
+
+        let c = column("B=/a/b");
+        assert_eq!(c.name, "B");
+        assert_eq!(c.evaluate(Path::new("d"), &value).unwrap(), "text");
+
+        let c = column("");
+        assert_eq!(c.evaluate(Path::new("d"), &value).unwrap(), value);
+
+        let c = column("/c/1");
+        assert_eq!(c.evaluate(Path::new("d"), &value).unwrap(), 2);
+
+        assert!(matches!(
+            column("/x").evaluate(Path::new("d"), &value),
+            Err(Error::JSONPointerNotFound(_, _))
+        ));
+    }
+
+    #[test]
+    #[parallel]
+    fn value_column_quoted_pointer() {
+        let value = serde_json::json!({"a b": 2, "(x+y)*z": 3, "m/n": 4, "~": 5, "q\"": 6});
+        let evaluate = |s: &str| column(s).evaluate(Path::new("d"), &value).unwrap();
+
+        assert_eq!(evaluate(r#""/a b""#), 2);
+        assert_eq!(evaluate(r#""/a b" * "/(x+y)*z""#), 6);
+        assert_eq!(evaluate("/m~1n + /~0"), 9);
+        assert_eq!(evaluate(r#"X="/q\"" - 1"#), 5);
+        assert_eq!(evaluate(r#""""#), value);
+
+        let c = column(r#""/a=b""#);
+        assert_eq!(c.name, r#""/a=b""#);
+        assert_eq!(c.pointers(), vec!["/a=b".to_string()]);
+
+        assert!(ValueColumn::from_str(r#""/a"#).is_err());
+        assert!(ValueColumn::from_str(r#""a""#).is_err());
+        assert!(ValueColumn::from_str(r#""/a\b""#).is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn value_column_arithmetic() {
+        let value = serde_json::json!({"n": [3, 4, 5], "t": 1.5, "my-key": 10, "s": "x"});
+        let evaluate = |s: &str| column(s).evaluate(Path::new("d"), &value).unwrap();
+
+        assert_eq!(evaluate("N2=/n/2*2"), 10);
+        assert_eq!(evaluate("/n/0 + /n/1 * /n/2"), 23);
+        assert_eq!(evaluate("(/n/0 + /n/1) * /n/2"), 35);
+        assert_eq!(evaluate("/n/1 / 8"), 0.5);
+        assert_eq!(evaluate("/my-key - 1"), 9);
+        assert_eq!(evaluate("-/t*2"), -3.0);
+        assert_eq!(evaluate("2 * /t"), 3.0);
+
+        let c = column("S=/n/0+/t");
+        assert_eq!(c.name, "S");
+        assert_eq!(c.pointers(), vec!["/n/0".to_string(), "/t".to_string()]);
+
+        assert!(matches!(
+            column("/s + 1").evaluate(Path::new("d"), &value),
+            Err(Error::NonNumericValue(_, _))
+        ));
+    }
+
+    #[test]
+    #[parallel]
+    fn value_column_invalid() {
+        assert!(ValueColumn::from_str("/a +").is_err());
+        assert!(ValueColumn::from_str("(/a").is_err());
+        assert!(ValueColumn::from_str("/a)").is_err());
+        assert!(ValueColumn::from_str("1.2.3").is_err());
+        assert!(ValueColumn::from_str("X=* 2").is_err());
+    }
 }
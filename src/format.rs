@@ -1,16 +1,29 @@
 // Copyright (c) 2024 The Regents of the University of Michigan.
 // Part of row, released under the BSD 3-Clause License.
 
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::time::Duration;
 
+/// How to format durations (such as walltimes).
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeFormat {
+    /// Human readable: `2 hours`.
+    #[default]
+    Human,
+
+    /// Clock time: `02:00:00`.
+    Clock,
+}
+
 /// Extend `indicatif::HumanDuration` with milliseconds
 #[derive(Debug)]
 pub struct HumanDuration(pub Duration);
 
 impl fmt::Display for HumanDuration {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.0.as_secs_f64() > 1.0 {
+        if self.0.as_secs_f64() >= 1.0 {
             indicatif::HumanDuration(self.0).fmt(f)
         } else {
             #[allow(clippy::cast_sign_loss)]
@@ -24,3 +37,129 @@ impl fmt::Display for HumanDuration {
         }
     }
 }
+
+/// Format a duration as `HH:MM:SS`.
+///
+/// Hours may exceed 24. Rounds down to the nearest second.
+///
+#[derive(Debug)]
+pub struct ClockDuration(pub Duration);
+
+impl fmt::Display for ClockDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.0.as_secs();
+        write!(
+            f,
+            "{:02}:{:02}:{:02}",
+            seconds / 3600,
+            (seconds % 3600) / 60,
+            seconds % 60
+        )
+    }
+}
+
+/// Format a duration with the given `TimeFormat`.
+///
+/// The alternate form (`{:#}`) selects the short form of `HumanDuration`.
+///
+#[derive(Debug)]
+pub struct FormattedDuration(pub Duration, pub TimeFormat);
+
+impl fmt::Display for FormattedDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.1 {
+            TimeFormat::Human => HumanDuration(self.0).fmt(f),
+            TimeFormat::Clock => ClockDuration(self.0).fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for TimeFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeFormat::Human => write!(f, "human"),
+            TimeFormat::Clock => write!(f, "clock"),
+        }
+    }
+}
+
+/// Format seconds since the UNIX epoch as a UTC timestamp (`YYYY-MM-DDTHH:MM:SSZ`).
+#[derive(Debug)]
+pub struct UtcTimestamp(pub u64);
+
+impl fmt::Display for UtcTimestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let days = self.0 / 86400;
+        let seconds = self.0 % 86400;
+
+        // Convert days since the epoch to a civil date (Howard Hinnant's algorithm).
+        let z = days + 719_468;
+        let era = z / 146_097;
+        let day_of_era = z % 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+            seconds / 3600,
+            (seconds % 3600) / 60,
+            seconds % 60
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::parallel;
+
+    #[test]
+    #[parallel]
+    fn durations() {
+        let duration = Duration::from_secs(2 * 86400 + 3661);
+        assert_eq!(ClockDuration(duration).to_string(), "49:01:01");
+        assert_eq!(
+            ClockDuration(Duration::from_millis(999)).to_string(),
+            "00:00:00"
+        );
+        assert_eq!(
+            FormattedDuration(duration, TimeFormat::Clock).to_string(),
+            "49:01:01"
+        );
+        assert_eq!(
+            FormattedDuration(Duration::from_secs(7200), TimeFormat::Human).to_string(),
+            "2 hours"
+        );
+        assert_eq!(
+            HumanDuration(Duration::from_secs(1)).to_string(),
+            "1 second"
+        );
+        assert_eq!(
+            format!(
+                "{:#}",
+                FormattedDuration(Duration::from_millis(5), TimeFormat::Human)
+            ),
+            "5ms"
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn utc_timestamp() {
+        assert_eq!(UtcTimestamp(0).to_string(), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            UtcTimestamp(951_782_400).to_string(),
+            "2000-02-29T00:00:00Z"
+        );
+        assert_eq!(
+            UtcTimestamp(1_718_454_896).to_string(),
+            "2024-06-15T12:34:56Z"
+        );
+    }
+}
@@ -1,7 +1,7 @@
 // Copyright (c) 2024 The Regents of the University of Michigan.
 // Part of row, released under the BSD 3-Clause License.
 
-use log::trace;
+use log::{debug, trace, warn};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
@@ -10,9 +10,12 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::io::{self, BufReader};
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
 
 use crate::builtin::BuiltIn;
-use crate::workflow::Resources;
+use crate::cluster::Cluster;
+use crate::workflow::{Action, Resources};
 use crate::Error;
 
 /// Launcher configuration
@@ -37,54 +40,103 @@ pub struct Launcher {
     pub gpus_per_process: Option<String>,
     pub processes: Option<String>,
     pub threads_per_process: Option<String>,
+    pub cpu_bind: Option<String>,
+    pub concurrent: Option<String>,
+
+    /// Replace this launcher with the detected MPI launcher when it is used.
+    #[serde(skip)]
+    pub(crate) detect_mpi: bool,
 }
 
 impl Launcher {
+    /// Override the fields of this launcher with the fields set in `other`.
+    ///
+    /// A field set to the empty string in `other` clears the field.
+    ///
+    fn merge(&mut self, other: Launcher) {
+        fn merge_field(field: &mut Option<String>, value: Option<String>) {
+            if let Some(value) = value {
+                *field = (!value.is_empty()).then_some(value);
+            }
+        }
+
+        let Launcher {
+            executable,
+            gpus_per_process,
+            processes,
+            threads_per_process,
+            cpu_bind,
+            concurrent,
+            detect_mpi: _,
+        } = other;
+
+        merge_field(&mut self.executable, executable);
+        merge_field(&mut self.gpus_per_process, gpus_per_process);
+        merge_field(&mut self.processes, processes);
+        merge_field(&mut self.threads_per_process, threads_per_process);
+        merge_field(&mut self.cpu_bind, cpu_bind);
+        merge_field(&mut self.concurrent, concurrent);
+    }
+
     /// Build the launcher prefix appropriate for the given resources
     pub fn prefix(&self, resources: &Resources, n_directories: usize) -> String {
         let mut result = String::new();
-        let mut need_space = false;
 
         if let Some(executable) = &self.executable {
             result.push_str(executable);
-            need_space = true;
+            result.push(' ');
+        }
+
+        result.push_str(&self.arguments(resources, n_directories));
+        result
+    }
+
+    /// Build the launcher prefix for one of several directories that execute at the same time.
+    pub fn concurrent_prefix(&self, resources: &Resources) -> String {
+        let mut result = String::new();
+
+        if let Some(executable) = &self.executable {
+            result.push_str(executable);
+            result.push(' ');
+        }
+
+        if let Some(concurrent) = &self.concurrent {
+            result.push_str(concurrent);
+            result.push(' ');
         }
 
+        result.push_str(&self.arguments(resources, 1));
+        result
+    }
+
+    /// Build the launcher arguments (the prefix without the executable).
+    pub fn arguments(&self, resources: &Resources, n_directories: usize) -> String {
+        let mut result = String::new();
+
         if let Some(processes) = &self.processes {
-            if need_space {
-                result.push(' ');
-            }
             let _ = write!(
                 result,
-                "{processes}{}",
+                "{processes}{} ",
                 resources.total_processes(n_directories)
             );
-            need_space = true;
         }
 
         if let (Some(self_threads), Some(resources_threads)) =
             (&self.threads_per_process, resources.threads_per_process)
         {
-            if need_space {
-                result.push(' ');
-            }
-            let _ = write!(result, "{self_threads}{resources_threads}");
-            need_space = true;
+            let _ = write!(result, "{self_threads}{resources_threads} ");
         }
 
         if let (Some(self_gpus), Some(resources_gpus)) =
             (&self.gpus_per_process, resources.gpus_per_process)
         {
-            if need_space {
-                result.push(' ');
-            }
-            let _ = write!(result, "{self_gpus}{resources_gpus}");
-            need_space = true;
+            let _ = write!(result, "{self_gpus}{resources_gpus} ");
         }
 
-        if need_space {
-            result.push(' ');
+        if let (Some(self_bind), Some(resources_binding)) = (&self.cpu_bind, resources.binding) {
+            let _ = write!(result, "{self_bind}{resources_binding} ");
         }
+
         result
     }
 }
@@ -134,7 +186,7 @@ impl Configuration {
         trace!("Parsing '{}'.", &launchers_toml_path.display());
         let user_config = Self::parse_str(&launchers_toml_path, &launchers_string)?;
         launchers.merge(user_config);
-        launchers.validate()?;
+        launchers.validate(&launchers_toml_path)?;
         Ok(launchers)
     }
 
@@ -144,22 +196,26 @@ impl Configuration {
     ///
     pub(crate) fn parse_str(path: &Path, toml: &str) -> Result<Self, Error> {
         Ok(Configuration {
-            launchers: toml::from_str(toml)
-                .map_err(|e| Error::TOMLParse(path.join("launchers.toml"), e))?,
+            launchers: toml::from_str(toml).map_err(|e| Error::TOMLParse(path.into(), e))?,
         })
     }
 
     /// Merge keys from another configuration into this one.
     ///
-    /// Merging adds new keys from `b` into self. It also overrides any keys in
-    /// both with the value in `b`.
+    /// Merging adds new launchers and clusters from `b` into self. When both
+    /// configure the same launcher and cluster, the fields set in `b` override
+    /// those in self and the fields omitted in `b` keep their values. Fields
+    /// set to the empty string in `b` are cleared.
     ///
     fn merge(&mut self, b: Self) {
         for (launcher_name, launcher_clusters) in b.launchers {
-            self.launchers
-                .entry(launcher_name)
-                .and_modify(|e| e.extend(launcher_clusters.clone()))
-                .or_insert(launcher_clusters);
+            let clusters = self.launchers.entry(launcher_name.clone()).or_default();
+            for (cluster_name, launcher) in launcher_clusters {
+                if clusters.contains_key(&cluster_name) {
+                    trace!("Overriding the fields set in '{launcher_name}.{cluster_name}'.");
+                }
+                clusters.entry(cluster_name).or_default().merge(launcher);
+            }
         }
     }
 
@@ -167,10 +223,20 @@ impl Configuration {
     ///
     /// Valid launcher configurations have a `default` cluster for all
     /// launchers.
-    fn validate(&self) -> Result<(), Error> {
-        for (launcher_name, launcher_clusters) in &self.launchers {
-            if !launcher_clusters.contains_key("default") {
-                return Err(Error::LauncherMissingDefault(launcher_name.clone()));
+    ///
+    /// # Errors
+    /// Returns `Err(row::Error::LauncherMissingDefault)` naming `path` and
+    /// the first launcher (in sorted order) without a default.
+    ///
+    fn validate(&self, path: &Path) -> Result<(), Error> {
+        let mut launcher_names: Vec<_> = self.launchers.keys().collect();
+        launcher_names.sort_unstable();
+        for launcher_name in launcher_names {
+            if !self.launchers[launcher_name].contains_key("default") {
+                return Err(Error::LauncherMissingDefault(
+                    path.into(),
+                    launcher_name.clone(),
+                ));
             }
         }
 
@@ -202,12 +268,191 @@ impl Configuration {
         result
     }
 
+    /// Get all launchers for a cluster, resolving the cluster's `mpi_launcher`.
+    ///
+    /// When the cluster sets `mpi_launcher`, the `mpi` launcher takes the
+    /// configuration of the named launcher. `mpi_launcher = "detect"` marks the
+    /// `mpi` launcher so that `resolve_prefix` selects the launcher that matches
+    /// the output of `mpirun --version` the first time a script uses it.
+    ///
+    /// # Errors
+    /// Returns `Err(row::Error::MpiLauncherNotFound)` when the named launcher
+    /// does not exist.
+    ///
+    pub fn for_cluster(&self, cluster: &Cluster) -> Result<HashMap<String, Launcher>, Error> {
+        let mut result = self.by_cluster(&cluster.name);
+
+        let mpi_launcher = match cluster.mpi_launcher.as_deref() {
+            None => return Ok(result),
+            Some("detect") => {
+                if let Some(mpi) = result.get_mut("mpi") {
+                    mpi.detect_mpi = true;
+                }
+                return Ok(result);
+            }
+            Some(name) => name,
+        };
+
+        let launcher = result
+            .get(mpi_launcher)
+            .cloned()
+            .ok_or_else(|| Error::MpiLauncherNotFound(mpi_launcher.into(), cluster.name.clone()))?;
+        debug!(
+            "Using the '{mpi_launcher}' launcher for 'mpi' on cluster '{}'.",
+            cluster.name
+        );
+        result.insert("mpi".into(), launcher);
+
+        Ok(result)
+    }
+
     /// Get the complete launcher configuration.
     pub fn full_config(&self) -> &HashMap<String, HashMap<String, Launcher>> {
         &self.launchers
     }
 }
 
+/// The launcher prefix that executes an action.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ActionPrefix {
+    /// Prefix of the command (for the first component of heterogeneous jobs).
+    pub prefix: String,
+
+    /// Process launcher arguments for each following heterogeneous component.
+    pub components: Vec<String>,
+}
+
+/// Resolve the launcher prefix that executes `action` on `n_directories` directories.
+///
+/// `launchers` holds the launchers on the cluster `cluster_name`. Actions that
+/// set `concurrent_directories` use the concurrent prefix for one directory.
+///
+/// # Errors
+/// Returns `Err(row::Error)` when the action's launchers are not in
+/// `launchers`, the action requests more than one process without a process
+/// launcher, or the action uses more than one process launcher.
+///
+#[allow(clippy::implicit_hasher)]
+pub fn resolve_prefix(
+    launchers: &HashMap<String, Launcher>,
+    action: &Action,
+    cluster_name: &str,
+    n_directories: usize,
+) -> Result<ActionPrefix, Error> {
+    let concurrent = action.resources.concurrent_directories.is_some();
+    let components = &action.resources.component;
+    let mut result = ActionPrefix::default();
+    let mut process_launchers = 0;
+
+    for launcher in action.launchers(cluster_name) {
+        let mut launcher = launchers.get(launcher).ok_or_else(|| {
+            Error::LauncherNotFound(launcher.clone(), action.name().into(), cluster_name.into())
+        })?;
+        if launcher.detect_mpi {
+            launcher = detected_mpi_launcher(launchers, cluster_name).unwrap_or(launcher);
+        }
+
+        if concurrent {
+            result
+                .prefix
+                .push_str(&launcher.concurrent_prefix(&action.resources));
+        } else {
+            let resources = components.first().unwrap_or(&action.resources);
+            result
+                .prefix
+                .push_str(&launcher.prefix(resources, n_directories));
+        }
+
+        if launcher.processes.is_some() {
+            process_launchers += 1;
+            if !concurrent {
+                for component in components.iter().skip(1) {
+                    result
+                        .components
+                        .push(launcher.arguments(component, n_directories));
+                }
+            }
+        }
+    }
+
+    let total_processes =
+        action
+            .resources
+            .total_processes(if concurrent { 1 } else { n_directories });
+    if total_processes > 1 && process_launchers == 0 {
+        return Err(Error::NoProcessLauncher(
+            action.name().into(),
+            total_processes,
+        ));
+    }
+    if process_launchers > 1 {
+        return Err(Error::TooManyProcessLaunchers(action.name().into()));
+    }
+
+    Ok(result)
+}
+
+/// Get the launcher of the MPI implementation that provides `mpirun`.
+///
+/// Detect the implementation once per process.
+///
+/// # Returns
+/// `None` when the implementation cannot be detected.
+///
+fn detected_mpi_launcher<'a>(
+    launchers: &'a HashMap<String, Launcher>,
+    cluster_name: &str,
+) -> Option<&'a Launcher> {
+    static DETECTED: OnceLock<Option<&'static str>> = OnceLock::new();
+
+    let name = DETECTED.get_or_init(|| {
+        let name = detect_mpi_launcher();
+        if let Some(name) = name {
+            debug!("Using the '{name}' launcher for 'mpi' on cluster '{cluster_name}'.");
+        } else {
+            warn!(
+                "Unable to detect the MPI implementation on '{cluster_name}', using the 'mpi' launcher."
+            );
+        }
+        name
+    });
+    launchers.get((*name)?)
+}
+
+/// Detect the MPI implementation that provides `mpirun`.
+///
+/// # Returns
+/// `Some(launcher_name)` with the name of the matching built-in launcher, or
+/// `None` when `mpirun` cannot be executed or is not recognized.
+///
+fn detect_mpi_launcher() -> Option<&'static str> {
+    trace!("Executing 'mpirun --version'.");
+    let output = match Command::new("mpirun").arg("--version").output() {
+        Ok(output) => output,
+        Err(error) => {
+            debug!("Unable to execute 'mpirun --version': {error}.");
+            return None;
+        }
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    mpi_launcher_from_version(&stdout).or_else(|| mpi_launcher_from_version(&stderr))
+}
+
+/// Identify the MPI implementation from the output of `mpirun --version`.
+fn mpi_launcher_from_version(version: &str) -> Option<&'static str> {
+    if version.contains("Open MPI") || version.contains("OpenRTE") {
+        Some("openmpi")
+    } else if version.contains("Intel(R) MPI") {
+        Some("intelmpi")
+    } else if version.contains("HYDRA") {
+        Some("mpich")
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use assert_fs::prelude::*;
@@ -215,7 +460,8 @@ mod tests {
     use serial_test::parallel;
 
     use super::*;
-    use crate::workflow::Processes;
+    use crate::cluster::{IdentificationMethod, SchedulerType};
+    use crate::workflow::{Binding, Processes};
 
     fn setup() {
         let _ = env_logger::builder()
@@ -319,6 +565,130 @@ mod tests {
         );
     }
 
+    #[test]
+    #[parallel]
+    fn binding_prefix() {
+        setup();
+        let launchers = Configuration::built_in();
+        let launchers_by_cluster = launchers.by_cluster("any_cluster");
+
+        let cores = Resources {
+            processes: Some(Processes::PerSubmission(4)),
+            binding: Some(Binding::Cores),
+            ..Resources::default()
+        };
+        assert_eq!(
+            launchers_by_cluster["srun"].prefix(&cores, 1),
+            "srun --ntasks=4 --cpu-bind=cores "
+        );
+        assert_eq!(
+            launchers_by_cluster["mpi"].prefix(&cores, 1),
+            "srun --ntasks=4 --cpu-bind=cores "
+        );
+        assert_eq!(launchers_by_cluster["openmp"].prefix(&cores, 1), "");
+
+        let sockets = Resources {
+            binding: Some(Binding::Sockets),
+            ..Resources::default()
+        };
+        assert_eq!(
+            launchers_by_cluster["srun"].prefix(&sockets, 1),
+            "srun --ntasks=1 --cpu-bind=sockets "
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn mpi_variant_prefixes() {
+        setup();
+        let launchers = Configuration::built_in();
+        let launchers_by_cluster = launchers.by_cluster("any_cluster");
+
+        let all = Resources {
+            processes: Some(Processes::PerDirectory(6)),
+            threads_per_process: Some(3),
+            gpus_per_process: Some(8),
+            ..Resources::default()
+        };
+        assert_eq!(
+            launchers_by_cluster["srun"].prefix(&all, 1),
+            "srun --ntasks=6 --cpus-per-task=3 --tres-per-task=gres/gpu:8 "
+        );
+        assert_eq!(
+            launchers_by_cluster["openmpi"].prefix(&all, 1),
+            "mpirun -np 6 --map-by slot:PE=3 "
+        );
+        assert_eq!(
+            launchers_by_cluster["mpich"].prefix(&all, 1),
+            "mpiexec -n 6 "
+        );
+        assert_eq!(
+            launchers_by_cluster["intelmpi"].prefix(&all, 1),
+            "mpirun -n 6 "
+        );
+    }
+
+    fn cluster(mpi_launcher: Option<&str>) -> Cluster {
+        Cluster {
+            name: "cluster".into(),
+            identify: IdentificationMethod::Always(false),
+            scheduler: SchedulerType::Bash,
+            partition: Vec::new(),
+            submit_options: Vec::new(),
+            mpi_launcher: mpi_launcher.map(Into::into),
+            qos: Vec::new(),
+            bash: None,
+            job_url_template: None,
+            kubernetes: None,
+            max_submissions_per_minute: None,
+            auto_select_strategy: None,
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn for_cluster_mpi_launcher() {
+        setup();
+        let launchers = Configuration::built_in();
+
+        let unset = launchers.for_cluster(&cluster(None)).unwrap();
+        assert_eq!(unset, launchers.by_cluster("cluster"));
+
+        let openmpi = launchers.for_cluster(&cluster(Some("openmpi"))).unwrap();
+        assert_eq!(openmpi["mpi"], launchers.by_cluster("cluster")["openmpi"]);
+        assert_eq!(
+            openmpi["openmpi"],
+            launchers.by_cluster("cluster")["openmpi"]
+        );
+
+        let missing = launchers.for_cluster(&cluster(Some("not_a_launcher")));
+        assert!(matches!(missing, Err(Error::MpiLauncherNotFound(_, _))));
+
+        // Detection is deferred until a script uses the launcher.
+        let detect = launchers.for_cluster(&cluster(Some("detect"))).unwrap();
+        assert!(detect["mpi"].detect_mpi);
+        assert!(!detect["openmpi"].detect_mpi);
+    }
+
+    #[test]
+    #[parallel]
+    fn detect_mpi_version() {
+        setup();
+        assert_eq!(
+            mpi_launcher_from_version("mpirun (Open MPI) 4.1.5\n\nReport bugs to"),
+            Some("openmpi")
+        );
+        assert_eq!(
+            mpi_launcher_from_version("Intel(R) MPI Library for Linux* OS, Version 2021.10"),
+            Some("intelmpi")
+        );
+        assert_eq!(
+            mpi_launcher_from_version("HYDRA build details:\n    Version: 4.1.2"),
+            Some("mpich")
+        );
+        assert_eq!(mpi_launcher_from_version("command not found"), None);
+    }
+
     #[test]
     #[parallel]
     fn open_no_file() {
@@ -350,7 +720,9 @@ mod tests {
         )
         .unwrap();
         let error = Configuration::open_from_path(temp.path().into());
-        assert!(matches!(error, Err(Error::LauncherMissingDefault(_))));
+        assert!(
+            matches!(error, Err(Error::LauncherMissingDefault(path, name)) if path == temp.path() && name == "new_launcher")
+        );
     }
 
     #[test]
@@ -374,7 +746,7 @@ executable = "e"
         let launchers = Configuration::open_from_path(temp.path().into()).expect("valid launcher");
 
         let built_in = Configuration::built_in();
-        assert_eq!(launchers.launchers.len(), 3);
+        assert_eq!(launchers.launchers.len(), 7);
         assert_eq!(launchers.launchers["openmp"], built_in.launchers["openmp"]);
         assert_eq!(launchers.launchers["mpi"], built_in.launchers["mpi"]);
 
@@ -392,4 +764,66 @@ executable = "e"
         assert_eq!(default.threads_per_process, Some("c".into()));
         assert_eq!(default.gpus_per_process, Some("d".into()));
     }
+
+    #[test]
+    #[parallel]
+    fn override_fields() {
+        setup();
+        let temp = TempDir::new().unwrap().child("launchers.json");
+        temp.write_str(
+            r#"
+[mpi.default]
+executable = "a"
+cpu_bind = ""
+
+[mpi.none]
+processes = "-np "
+
+[mpi.new_cluster]
+executable = "b"
+cpu_bind = ""
+"#,
+        )
+        .unwrap();
+        let launchers = Configuration::open_from_path(temp.path().into()).expect("valid launcher");
+        let built_in = Configuration::built_in();
+
+        let default = &launchers.launchers["mpi"]["default"];
+        let built_in_default = &built_in.launchers["mpi"]["default"];
+        assert_eq!(default.executable, Some("a".into()));
+        assert_eq!(default.processes, built_in_default.processes);
+        assert_eq!(
+            default.threads_per_process,
+            built_in_default.threads_per_process
+        );
+        assert_eq!(default.concurrent, built_in_default.concurrent);
+        assert!(built_in_default.cpu_bind.is_some());
+        assert_eq!(default.cpu_bind, None);
+
+        let none = &launchers.launchers["mpi"]["none"];
+        let built_in_none = &built_in.launchers["mpi"]["none"];
+        assert_eq!(none.executable, built_in_none.executable);
+        assert_eq!(none.processes, Some("-np ".into()));
+
+        let new_cluster = &launchers.launchers["mpi"]["new_cluster"];
+        assert_eq!(new_cluster.executable, Some("b".into()));
+        assert_eq!(new_cluster.processes, None);
+        assert_eq!(new_cluster.cpu_bind, None);
+    }
+
+    #[test]
+    #[parallel]
+    fn parse_error_names_file() {
+        setup();
+        let temp = TempDir::new().unwrap().child("launchers.toml");
+        temp.write_str(
+            r#"
+[mpi.default]
+not_a_field = "a"
+"#,
+        )
+        .unwrap();
+        let error = Configuration::open_from_path(temp.path().into());
+        assert!(matches!(error, Err(Error::TOMLParse(path, _)) if path == temp.path()));
+    }
 }
@@ -9,18 +9,29 @@
 #![allow(clippy::must_use_candidate)]
 #![warn(clippy::format_push_string)]
 
+pub mod alias;
 pub(crate) mod builtin;
 pub mod cluster;
-mod expr;
+pub mod config;
+pub mod exitcode;
+pub mod export;
+pub(crate) mod expr;
 pub mod format;
 pub mod launcher;
 pub mod progress_styles;
 pub mod project;
+#[cfg(feature = "s3")]
+pub(crate) mod s3;
 pub mod scheduler;
 pub mod state;
+pub mod suggest;
+pub mod sweep;
+pub mod values;
 pub mod workflow;
 pub mod workspace;
 
+pub use expr::ValueColumn;
+
 use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget};
 use serde_json::{self, Value};
 use std::io;
@@ -28,12 +39,48 @@ use std::path::PathBuf;
 use std::sync::mpsc;
 
 pub const DATA_DIRECTORY_NAME: &str = ".row";
+pub const DEFAULT_IO_THREADS: u16 = 8;
+pub const DEFAULT_QUEUE_TIMEOUT: u64 = 30;
 pub const COMPLETED_DIRECTORY_NAME: &str = "completed";
+pub const WORKSPACES_DIRECTORY_NAME: &str = "workspaces";
 pub const MIN_PROGRESS_BAR_SIZE: usize = 1;
 
+pub const ALIASES_FILE_NAME: &str = "aliases.json";
+pub const CONFIG_FILE_NAME: &str = "config.toml";
 pub const DIRECTORY_CACHE_FILE_NAME: &str = "directories.json";
-pub const COMPLETED_CACHE_FILE_NAME: &str = "completed.postcard";
+pub const COMPLETED_CACHE_FILE_NAME: &str = "completed.v2.postcard";
+pub const LEGACY_COMPLETED_CACHE_FILE_NAME: &str = "completed.postcard";
+pub const COMPLETION_HASHES_FILE_NAME: &str = "completion_hashes.postcard";
+pub const COMPLETED_PACK_EXTENSION: &str = "v4.postcard";
+pub const PENDING_PACK_EXTENSION: &str = "v4.pending";
+pub const V3_COMPLETED_PACK_EXTENSION: &str = "v3.postcard";
+pub const V3_PENDING_PACK_EXTENSION: &str = "v3.pending";
+pub const V2_COMPLETED_PACK_EXTENSION: &str = "v2.postcard";
+pub const V2_PENDING_PACK_EXTENSION: &str = "pending";
+pub const JOBS_CACHE_FILE_NAME: &str = "jobs.postcard";
+pub const LOG_DIRECTORY_NAME: &str = ".row_logs";
 pub const SUBMITTED_CACHE_FILE_NAME: &str = "submitted.postcard";
+pub const SUBMITTED_GROUPS_CACHE_FILE_NAME: &str = "submitted_groups.postcard";
+pub const SUBMIT_LOCK_FILE_NAME: &str = "submit.lock";
+pub const TOML_WORKFLOW_FILE_NAME: &str = "workflow.toml";
+pub const YAML_WORKFLOW_FILE_NAME: &str = "workflow.yaml";
+pub const GENERATED_WORKFLOW_PREFIX: &str = "generated_";
+
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Update a 64-bit FNV-1a hash with the given bytes.
+///
+/// Start with `FNV_OFFSET_BASIS`. Unlike `std::hash::DefaultHasher`, the result
+/// does not change between versions of Rust or row.
+///
+pub(crate) fn fnv1a<'a>(mut hash: u64, bytes: impl IntoIterator<Item = &'a u8>) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
 
 /// Hold a `MultiProgress` and all of its progress bars.
 ///
@@ -71,21 +118,47 @@ pub enum Error {
     #[error("Unable to remove '{0}': {1}")]
     FileRemove(PathBuf, #[source] io::Error),
 
+    #[error("Unable to lock '{0}': {1}")]
+    FileLock(PathBuf, #[source] io::Error),
+
     #[error("File '{0}' already exists.")]
     FileExists(PathBuf),
 
     #[error("Unable to read '{0}': {1}")]
     DirectoryRead(PathBuf, #[source] io::Error),
 
-    #[error("Directory '{0}' not found in workspace.")]
-    DirectoryNotFound(PathBuf),
+    #[error("Unable to change to the project directory '{0}': {1}")]
+    ProjectDirectory(PathBuf, #[source] io::Error),
+
+    #[error("Directory '{0}' not found in workspace.{1}")]
+    DirectoryNotFound(PathBuf, String),
+
+    #[error("Unable to rename '{0}' to '{1}': {2}")]
+    DirectoryRename(PathBuf, PathBuf, #[source] io::Error),
+
+    #[error("'{0}' is not a valid directory name.")]
+    InvalidDirectoryName(PathBuf),
+
+    #[error("Directory '{0}' is submitted for action '{1}'.\nWait for the job to finish or pass `--force`.")]
+    DirectorySubmitted(PathBuf, String),
 
     #[error("Unable to create directory '{0}': {1}")]
     DirectoryCreate(PathBuf, #[source] io::Error),
 
-    #[error("Non-UTF-8 directory name '{0}'")]
+    #[error(
+        "Non-UTF-8 directory name '{0}'. Set `workspace.non_utf8_names = \"skip\"` to exclude it."
+    )]
     NonUTF8DirectoryName(PathBuf),
 
+    #[error("Unable to check the free space in '{0}': {1}")]
+    FreeSpace(PathBuf, #[source] nix::errno::Errno),
+
+    #[error("Only {1} free in '{0}', which is less than `workspace.minimum_free` ({2}).\nFree space in the workspace before submitting jobs.")]
+    InsufficientFreeSpace(PathBuf, String, String),
+
+    #[error("Unable to access '{0}': {1}")]
+    ObjectStore(String, String),
+
     #[error("Unable to spawn '{0}': {1}.")]
     SpawnProcess(String, #[source] io::Error),
 
@@ -93,6 +166,12 @@ pub enum Error {
     #[error("Unable to parse '{0}'.\n{1}")]
     TOMLParse(PathBuf, #[source] toml::de::Error),
 
+    #[error("Unable to serialize '{0}'.\n{1}")]
+    TOMLSerialize(PathBuf, #[source] toml::ser::Error),
+
+    #[error("Unable to parse '{0}'.\n{1}")]
+    YAMLParse(PathBuf, #[source] serde_norway::Error),
+
     #[error("Unable to parse '{0}'\n{1}")]
     JSONParse(PathBuf, #[source] serde_json::Error),
 
@@ -105,28 +184,105 @@ pub enum Error {
     #[error("Unable to serialize '{0}': {1}")]
     PostcardSerialize(PathBuf, #[source] postcard::Error),
 
+    #[error("The cache '{0}' is corrupt: {2}\nMoved it to '{1}'. {3}")]
+    CorruptCache(PathBuf, PathBuf, String, String),
+
     // workflow errors
+    #[error("Invalid resources.component in action '{0}': {1}.")]
+    InvalidComponent(String, String),
+
+    #[error("Invalid resources.concurrent_directories in action '{0}': {1}.")]
+    InvalidConcurrentDirectories(String, String),
+
+    #[error(
+        "Action '{0}' sets `log_output`, which requires a command that includes {{directory}}."
+    )]
+    InvalidLogOutput(String),
+
+    #[error("Action '{0}' uses the unknown placeholder '{1}' in `job_name`.")]
+    InvalidJobName(String, String),
+
+    #[error("Action '{0}' must reference {{script}} in `submit_command_override`.")]
+    SubmitCommandMissingScript(String),
+
+    #[error("Action '{0}' uses the unknown placeholder '{1}' in `products`. Products may include only JSON pointers ('{{/pointer}}').")]
+    InvalidProduct(String, String),
+
+    #[error(
+        "Action '{0}' uses JSON pointers in `products`, which requires `workspace.value_file`."
+    )]
+    ProductsRequireValueFile(String),
+
     #[error("Previous action '{0}' not found in action '{1}'.")]
     PreviousActionNotFound(String, String),
 
+    #[error("The fraction of previous action '{0}' in action '{1}' must be greater than 0 and at most 1.")]
+    InvalidPreviousFraction(String, String),
+
+    #[error("Action '{0}' not found: Required by 'completed:{0}' in the include conditions of action '{1}'.")]
+    IncludeActionNotFound(String, String),
+
+    #[error("No row project found in '{0}' (a previous project of action '{1}').")]
+    PreviousProjectNotFound(PathBuf, String),
+
     #[error("Define 'processes' or 'processes_per_directory', not both in action '{0}'.")]
     DuplicateProcesses(String),
 
+    #[error("Use '{{launcher}}' at most once in each step of the command of action '{0}'.")]
+    DuplicateLauncherPlaceholder(String),
+
     #[error("Use '{{directory}}' or '{{directories}}', not both in the command of action '{0}'.")]
     ActionContainsMultipleTemplates(String),
 
     #[error("Use '{{directory}}' or '{{directories}}' in the command of action '{0}'.")]
     ActionContainsNoTemplate(String),
 
-    #[error("workflow.toml not found in the current working directory or any parents.")]
+    #[error(
+        "workflow.toml or workflow.yaml not found in the current working directory or any parents."
+    )]
     WorkflowNotFound,
 
+    #[error("Unknown setting '{0}'. Expected one of: cluster, color, io_threads.")]
+    InvalidConfigKey(String),
+
+    #[error("Invalid value '{1}' for setting '{0}': {2}.")]
+    InvalidConfigValue(String, String, String),
+
+    #[error("Found both workflow.toml and workflow.yaml in '{0}'. Remove one of them.")]
+    MultipleWorkflowFiles(PathBuf),
+
+    #[error("The workflow generated by '{0}' must not set 'generator'.")]
+    NestedGenerator(String),
+
     #[error("The value in directory '{0}' does not contain the JSON pointer '{1}'.")]
     JSONPointerNotFound(PathBuf, String),
 
+    #[error("Directory '{0}' has no value in '{1}'.")]
+    ConsolidatedValueNotFound(PathBuf, PathBuf),
+
+    #[error("Line {1} of '{0}' is not a valid checksum entry.")]
+    InvalidChecksumLine(PathBuf, usize),
+
+    #[error("The element '{1}' in directory '{0}' is not a number.")]
+    NonNumericValue(PathBuf, String),
+
+    #[error("Invalid value expression '{0}': {1}.")]
+    InvalidValueExpression(String, String),
+
     #[error("Cannot compare {0} and {1} while checking directory '{2}'.")]
     CannotCompareInclude(Value, Value, PathBuf),
 
+    #[error("The condition on '{0}' in action '{1}' must compare with an array of values when using 'in' or 'not_in'.")]
+    MembershipNotArray(String, String),
+
+    #[error(
+        "The condition on '{0}' in action '{1}' must compare with a string when using 'matches'."
+    )]
+    PatternNotString(String, String),
+
+    #[error("Invalid regular expression '{0}' in action '{1}': {2}")]
+    InvalidPattern(String, String, #[source] regex::Error),
+
     #[error("Action at index {0} is missing `name`.")]
     ActionMissingName(usize),
 
@@ -142,12 +298,27 @@ pub enum Error {
     #[error("Cannot resolve recursive `from={0}`.")]
     RecursiveFrom(String),
 
+    #[error("Resource preset '{0}' set in action '{1}' not found.")]
+    ResourcePresetNotFound(String, String),
+
+    #[error("Resource preset '{0}' must not set `preset`.")]
+    NestedResourcePreset(String),
+
     #[error("Duplicate actions '{0}' must have the same `products`.")]
     DuplicateActionsDifferentProducts(String),
 
     #[error("Duplicate actions '{0}' must have the same `previous_actions`.")]
     DuplicateActionsDifferentPreviousActions(String),
 
+    #[error("Unsupported workspace url '{0}': expected 's3://bucket/prefix'.")]
+    UnsupportedWorkspaceUrl(String),
+
+    #[error("Workspace url '{0}' requires row to be built with the 's3' feature.")]
+    WorkspaceUrlNotEnabled(String),
+
+    #[error("`workspace.value_mode = \"consolidated\"` requires `workspace.value_file`.")]
+    ConsolidatedValueFileMissing,
+
     // submission errors
     #[error("Error encountered while executing action '{0}': {1}.")]
     ExecuteAction(String, String),
@@ -155,24 +326,98 @@ pub enum Error {
     #[error("Error encountered while submitting action '{0}': {1}.")]
     SubmitAction(String, String),
 
+    #[error("sbatch rejected the job for action '{0}': {1}.\nsbatch reported:\n{2}")]
+    SbatchRejected(String, String, String),
+
+    #[error("Cluster '{0}' uses the kubernetes scheduler and must set `kubernetes.image`.")]
+    KubernetesImageMissing(String),
+
     #[error("Unepxected output from {0}: {1}")]
     UnexpectedOutput(String, String),
 
     #[error("Error encountered while running squeue: {0}.\n{1}")]
     ExecuteSqueue(String, String),
 
+    #[error("{0} did not finish within {1} seconds.")]
+    QueueTimeout(String, u64),
+
+    #[error("Error encountered while running {0}: {1}.\n{2}")]
+    ExecuteCommand(String, String, String),
+
+    #[error("Unable to render the job script template '{0}': {1:#}")]
+    JobTemplate(PathBuf, #[source] minijinja::Error),
+
     #[error("Interrupted")]
     Interrupted,
 
+    #[error("There is nothing to do.")]
+    NothingToDo,
+
+    #[error("The workspace '{0}' is read-only.")]
+    ReadOnlyWorkspace(String),
+
+    #[error("Pass `--yes` to confirm the {0} when {1}.")]
+    ConfirmationRequired(String, String),
+
+    // sweep errors
+    #[error(
+        "The sweep defines no values.\nSet parameters in `product` or add tables to `values`."
+    )]
+    SweepEmpty(),
+
+    #[error("The sweep parameter '{0}' must be an array of values.")]
+    SweepParameterNotArray(String),
+
+    #[error("The sweep `name` may only include JSON pointers, found '{0}'.")]
+    SweepInvalidPlaceholder(String),
+
+    #[error("The sweep value {1} does not contain the JSON pointer '{0}' used in `name`.")]
+    SweepPointerNotFound(String, String),
+
+    #[error("Several values in the sweep have the directory name '{0}'.\nInclude more parameters in `name`.")]
+    SweepNameCollision(PathBuf),
+
+    #[error("Directory '{0}' already exists with a different value.")]
+    SweepValueMismatch(PathBuf),
+
+    #[error(
+        "`row sweep` requires `workspace.value_file` with `workspace.value_mode = \"directory\"`."
+    )]
+    SweepRequiresValueFile(),
+
     #[error("'{0}' would be submitted multiple times in action '{1}'.\nCheck that duplicate actions include non-overlapping groups.")]
     WouldSubmitMultipleTimes(PathBuf, String),
 
+    #[error("Another process submitted '{0}' in action '{1}' (job {3} on '{2}').\nExecute `row submit` again to submit the remaining directories.")]
+    DuplicateSubmission(PathBuf, String, String, u32),
+
+    #[error("The imported state of '{0}' in action '{1}' differs from the cache.\nPass `--conflict keep` or `--conflict replace` to resolve it.")]
+    ImportConflict(PathBuf, String),
+
+    #[error("The imported submitted group '{0}' in action '{1}' differs from the cache.\nPass `--conflict keep` or `--conflict replace` to resolve it.")]
+    ImportGroupConflict(String, String),
+
+    #[error("Invalid bundle '{0}': {1}.")]
+    InvalidBundle(PathBuf, String),
+
+    #[error("Refusing to submit jobs to '{1}' from inside Slurm job {0}.\nPass `--allow-nested` to submit anyway.")]
+    NestedSubmission(String, String),
+
+    #[error("The plan '{0}' is for cluster '{1}', not '{2}'.\nExecute `row plan` again on '{2}'.")]
+    PlanClusterMismatch(PathBuf, String, String),
+
+    #[error("The plan '{0}' is out of date: {1}.\nExecute `row plan` again.")]
+    PlanOutdated(PathBuf, String),
+
     // launcher errors
-    #[error("Launcher '{0}' does not contain a default configuration")]
-    LauncherMissingDefault(String),
+    #[error("Launcher '{1}' in '{0}' does not contain a default configuration.\nAdd a `[{1}.default]` table with the settings to use on clusters without a `[{1}.<cluster>]` table.")]
+    LauncherMissingDefault(PathBuf, String),
+
+    #[error("Launcher '{0}' not found on cluster '{2}': Required by action '{1}'.\nExecute 'row show launchers' to list the available launchers.")]
+    LauncherNotFound(String, String, String),
 
-    #[error("Launcher '{0}' not found: Required by action '{1}'.")]
-    LauncherNotFound(String, String),
+    #[error("Launcher '{0}' not found: Required by 'mpi_launcher' in cluster '{1}'.")]
+    MpiLauncherNotFound(String, String),
 
     #[error("No process launcher for action '{0}' which requests {1} processes.")]
     NoProcessLauncher(String, usize),
@@ -180,6 +425,15 @@ pub enum Error {
     #[error("More than one process launcher for action '{0}'.")]
     TooManyProcessLaunchers(String),
 
+    #[error("Found {0} problem(s) in the configuration.")]
+    CheckFailed(usize),
+
+    #[error("Found {0} problem(s). Follow the suggestions above to correct them.")]
+    DoctorFoundProblems(usize),
+
+    #[error("Timed out after {0}: {1} directories have not reached the target status.")]
+    WaitTimedOut(String, usize),
+
     // cluster errors
     #[error(
         "Cluster '{0}' not found: execute 'row show cluster --all --short' to see available clusters."
@@ -195,9 +449,21 @@ pub enum Error {
     #[error("No valid partitions:\n{0}\nExecute 'row show cluster' to see available partitions.")]
     PartitionNotFound(String),
 
+    #[error("QOS '{0}' is not allowed on cluster '{1}'. Choose one of: {2}.")]
+    QosNotAllowed(String, String, String),
+
     // command errors
-    #[error("Action '{0}' not found in the workflow.")]
-    ActionNotFound(String),
+    #[error("Action '{0}' not found in the workflow.{1}")]
+    ActionNotFound(String, String),
+
+    #[error("Unable to write a configuration to '{0}': use a '.json' or '.toml' file.")]
+    UnsupportedOutputFormat(PathBuf),
+
+    #[error("Alias '{0}' not found.")]
+    AliasNotFound(String),
+
+    #[error("Alias '{0}' is the name of a directory in the workspace.")]
+    AliasIsDirectory(String),
 
     #[error("A row project already exists in '{0}'.")]
     ProjectExists(PathBuf),
@@ -217,6 +483,9 @@ pub enum Error {
     #[error("Attempting partial submission of action '{0}' when `submit_whole=true`.")]
     PartialGroupSubmission(String),
 
+    #[error("Group {1} of action '{0}' has {2} directories, fewer than `minimum_size={3}`.")]
+    GroupBelowMinimumSize(String, String, usize, usize),
+
     // thread errors
     #[error("Unexpected error communicating between threads in 'find_completed_directories'.")]
     CompletedDirectoriesSend(#[from] mpsc::SendError<(PathBuf, String)>),
@@ -7,6 +7,8 @@ use clap::Parser;
 use indicatif::{MultiProgress, ProgressDrawTarget};
 use indicatif_log_bridge::LogWrapper;
 use log::{error, info};
+use path_absolutize::Absolutize;
+use std::env;
 use std::error::Error;
 use std::io::{self, Write};
 use std::process::ExitCode;
@@ -15,17 +17,57 @@ use std::time::Instant;
 mod cli;
 mod ui;
 
-use cli::{ColorMode, Commands, Options, ShowCommands};
-use row::format::HumanDuration;
+use cli::{
+    AliasCommands, ColorMode, Commands, ConfigCommands, DebugCommands, FakeSchedulerCommands,
+    Options, ShowCommands,
+};
+use row::config::Config;
+use row::exitcode;
+use row::format::FormattedDuration;
 use row::MultiProgressContainer;
 use ui::MultiProgressWriter;
 
+#[allow(clippy::too_many_lines)]
 fn main_detail() -> Result<(), Box<dyn Error>> {
     let instant = Instant::now();
-    let options = Options::parse();
+    let mut options = Options::parse();
+
+    // Resolve --cache-dir relative to the current directory. Export it so that
+    // the workflow and the submitted jobs use the same caches.
+    let cache_directory = options
+        .global
+        .cache_dir
+        .as_ref()
+        .map(|path| -> Result<(), Box<dyn Error>> {
+            env::set_var("ROW_CACHE_DIR", path.absolutize()?.as_os_str());
+            Ok(())
+        })
+        .transpose();
+
+    // Execute in the project directory given by --project.
+    let project_directory = options
+        .global
+        .project
+        .as_ref()
+        .map(|project| {
+            env::set_current_dir(project)
+                .map_err(|e| row::Error::ProjectDirectory(project.clone(), e))
+        })
+        .transpose();
+
+    // Settings provide defaults for options not given on the command line.
+    // `row config` reads the settings itself.
+    let config = if matches!(options.command, Some(Commands::Config(_))) {
+        Ok(None)
+    } else {
+        Config::open().map(Some)
+    };
+    if let Ok(Some(config)) = &config {
+        options.global.apply_settings(&config.settings());
+    }
 
     let log_style;
-    match options.global.color {
+    match options.global.color.unwrap_or(ColorMode::Auto) {
         ColorMode::Never => {
             log_style = "never";
             console::set_colors_enabled(false);
@@ -39,6 +81,10 @@ fn main_detail() -> Result<(), Box<dyn Error>> {
         }
     }
 
+    // `--quiet` hides warnings and selects the machine-readable mode.
+    options.global.quiet =
+        options.verbose.log_level_filter() < clap_verbosity_flag::LevelFilter::Warn;
+
     let log_level = match options.verbose.log_level_filter() {
         clap_verbosity_flag::LevelFilter::Off => "off",
         clap_verbosity_flag::LevelFilter::Error => "error",
@@ -49,7 +95,13 @@ fn main_detail() -> Result<(), Box<dyn Error>> {
         clap_verbosity_flag::LevelFilter::Trace => "trace",
     };
 
-    let multi_progress = if options.global.no_progress {
+    // Machine-readable output should not be interleaved with progress bars.
+    let short_status = matches!(
+        &options.command,
+        Some(Commands::Show(ShowCommands::Status(args))) if args.short
+    );
+
+    let multi_progress = if options.global.no_progress || options.global.quiet || short_status {
         MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
     } else {
         MultiProgress::new()
@@ -67,44 +119,151 @@ fn main_detail() -> Result<(), Box<dyn Error>> {
 
     LogWrapper::new(multi_progress.clone(), logger).try_init()?;
 
+    // Report errors now that the logger is available.
+    cache_directory?;
+    project_directory?;
+    config?;
+
     let mut multi_progress_container = MultiProgressContainer::new(multi_progress.clone());
 
     match options.command {
         Some(Commands::Init(args)) => {
             cli::init::init(&options.global, &args, &mut output)?;
         }
-        Some(Commands::Show(show)) => match show {
-            ShowCommands::Status(args) => cli::status::status(
-                &options.global,
-                args,
-                &mut multi_progress_container,
-                &mut output,
-            )?,
-            ShowCommands::Directories(args) => cli::directories::directories(
-                &options.global,
-                args,
-                &mut multi_progress_container,
-                &mut output,
-            )?,
-            ShowCommands::Cluster(args) => {
-                cli::cluster::cluster(&options.global, &args, &mut output)?;
+        Some(Commands::Show(show)) => {
+            // Write to the --output file (without styles) when given.
+            let mut file_output = show.output().map(cli::create_output).transpose()?;
+            if file_output.is_some() {
+                console::set_colors_enabled(false);
             }
-            ShowCommands::Launchers(args) => {
-                cli::launchers::launchers(&options.global, &args, &mut output)?;
+            let mut show_output: &mut dyn Write = match &mut file_output {
+                Some(file) => file,
+                None => &mut output,
+            };
+
+            match show {
+                ShowCommands::Status(args) => cli::status::status(
+                    &options.global,
+                    &args,
+                    &mut multi_progress_container,
+                    &mut show_output,
+                )?,
+                ShowCommands::Directories(args) => cli::directories::directories(
+                    &options.global,
+                    args,
+                    &mut multi_progress_container,
+                    &mut show_output,
+                )?,
+                ShowCommands::Cluster(args) => {
+                    cli::cluster::cluster(&options.global, &args, &mut show_output)?;
+                }
+                ShowCommands::Launchers(args) => {
+                    cli::launchers::launchers(&options.global, &args, &mut show_output)?;
+                }
+                ShowCommands::Staged(args) => {
+                    cli::staged::staged(&options.global, &args, &mut show_output)?;
+                }
+                ShowCommands::Efficiency(args) => cli::efficiency::efficiency(
+                    &options.global,
+                    &args,
+                    &mut multi_progress_container,
+                    &mut show_output,
+                )?,
+                ShowCommands::Progress(args) => cli::progress::progress(
+                    &options.global,
+                    &args,
+                    &mut multi_progress_container,
+                    &mut show_output,
+                )?,
             }
-        },
+
+            show_output.flush()?;
+        }
         Some(Commands::Scan(args)) => {
             cli::scan::scan(&options.global, args, &mut multi_progress_container)?;
         }
+        Some(Commands::Plan(args)) => cli::plan::plan(
+            &options.global,
+            args,
+            &mut multi_progress_container,
+            &mut output,
+        )?,
         Some(Commands::Submit(args)) => cli::submit::submit(
             &options.global,
             args,
             &mut multi_progress_container,
             &mut output,
         )?,
+        Some(Commands::Exec(args)) => cli::exec::exec(
+            &options.global,
+            args,
+            &mut multi_progress_container,
+            &mut output,
+        )?,
+        Some(Commands::Rerun(args)) => cli::rerun::rerun(
+            &options.global,
+            args,
+            &mut multi_progress_container,
+            &mut output,
+        )?,
+        Some(Commands::Wait(args)) => {
+            cli::wait::wait(&options.global, &args, &mut multi_progress_container)?;
+        }
+        Some(Commands::Check(args)) => {
+            cli::check::check(&options.global, &args, &mut output)?;
+        }
+        Some(Commands::Doctor(args)) => {
+            cli::doctor::doctor(
+                &options.global,
+                &args,
+                &mut multi_progress_container,
+                &mut output,
+            )?;
+        }
+        Some(Commands::Mv(args)) => {
+            cli::mv::mv(&options.global, args, &mut multi_progress_container)?;
+        }
+        Some(Commands::Sweep(args)) => {
+            cli::sweep::sweep(&options.global, &args, &mut output)?;
+        }
+        Some(Commands::Rm(args)) => cli::rm::rm(
+            &options.global,
+            args,
+            &mut multi_progress_container,
+            &mut output,
+        )?,
         Some(Commands::Clean(args)) => {
             cli::clean::clean(&options.global, &args, &mut multi_progress_container)?;
         }
+        Some(Commands::Export(args)) => {
+            cli::export::export(&options.global, &args, &mut multi_progress_container)?;
+        }
+        Some(Commands::Import(args)) => cli::import::import(
+            &options.global,
+            &args,
+            &mut multi_progress_container,
+            &mut output,
+        )?,
+        Some(Commands::Alias(alias)) => match alias {
+            AliasCommands::Add(args) => {
+                cli::alias::add(&options.global, args, &mut multi_progress_container)?;
+            }
+            AliasCommands::Remove(args) => cli::alias::remove(&options.global, &args)?,
+            AliasCommands::List(args) => cli::alias::list(
+                &options.global,
+                &args,
+                &mut multi_progress_container,
+                &mut output,
+            )?,
+        },
+        Some(Commands::Config(config)) => match config {
+            ConfigCommands::Get(args) => cli::config::get(&args, &mut output)?,
+            ConfigCommands::Set(args) => cli::config::set(&args)?,
+            ConfigCommands::List(args) => cli::config::list(&args, &mut output)?,
+        },
+        Some(Commands::Debug(DebugCommands::FakeScheduler(fake))) => match fake {
+            FakeSchedulerCommands::Install(args) => cli::debug::install(&args, &mut output)?,
+        },
         None => (),
     }
 
@@ -113,7 +272,10 @@ fn main_detail() -> Result<(), Box<dyn Error>> {
     output.flush()?;
     drop(output);
 
-    info!("Completed in {}.", HumanDuration(instant.elapsed()));
+    info!(
+        "Completed in {}.",
+        FormattedDuration(instant.elapsed(), options.global.time_format())
+    );
 
     if options.global.clear_progress {
         multi_progress.clear().unwrap();
@@ -123,10 +285,15 @@ fn main_detail() -> Result<(), Box<dyn Error>> {
 }
 
 fn main() -> ExitCode {
-    if let Err(error) = main_detail() {
+    let Err(error) = main_detail() else {
+        return ExitCode::from(exitcode::SUCCESS);
+    };
+
+    let code = error
+        .downcast_ref::<row::Error>()
+        .map_or(exitcode::FAILURE, exitcode::from_error);
+    if code != exitcode::NOTHING_TO_DO {
         error!("{error}");
-        return ExitCode::FAILURE;
     }
-
-    ExitCode::SUCCESS
+    ExitCode::from(code)
 }
@@ -6,13 +6,19 @@ use std::fmt::Write;
 
 use crate::format::HumanDuration;
 
-pub(crate) const STEADY_TICK: u64 = 110;
+/// Milliseconds between spinner updates.
+pub const STEADY_TICK: u64 = 110;
 
 /// Format progress duration in milliseconds
 fn elapsed(state: &ProgressState, w: &mut dyn Write) {
     let _ = write!(w, "{:#}", HumanDuration(state.elapsed()));
 }
 
+/// Format the progress rate in counts per second.
+fn rate(state: &ProgressState, w: &mut dyn Write) {
+    let _ = write!(w, "{:.0}/s", state.per_sec());
+}
+
 /// Create a named spinner.
 ///
 /// # Panics
@@ -37,6 +43,21 @@ pub fn counted_spinner() -> ProgressStyle {
         .tick_strings(&["◐", "◓", "◑", "◒", "⊙"])
 }
 
+/// Create a spinner that displays the current counted position and rate.
+///
+/// # Panics
+/// When the progress style is invalid.
+///
+pub fn rate_spinner() -> ProgressStyle {
+    ProgressStyle::with_template(
+        "{spinner:.green.bold} {msg:.bold}: {human_pos} ({rate:.dim}, {elapsed:.dim})",
+    )
+    .expect("Valid template")
+    .with_key("elapsed", elapsed)
+    .with_key("rate", rate)
+    .tick_strings(&["◐", "◓", "◑", "◒", "⊙"])
+}
+
 /// Create a progress bar that displays the current counted position.
 ///
 /// # Panics
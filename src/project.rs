@@ -3,22 +3,39 @@
 
 use indicatif::ProgressBar;
 use log::{debug, trace, warn};
+use rayon::prelude::*;
 use serde_json::Value;
+use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use std::time::Duration;
 
+use crate::alias::Aliases;
 use crate::cluster::{self, SchedulerType};
 use crate::expr;
 use crate::launcher;
 use crate::progress_styles;
 use crate::scheduler::bash::Bash;
+use crate::scheduler::kubernetes::Kubernetes;
 use crate::scheduler::slurm::Slurm;
-use crate::scheduler::Scheduler;
-use crate::state::State;
-use crate::workflow::{Action, Selector, Workflow};
-use crate::{Error, MultiProgressContainer};
+use crate::scheduler::{ActiveJobs, JobTemplate, Scheduler};
+use crate::state::{
+    group_id, Completions, Conflict, Import, Imported, JobRecord, State, SubmitLock,
+};
+use crate::values::DirectoryValues;
+use crate::workflow::{
+    self, Action, Comparison, Leftover, Missing, ResourceCost, Selector, Workflow,
+};
+use crate::workspace;
+use crate::{
+    Error, MultiProgressContainer, DEFAULT_QUEUE_TIMEOUT, TOML_WORKFLOW_FILE_NAME,
+    YAML_WORKFLOW_FILE_NAME,
+};
 
 /// Encapsulate the workflow, state, and scheduler into a project.
 ///
@@ -46,6 +63,33 @@ pub struct Project {
 
     /// The cluster's name.
     cluster_name: String,
+
+    /// The cluster's scheduler.
+    scheduler_type: SchedulerType,
+
+    /// The URL of a web page that shows a job on the cluster.
+    job_url_template: Option<String>,
+
+    /// The maximum number of submissions per minute on the cluster.
+    max_submissions_per_minute: Option<NonZeroU32>,
+
+    /// Completed actions in other projects named by `previous_actions`.
+    previous_projects: HashMap<PathBuf, Completions>,
+
+    /// Short names for directories.
+    aliases: Aliases,
+
+    /// Set when the status of submitted jobs is unknown.
+    offline: bool,
+
+    /// Number of threads to use for IO intensive operations.
+    io_threads: u16,
+
+    /// Results of `eligible_when` commands by action and directory.
+    eligible_when_results: RefCell<HashMap<(String, PathBuf), bool>>,
+
+    /// Number of completed and included directories by action, for thresholds.
+    completed_counts: RefCell<HashMap<String, (usize, usize)>>,
 }
 
 /// Store individual sets of jobs, separated by status for a given action.
@@ -67,33 +111,167 @@ pub struct Status {
     pub waiting: Vec<PathBuf>,
 }
 
+/// Groups separated by size.
+///
+/// Call `Project::separate_leftover_groups` to produce a `Leftovers`.
+///
+#[derive(Debug)]
+pub struct Leftovers {
+    /// Groups to submit.
+    pub submit: Vec<Vec<PathBuf>>,
+
+    /// Groups smaller than `minimum_size` to submit later.
+    pub deferred: Vec<Vec<PathBuf>>,
+}
+
+/// How the finished jobs of an action used the resources they requested.
+///
+/// Call `Project::efficiency` to produce an `Efficiency`.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Efficiency {
+    /// Number of jobs with known start and finish times.
+    pub jobs: usize,
+
+    /// The longest runtime of the jobs in seconds.
+    pub longest_runtime: u64,
+
+    /// The largest fraction of the requested walltime that any job used.
+    pub walltime_fraction: f64,
+
+    /// The distinct numbers of directories in the jobs.
+    pub group_sizes: BTreeSet<usize>,
+
+    /// The distinct numbers of GPUs that the jobs requested.
+    pub gpus: BTreeSet<usize>,
+}
+
+/// Weighted progress of the workflow over a set of directories.
+///
+/// Call `Project::progress` to produce a `Progress`.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Progress {
+    /// Number of directories.
+    pub directories: usize,
+
+    /// Number of actions completed in each directory, summed over directories.
+    pub completed: usize,
+
+    /// Number of actions that include each directory, summed over directories.
+    pub total: usize,
+
+    /// Estimated cost of the completed actions.
+    pub completed_cost: ResourceCost,
+
+    /// Estimated cost of all actions.
+    pub total_cost: ResourceCost,
+}
+
+impl Progress {
+    /// Get the completed fraction of the work.
+    ///
+    /// Weight each action by its estimated cost (counting one GPU-hour as one
+    /// CPU-hour). Count actions equally when the total cost is zero.
+    ///
+    /// # Returns
+    /// `None` when no action includes any of the directories.
+    ///
+    pub fn fraction(&self) -> Option<f64> {
+        let total_hours = self.total_cost.cpu_hours + self.total_cost.gpu_hours;
+        if total_hours > 0.0 {
+            Some((self.completed_cost.cpu_hours + self.completed_cost.gpu_hours) / total_hours)
+        } else if self.total > 0 {
+            Some(self.completed as f64 / self.total as f64)
+        } else {
+            None
+        }
+    }
+}
+
 impl Project {
     /// Open a project from the current working directory or any parents.
     ///
+    /// `queue_timeout` sets the number of seconds to wait for each attempt to
+    /// query the scheduler's queue. When the query fails, `Project` warns and
+    /// keeps every submitted job in the cache (as `open_offline` does).
+    ///
     /// # Errors
     /// Returns `Err<row::Error>` when the project cannot be opened.
     ///
     pub fn open(
-        io_threads: u16,
+        io_threads: Option<u16>,
         cluster_name: &Option<String>,
+        workspace: Option<&Path>,
+        queue_timeout: Option<u64>,
+        multi_progress: &mut MultiProgressContainer,
+    ) -> Result<Project, Error> {
+        Self::open_impl(
+            io_threads,
+            cluster_name.as_deref(),
+            workspace,
+            queue_timeout,
+            false,
+            multi_progress,
+        )
+    }
+
+    /// Open a project without querying the scheduler's queue.
+    ///
+    /// The status of submitted jobs is unknown: `Project` keeps every
+    /// submitted job in the cache.
+    ///
+    /// # Errors
+    /// Returns `Err<row::Error>` when the project cannot be opened.
+    ///
+    pub fn open_offline(
+        io_threads: Option<u16>,
+        cluster_name: &Option<String>,
+        workspace: Option<&Path>,
+        multi_progress: &mut MultiProgressContainer,
+    ) -> Result<Project, Error> {
+        Self::open_impl(
+            io_threads,
+            cluster_name.as_deref(),
+            workspace,
+            None,
+            true,
+            multi_progress,
+        )
+    }
+
+    fn open_impl(
+        io_threads: Option<u16>,
+        cluster_name: Option<&str>,
+        workspace: Option<&Path>,
+        queue_timeout: Option<u64>,
+        mut offline: bool,
         multi_progress: &mut MultiProgressContainer,
     ) -> Result<Project, Error> {
         trace!("Opening project.");
-        let workflow = Workflow::open()?;
+        let workflow = Workflow::open_with_workspace(workspace)?;
+        let io_threads = workflow.io_threads(io_threads);
         let clusters = cluster::Configuration::open()?;
-        let cluster = clusters.identify(cluster_name.as_deref())?;
-        let launchers = launcher::Configuration::open()?.by_cluster(&cluster.name);
+        let cluster = clusters.identify(cluster_name)?;
+        let launchers = launcher::Configuration::open()?.for_cluster(&cluster)?;
         let cluster_name = cluster.name.clone();
+        let scheduler_type = cluster.scheduler.clone();
+        let job_url_template = cluster.job_url_template.clone();
+        let max_submissions_per_minute = cluster.max_submissions_per_minute;
 
-        let scheduler: Box<dyn Scheduler> = match cluster.scheduler {
-            SchedulerType::Bash => Box::new(Bash::new(cluster, launchers)),
-            SchedulerType::Slurm => Box::new(Slurm::new(cluster, launchers)),
-        };
+        let template = JobTemplate::open()?;
+        let scheduler = new_scheduler(cluster, launchers, template, &workflow, queue_timeout);
 
         let mut state = State::from_cache(&workflow)?;
+        let previous_projects = read_previous_projects(&workflow, io_threads, multi_progress)?;
+        let aliases = Aliases::open(&workflow)?;
 
         // squeue will likely take the longest to finish, start it first.
-        let jobs = state.jobs_submitted_on(&cluster_name);
+        let mut jobs = state.jobs_submitted_on(&cluster_name);
+        if offline {
+            debug!("Skipping the queue query for {} jobs.", jobs.len());
+            jobs.clear();
+        }
         let mut progress =
             ProgressBar::new_spinner().with_message("Checking submitted job statuses");
         progress = multi_progress.add_or_hide(progress, jobs.is_empty());
@@ -102,26 +280,64 @@ impl Project {
         progress.set_style(progress_styles::uncounted_spinner());
         progress.tick();
 
-        let active_jobs = scheduler.active_jobs(&jobs)?;
+        let active_jobs = match scheduler.active_jobs(&jobs) {
+            Ok(active_jobs) => Some(active_jobs),
+            Err(Error::SpawnProcess(executable, error))
+                if error.kind() == io::ErrorKind::NotFound =>
+            {
+                warn!(
+                    "'{executable}' not found. The status of {} submitted {} is unknown.",
+                    jobs.len(),
+                    if jobs.len() == 1 { "job" } else { "jobs" }
+                );
+                offline = true;
+                None
+            }
+            Err(error) => return Err(error),
+        };
 
         // Then synchronize with the workspace while squeue is running.
-        state.synchronize_workspace(&workflow, io_threads, multi_progress)?;
+        state.synchronize_workspace(&workflow, &cluster_name, io_threads, multi_progress)?;
 
         // Now, wait for squeue to finish and remove any inactive jobs.
-        let active_jobs = active_jobs.get()?;
-        progress.finish();
-
-        if active_jobs.len() != jobs.len() {
-            state.remove_inactive_submitted(&cluster_name, &active_jobs);
-        } else if !jobs.is_empty() {
-            trace!("All submitted jobs remain active on {cluster_name}.");
+        // When the queue cannot be read, keep the previous submitted jobs
+        // instead of failing the whole command.
+        match active_jobs.map(ActiveJobs::get) {
+            Some(Ok(active_jobs)) => {
+                if active_jobs.len() != jobs.len() {
+                    state.remove_inactive_submitted(&cluster_name, &active_jobs);
+                } else if !jobs.is_empty() {
+                    trace!("All submitted jobs remain active on {cluster_name}.");
+                }
+            }
+            Some(Err(error)) => {
+                warn!("{}", error.to_string().trim_end());
+                warn!(
+                    "The status of {} submitted {} is unknown. Assuming that {} still submitted.",
+                    jobs.len(),
+                    if jobs.len() == 1 { "job" } else { "jobs" },
+                    if jobs.len() == 1 { "it is" } else { "they are" }
+                );
+                offline = true;
+            }
+            None => (),
         }
+        progress.finish();
 
         Ok(Self {
             workflow,
             state,
             scheduler,
             cluster_name,
+            scheduler_type,
+            job_url_template,
+            max_submissions_per_minute,
+            previous_projects,
+            aliases,
+            offline,
+            io_threads,
+            eligible_when_results: RefCell::new(HashMap::new()),
+            completed_counts: RefCell::new(HashMap::new()),
         })
     }
 
@@ -153,6 +369,94 @@ impl Project {
         &self.state
     }
 
+    /// Get the name of the cluster that the project submits jobs to.
+    pub fn cluster_name(&self) -> &str {
+        &self.cluster_name
+    }
+
+    /// Get the URL of a web page that shows a submitted job.
+    ///
+    /// # Returns
+    /// `Some(url)` when the job was submitted on the current cluster and the
+    /// cluster sets `job_url_template`. `None` otherwise.
+    ///
+    pub fn job_url(&self, cluster_name: &str, job_id: u32) -> Option<String> {
+        if cluster_name != self.cluster_name {
+            return None;
+        }
+
+        self.job_url_template
+            .as_ref()
+            .map(|template| template.replace("{job_id}", &job_id.to_string()))
+    }
+
+    /// Get the type of the cluster's scheduler.
+    pub fn scheduler_type(&self) -> &SchedulerType {
+        &self.scheduler_type
+    }
+
+    /// Get the maximum number of jobs to submit per minute on the cluster.
+    pub fn max_submissions_per_minute(&self) -> Option<NonZeroU32> {
+        self.max_submissions_per_minute
+    }
+
+    /// Check whether the status of submitted jobs is unknown.
+    ///
+    /// `true` when the project was opened offline or the scheduler's queue
+    /// could not be queried.
+    ///
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Get the number of threads to use for IO intensive operations.
+    pub fn io_threads(&self) -> u16 {
+        self.io_threads
+    }
+
+    /// Get the project's directory aliases.
+    pub fn aliases(&self) -> &Aliases {
+        &self.aliases
+    }
+
+    /// Read complete values when the cached values lack any of the given pointers.
+    ///
+    /// # Returns
+    /// `Ok(None)` when the cached values contain all `pointers`. Otherwise,
+    /// `Ok(Some(values))` with the complete values of `directories`.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when a value file cannot be read or parsed.
+    ///
+    pub fn read_uncached_values(
+        &self,
+        pointers: &[String],
+        directories: Vec<PathBuf>,
+        io_threads: u16,
+        multi_progress: &mut MultiProgressContainer,
+    ) -> Result<Option<DirectoryValues>, Error> {
+        let Some(cached) = self.state.value_pointers() else {
+            return Ok(None);
+        };
+
+        if pointers
+            .iter()
+            .all(|p| workspace::is_pointer_cached(cached, p))
+        {
+            return Ok(None);
+        }
+
+        debug!("Reading complete values to resolve pointers that are not cached.");
+        let values = workspace::read_values(
+            &self.workflow,
+            directories,
+            None,
+            io_threads,
+            multi_progress,
+        )?;
+        Ok(Some(DirectoryValues::try_from_iter(values.get()?)?))
+    }
+
     /// Find the directories that are included by the action.
     ///
     /// # Parameters:
@@ -182,60 +486,39 @@ impl Project {
 
         let mut matching_directories = Vec::with_capacity(directories.len());
 
-        'outer: for name in directories {
+        // `group_directories` excludes these directories, so they never match.
+        let exclude_missing_sort_key = action.group.missing() == Missing::Exclude;
+
+        for name in directories {
             if let Some(value) = self.state.values().get(&name) {
-                if action.group.include().is_empty() {
+                let completed = self.state.completed();
+                let is_completed =
+                    |a: &str| completed.get(a).is_some_and(|d| d.contains_key(&name));
+                if exclude_missing_sort_key
+                    && action
+                        .group
+                        .sort_by()
+                        .iter()
+                        .any(|pointer| value.pointer(pointer).is_none())
+                {
+                    trace!(
+                        "Excluding '{}': its value is missing a sort_by pointer.",
+                        name.display()
+                    );
+                    continue;
+                }
+
+                if matches_any(
+                    action.group.include(),
+                    action.group.missing(),
+                    &name,
+                    value,
+                    is_completed,
+                )? {
                     matching_directories.push(name);
-                } else {
-                    for selector in action.group.include() {
-                        let result = match selector {
-                            Selector::Condition((include, comparison, expected)) => {
-                                let actual = value.pointer(include).ok_or_else(|| {
-                                    Error::JSONPointerNotFound(name.clone(), include.clone())
-                                })?;
-
-                                expr::evaluate_json_comparison(comparison, actual, expected)
-                                    .ok_or_else(|| {
-                                        Error::CannotCompareInclude(
-                                            actual.clone(),
-                                            expected.clone(),
-                                            name.clone(),
-                                        )
-                                    })
-                            }
-
-                            Selector::All(conditions) => {
-                                let mut matches = 0;
-                                for (include, comparison, expected) in conditions {
-                                    let actual = value.pointer(include).ok_or_else(|| {
-                                        Error::JSONPointerNotFound(name.clone(), include.clone())
-                                    })?;
-
-                                    if !expr::evaluate_json_comparison(comparison, actual, expected)
-                                        .ok_or_else(|| {
-                                            Error::CannotCompareInclude(
-                                                actual.clone(),
-                                                expected.clone(),
-                                                name.clone(),
-                                            )
-                                        })?
-                                    {
-                                        break;
-                                    }
-                                    matches += 1;
-                                }
-                                Ok(matches == conditions.len())
-                            }
-                        };
-
-                        if result? {
-                            matching_directories.push(name);
-                            continue 'outer;
-                        }
-                    }
                 }
             } else {
-                warn!("Directory '{}' not found in workspace.", name.display());
+                warn!("{}", self.state.values().directory_not_found(name));
             }
         }
 
@@ -253,7 +536,8 @@ impl Project {
     /// `Ok(Status)` listing all input `directories` in categories.
     ///
     /// # Errors
-    /// `Err(row::Error)` when a given directory is not present.
+    /// `Err(row::Error)` when a given directory is not present or a JSON
+    /// pointer in a conditional previous action cannot be resolved.
     ///
     pub fn separate_by_status(
         &self,
@@ -275,19 +559,17 @@ impl Project {
 
         for directory_name in directories {
             if !self.state.values().contains_key(&directory_name) {
-                return Err(Error::DirectoryNotFound(directory_name));
+                return Err(self.state.values().directory_not_found(directory_name));
             }
 
             let completed = self.state.completed();
 
-            if completed[action.name()].contains(&directory_name) {
+            if completed[action.name()].contains_key(&directory_name) {
                 status.completed.push(directory_name);
             } else if self.state.is_submitted(action.name(), &directory_name) {
                 status.submitted.push(directory_name);
-            } else if action
-                .previous_actions()
-                .iter()
-                .all(|a| completed[a].contains(&directory_name))
+            } else if self.previous_actions_completed(action, &directory_name, &HashMap::new())?
+                && self.eligible_when(action, &directory_name)?
             {
                 status.eligible.push(directory_name);
             } else {
@@ -298,8 +580,207 @@ impl Project {
         Ok(status)
     }
 
+    /// Check whether a directory is still eligible to execute an action.
+    ///
+    /// Unlike `separate_by_status`, `recheck_eligible` ignores submitted jobs,
+    /// scans the directory's products again so that actions completed since
+    /// the project opened count, and executes the `eligible_when` command again.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when the directory is not present, its products cannot
+    /// be scanned, or the `eligible_when` command cannot be executed.
+    ///
+    pub fn recheck_eligible(
+        &self,
+        action: &Action,
+        directory_name: &Path,
+        multi_progress: &mut MultiProgressContainer,
+    ) -> Result<bool, Error> {
+        if !self.state.values().contains_key(directory_name) {
+            return Err(self
+                .state
+                .values()
+                .directory_not_found(directory_name.to_path_buf()));
+        }
+
+        let scanned = workspace::find_completed_directories(
+            &self.workflow,
+            vec![directory_name.to_path_buf()],
+            1,
+            multi_progress,
+        )?
+        .get()?;
+
+        if self.state.completed()[action.name()].contains_key(directory_name)
+            || scanned
+                .get(action.name())
+                .is_some_and(|d| d.contains(directory_name))
+        {
+            return Ok(false);
+        }
+
+        if !self.previous_actions_completed(action, directory_name, &scanned)? {
+            return Ok(false);
+        }
+
+        match action.eligible_when() {
+            None => Ok(true),
+            Some(eligible_when) => {
+                self.execute_eligible_when(action, directory_name, eligible_when)
+            }
+        }
+    }
+
+    /// Check whether the previous actions of `action` are completed in a directory.
+    ///
+    /// `scanned` lists additional directories known to complete each action.
+    ///
+    fn previous_actions_completed(
+        &self,
+        action: &Action,
+        directory_name: &Path,
+        scanned: &HashMap<String, HashSet<PathBuf>>,
+    ) -> Result<bool, Error> {
+        let completed = self.state.completed();
+        for previous in action.all_previous_actions() {
+            if previous.threshold().is_some() {
+                self.count_completed(previous.name())?;
+            }
+        }
+        let completed_counts = self.completed_counts.borrow();
+
+        action.previous_actions_completed(
+            |previous| {
+                let a = previous.name();
+                let is_completed = match previous.project() {
+                    None => {
+                        completed[a].contains_key(directory_name)
+                            || scanned.get(a).is_some_and(|d| d.contains(directory_name))
+                    }
+                    Some(project) => self.previous_projects[project]
+                        .get(a)
+                        .is_some_and(|d| d.contains_key(directory_name)),
+                };
+                is_completed
+                    && previous.threshold().map_or(true, |threshold| {
+                        let (n_completed, n_included) = completed_counts[a];
+                        threshold.is_met(n_completed, n_included)
+                    })
+            },
+            |include| {
+                let value = self.state.values().get(directory_name).ok_or_else(|| {
+                    self.state
+                        .values()
+                        .directory_not_found(directory_name.to_path_buf())
+                })?;
+                let is_completed = |a: &str| {
+                    completed
+                        .get(a)
+                        .is_some_and(|d| d.contains_key(directory_name))
+                        || scanned.get(a).is_some_and(|d| d.contains(directory_name))
+                };
+                matches_any(include, Missing::Error, directory_name, value, is_completed)
+            },
+        )
+    }
+
+    /// Count the directories that have completed an action.
+    ///
+    /// Remember the result for the lifetime of the `Project`.
+    ///
+    /// # Returns
+    /// `Ok((completed, included))` where `included` is the number of directories
+    /// that any action with the given name includes.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when an include pointer cannot be resolved.
+    ///
+    fn count_completed(&self, action_name: &str) -> Result<(usize, usize), Error> {
+        if let Some(counts) = self.completed_counts.borrow().get(action_name) {
+            return Ok(*counts);
+        }
+
+        let mut included = HashSet::new();
+        for action in self
+            .workflow
+            .action
+            .iter()
+            .filter(|a| a.name() == action_name)
+        {
+            included.extend(self.find_matching_directories(action, self.state.list_directories())?);
+        }
+        let completed = self.state.completed().get(action_name).map_or(0, |d| {
+            included.iter().filter(|i| d.contains_key(*i)).count()
+        });
+
+        let counts = (completed, included.len());
+        self.completed_counts
+            .borrow_mut()
+            .insert(action_name.into(), counts);
+        Ok(counts)
+    }
+
+    /// Check whether the `eligible_when` command of `action` succeeds in a directory.
+    ///
+    /// Execute the command with `sh -c` in the workspace directory. Remember the
+    /// result for the lifetime of the `Project`.
+    ///
+    /// # Returns
+    /// `Ok(true)` when the action has no `eligible_when` command or the command
+    /// exits with status 0.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when the command cannot be executed.
+    ///
+    fn eligible_when(&self, action: &Action, directory_name: &Path) -> Result<bool, Error> {
+        let Some(eligible_when) = action.eligible_when() else {
+            return Ok(true);
+        };
+
+        let key = (action.name().to_string(), directory_name.to_path_buf());
+        if let Some(result) = self.eligible_when_results.borrow().get(&key) {
+            return Ok(*result);
+        }
+
+        let result = self.execute_eligible_when(action, directory_name, eligible_when)?;
+        self.eligible_when_results.borrow_mut().insert(key, result);
+        Ok(result)
+    }
+
+    /// Execute an `eligible_when` command in a directory.
+    fn execute_eligible_when(
+        &self,
+        action: &Action,
+        directory_name: &Path,
+        eligible_when: &str,
+    ) -> Result<bool, Error> {
+        // Quote the expanded variables so that names with spaces remain one argument.
+        let command = eligible_when
+            .replace("{action}", "\"$ACTION_NAME\"")
+            .replace("{directory}", "\"$ACTION_DIRECTORY\"");
+        trace!(
+            "Executing '{eligible_when}' for action '{}' on '{}'.",
+            action.name(),
+            directory_name.display()
+        );
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .current_dir(self.workflow.root.join(&self.workflow.workspace.path))
+            .env("ACTION_NAME", action.name())
+            .env("ACTION_DIRECTORY", directory_name)
+            .stdin(Stdio::null())
+            .stdout(io::stderr())
+            .status()
+            .map_err(|e| Error::SpawnProcess("sh".into(), e))?;
+
+        Ok(status.success())
+    }
+
     /// Separate directories into groups based on the given parameters
     ///
+    /// See `group_directories`.
+    ///
     /// # Errors
     /// `Err(row::Error)` when a given directory is not present or a JSON
     /// pointer used for sorting is not present.
@@ -310,85 +791,142 @@ impl Project {
     pub fn separate_into_groups(
         &self,
         action: &Action,
-        mut directories: Vec<PathBuf>,
+        directories: Vec<PathBuf>,
     ) -> Result<Vec<Vec<PathBuf>>, Error> {
-        trace!(
-            "Separating {} directories into groups for '{}'.",
-            directories.len(),
-            action.name()
-        );
-
-        if directories.is_empty() {
-            return Ok(Vec::new());
-        }
+        group_directories(self.state.values(), action, directories)
+    }
 
-        // First, sort the directories by name.
-        directories.sort_unstable();
+    /// Measure the weighted progress of the given actions.
+    ///
+    /// Separate the directories of each action into the groups that `row submit`
+    /// would submit and weight each group by its estimated cost. A partially
+    /// completed group contributes the completed fraction of its cost. When `by`
+    /// is set, separate the directories by the JSON pointer `by` and measure the
+    /// progress of each separate set.
+    ///
+    /// # Returns
+    /// `Ok(Vec<(key, Progress)>)` sorted by key. The key is the value of
+    /// `by` (`None` when `by` is not set).
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when a directory is not present, an include or sort
+    /// pointer cannot be resolved, or a directory's value does not contain `by`.
+    ///
+    pub fn progress(
+        &self,
+        actions: &[&Action],
+        directories: Vec<PathBuf>,
+        by: Option<(&str, &DirectoryValues)>,
+    ) -> Result<Vec<(Option<Value>, Progress)>, Error> {
+        let sets = match by {
+            None => vec![(None, directories)],
+            Some((pointer, values)) => {
+                let mut sets: HashMap<String, (Value, Vec<PathBuf>)> = HashMap::new();
+                for directory in directories {
+                    let value = values
+                        .get(&directory)
+                        .ok_or_else(|| values.directory_not_found(directory.clone()))?;
+                    let key = value.pointer(pointer).cloned().ok_or_else(|| {
+                        Error::JSONPointerNotFound(directory.clone(), pointer.into())
+                    })?;
+                    sets.entry(key.to_string())
+                        .or_insert_with(|| (key, Vec::new()))
+                        .1
+                        .push(directory);
+                }
 
-        // Determine the user-provided sort keys.
-        let mut sort_keys = HashMap::new();
-        for directory_name in &directories {
-            let value = self
-                .state
-                .values()
-                .get(directory_name)
-                .ok_or_else(|| Error::DirectoryNotFound(directory_name.clone()))?;
+                // Order keys of different types by their JSON text.
+                let mut sets: Vec<_> = sets.into_values().collect();
+                sets.sort_by(|(a, _), (b, _)| {
+                    expr::partial_cmp_json_values_null_first(a, b)
+                        .unwrap_or_else(|| a.to_string().cmp(&b.to_string()))
+                });
+                sets.into_iter()
+                    .map(|(key, directories)| (Some(key), directories))
+                    .collect()
+            }
+        };
 
-            let mut sort_key = Vec::new();
-            for pointer in action.group.sort_by() {
-                let element = value.pointer(pointer).ok_or_else(|| {
-                    Error::JSONPointerNotFound(directory_name.clone(), pointer.clone())
-                })?;
-                sort_key.push(element.clone());
+        let completed = self.state.completed();
+        let mut result = Vec::with_capacity(sets.len());
+        for (key, directories) in sets {
+            let mut progress = Progress {
+                directories: directories.len(),
+                ..Progress::default()
+            };
+
+            for action in actions {
+                let matching = self.find_matching_directories(action, directories.clone())?;
+                for group in self.separate_into_groups(action, matching)? {
+                    let n_completed = completed
+                        .get(action.name())
+                        .map_or(0, |d| group.iter().filter(|g| d.contains_key(*g)).count());
+                    let cost = action.resources.cost(group.len());
+                    let fraction = n_completed as f64 / group.len() as f64;
+
+                    progress.total += group.len();
+                    progress.completed += n_completed;
+                    progress.completed_cost = progress.completed_cost
+                        + ResourceCost::with_values(
+                            cost.cpu_hours * fraction,
+                            cost.gpu_hours * fraction,
+                        );
+                    progress.total_cost = progress.total_cost + cost;
+                }
             }
-            sort_keys.insert(directory_name.clone(), Value::Array(sort_key));
+
+            result.push((key, progress));
         }
 
-        // Sort by key when there are keys to sort by.
-        let mut result = Vec::new();
-        if action.group.sort_by().is_empty() {
-            if action.group.reverse_sort() {
-                directories.reverse();
-            }
-            result.push(directories);
-        } else {
-            directories.sort_by(|a, b| {
-                expr::partial_cmp_json_values(&sort_keys[a], &sort_keys[b])
-                    .expect("Valid JSON comparison")
-            });
+        Ok(result)
+    }
 
-            if action.group.reverse_sort() {
-                directories.reverse();
-            }
+    /// Separate groups smaller than the action's `minimum_size`.
+    ///
+    /// Apply the action's `leftover` policy to groups with fewer than
+    /// `minimum_size` directories.
+    ///
+    /// # Returns
+    /// `Ok(Leftovers)` with the groups to submit and the groups to submit later.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when a group is smaller than `minimum_size` and the
+    /// policy is `error`.
+    ///
+    pub fn separate_leftover_groups(
+        action: &Action,
+        groups: Vec<Vec<PathBuf>>,
+    ) -> Result<Leftovers, Error> {
+        let Some(minimum_size) = action.group.minimum_size else {
+            return Ok(Leftovers {
+                submit: groups,
+                deferred: Vec::new(),
+            });
+        };
 
-            // Split by the sort key when requested.
-            #[allow(clippy::redundant_closure_for_method_calls)]
-            if action.group.split_by_sort_key() {
-                result.extend(
-                    directories
-                        .chunk_by(|a, b| {
-                            expr::partial_cmp_json_values(&sort_keys[a], &sort_keys[b])
-                                .expect("Valid JSON comparison")
-                                == Ordering::Equal
-                        })
-                        .map(|v| v.to_vec()),
-                );
-            } else {
-                result.push(directories);
+        let mut submit = Vec::with_capacity(groups.len());
+        let mut deferred = Vec::new();
+        for group in groups {
+            if group.len() >= minimum_size {
+                submit.push(group);
+                continue;
             }
-        }
 
-        if let Some(maximum_size) = action.group.maximum_size {
-            let mut new_result = Vec::new();
-            for array in result {
-                #[allow(clippy::redundant_closure_for_method_calls)]
-                new_result.extend(array.chunks(maximum_size).map(|v| v.to_vec()));
+            match action.group.leftover() {
+                Leftover::Submit => submit.push(group),
+                Leftover::Defer => deferred.push(group),
+                Leftover::Error => {
+                    return Err(Error::GroupBelowMinimumSize(
+                        action.name().into(),
+                        group_id(&group),
+                        group.len(),
+                        minimum_size,
+                    ))
+                }
             }
-
-            result = new_result;
         }
 
-        Ok(result)
+        Ok(Leftovers { submit, deferred })
     }
 
     /// Get the scheduler.
@@ -396,11 +934,379 @@ impl Project {
         self.scheduler.as_ref()
     }
 
+    /// Add a job to the job history.
+    pub fn add_job(&mut self, job: JobRecord) {
+        self.state.add_job(job);
+    }
+
+    /// Summarize how the finished jobs of each action used the requested resources.
+    ///
+    /// Only jobs in the job history with known start and finish times count.
+    ///
+    /// # Returns
+    /// Action name -> efficiency for the actions with at least one such job.
+    ///
+    pub fn efficiency(&self) -> HashMap<String, Efficiency> {
+        let mut result: HashMap<String, Efficiency> = HashMap::new();
+        for job in self.state.jobs() {
+            let Some(runtime) = job.runtime() else {
+                continue;
+            };
+
+            let efficiency = result.entry(job.action.clone()).or_default();
+            efficiency.jobs += 1;
+            efficiency.longest_runtime = efficiency.longest_runtime.max(runtime);
+            if job.walltime > 0 {
+                efficiency.walltime_fraction = efficiency
+                    .walltime_fraction
+                    .max(runtime as f64 / job.walltime as f64);
+            }
+            efficiency.group_sizes.insert(job.directories);
+            efficiency.gpus.insert(job.gpus);
+        }
+        result
+    }
+
+    /// Lock the project against concurrent submissions.
+    ///
+    /// Wait for other processes that are submitting jobs. Then add the jobs
+    /// that they submitted after this project was opened.
+    ///
+    /// # Errors
+    /// Returns `Err<row::Error>` when the lock cannot be acquired or the cache
+    /// cannot be read.
+    ///
+    pub fn lock_submissions(&mut self) -> Result<SubmitLock, Error> {
+        let lock = SubmitLock::acquire(&self.workflow)?;
+        self.state.merge_submitted(&self.workflow)?;
+        Ok(lock)
+    }
+
+    /// Import completions and submitted jobs from another copy of the project.
+    ///
+    /// See `State::import`.
+    ///
+    /// # Errors
+    /// Returns `Err<row::Error>` when an imported entry conflicts with the
+    /// cache and `conflict` is `Conflict::Error`.
+    ///
+    pub fn import(&mut self, import: Import, conflict: Conflict) -> Result<Imported, Error> {
+        self.state
+            .import(&self.workflow, import, &self.cluster_name, conflict)
+    }
+
     /// Add a new submitted job.
     pub fn add_submitted(&mut self, action_name: &str, directories: &[PathBuf], job_id: u32) {
         self.state
             .add_submitted(action_name, directories, &self.cluster_name, job_id);
     }
+
+    /// Remove directories from the completed cache.
+    pub fn remove_completed(&mut self, action_name: &str, directories: &[PathBuf]) {
+        self.state.remove_completed(action_name, directories);
+    }
+
+    /// Rename a directory in the workspace and the caches.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when the directory cannot be renamed.
+    ///
+    pub fn rename_directory(&mut self, old: &Path, new: &Path) -> Result<(), Error> {
+        let workspace_path = self.workflow.root.join(&self.workflow.workspace.path);
+        fs::rename(workspace_path.join(old), workspace_path.join(new))
+            .map_err(|e| Error::DirectoryRename(old.into(), new.into(), e))?;
+        self.state.rename_directory(old, new)
+    }
+
+    /// Remove directories (and their contents) from the workspace and the caches.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when a directory cannot be removed. The caches still
+    /// reflect the directories removed before the error.
+    ///
+    pub fn remove_directories(&mut self, directories: &[PathBuf]) -> Result<(), Error> {
+        let workspace_path = self.workflow.root.join(&self.workflow.workspace.path);
+        let mut removed = Vec::with_capacity(directories.len());
+        let mut result = Ok(());
+        for directory in directories {
+            let path = workspace_path.join(directory);
+            trace!("Removing '{}'.", path.display());
+            if let Err(error) = fs::remove_dir_all(&path) {
+                result = Err(Error::FileRemove(path, error));
+                break;
+            }
+            removed.push(directory.clone());
+        }
+
+        self.state.remove_directories(&self.workflow, &removed);
+        result
+    }
+}
+
+/// Read the completed actions of the other projects named in `previous_actions`.
+///
+/// # Errors
+/// `Err(row::Error)` when a project does not exist or its cache cannot be read.
+///
+fn read_previous_projects(
+    workflow: &Workflow,
+    io_threads: u16,
+    multi_progress: &mut MultiProgressContainer,
+) -> Result<HashMap<PathBuf, Completions>, Error> {
+    let mut result = HashMap::new();
+    for action in &workflow.action {
+        for project in action.previous_projects() {
+            if result.contains_key(project) {
+                continue;
+            }
+
+            let root = workflow.root.join(project);
+            if !root.join(TOML_WORKFLOW_FILE_NAME).is_file()
+                && !root.join(YAML_WORKFLOW_FILE_NAME).is_file()
+            {
+                return Err(Error::PreviousProjectNotFound(
+                    project.into(),
+                    action.name().into(),
+                ));
+            }
+
+            debug!("Reading completed actions in '{}'.", root.display());
+            let other = Workflow::open_in(&root)?;
+            result.insert(
+                project.to_path_buf(),
+                State::read_completed(&other, io_threads, multi_progress)?,
+            );
+        }
+    }
+
+    Ok(result)
+}
+
+/// Create the scheduler for a cluster.
+fn new_scheduler(
+    cluster: cluster::Cluster,
+    launchers: HashMap<String, launcher::Launcher>,
+    template: Option<JobTemplate>,
+    workflow: &Workflow,
+    queue_timeout: Option<u64>,
+) -> Box<dyn Scheduler> {
+    let workspace = workflow.workspace.path.clone();
+    match cluster.scheduler {
+        SchedulerType::Bash => Box::new(
+            Bash::new(cluster, launchers)
+                .with_template(template)
+                .with_workspace(workspace)
+                .with_root(workflow.root.clone())
+                .with_cache_directory(workflow.cache_directory().map(Path::to_path_buf)),
+        ),
+        SchedulerType::Slurm => Box::new(
+            Slurm::new(cluster, launchers)
+                .with_template(template)
+                .with_workspace(workspace)
+                .with_root(workflow.root.clone())
+                .with_cache_directory(workflow.cache_directory().map(Path::to_path_buf))
+                .with_queue_timeout(Duration::from_secs(
+                    queue_timeout.unwrap_or(DEFAULT_QUEUE_TIMEOUT),
+                )),
+        ),
+        SchedulerType::Kubernetes => Box::new(
+            Kubernetes::new(cluster, launchers)
+                .with_template(template)
+                .with_workspace(workspace)
+                .with_root(workflow.root.clone()),
+        ),
+    }
+}
+
+/// Separate directories into groups based on the action's `group` parameters.
+///
+/// Extract the sort keys of all directories in parallel, then sort and split
+/// the directories by key. Handle directories without a sort key according
+/// to `group.missing`.
+///
+/// # Errors
+/// `Err(row::Error)` when a given directory is not present or a JSON
+/// pointer used for sorting is not present and `group.missing = "error"`.
+///
+/// # Panics
+/// When two JSON pointers are not valid for comparison.
+///
+pub fn group_directories(
+    values: &DirectoryValues,
+    action: &Action,
+    mut directories: Vec<PathBuf>,
+) -> Result<Vec<Vec<PathBuf>>, Error> {
+    trace!(
+        "Separating {} directories into groups for '{}'.",
+        directories.len(),
+        action.name()
+    );
+
+    if directories.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // First, sort the directories by name so that the groups do not depend
+    // on the order of `directories`. The stable sort by key below keeps
+    // directories with equal keys in name order.
+    directories.par_sort_unstable();
+
+    let sort_by = action.group.sort_by();
+    let mut result = Vec::new();
+    if sort_by.is_empty() {
+        if let Some(missing) = directories.iter().find(|d| !values.contains_key(d)) {
+            return Err(values.directory_not_found(missing.clone()));
+        }
+
+        if action.group.reverse_sort() {
+            directories.reverse();
+        }
+        result.push(directories);
+    } else {
+        let missing = action.group.missing();
+        let mut keyed = directories
+            .into_par_iter()
+            .map(|directory_name| {
+                let value = values
+                    .get(&directory_name)
+                    .ok_or_else(|| values.directory_not_found(directory_name.clone()))?;
+
+                let mut sort_key = Vec::with_capacity(sort_by.len());
+                for pointer in sort_by {
+                    let element = match (value.pointer(pointer), missing) {
+                        (Some(element), _) => element.clone(),
+                        (None, Missing::Exclude) => return Ok(None),
+                        (None, Missing::TreatAsNull) => Value::Null,
+                        (None, Missing::Error) => {
+                            return Err(Error::JSONPointerNotFound(directory_name, pointer.clone()))
+                        }
+                    };
+                    sort_key.push(element);
+                }
+                Ok(Some((Value::Array(sort_key), directory_name)))
+            })
+            .filter_map(Result::transpose)
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let compare = if missing == Missing::TreatAsNull {
+            expr::partial_cmp_json_values_null_first
+        } else {
+            expr::partial_cmp_json_values
+        };
+        keyed.par_sort_by(|(a, _), (b, _)| compare(a, b).expect("Valid JSON comparison"));
+
+        if action.group.reverse_sort() {
+            keyed.reverse();
+        }
+
+        let (keys, directories): (Vec<Value>, Vec<PathBuf>) = keyed.into_iter().unzip();
+
+        // Split by the sort key when requested.
+        if action.group.split_by_sort_key() {
+            let mut directories = directories.into_iter();
+            for chunk in keys
+                .chunk_by(|a, b| compare(a, b).expect("Valid JSON comparison") == Ordering::Equal)
+            {
+                result.push(directories.by_ref().take(chunk.len()).collect());
+            }
+        } else {
+            result.push(directories);
+        }
+    }
+
+    if let Some(maximum_size) = action.group.maximum_size {
+        let mut new_result = Vec::new();
+        for array in result {
+            let mut array = array.into_iter().peekable();
+            while array.peek().is_some() {
+                new_result.push(array.by_ref().take(maximum_size).collect());
+            }
+        }
+
+        result = new_result;
+    }
+
+    Ok(result)
+}
+
+/// Check whether a directory's value matches any of the selectors.
+///
+/// Conditions on `completed:<action>` compare `is_completed(action)` (a JSON
+/// boolean) instead of an element of the value.
+///
+/// A condition on a JSON pointer that is not present in `value` is an error
+/// with `Missing::Error`, does not match with `Missing::Exclude`, and compares
+/// `null` with `Missing::TreatAsNull`. `null` is not equal to any other value.
+/// A selector's `missing` overrides the given `missing`.
+///
+/// # Returns
+/// `Ok(true)` when `value` matches any selector or `selectors` is empty.
+///
+/// # Errors
+/// `Err(row::Error)` when a JSON pointer cannot be resolved or the values
+/// cannot be compared.
+///
+fn matches_any<F>(
+    selectors: &[Selector],
+    missing: Missing,
+    name: &Path,
+    value: &Value,
+    is_completed: F,
+) -> Result<bool, Error>
+where
+    F: Fn(&str) -> bool,
+{
+    if selectors.is_empty() {
+        return Ok(true);
+    }
+
+    let evaluate = |(include, comparison, expected): &(String, Comparison, Value),
+                    missing: Missing| {
+        let completed;
+        let actual = if let Some(action) = workflow::completed_selector(include) {
+            completed = Value::Bool(is_completed(action));
+            &completed
+        } else {
+            match (value.pointer(include), missing) {
+                (Some(element), _) => element,
+                (None, Missing::Exclude) => return Ok(false),
+                (None, Missing::TreatAsNull) => &Value::Null,
+                (None, Missing::Error) => {
+                    return Err(Error::JSONPointerNotFound(name.into(), include.clone()))
+                }
+            }
+        };
+
+        match expr::evaluate_json_comparison(comparison, actual, expected) {
+            Some(result) => Ok(result),
+            None if actual.is_null() && missing == Missing::TreatAsNull => Ok(matches!(
+                comparison,
+                Comparison::NotEqualTo | Comparison::NotIn
+            )),
+            None => Err(Error::CannotCompareInclude(
+                actual.clone(),
+                expected.clone(),
+                name.into(),
+            )),
+        }
+    };
+
+    for selector in selectors {
+        let missing = selector.missing.unwrap_or(missing);
+        let mut matches = true;
+        for condition in selector.as_slice() {
+            if !evaluate(condition, missing)? {
+                matches = false;
+                break;
+            }
+        }
+
+        if matches {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
 }
 
 #[cfg(test)]
@@ -409,11 +1315,11 @@ mod tests {
     use assert_fs::TempDir;
     use indicatif::{MultiProgress, ProgressDrawTarget};
     use serde_json::Value;
-    use serial_test::serial;
+    use serial_test::{parallel, serial};
     use std::env;
 
     use super::*;
-    use crate::workflow::Comparison;
+    use crate::workflow::{PreviousAction, PreviousActions, Threshold, Walltime};
 
     fn setup(n: usize) -> Project {
         let _ = env_logger::builder()
@@ -471,7 +1377,7 @@ previous_actions = ["two"]
 
         temp.child("workflow.toml").write_str(&workflow).unwrap();
 
-        Project::open(2, &None, &mut multi_progress).unwrap()
+        Project::open(Some(2), &None, None, None, &mut multi_progress).unwrap()
     }
 
     #[test]
@@ -502,7 +1408,7 @@ previous_actions = ["two"]
         let mut action = project.workflow.action[1].clone();
         let include = action.group.include.as_mut().unwrap();
         include.clear();
-        include.push(Selector::All(vec![
+        include.push(Selector::all(vec![
             ("/i".into(), Comparison::GreaterThan, Value::from(4)),
             ("/i".into(), Comparison::LessThan, Value::from(6)),
         ]));
@@ -517,13 +1423,13 @@ previous_actions = ["two"]
         let mut action = project.workflow.action[1].clone();
         let include = action.group.include.as_mut().unwrap();
         include.clear();
-        include.push(Selector::Condition((
+        include.push(Selector::condition((
             "/i".into(),
             Comparison::LessThan,
             Value::from(1),
         )));
 
-        include.push(Selector::Condition((
+        include.push(Selector::condition((
             "/i".into(),
             Comparison::GreaterThan,
             Value::from(6),
@@ -535,6 +1441,20 @@ previous_actions = ["two"]
                 .unwrap(),
             vec![PathBuf::from("dir0"), PathBuf::from("dir7")]
         );
+
+        // Check the completion status of another action.
+        let mut action = project.workflow.action[0].clone();
+        action.group.include = Some(vec![Selector::condition((
+            "completed:two".into(),
+            Comparison::EqualTo,
+            Value::from(false),
+        ))]);
+        assert_eq!(
+            project
+                .find_matching_directories(&action, all_directories.clone())
+                .unwrap(),
+            all_directories[4..8]
+        );
     }
 
     #[test]
@@ -571,6 +1491,67 @@ previous_actions = ["two"]
         assert!(status.submitted.is_empty());
         assert_eq!(status.eligible, all_directories[0..4]);
         assert_eq!(status.waiting, all_directories[4..8]);
+
+        // Eligible when any previous action is completed.
+        let mut action = project.workflow.action[2].clone();
+        action.previous_actions = Some(PreviousActions::Any {
+            any: vec![
+                PreviousAction::Name("two".into()),
+                PreviousAction::Name("one".into()),
+            ],
+        });
+        let status = project
+            .separate_by_status(&action, all_directories.clone())
+            .unwrap();
+        assert_eq!(status.eligible, all_directories);
+        assert!(status.waiting.is_empty());
+
+        action.previous_actions = Some(PreviousActions::Any {
+            any: vec![PreviousAction::Name("two".into())],
+        });
+        let status = project
+            .separate_by_status(&action, all_directories.clone())
+            .unwrap();
+        assert_eq!(status.eligible, all_directories[0..4]);
+        assert_eq!(status.waiting, all_directories[4..8]);
+
+        // Require two only when /i < 6.
+        action.previous_actions = Some(PreviousActions::All(vec![PreviousAction::Conditional {
+            action: "two".into(),
+            include: vec![Selector::condition((
+                "/i".into(),
+                Comparison::LessThan,
+                Value::from(6),
+            ))],
+        }]));
+        let status = project
+            .separate_by_status(&action, all_directories.clone())
+            .unwrap();
+        assert_eq!(
+            status.eligible,
+            [&all_directories[0..4], &all_directories[6..8]].concat()
+        );
+        assert_eq!(status.waiting, all_directories[4..6]);
+
+        // Require two in the directory and in a fraction (or count) of all its
+        // directories. 4 of the 6 directories that two includes are completed.
+        for (threshold, eligible) in [
+            (Threshold::Fraction { fraction: 0.5 }, 4),
+            (Threshold::Fraction { fraction: 0.9 }, 0),
+            (Threshold::Count { count: 4 }, 4),
+            (Threshold::Count { count: 5 }, 0),
+        ] {
+            action.previous_actions = Some(PreviousActions::All(vec![PreviousAction::Threshold {
+                action: "two".into(),
+                threshold,
+                include: Vec::new(),
+            }]));
+            let status = project
+                .separate_by_status(&action, all_directories.clone())
+                .unwrap();
+            assert_eq!(status.eligible, all_directories[0..eligible]);
+            assert_eq!(status.waiting.len(), 8 - eligible);
+        }
     }
 
     #[test]
@@ -629,6 +1610,43 @@ previous_actions = ["two"]
         );
     }
 
+    #[test]
+    #[serial]
+    fn group_leftover() {
+        let project = setup(8);
+
+        let mut all_directories = project.state().list_directories();
+        all_directories.sort_unstable();
+
+        let mut action = project.workflow.action[0].clone();
+        action.group.maximum_size = Some(3);
+        action.group.minimum_size = Some(3);
+        let groups = project
+            .separate_into_groups(&action, all_directories.clone())
+            .unwrap();
+
+        let leftovers = Project::separate_leftover_groups(&action, groups.clone()).unwrap();
+        assert_eq!(leftovers.submit, groups);
+        assert!(leftovers.deferred.is_empty());
+
+        action.group.leftover = Some(Leftover::Defer);
+        let leftovers = Project::separate_leftover_groups(&action, groups.clone()).unwrap();
+        assert_eq!(
+            leftovers.submit,
+            vec![
+                all_directories[0..3].to_vec(),
+                all_directories[3..6].to_vec()
+            ]
+        );
+        assert_eq!(leftovers.deferred, vec![all_directories[6..8].to_vec()]);
+
+        action.group.leftover = Some(Leftover::Error);
+        assert!(matches!(
+            Project::separate_leftover_groups(&action, groups),
+            Err(Error::GroupBelowMinimumSize(_, _, 2, 3))
+        ));
+    }
+
     #[test]
     #[serial]
     fn group_sort() {
@@ -681,4 +1699,194 @@ previous_actions = ["two"]
             ]
         );
     }
+
+    #[test]
+    #[serial]
+    fn group_sort_repeated_pointer() {
+        let project = setup(8);
+
+        let mut all_directories = project.state().list_directories();
+        all_directories.sort_unstable();
+
+        let mut action = project.workflow.action[0].clone();
+        action.group.sort_by = Some(vec!["/j".to_string(), "/j".to_string()]);
+        action.group.split_by_sort_key = Some(true);
+        let groups = project
+            .separate_into_groups(&action, all_directories.clone())
+            .unwrap();
+        assert_eq!(groups.len(), 4);
+        assert_eq!(
+            groups[0],
+            vec![PathBuf::from("dir6"), PathBuf::from("dir7")]
+        );
+
+        let result = project.separate_into_groups(&action, vec![PathBuf::from("missing")]);
+        assert!(matches!(result, Err(Error::DirectoryNotFound(..))));
+        action.group.sort_by = None;
+        let result = project.separate_into_groups(&action, vec![PathBuf::from("missing")]);
+        assert!(matches!(result, Err(Error::DirectoryNotFound(..))));
+    }
+
+    #[test]
+    #[serial]
+    fn group_sort_missing() {
+        let project = setup(4);
+
+        let mut all_directories = project.state().list_directories();
+        all_directories.sort_unstable();
+
+        let mut action = project.workflow.action[0].clone();
+        action.group.sort_by = Some(vec!["/k".to_string(), "/i".to_string()]);
+        action.group.split_by_sort_key = Some(true);
+        let result = project.separate_into_groups(&action, all_directories.clone());
+        assert!(matches!(result, Err(Error::JSONPointerNotFound(_, p)) if p == "/k"));
+
+        action.group.missing = Some(Missing::Exclude);
+        let groups = project
+            .separate_into_groups(&action, all_directories.clone())
+            .unwrap();
+        assert!(groups.is_empty());
+        assert!(project
+            .find_matching_directories(&action, all_directories.clone())
+            .unwrap()
+            .is_empty());
+
+        action.group.missing = Some(Missing::TreatAsNull);
+        let groups = project
+            .separate_into_groups(&action, all_directories.clone())
+            .unwrap();
+        assert_eq!(groups.len(), 4);
+        assert_eq!(groups[0], vec![PathBuf::from("dir0")]);
+    }
+
+    #[test]
+    #[serial]
+    fn progress() {
+        let project = setup(8);
+        let actions: Vec<&Action> = project.workflow.action.iter().collect();
+        let directories = project.state().list_directories();
+
+        let result = project
+            .progress(&actions, directories.clone(), None)
+            .unwrap();
+        assert_eq!(result.len(), 1);
+        let (key, progress) = &result[0];
+        assert_eq!(*key, None);
+        assert_eq!(progress.directories, 8);
+        assert_eq!(progress.completed, 12);
+        assert_eq!(progress.total, 22);
+        assert_eq!(
+            progress.completed_cost,
+            ResourceCost::with_values(12.0, 0.0)
+        );
+        assert_eq!(progress.total_cost, ResourceCost::with_values(22.0, 0.0));
+        assert_eq!(progress.fraction(), Some(12.0 / 22.0));
+
+        let result = project
+            .progress(
+                &actions,
+                directories.clone(),
+                Some(("/j", project.state().values())),
+            )
+            .unwrap();
+        let keys: Vec<_> = result.iter().map(|(k, _)| k.clone().unwrap()).collect();
+        assert_eq!(keys, vec![0, 1, 2, 3]);
+        assert_eq!((result[0].1.completed, result[0].1.total), (2, 4));
+        assert_eq!((result[3].1.completed, result[3].1.total), (4, 6));
+
+        assert!(matches!(
+            project.progress(
+                &actions,
+                directories,
+                Some(("/k", project.state().values()))
+            ),
+            Err(Error::JSONPointerNotFound(..))
+        ));
+
+        assert_eq!(Progress::default().fraction(), None);
+        let progress = Progress {
+            completed: 1,
+            total: 4,
+            ..Progress::default()
+        };
+        assert_eq!(progress.fraction(), Some(0.25));
+
+        // Weight each group by its cost.
+        let mut action = project.workflow.action[1].clone();
+        action.resources.walltime = Some(Walltime::PerSubmission(
+            speedate::Duration::new(true, 0, 3600, 0).unwrap(),
+        ));
+        action.group.maximum_size = Some(4);
+        let mut directories = project.state().list_directories();
+        directories.sort_unstable();
+        let result = project.progress(&[&action], directories, None).unwrap();
+        let (_, progress) = &result[0];
+        assert_eq!((progress.completed, progress.total), (4, 6));
+        assert_eq!(progress.completed_cost, ResourceCost::with_values(1.0, 0.0));
+        assert_eq!(progress.total_cost, ResourceCost::with_values(2.0, 0.0));
+        assert_eq!(progress.fraction(), Some(0.5));
+    }
+
+    #[test]
+    #[parallel]
+    fn matches_missing() {
+        let name = Path::new("dir");
+        let value = serde_json::json!({"a": 1});
+        let condition = |pointer: &str, comparison, expected: i64| {
+            vec![Selector::condition((
+                pointer.into(),
+                comparison,
+                Value::from(expected),
+            ))]
+        };
+        let matches = |selectors: &[Selector], missing| {
+            matches_any(selectors, missing, name, &value, |_| false)
+        };
+
+        let present = condition("/a", Comparison::EqualTo, 1);
+        assert!(matches(&present, Missing::Error).unwrap());
+        assert!(matches(&present, Missing::Exclude).unwrap());
+
+        let equal = condition("/b", Comparison::EqualTo, 1);
+        let not_equal = condition("/b", Comparison::NotEqualTo, 1);
+        assert!(matches!(
+            matches(&equal, Missing::Error),
+            Err(Error::JSONPointerNotFound(_, p)) if p == "/b"
+        ));
+        assert!(!matches(&equal, Missing::Exclude).unwrap());
+        assert!(!matches(&not_equal, Missing::Exclude).unwrap());
+        assert!(!matches(&equal, Missing::TreatAsNull).unwrap());
+        assert!(matches(&not_equal, Missing::TreatAsNull).unwrap());
+        assert!(!matches(
+            &condition("/b", Comparison::LessThan, 1),
+            Missing::TreatAsNull
+        )
+        .unwrap());
+
+        // The selector's missing overrides the given missing.
+        let mut exclude = condition("/b", Comparison::NotEqualTo, 1);
+        exclude[0].missing = Some(Missing::Exclude);
+        assert!(!matches(&exclude, Missing::Error).unwrap());
+        assert!(!matches(&exclude, Missing::TreatAsNull).unwrap());
+    }
+
+    #[test]
+    #[serial]
+    fn group_order_is_deterministic() {
+        let project = setup(8);
+
+        let mut all_directories = project.state().list_directories();
+        all_directories.sort_unstable();
+        let mut reversed = all_directories.clone();
+        reversed.reverse();
+
+        let mut action = project.workflow.action[0].clone();
+        action.group.sort_by = Some(vec!["/j".to_string()]);
+        action.group.split_by_sort_key = Some(true);
+        let groups = project
+            .separate_into_groups(&action, all_directories)
+            .unwrap();
+        let reversed_groups = project.separate_into_groups(&action, reversed).unwrap();
+        assert_eq!(groups, reversed_groups);
+    }
 }
@@ -0,0 +1,237 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+//! Read-only workspace stored in an S3 bucket (experimental).
+
+use aws_config::BehaviorVersion;
+use aws_sdk_s3::error::DisplayErrorContext;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client;
+use indicatif::ProgressBar;
+use log::debug;
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use tokio::runtime::{self, Runtime};
+
+use crate::workspace::{self, Backend};
+use crate::Error;
+
+/// A workspace stored under a key prefix in an S3 bucket.
+///
+/// Each directory in the workspace is a common prefix (delimited by `/`) under
+/// the workspace prefix. Credentials and the region are read from the standard
+/// AWS environment variables and configuration files.
+///
+pub(crate) struct S3 {
+    /// The runtime that executes requests.
+    runtime: Runtime,
+
+    /// The S3 client.
+    client: Client,
+
+    /// The bucket name.
+    bucket: String,
+
+    /// The key prefix of the workspace (empty or ends with `/`).
+    prefix: String,
+}
+
+impl S3 {
+    /// Open the workspace at `url`.
+    ///
+    /// # Errors
+    /// Returns `Err<row::Error>` when `url` is invalid or the runtime cannot be started.
+    ///
+    pub(crate) fn open(url: &str) -> Result<Self, Error> {
+        let (bucket, prefix) = workspace::parse_s3_url(url)?;
+        debug!("Opening workspace in bucket '{bucket}' with prefix '{prefix}'.");
+
+        let runtime = runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| Error::ObjectStore(url.into(), e.to_string()))?;
+        let config = runtime.block_on(aws_config::load_defaults(BehaviorVersion::latest()));
+        let client = Client::new(&config);
+
+        Ok(Self {
+            runtime,
+            client,
+            bucket,
+            prefix,
+        })
+    }
+
+    /// Get the key of a path in the workspace.
+    fn key(&self, path: &Path) -> String {
+        format!("{}{}", self.prefix, path.to_string_lossy())
+    }
+
+    /// List the keys and common prefixes directly under `prefix`.
+    ///
+    /// Both are returned relative to `prefix` without a trailing `/`.
+    ///
+    fn list(&self, prefix: &str) -> Result<(Vec<String>, Vec<String>), Error> {
+        self.runtime.block_on(async {
+            let mut keys = Vec::new();
+            let mut prefixes = Vec::new();
+
+            let mut pages = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(prefix)
+                .delimiter("/")
+                .into_paginator()
+                .send();
+
+            while let Some(page) = pages.next().await {
+                let page = page.map_err(|e| {
+                    Error::ObjectStore(
+                        format!("s3://{}/{prefix}", self.bucket),
+                        DisplayErrorContext(e).to_string(),
+                    )
+                })?;
+
+                for common_prefix in page.common_prefixes() {
+                    if let Some(name) = common_prefix
+                        .prefix()
+                        .and_then(|p| p.strip_prefix(prefix))
+                        .map(|p| p.trim_end_matches('/'))
+                    {
+                        if !name.is_empty() {
+                            prefixes.push(name.to_string());
+                        }
+                    }
+                }
+
+                for object in page.contents() {
+                    if let Some(name) = object.key().and_then(|k| k.strip_prefix(prefix)) {
+                        if !name.is_empty() {
+                            keys.push(name.to_string());
+                        }
+                    }
+                }
+            }
+
+            Ok((keys, prefixes))
+        })
+    }
+}
+
+/// Read the body of an object one chunk at a time.
+struct ObjectReader<'a> {
+    /// The runtime that executes requests.
+    runtime: &'a Runtime,
+
+    /// The body of the object.
+    body: ByteStream,
+
+    /// The current chunk.
+    chunk: Vec<u8>,
+
+    /// The number of bytes already read from the current chunk.
+    position: usize,
+}
+
+impl Read for ObjectReader<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.chunk.len() {
+            match self
+                .runtime
+                .block_on(self.body.try_next())
+                .map_err(io::Error::other)?
+            {
+                Some(chunk) => {
+                    self.chunk = chunk.to_vec();
+                    self.position = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+
+        let n = buffer.len().min(self.chunk.len() - self.position);
+        buffer[..n].copy_from_slice(&self.chunk[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+impl Backend for S3 {
+    fn list_directories(&self, progress: &ProgressBar) -> Result<Vec<OsString>, Error> {
+        let (_, prefixes) = self.list(&self.prefix)?;
+        progress.inc(prefixes.len() as u64);
+        Ok(prefixes.into_iter().map(OsString::from).collect())
+    }
+
+    fn list_entries(&self, directory: &Path) -> Result<HashSet<OsString>, Error> {
+        let (keys, prefixes) = self.list(&format!("{}/", self.key(directory)))?;
+        Ok(keys
+            .into_iter()
+            .chain(prefixes)
+            .map(OsString::from)
+            .collect())
+    }
+
+    fn read_file(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        let key = self.key(path);
+        self.runtime.block_on(async {
+            let error = |message: String| {
+                Error::ObjectStore(format!("s3://{}/{key}", self.bucket), message)
+            };
+
+            let object = self
+                .client
+                .get_object()
+                .bucket(&self.bucket)
+                .key(&key)
+                .send()
+                .await
+                .map_err(|e| error(DisplayErrorContext(e).to_string()))?;
+
+            let bytes = object
+                .body
+                .collect()
+                .await
+                .map_err(|e| error(e.to_string()))?;
+
+            Ok(bytes.into_bytes().to_vec())
+        })
+    }
+
+    fn open_file<'a>(&'a self, path: &Path) -> Result<Box<dyn Read + 'a>, Error> {
+        let key = self.key(path);
+        let object = self
+            .runtime
+            .block_on(
+                self.client
+                    .get_object()
+                    .bucket(&self.bucket)
+                    .key(&key)
+                    .send(),
+            )
+            .map_err(|e| {
+                Error::ObjectStore(
+                    format!("s3://{}/{key}", self.bucket),
+                    DisplayErrorContext(e).to_string(),
+                )
+            })?;
+
+        Ok(Box::new(ObjectReader {
+            runtime: &self.runtime,
+            body: object.body,
+            chunk: Vec::new(),
+            position: 0,
+        }))
+    }
+
+    fn modified_time(&self) -> Result<Option<(i64, i64)>, Error> {
+        // Object stores have no directory modification times.
+        Ok(None)
+    }
+
+    fn location(&self, path: &Path) -> PathBuf {
+        PathBuf::from(format!("s3://{}/{}", self.bucket, self.key(path)))
+    }
+}
@@ -2,20 +2,137 @@
 // Part of row, released under the BSD 3-Clause License.
 
 pub mod bash;
+pub mod kubernetes;
 pub mod slurm;
 
-use std::collections::HashSet;
+use log::{debug, trace};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::io;
+use std::num::NonZeroU32;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use crate::workflow::Action;
 use crate::Error;
 
+/// A user-provided template for job scripts.
+///
+/// The template replaces the built-in skeleton of the job script. It may
+/// reference the pieces of the built-in script (`preamble`, `variables`,
+/// `setup`, `trap`, and `execution`) and details of the job.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JobTemplate {
+    /// The path to the template file.
+    pub path: PathBuf,
+
+    /// The contents of the template file.
+    pub source: String,
+}
+
+impl JobTemplate {
+    /// Open the job script template
+    ///
+    /// Open `$HOME/.config/row/templates/job.sh` if it exists.
+    ///
+    /// # Errors
+    /// Returns `Err(row::Error)` when the file cannot be read.
+    ///
+    pub fn open() -> Result<Option<Self>, Error> {
+        let home = match env::var("ROW_HOME") {
+            Ok(row_home) => PathBuf::from(row_home),
+            Err(_) => home::home_dir().ok_or_else(Error::NoHome)?,
+        };
+        let path = home
+            .join(".config")
+            .join("row")
+            .join("templates")
+            .join("job.sh");
+        Self::open_from_path(path)
+    }
+
+    fn open_from_path(path: PathBuf) -> Result<Option<Self>, Error> {
+        match fs::read_to_string(&path) {
+            Ok(source) => {
+                debug!("Using the job script template '{}'.", path.display());
+                Ok(Some(Self { path, source }))
+            }
+            Err(error) => match error.kind() {
+                io::ErrorKind::NotFound => {
+                    trace!(
+                        "'{}' does not exist, using the built-in job script.",
+                        path.display()
+                    );
+                    Ok(None)
+                }
+                _ => Err(Error::FileRead(path, error)),
+            },
+        }
+    }
+}
+
+/// The position of a job among the groups of one action in a submission.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GroupPosition {
+    /// Index of the group (starting at 0).
+    pub index: usize,
+
+    /// Number of groups of the action.
+    pub count: usize,
+}
+
+impl Default for GroupPosition {
+    fn default() -> Self {
+        GroupPosition { index: 0, count: 1 }
+    }
+}
+
+impl GroupPosition {
+    /// Number the groups of each action in the order they will be submitted.
+    ///
+    /// The numbers depend on the groups in this submission only. They are not
+    /// stable between invocations.
+    ///
+    /// # Returns
+    /// The position of each group, in the same order as `action_names`.
+    ///
+    pub fn number<'a, I>(action_names: I) -> Vec<GroupPosition>
+    where
+        I: IntoIterator<Item = &'a str>,
+        I::IntoIter: Clone,
+    {
+        let names = action_names.into_iter();
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for name in names.clone() {
+            *counts.entry(name).or_default() += 1;
+        }
+
+        let mut next: HashMap<&str, usize> = HashMap::new();
+        names
+            .map(|name| {
+                let index = next.entry(name).or_default();
+                let position = GroupPosition {
+                    index: *index,
+                    count: counts[name],
+                };
+                *index += 1;
+                position
+            })
+            .collect()
+    }
+}
+
 /// A `Scheduler` creates and submits job scripts.
 pub trait Scheduler {
     /// Make a job script given an `Action` and a list of directories.
     ///
+    /// Schedulers that name jobs use `job_name` (see `Action::job_name`). `group`
+    /// is the job's position among the groups of the action in the submission.
+    ///
     /// Useful for showing the script that would be submitted to the user.
     ///
     /// # Returns
@@ -24,7 +141,27 @@ pub trait Scheduler {
     /// # Errors
     /// Returns `Err<row::Error>` when the script cannot be created.
     ///
-    fn make_script(&self, action: &Action, directories: &[PathBuf]) -> Result<String, Error>;
+    fn make_script(
+        &self,
+        action: &Action,
+        directories: &[PathBuf],
+        job_name: &str,
+        group: GroupPosition,
+    ) -> Result<String, Error>;
+
+    /// Select the partitions for a job.
+    ///
+    /// # Returns
+    /// The name of the partition selected for each component of the job. Empty
+    /// when the scheduler does not use partitions.
+    ///
+    /// # Errors
+    /// Returns `Err<row::Error>` when no partition is valid for the job.
+    ///
+    fn partitions(&self, action: &Action, directories: &[PathBuf]) -> Result<Vec<String>, Error> {
+        let _ = (action, directories);
+        Ok(Vec::new())
+    }
 
     /// Submit a job to the scheduler.
     ///
@@ -32,6 +169,8 @@ pub trait Scheduler {
     /// * `working_directory`: The working directory the action should be submitted from.
     /// * `action`: The action to submit.
     /// * `directories`: The directories to include in the submission.
+    /// * `job_name`: The name of the job (see `make_script`).
+    /// * `group`: The position of the job (see `make_script`).
     /// * `should_terminate`: Set to true when the user terminates the process.
     ///
     /// # Returns
@@ -52,6 +191,8 @@ pub trait Scheduler {
         working_directory: &Path,
         action: &Action,
         directories: &[PathBuf],
+        job_name: &str,
+        group: GroupPosition,
         should_terminate: Arc<AtomicBool>,
     ) -> Result<Option<u32>, Error>;
 
@@ -80,3 +221,111 @@ pub trait ActiveJobs {
     ///
     fn get(self: Box<Self>) -> Result<HashSet<u32>, Error>;
 }
+
+/// Limit the rate of job submissions with a token bucket.
+///
+/// The bucket holds up to `per_minute` tokens and refills continuously at
+/// `per_minute` tokens per minute. Each submission takes one token.
+///
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    /// The maximum number of tokens.
+    capacity: f64,
+
+    /// The number of tokens available at `updated`.
+    tokens: f64,
+
+    /// Tokens added per second.
+    rate: f64,
+
+    /// The time when `tokens` was last updated.
+    updated: Instant,
+}
+
+impl RateLimiter {
+    /// Create a full bucket that allows `per_minute` submissions per minute.
+    pub fn new(per_minute: NonZeroU32, now: Instant) -> Self {
+        let capacity = f64::from(per_minute.get());
+        Self {
+            capacity,
+            tokens: capacity,
+            rate: capacity / 60.0,
+            updated: now,
+        }
+    }
+
+    /// Add the tokens accumulated since the last update.
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// Determine how long to wait before the next submission.
+    pub fn delay(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.rate)
+        }
+    }
+
+    /// Take a token for a submission.
+    pub fn take(&mut self, now: Instant) {
+        self.refill(now);
+        self.tokens -= 1.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::parallel;
+
+    use super::*;
+
+    #[test]
+    #[parallel]
+    fn group_positions() {
+        let positions = GroupPosition::number(["a", "b", "a", "a"]);
+        assert_eq!(
+            positions,
+            vec![
+                GroupPosition { index: 0, count: 3 },
+                GroupPosition { index: 0, count: 1 },
+                GroupPosition { index: 1, count: 3 },
+                GroupPosition { index: 2, count: 3 },
+            ]
+        );
+        assert!(GroupPosition::number([]).is_empty());
+    }
+
+    #[test]
+    #[parallel]
+    fn rate_limiter() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(NonZeroU32::new(2).unwrap(), start);
+
+        assert_eq!(limiter.delay(start), Duration::ZERO);
+        limiter.take(start);
+        assert_eq!(limiter.delay(start), Duration::ZERO);
+        limiter.take(start);
+
+        let delay = limiter.delay(start);
+        assert!((delay.as_secs_f64() - 30.0).abs() < 1e-6);
+
+        let later = start + Duration::from_secs(15);
+        let delay = limiter.delay(later);
+        assert!((delay.as_secs_f64() - 15.0).abs() < 1e-6);
+
+        let later = start + Duration::from_secs(30);
+        assert_eq!(limiter.delay(later), Duration::ZERO);
+        limiter.take(later);
+
+        // The bucket holds at most `per_minute` tokens.
+        let much_later = start + Duration::from_secs(3600);
+        limiter.take(much_later);
+        limiter.take(much_later);
+        assert!(limiter.delay(much_later) > Duration::ZERO);
+    }
+}
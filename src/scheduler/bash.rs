@@ -2,8 +2,10 @@
 // Part of row, released under the BSD 3-Clause License.
 
 use log::{debug, error, trace};
+use minijinja::{context, Environment, UndefinedBehavior};
 use nix::sys::signal::{self, Signal};
 use nix::unistd::Pid;
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fmt::Write as _;
@@ -17,10 +19,14 @@ use std::thread;
 use std::time::Duration;
 
 use crate::cluster::Cluster;
-use crate::launcher::Launcher;
-use crate::scheduler::{ActiveJobs, Scheduler};
+use crate::launcher::{self, ActionPrefix, Launcher};
+use crate::scheduler::{ActiveJobs, GroupPosition, JobTemplate, Scheduler};
 use crate::workflow::{Action, Processes};
-use crate::Error;
+use crate::{Error, LOG_DIRECTORY_NAME};
+
+/// The line that follows the user-provided setup commands in a job script.
+pub const SETUP_CHECK: &str =
+    r#"test $? -eq 0 || { >&2 echo "[row] Error executing setup."; exit 1; }"#;
 
 /// `BashScriptBuilder` builds `bash` scripts that execute row actions.
 pub(crate) struct BashScriptBuilder<'a> {
@@ -31,6 +37,12 @@ pub(crate) struct BashScriptBuilder<'a> {
     directories: &'a [PathBuf],
     preamble: &'a str,
     launchers: &'a HashMap<String, Launcher>,
+    template: Option<&'a JobTemplate>,
+    workspace: &'a Path,
+    root: Option<&'a Path>,
+    cache_directory: Option<&'a Path>,
+    row_executable: Option<&'a str>,
+    group: GroupPosition,
 }
 
 impl<'a> BashScriptBuilder<'a> {
@@ -55,15 +67,59 @@ impl<'a> BashScriptBuilder<'a> {
             directories,
             preamble: "",
             launchers,
+            template: None,
+            workspace: Path::new("workspace"),
+            root: None,
+            cache_directory: None,
+            row_executable: None,
+            group: GroupPosition::default(),
         }
     }
 
+    /// Set the workspace path (relative to the directory the script executes in).
+    pub(crate) fn with_workspace(mut self, workspace: &'a Path) -> Self {
+        self.workspace = workspace;
+        self
+    }
+
+    /// Set the position of the job among the groups of the action.
+    pub(crate) fn with_group(mut self, group: GroupPosition) -> Self {
+        self.group = group;
+        self
+    }
+
+    /// Pass the project root to the `row` commands in the script.
+    pub(crate) fn with_root(mut self, root: Option<&'a Path>) -> Self {
+        self.root = root;
+        self
+    }
+
+    /// Export `ROW_CACHE_DIR` so that the `row` commands in the script use the
+    /// given cache directory.
+    pub(crate) fn with_cache_directory(mut self, cache_directory: Option<&'a Path>) -> Self {
+        self.cache_directory = cache_directory;
+        self
+    }
+
+    /// Execute `row` in the script with the given command instead of the path
+    /// to the current executable.
+    pub(crate) fn with_row_executable(mut self, row_executable: Option<&'a str>) -> Self {
+        self.row_executable = row_executable;
+        self
+    }
+
     /// Add a preamble.
     pub(crate) fn with_preamble(mut self, preamble: &'a str) -> Self {
         self.preamble = preamble;
         self
     }
 
+    /// Render the script with a user-provided template.
+    pub(crate) fn with_template(mut self, template: Option<&'a JobTemplate>) -> Self {
+        self.template = template;
+        self
+    }
+
     /// Create the bash script header.
     fn header(&self) -> String {
         let mut result = "#!/bin/bash\n".to_string();
@@ -95,13 +151,27 @@ export ACTION_CLUSTER="{}"
 export ACTION_NAME="{}"
 export ACTION_PROCESSES="{}"
 export ACTION_WALLTIME_IN_MINUTES="{}"
+export ACTION_DIRECTORY_COUNT="{}"
+export ACTION_GROUP_INDEX="{}"
+export ACTION_GROUP_COUNT="{}"
 "#,
             self.cluster_name,
             self.action.name(),
             self.total_processes,
             self.walltime_in_minutes,
+            self.directories.len(),
+            self.group.index,
+            self.group.count,
         );
 
+        if let Some(cache_directory) = self.cache_directory {
+            let _ = writeln!(
+                result,
+                "export ROW_CACHE_DIR=\"{}\"",
+                cache_directory.display()
+            );
+        }
+
         if let Processes::PerDirectory(processes_per_directory) = self.action.resources.processes()
         {
             let _ = writeln!(
@@ -127,7 +197,8 @@ export ACTION_WALLTIME_IN_MINUTES="{}"
         Ok(result)
     }
 
-    fn setup(&self) -> Result<String, Error> {
+    /// Execute the user-provided setup commands.
+    fn setup(&self) -> String {
         let mut result = String::new();
         let user_setup = self
             .action
@@ -140,74 +211,178 @@ export ACTION_WALLTIME_IN_MINUTES="{}"
             result.push('\n');
             result.push_str(&user_setup);
             result.push_str("\n\n");
-            result.push_str(
-                r#"test $? -eq 0 || { >&2 echo "[row] Error executing setup."; exit 1; }"#,
-            );
+            result.push_str(SETUP_CHECK);
         }
 
+        result
+    }
+
+    /// The `--project` and `--workspace` options to pass to `row`.
+    ///
+    /// Empty when the root is not set.
+    ///
+    fn project_option(&self) -> String {
+        self.root
+            .map(|root| {
+                format!(
+                    " --project \"{}\" --workspace \"{}\"",
+                    root.display(),
+                    self.workspace.display()
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    /// The `row` executable that the script calls.
+    ///
+    /// # Panics
+    /// When the path to the current executable is not valid UTF-8.
+    ///
+    fn row_executable(&self) -> Result<Cow<'a, str>, Error> {
+        self.row_executable.map_or_else(
+            || {
+                let current_exe = env::current_exe().map_err(Error::FindCurrentExecutable)?;
+                Ok(Cow::Owned(
+                    current_exe
+                        .into_os_string()
+                        .into_string()
+                        .expect("UTF-8 path to executable."),
+                ))
+            },
+            |row_executable| Ok(Cow::Borrowed(row_executable)),
+        )
+    }
+
+    /// Scan the directories for completed products when the script exits.
+    fn trap(&self) -> Result<String, Error> {
         let action_name = self.action.name();
-        let row_executable = env::current_exe().map_err(Error::FindCurrentExecutable)?;
-        let row_executable = row_executable.to_str().expect("UTF-8 path to executable.");
-        let _ = write!(
-            result,
+        let row_executable = self.row_executable()?;
+        let project = self.project_option();
+        Ok(format!(
             r#"
-trap 'printf %s\\n "${{directories[@]}}" | {row_executable} scan --no-progress -a {action_name} - || exit 3' EXIT"#
+trap 'printf %s\\n "${{directories[@]}}" | {row_executable}{project} scan --no-progress --pack-only -a {action_name} - || exit 3' EXIT"#
+        ))
+    }
+
+    /// Redirect the output of the command for `$directory` to a log file.
+    ///
+    /// # Returns
+    /// `(setup, command)`: The line that creates the log directory and the
+    /// command with its output redirected. `setup` is empty and `command` is
+    /// unchanged when the action does not set `log_output`.
+    ///
+    fn log_redirect(&self, command: String) -> (String, String) {
+        if !self.action.log_output() {
+            return (String::new(), command);
+        }
+
+        let log_directory = format!(
+            "{}/$directory/{LOG_DIRECTORY_NAME}",
+            self.workspace.display()
         );
+        (
+            format!("mkdir -p \"{log_directory}\"\n    "),
+            format!(
+                "{{ {command}; }} > \"{log_directory}/{}-$(date -u +%Y%m%dT%H%M%SZ).log\" 2>&1",
+                self.action.name()
+            ),
+        )
+    }
 
-        Ok(result)
+    /// Expand the steps of the command and chain them with `&&`.
+    ///
+    /// Replace `placeholder` with `value` and apply `launch` to each step.
+    /// When any step includes `{launcher}`, apply `launch` only to the part of
+    /// those steps that follows `{launcher}` and execute the other steps
+    /// without launchers. Otherwise, launchers prefix every step. The chain
+    /// stops at the first step that fails.
+    ///
+    /// Group each step of a multi-step command in `{ ...; }` so that `||`
+    /// and `;` in a step do not change the control flow of the chain.
+    ///
+    fn chain<F>(&self, launch: F, placeholder: &str, value: &str) -> String
+    where
+        F: Fn(String) -> String,
+    {
+        let marked = self.action.command_contains("{launcher}");
+        let commands = self.action.commands();
+        commands
+            .iter()
+            .map(|step| {
+                let step = step.replace(placeholder, value);
+                let step = if marked {
+                    match step.split_once("{launcher}") {
+                        Some((before, after)) => {
+                            format!("{before}{}", launch(after.trim_start().into()))
+                        }
+                        None => step,
+                    }
+                } else {
+                    launch(step)
+                };
+                if commands.len() > 1 {
+                    format!("{{ {step}; }}")
+                } else {
+                    step
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" && ")
     }
 
     fn execution(&self) -> Result<String, Error> {
-        let contains_directory = self.action.command().contains("{directory}");
-        let contains_directories = self.action.command().contains("{directories}");
+        let contains_directory = self.action.command_contains("{directory}");
+        let contains_directories = self.action.command_contains("{directories}");
         if contains_directory && contains_directories {
             return Err(Error::ActionContainsMultipleTemplates(
                 self.action.name().into(),
             ));
         }
 
-        // Build up launcher prefix
-        let mut launcher_prefix = String::new();
-        let mut process_launchers = 0;
-        for launcher in self.action.launchers() {
-            let launcher = self.launchers.get(launcher).ok_or_else(|| {
-                Error::LauncherNotFound(launcher.clone(), self.action.name().into())
-            })?;
-            launcher_prefix
-                .push_str(&launcher.prefix(&self.action.resources, self.directories.len()));
-            if launcher.processes.is_some() {
-                process_launchers += 1;
-            }
+        if self.action.aggregate {
+            return self.aggregate_execution();
         }
 
-        if self.total_processes > 1 && process_launchers == 0 {
-            return Err(Error::NoProcessLauncher(
-                self.action.name().into(),
-                self.total_processes,
-            ));
-        }
-        if process_launchers > 1 {
-            return Err(Error::TooManyProcessLaunchers(self.action.name().into()));
+        if let Some(concurrent) = self.action.resources.concurrent_directories {
+            return self.concurrent_execution(concurrent);
         }
 
+        // Heterogeneous jobs launch the first component with the full prefix
+        // and each following component with only the process launcher's arguments.
+        let ActionPrefix {
+            prefix: launcher_prefix,
+            components: component_arguments,
+        } = launcher::resolve_prefix(
+            self.launchers,
+            self.action,
+            self.cluster_name,
+            self.directories.len(),
+        )?;
+
+        let launch = |command: String| {
+            let mut result = format!("{launcher_prefix}{command}");
+            for arguments in &component_arguments {
+                let _ = write!(result, " : {arguments}{command}");
+            }
+            result
+        };
+
         if contains_directory {
-            let command = self.action.command().replace("{directory}", "$directory");
+            let command = self.chain(launch, "{directory}", "$directory");
+            let (log_setup, command) = self.log_redirect(command);
             Ok(format!(
                 r#"
 for directory in "${{directories[@]}}"
 do
-    {launcher_prefix}{command} || {{ >&2 echo "[ERROR row::action] Error executing command."; exit 2; }}
+    {log_setup}{command} || {{ >&2 echo "[ERROR row::action] Error executing command."; exit 2; }}
 done
 "#
             ))
         } else if contains_directories {
-            let command = self
-                .action
-                .command()
-                .replace("{directories}", r#""${directories[@]}""#);
+            let command = self.chain(launch, "{directories}", r#""${directories[@]}""#);
             Ok(format!(
                 r#"
-{launcher_prefix}{command} || {{ >&2 echo "[row] Error executing command."; exit 1; }}
+{command} || {{ >&2 echo "[row] Error executing command."; exit 1; }}
 "#
             ))
         } else {
@@ -215,8 +390,106 @@ done
         }
     }
 
+    /// Execute the directories with `row exec`, which checks that each is still eligible.
+    fn aggregate_execution(&self) -> Result<String, Error> {
+        let project = self.project_option();
+        let action_name = self.action.name();
+        let cluster_name = self.cluster_name;
+        let row_executable = self.row_executable()?;
+        Ok(format!(
+            r#"
+printf %s\\n "${{directories[@]}}" | {row_executable}{project} exec --no-progress --skip-setup --cluster {cluster_name} -a {action_name} - || {{ >&2 echo "[row] Error executing command."; exit 1; }}
+"#
+        ))
+    }
+
+    /// Execute up to `concurrent` directories at a time in the background.
+    fn concurrent_execution(&self, concurrent: usize) -> Result<String, Error> {
+        if !self.action.command_contains("{directory}") {
+            return Err(Error::InvalidConcurrentDirectories(
+                self.action.name().into(),
+                "the command must include {directory}".into(),
+            ));
+        }
+
+        let launcher_prefix = launcher::resolve_prefix(
+            self.launchers,
+            self.action,
+            self.cluster_name,
+            self.directories.len(),
+        )?
+        .prefix;
+
+        let command = self.chain(
+            |command| format!("{launcher_prefix}{command}"),
+            "{directory}",
+            "$directory",
+        );
+        let (log_setup, command) = self.log_redirect(command);
+        Ok(format!(
+            r#"
+running=0
+failed=0
+for directory in "${{directories[@]}}"
+do
+    if [ "$running" -ge {concurrent} ]
+    then
+        wait -n || failed=1
+        running=$((running - 1))
+    fi
+    {log_setup}{command} &
+    running=$((running + 1))
+done
+while [ "$running" -gt 0 ]
+do
+    wait -n || failed=1
+    running=$((running - 1))
+done
+test "$failed" -eq 0 || {{ >&2 echo "[ERROR row::action] Error executing command."; exit 2; }}
+"#
+        ))
+    }
+
     pub(crate) fn build(&self) -> Result<String, Error> {
-        Ok(self.header() + &self.variables()? + &self.setup()? + &self.execution()?)
+        let header = self.header();
+        let variables = self.variables()?;
+        let setup = self.setup();
+        let trap = self.trap()?;
+        let execution = self.execution()?;
+
+        let Some(template) = self.template else {
+            return Ok(header + &variables + &setup + &trap + &execution);
+        };
+
+        let directories = self
+            .directories
+            .iter()
+            .map(|d| {
+                d.to_str()
+                    .ok_or_else(|| Error::NonUTF8DirectoryName(d.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut environment = Environment::new();
+        environment.set_keep_trailing_newline(true);
+        environment.set_undefined_behavior(UndefinedBehavior::Strict);
+        environment
+            .template_from_str(&template.source)
+            .and_then(|t| {
+                t.render(context! {
+                    preamble => self.preamble,
+                    variables,
+                    setup,
+                    trap,
+                    execution,
+                    action => self.action.name(),
+                    cluster => self.cluster_name,
+                    directories,
+                    processes => self.total_processes,
+                    walltime_in_minutes => self.walltime_in_minutes,
+                })
+            })
+            .map_err(|e| Error::JobTemplate(template.path.clone(), e))
     }
 }
 
@@ -224,20 +497,79 @@ done
 pub struct Bash {
     cluster: Cluster,
     launchers: HashMap<String, Launcher>,
+    template: Option<JobTemplate>,
+    workspace: PathBuf,
+    root: Option<PathBuf>,
+    cache_directory: Option<PathBuf>,
 }
 
 impl Bash {
     /// Construct a new Bash scheduler.
     pub fn new(cluster: Cluster, launchers: HashMap<String, Launcher>) -> Self {
-        Self { cluster, launchers }
+        Self {
+            cluster,
+            launchers,
+            template: None,
+            workspace: PathBuf::from("workspace"),
+            root: None,
+            cache_directory: None,
+        }
+    }
+
+    /// Render job scripts with the given template.
+    #[must_use]
+    pub fn with_template(mut self, template: Option<JobTemplate>) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Set the workspace path (relative to the project root).
+    #[must_use]
+    pub fn with_workspace(mut self, workspace: PathBuf) -> Self {
+        self.workspace = workspace;
+        self
+    }
+
+    /// Set the project root that job scripts pass to `row` with `--project`.
+    #[must_use]
+    pub fn with_root(mut self, root: PathBuf) -> Self {
+        self.root = Some(root);
+        self
+    }
+
+    /// Set the cache directory that job scripts export in `ROW_CACHE_DIR`.
+    #[must_use]
+    pub fn with_cache_directory(mut self, cache_directory: Option<PathBuf>) -> Self {
+        self.cache_directory = cache_directory;
+        self
     }
 }
 
 pub struct ActiveBashJobs {}
 
 impl Scheduler for Bash {
-    fn make_script(&self, action: &Action, directories: &[PathBuf]) -> Result<String, Error> {
-        BashScriptBuilder::new(&self.cluster.name, action, directories, &self.launchers).build()
+    fn make_script(
+        &self,
+        action: &Action,
+        directories: &[PathBuf],
+        _job_name: &str,
+        group: GroupPosition,
+    ) -> Result<String, Error> {
+        let preamble = self
+            .cluster
+            .bash
+            .as_ref()
+            .and_then(|b| b.preamble.as_deref())
+            .unwrap_or_default();
+
+        BashScriptBuilder::new(&self.cluster.name, action, directories, &self.launchers)
+            .with_preamble(preamble)
+            .with_template(self.template.as_ref())
+            .with_workspace(&self.workspace)
+            .with_root(self.root.as_deref())
+            .with_cache_directory(self.cache_directory.as_deref())
+            .with_group(group)
+            .build()
     }
 
     fn submit(
@@ -245,10 +577,12 @@ impl Scheduler for Bash {
         working_directory: &Path,
         action: &Action,
         directories: &[PathBuf],
+        job_name: &str,
+        group: GroupPosition,
         should_terminate: Arc<AtomicBool>,
     ) -> Result<Option<u32>, Error> {
         debug!("Executing '{}' in bash.", action.name());
-        let script = self.make_script(action, directories)?;
+        let script = self.make_script(action, directories, job_name, group)?;
 
         let mut child = Command::new("bash")
             .stdin(Stdio::piped())
@@ -315,25 +649,29 @@ mod tests {
     use speedate::Duration;
 
     use crate::builtin::BuiltIn;
-    use crate::cluster::{IdentificationMethod, SchedulerType};
+    use crate::cluster::{BashOptions, IdentificationMethod, SchedulerType};
     use crate::launcher;
     use crate::workflow::Walltime;
-    use crate::workflow::{Resources, SubmitOptions};
+    use crate::workflow::{ActionCommand, Launchers, Resources, SubmitOptions};
 
     fn setup() -> (Action, Vec<PathBuf>, HashMap<String, Launcher>) {
         let resources = Resources {
+            preset: None,
             processes: Some(Processes::PerDirectory(2)),
             threads_per_process: Some(4),
             gpus_per_process: Some(1),
             walltime: Some(Walltime::PerSubmission(
                 Duration::new(true, 0, 240, 0).expect("Valid duration."),
             )),
+            component: Vec::new(),
+            binding: None,
+            concurrent_directories: None,
         };
 
         let action = Action {
             name: Some("action".to_string()),
-            command: Some("command {directory}".to_string()),
-            launchers: Some(vec!["mpi".into()]),
+            command: Some(ActionCommand::Single("command {directory}".to_string())),
+            launchers: Some(Launchers::All(vec!["mpi".into()])),
             resources,
             ..Action::default()
         };
@@ -368,6 +706,44 @@ mod tests {
         assert!(script.contains("#preamble\n"));
     }
 
+    #[test]
+    #[parallel]
+    fn root() {
+        let (action, directories, launchers) = setup();
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .build()
+            .expect("Valid script.");
+        assert!(!script.contains("--project"));
+
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .with_root(Some(Path::new("/path/to/project")))
+            .build()
+            .expect("Valid script.");
+        println!("{script}");
+
+        assert!(script.contains(
+            r#" --project "/path/to/project" --workspace "workspace" scan --no-progress"#
+        ));
+    }
+
+    #[test]
+    #[parallel]
+    fn cache_directory() {
+        let (action, directories, launchers) = setup();
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .build()
+            .expect("Valid script.");
+        assert!(!script.contains("ROW_CACHE_DIR"));
+
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .with_cache_directory(Some(Path::new("/scratch/cache")))
+            .build()
+            .expect("Valid script.");
+        println!("{script}");
+
+        assert!(script.contains("export ROW_CACHE_DIR=\"/scratch/cache\"\n"));
+    }
+
     #[test]
     #[parallel]
     fn no_setup() {
@@ -403,6 +779,67 @@ mod tests {
         assert!(script.contains("test $? -eq 0 ||"));
     }
 
+    #[test]
+    #[parallel]
+    fn template() {
+        let (mut action, directories, launchers) = setup();
+        action.submit_options.insert(
+            "cluster".to_string(),
+            SubmitOptions {
+                setup: Some("my setup".into()),
+                ..SubmitOptions::default()
+            },
+        );
+
+        let default_script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .with_preamble("#preamble")
+            .build()
+            .expect("Valid script.");
+
+        let template = JobTemplate {
+            path: PathBuf::from("job.sh"),
+            source:
+                "#!/bin/bash\n{{ preamble }}\n{{ variables }}{{ setup }}{{ trap }}{{ execution }}"
+                    .into(),
+        };
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .with_preamble("#preamble")
+            .with_template(Some(&template))
+            .build()
+            .expect("Valid script.");
+        assert_eq!(script, default_script);
+
+        let template = JobTemplate {
+            path: PathBuf::from("job.sh"),
+            source: "#!/bin/bash\nmodule load site\n{{ execution }}\n\
+                     echo {{ action }} {{ cluster }} {{ processes }} {{ walltime_in_minutes }}\n\
+                     {% for d in directories %}{{ d }},{% endfor %}\n"
+                .into(),
+        };
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .with_template(Some(&template))
+            .build()
+            .expect("Valid script.");
+        println!("{script}");
+        assert!(script.starts_with("#!/bin/bash\nmodule load site\n"));
+        assert!(script.contains("command $directory"));
+        assert!(script.contains("echo action cluster 6 4\n"));
+        assert!(script.ends_with("a,b,c,\n"));
+        assert!(!script.contains("my setup"));
+
+        let template = JobTemplate {
+            path: PathBuf::from("job.sh"),
+            source: "{{ not_a_variable }}".into(),
+        };
+        let result = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .with_template(Some(&template))
+            .build();
+        assert!(
+            matches!(result, Err(Error::JobTemplate(..))),
+            "Expected a job template error, got {result:?}"
+        );
+    }
+
     #[test]
     #[parallel]
     fn execution_directory() {
@@ -415,11 +852,97 @@ mod tests {
         assert!(script.contains("command $directory"));
     }
 
+    #[test]
+    #[parallel]
+    fn execution_steps() {
+        let (mut action, directories, launchers) = setup();
+        action.launchers = Some(Launchers::All(vec!["openmp".into()]));
+        action.resources.processes = None;
+        action.resources.gpus_per_process = None;
+        action.command = Some(ActionCommand::Steps(vec![
+            "prep {directory}".to_string(),
+            "run \"{directory}\"".to_string(),
+        ]));
+
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .build()
+            .expect("Valid script.");
+        println!("{script}");
+        assert!(script.contains(
+            "{ OMP_NUM_THREADS=4 prep $directory; } && { OMP_NUM_THREADS=4 run \"$directory\"; } || {"
+        ));
+
+        action.command = Some(ActionCommand::Steps(vec![
+            "prep {directories}".to_string(),
+            "run {directories}".to_string(),
+        ]));
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .build()
+            .expect("Valid script.");
+        println!("{script}");
+        assert!(script.contains(
+            "{ OMP_NUM_THREADS=4 prep \"${directories[@]}\"; } && { OMP_NUM_THREADS=4 run \"${directories[@]}\"; } || {"
+        ));
+
+        action.launchers = None;
+        action.command = Some(ActionCommand::Steps(vec![
+            "prep {directory} || true".to_string(),
+            "cd {directory}; run".to_string(),
+        ]));
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .build()
+            .expect("Valid script.");
+        println!("{script}");
+        assert!(script.contains("{ prep $directory || true; } && { cd $directory; run; } || {"));
+
+        action.command = Some(ActionCommand::Steps(vec![
+            "prep {directory}".to_string(),
+            "run {directories}".to_string(),
+        ]));
+        let result = BashScriptBuilder::new("cluster", &action, &directories, &launchers).build();
+        assert!(matches!(
+            result,
+            Err(Error::ActionContainsMultipleTemplates(_))
+        ));
+    }
+
+    #[test]
+    #[parallel]
+    fn execution_launcher_placeholder() {
+        let (mut action, directories, launchers) = setup();
+        action.launchers = Some(Launchers::All(vec!["openmp".into()]));
+        action.resources.processes = None;
+        action.resources.gpus_per_process = None;
+        action.command = Some(ActionCommand::Steps(vec![
+            "prep {directory}".to_string(),
+            "cd $directory && {launcher} run {directory}".to_string(),
+            "post {directory}".to_string(),
+        ]));
+
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .build()
+            .expect("Valid script.");
+        println!("{script}");
+        assert!(script.contains(
+            "{ prep $directory; } && { cd $directory && OMP_NUM_THREADS=4 run $directory; } && { post $directory; } || {"
+        ));
+
+        action.resources.processes = Some(Processes::PerDirectory(1));
+        action.resources.concurrent_directories = Some(2);
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .build()
+            .expect("Valid script.");
+        println!("{script}");
+        assert!(script.contains(
+            "{ prep $directory; } && { cd $directory && OMP_NUM_THREADS=4 run $directory; } && { post $directory; } &"
+        ));
+    }
+
     #[test]
     #[parallel]
     fn execution_directories() {
         let (mut action, directories, launchers) = setup();
-        action.command = Some("command {directories}".to_string());
+        action.command = Some(ActionCommand::Single("command {directories}".to_string()));
 
         let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
             .build()
@@ -434,8 +957,8 @@ mod tests {
     fn execution_openmp() {
         let (mut action, directories, launchers) = setup();
         action.resources.processes = Some(Processes::PerSubmission(1));
-        action.launchers = Some(vec!["openmp".into()]);
-        action.command = Some("command {directories}".to_string());
+        action.launchers = Some(Launchers::All(vec!["openmp".into()]));
+        action.command = Some(ActionCommand::Single("command {directories}".to_string()));
 
         let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
             .build()
@@ -449,8 +972,8 @@ mod tests {
     #[parallel]
     fn execution_mpi() {
         let (mut action, directories, launchers) = setup();
-        action.launchers = Some(vec!["mpi".into()]);
-        action.command = Some("command {directories}".to_string());
+        action.launchers = Some(Launchers::All(vec!["mpi".into()]));
+        action.command = Some(ActionCommand::Single("command {directories}".to_string()));
 
         let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
             .build()
@@ -462,11 +985,144 @@ mod tests {
         ));
     }
 
+    #[test]
+    #[parallel]
+    fn execution_launchers_by_cluster() {
+        let (mut action, directories, launchers) = setup();
+        action.resources.processes = Some(Processes::PerSubmission(1));
+        action.launchers = Some(Launchers::ByCluster(HashMap::from([
+            ("default".into(), vec!["mpi".into()]),
+            ("cluster".into(), vec!["openmp".into()]),
+        ])));
+        action.command = Some(ActionCommand::Single("command {directories}".to_string()));
+
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .build()
+            .expect("Valid script.");
+        println!("{script}");
+        assert!(script.contains("OMP_NUM_THREADS=4 command \"${directories[@]}\""));
+
+        let script = BashScriptBuilder::new("other", &action, &directories, &launchers)
+            .build()
+            .expect("Valid script.");
+        println!("{script}");
+        assert!(script.contains(
+            "srun --ntasks=1 --cpus-per-task=4 --tres-per-task=gres/gpu:1 command \"${directories[@]}\""
+        ));
+    }
+
+    #[test]
+    #[parallel]
+    fn execution_components() {
+        let (mut action, directories, launchers) = setup();
+        action.launchers = Some(Launchers::All(vec!["openmp".into(), "mpi".into()]));
+        action.resources.processes = None;
+        action.resources.threads_per_process = None;
+        action.resources.gpus_per_process = None;
+        action.resources.component = vec![
+            Resources {
+                processes: Some(Processes::PerSubmission(1)),
+                gpus_per_process: Some(1),
+                ..Resources::default()
+            },
+            Resources {
+                processes: Some(Processes::PerSubmission(16)),
+                threads_per_process: Some(2),
+                ..Resources::default()
+            },
+        ];
+
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .build()
+            .expect("Valid script.");
+        println!("{script}");
+        assert!(script.contains("export ACTION_PROCESSES=\"17\""));
+        assert!(script.contains(
+            "srun --ntasks=1 --tres-per-task=gres/gpu:1 command $directory : --ntasks=16 --cpus-per-task=2 command $directory"
+        ));
+    }
+
+    #[test]
+    #[parallel]
+    fn execution_concurrent() {
+        let (mut action, directories, launchers) = setup();
+        action.resources.processes = Some(Processes::PerDirectory(1));
+        action.resources.gpus_per_process = None;
+        action.resources.concurrent_directories = Some(2);
+
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .build()
+            .expect("Valid script.");
+        println!("{script}");
+        assert!(script.contains("export ACTION_PROCESSES=\"2\""));
+        assert!(script.contains(r#"if [ "$running" -ge 2 ]"#));
+        assert!(script.contains("srun --exact --ntasks=1 --cpus-per-task=4 command $directory &"));
+
+        action.launchers = Some(Launchers::All(vec!["openmp".into()]));
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .build()
+            .expect("Valid script.");
+        println!("{script}");
+        assert!(script.contains("OMP_NUM_THREADS=4 command $directory &"));
+
+        action.resources.processes = Some(Processes::PerDirectory(2));
+        let result = BashScriptBuilder::new("cluster", &action, &directories, &launchers).build();
+        assert!(matches!(result, Err(Error::NoProcessLauncher(_, 2))));
+    }
+
+    #[test]
+    #[parallel]
+    fn execution_aggregate() {
+        let (mut action, directories, launchers) = setup();
+        action.aggregate = true;
+
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .build()
+            .expect("Valid script.");
+        println!("{script}");
+        assert!(script.contains("export ACTION_PROCESSES=\"6\""));
+        assert!(
+            script.contains(" exec --no-progress --skip-setup --cluster cluster -a action - ||")
+        );
+        assert!(!script.contains("srun"));
+        assert!(!script.contains("command $directory"));
+    }
+
+    #[test]
+    #[parallel]
+    fn execution_log_output() {
+        let (mut action, directories, launchers) = setup();
+        action.log_output = Some(true);
+
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .with_workspace(Path::new("ws"))
+            .build()
+            .expect("Valid script.");
+        println!("{script}");
+        assert!(script.contains(r#"mkdir -p "ws/$directory/.row_logs""#));
+        assert!(script.contains(
+            r#"command $directory; } > "ws/$directory/.row_logs/action-$(date -u +%Y%m%dT%H%M%SZ).log" 2>&1 ||"#
+        ));
+
+        action.resources.processes = Some(Processes::PerDirectory(1));
+        action.resources.gpus_per_process = None;
+        action.resources.concurrent_directories = Some(2);
+        let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .with_workspace(Path::new("ws"))
+            .build()
+            .expect("Valid script.");
+        println!("{script}");
+        assert!(script.contains(r#"mkdir -p "ws/$directory/.row_logs""#));
+        assert!(script.contains("2>&1 &"));
+    }
+
     #[test]
     #[parallel]
     fn command_errors() {
         let (mut action, directories, launchers) = setup();
-        action.command = Some("command {directory} {directories}".to_string());
+        action.command = Some(ActionCommand::Single(
+            "command {directory} {directories}".to_string(),
+        ));
 
         let result = BashScriptBuilder::new("cluster", &action, &directories, &launchers).build();
 
@@ -475,7 +1131,7 @@ mod tests {
             Err(Error::ActionContainsMultipleTemplates { .. })
         ));
 
-        action.command = Some("command".to_string());
+        action.command = Some(ActionCommand::Single("command".to_string()));
 
         let result = BashScriptBuilder::new("cluster", &action, &directories, &launchers).build();
 
@@ -502,6 +1158,9 @@ mod tests {
         assert!(script.contains("export ACTION_PROCESSES_PER_DIRECTORY=\"2\"\n"));
         assert!(script.contains("export ACTION_THREADS_PER_PROCESS=\"4\"\n"));
         assert!(script.contains("export ACTION_GPUS_PER_PROCESS=\"1\"\n"));
+        assert!(script.contains("export ACTION_DIRECTORY_COUNT=\"3\"\n"));
+        assert!(script.contains("export ACTION_GROUP_INDEX=\"0\"\n"));
+        assert!(script.contains("export ACTION_GROUP_COUNT=\"1\"\n"));
     }
 
     #[test]
@@ -516,6 +1175,7 @@ mod tests {
         action.resources.gpus_per_process = None;
 
         let script = BashScriptBuilder::new("cluster", &action, &directories, &launchers)
+            .with_group(GroupPosition { index: 2, count: 5 })
             .build()
             .expect("Valid script.");
 
@@ -524,6 +1184,8 @@ mod tests {
         assert!(script.contains("export ACTION_CLUSTER=\"cluster\"\n"));
         assert!(script.contains("export ACTION_NAME=\"action\"\n"));
         assert!(script.contains("export ACTION_PROCESSES=\"10\"\n"));
+        assert!(script.contains("export ACTION_GROUP_INDEX=\"2\"\n"));
+        assert!(script.contains("export ACTION_GROUP_COUNT=\"5\"\n"));
         assert!(script.contains("export ACTION_WALLTIME_IN_MINUTES=\"3\"\n"));
         assert!(!script.contains("export ACTION_PROCESSES_PER_DIRECTORY"));
         assert!(!script.contains("export ACTION_THREADS_PER_PROCESS"));
@@ -540,21 +1202,57 @@ mod tests {
             identify: IdentificationMethod::Always(false),
             partition: Vec::new(),
             submit_options: Vec::new(),
+            mpi_launcher: None,
+            qos: Vec::new(),
+            bash: None,
+            job_url_template: None,
+            kubernetes: None,
+            max_submissions_per_minute: None,
+            auto_select_strategy: None,
         };
         let script = Bash::new(cluster, launchers)
-            .make_script(&action, &directories)
+            .make_script(&action, &directories, "job", GroupPosition::default())
             .expect("Valid script");
         println!("{script}");
 
         assert!(script.contains("command $directory"));
     }
 
+    #[test]
+    #[parallel]
+    fn scheduler_preamble() {
+        let (action, directories, launchers) = setup();
+        let cluster = Cluster {
+            name: "cluster".into(),
+            scheduler: SchedulerType::Bash,
+            identify: IdentificationMethod::Always(false),
+            partition: Vec::new(),
+            submit_options: Vec::new(),
+            mpi_launcher: None,
+            qos: Vec::new(),
+            bash: Some(BashOptions {
+                preamble: Some("set -euo pipefail".into()),
+            }),
+            job_url_template: None,
+            kubernetes: None,
+            max_submissions_per_minute: None,
+            auto_select_strategy: None,
+        };
+        let script = Bash::new(cluster, launchers)
+            .make_script(&action, &directories, "job", GroupPosition::default())
+            .expect("Valid script");
+        println!("{script}");
+
+        assert!(script.starts_with("#!/bin/bash\nset -euo pipefail\n"));
+        assert!(!script.contains("#SBATCH"));
+    }
+
     #[test]
     #[parallel]
     fn launcher_required() {
         let (mut action, directories, launchers) = setup();
-        action.launchers = Some(vec![]);
-        action.command = Some("command {directories}".to_string());
+        action.launchers = Some(Launchers::All(vec![]));
+        action.command = Some(ActionCommand::Single("command {directories}".to_string()));
 
         let result = BashScriptBuilder::new("cluster", &action, &directories, &launchers).build();
 
@@ -566,8 +1264,8 @@ mod tests {
     fn too_many_launchers() {
         let (mut action, directories, launchers) = setup();
         action.resources.processes = Some(Processes::PerSubmission(1));
-        action.launchers = Some(vec!["mpi".into(), "mpi".into()]);
-        action.command = Some("command {directories}".to_string());
+        action.launchers = Some(Launchers::All(vec!["mpi".into(), "mpi".into()]));
+        action.command = Some(ActionCommand::Single("command {directories}".to_string()));
 
         let result = BashScriptBuilder::new("cluster", &action, &directories, &launchers).build();
 
@@ -0,0 +1,559 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use log::{debug, error, trace};
+use serde_json::{json, Map, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{self, Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+use std::{str, thread};
+
+use crate::cluster::{Cluster, KubernetesOptions};
+use crate::launcher::Launcher;
+use crate::scheduler::bash::BashScriptBuilder;
+use crate::scheduler::{ActiveJobs, GroupPosition, JobTemplate, Scheduler};
+use crate::workflow::Action;
+use crate::Error;
+
+/// The label that identifies jobs submitted by row.
+const MANAGED_BY_LABEL: &str = "app.kubernetes.io/managed-by";
+
+/// The label that stores the row job ID.
+const JOB_ID_LABEL: &str = "row-job-id";
+
+/// The `Kubernetes` scheduler renders each group as a Job manifest and applies it with `kubectl`.
+///
+/// Kubernetes names jobs with strings. `Kubernetes` assigns each job a
+/// numeric ID, names the job `row-{id}`, and stores the ID in the
+/// `row-job-id` label.
+///
+pub struct Kubernetes {
+    cluster: Cluster,
+    launchers: HashMap<String, Launcher>,
+    template: Option<JobTemplate>,
+    workspace: PathBuf,
+    root: PathBuf,
+}
+
+impl Kubernetes {
+    /// Construct a new Kubernetes scheduler.
+    pub fn new(cluster: Cluster, launchers: HashMap<String, Launcher>) -> Self {
+        Self {
+            cluster,
+            launchers,
+            template: None,
+            workspace: PathBuf::from("workspace"),
+            root: PathBuf::from("."),
+        }
+    }
+
+    /// Render job scripts with the given template.
+    #[must_use]
+    pub fn with_template(mut self, template: Option<JobTemplate>) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Set the workspace path (relative to the project root).
+    #[must_use]
+    pub fn with_workspace(mut self, workspace: PathBuf) -> Self {
+        self.workspace = workspace;
+        self
+    }
+
+    /// Set the project root, which is the working directory of the job's container.
+    #[must_use]
+    pub fn with_root(mut self, root: PathBuf) -> Self {
+        self.root = root;
+        self
+    }
+
+    /// Get the cluster's Kubernetes options.
+    fn options(&self) -> Result<&KubernetesOptions, Error> {
+        self.cluster
+            .kubernetes
+            .as_ref()
+            .filter(|k| !k.image.is_empty())
+            .ok_or_else(|| Error::KubernetesImageMissing(self.cluster.name.clone()))
+    }
+
+    /// Build the kubectl command with the cluster's namespace.
+    fn kubectl(&self) -> Command {
+        let mut command = Command::new("kubectl");
+        if let Some(namespace) = self
+            .cluster
+            .kubernetes
+            .as_ref()
+            .and_then(|k| k.namespace.as_ref())
+        {
+            command.arg("--namespace").arg(namespace);
+        }
+        command
+    }
+
+    /// Render the Job manifest for a group of directories.
+    ///
+    /// # Arguments
+    /// * `job_id`: The job's ID. Use `None` to preview the manifest, which lets
+    ///   Kubernetes generate the job's name.
+    ///
+    fn manifest(
+        &self,
+        action: &Action,
+        directories: &[PathBuf],
+        job_name: &str,
+        group: GroupPosition,
+        job_id: Option<u32>,
+    ) -> Result<Value, Error> {
+        let options = self.options()?;
+        let script =
+            BashScriptBuilder::new(&self.cluster.name, action, directories, &self.launchers)
+                .with_preamble("")
+                .with_template(self.template.as_ref())
+                .with_workspace(&self.workspace)
+                .with_row_executable(Some(options.row_executable.as_deref().unwrap_or("row")))
+                .with_group(group)
+                .build()?;
+
+        let mut labels = Map::new();
+        labels.insert(MANAGED_BY_LABEL.into(), "row".into());
+        let mut metadata = Map::new();
+        match job_id {
+            Some(job_id) => {
+                labels.insert(JOB_ID_LABEL.into(), job_id.to_string().into());
+                metadata.insert("name".into(), format!("row-{job_id}").into());
+            }
+            None => {
+                metadata.insert("generateName".into(), "row-".into());
+            }
+        }
+        if let Some(namespace) = &options.namespace {
+            metadata.insert("namespace".into(), namespace.clone().into());
+        }
+        metadata.insert("labels".into(), Value::Object(labels.clone()));
+        metadata.insert(
+            "annotations".into(),
+            json!({"row/action": action.name(), "row/job-name": job_name}),
+        );
+
+        let n_directories = directories.len();
+        let resources = &action.resources;
+        let mut requests = Map::new();
+        requests.insert(
+            "cpu".into(),
+            resources.total_cpus(n_directories).to_string().into(),
+        );
+        let mut limits = Map::new();
+        let gpus = resources.total_gpus(n_directories);
+        if gpus > 0 {
+            // Kubernetes requires GPU requests to equal the limits.
+            requests.insert("nvidia.com/gpu".into(), gpus.to_string().into());
+            limits.insert("nvidia.com/gpu".into(), gpus.to_string().into());
+        }
+
+        let mut container = json!({
+            "name": "row",
+            "image": options.image,
+            "command": ["/bin/bash", "-c", script],
+            "workingDir": self.root,
+            "resources": {"requests": requests, "limits": limits},
+        });
+        let mut pod_spec = json!({
+            "restartPolicy": "Never",
+        });
+        if let Some(claim) = &options.volume_claim {
+            container["volumeMounts"] = json!([{"name": "project", "mountPath": self.root}]);
+            pod_spec["volumes"] =
+                json!([{"name": "project", "persistentVolumeClaim": {"claimName": claim}}]);
+        }
+        pod_spec["containers"] = json!([container]);
+
+        let deadline = resources
+            .total_walltime(n_directories)
+            .signed_total_seconds();
+
+        Ok(json!({
+            "apiVersion": "batch/v1",
+            "kind": "Job",
+            "metadata": metadata,
+            "spec": {
+                "backoffLimit": 0,
+                "activeDeadlineSeconds": deadline,
+                "template": {
+                    "metadata": {"labels": labels},
+                    "spec": pod_spec,
+                },
+            },
+        }))
+    }
+}
+
+/// Choose an ID for a new job.
+///
+/// The ID is a hash of the current time, the process ID, and the group.
+///
+fn new_job_id(action: &Action, directories: &[PathBuf]) -> u32 {
+    let mut hasher = DefaultHasher::new();
+    SystemTime::now().hash(&mut hasher);
+    process::id().hash(&mut hasher);
+    action.name().hash(&mut hasher);
+    directories.hash(&mut hasher);
+
+    // Keep IDs positive and nonzero in signed 32-bit representations.
+    (hasher.finish() % u64::from(i32::MAX as u32 - 1)) as u32 + 1
+}
+
+/// Track the running kubectl process
+///
+/// Or `None` when no process was launched.
+pub struct ActiveKubernetesJobs {
+    kubectl: Option<Child>,
+    max_jobs: usize,
+}
+
+impl Scheduler for Kubernetes {
+    fn make_script(
+        &self,
+        action: &Action,
+        directories: &[PathBuf],
+        job_name: &str,
+        group: GroupPosition,
+    ) -> Result<String, Error> {
+        let manifest = self.manifest(action, directories, job_name, group, None)?;
+        Ok(serde_json::to_string_pretty(&manifest).expect("Valid JSON") + "\n")
+    }
+
+    fn submit(
+        &self,
+        working_directory: &Path,
+        action: &Action,
+        directories: &[PathBuf],
+        job_name: &str,
+        group: GroupPosition,
+        should_terminate: Arc<AtomicBool>,
+    ) -> Result<Option<u32>, Error> {
+        debug!("Submitting '{}' with kubectl.", action.name());
+
+        // See `Slurm::submit`.
+        if should_terminate.load(Ordering::Relaxed) {
+            error!("Interrupted! Cancelling further job submissions.");
+            return Err(Error::Interrupted);
+        }
+
+        let job_id = new_job_id(action, directories);
+        let manifest = self.manifest(action, directories, job_name, group, Some(job_id))?;
+        let manifest = serde_json::to_string(&manifest).expect("Valid JSON");
+
+        let mut child = self
+            .kubectl()
+            .args(["apply", "-f", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(working_directory)
+            .spawn()
+            .map_err(|e| Error::SpawnProcess("kubectl".into(), e))?;
+
+        let mut stdin = child.stdin.take().expect("Piped stdin");
+        let input_thread = thread::spawn(move || {
+            let _ = write!(stdin, "{manifest}");
+        });
+
+        trace!("Waiting for kubectl to complete.");
+        let output = child
+            .wait_with_output()
+            .map_err(|e| Error::SpawnProcess("kubectl".into(), e))?;
+
+        input_thread.join().expect("The thread should not panic");
+
+        if output.status.success() {
+            Ok(Some(job_id))
+        } else {
+            let message = match output.status.code() {
+                None => match output.status.signal() {
+                    None => "kubectl was terminated by a unknown signal".to_string(),
+                    Some(signal) => format!("kubectl was terminated by signal {signal}"),
+                },
+                Some(code) => format!(
+                    "kubectl exited with code {code}: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            };
+            Err(Error::SubmitAction(action.name().into(), message))
+        }
+    }
+
+    /// Use `kubectl get jobs` to determine the jobs that have not finished.
+    ///
+    /// Select the jobs by the `row-job-id` label and print the conditions of
+    /// each. Jobs with the `Complete` or `Failed` condition are no longer active.
+    ///
+    fn active_jobs(&self, jobs: &[u32]) -> Result<Box<dyn ActiveJobs>, Error> {
+        if jobs.is_empty() {
+            return Ok(Box::new(ActiveKubernetesJobs {
+                kubectl: None,
+                max_jobs: 0,
+            }));
+        }
+
+        debug!("Checking job status with kubectl.");
+
+        let mut selector = format!("{MANAGED_BY_LABEL}=row,{JOB_ID_LABEL} in (");
+        for (i, job) in jobs.iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            let _ = write!(selector, "{separator}{job}");
+        }
+        selector.push(')');
+
+        let kubectl = self
+            .kubectl()
+            .args(["get", "jobs", "--no-headers", "--selector"])
+            .arg(&selector)
+            .arg(format!(
+                "--output=custom-columns=ID:.metadata.labels.{JOB_ID_LABEL},\
+                 CONDITIONS:.status.conditions[*].type"
+            ))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| Error::SpawnProcess("kubectl".into(), e))?;
+
+        Ok(Box::new(ActiveKubernetesJobs {
+            kubectl: Some(kubectl),
+            max_jobs: jobs.len(),
+        }))
+    }
+}
+
+/// Parse the output of `kubectl get jobs` into the set of active jobs.
+fn parse_active_jobs(output: &str, max_jobs: usize) -> Result<HashSet<u32>, Error> {
+    let mut result = HashSet::with_capacity(max_jobs);
+
+    for line in output.lines() {
+        let mut columns = line.split_whitespace();
+        let Some(id) = columns.next() else {
+            continue;
+        };
+        let id = id
+            .parse()
+            .map_err(|_| Error::UnexpectedOutput("kubectl".into(), line.into()))?;
+
+        let finished = columns
+            .flat_map(|c| c.split(','))
+            .any(|condition| condition == "Complete" || condition == "Failed");
+        if !finished {
+            result.insert(id);
+        }
+    }
+
+    Ok(result)
+}
+
+impl ActiveJobs for ActiveKubernetesJobs {
+    fn get(self: Box<Self>) -> Result<HashSet<u32>, Error> {
+        let Some(kubectl) = self.kubectl else {
+            return Ok(HashSet::new());
+        };
+
+        trace!("Waiting for kubectl to complete.");
+        let output = kubectl
+            .wait_with_output()
+            .map_err(|e| Error::SpawnProcess("kubectl".into(), e))?;
+
+        if !output.status.success() {
+            let message = match output.status.code() {
+                None => match output.status.signal() {
+                    None => "kubectl was terminated by a unknown signal".to_string(),
+                    Some(signal) => format!("kubectl was terminated by signal {signal}"),
+                },
+                Some(code) => format!("kubectl exited with code {code}"),
+            };
+            return Err(Error::ExecuteCommand(
+                "kubectl".into(),
+                message,
+                String::from_utf8_lossy(&output.stderr).into(),
+            ));
+        }
+
+        parse_active_jobs(&String::from_utf8_lossy(&output.stdout), self.max_jobs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::parallel;
+
+    use crate::builtin::BuiltIn;
+    use crate::cluster::{IdentificationMethod, SchedulerType};
+    use crate::launcher;
+    use crate::workflow::{ActionCommand, Processes, Resources};
+
+    fn setup() -> (Action, Vec<PathBuf>, Kubernetes) {
+        let action = Action {
+            name: Some("action".to_string()),
+            command: Some(ActionCommand::Single("command {directory}".to_string())),
+            ..Action::default()
+        };
+
+        let directories = vec![PathBuf::from("a"), PathBuf::from("b"), PathBuf::from("c")];
+        let launchers = launcher::Configuration::built_in();
+        let cluster = Cluster {
+            name: "cluster".into(),
+            identify: IdentificationMethod::Always(false),
+            scheduler: SchedulerType::Kubernetes,
+            partition: Vec::new(),
+            submit_options: Vec::new(),
+            mpi_launcher: None,
+            qos: Vec::new(),
+            bash: None,
+            job_url_template: None,
+            kubernetes: Some(KubernetesOptions {
+                image: "python:3.12".into(),
+                namespace: Some("science".into()),
+                volume_claim: None,
+                row_executable: None,
+            }),
+            max_submissions_per_minute: None,
+            auto_select_strategy: None,
+        };
+
+        let kubernetes = Kubernetes::new(cluster, launchers.by_cluster("cluster"))
+            .with_root(PathBuf::from("/project"));
+        (action, directories, kubernetes)
+    }
+
+    #[test]
+    #[parallel]
+    fn manifest() {
+        let (action, directories, kubernetes) = setup();
+        let manifest = kubernetes
+            .manifest(
+                &action,
+                &directories,
+                "action-a+2",
+                GroupPosition::default(),
+                Some(42),
+            )
+            .expect("valid manifest");
+        println!("{manifest:#}");
+
+        assert_eq!(manifest["kind"], "Job");
+        assert_eq!(manifest["metadata"]["name"], "row-42");
+        assert_eq!(manifest["metadata"]["namespace"], "science");
+        assert_eq!(manifest["metadata"]["labels"]["row-job-id"], "42");
+        assert_eq!(
+            manifest["metadata"]["annotations"]["row/job-name"],
+            "action-a+2"
+        );
+        assert_eq!(manifest["spec"]["backoffLimit"], 0);
+        assert_eq!(manifest["spec"]["activeDeadlineSeconds"], 3 * 3600);
+
+        let container = &manifest["spec"]["template"]["spec"]["containers"][0];
+        assert_eq!(container["image"], "python:3.12");
+        assert_eq!(container["workingDir"], "/project");
+        assert_eq!(container["resources"]["requests"]["cpu"], "1");
+        assert!(container["resources"]["limits"]
+            .get("nvidia.com/gpu")
+            .is_none());
+        assert!(container["command"][2]
+            .as_str()
+            .unwrap()
+            .contains("command $directory"));
+        assert!(container["command"][2]
+            .as_str()
+            .unwrap()
+            .contains("| row scan --no-progress"));
+        assert!(manifest["spec"]["template"]["spec"]
+            .get("volumes")
+            .is_none());
+    }
+
+    #[test]
+    #[parallel]
+    fn preview() {
+        let (action, directories, kubernetes) = setup();
+        let script = kubernetes
+            .make_script(&action, &directories, "action", GroupPosition::default())
+            .expect("valid script");
+        println!("{script}");
+
+        let manifest: Value = serde_json::from_str(&script).expect("valid JSON");
+        assert_eq!(manifest["metadata"]["generateName"], "row-");
+        assert!(manifest["metadata"]["labels"].get("row-job-id").is_none());
+    }
+
+    #[test]
+    #[parallel]
+    fn resources() {
+        let (mut action, directories, mut kubernetes) = setup();
+        action.resources = Resources {
+            processes: Some(Processes::PerSubmission(1)),
+            threads_per_process: Some(4),
+            gpus_per_process: Some(1),
+            ..Resources::default()
+        };
+        kubernetes.cluster.kubernetes.as_mut().unwrap().volume_claim = Some("data".into());
+
+        let manifest = kubernetes
+            .manifest(
+                &action,
+                &directories,
+                "action",
+                GroupPosition::default(),
+                Some(1),
+            )
+            .expect("valid manifest");
+        let pod = &manifest["spec"]["template"]["spec"];
+        let container = &pod["containers"][0];
+        assert_eq!(container["resources"]["requests"]["cpu"], "4");
+        assert_eq!(container["resources"]["requests"]["nvidia.com/gpu"], "1");
+        assert_eq!(container["resources"]["limits"]["nvidia.com/gpu"], "1");
+        assert_eq!(container["volumeMounts"][0]["mountPath"], "/project");
+        assert_eq!(
+            pod["volumes"][0]["persistentVolumeClaim"]["claimName"],
+            "data"
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn missing_image() {
+        let (action, directories, mut kubernetes) = setup();
+        kubernetes.cluster.kubernetes = None;
+
+        let result =
+            kubernetes.make_script(&action, &directories, "action", GroupPosition::default());
+        assert!(matches!(result, Err(Error::KubernetesImageMissing(_))));
+    }
+
+    #[test]
+    #[parallel]
+    fn job_ids() {
+        let (action, directories, _) = setup();
+        let id = new_job_id(&action, &directories);
+        assert!(id > 0);
+        assert!(id <= i32::MAX as u32);
+    }
+
+    #[test]
+    #[parallel]
+    fn active_jobs() {
+        let output = "1   <none>\n2   Complete\n3   SuccessCriteriaMet,Complete\n4   Failed\n\
+                      5   Suspended\n";
+        let active = parse_active_jobs(output, 5).expect("valid output");
+        assert_eq!(active, HashSet::from([1, 5]));
+
+        let result = parse_active_jobs("x <none>\n", 1);
+        assert!(matches!(result, Err(Error::UnexpectedOutput(_, _))));
+    }
+}
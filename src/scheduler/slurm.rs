@@ -1,34 +1,94 @@
 // Copyright (c) 2024 The Regents of the University of Michigan.
 // Part of row, released under the BSD 3-Clause License.
 
-use log::{debug, error, trace};
+use log::{debug, error, trace, warn};
 use std::collections::{HashMap, HashSet};
+use std::env;
 use std::fmt::Write as _;
-use std::io::Write;
+use std::fs;
+use std::io::{Read, Write};
 use std::os::unix::process::ExitStatusExt;
 use std::path::{Path, PathBuf};
-use std::process::{Child, Command, Stdio};
+use std::process::{Child, Command, Output, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 use std::{str, thread};
+use uuid::Uuid;
 
-use crate::cluster::Cluster;
+use crate::cluster::{AutoSelectStrategy, Cluster, Partition};
 use crate::launcher::Launcher;
 use crate::scheduler::bash::BashScriptBuilder;
-use crate::scheduler::{ActiveJobs, Scheduler};
-use crate::workflow::Action;
-use crate::Error;
+use crate::scheduler::{ActiveJobs, GroupPosition, JobTemplate, Scheduler};
+use crate::workflow::{Action, Binding, Resources};
+use crate::{Error, DEFAULT_QUEUE_TIMEOUT};
+
+/// Number of times to execute `squeue` before giving up.
+const QUEUE_ATTEMPTS: u32 = 3;
+
+/// Time to wait before the first retry. The delay doubles after each attempt.
+const QUEUE_RETRY_DELAY: Duration = Duration::from_millis(500);
 
 /// The `Slurm` scheduler constructs bash scripts and executes them with `sbatch`.
 pub struct Slurm {
     cluster: Cluster,
     launchers: HashMap<String, Launcher>,
+    template: Option<JobTemplate>,
+    workspace: PathBuf,
+    root: Option<PathBuf>,
+    cache_directory: Option<PathBuf>,
+    queue_timeout: Duration,
+    idle_cpus: OnceLock<Option<HashMap<String, usize>>>,
 }
 
 impl Slurm {
     /// Construct a new Slurm scheduler.
     pub fn new(cluster: Cluster, launchers: HashMap<String, Launcher>) -> Self {
-        Self { cluster, launchers }
+        Self {
+            cluster,
+            launchers,
+            template: None,
+            workspace: PathBuf::from("workspace"),
+            root: None,
+            cache_directory: None,
+            queue_timeout: Duration::from_secs(DEFAULT_QUEUE_TIMEOUT),
+            idle_cpus: OnceLock::new(),
+        }
+    }
+
+    /// Render job scripts with the given template.
+    #[must_use]
+    pub fn with_template(mut self, template: Option<JobTemplate>) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Set the workspace path (relative to the project root).
+    #[must_use]
+    pub fn with_workspace(mut self, workspace: PathBuf) -> Self {
+        self.workspace = workspace;
+        self
+    }
+
+    /// Set the project root that job scripts pass to `row` with `--project`.
+    #[must_use]
+    pub fn with_root(mut self, root: PathBuf) -> Self {
+        self.root = Some(root);
+        self
+    }
+
+    /// Set the cache directory that job scripts export in `ROW_CACHE_DIR`.
+    #[must_use]
+    pub fn with_cache_directory(mut self, cache_directory: Option<PathBuf>) -> Self {
+        self.cache_directory = cache_directory;
+        self
+    }
+
+    /// Set the time to wait for each `squeue` attempt.
+    #[must_use]
+    pub fn with_queue_timeout(mut self, queue_timeout: Duration) -> Self {
+        self.queue_timeout = queue_timeout;
+        self
     }
 }
 
@@ -37,39 +97,48 @@ impl Slurm {
 /// Or `None` when no process was launched.
 pub struct ActiveSlurmJobs {
     squeue: Option<Child>,
+    jobs: String,
     max_jobs: usize,
+    timeout: Duration,
 }
 
-impl Scheduler for Slurm {
-    fn make_script(&self, action: &Action, directories: &[PathBuf]) -> Result<String, Error> {
-        let mut preamble = String::with_capacity(512);
-        let mut user_partition = &None;
-
-        write!(preamble, "#SBATCH --job-name={}", action.name()).expect("valid format");
-        let _ = match directories.first() {
-            Some(directory) => match directories.len() {
-                0..=1 => writeln!(preamble, "-{}", directory.display()),
-                _ => writeln!(
-                    preamble,
-                    "-{}+{}",
-                    directory.display(),
-                    directories.len() - 1
-                ),
-            },
-            None => writeln!(preamble),
-        };
-
-        let _ = writeln!(preamble, "#SBATCH --output={}-%j.out", action.name());
-
-        if let Some(submit_options) = action.submit_options.get(&self.cluster.name) {
-            user_partition = &submit_options.partition;
+impl Slurm {
+    /// Get the idle CPUs in each partition when the cluster selects partitions by them.
+    ///
+    /// Query `sinfo` at most once. Return `None` (select partitions in order) when
+    /// the query fails.
+    ///
+    fn idle_cpus(&self) -> Option<&HashMap<String, usize>> {
+        if self.cluster.auto_select_strategy.unwrap_or_default() != AutoSelectStrategy::MostIdle {
+            return None;
         }
 
+        self.idle_cpus
+            .get_or_init(|| match idle_cpus() {
+                Ok(idle_cpus) => Some(idle_cpus),
+                Err(error) => {
+                    warn!("Selecting partitions in order: {error}");
+                    None
+                }
+            })
+            .as_ref()
+    }
+
+    /// Write the options that request the resources of one job component.
+    fn write_component(
+        &self,
+        preamble: &mut String,
+        action: &Action,
+        resources: &Resources,
+        user_partition: Option<&str>,
+        n_directories: usize,
+    ) -> Result<(), Error> {
         // The partition
-        let partition = self.cluster.find_partition(
-            user_partition.as_deref(),
-            &action.resources,
-            directories.len(),
+        let partition = self.cluster.select_partition(
+            user_partition,
+            resources,
+            n_directories,
+            self.idle_cpus(),
         )?;
         let _ = writeln!(preamble, "#SBATCH --partition={}", partition.name);
 
@@ -77,18 +146,20 @@ impl Scheduler for Slurm {
         let _ = writeln!(
             preamble,
             "#SBATCH --ntasks={}",
-            action.resources.total_processes(directories.len())
+            resources.total_processes(n_directories)
         );
 
-        if let Some(threads_per_process) = action.resources.threads_per_process {
+        if let Some(threads_per_process) = resources.threads_per_process {
             let _ = writeln!(preamble, "#SBATCH --cpus-per-task={threads_per_process}");
         }
-        if let Some(gpus_per_process) = action.resources.gpus_per_process {
+        if let Some(binding) = resources.binding {
+            Self::write_binding(preamble, action, resources, partition, binding);
+        }
+        if let Some(gpus_per_process) = resources.gpus_per_process {
             let _ = writeln!(preamble, "#SBATCH --gpus-per-task={gpus_per_process}");
 
             if let Some(ref gpus_per_node) = partition.gpus_per_node {
-                let n_nodes = (action.resources.total_gpus(directories.len()) + gpus_per_node - 1)
-                    / gpus_per_node;
+                let n_nodes = resources.total_gpus(n_directories).div_ceil(*gpus_per_node);
                 let _ = writeln!(preamble, "#SBATCH --nodes={n_nodes}");
             }
 
@@ -97,8 +168,7 @@ impl Scheduler for Slurm {
             }
         } else {
             if let Some(ref cpus_per_node) = partition.cpus_per_node {
-                let n_nodes = (action.resources.total_cpus(directories.len()) + cpus_per_node - 1)
-                    / cpus_per_node;
+                let n_nodes = resources.total_cpus(n_directories).div_ceil(*cpus_per_node);
                 let _ = writeln!(preamble, "#SBATCH --nodes={n_nodes}");
             }
 
@@ -110,17 +180,11 @@ impl Scheduler for Slurm {
         // Slurm doesn't store times in seconds, so round up to the nearest minute.
         let total = action
             .resources
-            .total_walltime(directories.len())
+            .total_walltime(n_directories)
             .signed_total_seconds();
         let minutes = (total + 59) / 60;
         let _ = writeln!(preamble, "#SBATCH --time={minutes}");
 
-        // Add global cluster submit options first so that users can override them.
-        for option in &self.cluster.submit_options {
-            let _ = writeln!(preamble, "#SBATCH {option}");
-        }
-
-        // Use provided submission options
         if let Some(submit_options) = action.submit_options.get(&self.cluster.name) {
             if let Some(ref account) = submit_options.account {
                 if let Some(ref suffix) = partition.account_suffix {
@@ -129,21 +193,161 @@ impl Scheduler for Slurm {
                     let _ = writeln!(preamble, "#SBATCH --account={account}");
                 }
             }
+            if let Some(ref qos) = submit_options.qos {
+                self.cluster.check_qos(qos)?;
+                let _ = writeln!(preamble, "#SBATCH --qos={qos}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the options that distribute processes over cores or sockets.
+    fn write_binding(
+        preamble: &mut String,
+        action: &Action,
+        resources: &Resources,
+        partition: &Partition,
+        binding: Binding,
+    ) {
+        let distribution = match binding {
+            Binding::Cores => "block:block",
+            Binding::Sockets => "block:cyclic",
+        };
+        let _ = writeln!(preamble, "#SBATCH --distribution={distribution}");
+
+        if let Some(sockets_per_node) = partition.sockets_per_node {
+            let _ = writeln!(preamble, "#SBATCH --sockets-per-node={sockets_per_node}");
+        }
+
+        let Some(cores_per_socket) = partition.cores_per_socket else {
+            return;
+        };
+        let _ = writeln!(preamble, "#SBATCH --cores-per-socket={cores_per_socket}");
+
+        // Processes that straddle sockets defeat the purpose of binding.
+        let threads_per_process = resources.threads_per_process.unwrap_or(1);
+        if binding == Binding::Cores && threads_per_process > cores_per_socket {
+            warn!(
+                "Action '{}' binds {threads_per_process} threads per process to cores, but \
+                 partition '{}' has only {cores_per_socket} cores per socket.",
+                action.name(),
+                partition.name
+            );
+        }
+        if binding == Binding::Sockets && threads_per_process % cores_per_socket != 0 {
+            warn!(
+                "Action '{}' binds processes to sockets, but threads_per_process={} is not a \
+                 multiple of the {cores_per_socket} cores per socket in partition '{}'.",
+                action.name(),
+                threads_per_process,
+                partition.name
+            );
+        }
+    }
+}
+
+impl Scheduler for Slurm {
+    fn make_script(
+        &self,
+        action: &Action,
+        directories: &[PathBuf],
+        job_name: &str,
+        group: GroupPosition,
+    ) -> Result<String, Error> {
+        let mut preamble = String::with_capacity(512);
+        let mut user_partition = &None;
+
+        let _ = writeln!(preamble, "#SBATCH --job-name={job_name}");
+
+        let _ = writeln!(preamble, "#SBATCH --output={}-%j.out", action.name());
+
+        if let Some(submit_options) = action.submit_options.get(&self.cluster.name) {
+            user_partition = &submit_options.partition;
+        }
+
+        // Heterogeneous jobs separate the options for each component with `hetjob`.
+        let components = if action.resources.component.is_empty() {
+            std::slice::from_ref(&action.resources)
+        } else {
+            &action.resources.component
+        };
+
+        self.write_component(
+            &mut preamble,
+            action,
+            &components[0],
+            user_partition.as_deref(),
+            directories.len(),
+        )?;
+
+        // Add global cluster submit options first so that users can override them.
+        for option in &self.cluster.submit_options {
+            let _ = writeln!(preamble, "#SBATCH {option}");
+        }
+
+        // Use provided submission options
+        if let Some(submit_options) = action.submit_options.get(&self.cluster.name) {
             for option in &submit_options.custom {
                 let _ = writeln!(preamble, "#SBATCH {option}");
             }
         }
 
+        for resources in &components[1..] {
+            let _ = writeln!(preamble, "#SBATCH hetjob");
+            self.write_component(
+                &mut preamble,
+                action,
+                resources,
+                user_partition.as_deref(),
+                directories.len(),
+            )?;
+        }
+
         BashScriptBuilder::new(&self.cluster.name, action, directories, &self.launchers)
             .with_preamble(&preamble)
+            .with_template(self.template.as_ref())
+            .with_workspace(&self.workspace)
+            .with_root(self.root.as_deref())
+            .with_cache_directory(self.cache_directory.as_deref())
+            .with_group(group)
             .build()
     }
 
+    fn partitions(&self, action: &Action, directories: &[PathBuf]) -> Result<Vec<String>, Error> {
+        let user_partition = action
+            .submit_options
+            .get(&self.cluster.name)
+            .and_then(|o| o.partition.as_deref());
+
+        let components = if action.resources.component.is_empty() {
+            std::slice::from_ref(&action.resources)
+        } else {
+            &action.resources.component
+        };
+
+        components
+            .iter()
+            .map(|resources| {
+                self.cluster
+                    .select_partition(
+                        user_partition,
+                        resources,
+                        directories.len(),
+                        self.idle_cpus(),
+                    )
+                    .map(|p| p.name.clone())
+            })
+            .collect()
+    }
+
     fn submit(
         &self,
         working_directory: &Path,
         action: &Action,
         directories: &[PathBuf],
+        job_name: &str,
+        group: GroupPosition,
         should_terminate: Arc<AtomicBool>,
     ) -> Result<Option<u32>, Error> {
         debug!("Submtitting '{}' with sbatch.", action.name());
@@ -158,19 +362,25 @@ impl Scheduler for Slurm {
             return Err(Error::Interrupted);
         }
 
-        let script = self.make_script(action, directories)?;
+        let script = self.make_script(action, directories, job_name, group)?;
+
+        if let Some(command) = &action.submit_command_override {
+            return submit_with_command(command, working_directory, action, &script);
+        }
 
         let mut child = Command::new("sbatch")
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
             .arg("--parsable")
             .current_dir(working_directory)
             .spawn()
             .map_err(|e| Error::SpawnProcess("sbatch".into(), e))?;
 
         let mut stdin = child.stdin.take().expect("Piped stdin");
+        let input = script.clone();
         let input_thread = thread::spawn(move || {
-            let _ = write!(stdin, "{script}");
+            let _ = write!(stdin, "{input}");
         });
 
         trace!("Waiting for sbatch to complete.");
@@ -180,7 +390,15 @@ impl Scheduler for Slurm {
 
         input_thread.join().expect("The thread should not panic");
 
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim_end();
+
         if output.status.success() {
+            // sbatch may warn about the job and still accept it.
+            for line in stderr.lines() {
+                warn!("{line}");
+            }
+
             let job_id_string = str::from_utf8(&output.stdout).expect("Valid UTF-8 output");
             let job_id = job_id_string
                 .trim_end_matches(char::is_whitespace)
@@ -195,7 +413,22 @@ impl Scheduler for Slurm {
                 },
                 Some(code) => format!("sbatch exited with code {code}"),
             };
-            Err(Error::SubmitAction(action.name().into(), message))
+
+            match diagnose_sbatch(stderr, &script, &self.cluster.name) {
+                Some(diagnosis) => Err(Error::SbatchRejected(
+                    action.name().into(),
+                    diagnosis,
+                    stderr.into(),
+                )),
+                None if stderr.is_empty() => {
+                    Err(Error::SubmitAction(action.name().into(), message))
+                }
+                None => Err(Error::SbatchRejected(
+                    action.name().into(),
+                    message,
+                    stderr.into(),
+                )),
+            }
         }
     }
 
@@ -208,7 +441,9 @@ impl Scheduler for Slurm {
         if jobs.is_empty() {
             return Ok(Box::new(ActiveSlurmJobs {
                 squeue: None,
+                jobs: String::new(),
                 max_jobs: 0,
+                timeout: self.queue_timeout,
             }));
         }
 
@@ -224,59 +459,382 @@ impl Scheduler for Slurm {
             let _ = write!(jobs_string, "{job},");
         }
 
-        let squeue = Command::new("squeue")
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .arg("--jobs")
-            .arg(&jobs_string)
-            .args(["-o", "%A"])
-            .arg("--noheader")
-            .spawn()
-            .map_err(|e| Error::SpawnProcess("squeue".into(), e))?;
-
         Ok(Box::new(ActiveSlurmJobs {
-            squeue: Some(squeue),
+            squeue: Some(spawn_squeue(&jobs_string)?),
+            jobs: jobs_string,
             max_jobs: jobs.len(),
+            timeout: self.queue_timeout,
         }))
     }
 }
 
 impl ActiveJobs for ActiveSlurmJobs {
+    /// Wait for squeue and parse its output.
+    ///
+    /// Controllers that are restarting may not respond, fail, or report partial
+    /// output. Execute `squeue` again (up to `QUEUE_ATTEMPTS` times) with an
+    /// increasing delay before reporting the error.
+    ///
     fn get(self: Box<Self>) -> Result<HashSet<u32>, Error> {
-        let mut result = HashSet::with_capacity(self.max_jobs);
+        let Some(mut squeue) = self.squeue else {
+            return Ok(HashSet::new());
+        };
 
-        if let Some(squeue) = self.squeue {
+        let mut delay = QUEUE_RETRY_DELAY;
+        let mut attempt = 1;
+        loop {
             trace!("Waiting for squeue to complete.");
-            let output = squeue
-                .wait_with_output()
-                .map_err(|e| Error::SpawnProcess("sbatch".into(), e))?;
-
-            if !output.status.success() {
-                let message = match output.status.code() {
-                    None => match output.status.signal() {
-                        None => "squeue was terminated by a unknown signal".to_string(),
-                        Some(signal) => format!("squeue was terminated by signal {signal}"),
-                    },
-                    Some(code) => format!("squeue exited with code {code}"),
-                };
-                return Err(Error::ExecuteSqueue(
-                    message,
-                    str::from_utf8(&output.stderr).expect("Valid UTF-8").into(),
-                ));
+            let result = wait_squeue(squeue, self.timeout)
+                .and_then(|output| parse_active_jobs(&output, self.max_jobs));
+
+            match result {
+                Ok(active) => return Ok(active),
+                Err(error) if attempt < QUEUE_ATTEMPTS => {
+                    debug!("{error}");
+                    warn!(
+                        "squeue failed (attempt {attempt} of {QUEUE_ATTEMPTS}). Retrying in {:.1} seconds.",
+                        delay.as_secs_f32()
+                    );
+                    thread::sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                    squeue = spawn_squeue(&self.jobs)?;
+                }
+                Err(error) => return Err(error),
             }
+        }
+    }
+}
+
+/// Submit a job script with the action's `submit_command_override`.
+///
+/// Write the script to a temporary file and execute `command` with `{script}`
+/// replaced by the file's path. The command must print the job ID as the last
+/// word of its output.
+///
+fn submit_with_command(
+    command: &str,
+    working_directory: &Path,
+    action: &Action,
+    script: &str,
+) -> Result<Option<u32>, Error> {
+    let path = env::temp_dir().join(format!("row-{}.sh", Uuid::new_v4()));
+    trace!("Writing the job script to '{}'.", path.display());
+    fs::write(&path, script).map_err(|e| Error::FileWrite(path.clone(), e))?;
+
+    debug!("Submitting with '{command}'.");
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command.replace("{script}", "\"$ACTION_SCRIPT\""))
+        .current_dir(working_directory)
+        .env("ACTION_SCRIPT", &path)
+        .stdin(Stdio::null())
+        .output();
+
+    if let Err(error) = fs::remove_file(&path) {
+        warn!("Unable to remove '{}': {error}.", path.display());
+    }
+    let output = output.map_err(|e| Error::SpawnProcess("sh".into(), e))?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stderr = stderr.trim_end();
+    if !output.status.success() {
+        let mut message = format!("'{command}' failed ({})", output.status);
+        if !stderr.is_empty() {
+            let _ = write!(message, ":\n{stderr}");
+        }
+        return Err(Error::SubmitAction(action.name().into(), message));
+    }
 
-            let jobs = str::from_utf8(&output.stdout).expect("Valid UTF-8");
-            for job in jobs.lines() {
-                result.insert(
-                    job.parse()
-                        .map_err(|_| Error::UnexpectedOutput("squeue".into(), job.into()))?,
-                );
+    for line in stderr.lines() {
+        warn!("{line}");
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let job_id = stdout
+        .split_whitespace()
+        .last()
+        .and_then(|word| word.parse::<u32>().ok())
+        .ok_or_else(|| Error::UnexpectedOutput(command.into(), stdout.as_ref().into()))?;
+    Ok(Some(job_id))
+}
+
+/// Find the value of an `#SBATCH` option in a job script.
+fn find_option<'a>(script: &'a str, option: &str) -> Option<&'a str> {
+    script.lines().find_map(|line| {
+        line.strip_prefix("#SBATCH --")
+            .and_then(|line| line.strip_prefix(option))
+            .and_then(|line| line.strip_prefix('='))
+    })
+}
+
+/// Explain common reasons that sbatch rejects a job.
+///
+/// Match the error messages that Slurm reports in `stderr` and describe the
+/// likely cause using the options in `script`.
+///
+/// # Returns
+/// `Some(String)` with the explanation or `None` when the error is not recognized.
+///
+fn diagnose_sbatch(stderr: &str, script: &str, cluster_name: &str) -> Option<String> {
+    let error = stderr.to_lowercase();
+    let partition = find_option(script, "partition").unwrap_or("<default>");
+
+    if error.contains("invalid account") {
+        Some(match find_option(script, "account") {
+            Some(account) => format!(
+                "account '{account}' is invalid on partition '{partition}' \
+                 (check `submit_options.{cluster_name}.account`)"
+            ),
+            None => format!(
+                "partition '{partition}' requires an account \
+                 (set `submit_options.{cluster_name}.account`)"
+            ),
+        })
+    } else if error.contains("invalid qos") {
+        Some(format!(
+            "QOS '{}' is invalid for this account or partition '{partition}' \
+             (check `submit_options.{cluster_name}.qos`)",
+            find_option(script, "qos").unwrap_or("<default>")
+        ))
+    } else if error.contains("maxsubmit")
+        || error.contains("submit limit")
+        || error.contains("accounting/qos policy")
+    {
+        Some(
+            "the job exceeds a QOS or account limit on the number or size of queued jobs \
+             (wait for queued jobs to finish or submit fewer with `row submit -n`)"
+                .into(),
+        )
+    } else if error.contains("time limit") {
+        Some(format!(
+            "the walltime {} minutes exceeds the limit of partition '{partition}' \
+             (reduce `resources.walltime`)",
+            find_option(script, "time").unwrap_or("<default>")
+        ))
+    } else if error.contains("invalid partition") {
+        Some(format!(
+            "partition '{partition}' does not exist on this cluster \
+             (check `clusters.toml` and `submit_options.{cluster_name}.partition`)"
+        ))
+    } else if error.contains("node configuration is not available") || error.contains("gres") {
+        Some(format!(
+            "no node in partition '{partition}' can provide the requested resources \
+             (check the processes, threads, GPUs, and memory in `resources`)"
+        ))
+    } else if error.contains("unable to contact slurm controller")
+        || error.contains("socket timed out")
+    {
+        Some("the Slurm controller is not responding (try again later)".into())
+    } else {
+        None
+    }
+}
+
+/// Launch `squeue` to list the given jobs.
+fn spawn_squeue(jobs: &str) -> Result<Child, Error> {
+    Command::new("squeue")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .arg("--jobs")
+        .arg(jobs)
+        .args(["-o", "%A"])
+        .arg("--noheader")
+        .spawn()
+        .map_err(|e| Error::SpawnProcess("squeue".into(), e))
+}
+
+/// Wait for `squeue` to finish and collect its output.
+///
+/// Kill `squeue` when it does not finish within `timeout`.
+///
+fn wait_squeue(mut squeue: Child, timeout: Duration) -> Result<Output, Error> {
+    // Read the pipes while waiting so that squeue never blocks on a full pipe.
+    let mut stdout = squeue.stdout.take().expect("Piped stdout");
+    let mut stderr = squeue.stderr.take().expect("Piped stderr");
+    let stdout_reader = thread::spawn(move || {
+        let mut buffer = Vec::new();
+        stdout.read_to_end(&mut buffer).map(|_| buffer)
+    });
+    let stderr_reader = thread::spawn(move || {
+        let mut buffer = Vec::new();
+        stderr.read_to_end(&mut buffer).map(|_| buffer)
+    });
+
+    let start = Instant::now();
+    let status = loop {
+        match squeue.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if start.elapsed() >= timeout => {
+                let _ = squeue.kill();
+                let _ = squeue.wait();
+                return Err(Error::QueueTimeout("squeue".into(), timeout.as_secs()));
             }
+            Ok(None) => thread::sleep(Duration::from_millis(10)),
+            Err(e) => return Err(Error::SpawnProcess("squeue".into(), e)),
         }
+    };
+
+    let read = |reader: thread::JoinHandle<std::io::Result<Vec<u8>>>| {
+        reader
+            .join()
+            .expect("The reader does not panic")
+            .map_err(|e| Error::SpawnProcess("squeue".into(), e))
+    };
+    Ok(Output {
+        status,
+        stdout: read(stdout_reader)?,
+        stderr: read(stderr_reader)?,
+    })
+}
+
+/// Parse the job IDs printed by `squeue`.
+fn parse_active_jobs(output: &Output, max_jobs: usize) -> Result<HashSet<u32>, Error> {
+    if !output.status.success() {
+        let message = match output.status.code() {
+            None => match output.status.signal() {
+                None => "squeue was terminated by a unknown signal".to_string(),
+                Some(signal) => format!("squeue was terminated by signal {signal}"),
+            },
+            Some(code) => format!("squeue exited with code {code}"),
+        };
+        return Err(Error::ExecuteSqueue(
+            message,
+            String::from_utf8_lossy(&output.stderr).into(),
+        ));
+    }
 
-        Ok(result)
+    let mut result = HashSet::with_capacity(max_jobs);
+    let jobs = String::from_utf8_lossy(&output.stdout);
+    for job in jobs.lines() {
+        result.insert(
+            job.trim()
+                .parse()
+                .map_err(|_| Error::UnexpectedOutput("squeue".into(), job.into()))?,
+        );
     }
+
+    Ok(result)
+}
+
+/// Execute a Slurm command and return its standard output.
+fn query(program: &str, args: &[&str]) -> Result<String, Error> {
+    debug!("Executing '{program} {}'.", args.join(" "));
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| Error::SpawnProcess(program.into(), e))?;
+
+    if !output.status.success() {
+        let message = match output.status.code() {
+            None => match output.status.signal() {
+                None => format!("{program} was terminated by a unknown signal"),
+                Some(signal) => format!("{program} was terminated by signal {signal}"),
+            },
+            Some(code) => format!("{program} exited with code {code}"),
+        };
+        return Err(Error::ExecuteCommand(
+            program.into(),
+            message,
+            String::from_utf8_lossy(&output.stderr).into(),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into())
+}
+
+/// Parse one name per line, ignoring blank lines.
+fn parse_names(output: &str) -> HashSet<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Query the partitions available on the current cluster with `sinfo`.
+///
+/// # Returns
+/// `Ok(HashSet<String>)` with the partition names.
+///
+/// # Errors
+/// Returns `Err(row::Error)` when `sinfo` fails.
+///
+pub fn live_partitions() -> Result<HashSet<String>, Error> {
+    Ok(parse_names(&query(
+        "sinfo",
+        &["--noheader", "--format=%R"],
+    )?))
+}
+
+/// Parse the output of `sinfo --format="%R %C"`.
+///
+/// # Returns
+/// The number of idle CPUs in each partition.
+///
+fn parse_idle_cpus(output: &str) -> HashMap<String, usize> {
+    let mut result = HashMap::new();
+    for line in output.lines() {
+        // %C is allocated/idle/other/total.
+        let Some((name, cpus)) = line.trim().split_once(char::is_whitespace) else {
+            continue;
+        };
+        let Some(idle) = cpus
+            .trim()
+            .split('/')
+            .nth(1)
+            .and_then(|i| i.parse::<usize>().ok())
+        else {
+            warn!("Unexpected output from sinfo: '{line}'.");
+            continue;
+        };
+        *result.entry(name.to_lowercase()).or_default() += idle;
+    }
+    result
+}
+
+/// Query the number of idle CPUs in each partition with `sinfo`.
+///
+/// # Errors
+/// Returns `Err(row::Error)` when `sinfo` fails.
+///
+pub fn idle_cpus() -> Result<HashMap<String, usize>, Error> {
+    Ok(parse_idle_cpus(&query(
+        "sinfo",
+        &["--noheader", "--format=%R %C"],
+    )?))
+}
+
+/// Query the accounts the current user may submit to with `sacctmgr`.
+///
+/// # Returns
+/// `Ok(HashSet<String>)` with the lowercase account names.
+///
+/// # Errors
+/// Returns `Err(row::Error)` when `sacctmgr` fails.
+///
+pub fn live_accounts() -> Result<HashSet<String>, Error> {
+    let user = env::var("USER").unwrap_or_default();
+    let mut args = vec![
+        "--noheader",
+        "--parsable2",
+        "show",
+        "associations",
+        "format=account",
+    ];
+    let user_filter = format!("user={user}");
+    if !user.is_empty() {
+        args.push("where");
+        args.push(&user_filter);
+    }
+
+    Ok(parse_names(&query("sacctmgr", &args)?)
+        .iter()
+        .map(|account| account.to_lowercase())
+        .collect())
 }
 
 #[cfg(test)]
@@ -287,13 +845,13 @@ mod tests {
     use crate::builtin::BuiltIn;
     use crate::cluster::{Cluster, IdentificationMethod, Partition, SchedulerType};
     use crate::launcher;
-    use crate::workflow::{Processes, SubmitOptions};
+    use crate::workflow::{ActionCommand, Launchers, Processes, Resources, SubmitOptions};
 
     fn setup() -> (Action, Vec<PathBuf>, Slurm) {
         let action = Action {
             name: Some("action".to_string()),
-            command: Some("command {directory}".to_string()),
-            launchers: Some(vec!["mpi".into()]),
+            command: Some(ActionCommand::Single("command {directory}".to_string())),
+            launchers: Some(Launchers::All(vec!["mpi".into()])),
             ..Action::default()
         };
 
@@ -305,6 +863,13 @@ mod tests {
             scheduler: SchedulerType::Slurm,
             partition: vec![Partition::default()],
             submit_options: Vec::new(),
+            mpi_launcher: None,
+            qos: Vec::new(),
+            bash: None,
+            job_url_template: None,
+            kubernetes: None,
+            max_submissions_per_minute: None,
+            auto_select_strategy: None,
         };
 
         let slurm = Slurm::new(cluster, launchers.by_cluster("cluster"));
@@ -316,7 +881,7 @@ mod tests {
     fn default() {
         let (action, directories, slurm) = setup();
         let script = slurm
-            .make_script(&action, &directories)
+            .make_script(&action, &directories, "action", GroupPosition::default())
             .expect("valid script");
         println!("{script}");
 
@@ -329,6 +894,23 @@ mod tests {
         assert!(script.contains("#SBATCH --time=180"));
     }
 
+    #[test]
+    #[parallel]
+    fn job_name() {
+        let (action, directories, slurm) = setup();
+
+        let script = slurm
+            .make_script(
+                &action,
+                &directories,
+                "action-T1.5",
+                GroupPosition::default(),
+            )
+            .expect("valid script");
+        assert!(script.contains("#SBATCH --job-name=action-T1.5\n"));
+        assert!(script.contains("#SBATCH --output=action-%j.out\n"));
+    }
+
     #[test]
     #[parallel]
     fn cluster_submit_options() {
@@ -336,7 +918,7 @@ mod tests {
         slurm.cluster.submit_options = vec!["--option=value".to_string()];
 
         let script = slurm
-            .make_script(&action, &directories)
+            .make_script(&action, &directories, "action", GroupPosition::default())
             .expect("valid script");
         println!("{script}");
 
@@ -358,7 +940,7 @@ mod tests {
         action.resources.processes = Some(Processes::PerDirectory(3));
 
         let script = slurm
-            .make_script(&action, &directories)
+            .make_script(&action, &directories, "action", GroupPosition::default())
             .expect("valid script");
         println!("{script}");
 
@@ -379,13 +961,131 @@ mod tests {
         );
 
         let script = slurm
-            .make_script(&action, &directories)
+            .make_script(&action, &directories, "action", GroupPosition::default())
             .expect("valid script");
         println!("{script}");
 
         assert!(script.contains("#SBATCH --account=c"));
     }
 
+    #[test]
+    #[parallel]
+    fn qos() {
+        let (mut action, directories, mut slurm) = setup();
+
+        action.submit_options.insert(
+            "cluster".into(),
+            SubmitOptions {
+                qos: Some("high".into()),
+                ..SubmitOptions::default()
+            },
+        );
+
+        let script = slurm
+            .make_script(&action, &directories, "action", GroupPosition::default())
+            .expect("valid script");
+        println!("{script}");
+        assert!(script.contains("#SBATCH --qos=high"));
+
+        slurm.cluster.qos = vec!["normal".into(), "high".into()];
+        let script = slurm
+            .make_script(&action, &directories, "action", GroupPosition::default())
+            .expect("valid script");
+        assert!(script.contains("#SBATCH --qos=high"));
+
+        slurm.cluster.qos = vec!["normal".into()];
+        let result = slurm.make_script(&action, &directories, "action", GroupPosition::default());
+        assert!(matches!(result, Err(Error::QosNotAllowed(..))));
+    }
+
+    #[test]
+    #[parallel]
+    fn heterogeneous() {
+        let (mut action, directories, slurm) = setup();
+
+        action.submit_options.insert(
+            "cluster".into(),
+            SubmitOptions {
+                account: Some("c".into()),
+                custom: vec!["--mail-type=all".into()],
+                ..SubmitOptions::default()
+            },
+        );
+        action.resources.component = vec![
+            Resources {
+                processes: Some(Processes::PerSubmission(1)),
+                gpus_per_process: Some(1),
+                ..Resources::default()
+            },
+            Resources {
+                processes: Some(Processes::PerSubmission(16)),
+                ..Resources::default()
+            },
+        ];
+
+        let script = slurm
+            .make_script(&action, &directories, "action", GroupPosition::default())
+            .expect("valid script");
+        println!("{script}");
+
+        let (first, second) = script.split_once("#SBATCH hetjob\n").expect("a hetjob");
+        assert!(first.contains("#SBATCH --ntasks=1\n"));
+        assert!(first.contains("#SBATCH --gpus-per-task=1\n"));
+        assert!(first.contains("#SBATCH --account=c\n"));
+        assert!(first.contains("#SBATCH --mail-type=all\n"));
+        assert!(second.contains("#SBATCH --ntasks=16\n"));
+        assert!(!second.contains("--gpus-per-task"));
+        assert!(second.contains("#SBATCH --account=c\n"));
+        assert!(!second.contains("--mail-type"));
+        assert!(second.contains("srun --ntasks=1 --tres-per-task=gres/gpu:1 command $directory : --ntasks=16 command $directory"));
+    }
+
+    #[test]
+    #[parallel]
+    fn partitions() {
+        let (mut action, directories, mut slurm) = setup();
+        slurm.cluster.partition = vec![
+            Partition {
+                name: "gpu".into(),
+                minimum_gpus_per_job: Some(1),
+                ..Partition::default()
+            },
+            Partition {
+                name: "cpu".into(),
+                ..Partition::default()
+            },
+        ];
+
+        assert_eq!(
+            slurm.partitions(&action, &directories).unwrap(),
+            vec!["cpu".to_string()]
+        );
+
+        action.resources.component = vec![
+            Resources {
+                gpus_per_process: Some(1),
+                ..Resources::default()
+            },
+            Resources::default(),
+        ];
+        assert_eq!(
+            slurm.partitions(&action, &directories).unwrap(),
+            vec!["gpu".to_string(), "cpu".to_string()]
+        );
+
+        action.submit_options.insert(
+            "cluster".into(),
+            SubmitOptions {
+                partition: Some("other".into()),
+                ..SubmitOptions::default()
+            },
+        );
+        assert!(matches!(
+            slurm.partitions(&action, &directories),
+            Err(Error::PartitionNameNotFound(_))
+        ));
+    }
+
     #[test]
     #[parallel]
     fn custom() {
@@ -400,7 +1100,7 @@ mod tests {
         );
 
         let script = slurm
-            .make_script(&action, &directories)
+            .make_script(&action, &directories, "action", GroupPosition::default())
             .expect("valid script");
         println!("{script}");
 
@@ -416,7 +1116,7 @@ mod tests {
         action.resources.threads_per_process = Some(5);
 
         let script = slurm
-            .make_script(&action, &directories)
+            .make_script(&action, &directories, "action", GroupPosition::default())
             .expect("valid script");
         println!("{script}");
 
@@ -431,7 +1131,7 @@ mod tests {
         action.resources.gpus_per_process = Some(5);
 
         let script = slurm
-            .make_script(&action, &directories)
+            .make_script(&action, &directories, "action", GroupPosition::default())
             .expect("valid script");
         println!("{script}");
 
@@ -449,6 +1149,13 @@ mod tests {
             identify: IdentificationMethod::Always(false),
             scheduler: SchedulerType::Slurm,
             submit_options: Vec::new(),
+            mpi_launcher: None,
+            qos: Vec::new(),
+            bash: None,
+            job_url_template: None,
+            kubernetes: None,
+            max_submissions_per_minute: None,
+            auto_select_strategy: None,
             partition: vec![Partition {
                 memory_per_cpu: Some("a".into()),
                 ..Partition::default()
@@ -458,7 +1165,7 @@ mod tests {
         let slurm = Slurm::new(cluster, launchers.by_cluster("cluster"));
 
         let script = slurm
-            .make_script(&action, &directories)
+            .make_script(&action, &directories, "action", GroupPosition::default())
             .expect("valid script");
         println!("{script}");
 
@@ -476,6 +1183,13 @@ mod tests {
             identify: IdentificationMethod::Always(false),
             scheduler: SchedulerType::Slurm,
             submit_options: Vec::new(),
+            mpi_launcher: None,
+            qos: Vec::new(),
+            bash: None,
+            job_url_template: None,
+            kubernetes: None,
+            max_submissions_per_minute: None,
+            auto_select_strategy: None,
             partition: vec![Partition {
                 memory_per_gpu: Some("b".into()),
                 ..Partition::default()
@@ -487,7 +1201,7 @@ mod tests {
         action.resources.gpus_per_process = Some(1);
 
         let script = slurm
-            .make_script(&action, &directories)
+            .make_script(&action, &directories, "action", GroupPosition::default())
             .expect("valid script");
         println!("{script}");
 
@@ -505,6 +1219,13 @@ mod tests {
             identify: IdentificationMethod::Always(false),
             scheduler: SchedulerType::Slurm,
             submit_options: Vec::new(),
+            mpi_launcher: None,
+            qos: Vec::new(),
+            bash: None,
+            job_url_template: None,
+            kubernetes: None,
+            max_submissions_per_minute: None,
+            auto_select_strategy: None,
             partition: vec![Partition {
                 cpus_per_node: Some(10),
                 ..Partition::default()
@@ -516,13 +1237,62 @@ mod tests {
         action.resources.processes = Some(Processes::PerSubmission(81));
 
         let script = slurm
-            .make_script(&action, &directories)
+            .make_script(&action, &directories, "action", GroupPosition::default())
             .expect("valid script");
         println!("{script}");
 
         assert!(script.contains("#SBATCH --nodes=9"));
     }
 
+    #[test]
+    #[parallel]
+    fn binding() {
+        let (mut action, directories, _) = setup();
+
+        let launchers = launcher::Configuration::built_in();
+        let cluster = Cluster {
+            name: "cluster".into(),
+            identify: IdentificationMethod::Always(false),
+            scheduler: SchedulerType::Slurm,
+            submit_options: Vec::new(),
+            mpi_launcher: None,
+            qos: Vec::new(),
+            bash: None,
+            job_url_template: None,
+            kubernetes: None,
+            max_submissions_per_minute: None,
+            auto_select_strategy: None,
+            partition: vec![Partition {
+                cores_per_socket: Some(32),
+                sockets_per_node: Some(2),
+                ..Partition::default()
+            }],
+        };
+
+        let slurm = Slurm::new(cluster, launchers.by_cluster("cluster"));
+
+        let script = slurm
+            .make_script(&action, &directories, "action", GroupPosition::default())
+            .expect("valid script");
+        assert!(!script.contains("--distribution"));
+        assert!(!script.contains("--cores-per-socket"));
+
+        action.resources.binding = Some(Binding::Cores);
+        let script = slurm
+            .make_script(&action, &directories, "action", GroupPosition::default())
+            .expect("valid script");
+        println!("{script}");
+        assert!(script.contains("#SBATCH --distribution=block:block"));
+        assert!(script.contains("#SBATCH --cores-per-socket=32"));
+        assert!(script.contains("#SBATCH --sockets-per-node=2"));
+
+        action.resources.binding = Some(Binding::Sockets);
+        let script = slurm
+            .make_script(&action, &directories, "action", GroupPosition::default())
+            .expect("valid script");
+        assert!(script.contains("#SBATCH --distribution=block:cyclic"));
+    }
+
     #[test]
     #[parallel]
     fn gpus_per_node() {
@@ -534,6 +1304,13 @@ mod tests {
             identify: IdentificationMethod::Always(false),
             scheduler: SchedulerType::Slurm,
             submit_options: Vec::new(),
+            mpi_launcher: None,
+            qos: Vec::new(),
+            bash: None,
+            job_url_template: None,
+            kubernetes: None,
+            max_submissions_per_minute: None,
+            auto_select_strategy: None,
             partition: vec![Partition {
                 gpus_per_node: Some(5),
                 ..Partition::default()
@@ -546,10 +1323,125 @@ mod tests {
         action.resources.gpus_per_process = Some(1);
 
         let script = slurm
-            .make_script(&action, &directories)
+            .make_script(&action, &directories, "action", GroupPosition::default())
             .expect("valid script");
         println!("{script}");
 
         assert!(script.contains("#SBATCH --nodes=17"));
     }
+
+    #[test]
+    #[parallel]
+    fn parse_live_names() {
+        let names = parse_names("shared\nGPU\n\n  debug  \n");
+        assert_eq!(names.len(), 3);
+        assert!(names.contains("shared"));
+        assert!(names.contains("GPU"));
+        assert!(names.contains("debug"));
+    }
+
+    #[test]
+    #[parallel]
+    fn parse_sinfo_idle_cpus() {
+        let idle = parse_idle_cpus("shared 10/20/0/30\nGPU 0/8/0/8\nshared 4/2/0/6\n\nbad 1\n");
+        assert_eq!(idle.len(), 2);
+        assert_eq!(idle["shared"], 22);
+        assert_eq!(idle["gpu"], 8);
+    }
+
+    #[test]
+    #[parallel]
+    fn diagnose_sbatch_errors() {
+        let script = "#SBATCH --partition=shared\n#SBATCH --account=abc\n#SBATCH --time=2880\n";
+
+        let diagnosis = diagnose_sbatch(
+            "sbatch: error: Batch job submission failed: Invalid account or account/partition combination specified",
+            script,
+            "cluster",
+        )
+        .unwrap();
+        assert!(diagnosis.contains("account 'abc' is invalid on partition 'shared'"));
+        assert!(diagnosis.contains("submit_options.cluster.account"));
+
+        let diagnosis = diagnose_sbatch(
+            "sbatch: error: Batch job submission failed: Invalid account or account/partition combination specified",
+            "#SBATCH --partition=shared\n",
+            "cluster",
+        )
+        .unwrap();
+        assert!(diagnosis.contains("requires an account"));
+
+        let diagnosis = diagnose_sbatch(
+            "sbatch: error: QOSMaxSubmitJobPerUserLimit\nsbatch: error: Batch job submission failed: Job violates accounting/QOS policy (job submit limit, user's size and/or time limits)",
+            script,
+            "cluster",
+        )
+        .unwrap();
+        assert!(diagnosis.contains("exceeds a QOS or account limit"));
+
+        let diagnosis = diagnose_sbatch(
+            "sbatch: error: Batch job submission failed: Requested time limit is invalid (missing or exceeds some limit)",
+            script,
+            "cluster",
+        )
+        .unwrap();
+        assert!(diagnosis.contains("walltime 2880 minutes exceeds the limit of partition 'shared'"));
+
+        let diagnosis = diagnose_sbatch(
+            "sbatch: error: Batch job submission failed: Invalid qos specification",
+            script,
+            "cluster",
+        )
+        .unwrap();
+        assert!(diagnosis.contains("QOS '<default>' is invalid"));
+
+        assert!(diagnose_sbatch("sbatch: error: something else", script, "cluster").is_none());
+    }
+
+    #[test]
+    #[parallel]
+    fn parse_squeue_output() {
+        let output = |code: i32, stdout: &str| Output {
+            status: ExitStatusExt::from_raw(code << 8),
+            stdout: stdout.into(),
+            stderr: b"error".to_vec(),
+        };
+
+        let active = parse_active_jobs(&output(0, "12\n 13\n"), 2).unwrap();
+        assert_eq!(active, HashSet::from([12, 13]));
+        assert!(parse_active_jobs(&output(0, ""), 2).unwrap().is_empty());
+
+        assert!(matches!(
+            parse_active_jobs(&output(0, "12\nslurm_load_jobs error"), 2),
+            Err(Error::UnexpectedOutput(..))
+        ));
+        assert!(matches!(
+            parse_active_jobs(&output(1, "12\n"), 2),
+            Err(Error::ExecuteSqueue(..))
+        ));
+    }
+
+    #[test]
+    #[parallel]
+    fn squeue_timeout() {
+        let child = Command::new("sleep")
+            .arg("10")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let start = Instant::now();
+        let result = wait_squeue(child, Duration::from_millis(100));
+        assert!(matches!(result, Err(Error::QueueTimeout(..))));
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let child = Command::new("echo")
+            .arg("42")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let output = wait_squeue(child, Duration::from_secs(10)).unwrap();
+        assert_eq!(parse_active_jobs(&output, 1).unwrap(), HashSet::from([42]));
+    }
 }
@@ -3,24 +3,550 @@
 
 use indicatif::ProgressBar;
 use log::{debug, trace, warn};
+use nix::errno::Errno;
+use nix::fcntl::{Flock, FlockArg};
+use nix::unistd::gethostname;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::collections::{HashMap, HashSet};
-use std::fs::{self, File};
+use std::collections::{hash_map, HashMap, HashSet};
+use std::env;
+use std::fs::{self, File, OpenOptions};
 use std::io;
 use std::io::prelude::*;
 use std::os::unix::fs::MetadataExt;
-use std::path::PathBuf;
-
-use crate::workflow::Workflow;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tempfile::NamedTempFile;
+use uuid::Uuid;
+
+use crate::values::DirectoryValues;
+use crate::workflow::{Action, Workflow};
 use crate::{
-    progress_styles, workspace, Error, MultiProgressContainer, COMPLETED_CACHE_FILE_NAME,
-    COMPLETED_DIRECTORY_NAME, DATA_DIRECTORY_NAME, DIRECTORY_CACHE_FILE_NAME,
-    MIN_PROGRESS_BAR_SIZE, SUBMITTED_CACHE_FILE_NAME,
+    fnv1a, progress_styles, workspace, Error, MultiProgressContainer, COMPLETED_CACHE_FILE_NAME,
+    COMPLETED_DIRECTORY_NAME, COMPLETED_PACK_EXTENSION, COMPLETION_HASHES_FILE_NAME,
+    DIRECTORY_CACHE_FILE_NAME, FNV_OFFSET_BASIS, JOBS_CACHE_FILE_NAME,
+    LEGACY_COMPLETED_CACHE_FILE_NAME, MIN_PROGRESS_BAR_SIZE, PENDING_PACK_EXTENSION,
+    SUBMITTED_CACHE_FILE_NAME, SUBMITTED_GROUPS_CACHE_FILE_NAME, SUBMIT_LOCK_FILE_NAME,
+    V2_COMPLETED_PACK_EXTENSION, V2_PENDING_PACK_EXTENSION, V3_COMPLETED_PACK_EXTENSION,
+    V3_PENDING_PACK_EXTENSION,
 };
 
 type SubmittedJobs = HashMap<String, HashMap<PathBuf, (String, u32)>>;
 
+/// Submitted groups: action -> group ID -> directories
+type SubmittedGroups = HashMap<String, HashMap<String, Vec<PathBuf>>>;
+
+/// Completion hashes: action -> hash of the fields that determine completion
+type CompletionHashes = HashMap<String, u64>;
+
+/// Collect the (cluster, job ID) pairs in the submitted jobs.
+fn jobs_in(submitted: &SubmittedJobs) -> HashSet<(String, u32)> {
+    submitted
+        .values()
+        .flat_map(HashMap::values)
+        .cloned()
+        .collect()
+}
+
+/// Compute the completion hash of each action in the workflow.
+///
+/// Combines the hashes of actions that share a name.
+///
+fn completion_hashes(workflow: &Workflow) -> CompletionHashes {
+    let mut hashes = HashMap::new();
+    for action in &workflow.action {
+        let hash = action.completion_hash();
+        hashes
+            .entry(action.name().into())
+            .and_modify(|h| *h = fnv1a(*h, &hash.to_le_bytes()))
+            .or_insert(hash);
+    }
+    hashes
+}
+
+/// Compute a deterministic identifier for a group of directories.
+///
+/// The identifier is the 64-bit FNV-1a hash of the sorted directory names, so it
+/// does not depend on the order of `directories` or the version of row.
+///
+pub fn group_id(directories: &[PathBuf]) -> String {
+    let mut sorted: Vec<&PathBuf> = directories.iter().collect();
+    sorted.sort_unstable();
+
+    let mut hash = FNV_OFFSET_BASIS;
+    for directory in sorted {
+        // Terminate each name with a byte that is not valid UTF-8.
+        hash = fnv1a(
+            hash,
+            directory
+                .as_os_str()
+                .as_encoded_bytes()
+                .iter()
+                .chain(&[0xff]),
+        );
+    }
+
+    format!("{hash:016x}")
+}
+
+/// Completed directories: action -> directory -> completion
+pub type Completions = HashMap<String, HashMap<PathBuf, Completion>>;
+
+/// Record where and when row found a completed directory.
+///
+/// Both fields are `None` for completions read from caches written by
+/// earlier versions of row.
+///
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct Completion {
+    /// Name of the cluster.
+    pub cluster: Option<String>,
+
+    /// Time of the completion in seconds since the UNIX epoch.
+    pub time: Option<u64>,
+}
+
+impl Completion {
+    /// Record a completion on the given cluster at the current time.
+    pub fn now(cluster_name: &str) -> Self {
+        Self {
+            cluster: Some(cluster_name.into()),
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|d| d.as_secs()),
+        }
+    }
+}
+
+/// Attach the completion record to each newly completed directory.
+#[allow(clippy::implicit_hasher)]
+pub fn record_completions(
+    new_complete: HashMap<String, HashSet<PathBuf>>,
+    completion: &Completion,
+) -> Completions {
+    new_complete
+        .into_iter()
+        .map(|(action_name, directories)| {
+            (
+                action_name,
+                directories
+                    .into_iter()
+                    .map(|d| (d, completion.clone()))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+/// A job that row submitted.
+///
+/// `row submit` records each job. The completion packs written by the job
+/// record when it started and finished.
+///
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct JobRecord {
+    /// The name of the action.
+    pub action: String,
+
+    /// The name of the cluster.
+    pub cluster: String,
+
+    /// The scheduler's job ID (`None` when row executed the job directly).
+    pub job_id: Option<u32>,
+
+    /// The number of directories in the job.
+    pub directories: usize,
+
+    /// The requested walltime in seconds.
+    pub walltime: u64,
+
+    /// The number of requested GPUs.
+    pub gpus: usize,
+
+    /// Time that row submitted the job in seconds since the UNIX epoch.
+    pub submitted: u64,
+
+    /// Time that the job started in seconds since the UNIX epoch.
+    pub started: Option<u64>,
+
+    /// Time that the job finished in seconds since the UNIX epoch.
+    pub finished: Option<u64>,
+}
+
+impl JobRecord {
+    /// Record a job of `action` on `directories` directories submitted now.
+    pub fn new(action: &Action, cluster_name: &str, directories: usize) -> Self {
+        Self {
+            action: action.name().into(),
+            cluster: cluster_name.into(),
+            job_id: None,
+            directories,
+            walltime: u64::try_from(
+                action
+                    .resources
+                    .total_walltime(directories)
+                    .signed_total_seconds(),
+            )
+            .unwrap_or_default(),
+            gpus: action.resources.total_gpus(directories),
+            submitted: now(),
+            started: None,
+            finished: None,
+        }
+    }
+
+    /// Record that the job started when it was submitted and finished now.
+    ///
+    /// Use for jobs that the scheduler executes before `submit` returns.
+    ///
+    #[must_use]
+    pub fn finished_now(self) -> Self {
+        Self {
+            started: Some(self.submitted),
+            finished: Some(now()),
+            ..self
+        }
+    }
+
+    /// Get the time that the job executed in seconds.
+    ///
+    /// # Returns
+    /// `None` when the start or finish time is not known.
+    ///
+    pub fn runtime(&self) -> Option<u64> {
+        self.finished?.checked_sub(self.started?)
+    }
+}
+
+/// Get the current time in seconds since the UNIX epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// A staged completion pack and the process that wrote it.
+///
+/// `row scan` writes completion packs to `.row/completed`. The next command
+/// that synchronizes the project adds the completions to the cache and
+/// removes the pack. All the fields but `completions` are `None` in packs
+/// written by earlier versions of row.
+///
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct CompletionPack {
+    /// The action scanned by the process (`None` when it scanned all actions).
+    pub action: Option<String>,
+
+    /// The ID of the scheduler job that wrote the pack.
+    pub job_id: Option<String>,
+
+    /// Time that the scheduler job started in seconds since the UNIX epoch.
+    pub job_started: Option<u64>,
+
+    /// The name of the host that wrote the pack.
+    pub hostname: Option<String>,
+
+    /// The ID of the process that wrote the pack.
+    pub pid: Option<u32>,
+
+    /// Time that the pack was written in seconds since the UNIX epoch.
+    pub time: Option<u64>,
+
+    /// The completed directories.
+    pub completions: Completions,
+}
+
+/// A completion pack written by row 0.3 and earlier.
+#[derive(Deserialize)]
+struct CompletionPackV3 {
+    action: Option<String>,
+    job_id: Option<String>,
+    hostname: Option<String>,
+    pid: Option<u32>,
+    time: Option<u64>,
+    completions: Completions,
+}
+
+impl From<CompletionPackV3> for CompletionPack {
+    fn from(pack: CompletionPackV3) -> Self {
+        Self {
+            action: pack.action,
+            job_id: pack.job_id,
+            job_started: None,
+            hostname: pack.hostname,
+            pid: pack.pid,
+            time: pack.time,
+            completions: pack.completions,
+        }
+    }
+}
+
+impl CompletionPack {
+    /// Record completions found by the current process.
+    ///
+    /// Read the job ID from `SLURM_JOB_ID` and the time the job started from
+    /// `SLURM_JOB_START_TIME` when set.
+    ///
+    pub fn new(action: Option<String>, completions: Completions) -> Self {
+        Self {
+            action,
+            job_id: env::var("SLURM_JOB_ID").ok(),
+            job_started: env::var("SLURM_JOB_START_TIME")
+                .ok()
+                .and_then(|time| time.parse().ok()),
+            hostname: gethostname().ok().map(|h| h.to_string_lossy().into_owned()),
+            pid: Some(process::id()),
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|d| d.as_secs()),
+            completions,
+        }
+    }
+
+    /// Name the pack file by job ID, host name, and process ID.
+    ///
+    /// A random suffix distinguishes packs written by processes that share
+    /// these (for example, when the system reuses a process ID).
+    ///
+    pub fn file_name(&self, extension: &str) -> String {
+        let sanitize = |s: &str| s.replace(['/', '\\', '_'], "-");
+        format!(
+            "{}_{}_{}_{}.{extension}",
+            self.job_id.as_deref().map_or("nojob".into(), sanitize),
+            self.hostname.as_deref().map_or("nohost".into(), sanitize),
+            self.pid.unwrap_or_default(),
+            &Uuid::new_v4().simple().to_string()[..8],
+        )
+    }
+
+    /// Read a completion pack (or pending completion pack) of any version.
+    ///
+    /// # Errors
+    /// Returns `Err<row::Error>` when the file cannot be read or parsed.
+    ///
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        let name = path.to_string_lossy();
+        if name.ends_with(COMPLETED_PACK_EXTENSION) || name.ends_with(PENDING_PACK_EXTENSION) {
+            let bytes = fs::read(path).map_err(|e| Error::FileRead(path.into(), e))?;
+            postcard::from_bytes(&bytes).map_err(|e| Error::PostcardParse(path.into(), e))
+        } else if name.ends_with(V3_COMPLETED_PACK_EXTENSION)
+            || name.ends_with(V3_PENDING_PACK_EXTENSION)
+        {
+            let bytes = fs::read(path).map_err(|e| Error::FileRead(path.into(), e))?;
+            let pack: CompletionPackV3 =
+                postcard::from_bytes(&bytes).map_err(|e| Error::PostcardParse(path.into(), e))?;
+            Ok(pack.into())
+        } else if name.ends_with(V2_COMPLETED_PACK_EXTENSION)
+            || name.ends_with(V2_PENDING_PACK_EXTENSION)
+        {
+            let bytes = fs::read(path).map_err(|e| Error::FileRead(path.into(), e))?;
+            Ok(Self {
+                completions: postcard::from_bytes(&bytes)
+                    .map_err(|e| Error::PostcardParse(path.into(), e))?,
+                ..Self::default()
+            })
+        } else {
+            Ok(Self {
+                completions: State::read_legacy_completed(path)?,
+                ..Self::default()
+            })
+        }
+    }
+
+    /// Write the pack to a new file in the given directory.
+    ///
+    /// Write to a temporary file and rename it so that readers never see a
+    /// partially written pack.
+    ///
+    /// # Returns
+    /// The path to the new file.
+    ///
+    /// # Errors
+    /// Returns `Err<row::Error>` when the file cannot be written.
+    ///
+    pub fn write(&self, directory: &Path, extension: &str) -> Result<PathBuf, Error> {
+        let bytes = postcard::to_stdvec(self)
+            .map_err(|e| Error::PostcardSerialize("completed".into(), e))?;
+
+        let filename = directory.join(self.file_name(extension));
+        let tmp_filename = filename.with_extension("tmp");
+
+        fs::create_dir_all(directory).map_err(|e| Error::DirectoryCreate(directory.into(), e))?;
+
+        trace!(
+            "Writing {} bytes to '{}'.",
+            bytes.len(),
+            tmp_filename.display().to_string()
+        );
+        let mut file = File::create_new(&tmp_filename)
+            .map_err(|e| Error::FileWrite(tmp_filename.clone(), e))?;
+        file.write_all(&bytes)
+            .map_err(|e| Error::FileWrite(tmp_filename.clone(), e))?;
+        file.sync_all()
+            .map_err(|e| Error::FileWrite(tmp_filename.clone(), e))?;
+        drop(file);
+
+        fs::rename(&tmp_filename, &filename).map_err(|e| Error::FileWrite(filename.clone(), e))?;
+        Ok(filename)
+    }
+}
+
+/// Write a cache file atomically.
+///
+/// Write to a uniquely named temporary file in the same directory, sync it to
+/// disk, and rename it over `path` so that a crash never leaves a partially
+/// written cache and concurrent writers never share a temporary file.
+///
+fn write_cache(path: &Path, bytes: &[u8]) -> Result<(), Error> {
+    let directory = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut file =
+        NamedTempFile::new_in(directory).map_err(|e| Error::FileWrite(path.into(), e))?;
+
+    trace!(
+        "Writing {} bytes to '{}'.",
+        bytes.len(),
+        file.path().display()
+    );
+    file.write_all(bytes)
+        .and_then(|()| file.as_file().sync_all())
+        .map_err(|e| Error::FileWrite(file.path().into(), e))?;
+
+    file.persist(path)
+        .map_err(|e| Error::FileWrite(path.into(), e.error))?;
+
+    // Sync the directory so that the rename is also durable.
+    File::open(directory)
+        .and_then(|d| d.sync_all())
+        .map_err(|e| Error::FileWrite(path.into(), e))?;
+    Ok(())
+}
+
+/// Move a cache file that cannot be parsed aside.
+///
+/// The next invocation of **row** finds no cache and rebuilds it.
+///
+/// # Returns
+/// `Error::CorruptCache` with `advice` that describes how to recover.
+///
+fn quarantine_cache(path: PathBuf, error: &dyn std::error::Error, advice: &str) -> Error {
+    let mut corrupt_path = path.as_os_str().to_owned();
+    corrupt_path.push(".corrupt");
+    let corrupt_path = PathBuf::from(corrupt_path);
+
+    if let Err(rename_error) = fs::rename(&path, &corrupt_path) {
+        return Error::FileWrite(corrupt_path, rename_error);
+    }
+    Error::CorruptCache(path, corrupt_path, error.to_string(), advice.into())
+}
+
+/// Exclusive lock that serializes the processes that submit jobs in a project.
+///
+/// `SubmitLock` holds an advisory lock on `.row/submit.lock` and removes the
+/// file when dropped. The operating system releases the lock when the process
+/// exits, so a file left behind by a crash does not block other processes.
+/// The lock serializes only the processes that acquire it.
+///
+/// On filesystems that do not support file locks, `SubmitLock` warns and
+/// holds no lock.
+///
+#[derive(Debug)]
+pub struct SubmitLock {
+    path: PathBuf,
+    lock: Option<Flock<File>>,
+}
+
+impl SubmitLock {
+    /// Acquire the lock, waiting for any other process that holds it.
+    ///
+    /// # Errors
+    /// Returns `Err<row::Error>` when the lock file cannot be created or locked.
+    ///
+    pub fn acquire(workflow: &Workflow) -> Result<Self, Error> {
+        let data_directory = workflow.data_directory();
+        fs::create_dir_all(&data_directory)
+            .map_err(|e| Error::DirectoryCreate(data_directory.clone(), e))?;
+        let path = data_directory.join(SUBMIT_LOCK_FILE_NAME);
+
+        loop {
+            let file = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&path)
+                .map_err(|e| Error::FileWrite(path.clone(), e))?;
+
+            let lock = match Flock::lock(file, FlockArg::LockExclusiveNonblock) {
+                Ok(lock) => lock,
+                Err((file, Errno::EWOULDBLOCK)) => {
+                    warn!("Waiting for another process to finish submitting jobs.");
+                    Flock::lock(file, FlockArg::LockExclusive)
+                        .map_err(|(_, e)| Error::FileLock(path.clone(), e.into()))?
+                }
+                Err((_, error)) if locks_unsupported(error) => {
+                    warn!(
+                        "The filesystem does not support locking '{}' ({error}). Submitting \
+                         without the lock: run only one `row submit` in this project at a time.",
+                        path.display()
+                    );
+                    if let Err(error) = fs::remove_file(&path) {
+                        warn!("Unable to remove '{}': {error}", path.display());
+                    }
+                    return Ok(Self { path, lock: None });
+                }
+                Err((_, e)) => return Err(Error::FileLock(path.clone(), e.into())),
+            };
+
+            // The previous holder removes the file before it releases the lock.
+            // Lock the new file in that case.
+            let current = match (lock.metadata(), fs::metadata(&path)) {
+                (Ok(locked), Ok(on_disk)) => {
+                    locked.dev() == on_disk.dev() && locked.ino() == on_disk.ino()
+                }
+                _ => false,
+            };
+            if current {
+                debug!("Locked '{}'.", path.display());
+                return Ok(Self {
+                    path,
+                    lock: Some(lock),
+                });
+            }
+            trace!("'{}' was replaced, locking again.", path.display());
+        }
+    }
+}
+
+/// Check whether a `flock` error means that the filesystem does not support locks.
+fn locks_unsupported(error: Errno) -> bool {
+    matches!(error, Errno::ENOSYS | Errno::ENOTSUP | Errno::ENOLCK)
+}
+
+impl Drop for SubmitLock {
+    fn drop(&mut self) {
+        if self.lock.is_none() {
+            return;
+        }
+
+        // Remove the file while holding the lock.
+        if let Err(error) = fs::remove_file(&self.path) {
+            warn!("Unable to remove '{}': {error}", self.path.display());
+        }
+    }
+}
+
+/// Check whether a path names a pending completion pack (of any version).
+pub fn is_pending_pack(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "pending")
+}
+
+/// Check whether a path names a completion pack (of any version).
+fn is_pack(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "postcard") || is_pending_pack(path)
+}
+
 /// Directory cache
 ///
 /// Cache the directory values and store the last modified time.
@@ -31,7 +557,11 @@ pub struct DirectoryCache {
     modified_time: (i64, i64),
 
     /// Directory values.
-    values: HashMap<PathBuf, Value>,
+    values: DirectoryValues,
+
+    /// JSON pointers kept in `values` (`None` when `values` are complete).
+    #[serde(default)]
+    pointers: Option<Vec<String>>,
 }
 
 /// The state of the project.
@@ -41,24 +571,42 @@ pub struct DirectoryCache {
 /// * JSON values for each directory
 /// * Completed directories for each action.
 /// * Scheduled jobs by action, directory, (and cluster?).
+/// * Submitted groups of directories by action.
+/// * The history of submitted jobs.
 ///
 /// `State` implements methods that synchronize a state with the workspace on disk and
 /// to interface with the scheduler's queue.
 ///
 #[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct State {
     /// The directory cache.
     directory_cache: DirectoryCache,
 
     /// Completed directories for each action.
-    completed: HashMap<String, HashSet<PathBuf>>,
+    completed: Completions,
+
+    /// The completion hash of each action when `completed` was last saved.
+    completion_hashes: CompletionHashes,
 
     /// Submitted jobs: action -> directory -> (cluster, job ID)
     submitted: SubmittedJobs,
 
+    /// Submitted groups: action -> group ID -> directories
+    submitted_groups: SubmittedGroups,
+
+    /// The job history.
+    jobs: Vec<JobRecord>,
+    /// Jobs in the submitted cache when it was last read or written: (cluster, job ID)
+    read_jobs: HashSet<(String, u32)>,
+
     /// Completion files read while synchronizing.
     completed_file_names: Vec<PathBuf>,
 
+    /// Completions found while synchronizing: (action, directory). `save_cache`
+    /// executes the `on_action_complete` hook for these.
+    newly_completed: Vec<(String, PathBuf)>,
+
     /// Set to true when `values` is modified from the on-disk cache.
     directories_modified: bool,
 
@@ -67,16 +615,115 @@ pub struct State {
 
     /// Set to true when `submitted` is modified from the on-disk cache.
     submitted_modified: bool,
+
+    /// Set to true when `submitted_groups` is modified from the on-disk cache.
+    submitted_groups_modified: bool,
+
+    /// Set to true when `jobs` is modified from the on-disk cache.
+    jobs_modified: bool,
+}
+
+/// How `State::import` resolves entries that are in both the cache and the import.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Conflict {
+    /// Keep the cached entry.
+    #[default]
+    Keep,
+
+    /// Replace the cached entry with the imported entry.
+    Replace,
+
+    /// Return an error without changing the cache.
+    Error,
+}
+
+/// The state to import with `State::import`.
+#[derive(Debug, Default)]
+pub struct Import {
+    /// Completed directories: action -> directory -> completion
+    pub completed: Completions,
+
+    /// Submitted jobs: action -> directory -> (cluster, job ID)
+    pub submitted: HashMap<String, HashMap<PathBuf, (String, u32)>>,
+
+    /// Submitted groups: action -> group ID -> directories
+    pub submitted_groups: HashMap<String, HashMap<String, Vec<PathBuf>>>,
+}
+
+/// Changes made by `State::import`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Imported {
+    /// Number of completions added or replaced.
+    pub completed: usize,
+
+    /// Number of submitted directories added or replaced.
+    pub submitted: usize,
+
+    /// Number of submitted groups added.
+    pub submitted_groups: usize,
+
+    /// Number of imported entries that were already in the cache and were kept.
+    pub kept: usize,
+
+    /// Number of entries for actions or directories that are not in the
+    /// project, or for jobs on other clusters.
+    pub skipped: usize,
+}
+
+/// Changes made by `State::repair`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Repairs {
+    /// Directories added to the directory cache.
+    pub directories_added: Vec<PathBuf>,
+
+    /// Directories removed from the directory cache.
+    pub directories_removed: Vec<PathBuf>,
+
+    /// Directories with values that differ from the directory cache.
+    pub values_changed: Vec<PathBuf>,
+
+    /// Number of completed entries that referred to missing actions or directories.
+    pub orphaned_completed: usize,
+
+    /// Number of submitted entries that referred to missing actions or directories.
+    pub orphaned_submitted: usize,
+
+    /// Completions (action, directory) added because all products are present.
+    pub completions_added: Vec<(String, PathBuf)>,
+
+    /// Completions (action, directory) removed because products are missing.
+    pub completions_removed: Vec<(String, PathBuf)>,
+}
+
+impl Repairs {
+    /// Check whether the repair changed nothing.
+    pub fn is_empty(&self) -> bool {
+        self.directories_added.is_empty()
+            && self.directories_removed.is_empty()
+            && self.values_changed.is_empty()
+            && self.orphaned_completed == 0
+            && self.orphaned_submitted == 0
+            && self.completions_added.is_empty()
+            && self.completions_removed.is_empty()
+    }
 }
 
 impl State {
     /// Get the directory values.
-    pub fn values(&self) -> &HashMap<PathBuf, Value> {
+    pub fn values(&self) -> &DirectoryValues {
         &self.directory_cache.values
     }
 
-    /// Get the set of directories completed for a given action.
-    pub fn completed(&self) -> &HashMap<String, HashSet<PathBuf>> {
+    /// Get the JSON pointers kept in the directory values.
+    ///
+    /// `None` when the directory values are complete.
+    ///
+    pub fn value_pointers(&self) -> Option<&[String]> {
+        self.directory_cache.pointers.as_deref()
+    }
+
+    /// Get the directories completed for each action.
+    pub fn completed(&self) -> &Completions {
         &self.completed
     }
 
@@ -85,6 +732,11 @@ impl State {
         &self.submitted
     }
 
+    /// Get the mapping of actions -> group ID -> directories
+    pub fn submitted_groups(&self) -> &SubmittedGroups {
+        &self.submitted_groups
+    }
+
     /// Get the number of submitted jobs.
     pub fn num_submitted(&self) -> usize {
         let mut result = 0;
@@ -104,26 +756,392 @@ impl State {
         }
     }
 
-    /// Add a submitted job.
-    pub fn add_submitted(
-        &mut self,
-        action_name: &str,
-        directories: &[PathBuf],
-        cluster_name: &str,
-        job_id: u32,
-    ) {
-        for directory in directories {
-            self.submitted
-                .entry(action_name.into())
-                .and_modify(|e| {
-                    e.insert(directory.clone(), (cluster_name.to_string(), job_id));
-                })
-                .or_insert(HashMap::from([(
-                    directory.clone(),
-                    (cluster_name.to_string(), job_id),
-                )]));
-        }
-        self.submitted_modified = true;
+    /// Add a submitted job.
+    ///
+    /// Also record the directories as a submitted group (see `group_id`).
+    ///
+    pub fn add_submitted(
+        &mut self,
+        action_name: &str,
+        directories: &[PathBuf],
+        cluster_name: &str,
+        job_id: u32,
+    ) {
+        for directory in directories {
+            self.submitted
+                .entry(action_name.into())
+                .and_modify(|e| {
+                    e.insert(directory.clone(), (cluster_name.to_string(), job_id));
+                })
+                .or_insert(HashMap::from([(
+                    directory.clone(),
+                    (cluster_name.to_string(), job_id),
+                )]));
+        }
+        self.submitted_modified = true;
+
+        self.submitted_groups
+            .entry(action_name.into())
+            .or_default()
+            .insert(group_id(directories), directories.to_vec());
+        self.submitted_groups_modified = true;
+    }
+
+    /// Get the job history.
+    pub fn jobs(&self) -> &[JobRecord] {
+        &self.jobs
+    }
+
+    /// Add a job to the job history.
+    pub fn add_job(&mut self, job: JobRecord) {
+        self.jobs.push(job);
+        self.jobs_modified = true;
+    }
+
+    /// Record when the job that wrote `pack` started and finished.
+    ///
+    /// A job may write several packs: it started no later than the earliest
+    /// and finished no earlier than the last.
+    ///
+    fn record_job_times(&mut self, pack: &CompletionPack, cluster_name: &str) {
+        let Some(job_id) = pack.job_id.as_deref().and_then(|id| id.parse().ok()) else {
+            return;
+        };
+        let Some(job) = self
+            .jobs
+            .iter_mut()
+            .rev()
+            .find(|job| job.job_id == Some(job_id) && job.cluster == cluster_name)
+        else {
+            return;
+        };
+
+        if let Some(started) = pack.job_started {
+            job.started = Some(job.started.map_or(started, |s| s.min(started)));
+        }
+        if let Some(time) = pack.time {
+            job.finished = Some(job.finished.map_or(time, |f| f.max(time)));
+        }
+        self.jobs_modified = true;
+    }
+
+    /// Add the jobs that other processes submitted since the cache was read.
+    ///
+    /// Read the submitted caches again and add the jobs (and groups) that were
+    /// not present when this process read them, along with their job history
+    /// records. Jobs that this process removed remain removed. Call while
+    /// holding the `SubmitLock`.
+    ///
+    /// # Errors
+    /// Returns `Err<row::Error>` when the cache files cannot be read or parsed.
+    ///
+    pub fn merge_submitted(&mut self, workflow: &Workflow) -> Result<(), Error> {
+        let submitted = Self::read_submitted_cache(workflow)?;
+        let on_disk_jobs = jobs_in(&submitted);
+
+        for (action_name, directories) in submitted {
+            for (directory, job) in directories {
+                if self.read_jobs.contains(&job) {
+                    continue;
+                }
+                trace!(
+                    "Another process submitted '{}' in action '{}' (job {}).",
+                    directory.display(),
+                    action_name,
+                    job.1
+                );
+                self.submitted
+                    .entry(action_name.clone())
+                    .or_default()
+                    .entry(directory)
+                    .or_insert(job);
+                self.submitted_modified = true;
+            }
+        }
+        self.read_jobs.extend(on_disk_jobs);
+
+        for (action_name, groups) in Self::read_submitted_groups_cache(workflow)? {
+            let existing = self.submitted_groups.entry(action_name).or_default();
+            for (id, directories) in groups {
+                if let hash_map::Entry::Vacant(entry) = existing.entry(id) {
+                    entry.insert(directories);
+                    self.submitted_groups_modified = true;
+                }
+            }
+        }
+
+        let known: HashSet<_> = self
+            .jobs
+            .iter()
+            .map(|job| (job.cluster.clone(), job.job_id, job.submitted))
+            .collect();
+        for job in Self::read_jobs_cache(workflow)? {
+            if !known.contains(&(job.cluster.clone(), job.job_id, job.submitted)) {
+                self.jobs.push(job);
+                self.jobs_modified = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Import completions and submitted jobs from another copy of the project.
+    ///
+    /// Skip entries for actions that are not in the workflow, directories that
+    /// are not in the workspace, and jobs submitted on clusters other than
+    /// `cluster_name`. Import a submitted group only when the submitted jobs of
+    /// all its directories are imported. Resolve entries that are already in
+    /// the cache by `conflict`.
+    ///
+    /// # Errors
+    /// Returns `Err<row::Error>` when `conflict` is `Conflict::Error` and an
+    /// imported entry differs from the cached entry. The cache is unchanged in
+    /// this case.
+    ///
+    #[allow(clippy::too_many_lines)]
+    pub fn import(
+        &mut self,
+        workflow: &Workflow,
+        import: Import,
+        cluster_name: &str,
+        conflict: Conflict,
+    ) -> Result<Imported, Error> {
+        let known = |action_name: &str, directory: &Path| {
+            workflow.action_by_name(action_name).is_some()
+                && self.directory_cache.values.contains_key(directory)
+        };
+
+        let mut completed = Vec::new();
+        let mut submitted = Vec::new();
+        let mut result = Imported::default();
+        for (action_name, directories) in import.completed {
+            for (directory, completion) in directories {
+                if !known(&action_name, &directory) {
+                    result.skipped += 1;
+                    continue;
+                }
+                match self
+                    .completed
+                    .get(&action_name)
+                    .and_then(|d| d.get(&directory))
+                {
+                    None => completed.push((action_name.clone(), directory, completion)),
+                    Some(cached) if *cached == completion => result.kept += 1,
+                    Some(_) => match conflict {
+                        Conflict::Keep => result.kept += 1,
+                        Conflict::Replace => {
+                            completed.push((action_name.clone(), directory, completion));
+                        }
+                        Conflict::Error => {
+                            return Err(Error::ImportConflict(directory, action_name));
+                        }
+                    },
+                }
+            }
+        }
+
+        // The (action, directory) pairs whose cached job will be the imported job.
+        let mut imported_jobs = HashSet::new();
+        for (action_name, directories) in import.submitted {
+            for (directory, job) in directories {
+                if !known(&action_name, &directory) || job.0 != cluster_name {
+                    result.skipped += 1;
+                    continue;
+                }
+                match self.submitted_job(&action_name, &directory) {
+                    None => {
+                        imported_jobs.insert((action_name.clone(), directory.clone()));
+                        submitted.push((action_name.clone(), directory, job));
+                    }
+                    Some(cached) if *cached == job => {
+                        imported_jobs.insert((action_name.clone(), directory));
+                        result.kept += 1;
+                    }
+                    Some(_) => match conflict {
+                        Conflict::Keep => result.kept += 1,
+                        Conflict::Replace => {
+                            imported_jobs.insert((action_name.clone(), directory.clone()));
+                            submitted.push((action_name.clone(), directory, job));
+                        }
+                        Conflict::Error => {
+                            return Err(Error::ImportConflict(directory, action_name));
+                        }
+                    },
+                }
+            }
+        }
+
+        let mut submitted_groups = Vec::new();
+        for (action_name, groups) in import.submitted_groups {
+            for (id, directories) in groups {
+                if !directories
+                    .iter()
+                    .all(|d| imported_jobs.contains(&(action_name.clone(), d.clone())))
+                {
+                    result.skipped += 1;
+                    continue;
+                }
+                match self
+                    .submitted_groups
+                    .get(&action_name)
+                    .and_then(|g| g.get(&id))
+                {
+                    None => submitted_groups.push((action_name.clone(), id, directories)),
+                    Some(cached) if *cached == directories => result.kept += 1,
+                    Some(_) => match conflict {
+                        Conflict::Keep => result.kept += 1,
+                        Conflict::Replace => {
+                            submitted_groups.push((action_name.clone(), id, directories));
+                        }
+                        Conflict::Error => {
+                            return Err(Error::ImportGroupConflict(id, action_name));
+                        }
+                    },
+                }
+            }
+        }
+
+        result.completed = completed.len();
+        for (action_name, directory, completion) in completed {
+            self.completed
+                .entry(action_name)
+                .or_default()
+                .insert(directory, completion);
+            self.completed_modified = true;
+        }
+
+        result.submitted = submitted.len();
+        for (action_name, directory, job) in submitted {
+            self.submitted
+                .entry(action_name)
+                .or_default()
+                .insert(directory, job);
+            self.submitted_modified = true;
+        }
+
+        result.submitted_groups = submitted_groups.len();
+        for (action_name, id, directories) in submitted_groups {
+            self.submitted_groups
+                .entry(action_name)
+                .or_default()
+                .insert(id, directories);
+            self.submitted_groups_modified = true;
+        }
+
+        Ok(result)
+    }
+
+    /// Get the job that submitted a directory in the given action.
+    ///
+    /// # Returns
+    /// `Some((cluster, job ID))` when the directory is submitted.
+    ///
+    pub fn submitted_job(&self, action_name: &str, directory: &PathBuf) -> Option<&(String, u32)> {
+        self.submitted.get(action_name)?.get(directory)
+    }
+
+    /// Find previously submitted groups that a new group would split.
+    ///
+    /// # Returns
+    /// `(group ID, number of directories in common, number of directories in
+    /// the submitted group)` for each submitted group of the action that has some,
+    /// but not all, of its directories in `directories`. Sorted by group ID.
+    ///
+    pub fn split_submitted_groups(
+        &self,
+        action_name: &str,
+        directories: &[PathBuf],
+    ) -> Vec<(String, usize, usize)> {
+        let Some(groups) = self.submitted_groups.get(action_name) else {
+            return Vec::new();
+        };
+
+        let directories: HashSet<&PathBuf> = directories.iter().collect();
+        let mut result = Vec::new();
+        for (id, members) in groups {
+            let common = members.iter().filter(|d| directories.contains(d)).count();
+            if common > 0 && common < members.len() {
+                result.push((id.clone(), common, members.len()));
+            }
+        }
+
+        result.sort_unstable();
+        result
+    }
+
+    /// Remove directories from the completed cache for the given action.
+    pub fn remove_completed(&mut self, action_name: &str, directories: &[PathBuf]) {
+        if let Some(completed_directories) = self.completed.get_mut(action_name) {
+            for directory in directories {
+                if completed_directories.remove(directory).is_some() {
+                    self.completed_modified = true;
+                }
+            }
+        }
+    }
+
+    /// Rename a directory in all caches.
+    ///
+    /// Completions, submitted jobs, and submitted groups follow the directory to its
+    /// new name. Does nothing when `old` is not in the directory cache.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when `new` is not valid UTF-8.
+    ///
+    pub fn rename_directory(&mut self, old: &Path, new: &Path) -> Result<(), Error> {
+        let Some(value) = self.directory_cache.values.get(old).cloned() else {
+            return Ok(());
+        };
+        self.directory_cache.values.insert(new, &value)?;
+        self.directory_cache.values.retain(|d| d != old);
+        self.directories_modified = true;
+
+        for directories in self.completed.values_mut() {
+            if let Some(completion) = directories.remove(old) {
+                directories.insert(new.into(), completion);
+                self.completed_modified = true;
+            }
+        }
+
+        for directories in self.submitted.values_mut() {
+            if let Some(job) = directories.remove(old) {
+                directories.insert(new.into(), job);
+                self.submitted_modified = true;
+            }
+        }
+
+        for groups in self.submitted_groups.values_mut() {
+            let renamed: Vec<String> = groups
+                .iter()
+                .filter(|(_, members)| members.iter().any(|d| d == old))
+                .map(|(id, _)| id.clone())
+                .collect();
+            for id in renamed {
+                if let Some(mut members) = groups.remove(&id) {
+                    for member in &mut members {
+                        if member == old {
+                            *member = new.into();
+                        }
+                    }
+                    groups.insert(group_id(&members), members);
+                    self.submitted_groups_modified = true;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove directories from all caches.
+    pub fn remove_directories(&mut self, workflow: &Workflow, directories: &[PathBuf]) {
+        let directories: HashSet<&Path> = directories.iter().map(PathBuf::as_path).collect();
+        self.directory_cache
+            .values
+            .retain(|d| !directories.contains(d));
+        self.directories_modified = true;
+
+        self.remove_missing_completed(workflow);
+        self.remove_missing_submitted(workflow);
     }
 
     /// Remove inactive jobs on the given cluster.
@@ -158,7 +1176,7 @@ impl State {
     pub fn list_directories(&self) -> Vec<PathBuf> {
         trace!("Listing all directories in project.");
         let mut result = Vec::with_capacity(self.values().len());
-        result.extend(self.values().keys().cloned());
+        result.extend(self.values().keys().map(Path::to_path_buf));
         result
     }
 
@@ -170,18 +1188,27 @@ impl State {
     pub fn from_cache(workflow: &Workflow) -> Result<State, Error> {
         let mut state = State {
             directory_cache: Self::read_directory_cache(workflow)?,
-            completed: Self::read_completed_cache(workflow)?,
+            completed: Self::read_completed_cache(&workflow.data_directory())?,
+            completion_hashes: Self::read_completion_hashes(&workflow.data_directory())?,
             submitted: Self::read_submitted_cache(workflow)?,
+            submitted_groups: Self::read_submitted_groups_cache(workflow)?,
+            jobs: Self::read_jobs_cache(workflow)?,
+            read_jobs: HashSet::new(),
             completed_file_names: Vec::new(),
+            newly_completed: Vec::new(),
             directories_modified: false,
             completed_modified: false,
             submitted_modified: false,
+            submitted_groups_modified: false,
+            jobs_modified: false,
         };
 
+        state.read_jobs = jobs_in(&state.submitted);
+
         // Ensure that completed has keys for all actions in the workflow.
         for action in &workflow.action {
             if !state.completed.contains_key(action.name()) {
-                state.completed.insert(action.name().into(), HashSet::new());
+                state.completed.insert(action.name().into(), HashMap::new());
             }
         }
 
@@ -190,7 +1217,7 @@ impl State {
 
     /// Read the directory cache from disk.
     fn read_directory_cache(workflow: &Workflow) -> Result<DirectoryCache, Error> {
-        let data_directory = workflow.root.join(DATA_DIRECTORY_NAME);
+        let data_directory = workflow.data_directory();
         let directory_file = data_directory.join(DIRECTORY_CACHE_FILE_NAME);
 
         match fs::read(&directory_file) {
@@ -198,7 +1225,11 @@ impl State {
                 debug!("Reading cache '{}'.", directory_file.display().to_string());
 
                 let result = serde_json::from_slice(&bytes)
-                    .map_err(|e| Error::JSONParse(directory_file, e))?;
+                    .map_err(|e| quarantine_cache(
+                            directory_file,
+                            &e,
+                            "Row will read the directory values from the workspace again when you run it.",
+                        ))?;
 
                 Ok(result)
             }
@@ -210,7 +1241,8 @@ impl State {
                     );
                     Ok(DirectoryCache {
                         modified_time: (0, 0),
-                        values: HashMap::new(),
+                        values: DirectoryValues::default(),
+                        pointers: None,
                     })
                 }
 
@@ -220,10 +1252,11 @@ impl State {
     }
 
     /// Read the completed directories cache from disk.
-    fn read_completed_cache(
-        workflow: &Workflow,
-    ) -> Result<HashMap<String, HashSet<PathBuf>>, Error> {
-        let data_directory = workflow.root.join(DATA_DIRECTORY_NAME);
+    ///
+    /// Upgrade the legacy cache (which stores only directory names) when the
+    /// current cache is not present.
+    ///
+    fn read_completed_cache(data_directory: &Path) -> Result<Completions, Error> {
         let completed_file = data_directory.join(COMPLETED_CACHE_FILE_NAME);
 
         match fs::read(&completed_file) {
@@ -231,16 +1264,22 @@ impl State {
                 debug!("Reading cache '{}'.", completed_file.display().to_string());
 
                 let result = postcard::from_bytes(&bytes)
-                    .map_err(|e| Error::PostcardParse(completed_file, e))?;
+                    .map_err(|e| quarantine_cache(
+                            completed_file,
+                            &e,
+                            "Row will rebuild the cache when you run it again. Then execute `row scan` to find completed directories.",
+                        ))?;
                 Ok(result)
             }
             Err(error) => match error.kind() {
                 io::ErrorKind::NotFound => {
                     trace!(
-                        "'{}' not found, initializing empty completions.",
+                        "'{}' not found, checking for a legacy cache.",
                         completed_file.display().to_string()
                     );
-                    Ok(HashMap::new())
+                    Self::read_legacy_completed(
+                        &data_directory.join(LEGACY_COMPLETED_CACHE_FILE_NAME),
+                    )
                 }
 
                 _ => Err(Error::FileRead(completed_file, error)),
@@ -248,9 +1287,165 @@ impl State {
         }
     }
 
+    /// Read the completion hashes from disk.
+    fn read_completion_hashes(data_directory: &Path) -> Result<CompletionHashes, Error> {
+        let hashes_file = data_directory.join(COMPLETION_HASHES_FILE_NAME);
+
+        match fs::read(&hashes_file) {
+            Ok(bytes) => {
+                debug!("Reading cache '{}'.", hashes_file.display().to_string());
+
+                let result =
+                    postcard::from_bytes(&bytes).map_err(|e| quarantine_cache(
+                            hashes_file,
+                            &e,
+                            "Row will record the products of each action again when you run it. Execute `row scan` if you changed the products of any action.",
+                        ))?;
+                Ok(result)
+            }
+            Err(error) => match error.kind() {
+                io::ErrorKind::NotFound => {
+                    trace!(
+                        "'{}' not found, initializing empty hashes.",
+                        hashes_file.display().to_string()
+                    );
+                    Ok(HashMap::new())
+                }
+
+                _ => Err(Error::FileRead(hashes_file, error)),
+            },
+        }
+    }
+
+    /// Read a completed cache or completion pack written by an earlier version of row.
+    fn read_legacy_completed(path: &Path) -> Result<Completions, Error> {
+        match fs::read(path) {
+            Ok(bytes) => {
+                debug!("Upgrading legacy cache '{}'.", path.display().to_string());
+
+                let legacy: HashMap<String, HashSet<PathBuf>> = postcard::from_bytes(&bytes)
+                    .map_err(|e| Error::PostcardParse(path.into(), e))?;
+                Ok(record_completions(legacy, &Completion::default()))
+            }
+            Err(error) => match error.kind() {
+                io::ErrorKind::NotFound => {
+                    trace!(
+                        "'{}' not found, initializing empty completions.",
+                        path.display().to_string()
+                    );
+                    Ok(HashMap::new())
+                }
+
+                _ => Err(Error::FileRead(path.into(), error)),
+            },
+        }
+    }
+
+    /// List the staged completion packs of the project with the given workflow.
+    ///
+    /// # Returns
+    /// The path to and contents of each pack, sorted by path.
+    ///
+    /// # Errors
+    /// Returns `Err<row::Error>` when a pack cannot be read or parsed.
+    ///
+    pub fn read_staged(workflow: &Workflow) -> Result<Vec<(PathBuf, CompletionPack)>, Error> {
+        let completed_path = workflow.data_directory().join(COMPLETED_DIRECTORY_NAME);
+        let entries = match completed_path.read_dir() {
+            Ok(entries) => entries,
+            Err(error) => match error.kind() {
+                io::ErrorKind::NotFound => return Ok(Vec::new()),
+                _ => return Err(Error::DirectoryRead(completed_path, error)),
+            },
+        };
+
+        let mut paths = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|e| Error::DirectoryRead(completed_path.clone(), e))?
+                .path();
+            if is_pack(&path) {
+                paths.push(path);
+            }
+        }
+        paths.sort_unstable();
+
+        paths
+            .into_iter()
+            .map(|path| {
+                let pack = CompletionPack::read(&path)?;
+                Ok((path, pack))
+            })
+            .collect()
+    }
+
+    /// Read the completed actions of the project with the given workflow.
+    ///
+    /// Combine the completed cache with the staged completion packs without
+    /// modifying either. Include pending completions whose products are present.
+    /// Use this to read the completions of another project.
+    ///
+    /// # Errors
+    /// Returns `Err<row::Error>` when the cache files cannot be read or parsed.
+    ///
+    pub fn read_completed(
+        workflow: &Workflow,
+        io_threads: u16,
+        multi_progress: &mut MultiProgressContainer,
+    ) -> Result<Completions, Error> {
+        let data_directory = workflow.data_directory();
+        let mut completed = Self::read_completed_cache(&data_directory)?;
+
+        let completed_path = data_directory.join(COMPLETED_DIRECTORY_NAME);
+        let entries = match completed_path.read_dir() {
+            Ok(entries) => entries,
+            Err(error) => match error.kind() {
+                io::ErrorKind::NotFound => return Ok(completed),
+                _ => return Err(Error::DirectoryRead(completed_path, error)),
+            },
+        };
+
+        let mut pending = Completions::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|e| Error::DirectoryRead(completed_path.clone(), e))?
+                .path();
+            if !is_pack(&path) {
+                continue;
+            }
+            let target = if is_pending_pack(&path) {
+                &mut pending
+            } else {
+                &mut completed
+            };
+            for (action_name, directories) in CompletionPack::read(&path)?.completions {
+                target.entry(action_name).or_default().extend(directories);
+            }
+        }
+
+        if !pending.is_empty() {
+            let known = Self::read_directory_cache(workflow)?.values;
+            let verified = verify_pending(
+                workflow,
+                pending,
+                |d| known.contains_key(d),
+                io_threads,
+                multi_progress,
+            )?;
+            for (action_name, directories) in verified {
+                completed
+                    .entry(action_name)
+                    .or_default()
+                    .extend(directories);
+            }
+        }
+
+        Ok(completed)
+    }
+
     /// Read the submitted job cache from disk.
     fn read_submitted_cache(workflow: &Workflow) -> Result<SubmittedJobs, Error> {
-        let data_directory = workflow.root.join(DATA_DIRECTORY_NAME);
+        let data_directory = workflow.data_directory();
         let submitted_file = data_directory.join(SUBMITTED_CACHE_FILE_NAME);
 
         match fs::read(&submitted_file) {
@@ -258,7 +1453,11 @@ impl State {
                 debug!("Reading cache '{}'.", submitted_file.display().to_string());
 
                 let result = postcard::from_bytes(&bytes)
-                    .map_err(|e| Error::PostcardParse(submitted_file, e))?;
+                    .map_err(|e| quarantine_cache(
+                            submitted_file,
+                            &e,
+                            "Row no longer knows which jobs it submitted. Wait for those jobs to finish before you submit the same directories again.",
+                        ))?;
                 Ok(result)
             }
             Err(error) => match error.kind() {
@@ -275,8 +1474,75 @@ impl State {
         }
     }
 
+    /// Read the submitted group cache from disk.
+    fn read_submitted_groups_cache(workflow: &Workflow) -> Result<SubmittedGroups, Error> {
+        let data_directory = workflow.data_directory();
+        let groups_file = data_directory.join(SUBMITTED_GROUPS_CACHE_FILE_NAME);
+
+        match fs::read(&groups_file) {
+            Ok(bytes) => {
+                debug!("Reading cache '{}'.", groups_file.display().to_string());
+
+                let result = postcard::from_bytes(&bytes).map_err(|e| {
+                    quarantine_cache(
+                        groups_file,
+                        &e,
+                        "Row will not warn when a new group splits a previously submitted group.",
+                    )
+                })?;
+                Ok(result)
+            }
+            Err(error) => match error.kind() {
+                io::ErrorKind::NotFound => {
+                    debug!(
+                        "'{}' not found, assuming no submitted groups.",
+                        groups_file.display().to_string()
+                    );
+                    Ok(HashMap::new())
+                }
+
+                _ => Err(Error::FileRead(groups_file, error)),
+            },
+        }
+    }
+
+    /// Read the job history cache from disk.
+    fn read_jobs_cache(workflow: &Workflow) -> Result<Vec<JobRecord>, Error> {
+        let data_directory = workflow.data_directory();
+        let jobs_file = data_directory.join(JOBS_CACHE_FILE_NAME);
+
+        match fs::read(&jobs_file) {
+            Ok(bytes) => {
+                debug!("Reading cache '{}'.", jobs_file.display().to_string());
+
+                let result = postcard::from_bytes(&bytes).map_err(|e| {
+                    quarantine_cache(
+                        jobs_file,
+                        &e,
+                        "The job history that `row show efficiency` reports is lost.",
+                    )
+                })?;
+                Ok(result)
+            }
+            Err(error) => match error.kind() {
+                io::ErrorKind::NotFound => {
+                    debug!(
+                        "'{}' not found, assuming no job history.",
+                        jobs_file.display().to_string()
+                    );
+                    Ok(Vec::new())
+                }
+
+                _ => Err(Error::FileRead(jobs_file, error)),
+            },
+        }
+    }
+
     /// Save the state cache to the filesystem.
     ///
+    /// Then execute the `on_action_complete` hook for the completions found
+    /// since the last save.
+    ///
     /// # Errors
     /// Returns `Err<row::Error>` when a cache file cannot be saved.
     ///
@@ -300,12 +1566,30 @@ impl State {
             self.submitted_modified = false;
         }
 
+        if self.submitted_groups_modified {
+            self.save_submitted_groups_cache(workflow)?;
+            self.submitted_groups_modified = false;
+        }
+
+        if self.jobs_modified {
+            self.save_jobs_cache(workflow)?;
+            self.jobs_modified = false;
+        }
+
+        // Execute the hooks after saving so that a slow or failing hook cannot
+        // leave the cache out of date.
+        run_completion_hooks(
+            workflow,
+            std::mem::take(&mut self.newly_completed),
+            multi_progress,
+        );
+
         Ok(())
     }
 
     /// Save the directory cache to the filesystem.
     fn save_directory_cache(&self, workflow: &Workflow) -> Result<(), Error> {
-        let data_directory = workflow.root.join(DATA_DIRECTORY_NAME);
+        let data_directory = workflow.data_directory();
         let directory_cache_file = data_directory.join(DIRECTORY_CACHE_FILE_NAME);
 
         debug!(
@@ -318,10 +1602,7 @@ impl State {
 
         fs::create_dir_all(&data_directory)
             .map_err(|e| Error::DirectoryCreate(data_directory, e))?;
-        fs::write(&directory_cache_file, out_bytes)
-            .map_err(|e| Error::FileWrite(directory_cache_file.clone(), e))?;
-
-        Ok(())
+        write_cache(&directory_cache_file, &out_bytes)
     }
 
     /// Save the completed cache to the filesystem.
@@ -330,7 +1611,7 @@ impl State {
         workflow: &Workflow,
         multi_progress: &mut MultiProgressContainer,
     ) -> Result<(), Error> {
-        let data_directory = workflow.root.join(DATA_DIRECTORY_NAME);
+        let data_directory = workflow.data_directory();
         let completed_file = data_directory.join(COMPLETED_CACHE_FILE_NAME);
 
         debug!(
@@ -342,13 +1623,20 @@ impl State {
         let out_bytes: Vec<u8> = postcard::to_stdvec(&self.completed)
             .map_err(|e| Error::PostcardSerialize(completed_file.clone(), e))?;
 
-        let mut file = File::create(&completed_file)
-            .map_err(|e| Error::FileWrite(completed_file.clone(), e))?;
-        file.write_all(&out_bytes)
-            .map_err(|e| Error::FileWrite(completed_file.clone(), e))?;
-        file.sync_all()
-            .map_err(|e| Error::FileWrite(completed_file.clone(), e))?;
-        drop(file);
+        write_cache(&completed_file, &out_bytes)?;
+
+        let hashes_file = data_directory.join(COMPLETION_HASHES_FILE_NAME);
+        let out_bytes: Vec<u8> = postcard::to_stdvec(&self.completion_hashes)
+            .map_err(|e| Error::PostcardSerialize(hashes_file.clone(), e))?;
+        write_cache(&hashes_file, &out_bytes)?;
+
+        // The upgraded cache replaces the legacy cache.
+        let legacy_file = data_directory.join(LEGACY_COMPLETED_CACHE_FILE_NAME);
+        if let Err(error) = fs::remove_file(&legacy_file) {
+            if error.kind() != io::ErrorKind::NotFound {
+                return Err(Error::FileRemove(legacy_file, error));
+            }
+        }
 
         // Then remove the staged files.
         let mut progress = ProgressBar::new(self.completed_file_names.len() as u64)
@@ -373,7 +1661,7 @@ impl State {
 
     /// Save the completed cache to the filesystem.
     fn save_submitted_cache(&mut self, workflow: &Workflow) -> Result<(), Error> {
-        let data_directory = workflow.root.join(DATA_DIRECTORY_NAME);
+        let data_directory = workflow.data_directory();
         let submitted_file = data_directory.join(SUBMITTED_CACHE_FILE_NAME);
 
         debug!(
@@ -384,17 +1672,40 @@ impl State {
         let out_bytes: Vec<u8> = postcard::to_stdvec(&self.submitted)
             .map_err(|e| Error::PostcardSerialize(submitted_file.clone(), e))?;
 
-        let mut file = File::create(&submitted_file)
-            .map_err(|e| Error::FileWrite(submitted_file.clone(), e))?;
-        file.write_all(&out_bytes)
-            .map_err(|e| Error::FileWrite(submitted_file.clone(), e))?;
-        file.sync_all()
-            .map_err(|e| Error::FileWrite(submitted_file.clone(), e))?;
-        drop(file);
-
+        write_cache(&submitted_file, &out_bytes)?;
+        self.read_jobs = jobs_in(&self.submitted);
         Ok(())
     }
 
+    /// Save the submitted group cache to the filesystem.
+    fn save_submitted_groups_cache(&mut self, workflow: &Workflow) -> Result<(), Error> {
+        let data_directory = workflow.data_directory();
+        let groups_file = data_directory.join(SUBMITTED_GROUPS_CACHE_FILE_NAME);
+
+        debug!(
+            "Saving submitted group cache: '{}'.",
+            groups_file.display().to_string()
+        );
+
+        let out_bytes: Vec<u8> = postcard::to_stdvec(&self.submitted_groups)
+            .map_err(|e| Error::PostcardSerialize(groups_file.clone(), e))?;
+
+        write_cache(&groups_file, &out_bytes)
+    }
+
+    /// Save the job history cache to the filesystem.
+    fn save_jobs_cache(&mut self, workflow: &Workflow) -> Result<(), Error> {
+        let data_directory = workflow.data_directory();
+        let jobs_file = data_directory.join(JOBS_CACHE_FILE_NAME);
+
+        debug!("Saving job history: '{}'.", jobs_file.display().to_string());
+
+        let out_bytes: Vec<u8> = postcard::to_stdvec(&self.jobs)
+            .map_err(|e| Error::PostcardSerialize(jobs_file.clone(), e))?;
+
+        write_cache(&jobs_file, &out_bytes)
+    }
+
     /// Synchronize a workspace on disk with a `State`.
     ///
     /// * Remove directories from the state that are no longer present on the filesystem.
@@ -412,6 +1723,7 @@ impl State {
     pub(crate) fn synchronize_workspace(
         &mut self,
         workflow: &Workflow,
+        cluster_name: &str,
         io_threads: u16,
         multi_progress: &mut MultiProgressContainer,
     ) -> Result<&Self, Error> {
@@ -422,18 +1734,20 @@ impl State {
         let mut directories_to_add = Vec::new();
 
         // Check if the workspace directory has been modified since we last updated the cache.
-        let metadata = fs::metadata(workspace_path.clone())
-            .map_err(|e| Error::DirectoryRead(workspace_path.clone(), e))?;
-        let current_modified_time = (metadata.mtime(), metadata.mtime_nsec());
-        if current_modified_time == self.directory_cache.modified_time {
+        // Workspaces without a modification time are always listed.
+        let current_modified_time = workspace::modified_time(workflow)?;
+        if current_modified_time == Some(self.directory_cache.modified_time) {
             trace!("The workspace has not been modified.");
         } else {
             trace!("The workspace has been modified, updating the cache.");
             self.directories_modified = true;
-            self.directory_cache.modified_time = current_modified_time;
+            if let Some(modified_time) = current_modified_time {
+                self.directory_cache.modified_time = modified_time;
+            }
 
-            let filesystem_directories: HashSet<PathBuf> =
-                HashSet::from_iter(workspace::list_directories(workflow, multi_progress)?);
+            let filesystem_directories: HashSet<PathBuf> = HashSet::from_iter(
+                workspace::list_directories(workflow, io_threads, multi_progress)?,
+            );
 
             ////////////////////////////////////////////////
             // First, synchronize the values.
@@ -443,19 +1757,22 @@ impl State {
                 .values
                 .keys()
                 .filter(|&x| !filesystem_directories.contains(x))
-                .cloned()
+                .map(Path::to_path_buf)
                 .collect();
 
+            // Then remove them.
             if directories_to_remove.is_empty() {
                 trace!("No directories to remove from the directory cache.");
-            }
-            // Then remove them.
-            for directory in directories_to_remove {
-                trace!(
-                    "Removing '{}' from the directory cache",
-                    directory.display()
-                );
-                self.directory_cache.values.remove(&directory);
+            } else {
+                for directory in &directories_to_remove {
+                    trace!(
+                        "Removing '{}' from the directory cache",
+                        directory.display()
+                    );
+                }
+                self.directory_cache
+                    .values
+                    .retain(|d| filesystem_directories.contains(d));
             }
 
             // Make a copy of the directories to be added.
@@ -475,53 +1792,363 @@ impl State {
             }
         }
 
-        // Read value files from the directories.
-        let directory_values = workspace::read_values(
-            workflow,
-            directories_to_add.clone(),
-            io_threads,
-            multi_progress,
-        );
+        let rescanned = self.rescan_changed_actions(workflow, io_threads, multi_progress)?;
+
+        // Read all values again when the workflow references different pointers.
+        let mut directories_to_read = directories_to_add.clone();
+        let pointers = workflow.value_pointers();
+        if pointers != self.directory_cache.pointers {
+            debug!("The referenced JSON pointers changed, reading all values.");
+            self.directories_modified = true;
+            self.directory_cache.pointers = pointers;
+            directories_to_read.extend(self.directory_cache.values.keys().map(Path::to_path_buf));
+        }
+
+        // Read value files from the directories.
+        let directory_values = workspace::read_values(
+            workflow,
+            directories_to_read,
+            self.directory_cache.pointers.as_deref(),
+            io_threads,
+            multi_progress,
+        )?;
+
+        ///////////////////////////////////////////
+        // Synchronize completed with the disk.
+
+        // Determine which of the new actions are completed.
+        let new_complete = workspace::find_completed_directories(
+            workflow,
+            directories_to_add,
+            io_threads,
+            multi_progress,
+        )?;
+
+        let (mut newly_completed, pending) =
+            self.synchronize_completion_files(workflow, cluster_name, multi_progress)?;
+
+        ///////////////////////////////////////////
+        // Wait for launched threads to finish and merge results.
+        self.directory_cache
+            .values
+            .try_extend(directory_values.get()?)?;
+
+        let new_complete = new_complete.get()?;
+        if !new_complete.is_empty() {
+            self.completed_modified = true;
+        }
+
+        newly_completed.extend(self.insert_staged_completed(record_completions(
+            new_complete,
+            &Completion::now(cluster_name),
+        )));
+        newly_completed.extend(self.insert_pending_completed(
+            workflow,
+            pending,
+            io_threads,
+            multi_progress,
+        )?);
+
+        if let Some((changed_actions, rescanned)) = rescanned {
+            self.insert_rescanned_completed(changed_actions, &rescanned.get()?, cluster_name);
+        }
+
+        self.remove_missing_completed(workflow);
+        self.remove_missing_submitted(workflow);
+
+        self.newly_completed.extend(newly_completed);
+
+        Ok(self)
+    }
+
+    /// Rebuild the caches from the workspace.
+    ///
+    /// Unlike `synchronize_workspace`, `repair` does not trust the cache. It:
+    /// * Reads the value of every directory in the workspace.
+    /// * Removes completed and submitted entries that refer to actions or
+    ///   directories that are no longer present.
+    /// * Checks the products of every directory. Removes completions whose
+    ///   products are missing and adds completions whose products are present.
+    ///
+    /// # Errors
+    ///
+    /// * Returns `Error<row::Error>` when there is an I/O error reading the
+    ///   workspace.
+    ///
+    pub fn repair(
+        &mut self,
+        workflow: &Workflow,
+        cluster_name: &str,
+        io_threads: u16,
+        multi_progress: &mut MultiProgressContainer,
+    ) -> Result<Repairs, Error> {
+        debug!("Repairing the cache.");
+        let mut repairs = Repairs::default();
+
+        let current_modified_time = workspace::modified_time(workflow)?;
+        let directories = workspace::list_directories(workflow, io_threads, multi_progress)?;
+        let pointers = workflow.value_pointers();
+
+        let directory_values = workspace::read_values(
+            workflow,
+            directories.clone(),
+            pointers.as_deref(),
+            io_threads,
+            multi_progress,
+        )?;
+        let completed_directories = workspace::find_completed_directories(
+            workflow,
+            directories,
+            io_threads,
+            multi_progress,
+        )?;
+
+        self.synchronize_completion_files(workflow, cluster_name, multi_progress)?;
+
+        let values = directory_values.get()?;
+        let completed_directories = completed_directories.get()?;
+
+        ///////////////////////////////////////////
+        // Rebuild the directory cache.
+        for (directory, value) in &values {
+            match self.directory_cache.values.get(directory) {
+                None => repairs.directories_added.push(directory.clone()),
+                Some(cached) if cached != value => repairs.values_changed.push(directory.clone()),
+                Some(_) => (),
+            }
+        }
+        repairs.directories_removed = self
+            .directory_cache
+            .values
+            .keys()
+            .filter(|d| !values.contains_key(*d))
+            .map(Path::to_path_buf)
+            .collect();
+
+        repairs.directories_added.sort_unstable();
+        repairs.directories_removed.sort_unstable();
+        repairs.values_changed.sort_unstable();
+
+        if let Some(modified_time) = current_modified_time {
+            self.directory_cache.modified_time = modified_time;
+        }
+        self.directory_cache.values = DirectoryValues::try_from_iter(values)?;
+        self.directory_cache.pointers = pointers;
+        self.directories_modified = true;
+
+        ///////////////////////////////////////////
+        // Prune orphaned entries.
+        let completed_count = self.completed.values().map(HashMap::len).sum::<usize>();
+        let submitted_count = self.submitted.values().map(HashMap::len).sum::<usize>();
+        self.remove_missing_completed(workflow);
+        self.remove_missing_submitted(workflow);
+        repairs.orphaned_completed =
+            completed_count - self.completed.values().map(HashMap::len).sum::<usize>();
+        repairs.orphaned_submitted =
+            submitted_count - self.submitted.values().map(HashMap::len).sum::<usize>();
 
         ///////////////////////////////////////////
-        // Synchronize completed with the disk.
+        // Recompute completion from the products.
+        let completion = Completion::now(cluster_name);
+        for action in &workflow.action {
+            if action.products().is_empty() {
+                continue;
+            }
 
-        // Determine which of the new actions are completed.
-        let new_complete = workspace::find_completed_directories(
-            workflow,
-            directories_to_add,
+            let (removed, added) = self.replace_completed(
+                action.name(),
+                completed_directories.get(action.name()),
+                &completion,
+            );
+            repairs
+                .completions_removed
+                .extend(removed.into_iter().map(|d| (action.name().to_string(), d)));
+            repairs
+                .completions_added
+                .extend(added.into_iter().map(|d| (action.name().to_string(), d)));
+        }
+        self.completion_hashes = completion_hashes(workflow);
+        self.completed_modified = true;
+
+        Ok(repairs)
+    }
+
+    /// Scan the cached directories again for actions with changed completion hashes.
+    ///
+    /// Directories added to the workspace are scanned separately for all actions.
+    ///
+    /// # Returns
+    /// `Ok(None)` when no actions changed. Otherwise, `Ok(Some((changed_actions, completed)))`.
+    ///
+    fn rescan_changed_actions(
+        &mut self,
+        workflow: &Workflow,
+        io_threads: u16,
+        multi_progress: &mut MultiProgressContainer,
+    ) -> Result<Option<(HashSet<String>, workspace::CompletedDirectories)>, Error> {
+        let changed_actions = self.update_completion_hashes(workflow);
+        if changed_actions.is_empty() {
+            return Ok(None);
+        }
+
+        let mut changed_workflow = workflow.clone();
+        changed_workflow
+            .action
+            .retain(|a| changed_actions.contains(a.name()));
+        let completed = workspace::find_completed_directories(
+            &changed_workflow,
+            self.directory_cache
+                .values
+                .keys()
+                .map(Path::to_path_buf)
+                .collect(),
             io_threads,
             multi_progress,
-        );
+        )?;
 
-        self.synchronize_completion_files(workflow, multi_progress)?;
+        Ok(Some((changed_actions, completed)))
+    }
 
-        ///////////////////////////////////////////
-        // Wait for launched threads to finish and merge results.
-        self.directory_cache.values.extend(directory_values.get()?);
+    /// Replace the completions of the changed actions with the rescanned completions.
+    fn insert_rescanned_completed(
+        &mut self,
+        changed_actions: HashSet<String>,
+        rescanned: &HashMap<String, HashSet<PathBuf>>,
+        cluster_name: &str,
+    ) {
+        let completion = Completion::now(cluster_name);
+        let mut changed_actions: Vec<String> = changed_actions.into_iter().collect();
+        changed_actions.sort_unstable();
+        for action_name in changed_actions {
+            let (removed, added) =
+                self.replace_completed(&action_name, rescanned.get(&action_name), &completion);
+            warn!(
+                "The products or previous actions of '{action_name}' changed. Rescanned the \
+                 workspace: {} newly completed, {} no longer completed.",
+                added.len(),
+                removed.len()
+            );
+        }
+    }
 
-        let new_complete = new_complete.get()?;
-        if !new_complete.is_empty() {
+    /// Replace the completions of an action with the directories found on disk.
+    ///
+    /// Directories that remain complete keep their original completion record.
+    ///
+    /// # Returns
+    /// The (removed, added) directories in sorted order.
+    ///
+    fn replace_completed(
+        &mut self,
+        action_name: &str,
+        on_disk: Option<&HashSet<PathBuf>>,
+        completion: &Completion,
+    ) -> (Vec<PathBuf>, Vec<PathBuf>) {
+        let cached = self.completed.entry(action_name.into()).or_default();
+
+        let mut removed: Vec<PathBuf> = cached
+            .keys()
+            .filter(|d| !on_disk.is_some_and(|s| s.contains(*d)))
+            .cloned()
+            .collect();
+        removed.sort_unstable();
+        for directory in &removed {
+            cached.remove(directory);
+        }
+
+        let mut added: Vec<PathBuf> = on_disk
+            .into_iter()
+            .flatten()
+            .filter(|d| !cached.contains_key(*d))
+            .cloned()
+            .collect();
+        added.sort_unstable();
+        for directory in &added {
+            cached.insert(directory.clone(), completion.clone());
+        }
+
+        if !removed.is_empty() || !added.is_empty() {
             self.completed_modified = true;
         }
 
-        self.insert_staged_completed(new_complete);
-        self.remove_missing_completed(workflow);
-        self.remove_missing_submitted(workflow);
+        (removed, added)
+    }
 
-        Ok(self)
+    /// Record the completion hash of each action in the workflow.
+    ///
+    /// # Returns
+    /// The names of the actions with products whose hash differs from the cached
+    /// hash. Actions without a cached hash (such as new actions) are not included.
+    ///
+    fn update_completion_hashes(&mut self, workflow: &Workflow) -> HashSet<String> {
+        let hashes = completion_hashes(workflow);
+        if hashes == self.completion_hashes {
+            return HashSet::new();
+        }
+
+        let changed = workflow
+            .action
+            .iter()
+            .filter(|a| {
+                !a.products().is_empty()
+                    && self
+                        .completion_hashes
+                        .get(a.name())
+                        .is_some_and(|h| *h != hashes[a.name()])
+            })
+            .map(|a| a.name().to_string())
+            .collect();
+
+        self.completion_hashes = hashes;
+        self.completed_modified = true;
+        changed
     }
 
     /// Insert new completions.
-    fn insert_staged_completed(&mut self, new_complete: HashMap<String, HashSet<PathBuf>>) {
+    ///
+    /// Directories that are already complete keep their original completion record.
+    ///
+    /// # Returns
+    /// The action names and directories that were not previously complete.
+    ///
+    fn insert_staged_completed(&mut self, new_complete: Completions) -> Vec<(String, PathBuf)> {
+        let mut inserted = Vec::new();
         for (action_name, new_completed_directories) in new_complete {
-            if let Some(completed_directories) = self.completed.get_mut(&action_name) {
-                completed_directories.extend(new_completed_directories);
-            } else {
-                self.completed
-                    .insert(action_name, new_completed_directories);
+            let completed_directories = self.completed.entry(action_name.clone()).or_default();
+            for (directory, completion) in new_completed_directories {
+                if let hash_map::Entry::Vacant(entry) = completed_directories.entry(directory) {
+                    inserted.push((action_name.clone(), entry.key().clone()));
+                    entry.insert(completion);
+                }
             }
         }
+        inserted
+    }
+
+    /// Insert the pending completions whose products are present.
+    ///
+    /// `row scan --pack-only` stages pending completions without checking the
+    /// products. Check the products of the pending directories now.
+    ///
+    /// # Returns
+    /// The action names and directories that were not previously complete.
+    ///
+    fn insert_pending_completed(
+        &mut self,
+        workflow: &Workflow,
+        pending: Completions,
+        io_threads: u16,
+        multi_progress: &mut MultiProgressContainer,
+    ) -> Result<Vec<(String, PathBuf)>, Error> {
+        let verified = verify_pending(
+            workflow,
+            pending,
+            |d| self.directory_cache.values.contains_key(d),
+            io_threads,
+            multi_progress,
+        )?;
+
+        Ok(self.insert_staged_completed(verified))
     }
 
     /// Remove missing completed actions and directories.
@@ -544,8 +2171,8 @@ impl State {
 
         for directories in self.completed.values_mut() {
             let directories_to_remove: Vec<PathBuf> = directories
-                .iter()
-                .filter(|d| !self.directory_cache.values.contains_key(*d))
+                .keys()
+                .filter(|d| !self.directory_cache.values.contains_key(d))
                 .cloned()
                 .collect();
 
@@ -578,7 +2205,7 @@ impl State {
         for directory_map in self.submitted.values_mut() {
             let directories_to_remove: Vec<PathBuf> = directory_map
                 .keys()
-                .filter(|d| !self.directory_cache.values.contains_key(*d))
+                .filter(|d| !self.directory_cache.values.contains_key(d))
                 .cloned()
                 .collect();
 
@@ -591,18 +2218,50 @@ impl State {
 
         // Note: A separate method takes care of removing submitted job IDs that are
         // no longer submitted.
+
+        // Keep submitted groups after their jobs finish to detect later splits.
+        // Remove only the groups that no longer exist in the workflow and workspace.
+        let group_count = self
+            .submitted_groups
+            .values()
+            .map(HashMap::len)
+            .sum::<usize>();
+        self.submitted_groups
+            .retain(|action_name, _| current_actions.contains(action_name));
+        for groups in self.submitted_groups.values_mut() {
+            groups.retain(|_, members| {
+                members
+                    .iter()
+                    .any(|d| self.directory_cache.values.contains_key(d))
+            });
+        }
+        if self
+            .submitted_groups
+            .values()
+            .map(HashMap::len)
+            .sum::<usize>()
+            != group_count
+        {
+            self.submitted_groups_modified = true;
+        }
     }
 
     /// Synchronize with completion files on the filesystem.
+    ///
+    /// Also record when the jobs in the job history that wrote the files
+    /// started and finished.
+    ///
+    /// # Returns
+    /// The action names and directories that were not previously complete and
+    /// the pending completions (which the caller must verify).
+    ///
     fn synchronize_completion_files(
         &mut self,
         workflow: &Workflow,
+        cluster_name: &str,
         multi_progress: &mut MultiProgressContainer,
-    ) -> Result<(), Error> {
-        let completed_path = workflow
-            .root
-            .join(DATA_DIRECTORY_NAME)
-            .join(COMPLETED_DIRECTORY_NAME);
+    ) -> Result<(Vec<(String, PathBuf)>, Completions), Error> {
+        let completed_path = workflow.data_directory().join(COMPLETED_DIRECTORY_NAME);
         debug!(
             "Reading completed files in '{}'.",
             completed_path.display().to_string()
@@ -615,16 +2274,14 @@ impl State {
                         entry.map_err(|e| Error::DirectoryRead(completed_path.clone(), e))?;
                     let path = entry.path();
 
-                    if let Some(extension) = path.extension() {
-                        if extension == "postcard" {
-                            trace!("Reading '{}'", path.display().to_string());
-                            self.completed_file_names.push(path);
-                        } else {
-                            trace!(
-                                "Ignoring non-postcard file '{}'",
-                                path.display().to_string()
-                            );
-                        }
+                    if is_pack(&path) {
+                        trace!("Reading '{}'", path.display().to_string());
+                        self.completed_file_names.push(path);
+                    } else if path.extension().is_some() {
+                        trace!(
+                            "Ignoring non-postcard file '{}'",
+                            path.display().to_string()
+                        );
                     }
                 }
             }
@@ -632,7 +2289,7 @@ impl State {
             Err(error) => match error.kind() {
                 io::ErrorKind::NotFound => {
                     trace!("'{}' not found.", completed_path.display().to_string());
-                    return Ok(());
+                    return Ok((Vec::new(), Completions::new()));
                 }
 
                 _ => return Err(Error::DirectoryRead(completed_path, error)),
@@ -640,7 +2297,7 @@ impl State {
         };
 
         if self.completed_file_names.is_empty() {
-            return Ok(());
+            return Ok((Vec::new(), Completions::new()));
         }
 
         self.completed_modified = true;
@@ -654,28 +2311,153 @@ impl State {
         progress.set_style(progress_styles::counted_bar());
         progress.tick();
 
-        for completed_file_name in &self.completed_file_names {
+        let mut newly_completed = Vec::new();
+        let mut pending = Completions::new();
+        let completed_file_names = self.completed_file_names.clone();
+        for completed_file_name in &completed_file_names {
             trace!("Reading '{}'.", completed_file_name.display().to_string());
-            let bytes = fs::read(completed_file_name)
-                .map_err(|e| Error::FileRead(completed_file_name.clone(), e))?;
-            let new_complete: HashMap<String, HashSet<PathBuf>> = postcard::from_bytes(&bytes)
-                .map_err(|e| Error::PostcardParse(completed_file_name.clone(), e))?;
-
-            for (action_name, new_completed_directories) in new_complete {
-                if let Some(completed_directories) = self.completed.get_mut(&action_name) {
-                    completed_directories.extend(new_completed_directories);
-                } else {
-                    self.completed
-                        .insert(action_name, new_completed_directories);
+            let pack = CompletionPack::read(completed_file_name)?;
+            self.record_job_times(&pack, cluster_name);
+            let new_complete = pack.completions;
+            if is_pending_pack(completed_file_name) {
+                for (action_name, directories) in new_complete {
+                    pending.entry(action_name).or_default().extend(directories);
                 }
+            } else {
+                newly_completed.extend(self.insert_staged_completed(new_complete));
             }
-
             progress.inc(1);
         }
 
         progress.finish();
-        Ok(())
+        Ok((newly_completed, pending))
+    }
+}
+
+/// Keep the pending completions whose products are present.
+///
+/// Only check the directories in the workspace (those for which `is_known`
+/// returns true).
+///
+fn verify_pending<F>(
+    workflow: &Workflow,
+    pending: Completions,
+    is_known: F,
+    io_threads: u16,
+    multi_progress: &mut MultiProgressContainer,
+) -> Result<Completions, Error>
+where
+    F: Fn(&PathBuf) -> bool,
+{
+    let directories: HashSet<PathBuf> = pending
+        .values()
+        .flat_map(HashMap::keys)
+        .filter(|d| is_known(d))
+        .cloned()
+        .collect();
+    if directories.is_empty() {
+        return Ok(Completions::new());
+    }
+
+    debug!(
+        "Checking products of {} pending directories.",
+        directories.len()
+    );
+    let complete = workspace::find_completed_directories(
+        workflow,
+        directories.into_iter().collect(),
+        io_threads,
+        multi_progress,
+    )?
+    .get()?;
+
+    let mut verified = Completions::new();
+    for (action_name, directories) in pending {
+        let Some(complete_directories) = complete.get(&action_name) else {
+            continue;
+        };
+        verified.insert(
+            action_name,
+            directories
+                .into_iter()
+                .filter(|(d, _)| complete_directories.contains(d))
+                .collect(),
+        );
+    }
+
+    Ok(verified)
+}
+
+/// Execute the `on_action_complete` hook for each newly completed directory.
+///
+/// Hooks execute in parallel in the project root. **Row** collects the output of
+/// each hook and writes it to stderr when the hook exits so that the output of
+/// different hooks does not mix. Failed hooks produce warnings.
+///
+fn run_completion_hooks(
+    workflow: &Workflow,
+    mut newly_completed: Vec<(String, PathBuf)>,
+    multi_progress: &MultiProgressContainer,
+) {
+    let Some(hook) = &workflow.hooks.on_action_complete else {
+        return;
+    };
+
+    // Skip completions of actions that are no longer in the workflow.
+    newly_completed.retain(|(action_name, _)| workflow.action_by_name(action_name).is_some());
+    if newly_completed.is_empty() {
+        return;
     }
+    newly_completed.sort_unstable();
+    newly_completed.dedup();
+
+    let command = hook
+        .replace("{action}", "$ACTION_NAME")
+        .replace("{directory}", "$ACTION_DIRECTORY");
+    debug!(
+        "Executing the on_action_complete hook for {} completions.",
+        newly_completed.len()
+    );
+
+    multi_progress.suspend(|| {
+        newly_completed
+            .par_iter()
+            .for_each(|(action_name, directory)| {
+                trace!(
+                    "Executing '{hook}' for action '{action_name}' on '{}'.",
+                    directory.display()
+                );
+                let output = Command::new("sh")
+                    .arg("-c")
+                    .arg(&command)
+                    .current_dir(&workflow.root)
+                    .env("ACTION_NAME", action_name)
+                    .env("ACTION_DIRECTORY", directory)
+                    .stdin(Stdio::null())
+                    .output();
+
+                match output {
+                    Ok(output) => {
+                        let mut stderr = io::stderr().lock();
+                        let _ = stderr.write_all(&output.stdout);
+                        let _ = stderr.write_all(&output.stderr);
+                        drop(stderr);
+
+                        if !output.status.success() {
+                            warn!(
+                                "The on_action_complete hook '{hook}' failed ({}) for action \
+                             '{action_name}' on '{}'.",
+                                output.status,
+                                directory.display()
+                            );
+                        }
+                    }
+                    Err(error) => {
+                        warn!("Unable to execute the on_action_complete hook '{hook}': {error}.");
+                    }
+                }
+            });
+    });
 }
 
 #[cfg(test)]
@@ -683,9 +2465,11 @@ mod tests {
     use assert_fs::prelude::*;
     use assert_fs::TempDir;
     use indicatif::{MultiProgress, ProgressDrawTarget};
+    use serde_json::Value;
     use serial_test::parallel;
 
     use super::*;
+    use crate::DATA_DIRECTORY_NAME;
 
     fn setup() -> MultiProgressContainer {
         let _ = env_logger::builder()
@@ -710,7 +2494,7 @@ mod tests {
         let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
 
         let mut state = State::default();
-        let result = state.synchronize_workspace(&workflow, 2, &mut multi_progress);
+        let result = state.synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress);
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -729,7 +2513,7 @@ mod tests {
         let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
 
         let mut state = State::default();
-        let result = state.synchronize_workspace(&workflow, 2, &mut multi_progress);
+        let result = state.synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress);
         assert!(result.is_ok());
         assert_eq!(state.values().len(), 0);
     }
@@ -759,9 +2543,9 @@ mod tests {
         state
             .directory_cache
             .values
-            .insert(PathBuf::from("dir4"), Value::Null);
+            .insert(Path::new("dir4"), &Value::Null);
 
-        let result = state.synchronize_workspace(&workflow, 2, &mut multi_progress);
+        let result = state.synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress);
         assert!(result.is_ok());
 
         assert_eq!(state.values().len(), 3);
@@ -788,11 +2572,64 @@ mod tests {
 
         let mut state = State::default();
 
-        let result = state.synchronize_workspace(&workflow, 2, &mut multi_progress);
+        let result = state.synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress);
         assert!(result.is_ok());
         assert_eq!(state.values().len(), 1);
         assert!(state.values().contains_key(&PathBuf::from("dir1")));
-        assert_eq!(state.values()[&PathBuf::from("dir1")].as_i64(), Some(10));
+        assert_eq!(
+            state.values().get(&PathBuf::from("dir1")).unwrap().as_i64(),
+            Some(10)
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn value_referenced() {
+        let mut multi_progress = setup();
+
+        let temp = TempDir::new().unwrap();
+        let dir1 = temp.child("workspace").child("dir1");
+        dir1.create_dir_all().unwrap();
+        dir1.child("v.json")
+            .write_str(r#"{"a": 1, "b": [1, 2, 3]}"#)
+            .unwrap();
+
+        let workflow = r#"
+[workspace]
+value_file = "v.json"
+value_cache = "referenced"
+
+[[action]]
+name = "one"
+command = "c"
+group.sort_by = ["/a"]
+"#;
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        let mut state = State::default();
+        state
+            .synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress)
+            .unwrap();
+        assert_eq!(state.value_pointers(), Some(&["/a".to_string()][..]));
+        assert_eq!(
+            state.values().get(&PathBuf::from("dir1")).unwrap(),
+            &serde_json::json!({"a": 1})
+        );
+
+        // Read the complete values when the workflow caches all values.
+        let workflow = r#"
+[workspace]
+value_file = "v.json"
+"#;
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        state
+            .synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress)
+            .unwrap();
+        assert_eq!(state.value_pointers(), None);
+        assert_eq!(
+            state.values().get(&PathBuf::from("dir1")).unwrap(),
+            &serde_json::json!({"a": 1, "b": [1, 2, 3]})
+        );
     }
 
     fn setup_completion_directories(temp: &TempDir, n: usize) -> String {
@@ -827,43 +2664,377 @@ products = ["g"]
 
     #[test]
     #[parallel]
-    fn new_completeions_and_cache() {
+    fn new_completeions_and_cache() {
+        let mut multi_progress = setup();
+
+        let temp = TempDir::new().unwrap();
+        let n = 10;
+
+        let workflow = setup_completion_directories(&temp, n);
+        let workflow = Workflow::open_str(temp.path(), &workflow).unwrap();
+
+        let mut state = State::default();
+        let result = state.synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress);
+        assert!(result.is_ok());
+
+        assert_eq!(state.values().len(), n);
+        assert!(state.completed.contains_key("b"));
+        assert!(state.completed.contains_key("e"));
+        for i in 0..n {
+            let directory = PathBuf::from(format!("dir{i}"));
+            #[allow(clippy::cast_sign_loss)]
+            let value = state.values().get(&directory).unwrap().as_i64().unwrap() as usize;
+            assert_eq!(value, i);
+
+            if i < n / 2 {
+                assert!(state.completed["b"].contains_key(&directory));
+                assert!(!state.completed["e"].contains_key(&directory));
+            } else {
+                assert!(!state.completed["b"].contains_key(&directory));
+                assert!(state.completed["e"].contains_key(&directory));
+            }
+        }
+
+        let completion = &state.completed["b"][&PathBuf::from("dir0")];
+        assert_eq!(completion.cluster, Some("cluster".into()));
+        assert!(completion.time.is_some());
+
+        state
+            .save_cache(&workflow, &mut multi_progress)
+            .expect("Cache saved.");
+
+        let cached_state = State::from_cache(&workflow).expect("Read state from cache");
+        assert_eq!(state, cached_state);
+    }
+
+    #[test]
+    #[parallel]
+    fn upgrade_legacy_completed() {
+        let mut multi_progress = setup();
+
+        let temp = TempDir::new().unwrap();
+        let n = 4;
+
+        let workflow = setup_completion_directories(&temp, n);
+        let workflow = Workflow::open_str(temp.path(), &workflow).unwrap();
+
+        let legacy: HashMap<String, HashSet<PathBuf>> =
+            HashMap::from([("b".into(), HashSet::from([PathBuf::from("dir0")]))]);
+        let data_directory = temp.child(DATA_DIRECTORY_NAME);
+        data_directory
+            .child(LEGACY_COMPLETED_CACHE_FILE_NAME)
+            .write_binary(&postcard::to_stdvec(&legacy).unwrap())
+            .unwrap();
+
+        let legacy_pack: HashMap<String, HashSet<PathBuf>> =
+            HashMap::from([("e".into(), HashSet::from([PathBuf::from("dir1")]))]);
+        data_directory
+            .child(COMPLETED_DIRECTORY_NAME)
+            .child("pack.postcard")
+            .write_binary(&postcard::to_stdvec(&legacy_pack).unwrap())
+            .unwrap();
+
+        let pack: Completions = HashMap::from([(
+            "e".into(),
+            HashMap::from([(PathBuf::from("dir2"), Completion::now("other"))]),
+        )]);
+        data_directory
+            .child(COMPLETED_DIRECTORY_NAME)
+            .child(format!("pack.{V2_COMPLETED_PACK_EXTENSION}"))
+            .write_binary(&postcard::to_stdvec(&pack).unwrap())
+            .unwrap();
+
+        let mut state = State::from_cache(&workflow).expect("Read state from cache");
+        assert_eq!(
+            state.completed["b"][&PathBuf::from("dir0")],
+            Completion::default()
+        );
+
+        state
+            .directory_cache
+            .values
+            .try_extend((0..n).map(|i| (PathBuf::from(format!("dir{i}")), Value::Null)))
+            .unwrap();
+        let result = state.synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress);
+        assert!(result.is_ok());
+        assert_eq!(
+            state.completed["e"][&PathBuf::from("dir1")],
+            Completion::default()
+        );
+        assert_eq!(
+            state.completed["e"][&PathBuf::from("dir2")].cluster,
+            Some("other".into())
+        );
+
+        state
+            .save_cache(&workflow, &mut multi_progress)
+            .expect("Cache saved.");
+        data_directory
+            .child(LEGACY_COMPLETED_CACHE_FILE_NAME)
+            .assert(predicates::path::missing());
+        data_directory
+            .child(COMPLETED_CACHE_FILE_NAME)
+            .assert(predicates::path::exists());
+
+        let cached_state = State::from_cache(&workflow).expect("Read state from cache");
+        assert_eq!(state.completed, cached_state.completed);
+    }
+
+    #[test]
+    #[parallel]
+    fn completion_hooks() {
+        let mut multi_progress = setup();
+
+        let temp = TempDir::new().unwrap();
+        let n = 4;
+
+        let workflow = setup_completion_directories(&temp, n)
+            + r#"
+[hooks]
+on_action_complete = "echo {action} {directory} >> hooks.log"
+"#;
+        let workflow = Workflow::open_str(temp.path(), &workflow).unwrap();
+
+        let log = || {
+            let mut lines: Vec<String> = fs::read_to_string(temp.path().join("hooks.log"))
+                .unwrap_or_default()
+                .lines()
+                .map(String::from)
+                .collect();
+            lines.sort_unstable();
+            lines
+        };
+
+        // Hooks execute after the cache is saved.
+        let mut state = State::default();
+        state
+            .synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress)
+            .unwrap();
+        assert!(log().is_empty());
+        state
+            .save_cache(&workflow, &mut multi_progress)
+            .expect("Cache saved.");
+        assert_eq!(log(), vec!["b dir0", "b dir1", "e dir2", "e dir3"]);
+
+        // Known completions do not execute the hook again.
+        let mut state = State::from_cache(&workflow).unwrap();
+        state
+            .synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress)
+            .unwrap();
+        state
+            .save_cache(&workflow, &mut multi_progress)
+            .expect("Cache saved.");
+        assert_eq!(log(), vec!["b dir0", "b dir1", "e dir2", "e dir3"]);
+
+        // Staged completions (from `row scan`) do.
+        let mut complete = HashMap::new();
+        complete.insert(
+            "e".to_string(),
+            HashSet::from([PathBuf::from("dir0"), PathBuf::from("dir2")]),
+        );
+        CompletionPack::new(None, record_completions(complete, &Completion::default()))
+            .write(
+                &temp
+                    .path()
+                    .join(DATA_DIRECTORY_NAME)
+                    .join(COMPLETED_DIRECTORY_NAME),
+                COMPLETED_PACK_EXTENSION,
+            )
+            .unwrap();
+
+        state
+            .synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress)
+            .unwrap();
+        state
+            .save_cache(&workflow, &mut multi_progress)
+            .expect("Cache saved.");
+        assert_eq!(
+            log(),
+            vec!["b dir0", "b dir1", "e dir0", "e dir2", "e dir3"]
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn pending_completions() {
         let mut multi_progress = setup();
 
         let temp = TempDir::new().unwrap();
-        let n = 10;
+        let n = 4;
 
         let workflow = setup_completion_directories(&temp, n);
         let workflow = Workflow::open_str(temp.path(), &workflow).unwrap();
 
         let mut state = State::default();
-        let result = state.synchronize_workspace(&workflow, 2, &mut multi_progress);
-        assert!(result.is_ok());
+        state
+            .synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress)
+            .unwrap();
+        state
+            .save_cache(&workflow, &mut multi_progress)
+            .expect("Cache saved.");
 
-        assert_eq!(state.values().len(), n);
-        assert!(state.completed.contains_key("b"));
-        assert!(state.completed.contains_key("e"));
-        for i in 0..n {
-            let directory = PathBuf::from(format!("dir{i}"));
-            #[allow(clippy::cast_sign_loss)]
-            let value = state.values()[&directory].as_i64().unwrap() as usize;
-            assert_eq!(value, i);
+        // Complete 'e' in dir0 without changing the workspace modification time.
+        temp.child("workspace")
+            .child("dir0")
+            .child("g")
+            .touch()
+            .unwrap();
 
-            if i < n / 2 {
-                assert!(state.completed["b"].contains(&directory));
-                assert!(!state.completed["e"].contains(&directory));
-            } else {
-                assert!(!state.completed["b"].contains(&directory));
-                assert!(state.completed["e"].contains(&directory));
+        // Claim that 'b' and 'e' completed in dir0 and dir3 (and a missing directory).
+        let mut pending = HashMap::new();
+        for action in ["b", "e"] {
+            pending.insert(
+                action.to_string(),
+                HashSet::from([
+                    PathBuf::from("dir0"),
+                    PathBuf::from("dir3"),
+                    PathBuf::from("missing"),
+                ]),
+            );
+        }
+        let pack_path =
+            CompletionPack::new(None, record_completions(pending, &Completion::now("job")))
+                .write(
+                    &temp
+                        .path()
+                        .join(DATA_DIRECTORY_NAME)
+                        .join(COMPLETED_DIRECTORY_NAME),
+                    PENDING_PACK_EXTENSION,
+                )
+                .unwrap();
+
+        assert!(
+            State::read_completed(&workflow, 2, &mut multi_progress).unwrap()["e"]
+                .contains_key(&PathBuf::from("dir0"))
+        );
+
+        let mut state = State::from_cache(&workflow).unwrap();
+        state
+            .synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress)
+            .unwrap();
+
+        let directory = PathBuf::from("dir0");
+        assert_eq!(
+            state.completed["e"][&directory].cluster,
+            Some("job".to_string())
+        );
+        assert_eq!(state.completed["b"].len(), 2);
+        assert_eq!(state.completed["e"].len(), 3);
+        assert!(!state.completed["e"].contains_key(&PathBuf::from("missing")));
+
+        state
+            .save_cache(&workflow, &mut multi_progress)
+            .expect("Cache saved.");
+        assert!(!pack_path.exists());
+    }
+
+    #[test]
+    #[parallel]
+    fn completion_packs() {
+        let temp = TempDir::new().unwrap();
+        let workflow = Workflow::open_str(temp.path(), "").unwrap();
+        assert!(State::read_staged(&workflow).unwrap().is_empty());
+
+        let completed_directory = temp
+            .child(DATA_DIRECTORY_NAME)
+            .child(COMPLETED_DIRECTORY_NAME);
+        let complete = HashMap::from([("b".to_string(), HashSet::from([PathBuf::from("dir0")]))]);
+        let pack = CompletionPack::new(
+            Some("b".into()),
+            record_completions(complete, &Completion::now("cluster")),
+        );
+        assert_eq!(pack.pid, Some(process::id()));
+        assert!(pack.hostname.is_some());
+
+        let path = pack
+            .write(completed_directory.path(), PENDING_PACK_EXTENSION)
+            .unwrap();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(name.contains(&format!("_{}_", process::id())));
+        assert!(name.ends_with(".v4.pending"));
+        assert!(is_pending_pack(&path));
+
+        // A second pack from the same process does not replace the first.
+        let other = pack
+            .write(completed_directory.path(), COMPLETED_PACK_EXTENSION)
+            .unwrap();
+        assert_ne!(path, other);
+
+        // Packs written by earlier versions have no metadata.
+        let v2: Completions = HashMap::new();
+        completed_directory
+            .child(format!("old.{V2_PENDING_PACK_EXTENSION}"))
+            .write_binary(&postcard::to_stdvec(&v2).unwrap())
+            .unwrap();
+        completed_directory.child("ignored.tmp").touch().unwrap();
+
+        let staged = State::read_staged(&workflow).unwrap();
+        assert_eq!(staged.len(), 3);
+        let read = staged.iter().find(|(p, _)| *p == path).unwrap();
+        assert_eq!(read.1, pack);
+        assert_eq!(read.1.action.as_deref(), Some("b"));
+        let old = staged
+            .iter()
+            .find(|(p, _)| p.ends_with("old.pending"))
+            .unwrap();
+        assert_eq!(old.1, CompletionPack::default());
+    }
+
+    #[test]
+    #[parallel]
+    fn job_history() {
+        let mut multi_progress = setup();
+        let temp = TempDir::new().unwrap();
+        let workflow = setup_completion_directories(&temp, 2);
+        let workflow = Workflow::open_str(temp.path(), &workflow).unwrap();
+
+        let job = JobRecord {
+            job_id: Some(12),
+            ..JobRecord::new(&workflow.action[0], "cluster", 2)
+        };
+        assert_eq!(job.walltime, 7200);
+        assert_eq!(job.runtime(), None);
+
+        let other = JobRecord {
+            cluster: "other".into(),
+            ..job.clone()
+        };
+        let mut state = State::default();
+        state.add_job(job);
+        state.add_job(other.clone());
+
+        // The job wrote two packs. Packs from other jobs do not change it.
+        let completed_directory = temp
+            .child(DATA_DIRECTORY_NAME)
+            .child(COMPLETED_DIRECTORY_NAME);
+        for (job_id, time) in [("12", 160), ("12", 190), ("13", 500)] {
+            CompletionPack {
+                job_id: Some(job_id.into()),
+                job_started: Some(100),
+                time: Some(time),
+                ..CompletionPack::default()
             }
+            .write(completed_directory.path(), COMPLETED_PACK_EXTENSION)
+            .unwrap();
         }
 
+        state
+            .synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress)
+            .unwrap();
+        assert_eq!(state.jobs()[0].started, Some(100));
+        assert_eq!(state.jobs()[0].finished, Some(190));
+        assert_eq!(state.jobs()[0].runtime(), Some(90));
+        assert_eq!(state.jobs()[1], other);
+
         state
             .save_cache(&workflow, &mut multi_progress)
             .expect("Cache saved.");
+        let cached = State::from_cache(&workflow).unwrap();
+        assert_eq!(cached.jobs(), state.jobs());
 
-        let cached_state = State::from_cache(&workflow).expect("Read state from cache");
-        assert_eq!(state, cached_state);
+        let executed = JobRecord::new(&workflow.action[0], "cluster", 1).finished_now();
+        assert_eq!(executed.started, Some(executed.submitted));
+        assert!(executed.runtime().is_some());
     }
 
     #[test]
@@ -879,12 +3050,12 @@ products = ["g"]
             state
                 .directory_cache
                 .values
-                .insert(PathBuf::from(format!("dir{i}")), Value::Null);
+                .insert(&PathBuf::from(format!("dir{i}")), &Value::Null);
         }
 
         let workflow = setup_completion_directories(&temp, n);
         let workflow = Workflow::open_str(temp.path(), &workflow).unwrap();
-        let result = state.synchronize_workspace(&workflow, 2, &mut multi_progress);
+        let result = state.synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress);
         assert!(result.is_ok());
 
         assert_eq!(state.values().len(), n);
@@ -905,19 +3076,28 @@ products = ["g"]
         let mut state = State::default();
         state.completed.insert(
             "b".to_string(),
-            HashSet::from([PathBuf::from("notdir100"), PathBuf::from("notdir200")]),
+            HashMap::from([
+                (PathBuf::from("notdir100"), Completion::default()),
+                (PathBuf::from("notdir200"), Completion::default()),
+            ]),
         );
         state.completed.insert(
             "e".to_string(),
-            HashSet::from([PathBuf::from("notdir50"), PathBuf::from("notdir80")]),
+            HashMap::from([
+                (PathBuf::from("notdir50"), Completion::default()),
+                (PathBuf::from("notdir80"), Completion::default()),
+            ]),
         );
         state.completed.insert(
             "z".to_string(),
-            HashSet::from([PathBuf::from("dir1"), PathBuf::from("dir2")]),
+            HashMap::from([
+                (PathBuf::from("dir1"), Completion::default()),
+                (PathBuf::from("dir2"), Completion::default()),
+            ]),
         );
 
         let workflow = Workflow::open_str(temp.path(), &workflow).unwrap();
-        let result = state.synchronize_workspace(&workflow, 2, &mut multi_progress);
+        let result = state.synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress);
         assert!(result.is_ok());
 
         assert_eq!(state.values().len(), n);
@@ -931,19 +3111,151 @@ products = ["g"]
         for i in 0..n {
             let directory = PathBuf::from(format!("dir{i}"));
             #[allow(clippy::cast_sign_loss)]
-            let value = state.values()[&directory].as_i64().unwrap() as usize;
+            let value = state.values().get(&directory).unwrap().as_i64().unwrap() as usize;
             assert_eq!(value, i);
 
             if i < n / 2 {
-                assert!(state.completed["b"].contains(&directory));
-                assert!(!state.completed["e"].contains(&directory));
+                assert!(state.completed["b"].contains_key(&directory));
+                assert!(!state.completed["e"].contains_key(&directory));
             } else {
-                assert!(!state.completed["b"].contains(&directory));
-                assert!(state.completed["e"].contains(&directory));
+                assert!(!state.completed["b"].contains_key(&directory));
+                assert!(state.completed["e"].contains_key(&directory));
             }
         }
     }
 
+    #[test]
+    #[parallel]
+    fn changed_products_rescan() {
+        let mut multi_progress = setup();
+
+        let temp = TempDir::new().unwrap();
+        let n = 10;
+
+        let workflow = setup_completion_directories(&temp, n);
+        let workflow = Workflow::open_str(temp.path(), &workflow).unwrap();
+
+        let mut state = State::default();
+        let result = state.synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress);
+        assert!(result.is_ok());
+        assert_eq!(state.completed["b"].len(), n / 2);
+        assert!(state.completion_hashes.contains_key("b"));
+
+        // Unchanged workflows do not rescan.
+        state.completed_modified = false;
+        let result = state.synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress);
+        assert!(result.is_ok());
+        assert!(!state.completed_modified);
+
+        // Action b now produces the products of e.
+        let workflow = r#"
+[workspace]
+value_file = "v"
+
+[[action]]
+name = "b"
+command = "c"
+products = ["g"]
+
+[[action]]
+name = "e"
+command = "f"
+products = ["g"]
+"#;
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        let result = state.synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress);
+        assert!(result.is_ok());
+        assert!(state.completed_modified);
+
+        for i in 0..n {
+            let directory = PathBuf::from(format!("dir{i}"));
+            assert_eq!(state.completed["b"].contains_key(&directory), i >= n / 2);
+            assert_eq!(state.completed["e"].contains_key(&directory), i >= n / 2);
+        }
+        assert_eq!(
+            state.completion_hashes["b"],
+            workflow.action[0].completion_hash()
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn repair() {
+        let mut multi_progress = setup();
+
+        let temp = TempDir::new().unwrap();
+        let n = 10;
+
+        let workflow = setup_completion_directories(&temp, n);
+        let workflow = Workflow::open_str(temp.path(), &workflow).unwrap();
+
+        let mut state = State::default();
+        state
+            .synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress)
+            .unwrap();
+
+        // Corrupt the cache.
+        state
+            .directory_cache
+            .values
+            .insert(Path::new("dir0"), &Value::from(100));
+        state
+            .directory_cache
+            .values
+            .retain(|d| d != Path::new("dir1"));
+        state
+            .directory_cache
+            .values
+            .insert(Path::new("notdir"), &Value::Null);
+        state
+            .completed
+            .get_mut("b")
+            .unwrap()
+            .insert(PathBuf::from("notdir"), Completion::default());
+        state
+            .completed
+            .get_mut("b")
+            .unwrap()
+            .remove(&PathBuf::from("dir2"));
+        state
+            .completed
+            .get_mut("e")
+            .unwrap()
+            .insert(PathBuf::from("dir3"), Completion::default());
+        state.submitted.insert(
+            "z".into(),
+            HashMap::from([(PathBuf::from("dir4"), ("cluster".into(), 1))]),
+        );
+
+        let repairs = state
+            .repair(&workflow, "cluster", 2, &mut multi_progress)
+            .unwrap();
+
+        assert_eq!(repairs.directories_added, vec![PathBuf::from("dir1")]);
+        assert_eq!(repairs.directories_removed, vec![PathBuf::from("notdir")]);
+        assert_eq!(repairs.values_changed, vec![PathBuf::from("dir0")]);
+        assert_eq!(repairs.orphaned_completed, 1);
+        assert_eq!(repairs.orphaned_submitted, 1);
+        assert_eq!(
+            repairs.completions_added,
+            vec![("b".to_string(), PathBuf::from("dir2"))]
+        );
+        assert_eq!(
+            repairs.completions_removed,
+            vec![("e".to_string(), PathBuf::from("dir3"))]
+        );
+
+        assert_eq!(state.values().len(), n);
+        assert_eq!(state.completed["b"].len(), n / 2);
+        assert_eq!(state.completed["e"].len(), n / 2);
+        assert!(state.submitted.is_empty());
+
+        let repairs = state
+            .repair(&workflow, "cluster", 2, &mut multi_progress)
+            .unwrap();
+        assert!(repairs.is_empty());
+    }
+
     #[test]
     #[parallel]
     fn new_submitted_and_cache() {
@@ -956,7 +3268,7 @@ products = ["g"]
         let workflow = Workflow::open_str(temp.path(), &workflow).unwrap();
 
         let mut state = State::default();
-        let result = state.synchronize_workspace(&workflow, 2, &mut multi_progress);
+        let result = state.synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress);
         assert!(result.is_ok());
 
         assert!(state.submitted.is_empty());
@@ -996,6 +3308,282 @@ products = ["g"]
         assert_eq!(state, cached_state);
     }
 
+    #[test]
+    #[parallel]
+    fn merge_submitted() {
+        let mut multi_progress = setup();
+
+        let temp = TempDir::new().unwrap();
+        let workflow = setup_completion_directories(&temp, 8);
+        let workflow = Workflow::open_str(temp.path(), &workflow).unwrap();
+
+        let mut state = State::default();
+        state
+            .synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress)
+            .unwrap();
+        state.add_submitted("b", &["dir1".into()], "cluster", 11);
+        state.add_submitted("b", &["dir2".into()], "cluster", 12);
+        state.save_cache(&workflow, &mut multi_progress).unwrap();
+
+        let mut first = State::from_cache(&workflow).unwrap();
+        let mut second = State::from_cache(&workflow).unwrap();
+
+        // The first process removes a finished job and submits a new one.
+        first.remove_inactive_submitted("cluster", &HashSet::from([11]));
+        first.add_submitted("b", &["dir3".into()], "cluster", 13);
+        first.add_job(JobRecord {
+            job_id: Some(13),
+            ..JobRecord::new(&workflow.action[0], "cluster", 1)
+        });
+
+        // The second process submits a job and saves the cache in the meantime.
+        second.add_submitted("b", &["dir4".into(), "dir5".into()], "cluster", 14);
+        second.add_job(JobRecord {
+            job_id: Some(14),
+            ..JobRecord::new(&workflow.action[0], "cluster", 2)
+        });
+        second.save_cache(&workflow, &mut multi_progress).unwrap();
+
+        first.merge_submitted(&workflow).unwrap();
+        assert!(first.is_submitted("b", &"dir1".into()));
+        assert!(!first.is_submitted("b", &"dir2".into()));
+        assert!(first.is_submitted("b", &"dir3".into()));
+        assert_eq!(
+            first.submitted_job("b", &"dir4".into()),
+            Some(&("cluster".into(), 14))
+        );
+        assert!(first.is_submitted("b", &"dir5".into()));
+        assert_eq!(first.submitted_groups["b"].len(), 4);
+        assert_eq!(first.jobs().len(), 2);
+
+        first.save_cache(&workflow, &mut multi_progress).unwrap();
+        let cached = State::from_cache(&workflow).unwrap();
+        assert_eq!(cached.num_submitted(), 4);
+        assert_eq!(cached.jobs().len(), 2);
+    }
+
+    #[test]
+    #[parallel]
+    fn submit_lock() {
+        let temp = TempDir::new().unwrap();
+        let workflow = Workflow::open_str(temp.path(), "").unwrap();
+        let path = temp.path().join(".row").join(SUBMIT_LOCK_FILE_NAME);
+
+        let lock = SubmitLock::acquire(&workflow).unwrap();
+        assert!(path.exists());
+
+        let file = File::open(&path).unwrap();
+        assert!(matches!(
+            Flock::lock(file, FlockArg::LockExclusiveNonblock),
+            Err((_, Errno::EWOULDBLOCK))
+        ));
+
+        drop(lock);
+        assert!(!path.exists());
+        drop(SubmitLock::acquire(&workflow).unwrap());
+
+        assert!(locks_unsupported(Errno::ENOLCK));
+        assert!(locks_unsupported(Errno::ENOTSUP));
+        assert!(!locks_unsupported(Errno::EACCES));
+    }
+
+    #[test]
+    #[parallel]
+    fn import() {
+        let mut multi_progress = setup();
+
+        let temp = TempDir::new().unwrap();
+        let workflow = setup_completion_directories(&temp, 4);
+        let workflow = Workflow::open_str(temp.path(), &workflow).unwrap();
+
+        let mut state = State::default();
+        state
+            .synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress)
+            .unwrap();
+
+        let other = Completion {
+            cluster: Some("other".into()),
+            time: Some(10),
+        };
+        let make_import = || Import {
+            completed: HashMap::from([
+                (
+                    "b".into(),
+                    HashMap::from([
+                        ("dir0".into(), other.clone()),
+                        ("dir2".into(), other.clone()),
+                        ("dir9".into(), other.clone()),
+                    ]),
+                ),
+                ("z".into(), HashMap::from([("dir0".into(), other.clone())])),
+            ]),
+            submitted: HashMap::from([(
+                "e".into(),
+                HashMap::from([
+                    ("dir0".into(), ("cluster".into(), 5)),
+                    ("dir1".into(), ("other".into(), 6)),
+                ]),
+            )]),
+            submitted_groups: HashMap::from([(
+                "e".into(),
+                HashMap::from([
+                    ("g".into(), vec!["dir0".into()]),
+                    ("h".into(), vec!["dir0".into(), "dir1".into()]),
+                ]),
+            )]),
+        };
+
+        let unchanged = state.completed().clone();
+        assert!(matches!(
+            state.import(&workflow, make_import(), "cluster", Conflict::Error),
+            Err(Error::ImportConflict(directory, action)) if directory == PathBuf::from("dir0") && action == "b"
+        ));
+        assert_eq!(*state.completed(), unchanged);
+        assert_eq!(state.num_submitted(), 0);
+
+        let imported = state
+            .import(&workflow, make_import(), "cluster", Conflict::Keep)
+            .unwrap();
+        assert_eq!(
+            imported,
+            Imported {
+                completed: 1,
+                submitted: 1,
+                submitted_groups: 1,
+                kept: 1,
+                skipped: 4,
+            }
+        );
+        assert_ne!(state.completed()["b"][&PathBuf::from("dir0")], other);
+        assert_eq!(state.completed()["b"][&PathBuf::from("dir2")], other);
+        assert!(state.is_submitted("e", &"dir0".into()));
+        assert!(!state.is_submitted("e", &"dir1".into()));
+        assert!(state.submitted_groups()["e"].contains_key("g"));
+        assert!(!state.submitted_groups()["e"].contains_key("h"));
+
+        let imported = state
+            .import(&workflow, make_import(), "cluster", Conflict::Replace)
+            .unwrap();
+        assert_eq!(imported.completed, 1);
+        assert_eq!(imported.kept, 3);
+        assert_eq!(state.completed()["b"][&PathBuf::from("dir0")], other);
+
+        let mut conflicting = make_import();
+        conflicting.completed.clear();
+        conflicting.submitted_groups.insert(
+            "e".into(),
+            HashMap::from([("g".into(), vec!["dir0".into(), "dir0".into()])]),
+        );
+        assert!(matches!(
+            state.import(&workflow, conflicting, "cluster", Conflict::Error),
+            Err(Error::ImportGroupConflict(id, action)) if id == "g" && action == "e"
+        ));
+        assert_eq!(
+            state.submitted_groups()["e"]["g"],
+            vec![PathBuf::from("dir0")]
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn group_ids() {
+        let a = group_id(&["dir1".into(), "dir2".into()]);
+        assert_eq!(a, group_id(&["dir2".into(), "dir1".into()]));
+        assert_eq!(a.len(), 16);
+        assert_ne!(a, group_id(&["dir1".into()]));
+        assert_ne!(a, group_id(&["dir1dir2".into()]));
+        assert_eq!(group_id(&[]), "cbf29ce484222325");
+    }
+
+    #[test]
+    #[parallel]
+    fn split_submitted_groups() {
+        let mut multi_progress = setup();
+
+        let temp = TempDir::new().unwrap();
+        let n = 8;
+
+        let workflow = setup_completion_directories(&temp, n);
+        let workflow = Workflow::open_str(temp.path(), &workflow).unwrap();
+
+        let mut state = State::default();
+        let result = state.synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress);
+        assert!(result.is_ok());
+
+        let group: Vec<PathBuf> = vec!["dir1".into(), "dir2".into(), "dir3".into()];
+        state.add_submitted("b", &group, "cluster", 11);
+
+        assert!(state.split_submitted_groups("b", &group).is_empty());
+        assert!(state
+            .split_submitted_groups("b", &["dir4".into(), "dir5".into()])
+            .is_empty());
+        assert!(state
+            .split_submitted_groups("b", &[&group[..], &["dir4".into()]].concat())
+            .is_empty());
+        assert!(state
+            .split_submitted_groups("e", &["dir1".into()])
+            .is_empty());
+        assert_eq!(
+            state.split_submitted_groups("b", &["dir2".into(), "dir4".into()]),
+            vec![(group_id(&group), 1, 3)]
+        );
+
+        // Submitted groups persist after the jobs finish.
+        state.remove_inactive_submitted("cluster", &HashSet::new());
+        state
+            .save_cache(&workflow, &mut multi_progress)
+            .expect("Cache saved.");
+
+        let cached_state = State::from_cache(&workflow).expect("Read state from cache");
+        assert_eq!(state, cached_state);
+        assert!(!cached_state.is_submitted("b", &"dir1".into()));
+        assert_eq!(
+            cached_state.split_submitted_groups("b", &["dir3".into()]),
+            vec![(group_id(&group), 1, 3)]
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn corrupt_cache() {
+        let mut multi_progress = setup();
+        let temp = TempDir::new().unwrap();
+        let workflow = setup_completion_directories(&temp, 2);
+        let workflow = Workflow::open_str(temp.path(), &workflow).unwrap();
+
+        let mut state = State::default();
+        state
+            .synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress)
+            .unwrap();
+        state.add_submitted("b", &["dir1".into()], "cluster", 11);
+        state.save_cache(&workflow, &mut multi_progress).unwrap();
+
+        let data_directory = workflow.data_directory();
+        let submitted = data_directory.join(SUBMITTED_CACHE_FILE_NAME);
+        assert!(submitted.exists());
+        assert!(!fs::read_dir(&data_directory).unwrap().any(|entry| entry
+            .unwrap()
+            .file_name()
+            .to_string_lossy()
+            .starts_with(".tmp")));
+
+        // Truncate the cache as an interrupted write would.
+        let bytes = fs::read(&submitted).unwrap();
+        fs::write(&submitted, &bytes[..bytes.len() / 2]).unwrap();
+
+        let corrupt = data_directory.join(format!("{SUBMITTED_CACHE_FILE_NAME}.corrupt"));
+        assert!(matches!(
+            State::from_cache(&workflow),
+            Err(Error::CorruptCache(path, moved, _, advice))
+                if path == submitted && moved == corrupt && advice.contains("Wait for those jobs")
+        ));
+        assert!(corrupt.exists());
+        assert!(!submitted.exists());
+
+        let cached_state = State::from_cache(&workflow).unwrap();
+        assert!(cached_state.submitted().is_empty());
+    }
+
     #[test]
     #[parallel]
     fn remove_submitted_actions_and_dirs() {
@@ -1008,7 +3596,7 @@ products = ["g"]
         let workflow = Workflow::open_str(temp.path(), &workflow).unwrap();
 
         let mut state = State::default();
-        let result = state.synchronize_workspace(&workflow, 2, &mut multi_progress);
+        let result = state.synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress);
         assert!(result.is_ok());
 
         assert!(state.submitted.is_empty());
@@ -1034,7 +3622,8 @@ products = ["g"]
         let mut cached_state = State::from_cache(&workflow).expect("Read state from cache");
         assert_eq!(state, cached_state);
 
-        let result = cached_state.synchronize_workspace(&workflow, 2, &mut multi_progress);
+        let result =
+            cached_state.synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress);
         assert!(result.is_ok());
 
         assert!(!cached_state.submitted.contains_key("f"));
@@ -1047,6 +3636,46 @@ products = ["g"]
         assert!(!cached_state.is_submitted("b", &"dir27".into()));
     }
 
+    #[test]
+    #[parallel]
+    fn rename_directory() {
+        let mut multi_progress = setup();
+
+        let temp = TempDir::new().unwrap();
+        let n = 8;
+
+        let workflow = setup_completion_directories(&temp, n);
+        let workflow = Workflow::open_str(temp.path(), &workflow).unwrap();
+
+        let mut state = State::default();
+        let result = state.synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress);
+        assert!(result.is_ok());
+
+        state.add_submitted("f", &["dir1".into(), "dir3".into()], "cluster1", 18);
+
+        state
+            .rename_directory(Path::new("dir1"), Path::new("new1"))
+            .unwrap();
+        state
+            .rename_directory(Path::new("dir2"), Path::new("new2"))
+            .unwrap();
+
+        assert!(!state.values().contains_key(&PathBuf::from("dir1")));
+        assert!(state.values().contains_key(&PathBuf::from("new1")));
+        assert!(state.values().contains_key(&PathBuf::from("new2")));
+
+        assert!(!state.completed()["b"].contains_key(&PathBuf::from("dir2")));
+        assert!(state.completed()["b"].contains_key(&PathBuf::from("new2")));
+
+        assert!(!state.is_submitted("f", &"dir1".into()));
+        assert!(state.is_submitted("f", &"new1".into()));
+        let members: Vec<PathBuf> = vec!["new1".into(), "dir3".into()];
+        assert_eq!(
+            state.submitted_groups["f"].get(&group_id(&members)),
+            Some(&members)
+        );
+    }
+
     #[test]
     #[parallel]
     fn remove_inactive() {
@@ -1059,7 +3688,7 @@ products = ["g"]
         let workflow = Workflow::open_str(temp.path(), &workflow).unwrap();
 
         let mut state = State::default();
-        let result = state.synchronize_workspace(&workflow, 2, &mut multi_progress);
+        let result = state.synchronize_workspace(&workflow, "cluster", 2, &mut multi_progress);
         assert!(result.is_ok());
 
         assert!(state.submitted.is_empty());
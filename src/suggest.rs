@@ -0,0 +1,133 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+//! Suggest known names that are close to a mistyped name.
+
+/// Maximum number of names to suggest.
+const MAXIMUM_SUGGESTIONS: usize = 3;
+
+/// Compute the edit distance between two strings.
+///
+/// The distance is the minimum number of single character insertions,
+/// deletions, substitutions, and transpositions of adjacent characters that
+/// change `a` into `b` (the optimal string alignment distance).
+///
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut before_previous = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for i in 0..a.len() {
+        current[0] = i + 1;
+        for j in 0..b.len() {
+            let substitution = previous[j] + usize::from(a[i] != b[j]);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            if i > 0 && j > 0 && a[i] == b[j - 1] && a[i - 1] == b[j] {
+                current[j + 1] = current[j + 1].min(before_previous[j - 1] + 1);
+            }
+        }
+        before_previous.clone_from(&previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// Find the candidates that are close to `name`.
+///
+/// A candidate is close when its distance to `name` is at most one third of
+/// the length of `name` (and at least 1).
+///
+/// # Returns
+/// Up to 3 candidates, closest first.
+///
+pub fn closest<I, S>(name: &str, candidates: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let maximum_distance = (name.chars().count() / 3).max(1);
+
+    let mut result: Vec<(usize, String)> = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let candidate = candidate.as_ref();
+            let d = distance(name, candidate);
+            (d > 0 && d <= maximum_distance).then(|| (d, candidate.to_string()))
+        })
+        .collect();
+    result.sort_unstable();
+    result.truncate(MAXIMUM_SUGGESTIONS);
+
+    result.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+/// Format a suggestion to append to an error message.
+///
+/// # Returns
+/// `"\nDid you mean 'a'?"` (or `'a', 'b', or 'c'`) when any of the
+/// `candidates` are close to `name`. Otherwise, an empty string.
+///
+pub fn did_you_mean<I, S>(name: &str, candidates: I) -> String
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let names: Vec<String> = closest(name, candidates)
+        .into_iter()
+        .map(|candidate| format!("'{candidate}'"))
+        .collect();
+
+    match names.as_slice() {
+        [] => String::new(),
+        [a] => format!("\nDid you mean {a}?"),
+        [a, b] => format!("\nDid you mean {a} or {b}?"),
+        [rest @ .., last] => format!("\nDid you mean {}, or {last}?", rest.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::parallel;
+
+    use super::*;
+
+    #[test]
+    #[parallel]
+    fn distances() {
+        assert_eq!(distance("", ""), 0);
+        assert_eq!(distance("abc", ""), 3);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("simulate", "simulat"), 1);
+        assert_eq!(distance("analyze", "analzye"), 1);
+        assert_eq!(distance("ca", "abc"), 3);
+    }
+
+    #[test]
+    #[parallel]
+    fn suggestions() {
+        let actions = ["simulate", "simulate_gpu", "analyze", "plot"];
+
+        assert_eq!(closest("simulat", actions), vec!["simulate"]);
+        assert_eq!(closest("plto", actions), vec!["plot"]);
+        assert!(closest("simulate", actions).is_empty());
+        assert!(closest("compress", actions).is_empty());
+
+        assert_eq!(did_you_mean("compress", actions), "");
+        assert_eq!(
+            did_you_mean("simulat", actions),
+            "\nDid you mean 'simulate'?"
+        );
+        assert_eq!(
+            did_you_mean("dir", ["dir1", "dir2", "dir3", "dir4"]),
+            "\nDid you mean 'dir1', 'dir2', or 'dir3'?"
+        );
+        assert_eq!(
+            did_you_mean("dir", ["dir2", "dir1"]),
+            "\nDid you mean 'dir1' or 'dir2'?"
+        );
+    }
+}
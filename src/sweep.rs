@@ -0,0 +1,377 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use log::{debug, trace};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::workflow::{next_placeholder, push_pointer, ValueMode, Workflow};
+use crate::{fnv1a, Error, FNV_OFFSET_BASIS};
+
+/// A parameter sweep specification.
+///
+/// `Sweep` describes the values of the directories to create in the workspace:
+/// the cartesian product of the parameters in `product` followed by each
+/// value in `values`.
+///
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Sweep {
+    /// Template of the directory names (`{/pointer}` placeholders).
+    ///
+    /// `None` names each directory by a hash of its value.
+    pub name: Option<String>,
+
+    /// Values of each parameter to combine in the cartesian product.
+    #[serde(default)]
+    pub product: Map<String, Value>,
+
+    /// Explicit values.
+    #[serde(default)]
+    pub values: Vec<Map<String, Value>>,
+}
+
+/// The outcome of creating the directories in a sweep.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Created {
+    /// Directories that were created.
+    pub created: Vec<PathBuf>,
+
+    /// Directories that already existed with the same value.
+    pub existing: Vec<PathBuf>,
+}
+
+impl Sweep {
+    /// Open a sweep specification.
+    ///
+    /// Parse `.json` files as JSON and all other files as TOML.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when the file cannot be read or parsed.
+    ///
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        debug!("Reading sweep '{}'.", path.display());
+        let contents = fs::read_to_string(path).map_err(|e| Error::FileRead(path.into(), e))?;
+
+        if path.extension().is_some_and(|e| e == "json") {
+            serde_json::from_str(&contents).map_err(|e| Error::JSONParse(path.into(), e))
+        } else {
+            toml::from_str(&contents).map_err(|e| Error::TOMLParse(path.into(), e))
+        }
+    }
+
+    /// Generate the value of every directory in the sweep.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when a parameter in `product` is not an array or the
+    /// sweep has no values.
+    ///
+    pub fn values(&self) -> Result<Vec<Value>, Error> {
+        let mut result = Vec::new();
+
+        if !self.product.is_empty() {
+            let mut points = vec![Map::new()];
+            for (key, parameter) in &self.product {
+                let Value::Array(elements) = parameter else {
+                    return Err(Error::SweepParameterNotArray(key.clone()));
+                };
+
+                let mut next = Vec::with_capacity(points.len() * elements.len());
+                for point in &points {
+                    for element in elements {
+                        let mut point = point.clone();
+                        point.insert(key.clone(), element.clone());
+                        next.push(point);
+                    }
+                }
+                points = next;
+            }
+            result.extend(points.into_iter().map(Value::Object));
+        }
+
+        result.extend(self.values.iter().cloned().map(Value::Object));
+
+        if result.is_empty() {
+            return Err(Error::SweepEmpty());
+        }
+        Ok(result)
+    }
+
+    /// Determine the name of the directory that holds `value`.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when `name` references an element that is not in
+    /// `value` or the result is not a valid directory name.
+    ///
+    /// # Panics
+    /// When `value` cannot be serialized.
+    ///
+    pub fn directory_name(&self, value: &Value) -> Result<PathBuf, Error> {
+        let Some(template) = &self.name else {
+            let bytes = serde_json::to_vec(value).expect("Values are valid JSON");
+            return Ok(PathBuf::from(format!(
+                "{:016x}",
+                fnv1a(FNV_OFFSET_BASIS, &bytes)
+            )));
+        };
+
+        let mut result = String::with_capacity(template.len());
+        let mut rest = template.as_str();
+        while let Some((start, end)) = next_placeholder(rest) {
+            result.push_str(&rest[..start]);
+            let placeholder = &rest[start..end];
+            if !(placeholder.starts_with("{/") && placeholder.ends_with('}')) {
+                return Err(Error::SweepInvalidPlaceholder(placeholder.into()));
+            }
+            push_pointer(&mut result, placeholder, Path::new(""), value).map_err(|_| {
+                Error::SweepPointerNotFound(
+                    placeholder[1..placeholder.len() - 1].into(),
+                    value.to_string(),
+                )
+            })?;
+            rest = &rest[end..];
+        }
+        result.push_str(rest);
+
+        let name = PathBuf::from(result);
+        let mut components = name.components();
+        if !matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        ) {
+            return Err(Error::InvalidDirectoryName(name));
+        }
+        Ok(name)
+    }
+
+    /// Create a directory with a value file for each value in the sweep.
+    ///
+    /// Skip directories that already exist with the same value. When `dry_run`
+    /// is set, report the directories without creating them.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when the workflow does not set a per-directory value
+    /// file, two values map to the same directory, a directory already exists
+    /// with a different value, or on I/O errors.
+    ///
+    pub fn create(&self, workflow: &Workflow, dry_run: bool) -> Result<Created, Error> {
+        if let Some(url) = &workflow.workspace.url {
+            return Err(Error::ReadOnlyWorkspace(url.clone()));
+        }
+        let (Some(value_file), ValueMode::Directory) = (
+            &workflow.workspace.value_file,
+            workflow.workspace.value_mode,
+        ) else {
+            return Err(Error::SweepRequiresValueFile());
+        };
+
+        let mut named = HashMap::new();
+        let mut points = Vec::new();
+        for value in self.values()? {
+            let name = self.directory_name(&value)?;
+            match named.get(&name) {
+                Some(other) if *other == value => (),
+                Some(_) => return Err(Error::SweepNameCollision(name)),
+                None => {
+                    named.insert(name.clone(), value.clone());
+                    points.push((name, value));
+                }
+            }
+        }
+
+        let workspace = workflow.root.join(&workflow.workspace.path);
+        let mut result = Created::default();
+        let mut new_points = Vec::new();
+        for (name, value) in points {
+            let value_path = workspace.join(&name).join(value_file);
+
+            match fs::read(&value_path) {
+                Ok(bytes) => {
+                    let existing: Value = serde_json::from_slice(&bytes)
+                        .map_err(|e| Error::JSONParse(value_path.clone(), e))?;
+                    if existing != value {
+                        return Err(Error::SweepValueMismatch(name));
+                    }
+                    trace!("'{}' already exists.", name.display());
+                    result.existing.push(name);
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => new_points.push((name, value)),
+                Err(e) => return Err(Error::FileRead(value_path, e)),
+            }
+        }
+
+        // Check every directory before creating any.
+        for (name, value) in new_points {
+            if !dry_run {
+                trace!("Creating '{}'.", name.display());
+                let directory = workspace.join(&name);
+                let value_path = directory.join(value_file);
+                fs::create_dir_all(&directory)
+                    .map_err(|e| Error::DirectoryCreate(directory.clone(), e))?;
+                let contents = serde_json::to_string_pretty(&value)
+                    .map_err(|e| Error::JSONSerialize(value_path.clone(), e))?;
+                fs::write(&value_path, contents + "\n")
+                    .map_err(|e| Error::FileWrite(value_path.clone(), e))?;
+            }
+            result.created.push(name);
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_fs::prelude::*;
+    use assert_fs::TempDir;
+    use serde_json::json;
+    use serial_test::parallel;
+
+    use super::*;
+
+    fn sweep(toml: &str) -> Sweep {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    #[parallel]
+    fn product() {
+        let sweep = sweep(
+            r#"
+[product]
+a = [1, 2]
+b = ["x", "y", "z"]
+
+[[values]]
+a = 10
+c = true
+"#,
+        );
+
+        let values = sweep.values().unwrap();
+        assert_eq!(values.len(), 7);
+        assert_eq!(values[0], json!({"a": 1, "b": "x"}));
+        assert_eq!(values[1], json!({"a": 1, "b": "y"}));
+        assert_eq!(values[3], json!({"a": 2, "b": "x"}));
+        assert_eq!(values[6], json!({"a": 10, "c": true}));
+    }
+
+    #[test]
+    #[parallel]
+    fn invalid() {
+        assert!(matches!(
+            Sweep::default().values(),
+            Err(Error::SweepEmpty())
+        ));
+        assert!(matches!(
+            sweep("product.a = 1").values(),
+            Err(Error::SweepParameterNotArray(key)) if key == "a"
+        ));
+        assert!(toml::from_str::<Sweep>("other = 1").is_err());
+    }
+
+    #[test]
+    #[parallel]
+    fn names() {
+        let value = json!({"a": 1, "b": "x"});
+
+        let hashed = Sweep::default().directory_name(&value).unwrap();
+        assert_eq!(hashed.as_os_str().len(), 16);
+        assert_eq!(hashed, Sweep::default().directory_name(&value).unwrap());
+        assert_ne!(
+            hashed,
+            Sweep::default().directory_name(&json!({"a": 2})).unwrap()
+        );
+
+        let named = sweep(r#"name = "a={/a}_b={/b}""#);
+        assert_eq!(
+            named.directory_name(&value).unwrap(),
+            PathBuf::from("a=1_b=x")
+        );
+
+        assert!(matches!(
+            sweep(r#"name = "{/c}""#).directory_name(&value),
+            Err(Error::SweepPointerNotFound(..))
+        ));
+        assert!(matches!(
+            sweep(r#"name = "{a}""#).directory_name(&value),
+            Err(Error::SweepInvalidPlaceholder(..))
+        ));
+        assert!(matches!(
+            sweep(r#"name = "{/b}/{/a}""#).directory_name(&value),
+            Err(Error::InvalidDirectoryName(..))
+        ));
+    }
+
+    #[test]
+    #[parallel]
+    fn create() {
+        let temp = TempDir::new().unwrap();
+        let workflow = Workflow::open_str(
+            temp.path(),
+            r#"
+[workspace]
+value_file = "value.json"
+"#,
+        )
+        .unwrap();
+
+        let sweep = sweep(
+            r#"
+name = "a{/a}"
+product.a = [1, 2]
+"#,
+        );
+
+        let created = sweep.create(&workflow, true).unwrap();
+        assert_eq!(
+            created.created,
+            vec![PathBuf::from("a1"), PathBuf::from("a2")]
+        );
+        temp.child("workspace")
+            .child("a1")
+            .assert(predicates::path::missing());
+
+        let created = sweep.create(&workflow, false).unwrap();
+        assert_eq!(created.created.len(), 2);
+        assert!(created.existing.is_empty());
+        let contents = fs::read_to_string(temp.child("workspace/a2/value.json").path()).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&contents).unwrap(),
+            json!({"a": 2})
+        );
+
+        let created = sweep.create(&workflow, false).unwrap();
+        assert!(created.created.is_empty());
+        assert_eq!(created.existing.len(), 2);
+
+        temp.child("workspace/a1/value.json")
+            .write_str(r#"{"a": 3}"#)
+            .unwrap();
+        assert!(matches!(
+            sweep.create(&workflow, false),
+            Err(Error::SweepValueMismatch(name)) if name == PathBuf::from("a1")
+        ));
+
+        let collision = self::sweep(
+            r#"
+name = "same"
+product.a = [1, 2]
+"#,
+        );
+        assert!(matches!(
+            collision.create(&workflow, false),
+            Err(Error::SweepNameCollision(..))
+        ));
+
+        let workflow = Workflow::open_str(temp.path(), "").unwrap();
+        assert!(matches!(
+            sweep.create(&workflow, false),
+            Err(Error::SweepRequiresValueFile())
+        ));
+    }
+}
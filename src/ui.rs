@@ -4,12 +4,17 @@
 use console::Style;
 use indicatif::MultiProgress;
 use memchr::memmem;
+use std::borrow::Cow;
 use std::cmp;
 use std::io::{self, Write};
+use std::path::Path;
 
 /// The default writer buffer size.
 const DEFAULT_BUFFER_SIZE: usize = 1024;
 
+/// Narrowest width that a table column shrinks to when fitting a table.
+const MINIMUM_COLUMN_WIDTH: usize = 8;
+
 /// Buffered writer that interoperates with a `MultiProgress`.
 ///
 /// Use this writer to buffer writes to stdout/stderr. When flushed, the
@@ -70,16 +75,94 @@ impl<T: Write> Drop for MultiProgressWriter<T> {
     }
 }
 
+/// Format text as a terminal hyperlink (OSC 8) to `url`.
+///
+/// Returns `text` unchanged when styled output is disabled (e.g. when stdout
+/// is not a terminal).
+///
+pub(crate) fn hyperlink(text: &str, url: &str) -> String {
+    if console::colors_enabled() {
+        format!("\x1b]8;;{url}\x1b\\{text}\x1b]8;;\x1b\\")
+    } else {
+        text.to_string()
+    }
+}
+
+/// Get the width of the terminal.
+///
+/// Returns `None` when stdout is not a terminal.
+///
+pub(crate) fn terminal_width() -> Option<usize> {
+    console::Term::stdout()
+        .size_checked()
+        .map(|(_, columns)| usize::from(columns))
+}
+
+/// The format of command output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Format {
+    /// Human-readable text.
+    #[default]
+    Text,
+
+    /// Comma-separated values.
+    Csv,
+
+    /// Tab-separated values.
+    Tsv,
+
+    /// JSON.
+    Json,
+
+    /// TOML.
+    Toml,
+}
+
+impl Format {
+    /// Infer the format from the extension of an output file.
+    ///
+    /// Files with the extensions `csv`, `tsv`, `json`, and `toml` select the
+    /// corresponding format. All other files (and stdout) are `Text`.
+    ///
+    pub(crate) fn from_path(path: Option<&Path>) -> Self {
+        match path
+            .and_then(Path::extension)
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .as_deref()
+        {
+            Some("csv") => Format::Csv,
+            Some("tsv") => Format::Tsv,
+            Some("json") => Format::Json,
+            Some("toml") => Format::Toml,
+            _ => Format::Text,
+        }
+    }
+}
+
 pub(crate) enum Alignment {
     Left,
     Right,
 }
 
+/// How to fit items that are wider than their column.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Overflow {
+    /// Truncate the text and end it with an ellipsis.
+    #[default]
+    Truncate,
+
+    /// Wrap the text onto multiple lines.
+    Wrap,
+}
+
 /// One item in a table.
 pub(crate) struct Item {
     text: String,
     style: Style,
     alignment: Alignment,
+    link: Option<String>,
+    value: Option<serde_json::Value>,
 }
 
 /// A table row is either a separator or a vector of items.
@@ -98,6 +181,15 @@ pub(crate) struct Table {
 
     // Hide the header when true.
     hide_header: bool,
+
+    // The output format.
+    format: Format,
+
+    // The maximum width of text output.
+    max_width: Option<usize>,
+
+    // How to fit items that are wider than their column.
+    overflow: Overflow,
 }
 
 impl Item {
@@ -106,6 +198,8 @@ impl Item {
             text,
             style,
             alignment: Alignment::Left,
+            link: None,
+            value: None,
         }
     }
 
@@ -113,6 +207,18 @@ impl Item {
         self.alignment = alignment;
         self
     }
+
+    /// Link the item's text to a URL in text output.
+    pub(crate) fn with_link(mut self, link: Option<String>) -> Self {
+        self.link = link;
+        self
+    }
+
+    /// Write `value` in place of the item's text in JSON output.
+    pub(crate) fn with_value(mut self, value: serde_json::Value) -> Self {
+        self.value = Some(value);
+        self
+    }
 }
 
 impl Table {
@@ -121,6 +227,9 @@ impl Table {
             header: Vec::new(),
             rows: Vec::new(),
             hide_header: false,
+            format: Format::Text,
+            max_width: None,
+            overflow: Overflow::Truncate,
         }
     }
 
@@ -129,31 +238,226 @@ impl Table {
         self
     }
 
-    fn write_row<W: Write>(writer: &mut W, row: &[Item], column_width: &[usize]) -> io::Result<()> {
-        for (i, item) in row.iter().enumerate() {
-            let text = match item.alignment {
-                Alignment::Left => {
-                    if i == row.len() - 1 {
-                        item.text.clone()
-                    } else {
-                        format!("{:<width$}", &item.text, width = column_width[i])
+    pub(crate) fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Limit the width of text output to `max_width` columns (`None` for no limit).
+    ///
+    /// Left-aligned columns shrink (widest first) to fit the table in
+    /// `max_width`. They never shrink below the width of their header.
+    ///
+    pub(crate) fn with_max_width(mut self, max_width: Option<usize>) -> Self {
+        self.max_width = max_width;
+        self
+    }
+
+    pub(crate) fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Quote a CSV field when needed.
+    fn csv_field(text: &str) -> String {
+        if text.contains([',', '"', '\n', '\r']) || text.trim() != text {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Escape a TSV field.
+    fn tsv_field(text: &str) -> String {
+        text.replace('\\', "\\\\")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r")
+    }
+
+    /// Write the table as delimited values without styles.
+    ///
+    /// CSV and TSV have no representation for separators, so they are omitted.
+    ///
+    fn write_delimited<W: Write>(
+        &self,
+        writer: &mut W,
+        delimiter: &str,
+        field: fn(&str) -> String,
+    ) -> io::Result<()> {
+        let rows = self.rows.iter().filter_map(|row| match row {
+            Row::Items(items) => Some(items),
+            Row::Separator => None,
+        });
+
+        for items in (!self.hide_header)
+            .then_some(&self.header)
+            .into_iter()
+            .chain(rows)
+        {
+            let fields: Vec<String> = items.iter().map(|i| field(&i.text)).collect();
+            writeln!(writer, "{}", fields.join(delimiter))?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the table as a JSON array with one object per row.
+    ///
+    /// The header names the keys of each object. The values are the text of
+    /// each item (or its JSON value, when set).
+    ///
+    fn write_json<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let rows: Vec<&Vec<Item>> = self
+            .rows
+            .iter()
+            .filter_map(|row| match row {
+                Row::Items(items) => Some(items),
+                Row::Separator => None,
+            })
+            .collect();
+
+        writeln!(writer, "[")?;
+        for (row_idx, items) in rows.iter().enumerate() {
+            let fields: Vec<String> = self
+                .header
+                .iter()
+                .zip(items.iter())
+                .map(|(key, item)| {
+                    format!(
+                        "{}: {}",
+                        serde_json::Value::from(key.text.as_str()),
+                        item.value
+                            .clone()
+                            .unwrap_or_else(|| serde_json::Value::from(item.text.as_str()))
+                    )
+                })
+                .collect();
+            let comma = if row_idx == rows.len() - 1 { "" } else { "," };
+            writeln!(writer, "  {{{}}}{comma}", fields.join(", "))?;
+        }
+        writeln!(writer, "]")?;
+
+        Ok(())
+    }
+
+    /// Shrink the columns to fit the table in `max_width`.
+    fn fit_columns(&self, column_width: &mut [usize]) {
+        let Some(max_width) = self.max_width else {
+            return;
+        };
+
+        let minimum_width: Vec<Option<usize>> = self
+            .header
+            .iter()
+            .map(|h| match h.alignment {
+                Alignment::Left => Some(cmp::max(
+                    console::measure_text_width(&h.text),
+                    MINIMUM_COLUMN_WIDTH,
+                )),
+                Alignment::Right => None,
+            })
+            .collect();
+
+        let separators = column_width.len().saturating_sub(1);
+        let mut total = column_width.iter().sum::<usize>() + separators;
+        while total > max_width {
+            let widest = (0..column_width.len())
+                .filter(|&i| minimum_width[i].is_some_and(|m| column_width[i] > m))
+                .max_by_key(|&i| column_width[i]);
+            let Some(i) = widest else {
+                break;
+            };
+            column_width[i] -= 1;
+            total -= 1;
+        }
+    }
+
+    /// Split `text` into lines that fit in `width`.
+    fn fit_text<'a>(&self, text: &'a str, width: usize) -> Vec<Cow<'a, str>> {
+        if console::measure_text_width(text) <= width {
+            return vec![Cow::Borrowed(text)];
+        }
+
+        match self.overflow {
+            Overflow::Truncate => vec![console::truncate_str(text, width, "…")],
+            Overflow::Wrap => {
+                let mut lines = Vec::new();
+                let mut rest = text;
+                while !rest.is_empty() {
+                    let mut length = console::truncate_str(rest, width, "").len();
+                    if length == 0 {
+                        length = rest.chars().next().map_or(rest.len(), char::len_utf8);
                     }
+                    lines.push(Cow::Borrowed(&rest[..length]));
+                    rest = &rest[length..];
                 }
-                Alignment::Right => format!("{:>width$}", &item.text, width = column_width[i]),
+                lines
+            }
+        }
+    }
+
+    fn write_row<W: Write>(
+        &self,
+        writer: &mut W,
+        row: &[Item],
+        column_width: &[usize],
+    ) -> io::Result<()> {
+        let lines: Vec<Vec<Cow<str>>> = row
+            .iter()
+            .zip(column_width)
+            .map(|(item, width)| self.fit_text(&item.text, *width))
+            .collect();
+        let n_lines = lines.iter().map(Vec::len).max().unwrap_or(1);
+
+        for line in 0..n_lines {
+            // Wrapped lines end at the last column with text.
+            let n_items = if line == 0 {
+                row.len()
+            } else {
+                lines
+                    .iter()
+                    .rposition(|l| l.len() > line)
+                    .map_or(0, |i| i + 1)
             };
 
-            write!(writer, "{}", &item.style.apply_to(text))?;
-            if i != row.len() - 1 {
-                write!(writer, " ")?;
+            for (i, item) in row.iter().enumerate().take(n_items) {
+                let text = lines[i].get(line).map_or("", AsRef::as_ref);
+                let text = match item.alignment {
+                    Alignment::Left => {
+                        if i == n_items - 1 {
+                            text.to_string()
+                        } else {
+                            format!("{:<width$}", text, width = column_width[i])
+                        }
+                    }
+                    Alignment::Right => format!("{:>width$}", text, width = column_width[i]),
+                };
+                let text = match &item.link {
+                    Some(url) => hyperlink(&text, url),
+                    None => text,
+                };
+
+                write!(writer, "{}", &item.style.apply_to(text))?;
+                if i != n_items - 1 {
+                    write!(writer, " ")?;
+                }
             }
-        }
 
-        writeln!(writer)?;
+            writeln!(writer)?;
+        }
 
         Ok(())
     }
 
     pub(crate) fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self.format {
+            Format::Csv => return self.write_delimited(writer, ",", Self::csv_field),
+            Format::Tsv => return self.write_delimited(writer, "\t", Self::tsv_field),
+            Format::Json => return self.write_json(writer),
+            Format::Text | Format::Toml => (),
+        }
+
         let mut column_width: Vec<usize> = self
             .header
             .iter()
@@ -167,15 +471,16 @@ impl Table {
                 }
             }
         }
+        self.fit_columns(&mut column_width);
 
         if !self.hide_header {
-            Self::write_row(writer, &self.header, &column_width)?;
+            self.write_row(writer, &self.header, &column_width)?;
         }
 
         for (row_idx, row) in self.rows.iter().enumerate() {
             match row {
                 Row::Items(items) => {
-                    Self::write_row(writer, items, &column_width)?;
+                    self.write_row(writer, items, &column_width)?;
                 }
                 Row::Separator => {
                     if row_idx != self.rows.len() - 1 {
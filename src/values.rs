@@ -0,0 +1,486 @@
+// Copyright (c) 2024 The Regents of the University of Michigan.
+// Part of row, released under the BSD 3-Clause License.
+
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::fmt;
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::{suggest, Error};
+
+/// Identify a directory in `DirectoryValues`.
+pub type DirectoryId = u32;
+
+/// The values of the directories in the workspace.
+///
+/// Workspaces may have millions of directories. `DirectoryValues` interns each
+/// directory name in a single string arena and identifies it by a
+/// `DirectoryId`. It stores each value as serialized JSON, decodes it the
+/// first time it is requested, and keeps the decoded value.
+///
+/// `DirectoryValues` serializes as a map from directory name to value.
+///
+#[derive(Clone, Default)]
+pub struct DirectoryValues {
+    /// The names of all directories, concatenated.
+    names: String,
+
+    /// The end of each directory name in `names`, indexed by id.
+    ends: Vec<u32>,
+
+    /// The directory ids sorted by name.
+    sorted: Vec<DirectoryId>,
+
+    /// Ids inserted since `sorted` was last merged, sorted by name.
+    pending: Vec<DirectoryId>,
+
+    /// The serialized JSON value of each directory, indexed by id.
+    values: Vec<Box<[u8]>>,
+
+    /// The decoded value of each directory, indexed by id.
+    decoded: Vec<OnceLock<Value>>,
+}
+
+impl DirectoryValues {
+    /// Get the number of directories.
+    pub fn len(&self) -> usize {
+        self.sorted.len() + self.pending.len()
+    }
+
+    /// Check whether there are no directories.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the name of a directory.
+    ///
+    /// # Panics
+    /// When `id` is not a valid directory id.
+    ///
+    pub fn name(&self, id: DirectoryId) -> &Path {
+        Path::new(self.name_str(id))
+    }
+
+    fn name_str(&self, id: DirectoryId) -> &str {
+        let id = id as usize;
+        let start = if id == 0 {
+            0
+        } else {
+            self.ends[id - 1] as usize
+        };
+        &self.names[start..self.ends[id] as usize]
+    }
+
+    /// Find the position of `name` in `ids` (sorted by name).
+    fn search(&self, ids: &[DirectoryId], name: &str) -> Result<usize, usize> {
+        ids.binary_search_by(|id| self.name_str(*id).cmp(name))
+    }
+
+    /// Get the id of a directory.
+    pub fn id(&self, directory: &Path) -> Option<DirectoryId> {
+        let name = directory.to_str()?;
+        [&self.sorted, &self.pending]
+            .into_iter()
+            .find_map(|ids| self.search(ids, name).ok().map(|i| ids[i]))
+    }
+
+    /// Check whether the directory is present.
+    pub fn contains_key(&self, directory: &Path) -> bool {
+        self.id(directory).is_some()
+    }
+
+    /// Get the value of a directory.
+    pub fn get(&self, directory: &Path) -> Option<&Value> {
+        self.id(directory).map(|id| self.value(id))
+    }
+
+    /// Get the value of a directory by id.
+    ///
+    /// # Panics
+    /// When `id` is not a valid directory id.
+    ///
+    pub fn value(&self, id: DirectoryId) -> &Value {
+        let id = id as usize;
+        self.decoded[id].get_or_init(|| {
+            serde_json::from_slice(&self.values[id]).expect("Stored values are valid JSON")
+        })
+    }
+
+    /// Iterate over the directory ids in name order.
+    fn ordered(&self) -> impl Iterator<Item = DirectoryId> + '_ {
+        let mut sorted = self.sorted.iter().copied().peekable();
+        let mut pending = self.pending.iter().copied().peekable();
+        iter::from_fn(move || match (sorted.peek(), pending.peek()) {
+            (Some(a), Some(b)) if self.name_str(*b) < self.name_str(*a) => pending.next(),
+            (Some(_), _) => sorted.next(),
+            (None, _) => pending.next(),
+        })
+    }
+
+    /// Iterate over the directory names in sorted order.
+    pub fn keys(&self) -> impl Iterator<Item = &Path> {
+        self.ordered().map(|id| self.name(id))
+    }
+
+    /// Make a `DirectoryNotFound` error that suggests similar directory names.
+    pub fn directory_not_found(&self, directory: PathBuf) -> Error {
+        let suggestion = suggest::did_you_mean(
+            &directory.to_string_lossy(),
+            self.keys().map(Path::to_string_lossy),
+        );
+        Error::DirectoryNotFound(directory, suggestion)
+    }
+
+    /// Append a new directory name and value, leaving `sorted` unchanged.
+    fn push(&mut self, name: &str, value: Box<[u8]>) -> DirectoryId {
+        let id = DirectoryId::try_from(self.ends.len()).expect("Fewer than 2^32 directories");
+        self.names.push_str(name);
+        self.ends
+            .push(u32::try_from(self.names.len()).expect("Directory names fit in 4 GiB"));
+        self.values.push(value);
+        self.decoded.push(OnceLock::new());
+        id
+    }
+
+    /// Replace the value of an existing directory.
+    fn replace(&mut self, id: DirectoryId, value: Box<[u8]>) {
+        self.values[id as usize] = value;
+        self.decoded[id as usize] = OnceLock::new();
+    }
+
+    /// Set the value of a directory.
+    ///
+    /// New directories are kept in a short list of pending ids that is merged
+    /// into `sorted` when its length exceeds the square root of the number of
+    /// directories. Each insert costs O(sqrt(n)) amortized.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when the directory name is not valid UTF-8.
+    ///
+    pub fn insert(&mut self, directory: &Path, value: &Value) -> Result<(), Error> {
+        let name = utf8_name(directory)?;
+        if let Some(id) = self.id(directory) {
+            self.replace(id, serialize(value));
+            return Ok(());
+        }
+
+        let appends = self.pending.is_empty()
+            && self
+                .sorted
+                .last()
+                .map_or(true, |last| self.name_str(*last) < name);
+        let id = self.push(name, serialize(value));
+        if appends {
+            self.sorted.push(id);
+        } else {
+            let i = self.search(&self.pending, name).unwrap_err();
+            self.pending.insert(i, id);
+            if self.pending.len().pow(2) > self.sorted.len() {
+                self.merge_pending();
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the values of many directories.
+    ///
+    /// Append the new directories and sort once, which is faster than inserting
+    /// them one at a time.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when a directory name is not valid UTF-8. The
+    /// directories before it are set.
+    ///
+    pub fn try_extend<P, I>(&mut self, iter: I) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = (P, Value)>,
+    {
+        self.merge_pending();
+        let n = self.sorted.len();
+        let mut appended = false;
+        let mut result = Ok(());
+        for (directory, value) in iter {
+            let name = match utf8_name(directory.as_ref()) {
+                Ok(name) => name,
+                Err(error) => {
+                    result = Err(error);
+                    break;
+                }
+            };
+            if let Ok(i) = self.search(&self.sorted[..n], name) {
+                self.replace(self.sorted[i], serialize(&value));
+            } else {
+                let id = self.push(name, serialize(&value));
+                self.sorted.push(id);
+                appended = true;
+            }
+        }
+
+        if appended {
+            self.sort();
+        }
+        result
+    }
+
+    /// Collect the values of many directories.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when a directory name is not valid UTF-8.
+    ///
+    pub fn try_from_iter<P, I>(iter: I) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = (P, Value)>,
+    {
+        let mut result = Self::default();
+        result.try_extend(iter)?;
+        Ok(result)
+    }
+
+    /// Keep only the directories for which `f` returns `true`.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Path) -> bool,
+    {
+        self.merge_pending();
+        let mut old = std::mem::take(self);
+        let mut values = std::mem::take(&mut old.values);
+        let mut decoded = std::mem::take(&mut old.decoded);
+        for id in &old.sorted {
+            let name = old.name_str(*id);
+            if f(Path::new(name)) {
+                let new_id = self.push(name, std::mem::take(&mut values[*id as usize]));
+                self.decoded[new_id as usize] = std::mem::take(&mut decoded[*id as usize]);
+                self.sorted.push(new_id);
+            }
+        }
+    }
+
+    /// Merge the pending ids into `sorted`.
+    fn merge_pending(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let merged: Vec<DirectoryId> = self.ordered().collect();
+        self.sorted = merged;
+        self.pending.clear();
+    }
+
+    /// Sort the ids in `sorted` after appending directories.
+    ///
+    /// Keep the most recently appended id of each name.
+    ///
+    fn sort(&mut self) {
+        let mut sorted = std::mem::take(&mut self.sorted);
+        sorted.sort_by(|a, b| self.name_str(*a).cmp(self.name_str(*b)).then(b.cmp(a)));
+        sorted.dedup_by(|a, b| self.name_str(*a) == self.name_str(*b));
+        self.sorted = sorted;
+    }
+}
+
+/// Get the name of a directory as a string.
+fn utf8_name(directory: &Path) -> Result<&str, Error> {
+    directory
+        .to_str()
+        .ok_or_else(|| Error::NonUTF8DirectoryName(directory.into()))
+}
+
+fn serialize(value: &Value) -> Box<[u8]> {
+    serde_json::to_vec(value)
+        .expect("JSON values serialize")
+        .into_boxed_slice()
+}
+
+impl fmt::Debug for DirectoryValues {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.ordered().map(|id| (self.name(id), self.value(id))))
+            .finish()
+    }
+}
+
+impl PartialEq for DirectoryValues {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self.ordered().zip(other.ordered()).all(|(a, b)| {
+                self.name_str(a) == other.name_str(b) && self.value(a) == other.value(b)
+            })
+    }
+}
+
+impl Eq for DirectoryValues {}
+
+impl Serialize for DirectoryValues {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for id in self.ordered() {
+            let raw: &RawValue = serde_json::from_slice(&self.values[id as usize])
+                .expect("Stored values are valid JSON");
+            map.serialize_entry(self.name_str(id), raw)?;
+        }
+        map.end()
+    }
+}
+
+struct DirectoryValuesVisitor;
+
+impl<'de> Visitor<'de> for DirectoryValuesVisitor {
+    type Value = DirectoryValues;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map from directory names to JSON values")
+    }
+
+    fn visit_map<M: MapAccess<'de>>(self, mut access: M) -> Result<Self::Value, M::Error> {
+        let mut result = DirectoryValues::default();
+        while let Some((name, value)) = access.next_entry::<String, Box<RawValue>>()? {
+            let id = result.push(&name, value.get().as_bytes().into());
+            result.sorted.push(id);
+        }
+        result.sort();
+        Ok(result)
+    }
+}
+
+impl<'de> Deserialize<'de> for DirectoryValues {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(DirectoryValuesVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use serial_test::parallel;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    #[parallel]
+    fn insert_get() {
+        let mut values = DirectoryValues::default();
+        assert!(values.is_empty());
+
+        values.insert(Path::new("b"), &json!(2)).unwrap();
+        values.insert(Path::new("a"), &json!({"x": 1})).unwrap();
+        values.insert(Path::new("c"), &json!([3])).unwrap();
+        values.insert(Path::new("b"), &json!(20)).unwrap();
+
+        assert_eq!(values.len(), 3);
+        assert_eq!(values.get(Path::new("a")), Some(&json!({"x": 1})));
+        assert_eq!(values.get(Path::new("b")), Some(&json!(20)));
+        assert_eq!(values.get(Path::new("c")), Some(&json!([3])));
+        assert_eq!(values.get(Path::new("d")), None);
+        assert!(values.contains_key(Path::new("a")));
+        assert!(!values.contains_key(Path::new("d")));
+        assert_eq!(
+            values.keys().collect::<Vec<_>>(),
+            vec![Path::new("a"), Path::new("b"), Path::new("c")]
+        );
+
+        let id = values.id(Path::new("c")).unwrap();
+        assert_eq!(values.name(id), Path::new("c"));
+        assert_eq!(values.value(id), &json!([3]));
+    }
+
+    #[test]
+    #[parallel]
+    fn extend_retain() {
+        let mut values =
+            DirectoryValues::try_from_iter((0..10).rev().map(|i| (format!("dir{i}"), json!(i))))
+                .unwrap();
+        values
+            .try_extend([
+                (PathBuf::from("dir3"), json!(30)),
+                (PathBuf::from("dir10"), json!(10)),
+            ])
+            .unwrap();
+
+        assert_eq!(values.len(), 11);
+        assert_eq!(values.get(Path::new("dir3")), Some(&json!(30)));
+        assert_eq!(values.keys().next(), Some(Path::new("dir0")));
+        assert_eq!(values.keys().nth(2), Some(Path::new("dir10")));
+
+        values.retain(|d| d != Path::new("dir5") && d != Path::new("dir10"));
+        assert_eq!(values.len(), 9);
+        assert!(!values.contains_key(Path::new("dir5")));
+        assert_eq!(values.get(Path::new("dir9")), Some(&json!(9)));
+        assert_eq!(values.get(Path::new("dir3")), Some(&json!(30)));
+    }
+
+    #[test]
+    #[parallel]
+    fn serde() {
+        let map = HashMap::from([
+            ("dir1".to_string(), json!({"a": [1, 2], "b": "s"})),
+            ("dir2".to_string(), json!(null)),
+        ]);
+        let values = DirectoryValues::try_from_iter(map.clone()).unwrap();
+
+        let bytes = serde_json::to_vec(&values).unwrap();
+        let parsed: HashMap<String, Value> = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(parsed, map);
+
+        let pretty = serde_json::to_vec_pretty(&map).unwrap();
+        let read: DirectoryValues = serde_json::from_slice(&pretty).unwrap();
+        assert_eq!(read, values);
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_pending() {
+        let mut values = DirectoryValues::default();
+        for i in (0..100).rev() {
+            values
+                .insert(&PathBuf::from(format!("dir{i:03}")), &json!(i))
+                .unwrap();
+        }
+        values.insert(Path::new("dir050"), &json!(500)).unwrap();
+
+        assert_eq!(values.len(), 100);
+        assert!(values.keys().zip(values.keys().skip(1)).all(|(a, b)| a < b));
+        for i in 0..100 {
+            let expected = if i == 50 { 500 } else { i };
+            assert_eq!(
+                values.get(&PathBuf::from(format!("dir{i:03}"))),
+                Some(&json!(expected))
+            );
+        }
+
+        let copy = DirectoryValues::try_from_iter(
+            values
+                .keys()
+                .map(|d| (d.to_path_buf(), values.get(d).unwrap().clone())),
+        )
+        .unwrap();
+        assert_eq!(copy, values);
+    }
+
+    #[test]
+    #[parallel]
+    fn non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let directory = Path::new(OsStr::from_bytes(b"dir\xff"));
+        let mut values = DirectoryValues::default();
+        assert!(matches!(
+            values.insert(directory, &json!(1)),
+            Err(Error::NonUTF8DirectoryName(_))
+        ));
+        assert!(matches!(
+            DirectoryValues::try_from_iter([(directory, json!(1))]),
+            Err(Error::NonUTF8DirectoryName(_))
+        ));
+        assert!(values.is_empty());
+        assert_eq!(values.get(directory), None);
+    }
+}
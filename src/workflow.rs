@@ -1,22 +1,33 @@
 // Copyright (c) 2024 The Regents of the University of Michigan.
 // Part of row, released under the BSD 3-Clause License.
 
+use crate::scheduler::bash::SETUP_CHECK;
+use crate::state::group_id;
 use human_format::Formatter;
 use log::{debug, trace, warn};
+use path_absolutize::Absolutize;
+use regex::Regex;
 use serde::{Deserialize, Deserializer};
 use serde_json;
 use speedate::Duration;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::env;
 use std::fmt;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::prelude::*;
 use std::io::{self, BufReader};
+use std::num::NonZeroU16;
 use std::ops::Add;
 use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::slice;
 use std::str::FromStr;
 
-use crate::Error;
+use crate::{
+    fnv1a, suggest, workspace, Error, DATA_DIRECTORY_NAME, DEFAULT_IO_THREADS, FNV_OFFSET_BASIS,
+    GENERATED_WORKFLOW_PREFIX, TOML_WORKFLOW_FILE_NAME, WORKSPACES_DIRECTORY_NAME,
+    YAML_WORKFLOW_FILE_NAME,
+};
 
 /// The workflow definition.
 ///
@@ -40,6 +51,74 @@ pub struct Workflow {
     /// The actions.
     #[serde(default)]
     pub action: Vec<Action>,
+
+    /// A command that prints the effective workflow.
+    pub generator: Option<String>,
+
+    /// Commands to execute in response to events.
+    #[serde(default)]
+    pub hooks: Hooks,
+
+    /// Resources shared by actions.
+    #[serde(default)]
+    pub resources: WorkflowResources,
+
+    /// Submit options to mask in the job scripts that row shows and stores.
+    #[serde(default)]
+    pub redact: Vec<RedactedOption>,
+
+    /// Subdirectory of `.row` that holds the caches of an overridden workspace.
+    #[serde(skip)]
+    workspace_cache: Option<PathBuf>,
+
+    /// Directory that holds the caches in place of `.row`.
+    #[serde(skip)]
+    cache_directory: Option<PathBuf>,
+
+    /// The workspace backend, opened on first use.
+    #[serde(skip)]
+    pub(crate) backend: workspace::BackendCache,
+}
+
+/// Resources shared by actions.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct WorkflowResources {
+    /// Named resources that actions reference with `resources.preset`.
+    #[serde(default)]
+    pub presets: HashMap<String, Resources>,
+}
+
+/// Submit options that row can mask in job scripts.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactedOption {
+    /// `submit_options.<cluster>.account`.
+    Account,
+
+    /// `submit_options.<cluster>.custom`.
+    Custom,
+
+    /// `submit_options.<cluster>.qos`.
+    Qos,
+
+    /// `submit_options.<cluster>.setup`.
+    Setup,
+}
+
+/// The text that replaces redacted submit options.
+pub const REDACTED: &str = "<redacted>";
+
+/// Commands that row executes in response to events.
+///
+/// Row executes each hook with `sh -c` in the project root. `{action}` and
+/// `{directory}` in the command expand to `$ACTION_NAME` and `$ACTION_DIRECTORY`.
+///
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Hooks {
+    /// Executed once for each action and directory that row finds newly completed.
+    pub on_action_complete: Option<String>,
 }
 
 /// The workspace definition.
@@ -55,6 +134,80 @@ pub struct Workspace {
 
     /// Names of the static value file.
     pub value_file: Option<PathBuf>,
+
+    /// Where the value file is stored.
+    #[serde(default)]
+    pub value_mode: ValueMode,
+
+    /// How to handle directories with names that are not valid UTF-8.
+    #[serde(default)]
+    pub non_utf8_names: NonUtf8Names,
+
+    /// Which elements of the directory values to cache.
+    #[serde(default)]
+    pub value_cache: ValueCache,
+
+    /// The minimum free space (in bytes) required to submit jobs.
+    #[serde(default, deserialize_with = "deserialize_bytes_from_str")]
+    pub minimum_free: Option<u64>,
+
+    /// Name of the checksum manifest that verifies products.
+    pub products_checksum_file: Option<PathBuf>,
+
+    /// Which products to verify with the checksum manifest.
+    #[serde(default)]
+    pub products_checksum: ProductsChecksum,
+
+    /// Read the workspace from an object store instead (experimental).
+    pub url: Option<String>,
+}
+
+/// Policy for directories with names that are not valid UTF-8.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NonUtf8Names {
+    /// Include the directories. Submitting them is an error.
+    #[default]
+    Error,
+
+    /// Warn and exclude the directories from the workspace.
+    Skip,
+}
+
+/// Location of the directory values.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueMode {
+    /// Each directory has its own value file.
+    #[default]
+    Directory,
+
+    /// One value file in the workspace maps directory names to values.
+    Consolidated,
+}
+
+/// Elements of the directory values to cache.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueCache {
+    /// Cache the entire value.
+    #[default]
+    All,
+
+    /// Cache only the elements referenced by JSON pointers in the workflow.
+    Referenced,
+}
+
+/// Products to verify with the checksum manifest.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductsChecksum {
+    /// Verify every product listed in the manifest.
+    #[default]
+    Full,
+
+    /// Verify one of the listed products in each directory.
+    Sampled,
 }
 
 /// The submission options
@@ -77,6 +230,9 @@ pub struct SubmitOptions {
 
     /// The partition.
     pub partition: Option<String>,
+
+    /// The quality of service.
+    pub qos: Option<String>,
 }
 
 /// The action definition.
@@ -90,20 +246,36 @@ pub struct Action {
     pub name: Option<String>,
 
     /// The command to execute for this action.
-    pub command: Option<String>,
+    pub command: Option<ActionCommand>,
 
     /// Names of the launchers to use when executing the action.
     #[serde(default)]
-    pub launchers: Option<Vec<String>>,
+    pub launchers: Option<Launchers>,
 
     /// The names of the previous actions that must be completed before this action.
     #[serde(default)]
-    pub previous_actions: Option<Vec<String>>,
+    pub previous_actions: Option<PreviousActions>,
+
+    /// A command that must succeed for a directory to be eligible.
+    pub eligible_when: Option<String>,
 
     /// The product files this action creates.
     #[serde(default)]
     pub products: Option<Vec<String>>,
 
+    /// Tags that select groups of actions on the command line.
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+
+    /// Redirect the output of each directory to a log file in the directory.
+    pub log_output: Option<bool>,
+
+    /// The template for the names of submitted jobs.
+    pub job_name: Option<String>,
+
+    /// A command that submits the job script in place of `sbatch`.
+    pub submit_command_override: Option<String>,
+
     /// Resources used by this action.
     #[serde(default)]
     pub resources: Resources,
@@ -118,6 +290,10 @@ pub struct Action {
 
     // Name of the group to copy defaults from.
     pub from: Option<String>,
+
+    /// Execute the directories one at a time with `row exec` (set by `row submit --aggregate`).
+    #[serde(skip)]
+    pub aggregate: bool,
 }
 
 /// Default tables
@@ -129,6 +305,9 @@ pub struct Action {
 pub struct DefaultTables {
     #[serde(default)]
     pub action: Action,
+
+    /// Number of threads to use for IO intensive operations.
+    pub io_threads: Option<NonZeroU16>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
@@ -140,6 +319,96 @@ pub enum Walltime {
     PerDirectory(Duration),
 }
 
+/// Launchers used by an action.
+///
+/// Either a single list used on all clusters or a map of cluster names to
+/// lists. The map may include a `default` entry that applies to clusters
+/// that are not named explicitly.
+///
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Launchers {
+    All(Vec<String>),
+    ByCluster(HashMap<String, Vec<String>>),
+}
+
+/// The command template of an action.
+///
+/// An action executes a single command or a sequence of commands.
+///
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum ActionCommand {
+    /// A single command.
+    Single(String),
+
+    /// Commands to execute in order. Execution stops at the first that fails.
+    Steps(Vec<String>),
+}
+
+/// Previous actions that must be completed before an action.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum PreviousActions {
+    /// All of the previous actions must be completed.
+    All(Vec<PreviousAction>),
+
+    /// Any one of the previous actions must be completed.
+    Any { any: Vec<PreviousAction> },
+}
+
+/// A previous action.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum PreviousAction {
+    /// The named action is required in every directory.
+    Name(String),
+
+    /// The named action in another row project (relative to this project) is required.
+    ///
+    /// When `include` is not empty, the action is required only in directories that
+    /// match any of `include`.
+    Project {
+        project: PathBuf,
+        action: String,
+        #[serde(default)]
+        include: Vec<Selector>,
+    },
+
+    /// The named action is required in the directory and in a fraction (or
+    /// number) of all the directories that it includes.
+    ///
+    /// When `include` is not empty, the action is required only in directories that
+    /// match any of `include`.
+    Threshold {
+        action: String,
+        #[serde(flatten)]
+        threshold: Threshold,
+        #[serde(default)]
+        include: Vec<Selector>,
+    },
+
+    /// The named action is required in directories that match any of `include`.
+    Conditional {
+        action: String,
+        include: Vec<Selector>,
+    },
+}
+
+/// How many directories must complete a previous action.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum Threshold {
+    /// The fraction of the directories that the previous action includes.
+    Fraction { fraction: f64 },
+
+    /// The number of directories.
+    Count { count: usize },
+}
+
+// Validation rejects fractions that are not numbers.
+impl Eq for Threshold {}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum Processes {
@@ -151,6 +420,9 @@ pub enum Processes {
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct Resources {
+    /// Name of the preset that provides omitted keys.
+    pub preset: Option<String>,
+
     /// Number of processes.
     pub processes: Option<Processes>,
 
@@ -162,6 +434,36 @@ pub struct Resources {
 
     // Walltime.
     pub walltime: Option<Walltime>,
+
+    /// Components of a heterogeneous job.
+    #[serde(default)]
+    pub component: Vec<Resources>,
+
+    /// Bind processes to cores or sockets.
+    pub binding: Option<Binding>,
+
+    /// Maximum number of directories to execute at the same time.
+    pub concurrent_directories: Option<usize>,
+}
+
+/// Process binding.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Binding {
+    /// Bind each process to cores, packing processes onto sockets.
+    Cores,
+
+    /// Bind each process to a socket, distributing processes across sockets.
+    Sockets,
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Cores => write!(f, "cores"),
+            Binding::Sockets => write!(f, "sockets"),
+        }
+    }
 }
 
 /// Comparison operations
@@ -178,19 +480,130 @@ pub enum Comparison {
     GreaterThanOrEqualTo,
     #[serde(rename(deserialize = ">"))]
     GreaterThan,
+    #[serde(rename(deserialize = "!="))]
+    NotEqualTo,
+    #[serde(rename(deserialize = "in"))]
+    In,
+    #[serde(rename(deserialize = "not_in"))]
+    NotIn,
+    #[serde(rename(deserialize = "matches"))]
+    Matches,
 }
 
 /// Condition definition
 type ConditionElement = (String, Comparison, serde_json::Value);
 
+/// Prefix of the `include` conditions that test whether another action is completed.
+const COMPLETED_SELECTOR_PREFIX: &str = "completed:";
+
+/// Get the action that a `completed:<action>` condition references.
+///
+/// # Returns
+/// `None` when `include` is a JSON pointer.
+///
+pub(crate) fn completed_selector(include: &str) -> Option<&str> {
+    include.strip_prefix(COMPLETED_SELECTOR_PREFIX)
+}
+
 /// Directory selector
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "snake_case")]
-pub enum Selector {
+#[serde(try_from = "SelectorTable")]
+pub struct Selector {
+    /// The conditions that a directory must satisfy.
+    pub conditions: Conditions,
+
+    /// Policy for JSON pointers that are not present (overrides `group.missing`).
+    pub missing: Option<Missing>,
+}
+
+/// The conditions of a directory selector.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Conditions {
     Condition(ConditionElement),
     All(Vec<ConditionElement>),
 }
 
+/// A selector as given in the workflow file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SelectorTable {
+    condition: Option<ConditionElement>,
+    all: Option<Vec<ConditionElement>>,
+    missing: Option<Missing>,
+}
+
+impl TryFrom<SelectorTable> for Selector {
+    type Error = String;
+
+    fn try_from(table: SelectorTable) -> Result<Self, Self::Error> {
+        let conditions = match (table.condition, table.all) {
+            (Some(condition), None) => Conditions::Condition(condition),
+            (None, Some(all)) => Conditions::All(all),
+            _ => return Err("each selector must set one of `condition` or `all`".into()),
+        };
+        Ok(Self {
+            conditions,
+            missing: table.missing,
+        })
+    }
+}
+
+impl Selector {
+    /// Construct a selector with a single condition.
+    pub fn condition(condition: ConditionElement) -> Self {
+        Self {
+            conditions: Conditions::Condition(condition),
+            missing: None,
+        }
+    }
+
+    /// Construct a selector that requires all of the conditions.
+    pub fn all(conditions: Vec<ConditionElement>) -> Self {
+        Self {
+            conditions: Conditions::All(conditions),
+            missing: None,
+        }
+    }
+
+    /// Get the selector's conditions.
+    pub fn as_slice(&self) -> &[ConditionElement] {
+        match &self.conditions {
+            Conditions::Condition(condition) => slice::from_ref(condition),
+            Conditions::All(conditions) => conditions.as_slice(),
+        }
+    }
+}
+
+/// Policy for groups smaller than `minimum_size`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Leftover {
+    /// Submit the group.
+    #[default]
+    Submit,
+
+    /// Skip the group until enough directories are eligible.
+    Defer,
+
+    /// Submitting the group is an error.
+    Error,
+}
+
+/// Policy for JSON pointers that are not present in a directory's value.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Missing {
+    /// Missing elements are an error.
+    #[default]
+    Error,
+
+    /// Exclude the directory.
+    Exclude,
+
+    /// Use `null` in place of the missing element.
+    TreatAsNull,
+}
+
 /// Group definition.
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
@@ -214,9 +627,20 @@ pub struct Group {
     /// Maximum size of the submitted group.
     pub maximum_size: Option<usize>,
 
+    /// Minimum size of the submitted group.
+    pub minimum_size: Option<usize>,
+
+    /// How to submit groups smaller than `minimum_size`.
+    #[serde(default)]
+    pub leftover: Option<Leftover>,
+
     /// Submit only whole groups when true.
     #[serde(default)]
     pub submit_whole: Option<bool>,
+
+    /// How to handle `include` and `sort_by` pointers that are not present.
+    #[serde(default)]
+    pub missing: Option<Missing>,
 }
 
 /// Resource cost to execute an action.
@@ -305,18 +729,46 @@ impl Resources {
     /// `n_directories`: Number of directories in the submission.
     ///
     pub fn total_processes(&self, n_directories: usize) -> usize {
+        if !self.component.is_empty() {
+            return self
+                .component
+                .iter()
+                .map(|c| c.total_processes(n_directories))
+                .sum();
+        }
+
         match self.processes() {
-            Processes::PerDirectory(p) => p * n_directories,
+            Processes::PerDirectory(p) => p * self.concurrent_directories(n_directories),
             Processes::PerSubmission(p) => p,
         }
     }
 
+    /// Determine the number of directories that execute at the same time.
+    ///
+    /// # Arguments
+    /// `n_directories`: Number of directories in the submission.
+    ///
+    pub fn concurrent_directories(&self, n_directories: usize) -> usize {
+        match self.concurrent_directories {
+            Some(concurrent) => n_directories.min(concurrent),
+            None => n_directories,
+        }
+    }
+
     /// Determine the total number of CPUs this action will use.
     ///
     /// # Arguments
     /// `n_directories`: Number of directories in the submission.
     ///
     pub fn total_cpus(&self, n_directories: usize) -> usize {
+        if !self.component.is_empty() {
+            return self
+                .component
+                .iter()
+                .map(|c| c.total_cpus(n_directories))
+                .sum();
+        }
+
         self.total_processes(n_directories) * self.threads_per_process.unwrap_or(1)
     }
 
@@ -326,6 +778,14 @@ impl Resources {
     /// `n_directories`: Number of directories in the submission.
     ///
     pub fn total_gpus(&self, n_directories: usize) -> usize {
+        if !self.component.is_empty() {
+            return self
+                .component
+                .iter()
+                .map(|c| c.total_gpus(n_directories))
+                .sum();
+        }
+
         self.total_processes(n_directories) * self.gpus_per_process.unwrap_or(0)
     }
 
@@ -342,7 +802,8 @@ impl Resources {
             Walltime::PerDirectory(ref w) => Duration::new(
                 true,
                 0,
-                (w.signed_total_seconds() * (n_directories as i64)) as u32,
+                (w.signed_total_seconds() * (self.sequential_directories(n_directories) as i64))
+                    as u32,
                 0,
             )
             .expect("Valid duration."),
@@ -350,12 +811,59 @@ impl Resources {
         }
     }
 
+    /// Get the resources of a job that executes its directories with `row exec`.
+    ///
+    /// `row exec` executes each directory with the resources of a one-directory
+    /// submission, `concurrent_directories` (default 1) at a time. The job needs
+    /// the processes and GPUs of the directories that execute at the same time
+    /// and the walltime to execute the rest in sequence.
+    ///
+    #[must_use]
+    pub fn aggregated(&self) -> Self {
+        let processes = match self.processes() {
+            Processes::PerDirectory(p) | Processes::PerSubmission(p) => Processes::PerDirectory(p),
+        };
+        let walltime = match self.walltime() {
+            Walltime::PerDirectory(w) | Walltime::PerSubmission(w) => Walltime::PerDirectory(w),
+        };
+
+        Self {
+            processes: Some(processes),
+            walltime: Some(walltime),
+            component: self.component.iter().map(Self::aggregated).collect(),
+            concurrent_directories: Some(self.concurrent_directories.unwrap_or(1)),
+            ..self.clone()
+        }
+    }
+
+    /// Determine the number of directories that each concurrent task executes in sequence.
+    fn sequential_directories(&self, n_directories: usize) -> usize {
+        match self.concurrent_directories {
+            Some(concurrent) if concurrent > 0 => n_directories.div_ceil(concurrent),
+            _ => n_directories,
+        }
+    }
+
     /// Compute the total resource usage of an action execution.
     ///
     /// The cost is computed assuming that every job is executed to the full
     /// requested walltime.
     ///
     pub fn cost(&self, n_directories: usize) -> ResourceCost {
+        if !self.component.is_empty() {
+            return self
+                .component
+                .iter()
+                .map(|c| {
+                    Resources {
+                        walltime: self.walltime.clone(),
+                        ..c.clone()
+                    }
+                    .cost(n_directories)
+                })
+                .fold(ResourceCost::new(), |total, cost| total + cost);
+        }
+
         let process_hours = ((self.total_processes(n_directories) as i64)
             * self.total_walltime(n_directories).signed_total_seconds())
             as f64
@@ -383,18 +891,36 @@ impl Resources {
 
     /// Resolve omitted keys from the given template.
     fn resolve(&mut self, template: &Resources) {
-        if self.processes.is_none() {
-            self.processes.clone_from(&template.processes);
+        if self.component.is_empty()
+            && self.processes.is_none()
+            && self.threads_per_process.is_none()
+            && self.gpus_per_process.is_none()
+        {
+            self.component.clone_from(&template.component);
         }
-        if self.threads_per_process.is_none() {
-            self.threads_per_process = template.threads_per_process;
-        }
-        if self.gpus_per_process.is_none() {
-            self.gpus_per_process = template.gpus_per_process;
+        if self.component.is_empty() {
+            if self.processes.is_none() {
+                self.processes.clone_from(&template.processes);
+            }
+            if self.threads_per_process.is_none() {
+                self.threads_per_process = template.threads_per_process;
+            }
+            if self.gpus_per_process.is_none() {
+                self.gpus_per_process = template.gpus_per_process;
+            }
         }
         if self.walltime.is_none() {
             self.walltime.clone_from(&template.walltime);
         }
+        if self.binding.is_none() {
+            self.binding = template.binding;
+        }
+        if self.concurrent_directories.is_none() {
+            self.concurrent_directories = template.concurrent_directories;
+        }
+        if self.preset.is_none() {
+            self.preset.clone_from(&template.preset);
+        }
     }
 
     pub fn processes(&self) -> Processes {
@@ -419,90 +945,417 @@ impl Action {
         self.name.as_deref().unwrap_or("")
     }
 
-    /// Get the action's `command`.
-    pub fn command(&self) -> &str {
-        self.command.as_deref().unwrap_or("")
-    }
-
-    /// Get the action's `launchers`.
-    pub fn launchers(&self) -> &[String] {
-        self.launchers.as_deref().unwrap_or(&[])
+    /// Get the steps of the action's `command`.
+    ///
+    /// A single command has one step.
+    ///
+    pub fn commands(&self) -> &[String] {
+        match &self.command {
+            Some(ActionCommand::Single(command)) => slice::from_ref(command),
+            Some(ActionCommand::Steps(commands)) => commands,
+            None => &[],
+        }
     }
 
-    /// Get the action's `previous_actions`.
-    pub fn previous_actions(&self) -> &[String] {
-        self.previous_actions.as_deref().unwrap_or(&[])
+    /// Check whether any step of the action's `command` contains `pattern`.
+    pub fn command_contains(&self, pattern: &str) -> bool {
+        self.commands().iter().any(|c| c.contains(pattern))
     }
 
-    /// Get the action's products
-    pub fn products(&self) -> &[String] {
-        self.products.as_deref().unwrap_or(&[])
+    /// Get the action's `launchers` on the given cluster.
+    ///
+    /// When `launchers` is keyed by cluster, use the entry for `cluster_name`
+    /// and fall back to the `default` entry.
+    ///
+    pub fn launchers(&self, cluster_name: &str) -> &[String] {
+        match &self.launchers {
+            None => &[],
+            Some(Launchers::All(launchers)) => launchers,
+            Some(Launchers::ByCluster(launchers)) => launchers
+                .get(cluster_name)
+                .or_else(|| launchers.get("default"))
+                .map_or(&[], Vec::as_slice),
+        }
     }
 
-    /// Resolve the action's omitted keys with defaults
-    fn resolve(&mut self, template: &Action) {
-        if self.name.is_none() {
-            self.name.clone_from(&template.name);
-        }
-        if self.command.is_none() {
-            self.command.clone_from(&template.command);
-        }
-        if self.launchers.is_none() {
-            self.launchers.clone_from(&template.launchers);
+    /// Get the conditions in `group.include` and in the `include` of each previous action.
+    pub(crate) fn include_conditions(&self) -> Vec<&ConditionElement> {
+        let mut selectors = self.group.include().iter().collect::<Vec<_>>();
+        if let Some(PreviousActions::All(previous) | PreviousActions::Any { any: previous }) =
+            &self.previous_actions
+        {
+            selectors.extend(previous.iter().flat_map(PreviousAction::include));
         }
-        if self.previous_actions.is_none() {
-            self.previous_actions.clone_from(&template.previous_actions);
-        }
-        if self.products.is_none() {
-            self.products.clone_from(&template.products);
-        }
-
-        self.resources.resolve(&template.resources);
-        self.group.resolve(&template.group);
 
-        // Populate each action's submit_options with the global ones.
-        for (name, template_options) in &template.submit_options {
-            if self.submit_options.contains_key(name) {
-                let action_options = self
-                    .submit_options
-                    .get_mut(name)
-                    .expect("Key should be present");
-                if action_options.account.is_none() {
-                    action_options.account.clone_from(&template_options.account);
-                }
-                if action_options.setup.is_none() {
-                    action_options.setup.clone_from(&template_options.setup);
-                }
-                if action_options.partition.is_none() {
-                    action_options
-                        .partition
-                        .clone_from(&template_options.partition);
-                }
-                if action_options.custom.is_empty() {
-                    action_options.custom.clone_from(&template_options.custom);
-                }
-            } else {
-                self.submit_options
-                    .insert(name.clone(), template_options.clone());
-            }
-        }
+        selectors.into_iter().flat_map(Selector::as_slice).collect()
     }
-}
 
-impl Group {
-    /// Get the group's `include`.
-    pub fn include(&self) -> &[Selector] {
-        self.include.as_deref().unwrap_or(&[])
+    /// Get the names of the action's `previous_actions` in this project.
+    pub fn previous_actions(&self) -> Vec<&str> {
+        self.all_previous_actions()
+            .iter()
+            .filter(|p| p.project().is_none())
+            .map(PreviousAction::name)
+            .collect()
     }
 
-    /// Get the group's `sort_by`.
-    pub fn sort_by(&self) -> &[String] {
-        self.sort_by.as_deref().unwrap_or(&[])
+    /// Get the projects of the action's `previous_actions` in other projects.
+    pub fn previous_projects(&self) -> Vec<&Path> {
+        self.all_previous_actions()
+            .iter()
+            .filter_map(PreviousAction::project)
+            .collect()
     }
 
-    /// Get the group's `split_by_sort_key`.
-    pub fn split_by_sort_key(&self) -> bool {
-        self.split_by_sort_key.unwrap_or_default()
+    /// Get all elements of the action's `previous_actions`.
+    pub(crate) fn all_previous_actions(&self) -> &[PreviousAction] {
+        match &self.previous_actions {
+            None => &[],
+            Some(PreviousActions::All(previous) | PreviousActions::Any { any: previous }) => {
+                previous
+            }
+        }
+    }
+
+    /// Check whether the previous actions are completed.
+    ///
+    /// # Arguments
+    /// * `is_completed` - Return true when the given previous action is completed
+    ///   (and meets its threshold, if any).
+    /// * `is_included` - Return true when the directory matches any of the
+    ///   given selectors (or there are none).
+    ///
+    /// # Returns
+    /// `Ok(true)` when all (or any, with `previous_actions.any`) previous actions
+    /// that apply to the directory are completed. Always `Ok(true)` when no
+    /// previous actions apply.
+    ///
+    /// # Errors
+    /// Forwards errors from `is_included`.
+    ///
+    pub fn previous_actions_completed<C, I>(
+        &self,
+        is_completed: C,
+        is_included: I,
+    ) -> Result<bool, Error>
+    where
+        C: Fn(&PreviousAction) -> bool,
+        I: Fn(&[Selector]) -> Result<bool, Error>,
+    {
+        let (previous, any) = match &self.previous_actions {
+            None => return Ok(true),
+            Some(PreviousActions::All(previous)) => (previous, false),
+            Some(PreviousActions::Any { any }) => (any, true),
+        };
+
+        let mut applicable = 0;
+        for previous_action in previous {
+            if !is_included(previous_action.include())? {
+                continue;
+            }
+
+            applicable += 1;
+            let completed = is_completed(previous_action);
+            if any && completed {
+                return Ok(true);
+            }
+            if !any && !completed {
+                return Ok(false);
+            }
+        }
+
+        Ok(!any || applicable == 0)
+    }
+
+    /// Get the action's `eligible_when` command.
+    pub fn eligible_when(&self) -> Option<&str> {
+        self.eligible_when.as_deref()
+    }
+
+    /// Get the action's `log_output`.
+    pub fn log_output(&self) -> bool {
+        self.log_output.unwrap_or_default()
+    }
+
+    /// Get the name of a job that executes `directories`.
+    ///
+    /// Substitute `{action}`, `{group_index}`, `{group_id}`, and JSON pointers
+    /// (`{/pointer}`) in the action's `job_name`. `value` is the value of the
+    /// first directory in the group. When `job_name` is not set, name the job
+    /// after the action and the directories.
+    ///
+    /// Replace characters other than ASCII letters, digits, `-`, `_`, `.`, and
+    /// `+` with `_` so that the name cannot break the job script.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when `value` does not contain a JSON pointer in
+    /// `job_name`.
+    ///
+    pub fn job_name(
+        &self,
+        directories: &[PathBuf],
+        group_index: usize,
+        value: &serde_json::Value,
+    ) -> Result<String, Error> {
+        let Some(template) = &self.job_name else {
+            return Ok(sanitize_job_name(&match directories {
+                [] => self.name().to_string(),
+                [directory] => format!("{}-{}", self.name(), directory.display()),
+                [directory, rest @ ..] => {
+                    format!("{}-{}+{}", self.name(), directory.display(), rest.len())
+                }
+            }));
+        };
+
+        let mut result = String::with_capacity(template.len());
+        let mut rest = template.as_str();
+        while let Some((start, end)) = next_placeholder(rest) {
+            result.push_str(&rest[..start]);
+            match &rest[start..end] {
+                "{action}" => result.push_str(self.name()),
+                "{group_index}" => result.push_str(&group_index.to_string()),
+                "{group_id}" => result.push_str(&group_id(directories)),
+                placeholder => push_pointer(
+                    &mut result,
+                    placeholder,
+                    directories.first().map_or(Path::new(""), PathBuf::as_path),
+                    value,
+                )?,
+            }
+            rest = &rest[end..];
+        }
+        result.push_str(rest);
+
+        Ok(sanitize_job_name(&result))
+    }
+
+    /// Get the JSON pointers referenced by the action's `job_name`.
+    pub fn job_name_pointers(&self) -> Vec<String> {
+        template_pointers(self.job_name.as_deref().unwrap_or_default())
+    }
+
+    /// Get the action's products
+    pub fn products(&self) -> &[String] {
+        self.products.as_deref().unwrap_or(&[])
+    }
+
+    /// Check whether any of the action's products include JSON pointers.
+    pub fn has_product_templates(&self) -> bool {
+        self.products()
+            .iter()
+            .any(|p| next_placeholder(p).is_some())
+    }
+
+    /// Get the names of the action's products in a directory.
+    ///
+    /// Substitute JSON pointers (`{/pointer}`) in each product with the
+    /// elements of the directory's `value`.
+    ///
+    /// # Errors
+    /// `Err(row::Error)` when `value` does not contain a JSON pointer in
+    /// `products`.
+    ///
+    pub fn product_names(
+        &self,
+        directory: &Path,
+        value: &serde_json::Value,
+    ) -> Result<Vec<String>, Error> {
+        let mut names = Vec::with_capacity(self.products().len());
+        for product in self.products() {
+            let mut name = String::with_capacity(product.len());
+            let mut rest = product.as_str();
+            while let Some((start, end)) = next_placeholder(rest) {
+                name.push_str(&rest[..start]);
+                push_pointer(&mut name, &rest[start..end], directory, value)?;
+                rest = &rest[end..];
+            }
+            name.push_str(rest);
+            names.push(name);
+        }
+
+        Ok(names)
+    }
+
+    /// Get the JSON pointers referenced by the action's `products`.
+    pub fn product_pointers(&self) -> Vec<String> {
+        self.products()
+            .iter()
+            .flat_map(|p| template_pointers(p))
+            .collect()
+    }
+
+    /// Hash the fields that determine when the action is completed.
+    ///
+    /// The hash covers `products` and `previous_actions`. `State` stores it with
+    /// the completed cache to detect changes to these fields.
+    ///
+    pub fn completion_hash(&self) -> u64 {
+        let mut hash = FNV_OFFSET_BASIS;
+        for product in self.products() {
+            hash = fnv1a(hash, product.as_bytes());
+            hash = fnv1a(hash, &[0]);
+        }
+        hash = fnv1a(hash, &[0xff]);
+        fnv1a(hash, format!("{:?}", self.previous_actions).as_bytes())
+    }
+
+    /// Get the action's tags.
+    pub fn tags(&self) -> &[String] {
+        self.tags.as_deref().unwrap_or(&[])
+    }
+
+    /// Check whether the action has any of the given tags.
+    ///
+    /// Always `true` when `tags` is empty.
+    ///
+    pub fn has_any_tag(&self, tags: &[String]) -> bool {
+        tags.is_empty() || self.tags().iter().any(|t| tags.contains(t))
+    }
+
+    /// Resolve the action's omitted keys with defaults
+    fn resolve(&mut self, template: &Action) {
+        if self.name.is_none() {
+            self.name.clone_from(&template.name);
+        }
+        if self.command.is_none() {
+            self.command.clone_from(&template.command);
+        }
+        if self.launchers.is_none() {
+            self.launchers.clone_from(&template.launchers);
+        }
+        if self.previous_actions.is_none() {
+            self.previous_actions.clone_from(&template.previous_actions);
+        }
+        if self.eligible_when.is_none() {
+            self.eligible_when.clone_from(&template.eligible_when);
+        }
+        if self.products.is_none() {
+            self.products.clone_from(&template.products);
+        }
+        if self.tags.is_none() {
+            self.tags.clone_from(&template.tags);
+        }
+        if self.log_output.is_none() {
+            self.log_output = template.log_output;
+        }
+        if self.job_name.is_none() {
+            self.job_name.clone_from(&template.job_name);
+        }
+        if self.submit_command_override.is_none() {
+            self.submit_command_override
+                .clone_from(&template.submit_command_override);
+        }
+
+        self.resources.resolve(&template.resources);
+        self.group.resolve(&template.group);
+
+        // Populate each action's submit_options with the global ones.
+        for (name, template_options) in &template.submit_options {
+            if self.submit_options.contains_key(name) {
+                let action_options = self
+                    .submit_options
+                    .get_mut(name)
+                    .expect("Key should be present");
+                if action_options.account.is_none() {
+                    action_options.account.clone_from(&template_options.account);
+                }
+                if action_options.setup.is_none() {
+                    action_options.setup.clone_from(&template_options.setup);
+                }
+                if action_options.partition.is_none() {
+                    action_options
+                        .partition
+                        .clone_from(&template_options.partition);
+                }
+                if action_options.qos.is_none() {
+                    action_options.qos.clone_from(&template_options.qos);
+                }
+                if action_options.custom.is_empty() {
+                    action_options.custom.clone_from(&template_options.custom);
+                }
+            } else {
+                self.submit_options
+                    .insert(name.clone(), template_options.clone());
+            }
+        }
+    }
+}
+
+impl PreviousAction {
+    /// Get the name of the previous action.
+    pub fn name(&self) -> &str {
+        match self {
+            PreviousAction::Name(name)
+            | PreviousAction::Project { action: name, .. }
+            | PreviousAction::Threshold { action: name, .. }
+            | PreviousAction::Conditional { action: name, .. } => name,
+        }
+    }
+
+    /// Get the path to the other project that the previous action is in.
+    ///
+    /// `None` when the previous action is in this project.
+    ///
+    pub fn project(&self) -> Option<&Path> {
+        match self {
+            PreviousAction::Project { project, .. } => Some(project),
+            PreviousAction::Name(_)
+            | PreviousAction::Threshold { .. }
+            | PreviousAction::Conditional { .. } => None,
+        }
+    }
+
+    /// Get the selectors that determine when the previous action is required.
+    ///
+    /// Empty when the previous action is always required.
+    ///
+    pub fn include(&self) -> &[Selector] {
+        match self {
+            PreviousAction::Name(_) => &[],
+            PreviousAction::Project { include, .. }
+            | PreviousAction::Threshold { include, .. }
+            | PreviousAction::Conditional { include, .. } => include,
+        }
+    }
+
+    /// Get the number of directories that must complete the previous action.
+    ///
+    /// `None` when the previous action is required only in the directory itself.
+    ///
+    pub fn threshold(&self) -> Option<&Threshold> {
+        match self {
+            PreviousAction::Threshold { threshold, .. } => Some(threshold),
+            _ => None,
+        }
+    }
+}
+
+impl Threshold {
+    /// Check whether `completed` of `total` directories meet the threshold.
+    pub fn is_met(&self, completed: usize, total: usize) -> bool {
+        match self {
+            Threshold::Fraction { fraction } => {
+                total > 0 && completed as f64 >= fraction * total as f64
+            }
+            Threshold::Count { count } => completed >= *count,
+        }
+    }
+}
+
+impl Group {
+    /// Get the group's `include`.
+    pub fn include(&self) -> &[Selector] {
+        self.include.as_deref().unwrap_or(&[])
+    }
+
+    /// Get the group's `sort_by`.
+    pub fn sort_by(&self) -> &[String] {
+        self.sort_by.as_deref().unwrap_or(&[])
+    }
+
+    /// Get the group's `split_by_sort_key`.
+    pub fn split_by_sort_key(&self) -> bool {
+        self.split_by_sort_key.unwrap_or_default()
     }
 
     /// Get the group's `reverse_sort`.
@@ -515,6 +1368,16 @@ impl Group {
         self.submit_whole.unwrap_or_default()
     }
 
+    /// Get the group's `leftover`.
+    pub fn leftover(&self) -> Leftover {
+        self.leftover.unwrap_or_default()
+    }
+
+    /// Get the group's `missing`.
+    pub fn missing(&self) -> Missing {
+        self.missing.unwrap_or_default()
+    }
+
     /// Resolve omitted keys from the given template.
     fn resolve(&mut self, template: &Group) {
         if self.include.is_none() {
@@ -532,32 +1395,104 @@ impl Group {
         if self.maximum_size.is_none() {
             self.maximum_size = template.maximum_size;
         }
+        if self.minimum_size.is_none() {
+            self.minimum_size = template.minimum_size;
+        }
+        if self.leftover.is_none() {
+            self.leftover = template.leftover;
+        }
         if self.submit_whole.is_none() {
             self.submit_whole = template.submit_whole;
         }
+        if self.missing.is_none() {
+            self.missing = template.missing;
+        }
     }
 }
 
 impl Workflow {
     /// Open the workflow
     ///
-    /// Find `workflow.toml` in the current working directory or any parent directory. Open the
-    /// file, parse it, and return a `Workflow`.
+    /// Find `workflow.toml` (or `workflow.yaml`) in the current working directory or any
+    /// parent directory. Open the file, parse it, and return a `Workflow`.
     ///
     /// # Errors
     /// Returns `Err(row::Error)` when the file is not found, cannot be read, or there is a parse
     /// error.
     ///
     pub fn open() -> Result<Self, Error> {
-        let (path, file) = find_and_open_workflow()?;
+        let workflow = Self::open_in(&env::current_dir()?)?;
+        match env::var_os("ROW_CACHE_DIR") {
+            Some(path) if !path.is_empty() => workflow.with_cache_directory(Path::new(&path)),
+            _ => Ok(workflow),
+        }
+    }
+
+    /// Open the workflow and optionally operate on a different workspace.
+    ///
+    /// See `with_workspace`.
+    ///
+    /// # Errors
+    /// Returns `Err(row::Error)` when the workflow cannot be opened.
+    ///
+    pub fn open_with_workspace(workspace: Option<&Path>) -> Result<Self, Error> {
+        let workflow = Self::open()?;
+        match workspace {
+            Some(path) => workflow.with_workspace(path),
+            None => Ok(workflow),
+        }
+    }
+
+    /// Open the workflow in the given directory or any parent directory.
+    ///
+    /// # Errors
+    /// Returns `Err(row::Error)` when the file is not found, cannot be read, or there is a parse
+    /// error.
+    ///
+    pub fn open_in(path: &Path) -> Result<Self, Error> {
+        let (path, file_name, file) = find_and_open_workflow(path)?;
         let mut buffer = BufReader::new(file);
         let mut workflow_string = String::new();
         buffer
             .read_to_string(&mut workflow_string)
-            .map_err(|e| Error::FileRead(path.join("workflow.toml"), e))?;
+            .map_err(|e| Error::FileRead(path.join(file_name), e))?;
+
+        Self::open_file_str(&path, file_name, &workflow_string)
+    }
+
+    /// Build a workflow from the contents of the workflow file `file_name` in `path`.
+    ///
+    /// When the workflow sets `generator`, execute it (or reuse its cached output)
+    /// and parse its output in the same format as `file_name`.
+    ///
+    /// # Errors
+    /// Returns `Err(row::Error)` when there is a parse error, the generator fails,
+    /// or the workflow is invalid.
+    ///
+    pub(crate) fn open_file_str(
+        path: &Path,
+        file_name: &str,
+        contents: &str,
+    ) -> Result<Self, Error> {
+        trace!("Parsing '{}'.", path.join(file_name).display());
+        let workflow = Self::parse(path, file_name, contents, &path.join(file_name))?;
+
+        let Some(generator) = workflow.generator else {
+            return Ok(workflow);
+        };
 
-        trace!("Parsing '{}/workflow.toml'.", &path.display());
-        Self::open_str(&path, &workflow_string)
+        if !workflow.action.is_empty() {
+            warn!("Ignoring the actions in '{file_name}'. The generator '{generator}' provides the workflow.");
+        }
+
+        let (generated_path, generated) = generate(path, file_name, &generator, contents)?;
+        trace!("Parsing '{}'.", generated_path.display());
+        let workflow = Self::parse(path, file_name, &generated, &generated_path)?;
+        if workflow.generator.is_some() {
+            return Err(Error::NestedGenerator(generator));
+        }
+
+        Ok(workflow)
     }
 
     /// Build a workflow from a given path and toml string.
@@ -568,13 +1503,118 @@ impl Workflow {
     /// Returns `Err(row::Error)` when the file is not found, cannot be read, or there is a parse
     /// error.
     ///
+    #[cfg(test)]
     pub(crate) fn open_str(path: &Path, toml: &str) -> Result<Self, Error> {
-        let mut workflow: Workflow =
-            toml::from_str(toml).map_err(|e| Error::TOMLParse(path.join("workflow.toml"), e))?;
+        Self::parse(
+            path,
+            TOML_WORKFLOW_FILE_NAME,
+            toml,
+            &path.join(TOML_WORKFLOW_FILE_NAME),
+        )
+    }
+
+    /// Build a workflow from a given path and YAML string.
+    ///
+    /// Parse the contents of the given string as if it were `workflow.yaml` at the given `path`.
+    ///
+    /// # Errors
+    /// Returns `Err(row::Error)` when there is a parse error or the workflow is invalid.
+    ///
+    #[cfg(test)]
+    pub(crate) fn open_yaml_str(path: &Path, yaml: &str) -> Result<Self, Error> {
+        Self::parse(
+            path,
+            YAML_WORKFLOW_FILE_NAME,
+            yaml,
+            &path.join(YAML_WORKFLOW_FILE_NAME),
+        )
+    }
+
+    /// Parse and validate a workflow in the format of `file_name`.
+    ///
+    /// Parse errors refer to `source`.
+    ///
+    fn parse(path: &Path, file_name: &str, contents: &str, source: &Path) -> Result<Self, Error> {
+        let mut workflow: Workflow = if file_name == YAML_WORKFLOW_FILE_NAME {
+            let yaml_error = |e| Error::YAMLParse(source.into(), e);
+
+            // serde_norway expects YAML tags for enums. Convert through a JSON value so
+            // that enums are single-key tables, as they are in `workflow.toml`.
+            let value: Option<serde_json::Value> =
+                serde_norway::from_str(contents).map_err(yaml_error)?;
+            match value {
+                Some(value) => serde_json::from_value(value)
+                    .map_err(|e| yaml_error(serde::de::Error::custom(e)))?,
+                None => Workflow::default(),
+            }
+        } else {
+            toml::from_str(contents).map_err(|e| Error::TOMLParse(source.into(), e))?
+        };
+
         workflow.root = path.canonicalize()?;
         workflow.validate_and_set_defaults()
     }
 
+    /// Operate on a different workspace directory.
+    ///
+    /// `path` is relative to the current directory. The cache files for any
+    /// workspace other than the one in the workflow file are stored separately
+    /// in `.row/workspaces/`.
+    ///
+    /// # Errors
+    /// Returns `Err(row::Error)` when the current directory cannot be determined.
+    ///
+    pub fn with_workspace(mut self, path: &Path) -> Result<Self, Error> {
+        let path = path.absolutize()?.to_path_buf();
+        let default = self.root.join(&self.workspace.path);
+        if path == default.absolutize()? {
+            return Ok(self);
+        }
+
+        debug!("Using the workspace '{}'.", path.display());
+        let hash = fnv1a(FNV_OFFSET_BASIS, path.as_os_str().as_encoded_bytes());
+        self.workspace_cache =
+            Some(PathBuf::from(WORKSPACES_DIRECTORY_NAME).join(format!("{hash:016x}")));
+        self.workspace.path = path;
+        self.backend = workspace::BackendCache::default();
+        Ok(self)
+    }
+
+    /// Store the cache files in `path` instead of `.row` in the project root.
+    ///
+    /// The cache files are stored in a subdirectory of `path` named by a hash of
+    /// the project root so that several projects may share one `path`.
+    ///
+    /// # Errors
+    /// Returns `Err(row::Error)` when the current directory cannot be determined.
+    ///
+    pub fn with_cache_directory(mut self, path: &Path) -> Result<Self, Error> {
+        let path = path.absolutize()?.to_path_buf();
+        debug!("Using the cache directory '{}'.", path.display());
+        self.cache_directory = Some(path);
+        Ok(self)
+    }
+
+    /// Get the directory given to `with_cache_directory`, if any.
+    pub fn cache_directory(&self) -> Option<&Path> {
+        self.cache_directory.as_deref()
+    }
+
+    /// Get the directory that holds the workspace's cache files.
+    pub fn data_directory(&self) -> PathBuf {
+        let data_directory = self.cache_directory.as_ref().map_or_else(
+            || self.root.join(DATA_DIRECTORY_NAME),
+            |path| {
+                let hash = fnv1a(FNV_OFFSET_BASIS, self.root.as_os_str().as_encoded_bytes());
+                path.join(format!("{hash:016x}"))
+            },
+        );
+        match &self.workspace_cache {
+            Some(subdirectory) => data_directory.join(subdirectory),
+            None => data_directory,
+        }
+    }
+
     /// Find the action that matches the given name.
     pub fn action_by_name(&self, name: &str) -> Option<&Action> {
         if let Some(action_index) = self.action.iter().position(|a| a.name() == name) {
@@ -584,26 +1624,245 @@ impl Workflow {
         }
     }
 
-    /// Validate a `Workflow` and populate defaults.
+    /// Make an `ActionNotFound` error that suggests similar action names.
+    pub fn action_not_found(&self, name: &str) -> Error {
+        Error::ActionNotFound(
+            name.into(),
+            suggest::did_you_mean(name, self.action.iter().map(Action::name)),
+        )
+    }
+
+    /// Choose the number of threads to use for IO intensive operations.
     ///
-    /// Resolve each action to a fully defined struct with defaults populated
-    /// from: The current action, the action named by "from", and the default
-    /// action (in that order).
+    /// `io_threads` (given on the command line) takes precedence over
+    /// `default.io_threads`, which takes precedence over `DEFAULT_IO_THREADS`.
     ///
-    fn validate_and_set_defaults(mut self) -> Result<Self, Error> {
-        let mut action_names = HashSet::with_capacity(self.action.len());
+    pub fn io_threads(&self, io_threads: Option<u16>) -> u16 {
+        io_threads
+            .or(self.default.io_threads.map(NonZeroU16::get))
+            .unwrap_or(DEFAULT_IO_THREADS)
+    }
 
-        if self.default.action.from.is_some() {
-            return Err(Error::DefaultActionSetsFrom());
+    /// Mask the redacted submit options in a job script.
+    ///
+    /// Replace the values on the `#SBATCH --account=` and `#SBATCH --qos=`
+    /// lines, the `#SBATCH` lines of the custom options, and the setup commands
+    /// (of any action on the given cluster) with `<redacted>`. Leave the rest of
+    /// the script unchanged. Use this to hide sensitive values in scripts that
+    /// row shows to the user or stores on disk. Submit the original script to
+    /// the scheduler.
+    ///
+    pub fn redact(&self, cluster_name: &str, script: &str) -> String {
+        if self.redact.is_empty() {
+            return script.to_string();
         }
 
-        let source_actions = self.action.clone();
+        let mut custom: HashSet<&str> = HashSet::new();
+        let mut setups: Vec<&str> = Vec::new();
+        for submit_options in self
+            .action
+            .iter()
+            .filter_map(|a| a.submit_options.get(cluster_name))
+        {
+            if self.redact.contains(&RedactedOption::Custom) {
+                custom.extend(submit_options.custom.iter().map(String::as_str));
+            }
+            if self.redact.contains(&RedactedOption::Setup) {
+                setups.extend(submit_options.setup.as_deref());
+            }
+        }
 
-        for (action_idx, action) in self.action.iter_mut().enumerate() {
-            if let Some(from) = &action.from {
-                if let Some(action_index) = source_actions.iter().position(|a| a.name() == from) {
-                    if let Some(recursive_from) = &source_actions[action_index].from {
-                        return Err(Error::RecursiveFrom(recursive_from.clone()));
+        let mut script = script.to_string();
+        setups.retain(|s| !s.is_empty());
+        setups.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        setups.dedup();
+        for setup in setups {
+            script = script.replace(
+                &format!("\n{setup}\n\n{SETUP_CHECK}"),
+                &format!("\n{REDACTED}\n\n{SETUP_CHECK}"),
+            );
+        }
+
+        let mut result = String::with_capacity(script.len());
+        for line in script.split_inclusive('\n') {
+            let (content, newline) = match line.strip_suffix('\n') {
+                Some(content) => (content, "\n"),
+                None => (line, ""),
+            };
+            let masked = [
+                (RedactedOption::Account, "#SBATCH --account="),
+                (RedactedOption::Qos, "#SBATCH --qos="),
+            ]
+            .into_iter()
+            .find(|(option, prefix)| self.redact.contains(option) && content.starts_with(prefix))
+            .map(|(_, prefix)| prefix)
+            .or_else(|| {
+                content
+                    .strip_prefix("#SBATCH ")
+                    .is_some_and(|option| custom.contains(option))
+                    .then_some("#SBATCH ")
+            });
+
+            match masked {
+                Some(prefix) => {
+                    result.push_str(prefix);
+                    result.push_str(REDACTED);
+                }
+                None => result.push_str(content),
+            }
+            result.push_str(newline);
+        }
+        result
+    }
+
+    /// Mask the redacted submit options in a workflow file.
+    ///
+    /// Parse `contents` as TOML (or YAML when `path` ends in `.yaml`), replace
+    /// the values of the redacted options in every `submit_options` table with
+    /// `<redacted>`, and serialize the result in the same format. The result
+    /// omits the comments in `contents`.
+    ///
+    /// # Errors
+    /// Returns `Err(row::Error)` when `contents` cannot be parsed.
+    ///
+    /// # Panics
+    /// When the masked value cannot be serialized as YAML.
+    ///
+    pub fn redact_file(&self, path: &Path, contents: &str) -> Result<String, Error> {
+        let yaml = path.extension().is_some_and(|e| e == "yaml");
+        let mut value: serde_json::Value = if yaml {
+            serde_norway::from_str(contents).map_err(|e| Error::YAMLParse(path.into(), e))?
+        } else {
+            toml::from_str(contents).map_err(|e| Error::TOMLParse(path.into(), e))?
+        };
+        if value.is_null() || self.redact.is_empty() {
+            return Ok(contents.to_string());
+        }
+
+        if let Some(action) = value.pointer_mut("/default/action") {
+            self.redact_submit_options(action);
+        }
+        if let Some(serde_json::Value::Array(actions)) = value.get_mut("action") {
+            for action in actions {
+                self.redact_submit_options(action);
+            }
+        }
+
+        if yaml {
+            Ok(serde_norway::to_string(&value).expect("JSON values serialize to YAML"))
+        } else {
+            toml::to_string(&value).map_err(|e| Error::TOMLSerialize(path.into(), e))
+        }
+    }
+
+    /// Mask the redacted options in the `submit_options` table of an action.
+    fn redact_submit_options(&self, action: &mut serde_json::Value) {
+        let Some(serde_json::Value::Object(clusters)) = action.get_mut("submit_options") else {
+            return;
+        };
+        for submit_options in clusters.values_mut() {
+            for option in &self.redact {
+                let key = match option {
+                    RedactedOption::Account => "account",
+                    RedactedOption::Custom => "custom",
+                    RedactedOption::Qos => "qos",
+                    RedactedOption::Setup => "setup",
+                };
+                match submit_options.get_mut(key) {
+                    Some(serde_json::Value::Array(values)) => {
+                        for value in values {
+                            *value = REDACTED.into();
+                        }
+                    }
+                    Some(value) => *value = REDACTED.into(),
+                    None => (),
+                }
+            }
+        }
+    }
+
+    /// Get the JSON pointers to cache from each directory value.
+    ///
+    /// # Returns
+    /// `None` when row should cache the entire value. Otherwise, the sorted
+    /// pointers referenced by `group.include`, `group.sort_by`, conditional
+    /// `previous_actions`, and `job_name` in all actions.
+    ///
+    pub fn value_pointers(&self) -> Option<Vec<String>> {
+        if self.workspace.value_cache == ValueCache::All {
+            return None;
+        }
+
+        let mut pointers = BTreeSet::new();
+        for action in &self.action {
+            pointers.extend(
+                action
+                    .include_conditions()
+                    .into_iter()
+                    .map(|(include, _, _)| include)
+                    .filter(|include| completed_selector(include).is_none())
+                    .cloned(),
+            );
+            pointers.extend(action.group.sort_by().iter().cloned());
+            pointers.extend(action.job_name_pointers());
+            pointers.extend(action.product_pointers());
+        }
+
+        Some(pointers.into_iter().collect())
+    }
+
+    /// Fill omitted resources in the default and each action from their presets.
+    fn resolve_presets(&mut self) -> Result<(), Error> {
+        for (name, preset) in &self.resources.presets {
+            if preset.preset.is_some() {
+                return Err(Error::NestedResourcePreset(name.clone()));
+            }
+        }
+
+        resolve_preset(
+            &self.resources.presets,
+            &mut self.default.action.resources,
+            "default",
+        )?;
+        for action in &mut self.action {
+            let name = action.name().to_string();
+            resolve_preset(&self.resources.presets, &mut action.resources, &name)?;
+        }
+        Ok(())
+    }
+
+    /// Validate a `Workflow` and populate defaults.
+    ///
+    /// Resolve each action to a fully defined struct with defaults populated
+    /// from: The current action, its resource preset, the action named by
+    /// "from", and the default action (in that order).
+    ///
+    fn validate_and_set_defaults(mut self) -> Result<Self, Error> {
+        let mut action_names = HashSet::with_capacity(self.action.len());
+
+        if let Some(url) = &self.workspace.url {
+            workspace::parse_s3_url(url)?;
+        }
+
+        if self.workspace.value_mode == ValueMode::Consolidated
+            && self.workspace.value_file.is_none()
+        {
+            return Err(Error::ConsolidatedValueFileMissing);
+        }
+
+        if self.default.action.from.is_some() {
+            return Err(Error::DefaultActionSetsFrom());
+        }
+
+        self.resolve_presets()?;
+
+        let source_actions = self.action.clone();
+
+        for (action_idx, action) in self.action.iter_mut().enumerate() {
+            if let Some(from) = &action.from {
+                if let Some(action_index) = source_actions.iter().position(|a| a.name() == from) {
+                    if let Some(recursive_from) = &source_actions[action_index].from {
+                        return Err(Error::RecursiveFrom(recursive_from.clone()));
                     }
 
                     action.resolve(&source_actions[action_index]);
@@ -620,10 +1879,45 @@ impl Workflow {
             if action.name.is_none() {
                 return Err(Error::ActionMissingName(action_idx));
             }
-            if action.command.is_none() {
+            if action.commands().is_empty() {
                 return Err(Error::ActionMissingCommand(action.name().into()));
             }
 
+            validate_resources(action)?;
+
+            if action
+                .commands()
+                .iter()
+                .any(|c| c.matches("{launcher}").count() > 1)
+            {
+                return Err(Error::DuplicateLauncherPlaceholder(action.name().into()));
+            }
+
+            if action.log_output() && !action.command_contains("{directory}") {
+                return Err(Error::InvalidLogOutput(action.name().into()));
+            }
+
+            if let Some(job_name) = &action.job_name {
+                validate_job_name(action.name(), job_name)?;
+            }
+
+            if action
+                .submit_command_override
+                .as_ref()
+                .is_some_and(|c| !c.contains("{script}"))
+            {
+                return Err(Error::SubmitCommandMissingScript(action.name().into()));
+            }
+
+            for product in action.products() {
+                validate_product(action.name(), product)?;
+            }
+            if action.has_product_templates() && self.workspace.value_file.is_none() {
+                return Err(Error::ProductsRequireValueFile(action.name().into()));
+            }
+
+            validate_conditions(action)?;
+
             // Warn for apparently invalid sort_by.
             for pointer in action.group.sort_by() {
                 if !pointer.is_empty() && !pointer.starts_with('/') {
@@ -633,12 +1927,16 @@ impl Workflow {
         }
 
         for action in &self.action {
-            for previous_action in action.previous_actions() {
-                if !action_names.contains(previous_action) {
-                    return Err(Error::PreviousActionNotFound(
-                        previous_action.clone(),
-                        action.name().into(),
-                    ));
+            validate_previous_actions(action, &action_names)?;
+
+            for (include, _, _) in action.include_conditions() {
+                if let Some(other) = completed_selector(include) {
+                    if !action_names.contains(other) {
+                        return Err(Error::IncludeActionNotFound(
+                            other.into(),
+                            action.name().into(),
+                        ));
+                    }
                 }
             }
 
@@ -665,10 +1963,47 @@ impl Default for Workspace {
         Self {
             path: default_workspace_path(),
             value_file: None,
+            value_mode: ValueMode::default(),
+            non_utf8_names: NonUtf8Names::default(),
+            value_cache: ValueCache::default(),
+            minimum_free: None,
+            products_checksum_file: None,
+            products_checksum: ProductsChecksum::default(),
+            url: None,
         }
     }
 }
 
+/// Order actions so that each comes after the actions in its `previous_actions`.
+///
+/// Only dependencies between the given actions affect the order. Otherwise,
+/// the actions keep their given order. Actions in a dependency cycle keep their
+/// given order.
+///
+pub fn order_by_previous_actions<'a>(actions: &[&'a Action]) -> Vec<&'a Action> {
+    let mut remaining: Vec<&Action> = actions.to_vec();
+    let mut result = Vec::with_capacity(actions.len());
+
+    while !remaining.is_empty() {
+        let ready = remaining.iter().position(|action| {
+            action.previous_actions().iter().all(|previous| {
+                *previous == action.name() || remaining.iter().all(|a| a.name() != *previous)
+            })
+        });
+
+        let index = ready.unwrap_or_else(|| {
+            warn!(
+                "Actions {:?} depend on each other in a cycle.",
+                remaining.iter().map(|a| a.name()).collect::<Vec<_>>()
+            );
+            0
+        });
+        result.push(remaining.remove(index));
+    }
+
+    result
+}
+
 /// The default value for workspace.path.
 fn default_workspace_path() -> PathBuf {
     PathBuf::from("workspace")
@@ -684,42 +2019,374 @@ where
     Ok(duration)
 }
 
-/// Finds and opens the file `workflow.toml`.
+/// Parse a number of bytes with an optional unit.
 ///
-/// Looks in the current working directory and all parent directories.
+/// `KB`, `MB`, `GB`, and `TB` are powers of 1000. `K`, `M`, `G`, and `T` (or
+/// `KiB`, `MiB`, `GiB`, and `TiB`) are powers of 1024.
+///
+fn parse_bytes(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (number, unit) = s.split_at(
+        s.find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len()),
+    );
+    let scale: u64 = match unit.trim() {
+        "" | "B" => 1,
+        "KB" => 1000,
+        "MB" => 1000_u64.pow(2),
+        "GB" => 1000_u64.pow(3),
+        "TB" => 1000_u64.pow(4),
+        "K" | "KiB" => 1024,
+        "M" | "MiB" => 1024_u64.pow(2),
+        "G" | "GiB" => 1024_u64.pow(3),
+        "T" | "TiB" => 1024_u64.pow(4),
+        _ => return Err(format!("'{s}' is not a size (for example: 500GB or 2TiB)")),
+    };
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("'{s}' is not a size (for example: 500GB or 2TiB)"))?;
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    Ok((number * scale as f64).round() as u64)
+}
+
+/// Parse sizes from strings.
+fn deserialize_bytes_from_str<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    parse_bytes(&s).map(Some).map_err(serde::de::Error::custom)
+}
+
+/// Finds and opens the file `workflow.toml` or `workflow.yaml`.
+///
+/// Looks in `path` and all parent directories.
 ///
 /// # Errors
-/// Returns `Err(row::Error)` when the file is not found or cannot be opened.
+/// Returns `Err(row::Error)` when the file is not found, cannot be opened, or
+/// both files are present in the same directory.
 ///
 /// # Returns
-/// `Ok(PathBuf, File)` including the path where the file was found and the open file handle.
+/// `Ok(PathBuf, &str, File)` including the path where the file was found, the
+/// name of the file, and the open file handle.
 ///
-fn find_and_open_workflow() -> Result<(PathBuf, File), Error> {
-    let mut path = env::current_dir()?;
-
-    let workflow_file = loop {
-        path.push("workflow.toml");
-        trace!("Checking {}.", path.display());
-
-        let workflow_file_result = File::open(&path);
-        match workflow_file_result {
-            Ok(file) => break file,
-            Err(error) => match error.kind() {
-                io::ErrorKind::NotFound => (),
-                _ => return Err(Error::FileRead(path, error)),
-            },
+fn find_and_open_workflow(path: &Path) -> Result<(PathBuf, &'static str, File), Error> {
+    let mut path = path.to_path_buf();
+
+    loop {
+        let mut found = Vec::new();
+        for file_name in [TOML_WORKFLOW_FILE_NAME, YAML_WORKFLOW_FILE_NAME] {
+            let file_path = path.join(file_name);
+            trace!("Checking {}.", file_path.display());
+
+            match File::open(&file_path) {
+                Ok(file) => found.push((file_name, file)),
+                Err(error) => match error.kind() {
+                    io::ErrorKind::NotFound => (),
+                    _ => return Err(Error::FileRead(file_path, error)),
+                },
+            }
+        }
+
+        if found.len() > 1 {
+            return Err(Error::MultipleWorkflowFiles(path));
+        }
+
+        if let Some((file_name, file)) = found.pop() {
+            debug!("Found project in '{}'.", path.display());
+            return Ok((path, file_name, file));
         }
 
-        path.pop();
         if !path.pop() {
             return Err(Error::WorkflowNotFound);
         }
-    };
+    }
+}
+
+/// Execute the workflow generator and return the path to and contents of its output.
+///
+/// Reuse the output cached in the data directory when the generator inputs have not
+/// changed. The inputs are the `generator` command, the contents of the workflow
+/// file, and the contents of every file named in the command.
+///
+/// # Errors
+/// Returns `Err(row::Error)` when the generator fails or the cache cannot be written.
+///
+fn generate(
+    path: &Path,
+    file_name: &str,
+    generator: &str,
+    contents: &str,
+) -> Result<(PathBuf, String), Error> {
+    let mut hash = fnv1a(FNV_OFFSET_BASIS, generator.as_bytes().iter().chain(&[0xff]));
+    hash = fnv1a(hash, contents.as_bytes().iter().chain(&[0xff]));
+    for word in generator.split_whitespace() {
+        let input = path.join(word);
+        if input.is_file() {
+            let bytes = fs::read(&input).map_err(|e| Error::FileRead(input.clone(), e))?;
+            hash = fnv1a(hash, bytes.iter().chain(&[0xff]));
+        }
+    }
+    let header = format!("# Generated by '{generator}' with input hash {hash:016x}.\n");
+
+    let data_directory = path.join(DATA_DIRECTORY_NAME);
+    let generated_path = data_directory.join(format!("{GENERATED_WORKFLOW_PREFIX}{file_name}"));
+    if let Ok(cached) = fs::read_to_string(&generated_path) {
+        if let Some(generated) = cached.strip_prefix(&header) {
+            debug!("Using the cached output of '{generator}'.");
+            return Ok((generated_path, generated.into()));
+        }
+    }
+
+    debug!("Executing the workflow generator '{generator}'.");
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(generator)
+        .current_dir(path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| Error::SpawnProcess(generator.into(), e))?;
+
+    if !output.status.success() {
+        let message = match output.status.code() {
+            None => format!("'{generator}' was terminated by a signal"),
+            Some(code) => format!("'{generator}' exited with code {code}"),
+        };
+        return Err(Error::ExecuteCommand(
+            "the workflow generator".into(),
+            message,
+            String::from_utf8_lossy(&output.stderr).into(),
+        ));
+    }
+
+    let generated = String::from_utf8_lossy(&output.stdout).into_owned();
+
+    // The project may be read-only (see `--cache-dir`).
+    if let Err(error) = fs::create_dir_all(&data_directory)
+        .and_then(|()| fs::write(&generated_path, format!("{header}{generated}")))
+    {
+        warn!(
+            "Unable to cache the output of '{generator}' in '{}': {error}",
+            generated_path.display()
+        );
+    }
+
+    Ok((generated_path, generated))
+}
+
+/// Fill omitted keys in `resources` from the preset that it names.
+fn resolve_preset(
+    presets: &HashMap<String, Resources>,
+    resources: &mut Resources,
+    action_name: &str,
+) -> Result<(), Error> {
+    if let Some(name) = &resources.preset {
+        let preset = presets
+            .get(name)
+            .ok_or_else(|| Error::ResourcePresetNotFound(name.clone(), action_name.into()))?;
+        resources.resolve(preset);
+    }
+    Ok(())
+}
+
+/// Validate the resources of a resolved action.
+fn validate_resources(action: &Action) -> Result<(), Error> {
+    let resources = &action.resources;
+    if !resources.component.is_empty() {
+        if resources.processes.is_some()
+            || resources.threads_per_process.is_some()
+            || resources.gpus_per_process.is_some()
+        {
+            return Err(Error::InvalidComponent(
+                action.name().into(),
+                "set processes, threads_per_process, and gpus_per_process in each component".into(),
+            ));
+        }
+        if resources
+            .component
+            .iter()
+            .any(|c| c.walltime.is_some() || !c.component.is_empty() || c.preset.is_some())
+        {
+            return Err(Error::InvalidComponent(
+                action.name().into(),
+                "components may not set walltime, component, or preset".into(),
+            ));
+        }
+    }
+
+    if let Some(concurrent) = resources.concurrent_directories {
+        let invalid = |reason: &str| {
+            Err(Error::InvalidConcurrentDirectories(
+                action.name().into(),
+                reason.into(),
+            ))
+        };
+        if concurrent == 0 {
+            return invalid("must be at least 1");
+        }
+        if !resources.component.is_empty() {
+            return invalid("heterogeneous jobs may not execute directories concurrently");
+        }
+        if !matches!(resources.processes(), Processes::PerDirectory(_)) {
+            return invalid("set processes.per_directory");
+        }
+        if !action.command_contains("{directory}") {
+            return invalid("the command must include {directory}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Find the first `{...}` placeholder in `text`.
+///
+/// # Returns
+/// `Some((start, end))` with the byte range of the placeholder. An
+/// unterminated placeholder extends to the end of `text`.
+///
+pub(crate) fn next_placeholder(text: &str) -> Option<(usize, usize)> {
+    let start = text.find('{')?;
+    let end = text[start..]
+        .find('}')
+        .map_or(text.len(), |end| start + end + 1);
+    Some((start, end))
+}
+
+/// Replace the characters in a job name that schedulers may not accept.
+fn sanitize_job_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '+') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
 
-    path.pop();
-    debug!("Found project in '{}'.", path.display());
+/// Append the element of `value` that the placeholder `{/pointer}` names.
+///
+/// Strings are appended without quotes.
+///
+pub(crate) fn push_pointer(
+    result: &mut String,
+    placeholder: &str,
+    directory: &Path,
+    value: &serde_json::Value,
+) -> Result<(), Error> {
+    let pointer = &placeholder[1..placeholder.len() - 1];
+    let element = value
+        .pointer(pointer)
+        .ok_or_else(|| Error::JSONPointerNotFound(directory.into(), pointer.into()))?;
+    match element {
+        serde_json::Value::String(s) => result.push_str(s),
+        _ => result.push_str(&element.to_string()),
+    }
+    Ok(())
+}
+
+/// Get the JSON pointers (`{/pointer}`) referenced by a template.
+fn template_pointers(template: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut rest = template;
+    while let Some((start, end)) = next_placeholder(rest) {
+        let placeholder = &rest[start..end];
+        if placeholder.starts_with("{/") && placeholder.ends_with('}') {
+            result.push(placeholder[1..placeholder.len() - 1].to_string());
+        }
+        rest = &rest[end..];
+    }
+    result
+}
+
+/// Validate the names and thresholds of the previous actions in an action.
+fn validate_previous_actions(action: &Action, action_names: &HashSet<String>) -> Result<(), Error> {
+    for previous_action in action.previous_actions() {
+        if !action_names.contains(previous_action) {
+            return Err(Error::PreviousActionNotFound(
+                previous_action.to_string(),
+                action.name().into(),
+            ));
+        }
+    }
+
+    for previous_action in action.all_previous_actions() {
+        if let Some(Threshold::Fraction { fraction }) = previous_action.threshold() {
+            if !(*fraction > 0.0 && *fraction <= 1.0) {
+                return Err(Error::InvalidPreviousFraction(
+                    previous_action.name().into(),
+                    action.name().into(),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate the operands of the `include` conditions in an action.
+fn validate_conditions(action: &Action) -> Result<(), Error> {
+    for (pointer, comparison, value) in action.include_conditions() {
+        if matches!(comparison, Comparison::In | Comparison::NotIn) && !value.is_array() {
+            return Err(Error::MembershipNotArray(
+                pointer.clone(),
+                action.name().into(),
+            ));
+        }
+        if *comparison == Comparison::Matches {
+            let Some(pattern) = value.as_str() else {
+                return Err(Error::PatternNotString(
+                    pointer.clone(),
+                    action.name().into(),
+                ));
+            };
+            Regex::new(pattern)
+                .map_err(|e| Error::InvalidPattern(pattern.into(), action.name().into(), e))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that a product template uses only JSON pointers.
+fn validate_product(action_name: &str, product: &str) -> Result<(), Error> {
+    let mut rest = product;
+    while let Some((start, end)) = next_placeholder(rest) {
+        let placeholder = &rest[start..end];
+        if !(placeholder.starts_with("{/") && placeholder.ends_with('}')) {
+            return Err(Error::InvalidProduct(
+                action_name.into(),
+                placeholder.into(),
+            ));
+        }
+        rest = &rest[end..];
+    }
+
+    Ok(())
+}
+
+/// Check that a job name template uses only known placeholders.
+fn validate_job_name(action_name: &str, job_name: &str) -> Result<(), Error> {
+    let mut rest = job_name;
+    while let Some((start, end)) = next_placeholder(rest) {
+        let placeholder = &rest[start..end];
+        let is_pointer = placeholder.starts_with("{/") && placeholder.ends_with('}');
+        if !is_pointer && !matches!(placeholder, "{action}" | "{group_index}" | "{group_id}") {
+            return Err(Error::InvalidJobName(
+                action_name.into(),
+                placeholder.into(),
+            ));
+        }
+        rest = &rest[end..];
+    }
 
-    Ok((path, workflow_file))
+    Ok(())
 }
 
 #[cfg(test)]
@@ -737,7 +2404,7 @@ mod tests {
         let temp = TempDir::new().unwrap();
         env::set_current_dir(temp.path()).unwrap();
 
-        let result = find_and_open_workflow();
+        let result = find_and_open_workflow(&env::current_dir().unwrap());
         assert!(
             result.is_err(),
             "Expected to find no workflow file, but got {result:?}"
@@ -746,7 +2413,7 @@ mod tests {
         assert!(result
             .unwrap_err()
             .to_string()
-            .starts_with("workflow.toml not found in"));
+            .starts_with("workflow.toml or workflow.yaml not found in"));
     }
 
     #[test]
@@ -759,9 +2426,9 @@ mod tests {
         sub_path.create_dir_all().unwrap();
         env::set_current_dir(sub_path.path()).unwrap();
 
-        let result = find_and_open_workflow();
+        let result = find_and_open_workflow(&env::current_dir().unwrap());
 
-        if let Ok((path, _)) = result {
+        if let Ok((path, _, _)) = result {
             assert_eq!(
                 path.canonicalize().unwrap(),
                 temp.path().canonicalize().unwrap()
@@ -771,367 +2438,1696 @@ mod tests {
         }
     }
 
+    #[test]
+    #[serial]
+    fn yaml_search() {
+        let temp = TempDir::new().unwrap();
+        temp.child("workflow.yaml").touch().unwrap();
+
+        let sub_path = temp.child("a");
+        sub_path.create_dir_all().unwrap();
+        env::set_current_dir(sub_path.path()).unwrap();
+
+        let result = find_and_open_workflow(&env::current_dir().unwrap());
+        if let Ok((path, file_name, _)) = result {
+            assert_eq!(
+                path.canonicalize().unwrap(),
+                temp.path().canonicalize().unwrap()
+            );
+            assert_eq!(file_name, "workflow.yaml");
+        } else {
+            panic!("Expected to find a workflow file, but got {result:?}");
+        }
+
+        temp.child("workflow.toml").touch().unwrap();
+        let result = find_and_open_workflow(&env::current_dir().unwrap());
+        assert!(
+            matches!(result, Err(Error::MultipleWorkflowFiles(_))),
+            "Expected multiple workflow files error, but got {result:?}"
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn yaml() {
+        let temp = TempDir::new().unwrap();
+        let toml = r#"
+[workspace]
+value_file = "v.json"
+
+[[action]]
+name = "b"
+command = "c {directory}"
+products = ["d"]
+launchers = ["mpi"]
+previous_actions = { any = ["e"] }
+[action.resources]
+processes.per_directory = 2
+walltime.per_submission = "04:00:00"
+[action.group]
+maximum_size = 4
+include = [{ condition = ["/f", "==", 1] }]
+
+[[action]]
+name = "e"
+command = "g {directories}"
+"#;
+        let yaml = r#"
+workspace:
+  value_file: v.json
+
+action:
+  - name: b
+    command: c {directory}
+    products: [d]
+    launchers: [mpi]
+    previous_actions:
+      any: [e]
+    resources:
+      processes:
+        per_directory: 2
+      walltime:
+        per_submission: "04:00:00"
+    group:
+      maximum_size: 4
+      include:
+        - condition: [/f, "==", 1]
+
+  - name: e
+    command: g {directories}
+"#;
+        let from_toml = Workflow::open_str(temp.path(), toml).unwrap();
+        let from_yaml = Workflow::open_yaml_str(temp.path(), yaml).unwrap();
+        assert_eq!(from_toml, from_yaml);
+
+        let workflow = Workflow::open_yaml_str(temp.path(), "").unwrap();
+        assert_eq!(workflow.action.len(), 0);
+    }
+
+    #[test]
+    #[parallel]
+    fn yaml_errors() {
+        let temp = TempDir::new().unwrap();
+        let result = Workflow::open_yaml_str(temp.path(), "action: [");
+        assert!(
+            matches!(result, Err(Error::YAMLParse(..))),
+            "Expected YAML parse error, but got {result:?}"
+        );
+        assert!(result.unwrap_err().to_string().contains("workflow.yaml"));
+
+        let workflow = r"
+action:
+  - name: b
+    command: c
+    previous_actions: [z]
+";
+        let result = Workflow::open_yaml_str(temp.path(), workflow);
+        assert!(
+            matches!(result, Err(Error::PreviousActionNotFound(..))),
+            "Expected previous action not found error, but got {result:?}"
+        );
+
+        let result = Workflow::open_yaml_str(temp.path(), "action: [{ name: b, x: 1 }]");
+        assert!(
+            matches!(result, Err(Error::YAMLParse(..))),
+            "Expected YAML parse error, but got {result:?}"
+        );
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("unknown field `x`"));
+    }
+
+    #[test]
+    #[parallel]
+    fn generator() {
+        let temp = TempDir::new().unwrap();
+        temp.child("generate.sh")
+            .write_str(
+                r#"echo run >> runs
+echo '[[action]]'
+echo 'name = "b"'
+echo 'command = "c {directory}"'
+"#,
+            )
+            .unwrap();
+        let workflow = r#"generator = "sh generate.sh""#;
+
+        let result = Workflow::open_file_str(temp.path(), "workflow.toml", workflow).unwrap();
+        assert_eq!(result.action.len(), 1);
+        assert_eq!(result.action[0].name(), "b");
+        assert_eq!(result.generator, None);
+
+        // Reuse the cached output.
+        Workflow::open_file_str(temp.path(), "workflow.toml", workflow).unwrap();
+        temp.child("runs").assert("run\n");
+
+        // Execute the generator again when its inputs change.
+        temp.child("generate.sh")
+            .write_str("echo run >> runs\necho 'generator = \"x\"'\n")
+            .unwrap();
+        let result = Workflow::open_file_str(temp.path(), "workflow.toml", workflow);
+        assert!(
+            matches!(result, Err(Error::NestedGenerator(_))),
+            "Expected nested generator error, but got {result:?}"
+        );
+        temp.child("runs").assert("run\nrun\n");
+
+        temp.child("generate.sh")
+            .write_str("echo '[[action]]'\necho 'x = 1'\n")
+            .unwrap();
+        let result = Workflow::open_file_str(temp.path(), "workflow.toml", workflow);
+        assert!(
+            matches!(result, Err(Error::TOMLParse(..))),
+            "Expected TOML parse error, but got {result:?}"
+        );
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("generated_workflow.toml"));
+
+        let result =
+            Workflow::open_file_str(temp.path(), "workflow.toml", r#"generator = "exit 3""#);
+        assert!(
+            matches!(result, Err(Error::ExecuteCommand(..))),
+            "Expected execute command error, but got {result:?}"
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn empty_workflow_file() {
+        let temp = TempDir::new().unwrap();
+        let workflow = "";
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        assert_eq!(workflow.root, temp.path().canonicalize().unwrap());
+        assert_eq!(workflow.workspace.path, PathBuf::from("workspace"));
+        assert!(workflow.workspace.value_file.is_none());
+        assert_eq!(workflow.workspace.non_utf8_names, NonUtf8Names::Error);
+        assert_eq!(workflow.workspace.value_mode, ValueMode::Directory);
+        assert_eq!(workflow.workspace.value_cache, ValueCache::All);
+        assert_eq!(workflow.workspace.minimum_free, None);
+        assert!(workflow.workspace.products_checksum_file.is_none());
+        assert_eq!(workflow.workspace.products_checksum, ProductsChecksum::Full);
+        assert!(workflow.workspace.url.is_none());
+        assert_eq!(workflow.value_pointers(), None);
+        assert_eq!(workflow.default.action, Action::default());
+        assert_eq!(workflow.default.io_threads, None);
+        assert!(workflow.action.is_empty());
+    }
+
+    #[test]
+    #[parallel]
+    fn default_io_threads() {
+        let temp = TempDir::new().unwrap();
+        let workflow = Workflow::open_str(temp.path(), "").unwrap();
+        assert_eq!(workflow.io_threads(None), DEFAULT_IO_THREADS);
+        assert_eq!(workflow.io_threads(Some(3)), 3);
+
+        let workflow = r"
+[default]
+io_threads = 32
+";
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        assert_eq!(workflow.io_threads(None), 32);
+        assert_eq!(workflow.io_threads(Some(3)), 3);
+
+        let workflow = r"
+[default]
+io_threads = 0
+";
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(
+            result
+                .as_ref()
+                .is_err_and(|e| e.to_string().contains("nonzero")),
+            "Expected nonzero error, got {result:?}"
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn workspace() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[workspace]
+path = "p"
+value_file = "s"
+non_utf8_names = "skip"
+value_cache = "referenced"
+minimum_free = "500GB"
+products_checksum_file = "MANIFEST.sha256"
+products_checksum = "sampled"
+"#;
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        assert_eq!(workflow.workspace.path, PathBuf::from("p"));
+        assert_eq!(workflow.workspace.value_file, Some(PathBuf::from("s")));
+        assert_eq!(workflow.workspace.non_utf8_names, NonUtf8Names::Skip);
+        assert_eq!(workflow.workspace.value_cache, ValueCache::Referenced);
+        assert_eq!(workflow.workspace.minimum_free, Some(500_000_000_000));
+        assert_eq!(
+            workflow.workspace.products_checksum_file,
+            Some(PathBuf::from("MANIFEST.sha256"))
+        );
+        assert_eq!(
+            workflow.workspace.products_checksum,
+            ProductsChecksum::Sampled
+        );
+        assert_eq!(workflow.value_pointers(), Some(Vec::new()));
+    }
+
+    #[test]
+    #[parallel]
+    fn workspace_value_mode() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[workspace]
+value_file = "values.json"
+value_mode = "consolidated"
+"#;
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        assert_eq!(workflow.workspace.value_mode, ValueMode::Consolidated);
+
+        let workflow = r#"
+[workspace]
+value_mode = "consolidated"
+"#;
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(matches!(result, Err(Error::ConsolidatedValueFileMissing)));
+    }
+
+    #[test]
+    #[parallel]
+    fn bytes() {
+        assert_eq!(parse_bytes("100"), Ok(100));
+        assert_eq!(parse_bytes("100B"), Ok(100));
+        assert_eq!(parse_bytes("2KB"), Ok(2000));
+        assert_eq!(parse_bytes("1.5TB"), Ok(1_500_000_000_000));
+        assert_eq!(parse_bytes("3G"), Ok(3 * 1024 * 1024 * 1024));
+        assert_eq!(parse_bytes("3GiB"), Ok(3 * 1024 * 1024 * 1024));
+        assert_eq!(parse_bytes("10 MB"), Ok(10_000_000));
+        assert!(parse_bytes("GB").is_err());
+        assert!(parse_bytes("10 parsecs").is_err());
+
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[workspace]
+minimum_free = "lots"
+"#;
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(
+            result
+                .as_ref()
+                .is_err_and(|e| e.to_string().contains("is not a size")),
+            "Expected size error, got {result:?}"
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn workspace_url() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[workspace]
+url = "s3://bucket/prefix"
+"#;
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        assert_eq!(
+            workflow.workspace.url,
+            Some(String::from("s3://bucket/prefix"))
+        );
+
+        let workflow = r#"
+[workspace]
+url = "https://example.com/workspace"
+"#;
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(matches!(result, Err(Error::UnsupportedWorkspaceUrl(_))));
+    }
+
+    #[test]
+    #[parallel]
+    fn include_completed() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[workspace]
+value_cache = "referenced"
+
+[[action]]
+name = "a"
+command = "c"
+
+[[action]]
+name = "b"
+command = "c"
+[[action.group.include]]
+condition = ["completed:a", "==", true]
+[[action.group.include]]
+condition = ["/x", "==", 1]
+"#;
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        assert_eq!(workflow.value_pointers(), Some(vec!["/x".to_string()]));
+
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+[[action.group.include]]
+condition = ["completed:a", "==", true]
+"#;
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(matches!(
+            result,
+            Err(Error::IncludeActionNotFound(a, b)) if a == "a" && b == "b"
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn workspace_override() {
+        let temp = TempDir::new().unwrap();
+        env::set_current_dir(temp.path()).unwrap();
+        let workflow = Workflow::open_str(temp.path(), "").unwrap();
+        let data_directory = workflow.root.join(DATA_DIRECTORY_NAME);
+        assert_eq!(workflow.data_directory(), data_directory);
+
+        let same = workflow
+            .clone()
+            .with_workspace(&workflow.root.join("workspace"))
+            .unwrap();
+        assert_eq!(same.workspace.path, PathBuf::from("workspace"));
+        assert_eq!(same.data_directory(), data_directory);
+
+        let other = workflow
+            .clone()
+            .with_workspace(&workflow.root.join("other"))
+            .unwrap();
+        assert_eq!(other.workspace.path, workflow.root.join("other"));
+        assert_eq!(
+            other.data_directory().parent(),
+            Some(data_directory.join(WORKSPACES_DIRECTORY_NAME).as_path())
+        );
+
+        let another = workflow
+            .clone()
+            .with_workspace(&workflow.root.join("another"))
+            .unwrap();
+        assert_ne!(other.data_directory(), another.data_directory());
+    }
+
+    #[test]
+    #[serial]
+    fn cache_directory_override() {
+        let temp = TempDir::new().unwrap();
+        env::set_current_dir(temp.path()).unwrap();
+        let workflow = Workflow::open_str(temp.path(), "").unwrap();
+
+        let cached = workflow
+            .clone()
+            .with_cache_directory(Path::new("cache"))
+            .unwrap();
+        let project_cache = cached.data_directory();
+        assert_eq!(
+            project_cache.parent(),
+            Some(env::current_dir().unwrap().join("cache").as_path())
+        );
+
+        // Projects that share a cache directory use separate caches.
+        let project = temp.child("project");
+        project.create_dir_all().unwrap();
+        let another = Workflow::open_str(project.path(), "")
+            .unwrap()
+            .with_cache_directory(Path::new("cache"))
+            .unwrap();
+        assert_ne!(another.data_directory(), project_cache);
+
+        let other = cached.with_workspace(&workflow.root.join("other")).unwrap();
+        assert_eq!(
+            other.data_directory().parent(),
+            Some(project_cache.join(WORKSPACES_DIRECTORY_NAME).as_path())
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn submit_options_defaults() {
+        let temp = TempDir::new().unwrap();
+        let workflow = "[default.action.submit_options.a]";
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        assert_eq!(
+            workflow.root.canonicalize().unwrap(),
+            temp.path().canonicalize().unwrap()
+        );
+
+        assert_eq!(workflow.default.action.submit_options.len(), 1);
+        assert!(workflow.default.action.submit_options.contains_key("a"));
+
+        let submit_options = workflow.default.action.submit_options.get("a").unwrap();
+        assert_eq!(submit_options.account, None);
+        assert_eq!(submit_options.setup, None);
+        assert!(submit_options.custom.is_empty());
+        assert_eq!(submit_options.partition, None);
+        assert_eq!(submit_options.qos, None);
+    }
+
+    #[test]
+    #[parallel]
+    fn submit_options_nondefault() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[default.action.submit_options.a]
+account = "my_account"
+setup = "module load openmpi"
+custom = ["--option1", "--option2"]
+partition = "gpu"
+qos = "high"
+"#;
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        assert_eq!(
+            workflow.root.canonicalize().unwrap(),
+            temp.path().canonicalize().unwrap()
+        );
+
+        assert_eq!(workflow.default.action.submit_options.len(), 1);
+        assert!(workflow.default.action.submit_options.contains_key("a"));
+
+        let submit_options = workflow.default.action.submit_options.get("a").unwrap();
+        assert_eq!(submit_options.account, Some(String::from("my_account")));
+        assert_eq!(
+            submit_options.setup,
+            Some(String::from("module load openmpi"))
+        );
+        assert_eq!(submit_options.custom, vec!["--option1", "--option2"]);
+        assert_eq!(submit_options.partition, Some(String::from("gpu")));
+        assert_eq!(submit_options.qos, Some(String::from("high")));
+    }
+
+    #[test]
+    #[parallel]
+    fn action_defaults() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+"#;
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        assert_eq!(workflow.action.len(), 1);
+
+        let action = workflow.action.first().unwrap();
+        assert_eq!(action.name(), "b");
+        assert_eq!(action.commands(), ["c"]);
+        assert!(action.previous_actions.is_none());
+        assert!(action.products.is_none());
+        assert!(action.launchers.is_none());
+
+        assert_eq!(action.resources.processes, None);
+        assert_eq!(action.resources.processes(), Processes::PerSubmission(1));
+        assert_eq!(action.resources.threads_per_process, None);
+        assert_eq!(action.resources.gpus_per_process, None);
+        assert_eq!(action.resources.walltime, None,);
+        assert_eq!(
+            action.resources.walltime(),
+            Walltime::PerDirectory(Duration::new(true, 0, 3600, 0).unwrap())
+        );
+
+        assert!(action.submit_options.is_empty());
+        assert_eq!(action.group.include, None);
+        assert!(action.group.include().is_empty());
+        assert_eq!(action.group.sort_by, None);
+        assert!(action.group.sort_by().is_empty());
+        assert_eq!(action.group.split_by_sort_key, None);
+        assert!(!action.group.split_by_sort_key());
+        assert_eq!(action.group.maximum_size, None);
+        assert_eq!(action.group.minimum_size, None);
+        assert_eq!(action.group.leftover(), Leftover::Submit);
+        assert_eq!(action.group.submit_whole, None);
+        assert!(!action.group.submit_whole());
+        assert_eq!(action.group.reverse_sort, None);
+        assert!(!action.group.reverse_sort());
+    }
+
+    #[test]
+    #[parallel]
+    fn action_no_name() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+command = "c"
+"#;
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(result.is_err());
+
+        assert!(result.unwrap_err().to_string().contains("missing `name`"));
+    }
+
+    #[test]
+    #[parallel]
+    fn action_no_command() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "a"
+"#;
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(result.is_err());
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("missing `command`"));
+    }
+
+    #[test]
+    #[parallel]
+    fn action_command_steps() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "a"
+command = ["c {directory}", "d {directory}"]
+"#;
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        let action = &workflow.action[0];
+        assert_eq!(action.commands(), ["c {directory}", "d {directory}"]);
+        assert!(action.command_contains("{directory}"));
+        assert!(!action.command_contains("{directories}"));
+
+        let workflow = r#"
+[[action]]
+name = "a"
+command = []
+"#;
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(matches!(result, Err(Error::ActionMissingCommand(_))));
+
+        let workflow = r#"
+[[action]]
+name = "a"
+command = ["c {directory}", "{launcher} d {launcher} {directory}"]
+"#;
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(matches!(
+            result,
+            Err(Error::DuplicateLauncherPlaceholder(_))
+        ));
+    }
+
+    #[test]
+    #[parallel]
+    fn group_defaults() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+[action.group]
+"#;
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        assert_eq!(workflow.action.len(), 1);
+
+        let action = workflow.action.first().unwrap();
+
+        assert!(action.submit_options.is_empty());
+        assert!(action.group.include().is_empty());
+        assert!(action.group.sort_by().is_empty());
+        assert!(!action.group.split_by_sort_key());
+        assert_eq!(action.group.maximum_size, None);
+        assert!(!action.group.submit_whole());
+        assert!(!action.group.reverse_sort());
+    }
+
+    #[test]
+    #[parallel]
+    fn value_pointers() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[workspace]
+value_cache = "referenced"
+
+[[action]]
+name = "b"
+command = "c"
+[action.group]
+sort_by = ["/sort", "/d"]
+[[action.group.include]]
+condition = ["/d", "==", 5]
+
+[[action]]
+name = "e"
+command = "f"
+job_name = "{action}-{/name}"
+[[action.group.include]]
+all = [["/float", ">", 6.5], ["/a/1", "<", "str"]]
+"#;
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        assert_eq!(
+            workflow.value_pointers(),
+            Some(vec![
+                "/a/1".to_string(),
+                "/d".to_string(),
+                "/float".to_string(),
+                "/name".to_string(),
+                "/sort".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn action_duplicate() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+
+[[action]]
+name = "b"
+command = "d"
+"#;
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    #[parallel]
+    fn action_duplicate_different_products() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+products = ["e"]
+
+[[action]]
+name = "b"
+command = "d"
+products = ["b"]
+"#;
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(matches!(
+            result,
+            Err(Error::DuplicateActionsDifferentProducts(_))
+        ));
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("must have the same `products`"));
+    }
+
+    #[test]
+    #[parallel]
+    fn action_duplicate_different_previous_actions() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+
+[[action]]
+name = "b"
+command = "d"
+previous_actions = ["a"]
+
+[[action]]
+name = "a"
+command = "e"
+"#;
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(matches!(
+            result,
+            Err(Error::DuplicateActionsDifferentPreviousActions(_))
+        ));
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("must have the same `previous_actions`"));
+    }
+
+    #[test]
+    #[parallel]
+    fn action_launchers() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+launchers = ["openmp", "mpi"]
+"#;
+
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        assert_eq!(workflow.action.len(), 1);
+
+        let action = workflow.action.first().unwrap();
+        assert_eq!(
+            action.launchers("any_cluster"),
+            vec!["openmp".to_string(), "mpi".to_string()]
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn action_launchers_by_cluster() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+launchers.default = ["mpi"]
+launchers.frontier = ["openmp", "srun_gpu"]
+"#;
+
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        assert_eq!(workflow.action.len(), 1);
+
+        let action = workflow.action.first().unwrap();
+        assert_eq!(action.launchers("any_cluster"), vec!["mpi".to_string()]);
+        assert_eq!(
+            action.launchers("frontier"),
+            vec!["openmp".to_string(), "srun_gpu".to_string()]
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn action_launchers_by_cluster_no_default() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+launchers.frontier = ["mpi"]
+"#;
+
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        let action = workflow.action.first().unwrap();
+        assert!(action.launchers("any_cluster").is_empty());
+        assert_eq!(action.launchers("frontier"), vec!["mpi".to_string()]);
+    }
+
+    #[test]
+    #[parallel]
+    fn action_tags() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[default.action]
+tags = ["default"]
+
+[[action]]
+name = "b"
+command = "c"
+tags = ["analysis", "gpu"]
+
+[[action]]
+name = "d"
+command = "e"
+"#;
+
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        let action = workflow.action_by_name("b").unwrap();
+        assert_eq!(action.tags(), vec!["analysis", "gpu"]);
+        assert!(action.has_any_tag(&[]));
+        assert!(action.has_any_tag(&["gpu".into()]));
+        assert!(action.has_any_tag(&["cpu".into(), "analysis".into()]));
+        assert!(!action.has_any_tag(&["cpu".into()]));
+
+        let action = workflow.action_by_name("d").unwrap();
+        assert_eq!(action.tags(), vec!["default"]);
+    }
+
+    #[test]
+    #[parallel]
+    fn action_previous_actions_any() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+
+[[action]]
+name = "c"
+command = "c"
+
+[[action]]
+name = "d"
+command = "e"
+previous_actions = { any = ["b", "c"] }
+"#;
+
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        let action = workflow.action_by_name("d").unwrap();
+        assert_eq!(
+            action.previous_actions,
+            Some(PreviousActions::Any {
+                any: vec![
+                    PreviousAction::Name("b".to_string()),
+                    PreviousAction::Name("c".to_string())
+                ]
+            })
+        );
+        assert_eq!(
+            action.previous_actions(),
+            vec!["b".to_string(), "c".to_string()]
+        );
+        assert!(action
+            .previous_actions_completed(|p| p.name() == "c", |_| Ok(true))
+            .unwrap());
+        assert!(!action
+            .previous_actions_completed(|_| false, |_| Ok(true))
+            .unwrap());
+
+        let action = workflow.action_by_name("b").unwrap();
+        assert!(action
+            .previous_actions_completed(|_| false, |_| Ok(true))
+            .unwrap());
+    }
+
+    #[test]
+    #[parallel]
+    fn action_previous_actions_conditional() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[workspace]
+value_cache = "referenced"
+
+[[action]]
+name = "b"
+command = "c"
+
+[[action]]
+name = "equilibrate"
+command = "c"
+
+[[action]]
+name = "d"
+command = "e"
+previous_actions = [
+    "b",
+    { action = "equilibrate", include = [{ condition = ["/ensemble", "==", "npt"] }] },
+]
+"#;
+
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        let action = workflow.action_by_name("d").unwrap();
+        assert_eq!(action.previous_actions(), vec!["b", "equilibrate"]);
+        assert_eq!(
+            action.previous_actions,
+            Some(PreviousActions::All(vec![
+                PreviousAction::Name("b".to_string()),
+                PreviousAction::Conditional {
+                    action: "equilibrate".to_string(),
+                    include: vec![Selector::condition((
+                        "/ensemble".to_string(),
+                        Comparison::EqualTo,
+                        serde_json::Value::from("npt")
+                    ))]
+                }
+            ]))
+        );
+        assert_eq!(
+            workflow.value_pointers(),
+            Some(vec!["/ensemble".to_string()])
+        );
+
+        // equilibrate is required only when the selectors match.
+        assert!(!action
+            .previous_actions_completed(|p| p.name() == "b", |_| Ok(true))
+            .unwrap());
+        assert!(action
+            .previous_actions_completed(|p| p.name() == "b", |s| Ok(s.is_empty()))
+            .unwrap());
+        assert!(action
+            .previous_actions_completed(|_| true, |_| Ok(true))
+            .unwrap());
+    }
+
+    #[test]
+    #[parallel]
+    fn action_previous_actions_project() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[workspace]
+value_file = "v.json"
+value_cache = "referenced"
+
+[[action]]
+name = "b"
+command = "c"
+
+[[action]]
+name = "d"
+command = "e"
+previous_actions = [
+    "b",
+    { project = "../prep", action = "build" },
+    { project = "../other", action = "b", include = [{ condition = ["/f", "==", 1] }] },
+]
+"#;
+
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        let action = workflow.action_by_name("d").unwrap();
+        assert_eq!(action.previous_actions(), vec!["b"]);
+        assert_eq!(
+            action.previous_projects(),
+            vec![Path::new("../prep"), Path::new("../other")]
+        );
+        assert_eq!(workflow.value_pointers(), Some(vec!["/f".to_string()]));
+
+        let is_completed = |previous: &PreviousAction| match previous.project() {
+            None => previous.name() == "b",
+            Some(p) => p == Path::new("../prep") && previous.name() == "build",
+        };
+        assert!(!action
+            .previous_actions_completed(is_completed, |_| Ok(true))
+            .unwrap());
+        assert!(action
+            .previous_actions_completed(is_completed, |s| Ok(s.is_empty()))
+            .unwrap());
+    }
+
+    #[test]
+    #[parallel]
+    fn action_previous_actions_threshold() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+
+[[action]]
+name = "d"
+command = "e"
+previous_actions = [
+    { action = "b", fraction = 0.95 },
+    { action = "b", count = 10, include = [{ condition = ["/f", "==", 1] }] },
+]
+"#;
+
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        let action = workflow.action_by_name("d").unwrap();
+        assert_eq!(action.previous_actions(), vec!["b", "b"]);
+        assert_eq!(
+            action.previous_actions,
+            Some(PreviousActions::All(vec![
+                PreviousAction::Threshold {
+                    action: "b".to_string(),
+                    threshold: Threshold::Fraction { fraction: 0.95 },
+                    include: Vec::new(),
+                },
+                PreviousAction::Threshold {
+                    action: "b".to_string(),
+                    threshold: Threshold::Count { count: 10 },
+                    include: vec![Selector::condition((
+                        "/f".to_string(),
+                        Comparison::EqualTo,
+                        serde_json::Value::from(1)
+                    ))]
+                }
+            ]))
+        );
+
+        assert!(Threshold::Fraction { fraction: 0.5 }.is_met(2, 4));
+        assert!(!Threshold::Fraction { fraction: 0.5 }.is_met(1, 4));
+        assert!(!Threshold::Fraction { fraction: 0.5 }.is_met(0, 0));
+        assert!(Threshold::Count { count: 2 }.is_met(2, 4));
+        assert!(!Threshold::Count { count: 3 }.is_met(2, 4));
+
+        for fraction in ["0", "1.5", "-0.5", "nan"] {
+            let workflow = format!(
+                r#"
+[[action]]
+name = "b"
+command = "c"
+
+[[action]]
+name = "d"
+command = "e"
+previous_actions = [{{ action = "b", fraction = {fraction} }}]
+"#
+            );
+            let result = Workflow::open_str(temp.path(), &workflow);
+            assert!(
+                matches!(result, Err(Error::InvalidPreviousFraction(ref p, ref a)) if p == "b" && a == "d"),
+                "{fraction}: {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn action_previous_actions_any_not_found() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "d"
+command = "e"
+previous_actions.any = ["b"]
+"#;
+
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(matches!(result, Err(Error::PreviousActionNotFound(_, _))));
+    }
+
+    #[test]
+    #[parallel]
+    fn action_previous_actions() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+
+[[action]]
+name = "d"
+command = "e"
+previous_actions = ["b"]
+"#;
+
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        assert_eq!(workflow.action.len(), 2);
+
+        let action = workflow.action.get(1).unwrap();
+        assert_eq!(action.previous_actions(), vec!["b".to_string()]);
+
+        let action_a = workflow.action_by_name("b");
+        assert_eq!(action_a.unwrap().commands(), ["c"]);
+
+        let action_d = workflow.action_by_name("d");
+        assert_eq!(action_d.unwrap().commands(), ["e"]);
+
+        assert!(workflow.action_by_name("f").is_none());
+    }
+
+    #[test]
+    #[parallel]
+    fn previous_action_error() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+previous_actions = ["a"]
+"#;
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(
+            result.is_err(),
+            "Expected previous action error, but got {result:?}"
+        );
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with("Previous action 'a' not found"));
+    }
+
+    #[test]
+    #[parallel]
+    fn action_resources() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+[action.resources]
+processes.per_submission = 12
+threads_per_process = 8
+gpus_per_process = 1
+walltime.per_submission = "4d, 05:32:11"
+binding = "sockets"
+"#;
+
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        assert_eq!(workflow.action.len(), 1);
+
+        let action = workflow.action.first().unwrap();
+        assert_eq!(action.resources.processes(), Processes::PerSubmission(12));
+        assert_eq!(action.resources.threads_per_process, Some(8));
+        assert_eq!(action.resources.gpus_per_process, Some(1));
+        assert_eq!(action.resources.binding, Some(Binding::Sockets));
+        assert_eq!(
+            action.resources.walltime(),
+            Walltime::PerSubmission(
+                Duration::new(true, 4, 5 * 3600 + 32 * 60 + 11, 0)
+                    .expect("this should be a valid Duration"),
+            )
+        );
+    }
+
     #[test]
     #[parallel]
-    fn empty_workflow_file() {
+    fn action_resources_component() {
         let temp = TempDir::new().unwrap();
-        let workflow = "";
-        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+[action.resources]
+walltime.per_submission = "01:00"
+[[action.resources.component]]
+processes.per_submission = 1
+gpus_per_process = 1
+[[action.resources.component]]
+processes.per_directory = 16
+"#;
 
-        assert_eq!(workflow.root, temp.path().canonicalize().unwrap());
-        assert_eq!(workflow.workspace.path, PathBuf::from("workspace"));
-        assert!(workflow.workspace.value_file.is_none());
-        assert_eq!(workflow.default.action, Action::default());
-        assert!(workflow.action.is_empty());
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        let action = workflow.action.first().unwrap();
+        assert_eq!(action.resources.component.len(), 2);
+        assert_eq!(action.resources.total_processes(2), 33);
+        assert_eq!(action.resources.total_cpus(2), 33);
+        assert_eq!(action.resources.total_gpus(2), 1);
+        assert_eq!(
+            action.resources.cost(2),
+            ResourceCost::with_values(32.0, 1.0)
+        );
     }
 
     #[test]
     #[parallel]
-    fn workspace() {
+    fn action_resources_component_invalid() {
         let temp = TempDir::new().unwrap();
         let workflow = r#"
-[workspace]
-path = "p"
-value_file = "s"
+[[action]]
+name = "b"
+command = "c"
+[action.resources]
+processes.per_submission = 2
+[[action.resources.component]]
+processes.per_submission = 1
 "#;
-        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
 
-        assert_eq!(workflow.workspace.path, PathBuf::from("p"));
-        assert_eq!(workflow.workspace.value_file, Some(PathBuf::from("s")));
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(matches!(result, Err(Error::InvalidComponent(_, _))));
+
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+[[action.resources.component]]
+walltime.per_submission = "01:00"
+"#;
+
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(matches!(result, Err(Error::InvalidComponent(_, _))));
     }
 
     #[test]
     #[parallel]
-    fn submit_options_defaults() {
+    fn action_resources_concurrent_directories() {
         let temp = TempDir::new().unwrap();
-        let workflow = "[default.action.submit_options.a]";
-        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c {directory}"
+[action.resources]
+processes.per_directory = 2
+walltime.per_directory = "01:00:00"
+concurrent_directories = 4
+"#;
 
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        let action = workflow.action.first().unwrap();
+        assert_eq!(action.resources.concurrent_directories, Some(4));
+        assert_eq!(action.resources.total_processes(2), 4);
+        assert_eq!(action.resources.total_processes(10), 8);
         assert_eq!(
-            workflow.root.canonicalize().unwrap(),
-            temp.path().canonicalize().unwrap()
+            action.resources.total_walltime(10),
+            Duration::new(true, 0, 3 * 3600, 0).unwrap()
+        );
+        assert_eq!(
+            action.resources.cost(10),
+            ResourceCost::with_values(24.0, 0.0)
         );
 
-        assert_eq!(workflow.default.action.submit_options.len(), 1);
-        assert!(workflow.default.action.submit_options.contains_key("a"));
-
-        let submit_options = workflow.default.action.submit_options.get("a").unwrap();
-        assert_eq!(submit_options.account, None);
-        assert_eq!(submit_options.setup, None);
-        assert!(submit_options.custom.is_empty());
-        assert_eq!(submit_options.partition, None);
+        for invalid in [
+            "command = \"c {directory}\"\nresources.concurrent_directories = 0",
+            "command = \"c {directories}\"\nresources.processes.per_directory = 1\nresources.concurrent_directories = 2",
+            "command = \"c {directory}\"\nresources.processes.per_submission = 1\nresources.concurrent_directories = 2",
+        ] {
+            let workflow = format!("[[action]]\nname = \"b\"\n{invalid}\n");
+            let result = Workflow::open_str(temp.path(), &workflow);
+            assert!(
+                matches!(result, Err(Error::InvalidConcurrentDirectories(_, _))),
+                "{invalid}"
+            );
+        }
     }
 
     #[test]
     #[parallel]
-    fn submit_options_nondefault() {
+    fn action_resources_aggregated() {
         let temp = TempDir::new().unwrap();
         let workflow = r#"
-[default.action.submit_options.a]
-account = "my_account"
-setup = "module load openmpi"
-custom = ["--option1", "--option2"]
-partition = "gpu"
+[[action]]
+name = "b"
+command = "c {directory}"
+[action.resources]
+processes.per_submission = 4
+gpus_per_process = 1
+walltime.per_submission = "01:00:00"
+
+[[action]]
+name = "c"
+command = "c {directory}"
+[action.resources]
+processes.per_directory = 2
+walltime.per_directory = "00:30:00"
+concurrent_directories = 3
 "#;
-        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
 
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        let resources = workflow.action[0].resources.aggregated();
+        assert_eq!(resources.total_processes(10), 4);
+        assert_eq!(resources.total_gpus(10), 4);
         assert_eq!(
-            workflow.root.canonicalize().unwrap(),
-            temp.path().canonicalize().unwrap()
+            resources.total_walltime(10),
+            Duration::new(true, 0, 10 * 3600, 0).unwrap()
         );
+        assert_eq!(resources.cost(10), ResourceCost::with_values(0.0, 40.0));
 
-        assert_eq!(workflow.default.action.submit_options.len(), 1);
-        assert!(workflow.default.action.submit_options.contains_key("a"));
-
-        let submit_options = workflow.default.action.submit_options.get("a").unwrap();
-        assert_eq!(submit_options.account, Some(String::from("my_account")));
+        let resources = workflow.action[1].resources.aggregated();
+        assert_eq!(resources.total_processes(10), 6);
+        assert_eq!(resources.total_processes(2), 4);
         assert_eq!(
-            submit_options.setup,
-            Some(String::from("module load openmpi"))
+            resources.total_walltime(10),
+            Duration::new(true, 0, 4 * 1800, 0).unwrap()
         );
-        assert_eq!(submit_options.custom, vec!["--option1", "--option2"]);
-        assert_eq!(submit_options.partition, Some(String::from("gpu")));
     }
 
     #[test]
     #[parallel]
-    fn action_defaults() {
+    fn action_job_name() {
         let temp = TempDir::new().unwrap();
         let workflow = r#"
 [[action]]
-name = "b"
+name = "a"
 command = "c"
-"#;
-        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
 
-        assert_eq!(workflow.action.len(), 1);
+[[action]]
+name = "b"
+command = "c"
+job_name = "{action}-{group_index}"
 
-        let action = workflow.action.first().unwrap();
-        assert_eq!(action.name(), "b");
-        assert_eq!(action.command(), "c");
-        assert!(action.previous_actions.is_none());
-        assert!(action.products.is_none());
-        assert!(action.launchers.is_none());
+[[action]]
+name = "c"
+command = "c"
+job_name = "{action}-T{/temperature}-{/s}"
+"#;
 
-        assert_eq!(action.resources.processes, None);
-        assert_eq!(action.resources.processes(), Processes::PerSubmission(1));
-        assert_eq!(action.resources.threads_per_process, None);
-        assert_eq!(action.resources.gpus_per_process, None);
-        assert_eq!(action.resources.walltime, None,);
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        let directories = vec![PathBuf::from("dir1"), PathBuf::from("dir2")];
+        let value = serde_json::json!({"temperature": 1.5, "s": "x"});
         assert_eq!(
-            action.resources.walltime(),
-            Walltime::PerDirectory(Duration::new(true, 0, 3600, 0).unwrap())
+            workflow.action[0]
+                .job_name(&directories, 3, &value)
+                .unwrap(),
+            "a-dir1+1"
+        );
+        assert_eq!(
+            workflow.action[0]
+                .job_name(&directories[0..1], 3, &value)
+                .unwrap(),
+            "a-dir1"
+        );
+        assert_eq!(
+            workflow.action[1]
+                .job_name(&directories, 3, &value)
+                .unwrap(),
+            "b-3"
+        );
+        assert_eq!(
+            workflow.action[2]
+                .job_name(&directories, 3, &value)
+                .unwrap(),
+            "c-T1.5-x"
+        );
+        assert_eq!(
+            workflow.action[2].job_name_pointers(),
+            vec!["/temperature".to_string(), "/s".to_string()]
         );
+        assert!(matches!(
+            workflow.action[2].job_name(&directories, 3, &serde_json::Value::Null),
+            Err(Error::JSONPointerNotFound(_, _))
+        ));
 
-        assert!(action.submit_options.is_empty());
-        assert_eq!(action.group.include, None);
-        assert!(action.group.include().is_empty());
-        assert_eq!(action.group.sort_by, None);
-        assert!(action.group.sort_by().is_empty());
-        assert_eq!(action.group.split_by_sort_key, None);
-        assert!(!action.group.split_by_sort_key());
-        assert_eq!(action.group.maximum_size, None);
-        assert_eq!(action.group.submit_whole, None);
-        assert!(!action.group.submit_whole());
-        assert_eq!(action.group.reverse_sort, None);
-        assert!(!action.group.reverse_sort());
-    }
+        let value = serde_json::json!({"temperature": "1 K", "s": "x\n#SBATCH --qos=high"});
+        assert_eq!(
+            workflow.action[2]
+                .job_name(&directories, 3, &value)
+                .unwrap(),
+            "c-T1_K-x__SBATCH_--qos_high"
+        );
+        assert_eq!(
+            workflow.action[0]
+                .job_name(&[PathBuf::from("dir 1")], 3, &value)
+                .unwrap(),
+            "a-dir_1"
+        );
 
-    #[test]
-    #[parallel]
-    fn action_no_name() {
-        let temp = TempDir::new().unwrap();
         let workflow = r#"
 [[action]]
+name = "b"
 command = "c"
+job_name = "{action}-{directory}"
 "#;
         let result = Workflow::open_str(temp.path(), workflow);
-        assert!(result.is_err());
-
-        assert!(result.unwrap_err().to_string().contains("missing `name`"));
+        assert!(matches!(result, Err(Error::InvalidJobName(_, ref p)) if p == "{directory}"));
     }
 
     #[test]
     #[parallel]
-    fn action_no_command() {
+    fn order_previous_actions() {
         let temp = TempDir::new().unwrap();
         let workflow = r#"
 [[action]]
-name = "a"
-"#;
-        let result = Workflow::open_str(temp.path(), workflow);
-        assert!(result.is_err());
+name = "analyze"
+command = "c"
+previous_actions = ["simulate", "equilibrate"]
 
-        assert!(result
-            .unwrap_err()
-            .to_string()
-            .contains("missing `command`"));
-    }
+[[action]]
+name = "simulate"
+command = "c"
+previous_actions = ["equilibrate"]
+
+[[action]]
+name = "plot"
+command = "c"
+
+[[action]]
+name = "equilibrate"
+command = "c"
+
+[[action]]
+name = "a"
+command = "c"
+previous_actions = ["b"]
 
-    #[test]
-    #[parallel]
-    fn group_defaults() {
-        let temp = TempDir::new().unwrap();
-        let workflow = r#"
 [[action]]
 name = "b"
 command = "c"
-[action.group]
+previous_actions = ["a"]
 "#;
         let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        let actions: Vec<&Action> = workflow.action.iter().collect();
+        let names = |actions: Vec<&Action>| -> Vec<String> {
+            actions.iter().map(|a| a.name().to_string()).collect()
+        };
 
-        assert_eq!(workflow.action.len(), 1);
-
-        let action = workflow.action.first().unwrap();
-
-        assert!(action.submit_options.is_empty());
-        assert!(action.group.include().is_empty());
-        assert!(action.group.sort_by().is_empty());
-        assert!(!action.group.split_by_sort_key());
-        assert_eq!(action.group.maximum_size, None);
-        assert!(!action.group.submit_whole());
-        assert!(!action.group.reverse_sort());
+        assert_eq!(
+            names(order_by_previous_actions(&actions)),
+            vec!["plot", "equilibrate", "simulate", "analyze", "a", "b"]
+        );
+        assert_eq!(
+            names(order_by_previous_actions(&[actions[0], actions[1]])),
+            vec!["simulate", "analyze"]
+        );
+        assert!(order_by_previous_actions(&[]).is_empty());
     }
 
     #[test]
     #[parallel]
-    fn action_duplicate() {
+    fn action_submit_command_override() {
         let temp = TempDir::new().unwrap();
         let workflow = r#"
+[default.action]
+submit_command_override = "wrapper {script}"
+
 [[action]]
-name = "b"
+name = "a"
 command = "c"
 
 [[action]]
 name = "b"
-command = "d"
+command = "c"
+submit_command_override = "other --script={script}"
+"#;
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        assert_eq!(
+            workflow.action[0].submit_command_override.as_deref(),
+            Some("wrapper {script}")
+        );
+        assert_eq!(
+            workflow.action[1].submit_command_override.as_deref(),
+            Some("other --script={script}")
+        );
+
+        let workflow = r#"
+[[action]]
+name = "a"
+command = "c"
+submit_command_override = "wrapper"
 "#;
         let result = Workflow::open_str(temp.path(), workflow);
-        assert!(result.is_ok());
+        assert!(matches!(result, Err(Error::SubmitCommandMissingScript(_))));
     }
 
     #[test]
     #[parallel]
-    fn action_duplicate_different_products() {
+    fn action_product_templates() {
         let temp = TempDir::new().unwrap();
         let workflow = r#"
+[workspace]
+value_file = "v.json"
+value_cache = "referenced"
+
 [[action]]
-name = "b"
+name = "a"
 command = "c"
-products = ["e"]
+products = ["out.h5"]
 
 [[action]]
 name = "b"
-command = "d"
-products = ["b"]
+command = "c"
+products = ["out_{/seed}.h5", "{/name}-{/p/0}.log"]
 "#;
-        let result = Workflow::open_str(temp.path(), workflow);
-        assert!(matches!(
-            result,
-            Err(Error::DuplicateActionsDifferentProducts(_))
-        ));
-
-        assert!(result
-            .unwrap_err()
-            .to_string()
-            .contains("must have the same `products`"));
-    }
 
-    #[test]
-    #[parallel]
-    fn action_duplicate_different_previous_actions() {
-        let temp = TempDir::new().unwrap();
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        let directory = Path::new("dir1");
+        let value = serde_json::json!({"seed": 12, "name": "x", "p": [0.5]});
+        assert!(!workflow.action[0].has_product_templates());
+        assert_eq!(
+            workflow.action[0].product_names(directory, &value).unwrap(),
+            vec!["out.h5"]
+        );
+        assert!(workflow.action[1].has_product_templates());
+        assert_eq!(
+            workflow.action[1].product_names(directory, &value).unwrap(),
+            vec!["out_12.h5", "x-0.5.log"]
+        );
+        assert_eq!(
+            workflow.action[1].product_pointers(),
+            vec!["/seed".to_string(), "/name".to_string(), "/p/0".to_string()]
+        );
+        assert_eq!(
+            workflow.value_pointers(),
+            Some(vec![
+                "/name".to_string(),
+                "/p/0".to_string(),
+                "/seed".to_string()
+            ])
+        );
+        assert!(matches!(
+            workflow.action[1].product_names(directory, &serde_json::Value::Null),
+            Err(Error::JSONPointerNotFound(_, ref p)) if p == "/seed"
+        ));
+
         let workflow = r#"
 [[action]]
 name = "b"
 command = "c"
+products = ["out_{directory}.h5"]
+"#;
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(matches!(result, Err(Error::InvalidProduct(_, ref p)) if p == "{directory}"));
 
+        let workflow = r#"
 [[action]]
 name = "b"
-command = "d"
-previous_actions = ["a"]
-
-[[action]]
-name = "a"
-command = "e"
+command = "c"
+products = ["out_{/seed}.h5"]
 "#;
         let result = Workflow::open_str(temp.path(), workflow);
-        assert!(matches!(
-            result,
-            Err(Error::DuplicateActionsDifferentPreviousActions(_))
-        ));
-
-        assert!(result
-            .unwrap_err()
-            .to_string()
-            .contains("must have the same `previous_actions`"));
+        assert!(matches!(result, Err(Error::ProductsRequireValueFile(ref a)) if a == "b"));
     }
 
     #[test]
     #[parallel]
-    fn action_launchers() {
+    fn action_log_output() {
         let temp = TempDir::new().unwrap();
         let workflow = r#"
+[[action]]
+name = "a"
+command = "c {directory}"
+
 [[action]]
 name = "b"
-command = "c"
-launchers = ["openmp", "mpi"]
+command = "c {directory}"
+log_output = true
 "#;
 
         let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        assert!(!workflow.action[0].log_output());
+        assert!(workflow.action[1].log_output());
 
-        assert_eq!(workflow.action.len(), 1);
-
-        let action = workflow.action.first().unwrap();
-        assert_eq!(
-            action.launchers(),
-            vec!["openmp".to_string(), "mpi".to_string()]
-        );
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c {directories}"
+log_output = true
+"#;
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(matches!(result, Err(Error::InvalidLogOutput(_))));
     }
 
     #[test]
     #[parallel]
-    fn action_previous_actions() {
+    fn action_eligible_when() {
         let temp = TempDir::new().unwrap();
         let workflow = r#"
+[default.action]
+eligible_when = "d"
+
 [[action]]
-name = "b"
+name = "a"
 command = "c"
 
 [[action]]
-name = "d"
-command = "e"
-previous_actions = ["b"]
+name = "b"
+command = "c"
+eligible_when = "test -e {directory}/ready"
 "#;
 
         let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
-
-        assert_eq!(workflow.action.len(), 2);
-
-        let action = workflow.action.get(1).unwrap();
-        assert_eq!(action.previous_actions(), vec!["b".to_string()]);
-
-        let action_a = workflow.action_by_name("b");
-        assert_eq!(action_a.unwrap().command(), "c");
-
-        let action_d = workflow.action_by_name("d");
-        assert_eq!(action_d.unwrap().command(), "e");
-
-        assert!(workflow.action_by_name("f").is_none());
+        assert_eq!(workflow.action[0].eligible_when(), Some("d"));
+        assert_eq!(
+            workflow.action[1].eligible_when(),
+            Some("test -e {directory}/ready")
+        );
     }
 
     #[test]
     #[parallel]
-    fn previous_action_error() {
+    fn redact() {
         let temp = TempDir::new().unwrap();
         let workflow = r#"
+redact = ["account", "custom", "setup"]
+
+[default.action.submit_options.cluster]
+account = "secret"
+qos = "high"
+
+[[action]]
+name = "a"
+command = "c"
+[action.submit_options.cluster]
+custom = ["--comment=secret-project"]
+setup = "module load secret"
+
 [[action]]
 name = "b"
 command = "c"
-previous_actions = ["a"]
+[action.submit_options.other]
+account = "other"
 "#;
-        let result = Workflow::open_str(temp.path(), workflow);
-        assert!(
-            result.is_err(),
-            "Expected previous action error, but got {result:?}"
+
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        assert_eq!(
+            workflow.redact,
+            vec![
+                RedactedOption::Account,
+                RedactedOption::Custom,
+                RedactedOption::Setup
+            ]
+        );
+        let script = format!(
+            "#SBATCH --account=secret\n#SBATCH --comment=secret-project\n#SBATCH --qos=high\n\
+             \nmodule load secret\n\n{SETUP_CHECK}\necho secret other module load secret\n"
+        );
+        assert_eq!(
+            workflow.redact("cluster", &script),
+            format!(
+                "#SBATCH --account=<redacted>\n#SBATCH <redacted>\n#SBATCH --qos=high\n\
+                 \n<redacted>\n\n{SETUP_CHECK}\necho secret other module load secret\n"
+            )
+        );
+        assert_eq!(
+            workflow.redact("other", &script),
+            format!(
+                "#SBATCH --account=<redacted>\n#SBATCH --comment=secret-project\n\
+                 #SBATCH --qos=high\n\nmodule load secret\n\n{SETUP_CHECK}\n\
+                 echo secret other module load secret\n"
+            )
         );
 
-        assert!(result
-            .unwrap_err()
-            .to_string()
-            .starts_with("Previous action 'a' not found"));
+        let workflow = Workflow::open_str(temp.path(), "").unwrap();
+        assert_eq!(workflow.redact("cluster", &script), script);
     }
 
     #[test]
     #[parallel]
-    fn action_resources() {
+    fn redact_file() {
         let temp = TempDir::new().unwrap();
-        let workflow = r#"
-[[action]]
-name = "b"
-command = "c"
-[action.resources]
-processes.per_submission = 12
-threads_per_process = 8
-gpus_per_process = 1
-walltime.per_submission = "4d, 05:32:11"
-"#;
+        let contents = r#"
+redact = ["account", "custom"]
 
-        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
-
-        assert_eq!(workflow.action.len(), 1);
+# The account is secret.
+[default.action.submit_options.cluster]
+account = "secret"
+qos = "high"
 
-        let action = workflow.action.first().unwrap();
-        assert_eq!(action.resources.processes(), Processes::PerSubmission(12));
-        assert_eq!(action.resources.threads_per_process, Some(8));
-        assert_eq!(action.resources.gpus_per_process, Some(1));
+[[action]]
+name = "a"
+command = "echo secret"
+[action.submit_options.cluster]
+custom = ["--comment=project", "--mail-user=me"]
+"#;
+        let workflow = Workflow::open_str(temp.path(), contents).unwrap();
+
+        let redacted = workflow
+            .redact_file(Path::new("workflow.toml"), contents)
+            .unwrap();
+        assert!(!redacted.contains("The account"));
+        assert!(!redacted.contains("--comment"));
+        assert!(redacted.contains("command = \"echo secret\""));
+        assert!(redacted.contains("qos = \"high\""));
+        let parsed = Workflow::open_str(temp.path(), &redacted).unwrap();
         assert_eq!(
-            action.resources.walltime(),
-            Walltime::PerSubmission(
-                Duration::new(true, 4, 5 * 3600 + 32 * 60 + 11, 0)
-                    .expect("this should be a valid Duration"),
-            )
+            parsed.action[0].submit_options["cluster"]
+                .account
+                .as_deref(),
+            Some(REDACTED)
+        );
+        assert_eq!(
+            parsed.action[0].submit_options["cluster"].custom,
+            vec![REDACTED, REDACTED]
         );
+
+        let yaml = "action:\n- name: a\n  command: c\n  submit_options:\n    cluster:\n      account: secret\n";
+        let redacted = workflow
+            .redact_file(Path::new("workflow.yaml"), yaml)
+            .unwrap();
+        assert!(redacted.contains("account: <redacted>"));
+        assert!(!redacted.contains("secret"));
     }
 
     #[test]
@@ -1242,10 +4238,14 @@ command = "c"
 sort_by = ["/sort"]
 split_by_sort_key = true
 maximum_size = 10
+minimum_size = 4
+leftover = "defer"
 submit_whole = true
 reverse_sort = true
+missing = "treat_as_null"
 [[action.group.include]]
 condition = ["/d", "==", 5]
+missing = "exclude"
 [[action.group.include]]
 all = [["/float", ">", 6.5], ["/string", "<", "str"], ["/array", "==", [1,2,3]], ["/bool", "==", false]]
 "#;
@@ -1258,12 +4258,15 @@ all = [["/float", ">", 6.5], ["/string", "<", "str"], ["/array", "==", [1,2,3]],
         assert_eq!(
             action.group.include(),
             vec![
-                Selector::Condition((
-                    "/d".to_string(),
-                    Comparison::EqualTo,
-                    serde_json::Value::from(5)
-                )),
-                Selector::All(vec![
+                Selector {
+                    missing: Some(Missing::Exclude),
+                    ..Selector::condition((
+                        "/d".to_string(),
+                        Comparison::EqualTo,
+                        serde_json::Value::from(5)
+                    ))
+                },
+                Selector::all(vec![
                     (
                         "/float".to_string(),
                         Comparison::GreaterThan,
@@ -1290,8 +4293,133 @@ all = [["/float", ">", 6.5], ["/string", "<", "str"], ["/array", "==", [1,2,3]],
         assert_eq!(action.group.sort_by(), vec![String::from("/sort")]);
         assert!(action.group.split_by_sort_key());
         assert_eq!(action.group.maximum_size, Some(10));
+        assert_eq!(action.group.minimum_size, Some(4));
+        assert_eq!(action.group.leftover(), Leftover::Defer);
         assert!(action.group.submit_whole());
         assert!(action.group.reverse_sort());
+        assert_eq!(action.group.missing(), Missing::TreatAsNull);
+    }
+
+    #[test]
+    #[parallel]
+    fn action_group_invalid_selector() {
+        let temp = TempDir::new().unwrap();
+        for selector in [
+            "missing = \"exclude\"",
+            "condition = [\"/d\", \"==\", 5]\nall = []",
+        ] {
+            let workflow = format!(
+                r#"
+[[action]]
+name = "b"
+command = "c"
+[[action.group.include]]
+{selector}
+"#
+            );
+            let result = Workflow::open_str(temp.path(), &workflow);
+            assert!(
+                matches!(&result, Err(Error::TOMLParse(_, e)) if e.to_string().contains("one of `condition` or `all`")),
+                "{result:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn action_group_membership() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+[[action.group.include]]
+condition = ["/ensemble", "in", ["npt", "nvt"]]
+[[action.group.include]]
+all = [["/ensemble", "not_in", ["nve"]], ["/n", "!=", 4]]
+"#;
+
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        let action = workflow.action.first().unwrap();
+        assert_eq!(
+            action.group.include(),
+            vec![
+                Selector::condition((
+                    "/ensemble".to_string(),
+                    Comparison::In,
+                    serde_json::Value::from(vec!["npt", "nvt"])
+                )),
+                Selector::all(vec![
+                    (
+                        "/ensemble".to_string(),
+                        Comparison::NotIn,
+                        serde_json::Value::from(vec!["nve"])
+                    ),
+                    (
+                        "/n".to_string(),
+                        Comparison::NotEqualTo,
+                        serde_json::Value::from(4)
+                    ),
+                ])
+            ]
+        );
+
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+[[action.group.include]]
+condition = ["/ensemble", "in", "npt"]
+"#;
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(matches!(
+            result,
+            Err(Error::MembershipNotArray(pointer, action)) if pointer == "/ensemble" && action == "b"
+        ));
+    }
+
+    #[test]
+    #[parallel]
+    fn action_group_matches() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+[[action.group.include]]
+condition = ["/system/name", "matches", "^lj_.*"]
+"#;
+
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        let action = workflow.action.first().unwrap();
+        assert_eq!(
+            action.group.include(),
+            vec![Selector::condition((
+                "/system/name".to_string(),
+                Comparison::Matches,
+                serde_json::Value::from("^lj_.*")
+            ))]
+        );
+
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+[[action.group.include]]
+condition = ["/system/name", "matches", "lj_("]
+"#;
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(matches!(result, Err(Error::InvalidPattern(pattern, ..)) if pattern == "lj_("));
+
+        let workflow = r#"
+[[action]]
+name = "b"
+command = "c"
+[[action.group.include]]
+condition = ["/system/name", "matches", 4]
+"#;
+        let result = Workflow::open_str(temp.path(), workflow);
+        assert!(matches!(result, Err(Error::PatternNotString(..))));
     }
 
     #[test]
@@ -1545,7 +4673,10 @@ sort_by = ["/g"]
 split_by_sort_key = true
 reverse_sort = true
 maximum_size = 6
+minimum_size = 2
+leftover = "error"
 submit_whole = true
+missing = "exclude"
 [[default.action.group.include]]
 condition = ["/f", "==", 5]
 
@@ -1561,8 +4692,8 @@ name = "d"
 
         let action = workflow.action.first().unwrap();
         assert_eq!(action.name(), "a");
-        assert_eq!(action.command(), "b");
-        assert_eq!(action.launchers(), vec!["c"]);
+        assert_eq!(action.commands(), ["b"]);
+        assert_eq!(action.launchers("any_cluster"), vec!["c"]);
         assert_eq!(action.previous_actions(), vec!["d"]);
         assert_eq!(action.products(), vec!["e"]);
         assert_eq!(action.resources.processes(), Processes::PerDirectory(2));
@@ -1575,7 +4706,7 @@ name = "d"
         assert!(action.submit_options.is_empty());
         assert_eq!(
             action.group.include(),
-            vec![Selector::Condition((
+            vec![Selector::condition((
                 "/f".into(),
                 Comparison::EqualTo,
                 serde_json::Value::from(5)
@@ -1584,8 +4715,11 @@ name = "d"
         assert_eq!(action.group.sort_by(), vec!["/g"]);
         assert!(action.group.split_by_sort_key());
         assert!(action.group.reverse_sort());
+        assert_eq!(action.group.minimum_size, Some(2));
+        assert_eq!(action.group.leftover(), Leftover::Error);
         assert_eq!(action.group.maximum_size, Some(6));
         assert!(action.group.submit_whole());
+        assert_eq!(action.group.missing(), Missing::Exclude);
         assert_eq!(action.from, None);
     }
 
@@ -1651,8 +4785,8 @@ name = "dd"
 
         let action = workflow.action.first().unwrap();
         assert_eq!(action.name(), "aa");
-        assert_eq!(action.command(), "bb");
-        assert_eq!(action.launchers(), vec!["cc"]);
+        assert_eq!(action.commands(), ["bb"]);
+        assert_eq!(action.launchers("any_cluster"), vec!["cc"]);
         assert_eq!(action.previous_actions(), vec!["dd"]);
         assert_eq!(action.products(), vec!["ee"]);
         assert_eq!(action.resources.processes(), Processes::PerDirectory(4));
@@ -1665,7 +4799,7 @@ name = "dd"
         assert!(action.submit_options.is_empty());
         assert_eq!(
             action.group.include(),
-            vec![Selector::Condition((
+            vec![Selector::condition((
                 "/ff".into(),
                 Comparison::EqualTo,
                 serde_json::Value::from(10)
@@ -1722,8 +4856,8 @@ command = "e"
 
         let action = &workflow.action[1];
         assert_eq!(action.name(), "a");
-        assert_eq!(action.command(), "b");
-        assert_eq!(action.launchers(), vec!["c"]);
+        assert_eq!(action.commands(), ["b"]);
+        assert_eq!(action.launchers("any_cluster"), vec!["c"]);
         assert_eq!(action.previous_actions(), vec!["d"]);
         assert_eq!(action.products(), vec!["e"]);
         assert_eq!(action.resources.processes(), Processes::PerDirectory(2));
@@ -1736,7 +4870,7 @@ command = "e"
         assert!(action.submit_options.is_empty());
         assert_eq!(
             action.group.include(),
-            vec![Selector::Condition((
+            vec![Selector::condition((
                 "/f".into(),
                 Comparison::EqualTo,
                 serde_json::Value::from(5)
@@ -1820,8 +4954,8 @@ command = "e"
 
         let action = &workflow.action[1];
         assert_eq!(action.name(), "aa");
-        assert_eq!(action.command(), "bb");
-        assert_eq!(action.launchers(), vec!["cc"]);
+        assert_eq!(action.commands(), ["bb"]);
+        assert_eq!(action.launchers("any_cluster"), vec!["cc"]);
         assert_eq!(action.previous_actions(), vec!["dd"]);
         assert_eq!(action.products(), vec!["ee"]);
         assert_eq!(action.resources.processes(), Processes::PerDirectory(4));
@@ -1834,7 +4968,7 @@ command = "e"
         assert!(action.submit_options.is_empty());
         assert_eq!(
             action.group.include(),
-            vec![Selector::Condition((
+            vec![Selector::condition((
                 "/ff".into(),
                 Comparison::EqualTo,
                 serde_json::Value::from(10)
@@ -1848,6 +4982,91 @@ command = "e"
         assert_eq!(action.from, Some("a".into()));
     }
 
+    #[test]
+    #[parallel]
+    fn resource_presets() {
+        let temp = TempDir::new().unwrap();
+        let workflow = r#"
+[resources.presets.gpu_small]
+processes.per_submission = 1
+gpus_per_process = 1
+walltime.per_submission = "01:00:00"
+
+[default.action.resources]
+threads_per_process = 2
+walltime.per_submission = "00:00:01"
+
+[[action]]
+name = "a"
+command = "c"
+resources.preset = "gpu_small"
+resources.walltime.per_directory = "00:10:00"
+
+[[action]]
+name = "b"
+command = "c"
+from = "a"
+
+[[action]]
+name = "c"
+command = "c"
+"#;
+
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        for action in &workflow.action[0..2] {
+            assert_eq!(action.resources.preset, Some("gpu_small".into()));
+            assert_eq!(action.resources.processes(), Processes::PerSubmission(1));
+            assert_eq!(action.resources.gpus_per_process, Some(1));
+            assert_eq!(action.resources.threads_per_process, Some(2));
+            assert_eq!(
+                action.resources.walltime(),
+                Walltime::PerDirectory(Duration::new(true, 0, 600, 0).unwrap())
+            );
+        }
+
+        let action = &workflow.action[2];
+        assert_eq!(action.resources.preset, None);
+        assert_eq!(action.resources.gpus_per_process, None);
+        assert_eq!(
+            action.resources.walltime(),
+            Walltime::PerSubmission(Duration::new(true, 0, 1, 0).unwrap())
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn invalid_resource_presets() {
+        let temp = TempDir::new().unwrap();
+        let result = Workflow::open_str(
+            temp.path(),
+            r#"
+[[action]]
+name = "a"
+command = "c"
+resources.preset = "b"
+"#,
+        );
+        assert!(matches!(
+            result,
+            Err(Error::ResourcePresetNotFound(preset, action)) if preset == "b" && action == "a"
+        ));
+
+        let result = Workflow::open_str(
+            temp.path(),
+            r#"
+[resources.presets.a]
+preset = "b"
+[resources.presets.b]
+threads_per_process = 2
+"#,
+        );
+        assert!(matches!(
+            result,
+            Err(Error::NestedResourcePreset(preset)) if preset == "a"
+        ));
+    }
+
     #[test]
     #[parallel]
     fn action_override_mixed() {
@@ -1872,7 +5091,7 @@ resources.processes.per_directory = 8
 
         let action = &workflow.action[1];
         assert_eq!(action.name(), "a");
-        assert_eq!(action.command(), "b");
+        assert_eq!(action.commands(), ["b"]);
         assert_eq!(action.resources.processes(), Processes::PerDirectory(8));
         assert_eq!(action.resources.threads_per_process, Some(2));
         assert_eq!(action.resources.gpus_per_process, Some(4));
@@ -2021,12 +5240,16 @@ resources.processes.per_directory = 8
         assert_eq!(r.cost(4), ResourceCost::with_values(160.0, 0.0));
 
         let r = Resources {
+            preset: None,
             processes: Some(Processes::PerSubmission(10)),
             walltime: Some(Walltime::PerDirectory(
                 Duration::new(true, 0, 3600, 0).unwrap(),
             )),
             threads_per_process: Some(4),
             gpus_per_process: Some(2),
+            component: Vec::new(),
+            binding: None,
+            concurrent_directories: None,
         };
 
         assert_eq!(r.cost(1), ResourceCost::with_values(0.0, 20.0));
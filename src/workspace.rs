@@ -2,56 +2,302 @@
 // Part of row, released under the BSD 3-Clause License.
 
 use indicatif::ProgressBar;
-use log::debug;
+use log::{debug, trace, warn};
+use nix::sys::statvfs::statvfs;
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
 use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
-use std::ffi::OsStr;
-use std::fs;
-use std::path::PathBuf;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::mem;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{self, Receiver};
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
 use std::time::Duration;
 
-use crate::workflow::Workflow;
-use crate::{progress_styles, Error, MultiProgressContainer, MIN_PROGRESS_BAR_SIZE};
+use crate::workflow::{Action, NonUtf8Names, ProductsChecksum, ValueMode, Workflow};
+use crate::{
+    fnv1a, progress_styles, Error, MultiProgressContainer, FNV_OFFSET_BASIS, MIN_PROGRESS_BAR_SIZE,
+};
+
+/// Number of workspace entries to check in each parallel task when listing.
+const LIST_CHUNK_SIZE: usize = 1024;
+
+/// Access the directories and files in a workspace.
+///
+/// All paths are relative to the workspace.
+///
+pub(crate) trait Backend: Send + Sync {
+    /// List the names of the directories in the workspace.
+    ///
+    /// Increment `progress` by the number of entries listed.
+    ///
+    fn list_directories(&self, progress: &ProgressBar) -> Result<Vec<OsString>, Error>;
+
+    /// List the names of the entries (files and directories) in a directory.
+    fn list_entries(&self, directory: &Path) -> Result<HashSet<OsString>, Error>;
+
+    /// Read the contents of a file.
+    fn read_file(&self, path: &Path) -> Result<Vec<u8>, Error>;
+
+    /// Open a file to read its contents incrementally.
+    fn open_file<'a>(&'a self, path: &Path) -> Result<Box<dyn Read + 'a>, Error>;
+
+    /// Get the modification time of the workspace (`None` when not available).
+    fn modified_time(&self) -> Result<Option<(i64, i64)>, Error>;
+
+    /// Describe the location of a path for messages.
+    fn location(&self, path: &Path) -> PathBuf;
+}
+
+/// A workspace on the local filesystem.
+struct Filesystem {
+    /// The workspace directory.
+    path: PathBuf,
+}
+
+impl Backend for Filesystem {
+    fn list_directories(&self, progress: &ProgressBar) -> Result<Vec<OsString>, Error> {
+        let entries = self
+            .path
+            .read_dir()
+            .map_err(|e| Error::DirectoryRead(self.path.clone(), e))?;
+        let (sender, receiver) = mpsc::channel();
+
+        // `file_type` calls `stat` when the filesystem does not report entry
+        // types in the listing (common on network filesystems). Read the
+        // entries in chunks on this thread and check the types in parallel.
+        let check = |chunk: Vec<fs::DirEntry>| -> Result<Vec<OsString>, Error> {
+            let mut directories = Vec::new();
+            for entry in &chunk {
+                let file_type = entry
+                    .file_type()
+                    .map_err(|e| Error::DirectoryRead(self.path.clone(), e))?;
+                if file_type.is_dir() {
+                    directories.push(entry.file_name());
+                }
+            }
+
+            progress.inc(chunk.len() as u64);
+            Ok(directories)
+        };
+        let check = &check;
+
+        rayon::scope(|scope| {
+            let mut chunk = Vec::with_capacity(LIST_CHUNK_SIZE);
+            for entry in entries {
+                chunk.push(entry.map_err(|e| Error::DirectoryRead(self.path.clone(), e))?);
+
+                if chunk.len() == LIST_CHUNK_SIZE {
+                    let full = mem::replace(&mut chunk, Vec::with_capacity(LIST_CHUNK_SIZE));
+                    let sender = sender.clone();
+                    scope.spawn(move |_| {
+                        sender.send(check(full)).expect("Receiver should be open");
+                    });
+                }
+            }
+
+            sender.send(check(chunk)).expect("Receiver should be open");
+            Ok::<(), Error>(())
+        })?;
+        drop(sender);
+
+        let mut directories = Vec::new();
+        for result in receiver {
+            directories.extend(result?);
+        }
+
+        Ok(directories)
+    }
+
+    fn list_entries(&self, directory: &Path) -> Result<HashSet<OsString>, Error> {
+        let directory_path = self.path.join(directory);
+        let mut entries = HashSet::new();
+
+        for entry in directory_path
+            .read_dir()
+            .map_err(|e| Error::DirectoryRead(directory_path.clone(), e))?
+        {
+            let entry = entry.map_err(|e| Error::DirectoryRead(directory_path.clone(), e))?;
+            entries.insert(entry.file_name());
+        }
+
+        Ok(entries)
+    }
+
+    fn read_file(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        let path = self.path.join(path);
+        fs::read(&path).map_err(|e| Error::FileRead(path, e))
+    }
+
+    fn open_file<'a>(&'a self, path: &Path) -> Result<Box<dyn Read + 'a>, Error> {
+        let path = self.path.join(path);
+        let file = File::open(&path).map_err(|e| Error::FileRead(path, e))?;
+        Ok(Box::new(file))
+    }
+
+    fn modified_time(&self) -> Result<Option<(i64, i64)>, Error> {
+        let metadata =
+            fs::metadata(&self.path).map_err(|e| Error::DirectoryRead(self.path.clone(), e))?;
+        Ok(Some((metadata.mtime(), metadata.mtime_nsec())))
+    }
+
+    fn location(&self, path: &Path) -> PathBuf {
+        self.path.join(path)
+    }
+}
+
+/// The backend of a workflow's workspace, opened on first use.
+///
+/// Clones share the backend so that an object store client (and its runtime)
+/// is created once per project.
+///
+#[derive(Clone, Default)]
+pub(crate) struct BackendCache(Arc<Mutex<Option<Arc<dyn Backend>>>>);
+
+impl std::fmt::Debug for BackendCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("BackendCache")
+    }
+}
+
+/// The cache does not affect the workflow's behavior.
+impl PartialEq for BackendCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for BackendCache {}
+
+/// Open the workflow's workspace.
+///
+/// Reuse the backend opened by a previous call with the same workflow.
+///
+/// # Errors
+/// Returns `Err<row::Error>` when `workspace.url` is not supported or the
+/// object store cannot be configured.
+///
+/// # Panics
+/// When the backend cache mutex is poisoned.
+///
+pub(crate) fn open_backend(workflow: &Workflow) -> Result<Arc<dyn Backend>, Error> {
+    let mut cache = workflow.backend.0.lock().expect("Backend cache mutex");
+    if let Some(backend) = cache.as_ref() {
+        return Ok(Arc::clone(backend));
+    }
+
+    let backend: Arc<dyn Backend> = match &workflow.workspace.url {
+        None => Arc::new(Filesystem {
+            path: workflow.root.join(&workflow.workspace.path),
+        }),
+        #[cfg(feature = "s3")]
+        Some(url) => Arc::new(crate::s3::S3::open(url)?),
+        #[cfg(not(feature = "s3"))]
+        Some(url) => return Err(Error::WorkspaceUrlNotEnabled(url.clone())),
+    };
+    *cache = Some(Arc::clone(&backend));
+    Ok(backend)
+}
+
+/// Split an `s3://bucket/prefix` url into the bucket and the key prefix.
+///
+/// The prefix is empty or ends with `/`.
+///
+/// # Errors
+/// Returns `Err(row::Error::UnsupportedWorkspaceUrl)` when `url` is not an S3 url.
+///
+pub(crate) fn parse_s3_url(url: &str) -> Result<(String, String), Error> {
+    let Some(location) = url.strip_prefix("s3://") else {
+        return Err(Error::UnsupportedWorkspaceUrl(url.into()));
+    };
+
+    let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+    if bucket.is_empty() {
+        return Err(Error::UnsupportedWorkspaceUrl(url.into()));
+    }
+
+    let prefix = prefix.trim_matches('/');
+    if prefix.is_empty() {
+        Ok((bucket.into(), String::new()))
+    } else {
+        Ok((bucket.into(), format!("{prefix}/")))
+    }
+}
+
+/// Get the modification time of the workspace.
+///
+/// # Returns
+/// `Ok(None)` when the workspace does not provide a modification time.
+///
+/// # Errors
+/// Returns `Err<row::Error>` when the workspace cannot be accessed.
+///
+pub(crate) fn modified_time(workflow: &Workflow) -> Result<Option<(i64, i64)>, Error> {
+    open_backend(workflow)?.modified_time()
+}
+
+/// Get the free space (in bytes) available to the user in the workspace.
+///
+/// # Errors
+/// Returns `Err<row::Error>` when the workspace filesystem cannot be queried.
+///
+pub fn free_space(workflow: &Workflow) -> Result<u64, Error> {
+    let path = workflow.root.join(&workflow.workspace.path);
+    let stat = statvfs(&path).map_err(|e| Error::FreeSpace(path.clone(), e))?;
+
+    #[allow(clippy::useless_conversion)]
+    Ok(u64::from(stat.blocks_available()) * u64::from(stat.fragment_size()))
+}
 
 /// List all directories in the workspace as found on the filesystem.
 ///
+/// Check the types of the entries with `io_threads` threads. Show a spinner
+/// with the number of entries listed per second. Exclude the directories with
+/// names that are not valid UTF-8 when `workspace.non_utf8_names` is `skip`.
+///
 /// # Errors
 /// Returns `Err<row::Error>` when the workspace directory cannot be accessed.
 ///
+/// # Panics
+/// When the thread pool cannot be created.
+///
 pub fn list_directories(
     workflow: &Workflow,
+    io_threads: u16,
     multi_progress: &mut MultiProgressContainer,
 ) -> Result<Vec<PathBuf>, Error> {
-    let workspace_path = workflow.root.join(&workflow.workspace.path);
+    let backend = open_backend(workflow)?;
 
     let progress = multi_progress.add(ProgressBar::new_spinner().with_message("Listing workspace"));
-    progress.set_style(progress_styles::counted_spinner());
+    progress.set_style(progress_styles::rate_spinner());
     progress.enable_steady_tick(Duration::from_millis(progress_styles::STEADY_TICK));
 
-    let mut directories = Vec::new();
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(usize::from(io_threads))
+        .thread_name(|i| format!("list-workspace-{i}"))
+        .build()
+        .expect("Should be able to build the thread pool.");
 
-    for entry in workspace_path
-        .read_dir()
-        .map_err(|e| Error::DirectoryRead(workspace_path.clone(), e))?
-    {
-        match entry {
-            Ok(ref entry) => {
-                let file_type = entry
-                    .file_type()
-                    .map_err(|e| Error::DirectoryRead(workspace_path.clone(), e))?;
+    let mut directories = Vec::new();
 
-                if file_type.is_dir() {
-                    progress.inc(1);
-                    directories.push(PathBuf::from(entry.file_name()));
-                }
-            }
-            Err(e) => {
-                return Err(Error::DirectoryRead(workspace_path, e));
-            }
+    for name in pool.install(|| backend.list_directories(&progress))? {
+        if name.to_str().is_none() && workflow.workspace.non_utf8_names == NonUtf8Names::Skip {
+            warn!(
+                "Skipping directory '{}': its name is not valid UTF-8.",
+                name.to_string_lossy()
+            );
+            continue;
         }
+
+        directories.push(PathBuf::from(name));
     }
 
     progress.finish();
@@ -85,15 +331,21 @@ pub struct CompletedDirectories {
 /// * `directories` - The directories to scan. Must be present in the workspace.
 /// * `io_threads` - Number of threads to use while scanning directories.
 ///
+/// # Errors
+/// Returns `Err<row::Error>` when the workspace cannot be accessed.
+///
 /// # Panics
 /// When unable to spawn threads.
 ///
+#[allow(clippy::too_many_lines)]
 pub fn find_completed_directories(
     workflow: &Workflow,
     directories: Vec<PathBuf>,
     io_threads: u16,
     multi_progress: &mut MultiProgressContainer,
-) -> CompletedDirectories {
+) -> Result<CompletedDirectories, Error> {
+    let backend = open_backend(workflow)?;
+
     let mut progress =
         ProgressBar::new(directories.len() as u64).with_message("Scanning directories");
     progress = multi_progress.add_or_hide(progress, directories.len() < MIN_PROGRESS_BAR_SIZE);
@@ -104,22 +356,47 @@ pub fn find_completed_directories(
         debug!("Finding completed directories.");
     }
 
-    let workspace_path = workflow.root.join(&workflow.workspace.path);
     let directories_mutex = Arc::new(Mutex::new(directories));
     let (sender, receiver) = mpsc::channel();
 
-    let mut action_products: Vec<(String, Vec<String>)> = Vec::new();
-    for action in &workflow.action {
-        if !action.products().is_empty() {
-            action_products.push((action.name().into(), action.products().into()));
+    let actions: Arc<Vec<Action>> = Arc::new(
+        workflow
+            .action
+            .iter()
+            .filter(|a| !a.products().is_empty())
+            .cloned()
+            .collect(),
+    );
+
+    // Read the directory values only when products include JSON pointers.
+    let mut value_file = None;
+    let mut consolidated_values = None;
+    if actions.iter().any(Action::has_product_templates) {
+        match (
+            &workflow.workspace.value_file,
+            workflow.workspace.value_mode,
+        ) {
+            (Some(file), ValueMode::Consolidated) => {
+                consolidated_values = Some(Arc::new((
+                    file.clone(),
+                    read_consolidated_values(backend.as_ref(), file)?,
+                )));
+            }
+            (file, _) => value_file.clone_from(file),
         }
     }
 
+    let checksum_file = workflow.workspace.products_checksum_file.clone();
+    let checksum_mode = workflow.workspace.products_checksum;
+
     let mut threads = Vec::with_capacity(io_threads as usize);
 
     for i in 0..io_threads {
-        let action_products = action_products.clone();
-        let workspace_path = workspace_path.clone();
+        let actions = Arc::clone(&actions);
+        let value_file = value_file.clone();
+        let checksum_file = checksum_file.clone();
+        let consolidated_values = consolidated_values.clone();
+        let backend = Arc::clone(&backend);
         let directories_mutex = directories_mutex.clone();
         let sender = sender.clone();
         let progress = progress.clone();
@@ -129,9 +406,6 @@ pub fn find_completed_directories(
             thread::Builder::new()
                 .name(thread_name)
                 .spawn(move || -> Result<(), Error> {
-                    let mut directory_path = workspace_path;
-                    let mut directory_contents = HashSet::new();
-
                     loop {
                         let current_directory;
 
@@ -146,42 +420,88 @@ pub fn find_completed_directories(
                         }
 
                         // List all files in the current directory.
-                        directory_path.push(&current_directory);
-
-                        for entry in directory_path
-                            .read_dir()
-                            .map_err(|e| Error::DirectoryRead(directory_path.clone(), e))?
-                        {
-                            let entry_name = entry
-                                .map_err(|e| Error::DirectoryRead(directory_path.clone(), e))?
-                                .file_name();
-
-                            directory_contents.insert(entry_name);
-                        }
+                        let directory_contents = backend.list_entries(&current_directory)?;
+
+                        let value = match (&consolidated_values, &value_file) {
+                            (Some(consolidated), _) => {
+                                let (file, values) = consolidated.as_ref();
+                                values.get(&current_directory).cloned().ok_or_else(|| {
+                                    Error::ConsolidatedValueNotFound(
+                                        current_directory.clone(),
+                                        backend.location(file),
+                                    )
+                                })?
+                            }
+                            (None, Some(value_file)) => {
+                                read_value(backend.as_ref(), &current_directory.join(value_file))?
+                            }
+                            (None, None) => Value::Null,
+                        };
+
+                        // Read the checksum manifest only when an action's products are present.
+                        let mut checksums = None;
+
+                        for action in actions.iter() {
+                            let products = if action.has_product_templates() {
+                                match action.product_names(&current_directory, &value) {
+                                    Ok(names) => Cow::Owned(names),
+                                    Err(Error::JSONPointerNotFound(_, pointer)) => {
+                                        trace!(
+                                            "'{}' has no value at '{pointer}': not completed for action '{}'.",
+                                            current_directory.display(),
+                                            action.name()
+                                        );
+                                        continue;
+                                    }
+                                    Err(error) => return Err(error),
+                                }
+                            } else {
+                                Cow::Borrowed(action.products())
+                            };
 
-                        for (action_name, products) in &action_products {
-                            if products
+                            if !products
                                 .iter()
                                 .all(|p| directory_contents.contains(OsStr::new(&p)))
                             {
-                                sender.send((current_directory.clone(), action_name.clone()))?;
+                                continue;
                             }
+
+                            if let Some(checksum_file) = checksum_file
+                                .as_ref()
+                                .filter(|f| directory_contents.contains(f.as_os_str()))
+                            {
+                                if checksums.is_none() {
+                                    checksums = Some(read_checksums(
+                                        backend.as_ref(),
+                                        &current_directory.join(checksum_file),
+                                    )?);
+                                }
+                                if !products_match_checksums(
+                                    backend.as_ref(),
+                                    &current_directory,
+                                    &products,
+                                    checksums.as_ref().expect("Checksums are read"),
+                                    checksum_mode,
+                                )? {
+                                    continue;
+                                }
+                            }
+
+                            sender.send((current_directory.clone(), action.name().into()))?;
                         }
 
                         progress.inc(1);
-                        directory_path.pop();
-                        directory_contents.clear();
                     }
                 });
 
         threads.push(handle.expect("Should be able to spawn threads."));
     }
 
-    CompletedDirectories {
+    Ok(CompletedDirectories {
         threads,
         receiver,
         progress: progress.clone(),
-    }
+    })
 }
 
 impl CompletedDirectories {
@@ -214,14 +534,11 @@ impl CompletedDirectories {
 
 /// JSON values of directories.
 ///
-/// Call `get()` to wait for all pending threads to complete and return the result.
+/// Call `get()` to wait for the pending thread to complete and return the result.
 ///
 pub(crate) struct DirectoryValues {
-    /// Threads reading the values.
-    threads: Vec<JoinHandle<Result<(), Error>>>,
-
-    /// Channel to receive results from worker threads.
-    receiver: Receiver<(PathBuf, Value)>,
+    /// Thread that parses the values in the pool.
+    thread: JoinHandle<Result<HashMap<PathBuf, Value>, Error>>,
 
     /// Progress bar.
     progress: ProgressBar,
@@ -229,22 +546,30 @@ pub(crate) struct DirectoryValues {
 
 /// Read value files from directories.
 ///
-/// `read_values` spawns threads that read the JSON value files and
-/// returns immediately. Calling `get` on the result will wait for the threads
-/// to complete and then provides the map of directory names to values.
+/// `read_values` spawns a thread that parses the JSON value files in a thread
+/// pool and returns immediately. Calling `get` on the result will wait for the
+/// pool to complete and then provides the map of directory names to values.
 ///
 /// # Arguments
 /// * `workflow` - The `Workflow` to read from.
 /// * `directories` - The directories to read. Must be present in the workspace.
-/// * `io_threads` - Number of threads to use while scanning directories.
+/// * `pointers` - Keep only these elements of each value (`None` keeps all).
+/// * `io_threads` - Number of threads to use while reading and parsing values.
+///
+/// # Errors
+/// Returns `Err<row::Error>` when the workspace cannot be accessed.
+///
+/// # Panics
+/// When unable to spawn threads.
 ///
 pub(crate) fn read_values(
     workflow: &Workflow,
     directories: Vec<PathBuf>,
+    pointers: Option<&[String]>,
     io_threads: u16,
     multi_progress: &mut MultiProgressContainer,
-) -> DirectoryValues {
-    let (sender, receiver) = mpsc::channel();
+) -> Result<DirectoryValues, Error> {
+    let backend = open_backend(workflow)?;
 
     let mut progress = ProgressBar::new(directories.len() as u64).with_message("Reading values");
     progress = multi_progress.add_or_hide(progress, directories.len() < MIN_PROGRESS_BAR_SIZE);
@@ -255,83 +580,223 @@ pub(crate) fn read_values(
         debug!("Reading directory values.");
     }
 
-    let workspace_path = workflow.root.join(&workflow.workspace.path);
-    let directories_mutex = Arc::new(Mutex::new(directories));
+    let value_file = workflow.workspace.value_file.clone();
+    let value_mode = workflow.workspace.value_mode;
+    let pointers = pointers.map(<[String]>::to_vec);
+    let thread_progress = progress.clone();
+
+    let thread = thread::Builder::new()
+        .name("read-values".into())
+        .spawn(move || -> Result<HashMap<PathBuf, Value>, Error> {
+            let trim = |value: Value| match pointers {
+                Some(ref pointers) => trim_value(&value, pointers),
+                None => value,
+            };
+
+            if let (Some(value_file), ValueMode::Consolidated) = (&value_file, value_mode) {
+                let mut values = read_consolidated_values(backend.as_ref(), value_file)?;
+                return directories
+                    .into_iter()
+                    .map(|directory| {
+                        let value = values.remove(&directory).ok_or_else(|| {
+                            Error::ConsolidatedValueNotFound(
+                                directory.clone(),
+                                backend.location(value_file),
+                            )
+                        })?;
+
+                        thread_progress.inc(1);
+                        Ok((directory, trim(value)))
+                    })
+                    .collect();
+            }
 
-    let mut threads = Vec::with_capacity(io_threads as usize);
+            let pool = ThreadPoolBuilder::new()
+                .num_threads(usize::from(io_threads))
+                .thread_name(|i| format!("read-values-{i}"))
+                .build()
+                .expect("Should be able to build the thread pool.");
+
+            pool.install(|| {
+                directories
+                    .into_par_iter()
+                    .map(|directory| {
+                        let value = match value_file {
+                            Some(ref value_file) => {
+                                trim(read_value(backend.as_ref(), &directory.join(value_file))?)
+                            }
+                            None => Value::Null,
+                        };
+
+                        thread_progress.inc(1);
+                        Ok((directory, value))
+                    })
+                    .collect()
+            })
+        })
+        .expect("Should be able to spawn threads.");
+
+    Ok(DirectoryValues { thread, progress })
+}
 
-    for i in 0..io_threads {
-        let workspace_path = workspace_path.clone();
-        let directories_mutex = directories_mutex.clone();
-        let sender = sender.clone();
-        let progress = progress.clone();
-        let value_file = workflow.workspace.value_file.clone();
+/// Read and parse a single JSON value file.
+///
+/// Parse directly from the bytes to avoid validating and copying the file
+/// contents into an intermediate `String`.
+///
+fn read_value(backend: &dyn Backend, value_path: &Path) -> Result<Value, Error> {
+    let bytes = backend.read_file(value_path)?;
+    serde_json::from_slice(&bytes).map_err(|e| Error::JSONParse(backend.location(value_path), e))
+}
 
-        let thread_name = format!("read-values-{i}");
-        let handle =
-            thread::Builder::new()
-                .name(thread_name)
-                .spawn(move || -> Result<(), Error> {
-                    let mut value_path = workspace_path;
+/// Read a checksum manifest in the format written by `sha256sum`.
+///
+/// # Returns
+/// The expected checksum (lowercase hexadecimal) of each listed file.
+///
+fn read_checksums(backend: &dyn Backend, path: &Path) -> Result<HashMap<String, String>, Error> {
+    let bytes = backend.read_file(path)?;
+    let contents = String::from_utf8_lossy(&bytes);
+
+    let mut result = HashMap::new();
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
 
-                    loop {
-                        let current_directory;
+        // Each line is `<digest>  <name>` (text mode) or `<digest> *<name>` (binary mode).
+        let entry = line.split_once(' ').and_then(|(digest, name)| {
+            let name = name.strip_prefix(' ').or_else(|| name.strip_prefix('*'))?;
+            (digest.len() == 64 && digest.bytes().all(|b| b.is_ascii_hexdigit()))
+                .then(|| (name.to_string(), digest.to_ascii_lowercase()))
+        });
+        let Some((name, digest)) = entry else {
+            return Err(Error::InvalidChecksumLine(backend.location(path), i + 1));
+        };
+        result.insert(name, digest);
+    }
 
-                        // Pull the next directory to process off the shared stack.
-                        {
-                            let mut directories = directories_mutex.lock().unwrap();
-                            if let Some(d) = directories.pop() {
-                                current_directory = d;
-                            } else {
-                                break Ok(());
-                            }
-                        }
+    Ok(result)
+}
 
-                        // List all files in the current directory.
-                        value_path.push(&current_directory);
+/// Check the products in a directory against their listed checksums.
+///
+/// Products that are not listed in `checksums` always match. `ProductsChecksum::Sampled`
+/// checks only one of the listed products, chosen by the directory name.
+///
+/// # Returns
+/// `Ok(true)` when the checked products match their checksums.
+///
+fn products_match_checksums(
+    backend: &dyn Backend,
+    directory: &Path,
+    products: &[String],
+    checksums: &HashMap<String, String>,
+    mode: ProductsChecksum,
+) -> Result<bool, Error> {
+    let mut listed: Vec<(&String, &String)> = products
+        .iter()
+        .filter_map(|p| checksums.get(p).map(|digest| (p, digest)))
+        .collect();
+
+    if mode == ProductsChecksum::Sampled && listed.len() > 1 {
+        let hash = fnv1a(FNV_OFFSET_BASIS, directory.as_os_str().as_bytes());
+        let selected = listed[(hash % listed.len() as u64) as usize];
+        listed = vec![selected];
+    }
 
-                        // Parse the value JSON file (if given).
-                        if let Some(ref value_file) = value_file {
-                            value_path.push(value_file);
+    for (product, expected) in listed {
+        let path = directory.join(product);
+        let mut hasher = Sha256::new();
+        io::copy(&mut backend.open_file(&path)?, &mut hasher)
+            .map_err(|e| Error::FileRead(backend.location(&path), e))?;
+        let actual = format!("{:x}", hasher.finalize());
+        if actual != *expected {
+            warn!(
+                "'{}' does not match its checksum. Treating the action as incomplete.",
+                backend.location(&path).display()
+            );
+            return Ok(false);
+        }
+    }
 
-                            let value_str = fs::read_to_string(&value_path)
-                                .map_err(|e| Error::FileRead(value_path.clone(), e))?;
-                            let value: Value = serde_json::from_str(&value_str)
-                                .map_err(|e| Error::JSONParse(value_path.clone(), e))?;
+    Ok(true)
+}
 
-                            sender.send((current_directory.clone(), value))?;
+/// Read a consolidated value file that maps directory names to values.
+fn read_consolidated_values(
+    backend: &dyn Backend,
+    value_file: &Path,
+) -> Result<HashMap<PathBuf, Value>, Error> {
+    let bytes = backend.read_file(value_file)?;
+    serde_json::from_slice(&bytes).map_err(|e| Error::JSONParse(backend.location(value_file), e))
+}
 
-                            value_path.pop();
-                        } else {
-                            sender.send((current_directory.clone(), Value::Null))?;
-                        }
+/// Copy only the elements at the given JSON pointers into a new value.
+///
+/// The result has the same structure as `value`, so each pointer resolves to the
+/// same element in both. Array elements before a selected index are `null`.
+/// Pointers that do not resolve in `value` are omitted.
+///
+pub(crate) fn trim_value(value: &Value, pointers: &[String]) -> Value {
+    let mut result = Value::Null;
 
-                        progress.inc(1);
-                        value_path.pop();
+    'outer: for pointer in pointers {
+        if value.pointer(pointer).is_none() {
+            continue;
+        }
+
+        let mut source = value;
+        let mut target = &mut result;
+        for token in pointer.split('/').skip(1) {
+            let token = token.replace("~1", "/").replace("~0", "~");
+            match source {
+                Value::Array(array) => {
+                    let Ok(index) = token.parse::<usize>() else {
+                        continue 'outer;
+                    };
+                    if !target.is_array() {
+                        *target = Value::Array(Vec::new());
                     }
-                });
+                    let target_array = target.as_array_mut().expect("target is an array");
+                    if target_array.len() <= index {
+                        target_array.resize(index + 1, Value::Null);
+                    }
+                    source = &array[index];
+                    target = &mut target_array[index];
+                }
+                Value::Object(map) => {
+                    if !target.is_object() {
+                        *target = Value::Object(serde_json::Map::new());
+                    }
+                    source = &map[&token];
+                    target = target
+                        .as_object_mut()
+                        .expect("target is an object")
+                        .entry(token)
+                        .or_insert(Value::Null);
+                }
+                _ => continue 'outer,
+            }
+        }
 
-        threads.push(handle.expect("Should be able to spawn threads."));
+        target.clone_from(source);
     }
 
-    DirectoryValues {
-        threads,
-        receiver,
-        progress: progress.clone(),
-    }
+    result
+}
+
+/// Check whether the element at `pointer` is present in values trimmed to `cached`.
+pub fn is_pointer_cached(cached: &[String], pointer: &str) -> bool {
+    cached.iter().any(|c| {
+        pointer == c || (pointer.starts_with(c.as_str()) && pointer[c.len()..].starts_with('/'))
+    })
 }
 
 impl DirectoryValues {
     /// Get the JSON value of each directory.
     pub(crate) fn get(self) -> Result<HashMap<PathBuf, Value>, Error> {
-        let mut result: HashMap<PathBuf, Value> = HashMap::new();
-        for (directory, value) in &self.receiver {
-            result.entry(directory).or_insert(value);
-        }
-
-        for handle in self.threads {
-            handle.join().expect("The thread should not panic")?;
-        }
+        let result = self.thread.join().expect("The thread should not panic")?;
 
         self.progress.finish();
 
@@ -384,12 +849,141 @@ mod tests {
         let workflow = "";
         let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
 
-        let result = list_directories(&workflow, &mut multi_progress).unwrap();
+        let result = list_directories(&workflow, 2, &mut multi_progress).unwrap();
         assert!(result.contains(&PathBuf::from("dir1")));
         assert!(result.contains(&PathBuf::from("dir2")));
         assert!(result.contains(&PathBuf::from("dir3")));
     }
 
+    #[test]
+    #[parallel]
+    fn s3_url() {
+        assert_eq!(
+            parse_s3_url("s3://bucket").unwrap(),
+            ("bucket".into(), String::new())
+        );
+        assert_eq!(
+            parse_s3_url("s3://bucket/").unwrap(),
+            ("bucket".into(), String::new())
+        );
+        assert_eq!(
+            parse_s3_url("s3://bucket/a/b/").unwrap(),
+            ("bucket".into(), "a/b/".into())
+        );
+        assert_eq!(
+            parse_s3_url("s3://bucket/a/b").unwrap(),
+            ("bucket".into(), "a/b/".into())
+        );
+        assert!(matches!(
+            parse_s3_url("s3:///prefix"),
+            Err(Error::UnsupportedWorkspaceUrl(_))
+        ));
+        assert!(matches!(
+            parse_s3_url("file:///workspace"),
+            Err(Error::UnsupportedWorkspaceUrl(_))
+        ));
+    }
+
+    #[test]
+    #[parallel]
+    fn filesystem_backend() {
+        let temp = TempDir::new().unwrap();
+        temp.child("workspace")
+            .child("dir1")
+            .child("v")
+            .write_str("1")
+            .unwrap();
+        temp.child("workspace").child("file").touch().unwrap();
+
+        let workflow = Workflow::open_str(temp.path(), "").unwrap();
+        let backend = open_backend(&workflow).unwrap();
+
+        assert_eq!(
+            backend.list_directories(&ProgressBar::hidden()).unwrap(),
+            vec![OsString::from("dir1")]
+        );
+        assert_eq!(
+            backend.list_entries(Path::new("dir1")).unwrap(),
+            HashSet::from([OsString::from("v")])
+        );
+        assert_eq!(backend.read_file(Path::new("dir1/v")).unwrap(), b"1");
+        assert!(backend.modified_time().unwrap().is_some());
+        assert_eq!(
+            backend.location(Path::new("dir1/v")),
+            workflow.root.join("workspace/dir1/v")
+        );
+
+        // The workflow (and its clones) reuse the backend.
+        assert!(Arc::ptr_eq(&backend, &open_backend(&workflow).unwrap()));
+        assert!(Arc::ptr_eq(
+            &backend,
+            &open_backend(&workflow.clone()).unwrap()
+        ));
+    }
+
+    #[test]
+    #[parallel]
+    fn list_directories_chunks() {
+        let temp = TempDir::new().unwrap();
+        let n = LIST_CHUNK_SIZE * 2 + 10;
+        for i in 0..n {
+            temp.child("workspace")
+                .child(format!("dir{i}"))
+                .create_dir_all()
+                .unwrap();
+        }
+        for i in 0..LIST_CHUNK_SIZE {
+            temp.child("workspace")
+                .child(format!("file{i}"))
+                .touch()
+                .unwrap();
+        }
+
+        let workflow = Workflow::open_str(temp.path(), "").unwrap();
+        let progress = ProgressBar::hidden();
+        let mut result = open_backend(&workflow)
+            .unwrap()
+            .list_directories(&progress)
+            .unwrap();
+        result.sort_unstable();
+        let mut expected: Vec<_> = (0..n).map(|i| OsString::from(format!("dir{i}"))).collect();
+        expected.sort_unstable();
+        assert_eq!(result, expected);
+        assert_eq!(progress.position(), (n + LIST_CHUNK_SIZE) as u64);
+    }
+
+    #[test]
+    #[parallel]
+    fn list_directories_non_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut multi_progress = setup();
+
+        let temp = TempDir::new().unwrap();
+        temp.child("workspace")
+            .child("dir1")
+            .create_dir_all()
+            .unwrap();
+        let invalid = OsStr::from_bytes(b"dir\xff");
+        temp.child("workspace")
+            .child(invalid)
+            .create_dir_all()
+            .unwrap();
+
+        let workflow = Workflow::open_str(temp.path(), "").unwrap();
+        let result = list_directories(&workflow, 2, &mut multi_progress).unwrap();
+        assert_eq!(result.len(), 2);
+        assert!(result.contains(&PathBuf::from(invalid)));
+
+        let workflow = r#"
+[workspace]
+non_utf8_names = "skip"
+"#;
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        let result = list_directories(&workflow, 2, &mut multi_progress).unwrap();
+        assert_eq!(result, vec![PathBuf::from("dir1")]);
+    }
+
     #[test]
     #[parallel]
     fn find_completed() {
@@ -476,6 +1070,7 @@ products = ["3", "4"]
             2,
             &mut multi_progress,
         )
+        .unwrap()
         .get()
         .unwrap();
 
@@ -492,6 +1087,189 @@ products = ["3", "4"]
         assert!(!result.contains_key("four"));
     }
 
+    #[test]
+    #[parallel]
+    fn find_completed_checksums() {
+        let mut multi_progress = setup();
+
+        // The SHA-256 checksum of "abc".
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.child("workspace");
+        for directory in ["dir1", "dir2", "dir3", "dir4"] {
+            workspace
+                .child(directory)
+                .child("a")
+                .write_str("abc")
+                .unwrap();
+            workspace
+                .child(directory)
+                .child("b")
+                .write_str("abd")
+                .unwrap();
+        }
+        workspace
+            .child("dir1/MANIFEST.sha256")
+            .write_str(&format!("{abc}  a\n"))
+            .unwrap();
+        workspace
+            .child("dir2/MANIFEST.sha256")
+            .write_str(&format!("{abc}  a\n{abc} *b\n"))
+            .unwrap();
+        workspace
+            .child("dir3/MANIFEST.sha256")
+            .write_str(&format!("{abc}  b\n{abc}  c\n"))
+            .unwrap();
+
+        let workflow = r#"
+[workspace]
+products_checksum_file = "MANIFEST.sha256"
+
+[[action]]
+name = "one"
+command = "c"
+products = ["a"]
+
+[[action]]
+name = "two"
+command = "c"
+products = ["a", "b"]
+"#;
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+        let directories = vec![
+            PathBuf::from("dir1"),
+            PathBuf::from("dir2"),
+            PathBuf::from("dir3"),
+            PathBuf::from("dir4"),
+        ];
+
+        let result = find_completed_directories(&workflow, directories, 2, &mut multi_progress)
+            .unwrap()
+            .get()
+            .unwrap();
+
+        assert_eq!(result["one"].len(), 4);
+        assert_eq!(result["two"].len(), 2);
+        assert!(result["two"].contains(&PathBuf::from("dir1")));
+        assert!(result["two"].contains(&PathBuf::from("dir4")));
+
+        workspace
+            .child("dir1/MANIFEST.sha256")
+            .write_str("not a checksum\n")
+            .unwrap();
+        let result = find_completed_directories(
+            &workflow,
+            vec![PathBuf::from("dir1")],
+            2,
+            &mut multi_progress,
+        )
+        .unwrap()
+        .get();
+        assert!(matches!(result, Err(Error::InvalidChecksumLine(_, 1))));
+    }
+
+    #[test]
+    #[parallel]
+    fn products_checksum_modes() {
+        let temp = TempDir::new().unwrap();
+        temp.child("a").write_str("a").unwrap();
+        temp.child("b").write_str("b").unwrap();
+        let backend = Filesystem {
+            path: temp.path().into(),
+        };
+
+        let products = vec!["a".to_string(), "b".to_string()];
+        let wrong = "0".repeat(64);
+        let checksums = HashMap::from([("a".to_string(), wrong.clone()), ("b".to_string(), wrong)]);
+
+        for mode in [ProductsChecksum::Full, ProductsChecksum::Sampled] {
+            assert!(!products_match_checksums(
+                &backend,
+                Path::new(""),
+                &products,
+                &checksums,
+                mode
+            )
+            .unwrap());
+            assert!(products_match_checksums(
+                &backend,
+                Path::new(""),
+                &products,
+                &HashMap::new(),
+                mode
+            )
+            .unwrap());
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn find_completed_templates() {
+        let mut multi_progress = setup();
+
+        let temp = TempDir::new().unwrap();
+        for (name, seed) in [("dir1", 1), ("dir2", 2), ("dir3", 3)] {
+            let directory = temp.child("workspace").child(name);
+            directory.create_dir_all().unwrap();
+            directory
+                .child("v.json")
+                .write_str(&format!(r#"{{"seed": {seed}}}"#))
+                .unwrap();
+        }
+        temp.child("workspace")
+            .child("dir4")
+            .child("v.json")
+            .write_str(r#"{"other": 4}"#)
+            .unwrap();
+
+        temp.child("workspace")
+            .child("dir1")
+            .child("out_1.h5")
+            .touch()
+            .unwrap();
+        temp.child("workspace")
+            .child("dir2")
+            .child("out_1.h5")
+            .touch()
+            .unwrap();
+        temp.child("workspace")
+            .child("dir3")
+            .child("out_3.h5")
+            .touch()
+            .unwrap();
+
+        let workflow = r#"
+[workspace]
+value_file = "v.json"
+
+[[action]]
+name = "one"
+command = "c"
+products = ["out_{/seed}.h5"]
+"#;
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        let result = find_completed_directories(
+            &workflow,
+            vec![
+                PathBuf::from("dir1"),
+                PathBuf::from("dir2"),
+                PathBuf::from("dir3"),
+                PathBuf::from("dir4"),
+            ],
+            2,
+            &mut multi_progress,
+        )
+        .unwrap()
+        .get()
+        .unwrap();
+
+        assert_eq!(result["one"].len(), 2);
+        assert!(result["one"].contains(&PathBuf::from("dir1")));
+        assert!(result["one"].contains(&PathBuf::from("dir3")));
+    }
+
     #[test]
     #[parallel]
     fn read() {
@@ -541,9 +1319,11 @@ value_file = "v"
                 PathBuf::from("dir2"),
                 PathBuf::from("dir3"),
             ],
+            None,
             2,
             &mut multi_progress,
         )
+        .unwrap()
         .get()
         .unwrap();
 
@@ -552,4 +1332,110 @@ value_file = "v"
         assert_eq!(result[&PathBuf::from("dir2")].as_i64(), Some(2));
         assert_eq!(result[&PathBuf::from("dir3")].as_i64(), Some(3));
     }
+
+    #[test]
+    #[parallel]
+    fn read_consolidated() {
+        let mut multi_progress = setup();
+
+        let temp = TempDir::new().unwrap();
+        for name in ["dir1", "dir2", "dir3"] {
+            temp.child("workspace")
+                .child(name)
+                .create_dir_all()
+                .unwrap();
+        }
+        temp.child("workspace")
+            .child("values.json")
+            .write_str(r#"{"dir1": {"a": 1, "b": 2}, "dir2": {"a": 2}, "dir3": {"a": 3}}"#)
+            .unwrap();
+
+        let workflow = r#"
+[workspace]
+value_file = "values.json"
+value_mode = "consolidated"
+"#;
+        let workflow = Workflow::open_str(temp.path(), workflow).unwrap();
+
+        let directories = list_directories(&workflow, 2, &mut multi_progress).unwrap();
+        assert_eq!(directories.len(), 3);
+
+        let result = read_values(
+            &workflow,
+            vec![PathBuf::from("dir1"), PathBuf::from("dir3")],
+            Some(&["/a".to_string()]),
+            2,
+            &mut multi_progress,
+        )
+        .unwrap()
+        .get()
+        .unwrap();
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[&PathBuf::from("dir1")], serde_json::json!({"a": 1}));
+        assert_eq!(result[&PathBuf::from("dir3")], serde_json::json!({"a": 3}));
+
+        let result = read_values(
+            &workflow,
+            vec![PathBuf::from("dir4")],
+            None,
+            2,
+            &mut multi_progress,
+        )
+        .unwrap()
+        .get();
+        assert!(matches!(
+            result,
+            Err(Error::ConsolidatedValueNotFound(ref d, _)) if d == Path::new("dir4")
+        ));
+    }
+
+    #[test]
+    #[parallel]
+    fn trim() {
+        let value = serde_json::json!({
+            "a": 1,
+            "b": {"c": [10, 11, {"d": 12, "e": 13}], "f": 14},
+            "g/h": 15,
+            "i": [1, 2, 3],
+        });
+
+        let pointers = vec![
+            "/a".to_string(),
+            "/b/c/2/d".to_string(),
+            "/g~1h".to_string(),
+            "/i".to_string(),
+            "/missing".to_string(),
+        ];
+        let trimmed = trim_value(&value, &pointers);
+
+        assert_eq!(
+            trimmed,
+            serde_json::json!({
+                "a": 1,
+                "b": {"c": [null, null, {"d": 12}]},
+                "g/h": 15,
+                "i": [1, 2, 3],
+            })
+        );
+        for pointer in &pointers {
+            assert_eq!(trimmed.pointer(pointer), value.pointer(pointer));
+        }
+
+        assert_eq!(trim_value(&value, &[String::new()]), value);
+        assert_eq!(trim_value(&value, &[]), Value::Null);
+    }
+
+    #[test]
+    #[parallel]
+    fn pointer_cached() {
+        let cached = vec!["/a".to_string(), "/b/c".to_string()];
+        assert!(is_pointer_cached(&cached, "/a"));
+        assert!(is_pointer_cached(&cached, "/a/0"));
+        assert!(is_pointer_cached(&cached, "/b/c/d"));
+        assert!(!is_pointer_cached(&cached, "/ab"));
+        assert!(!is_pointer_cached(&cached, "/b"));
+        assert!(!is_pointer_cached(&cached, ""));
+        assert!(is_pointer_cached(&[String::new()], "/b"));
+    }
 }
@@ -7,8 +7,9 @@ use assert_fs::TempDir;
 use predicates::prelude::*;
 use serial_test::parallel;
 use std::fs;
+use std::time::Duration;
 
-use row::DATA_DIRECTORY_NAME;
+use row::{DATA_DIRECTORY_NAME, DIRECTORY_CACHE_FILE_NAME};
 
 /// Create a sample workflow and workspace to use with the tests.
 fn setup_sample_workflow(
@@ -38,6 +39,7 @@ value_file = "v.json"
 name = "one"
 command = "touch workspace/{directory}/one"
 products = ["one"]
+tags = ["first"]
 
 [[action]]
 name = "two"
@@ -92,9 +94,55 @@ fn no_workflow_file() -> Result<(), Box<dyn std::error::Error>> {
     cmd.args(["show", "status"])
         .args(["--cluster", "none"])
         .current_dir(temp.path());
-    cmd.assert()
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "workflow.toml or workflow.yaml not found",
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn yaml_workflow() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    temp.child("workspace").child("dir0").create_dir_all()?;
+    temp.child("workflow.yaml").write_str(
+        r"
+action:
+  - name: one
+    command: touch workspace/{directory}/one
+    products: [one]
+",
+    )?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^one +0 +0 +1 +0")?);
+
+    temp.child("workflow.yaml").write_str(
+        r"
+action:
+  - name: one
+    command: touch workspace/{directory}/one
+    previous_actions: [two]
+",
+    )?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
         .failure()
-        .stderr(predicate::str::contains("workflow.toml not found"));
+        .stderr(predicate::str::contains(
+            "Previous action 'two' not found in action 'one'.",
+        ));
 
     Ok(())
 }
@@ -154,6 +202,25 @@ fn status() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+#[parallel]
+fn status_short() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 10);
+
+    Command::cargo_bin("row")?
+        .args(["show", "status", "--short"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_COLOR", "always")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout("one\t0\t0\t10\t0\ntwo\t0\t0\t0\t10\n");
+
+    Ok(())
+}
+
 #[test]
 #[parallel]
 fn status_waiting() -> Result<(), Box<dyn std::error::Error>> {
@@ -246,6 +313,109 @@ fn status_all() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+#[parallel]
+fn status_hide_complete() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 10);
+    complete_action("one", &temp, 10)?;
+    complete_action("two", &temp, 4)?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .args(["--hide-complete"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^one ")?.not())
+        .stdout(predicate::str::is_match("(?m)^two +4 +0 +6 +0")?);
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .env("ROW_HIDE_COMPLETE", "true")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^one ")?.not())
+        .stdout(predicate::str::is_match("(?m)^two +4 +0 +6 +0")?);
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .args(["--hide-complete", "--all"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^one +10 +0 +0 +0")?);
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn status_eligible_when() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 10);
+    temp.child("workflow.toml").write_str(
+        r#"
+[workspace]
+value_file = "v.json"
+
+[[action]]
+name = "one"
+command = "touch workspace/{directory}/one"
+products = ["one"]
+eligible_when = "test -e {directory}/ready"
+"#,
+    )?;
+
+    for i in 0..3 {
+        temp.child("workspace")
+            .child(format!("dir{i}"))
+            .child("ready")
+            .touch()?;
+    }
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^one +0 +0 +3 +7")?);
+
+    Command::cargo_bin("row")?
+        .arg("submit")
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    temp.child("workspace/dir0/one")
+        .assert(predicate::path::exists());
+    temp.child("workspace/dir3/one")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
+
 #[test]
 #[parallel]
 fn status_completed() -> Result<(), Box<dyn std::error::Error>> {
@@ -301,6 +471,107 @@ fn status_action_selection() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+#[parallel]
+fn status_watch() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 10);
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .args(["--watch", "1"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .timeout(Duration::from_millis(1500))
+        .assert()
+        .failure()
+        .stdout(predicate::str::is_match(
+            "(?sm)^Every 1s: row show status.*^one +0 +0 +10 +0",
+        )?)
+        .stdout(predicate::str::contains("\x1b").not());
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .args(["--watch", "0"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn status_tag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 10);
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .args(["--tag", "first"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^one +0 +0 +10 +0")?)
+        .stdout(predicate::str::is_match("(?m)^two")?.not());
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .args(["--tag", "missing"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("No actions match"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn submit_tag() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 10);
+    complete_action("one", &temp, 10)?;
+
+    Command::cargo_bin("row")?
+        .arg("submit")
+        .args(["--cluster", "none"])
+        .args(["--tag", "first"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("no eligible jobs"));
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^two +0 +0 +10 +0")?);
+
+    Ok(())
+}
+
 #[test]
 #[parallel]
 fn status_directories() -> Result<(), Box<dyn std::error::Error>> {
@@ -509,20 +780,34 @@ fn scan_directories() -> Result<(), Box<dyn std::error::Error>> {
 
 #[test]
 #[parallel]
-fn submit() -> Result<(), Box<dyn std::error::Error>> {
+fn show_staged() -> Result<(), Box<dyn std::error::Error>> {
     let temp = TempDir::new()?;
-    let _ = setup_sample_workflow(&temp, 10);
+    let _ = setup_sample_workflow(&temp, 4);
 
     Command::cargo_bin("row")?
-        .arg("submit")
+        .args(["scan", "--pack-only", "-a", "one", "dir0", "dir1"])
         .args(["--cluster", "none"])
         .current_dir(temp.path())
+        .env_remove("SLURM_JOB_ID")
         .env_remove("ROW_COLOR")
         .env_remove("CLICOLOR")
         .env("ROW_HOME", "/not/a/path")
         .assert()
         .success();
 
+    Command::cargo_bin("row")?
+        .args(["show", "staged", "--no-header"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            r"(?m)^nojob_\S+\.v4\.pending +pending +one +\S+ +\d+ +2 +\d{4}-",
+        )?);
+
+    // Synchronizing the project removes the staged packs.
     Command::cargo_bin("row")?
         .args(["show", "status"])
         .args(["--cluster", "none"])
@@ -531,180 +816,153 @@ fn submit() -> Result<(), Box<dyn std::error::Error>> {
         .env_remove("CLICOLOR")
         .env("ROW_HOME", "/not/a/path")
         .assert()
+        .success();
+
+    Command::cargo_bin("row")?
+        .args(["show", "staged", "--no-header"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
         .success()
-        .stdout(predicate::str::is_match("(?m)^one +10 +0 +0 +0")?)
-        .stdout(predicate::str::is_match("(?m)^two +0 +0 +10 +0")?);
+        .stdout(predicate::str::is_empty());
 
     Ok(())
 }
 
 #[test]
 #[parallel]
-fn directories_no_action() -> Result<(), Box<dyn std::error::Error>> {
+fn show_efficiency() -> Result<(), Box<dyn std::error::Error>> {
     let temp = TempDir::new()?;
     let _ = setup_sample_workflow(&temp, 4);
 
     Command::cargo_bin("row")?
-        .args(["show", "directories"])
+        .args(["show", "efficiency"])
         .args(["--cluster", "none"])
+        .current_dir(temp.path())
         .env_remove("ROW_COLOR")
         .env_remove("CLICOLOR")
         .env("ROW_HOME", "/not/a/path")
-        .current_dir(temp.path())
         .assert()
         .success()
-        .stdout(predicates::str::diff("dir0\ndir1\ndir2\ndir3\n"));
-
-    Ok(())
-}
-
-#[test]
-#[parallel]
-fn directories() -> Result<(), Box<dyn std::error::Error>> {
-    let temp = TempDir::new()?;
-    let _ = setup_sample_workflow(&temp, 10);
+        .stdout(predicate::str::is_empty());
+
+    for directories in [["dir0", "dir1"], ["dir2", "dir3"]] {
+        Command::cargo_bin("row")?
+            .args(["submit", "-a", "one"])
+            .args(directories)
+            .args(["--cluster", "none"])
+            .current_dir(temp.path())
+            .env_remove("SLURM_JOB_ID")
+            .env_remove("ROW_COLOR")
+            .env_remove("CLICOLOR")
+            .env("ROW_HOME", "/not/a/path")
+            .assert()
+            .success();
+    }
 
     Command::cargo_bin("row")?
-        .args(["show", "directories"])
+        .args(["show", "efficiency", "--no-header"])
         .args(["--cluster", "none"])
-        .args(["--action", "one"])
         .current_dir(temp.path())
         .env_remove("ROW_COLOR")
         .env_remove("CLICOLOR")
         .env("ROW_HOME", "/not/a/path")
         .assert()
         .success()
-        .stdout(predicate::str::is_match("(?m)^Directory Status +Job ID")?)
-        .stdout(predicate::str::is_match("(?m)^dir0 *eligible *$")?)
-        .stdout(predicate::str::is_match("(?m)^dir1 *eligible *$")?)
-        .stdout(predicate::str::is_match("(?m)^dir2 *eligible *$")?)
-        .stdout(predicate::str::is_match("(?m)^dir3 *eligible *$")?)
-        .stdout(predicate::str::is_match("(?m)^dir4 *eligible *$")?)
-        .stdout(predicate::str::is_match("(?m)^dir5 *eligible *$")?)
-        .stdout(predicate::str::is_match("(?m)^dir6 *eligible *$")?)
-        .stdout(predicate::str::is_match("(?m)^dir7 *eligible *$")?)
-        .stdout(predicate::str::is_match("(?m)^dir8 *eligible *$")?)
-        .stdout(predicate::str::is_match("(?m)^dir9 *eligible *$")?);
-
-    Ok(())
-}
+        .stdout(predicate::str::is_match(r"(?m)^one +2 +.* +2 +0$")?)
+        .stderr(predicate::str::contains(
+            "Action 'one' used at most 0.0% of the requested walltime in 2 jobs.",
+        ))
+        .stderr(predicate::str::contains(
+            r#"`resources.walltime.per_directory = "00:01:00"`"#,
+        ));
 
-#[test]
-#[parallel]
-fn directories_select_directories() -> Result<(), Box<dyn std::error::Error>> {
-    let temp = TempDir::new()?;
-    let _ = setup_sample_workflow(&temp, 10);
+    Command::cargo_bin("row")?
+        .args(["clean", "--jobs"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
 
     Command::cargo_bin("row")?
-        .args(["show", "directories"])
+        .args(["show", "efficiency"])
         .args(["--cluster", "none"])
-        .args(["--action", "one"])
-        .arg("dir3")
-        .arg("dir9")
         .current_dir(temp.path())
         .env_remove("ROW_COLOR")
         .env_remove("CLICOLOR")
         .env("ROW_HOME", "/not/a/path")
         .assert()
         .success()
-        .stdout(predicate::str::is_match("(?m)^Directory Status +Job ID")?)
-        .stdout(predicate::str::is_match("(?m)^dir0 *eligible *$")?.not())
-        .stdout(predicate::str::is_match("(?m)^dir1 *eligible *$")?.not())
-        .stdout(predicate::str::is_match("(?m)^dir2 *eligible *$")?.not())
-        .stdout(predicate::str::is_match("(?m)^dir3 *eligible *$")?)
-        .stdout(predicate::str::is_match("(?m)^dir4 *eligible *$")?.not())
-        .stdout(predicate::str::is_match("(?m)^dir5 *eligible *$")?.not())
-        .stdout(predicate::str::is_match("(?m)^dir6 *eligible *$")?.not())
-        .stdout(predicate::str::is_match("(?m)^dir7 *eligible *$")?.not())
-        .stdout(predicate::str::is_match("(?m)^dir8 *eligible *$")?.not())
-        .stdout(predicate::str::is_match("(?m)^dir9 *eligible *$")?);
+        .stdout(predicate::str::is_empty());
 
     Ok(())
 }
 
 #[test]
 #[parallel]
-fn directories_no_header() -> Result<(), Box<dyn std::error::Error>> {
+fn submit() -> Result<(), Box<dyn std::error::Error>> {
     let temp = TempDir::new()?;
     let _ = setup_sample_workflow(&temp, 10);
 
     Command::cargo_bin("row")?
-        .args(["show", "directories"])
+        .arg("submit")
         .args(["--cluster", "none"])
-        .args(["--action", "one"])
-        .arg("--no-header")
         .current_dir(temp.path())
         .env_remove("ROW_COLOR")
         .env_remove("CLICOLOR")
         .env("ROW_HOME", "/not/a/path")
         .assert()
-        .success()
-        .stdout(predicate::str::is_match("(?m)^Directory Status")?.not());
-
-    Ok(())
-}
-
-#[test]
-#[parallel]
-fn directories_value() -> Result<(), Box<dyn std::error::Error>> {
-    let temp = TempDir::new()?;
-    let _ = setup_sample_workflow(&temp, 10);
+        .success();
 
     Command::cargo_bin("row")?
-        .args(["show", "directories"])
+        .args(["show", "status"])
         .args(["--cluster", "none"])
-        .args(["--value", "/v"])
-        .args(["--value", "/v2"])
-        .args(["--action", "one"])
-        .arg("dir3")
-        .arg("dir9")
         .current_dir(temp.path())
         .env_remove("ROW_COLOR")
         .env_remove("CLICOLOR")
         .env("ROW_HOME", "/not/a/path")
         .assert()
         .success()
-        .stdout(predicate::str::is_match(
-            "(?m)^Directory +Status +Job ID +/v +/v2",
-        )?)
-        .stdout(predicate::str::is_match("(?m)^dir3 +eligible +3 +1$")?)
-        .stdout(predicate::str::is_match("(?m)^dir9 +eligible +9 +4$")?);
+        .stdout(predicate::str::is_match("(?m)^one +10 +0 +0 +0")?)
+        .stdout(predicate::str::is_match("(?m)^two +0 +0 +10 +0")?);
 
     Ok(())
 }
 
 #[test]
 #[parallel]
-fn directories_short() -> Result<(), Box<dyn std::error::Error>> {
+fn plan() -> Result<(), Box<dyn std::error::Error>> {
     let temp = TempDir::new()?;
     let _ = setup_sample_workflow(&temp, 4);
 
     Command::cargo_bin("row")?
-        .args(["show", "directories"])
+        .args(["plan", "--action", "one", "-o", "plan.json", "dir0", "dir1"])
         .args(["--cluster", "none"])
-        .args(["--action", "one"])
-        .arg("--short")
         .current_dir(temp.path())
         .env_remove("ROW_COLOR")
         .env_remove("CLICOLOR")
         .env("ROW_HOME", "/not/a/path")
         .assert()
         .success()
-        .stdout(predicates::str::diff("dir0\ndir1\ndir2\ndir3\n"));
+        .stdout(predicate::str::contains("Planned 1 job"));
 
-    Ok(())
-}
+    temp.child("plan.json")
+        .assert(predicate::str::contains("\"cluster\": \"none\""))
+        .assert(predicate::str::contains("\"action\": \"one\""))
+        .assert(predicate::str::contains("\"dir1\""))
+        .assert(predicate::str::contains("dir2").not());
 
-#[test]
-#[parallel]
-fn directories_short_no_action() -> Result<(), Box<dyn std::error::Error>> {
-    let temp = TempDir::new()?;
-    let _ = setup_sample_workflow(&temp, 10);
+    // The submitted script must match the reviewed script.
+    let plan = fs::read_to_string(temp.child("plan.json").path())?;
+    temp.child("plan.json")
+        .write_str(&plan.replace("#!/bin/bash", "#!/usr/bin/env bash"))?;
 
     Command::cargo_bin("row")?
-        .args(["show", "directories"])
+        .args(["submit", "--plan", "plan.json"])
         .args(["--cluster", "none"])
-        .arg("--short")
         .current_dir(temp.path())
         .env_remove("ROW_COLOR")
         .env_remove("CLICOLOR")
@@ -712,148 +970,1011 @@ fn directories_short_no_action() -> Result<(), Box<dyn std::error::Error>> {
         .assert()
         .failure()
         .stderr(predicate::str::contains(
-            "following required arguments were not provided",
-        ))
-        .stderr(predicate::str::contains("--action"));
+            "the script of job 'one-dir0+1' has changed",
+        ));
 
-    Ok(())
-}
+    temp.child("plan.json").write_str(&plan)?;
 
-#[test]
-#[parallel]
-fn show_cluster() -> Result<(), Box<dyn std::error::Error>> {
-    let temp = TempDir::new()?;
+    let workflow = fs::read_to_string(temp.child("workflow.toml").path())?;
+    temp.child("workflow.toml").write_str(&workflow.replace(
+        "tags = [\"first\"]",
+        "tags = [\"first\"]\nresources.processes.per_submission = 2",
+    ))?;
 
     Command::cargo_bin("row")?
-        .args(["show", "cluster"])
+        .args(["submit", "--plan", "plan.json"])
         .args(["--cluster", "none"])
         .current_dir(temp.path())
         .env_remove("ROW_COLOR")
         .env_remove("CLICOLOR")
         .env("ROW_HOME", "/not/a/path")
         .assert()
-        .success()
-        .stdout(predicate::str::contains(r#"name = "none""#));
+        .failure()
+        .stderr(predicate::str::contains("have changed"));
 
-    Ok(())
-}
-#[test]
-#[parallel]
-fn show_cluster_short() -> Result<(), Box<dyn std::error::Error>> {
-    let temp = TempDir::new()?;
+    temp.child("workflow.toml").write_str(&workflow)?;
 
     Command::cargo_bin("row")?
-        .args(["show", "cluster"])
+        .args(["submit", "--plan", "plan.json"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
         .args(["--cluster", "none"])
-        .arg("--short")
         .current_dir(temp.path())
         .env_remove("ROW_COLOR")
         .env_remove("CLICOLOR")
         .env("ROW_HOME", "/not/a/path")
         .assert()
         .success()
-        .stdout(predicate::eq("none\n"));
+        .stdout(predicate::str::is_match("(?m)^one +2 +0 +2 +0")?);
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--plan", "plan.json"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("out of date"));
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--plan", "plan.json", "--action", "two"])
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
 
     Ok(())
 }
 
 #[test]
 #[parallel]
-fn show_launchers() -> Result<(), Box<dyn std::error::Error>> {
+fn redact() -> Result<(), Box<dyn std::error::Error>> {
     let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 2);
+    temp.child("workflow.toml").write_str(
+        r#"
+redact = ["account"]
+
+[[action]]
+name = "one"
+command = "touch workspace/{directory}/one"
+products = ["one"]
+[action.submit_options.slurm-cpu]
+account = "secret-project"
+"#,
+    )?;
 
     Command::cargo_bin("row")?
-        .args(["show", "launchers"])
-        .args(["--cluster", "none"])
+        .args(["submit", "--dry-run"])
+        .args(["--cluster", "slurm-cpu"])
         .current_dir(temp.path())
         .env_remove("ROW_COLOR")
         .env_remove("CLICOLOR")
         .env("ROW_HOME", "/not/a/path")
         .assert()
         .success()
-        .stdout(predicate::str::contains(r#"executable = "mpirun""#));
+        .stdout(predicate::str::contains("#SBATCH --account=<redacted>"))
+        .stdout(predicate::str::contains("secret-project").not());
 
-    Ok(())
-}
-#[test]
-#[parallel]
-fn show_launchers_short() -> Result<(), Box<dyn std::error::Error>> {
-    let temp = TempDir::new()?;
+    Command::cargo_bin("row")?
+        .args(["plan", "-o", "plan.json"])
+        .args(["--cluster", "slurm-cpu"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    temp.child("plan.json")
+        .assert(predicate::str::contains("--account=<redacted>"))
+        .assert(predicate::str::contains("secret-project").not());
 
     Command::cargo_bin("row")?
-        .args(["show", "launchers"])
-        .args(["--cluster", "none"])
-        .arg("--short")
+        .args(["submit", "--dry-run", "--plan", "plan.json"])
+        .args(["--cluster", "slurm-cpu"])
         .current_dir(temp.path())
         .env_remove("ROW_COLOR")
         .env_remove("CLICOLOR")
         .env("ROW_HOME", "/not/a/path")
         .assert()
         .success()
-        .stdout(predicate::str::contains("mpi"))
-        .stdout(predicate::str::contains("openmp\n"));
+        .stdout(predicate::str::contains("#SBATCH --account=<redacted>"));
 
     Ok(())
 }
 
 #[test]
 #[parallel]
-fn init_conflicting_args() -> Result<(), Box<dyn std::error::Error>> {
-    let mut cmd = Command::cargo_bin("row")?;
+fn config() -> Result<(), Box<dyn std::error::Error>> {
     let temp = TempDir::new()?;
-
-    cmd.args(["init"])
-        .arg("--signac")
-        .args(["--workspace", "test"])
-        .current_dir(temp.path());
-    cmd.assert()
+    let _ = setup_sample_workflow(&temp, 2);
+
+    let row_config = |args: &[&str]| -> Result<Command, Box<dyn std::error::Error>> {
+        let mut command = Command::cargo_bin("row")?;
+        command
+            .arg("config")
+            .args(args)
+            .current_dir(temp.path())
+            .env_remove("ROW_COLOR")
+            .env_remove("CLICOLOR")
+            .env_remove("ROW_CLUSTER")
+            .env_remove("ROW_IO_THREADS")
+            .env("ROW_HOME", temp.path());
+        Ok(command)
+    };
+
+    row_config(&["set", "cluster", "none"])?.assert().success();
+    row_config(&["set", "--local", "cluster", "slurm-cpu"])?
+        .assert()
+        .success();
+    row_config(&["set", "io_threads", "0"])?
+        .assert()
         .failure()
-        .stderr(predicate::str::contains("cannot be used with"));
+        .stderr(predicate::str::contains("expected a positive integer"));
+    row_config(&["set", "colour", "never"])?
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown setting 'colour'"));
 
-    Ok(())
-}
+    temp.child(".config/row/config.toml")
+        .assert("cluster = \"none\"\n");
+    temp.child(".row/config.toml")
+        .assert("cluster = \"slurm-cpu\"\n");
 
-#[test]
-#[parallel]
-fn init_invalid_path() -> Result<(), Box<dyn std::error::Error>> {
-    let mut cmd = Command::cargo_bin("row")?;
-    let temp = TempDir::new()?;
+    row_config(&["get", "cluster"])?
+        .assert()
+        .success()
+        .stdout("slurm-cpu\n");
+    row_config(&["get", "--user", "cluster"])?
+        .assert()
+        .success()
+        .stdout("none\n");
+    row_config(&["get", "color"])?.assert().success().stdout("");
+    row_config(&["list"])?
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            "(?m)^cluster +slurm-cpu +project$",
+        )?);
 
-    cmd.args(["init"])
-        .args(["--workspace", "/test/one"])
-        .arg(".")
-        .current_dir(temp.path());
-    cmd.assert()
+    // The project setting selects the cluster.
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one", "--dry-run"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env_remove("ROW_CLUSTER")
+        .env("ROW_HOME", temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("#SBATCH"));
+
+    // Command line options take precedence.
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one", "--dry-run"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("#SBATCH").not());
+
+    row_config(&["set", "--local", "--unset", "cluster"])?
+        .assert()
+        .success();
+    row_config(&["get", "cluster"])?
+        .assert()
+        .success()
+        .stdout("none\n");
+
+    // `row config set` refuses to replace an invalid settings file.
+    temp.child(".row/config.toml").write_str("cluster = \n")?;
+    row_config(&["set", "--local", "cluster", "none"])?
+        .assert()
         .failure()
-        .stderr(predicate::str::contains("must be a relative"));
+        .stderr(predicate::str::contains("config.toml"));
+    temp.child(".row/config.toml").assert("cluster = \n");
 
     Ok(())
 }
 
 #[test]
 #[parallel]
-fn init_workflow_exists() -> Result<(), Box<dyn std::error::Error>> {
-    let mut cmd = Command::cargo_bin("row")?;
+fn submit_nested() -> Result<(), Box<dyn std::error::Error>> {
     let temp = TempDir::new()?;
-    temp.child("workflow.toml").touch()?;
+    let _ = setup_sample_workflow(&temp, 2);
 
-    cmd.args(["init"]).arg(".").current_dir(temp.path());
-    cmd.assert()
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one"])
+        .args(["--cluster", "slurm-cpu"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .env("SLURM_JOB_ID", "1234")
+        .assert()
         .failure()
-        .stderr(predicate::str::contains("project already exists"));
+        .stderr(predicate::str::contains(
+            "Refusing to submit jobs to 'slurm-cpu' from inside Slurm job 1234.",
+        ));
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one", "--dry-run"])
+        .args(["--cluster", "slurm-cpu"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .env("SLURM_JOB_ID", "1234")
+        .assert()
+        .success();
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .env("SLURM_JOB_ID", "1234")
+        .assert()
+        .success();
 
     Ok(())
 }
 
 #[test]
 #[parallel]
-fn init_parent_exists() -> Result<(), Box<dyn std::error::Error>> {
-    let mut cmd = Command::cargo_bin("row")?;
+fn submit_minimum_free() -> Result<(), Box<dyn std::error::Error>> {
     let temp = TempDir::new()?;
-    temp.child("workflow.toml").touch()?;
-
-    let subdir = temp.child("subdir");
-    subdir.create_dir_all()?;
+    temp.child("workspace").child("dir1").create_dir_all()?;
+    temp.child("workflow.toml").write_str(
+        r#"
+[workspace]
+minimum_free = "1000000TB"
+
+[[action]]
+name = "one"
+command = "touch workspace/{directory}/one"
+products = ["one"]
+"#,
+    )?;
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--dry-run"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    Command::cargo_bin("row")?
+        .arg("submit")
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "which is less than `workspace.minimum_free`",
+        ));
+
+    temp.child("workspace/dir1/one")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn rerun() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 10);
+    complete_action("one", &temp, 10)?;
+
+    Command::cargo_bin("row")?
+        .args(["rerun", "--action", "one", "dir0", "dir1"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .env_remove("ROW_YES")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Pass `--yes` to confirm the rerun when the session is not interactive.",
+        ));
+
+    Command::cargo_bin("row")?
+        .args(["rerun", "--action", "one", "--yes", "dir0", "dir1"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Removing 2 product files from 2 directories",
+        ));
+
+    temp.child("workspace/dir0/one")
+        .assert(predicate::path::missing());
+    temp.child("workspace/dir2/one")
+        .assert(predicate::path::exists());
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^one +8 +0 +2 +0")?);
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn rerun_dry_run() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 10);
+    complete_action("one", &temp, 10)?;
+
+    Command::cargo_bin("row")?
+        .args(["rerun", "--action", "one", "--dry-run", "dir0"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("dir0/one"));
+
+    temp.child("workspace/dir0/one")
+        .assert(predicate::path::exists());
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^one +10 +0 +0 +0")?);
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn directories_no_action() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 4);
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories"])
+        .args(["--cluster", "none"])
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .current_dir(temp.path())
+        .assert()
+        .success()
+        .stdout(predicates::str::diff("dir0\ndir1\ndir2\ndir3\n"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn directories() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 10);
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories"])
+        .args(["--cluster", "none"])
+        .args(["--action", "one"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^Directory Status +Job ID")?)
+        .stdout(predicate::str::is_match("(?m)^dir0 *eligible *$")?)
+        .stdout(predicate::str::is_match("(?m)^dir1 *eligible *$")?)
+        .stdout(predicate::str::is_match("(?m)^dir2 *eligible *$")?)
+        .stdout(predicate::str::is_match("(?m)^dir3 *eligible *$")?)
+        .stdout(predicate::str::is_match("(?m)^dir4 *eligible *$")?)
+        .stdout(predicate::str::is_match("(?m)^dir5 *eligible *$")?)
+        .stdout(predicate::str::is_match("(?m)^dir6 *eligible *$")?)
+        .stdout(predicate::str::is_match("(?m)^dir7 *eligible *$")?)
+        .stdout(predicate::str::is_match("(?m)^dir8 *eligible *$")?)
+        .stdout(predicate::str::is_match("(?m)^dir9 *eligible *$")?);
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn directories_completion() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 4);
+    complete_action("one", &temp, 2)?;
+
+    Command::cargo_bin("row")?
+        .arg("scan")
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "-v"])
+        .args(["--cluster", "none"])
+        .args(["--action", "one"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            "(?m)^Directory Status +Job ID Completed on Completed at",
+        )?)
+        .stdout(predicate::str::is_match(
+            r"(?m)^dir0 *completed +none +\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z *$",
+        )?)
+        .stdout(predicate::str::is_match("(?m)^dir2 *eligible *$")?);
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn directories_select_directories() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 10);
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories"])
+        .args(["--cluster", "none"])
+        .args(["--action", "one"])
+        .arg("dir3")
+        .arg("dir9")
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^Directory Status +Job ID")?)
+        .stdout(predicate::str::is_match("(?m)^dir0 *eligible *$")?.not())
+        .stdout(predicate::str::is_match("(?m)^dir1 *eligible *$")?.not())
+        .stdout(predicate::str::is_match("(?m)^dir2 *eligible *$")?.not())
+        .stdout(predicate::str::is_match("(?m)^dir3 *eligible *$")?)
+        .stdout(predicate::str::is_match("(?m)^dir4 *eligible *$")?.not())
+        .stdout(predicate::str::is_match("(?m)^dir5 *eligible *$")?.not())
+        .stdout(predicate::str::is_match("(?m)^dir6 *eligible *$")?.not())
+        .stdout(predicate::str::is_match("(?m)^dir7 *eligible *$")?.not())
+        .stdout(predicate::str::is_match("(?m)^dir8 *eligible *$")?.not())
+        .stdout(predicate::str::is_match("(?m)^dir9 *eligible *$")?);
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn directories_no_header() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 10);
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories"])
+        .args(["--cluster", "none"])
+        .args(["--action", "one"])
+        .arg("--no-header")
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^Directory Status")?.not());
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn directories_value() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 10);
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories"])
+        .args(["--cluster", "none"])
+        .args(["--value", "/v"])
+        .args(["--value", "/v2"])
+        .args(["--action", "one"])
+        .arg("dir3")
+        .arg("dir9")
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            "(?m)^Directory +Status +Job ID +/v +/v2",
+        )?)
+        .stdout(predicate::str::is_match("(?m)^dir3 +eligible +3 +1$")?)
+        .stdout(predicate::str::is_match("(?m)^dir9 +eligible +9 +4$")?);
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn directories_value_expression() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 10);
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories"])
+        .args(["--cluster", "none"])
+        .args(["--value", "V=/v"])
+        .args(["--value", "S=/v * 2 + /v2"])
+        .args(["--action", "one"])
+        .arg("dir3")
+        .arg("dir9")
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            "(?m)^Directory +Status +Job ID +V +S$",
+        )?)
+        .stdout(predicate::str::is_match("(?m)^dir3 +eligible +3 +7$")?)
+        .stdout(predicate::str::is_match("(?m)^dir9 +eligible +9 +22$")?);
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn directories_value_referenced() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 10);
+    let workflow = fs::read_to_string(temp.child("workflow.toml").path())?;
+    temp.child("workflow.toml").write_str(&workflow.replace(
+        "value_file = \"v.json\"",
+        "value_file = \"v.json\"\nvalue_cache = \"referenced\"",
+    ))?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories"])
+        .args(["--cluster", "none"])
+        .args(["--value", "/v"])
+        .args(["--value", "/v2"])
+        .args(["--action", "one"])
+        .arg("dir3")
+        .arg("dir9")
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^dir3 +eligible +3 +1$")?)
+        .stdout(predicate::str::is_match("(?m)^dir9 +eligible +9 +4$")?);
+
+    let cache = fs::read_to_string(
+        temp.child(DATA_DIRECTORY_NAME)
+            .child(DIRECTORY_CACHE_FILE_NAME)
+            .path(),
+    )?;
+    assert!(cache.contains("\"v\""));
+    assert!(!cache.contains("\"v2\""));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn directories_group_preview() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 10);
+    temp.child("workflow.toml").write_str(
+        r#"
+[workspace]
+value_file = "v.json"
+
+[[action]]
+name = "one"
+command = "touch workspace/{directory}/one"
+products = ["one"]
+group.maximum_size = 4
+"#,
+    )?;
+    complete_action("one", &temp, 1)?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories"])
+        .args(["--cluster", "none"])
+        .args(["--action", "one"])
+        .arg("--group-preview")
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            "(?m)^Directory +Status +Group +Size +Cost",
+        )?)
+        .stdout(predicate::str::is_match("(?m)^dir0 ")?.not())
+        .stdout(predicate::str::is_match(
+            "(?m)^dir1 +eligible +1 +4 +4 CPU-hours$",
+        )?)
+        .stdout(predicate::str::is_match("(?m)^dir2 +eligible *$")?)
+        .stdout(predicate::str::is_match(
+            "(?m)^dir5 +eligible +2 +4 +4 CPU-hours$",
+        )?)
+        .stdout(predicate::str::is_match(
+            "(?m)^dir9 +eligible +3 +1 +1 CPU-hours$",
+        )?);
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn directories_short() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 4);
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories"])
+        .args(["--cluster", "none"])
+        .args(["--action", "one"])
+        .arg("--short")
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicates::str::diff("dir0\ndir1\ndir2\ndir3\n"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn directories_only_incomplete() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 4);
+    complete_action("one", &temp, 2)?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories"])
+        .args(["--cluster", "none"])
+        .args(["--action", "one"])
+        .args(["--only-incomplete", "--short"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicates::str::diff("dir2\ndir3\n"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn directories_short_no_action() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 10);
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories"])
+        .args(["--cluster", "none"])
+        .arg("--short")
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "following required arguments were not provided",
+        ))
+        .stderr(predicate::str::contains("--action"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn show_cluster() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "cluster"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#"name = "none""#));
+
+    Ok(())
+}
+#[test]
+#[parallel]
+fn show_cluster_short() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "cluster"])
+        .args(["--cluster", "none"])
+        .arg("--short")
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::eq("none\n"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn show_launchers() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "launchers"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#"executable = "mpirun""#));
+
+    Ok(())
+}
+#[test]
+#[parallel]
+fn show_launchers_short() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "launchers"])
+        .args(["--cluster", "none"])
+        .arg("--short")
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("mpi"))
+        .stdout(predicate::str::contains("openmp\n"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn show_launchers_resolve() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    temp.child("workflow.toml").write_str(
+        r#"
+[[action]]
+name = "mpi"
+command = "c {directory}"
+launchers = ["mpi"]
+resources.processes.per_directory = 2
+
+[[action]]
+name = "serial"
+command = "c {directory}"
+resources.processes.per_directory = 2
+
+[[action]]
+name = "both"
+command = "c {directory}"
+launchers = ["mpi", "mpi"]
+"#,
+    )?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "launchers", "--resolve", "-a", "mpi"])
+        .args(["--group-size", "3"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::eq("mpirun -n 6\n"));
+
+    Command::cargo_bin("row")?
+        .args(["show", "launchers", "--resolve", "-a", "serial"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No process launcher"));
+
+    Command::cargo_bin("row")?
+        .args(["show", "launchers", "--resolve", "-a", "both"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("More than one process launcher"));
+
+    Command::cargo_bin("row")?
+        .args(["show", "launchers", "--resolve"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn check() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 1);
+
+    Command::cargo_bin("row")?
+        .arg("check")
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::eq(""));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn check_problems() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    temp.child("workflow.toml").write_str(
+        r#"
+[[action]]
+name = "one"
+command = "c"
+launchers = ["not_a_launcher"]
+[action.submit_options.none]
+partition = "not_a_partition"
+"#,
+    )?;
+
+    Command::cargo_bin("row")?
+        .arg("check")
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "Launcher 'not_a_launcher' used by action 'one' not found.",
+        ))
+        .stdout(predicate::str::contains("not_a_partition"))
+        .stderr(predicate::str::contains("Found 2 problem(s)"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn init_conflicting_args() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("row")?;
+    let temp = TempDir::new()?;
+
+    cmd.args(["init"])
+        .arg("--signac")
+        .args(["--workspace", "test"])
+        .current_dir(temp.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn init_invalid_path() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("row")?;
+    let temp = TempDir::new()?;
+
+    cmd.args(["init"])
+        .args(["--workspace", "/test/one"])
+        .arg(".")
+        .current_dir(temp.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("must be a relative"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn init_workflow_exists() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("row")?;
+    let temp = TempDir::new()?;
+    temp.child("workflow.toml").touch()?;
+
+    cmd.args(["init"]).arg(".").current_dir(temp.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("project already exists"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn init_parent_exists() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("row")?;
+    let temp = TempDir::new()?;
+    temp.child("workflow.toml").touch()?;
+
+    let subdir = temp.child("subdir");
+    subdir.create_dir_all()?;
 
     cmd.args(["init"]).arg(".").current_dir(subdir.path());
     cmd.assert().failure().stderr(predicate::str::contains(
@@ -865,32 +1986,2290 @@ fn init_parent_exists() -> Result<(), Box<dyn std::error::Error>> {
 
 #[test]
 #[parallel]
-fn init_cache_exists() -> Result<(), Box<dyn std::error::Error>> {
-    let mut cmd = Command::cargo_bin("row")?;
+fn init_cache_exists() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("row")?;
+    let temp = TempDir::new()?;
+    temp.child(DATA_DIRECTORY_NAME).touch()?;
+
+    cmd.args(["init"]).arg(".").current_dir(temp.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("cache directory"))
+        .stderr(predicate::str::contains("already exists"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn init() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("row")?;
+    let temp = TempDir::new()?;
+
+    cmd.args(["init"]).arg(".").current_dir(temp.path());
+    cmd.assert().success();
+
+    temp.child("workspace").assert(predicate::path::is_dir());
+    temp.child("workflow.toml")
+        .assert(predicate::path::is_file());
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn doctor() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 4);
+
+    Command::cargo_bin("row")?
+        .arg("doctor")
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ok Read the workflow"))
+        .stdout(predicate::str::contains("does not exist yet"))
+        .stdout(predicate::str::contains("Identified cluster 'none'"))
+        .stdout(predicate::str::contains("warning").not());
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+    temp.child(DATA_DIRECTORY_NAME)
+        .child("submit.lock")
+        .touch()?;
+
+    Command::cargo_bin("row")?
+        .arg("doctor")
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("is writable"))
+        .stdout(predicate::str::contains(
+            "The cache is consistent (4 directories)",
+        ))
+        .stdout(predicate::str::is_match(
+            "(?m)^warning Found the lock file .*submit.lock",
+        )?);
+
+    Command::cargo_bin("row")?
+        .arg("doctor")
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .env("PATH", "/not/a/path")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("problem 'bash' not found in PATH"))
+        .stderr(predicate::str::contains("Found 1 problem(s)"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn doctor_repair() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 4);
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    // Complete an action without updating the workspace modification time.
+    temp.child("workspace").child("dir1").child("one").touch()?;
+
+    Command::cargo_bin("row")?
+        .args(["doctor", "--repair"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            "(?m)^repaired Marked 'dir1' completed for action 'one'",
+        )?);
+
+    Command::cargo_bin("row")?
+        .args(["doctor", "--repair"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("The cache needed no repairs."))
+        .stdout(predicate::str::contains("repaired").not());
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn submit_template() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 2);
+    temp.child("home")
+        .child(".config")
+        .child("row")
+        .child("templates")
+        .child("job.sh")
+        .write_str(
+            "#!/bin/bash\n# site prologue\n{{ variables }}{{ execution }}# site epilogue\n",
+        )?;
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one", "--dry-run"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", temp.child("home").path())
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "#!/bin/bash\n# site prologue\n",
+        ))
+        .stdout(predicate::str::contains("export ACTION_NAME=\"one\""))
+        .stdout(predicate::str::contains("trap").not())
+        .stdout(predicate::str::ends_with("# site epilogue\n"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn previous_project() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    for i in 0..2 {
+        temp.child("prep")
+            .child("workspace")
+            .child(format!("dir{i}"))
+            .create_dir_all()?;
+        temp.child("main")
+            .child("workspace")
+            .child(format!("dir{i}"))
+            .create_dir_all()?;
+    }
+    temp.child("prep").child("workflow.toml").write_str(
+        r#"
+[[action]]
+name = "build"
+command = "touch workspace/{directory}/build"
+products = ["build"]
+"#,
+    )?;
+    temp.child("main").child("workflow.toml").write_str(
+        r#"
+[[action]]
+name = "run"
+command = "touch workspace/{directory}/run"
+products = ["run"]
+previous_actions = [{ project = "../prep", action = "build" }]
+"#,
+    )?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.child("main").path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^run +0 +0 +0 +2")?);
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "build", "--yes", "dir1"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.child("prep").path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "run"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.child("main").path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^dir0 +waiting")?)
+        .stdout(predicate::str::is_match("(?m)^dir1 +eligible")?);
+
+    temp.child("main").child("workflow.toml").write_str(
+        r#"
+[[action]]
+name = "run"
+command = "touch workspace/{directory}/run"
+previous_actions = [{ project = "../missing", action = "build" }]
+"#,
+    )?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.child("main").path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No row project found in '../missing'",
+        ));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn alias() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 4);
+
+    Command::cargo_bin("row")?
+        .args(["alias", "add", "first", "dir1"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    Command::cargo_bin("row")?
+        .args(["alias", "add", "dir2", "dir1"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is the name of a directory"));
+
+    Command::cargo_bin("row")?
+        .args(["alias", "add", "other", "dir9"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not found in workspace"));
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "one", "first"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^first +eligible")?)
+        .stdout(predicate::str::contains("dir1").not());
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "one", "--short", "first"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout("dir1\n");
+
+    Command::cargo_bin("row")?
+        .args(["alias", "list"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^first +dir1")?);
+
+    Command::cargo_bin("row")?
+        .args(["alias", "remove", "first"])
+        .current_dir(temp.path())
+        .assert()
+        .success();
+
+    Command::cargo_bin("row")?
+        .args(["alias", "remove", "first"])
+        .current_dir(temp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Alias 'first' not found"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn show_output() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 4);
+
+    Command::cargo_bin("row")?
+        .args(["show", "status", "--output", "status.csv"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout("");
+
+    temp.child("status.csv").assert(
+        "Action,Completed,Submitted,Eligible,Waiting,Remaining cost,Submitted jobs\n\
+         one,0,0,4,0,4 CPU-hours,[]\n\
+         two,0,0,0,4,4 CPU-hours,[]\n",
+    );
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "one", "dir1", "dir2"])
+        .args(["--output", "directories.json"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout("");
+
+    temp.child("directories.json").assert(
+        "[\n  \
+         {\"Directory\": \"dir1\", \"Status\": \"eligible\", \"Job ID\": \"\"},\n  \
+         {\"Directory\": \"dir2\", \"Status\": \"eligible\", \"Job ID\": \"\"}\n\
+         ]\n",
+    );
+
+    Command::cargo_bin("row")?
+        .args(["show", "cluster", "--output", "cluster.json"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    temp.child("cluster.json")
+        .assert(predicate::str::contains(r#""name": "none""#));
+
+    Command::cargo_bin("row")?
+        .args(["show", "cluster", "--output", "cluster.csv"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("use a '.json' or '.toml' file"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn submit_describe_group() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 4);
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one", "--dry-run", "-v", "dir1"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "1 directory, 1 process, 60 minutes walltime, costs up to 1 CPU-hours.",
+        ));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn submit_leftover() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 5);
+    let workflow = fs::read_to_string(temp.child("workflow.toml").path())?;
+    temp.child("workflow.toml").write_str(&workflow.replace(
+        "tags = [\"first\"]",
+        "tags = [\"first\"]\ngroup.maximum_size = 2\ngroup.minimum_size = 2\ngroup.leftover = \"defer\"",
+    ))?;
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one", "--dry-run"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Deferring 1 group of action 'one' (1 directory) smaller than `minimum_size=2`.",
+        ))
+        .stdout(predicate::str::contains("dir3"))
+        .stdout(predicate::str::contains("dir4").not());
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn submit_concurrent_directories() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 5);
+    let workflow = fs::read_to_string(temp.child("workflow.toml").path())?;
+    temp.child("workflow.toml").write_str(&workflow.replace(
+        "tags = [\"first\"]",
+        "tags = [\"first\"]\nresources.processes.per_directory = 1\nresources.concurrent_directories = 2",
+    ))?;
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    Command::cargo_bin("row")?
+        .args(["show", "status", "--action", "one"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^one +5 +0 +0 +0")?);
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn submit_aggregate() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 10);
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one", "--aggregate", "4", "--dry-run"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            " exec --no-progress --skip-setup --cluster none -a one -",
+        ))
+        .stdout(predicate::str::contains("'dir3'\n)"));
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one", "--aggregate", "4"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Submitting 1 job"))
+        .stdout(predicate::str::contains(
+            "Executing action 'one' on directory dir3.",
+        ));
+
+    Command::cargo_bin("row")?
+        .args(["show", "status", "--action", "one"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^one +4 +0 +6 +0")?);
+
+    // `row exec` skips directories that completed since the project was scanned.
+    temp.child("workspace/dir4/one").touch()?;
+    Command::cargo_bin("row")?
+        .args(["exec", "--action", "one", "dir4", "dir5"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("directory dir4").not())
+        .stdout(predicate::str::contains(
+            "Executing action 'one' on directory dir5.",
+        ));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn exec() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 4);
+    let workflow = fs::read_to_string(temp.child("workflow.toml").path())?;
+    temp.child("workflow.toml").write_str(&workflow.replace(
+        "tags = [\"first\"]",
+        "tags = [\"first\"]\nsubmit_options.none.setup = \"echo setup-$ACTION_NAME\"",
+    ))?;
+
+    Command::cargo_bin("row")?
+        .args(["exec", "--action", "one", "--dry-run", "dir1"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("directories=(\n'dir1'\n)"))
+        .stdout(predicate::str::contains("echo setup-$ACTION_NAME"));
+
+    Command::cargo_bin("row")?
+        .args(["exec", "--action", "one", "dir1"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("setup-one"));
+    temp.child("workspace/dir1/one")
+        .assert(predicate::path::exists());
+    temp.child("workspace/dir2/one")
+        .assert(predicate::path::missing());
+
+    Command::cargo_bin("row")?
+        .args(["exec", "--action", "one", "--skip-setup", "dir1", "dir2"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("directory dir1").not())
+        .stdout(predicate::str::contains(
+            "Executing action 'one' on directory dir2.",
+        ))
+        .stdout(predicate::str::contains("setup-one").not());
+
+    Command::cargo_bin("row")?
+        .args(["exec", "--action", "one", "--force", "--skip-setup", "dir1"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Executing action 'one' on directory dir1.",
+        ));
+
+    Command::cargo_bin("row")?
+        .args(["exec", "--action", "four"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Action 'four' not found"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn submit_log_output() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 2);
+    let workflow = fs::read_to_string(temp.child("workflow.toml").path())?;
+    temp.child("workflow.toml").write_str(&workflow.replace(
+        "command = \"touch workspace/{directory}/one\"",
+        "command = \"echo output {directory}; touch workspace/{directory}/one\"\nlog_output = true",
+    ))?;
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("output dir0").not());
+
+    for directory in ["dir0", "dir1"] {
+        let logs = fs::read_dir(temp.child("workspace").child(directory).child(".row_logs"))?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(logs.len(), 1);
+        let name = logs[0].file_name().into_string().unwrap();
+        assert!(name.starts_with("one-") && name.ends_with(".log"), "{name}");
+        assert_eq!(
+            fs::read_to_string(logs[0].path())?,
+            format!("output {directory}\n")
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn show_cluster_project() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 1);
+    let cluster = |partition: &str| {
+        format!(
+            r#"
+[[cluster]]
+name = "test"
+identify.always = false
+scheduler = "bash"
+[[cluster.partition]]
+name = "{partition}"
+"#
+        )
+    };
+    temp.child(".config")
+        .child("row")
+        .child("clusters.toml")
+        .write_str(&cluster("user"))?;
+    temp.child("clusters.toml").write_str(&cluster("project"))?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "cluster"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path().join("workspace"))
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#"name = "project""#))
+        .stdout(predicate::str::contains(r#"name = "user""#).not());
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn show_cluster_partitions() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 1);
+    temp.child(".config")
+        .child("row")
+        .child("clusters.toml")
+        .write_str(
+            r#"
+[[cluster]]
+name = "test"
+identify.always = false
+scheduler = "bash"
+[[cluster.partition]]
+name = "shared"
+maximum_cpus_per_job = 127
+maximum_gpus_per_job = 0
+memory_per_cpu = "2G"
+[[cluster.partition]]
+name = "compute"
+require_cpus_multiple_of = 128
+cpus_per_node = 128
+[[cluster.partition]]
+name = "debug"
+prevent_auto_select = true
+"#,
+        )?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "cluster", "--partitions", "--no-header"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            "(?m)^shared +at most 127 +0 +2G/CPU +- +yes +one, two, three$",
+        )?)
+        .stdout(predicate::str::is_match(
+            "(?m)^compute +multiple of 128 +any +- +128 CPUs +yes +-$",
+        )?)
+        .stdout(predicate::str::is_match(
+            "(?m)^debug +any +any +- +- +no +-$",
+        )?);
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn status_offline() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 2);
+    temp.child(".config")
+        .child("row")
+        .child("clusters.toml")
+        .write_str(
+            r#"
+[[cluster]]
+name = "test"
+identify.always = false
+scheduler = "slurm"
+[[cluster.partition]]
+name = "shared"
+"#,
+        )?;
+
+    let bin = temp.child("bin");
+    bin.create_dir_all()?;
+    let add_executable = |name: &str, contents: &str| -> std::io::Result<()> {
+        let path = bin.child(name);
+        fs::write(path.path(), contents)?;
+        fs::set_permissions(path.path(), fs::Permissions::from_mode(0o755))
+    };
+    add_executable("sbatch", "#!/bin/sh\ncat > /dev/null\necho 42\n")?;
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", temp.path())
+        .env("PATH", bin.path())
+        .assert()
+        .success();
+
+    // squeue is not in the PATH.
+    Command::cargo_bin("row")?
+        .args(["show", "status", "--action", "one"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", temp.path())
+        .env("PATH", bin.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("'squeue' not found"))
+        .stdout(predicate::str::is_match("(?m)^Action +Completed +Unknown")?)
+        .stdout(predicate::str::is_match("(?m)^one +0 +2 +0 +0")?);
+
+    // A failing squeue keeps the submitted jobs.
+    add_executable("squeue", "#!/bin/sh\nexit 1\n")?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "status", "--action", "one"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", temp.path())
+        .env("PATH", bin.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("squeue exited with code 1"))
+        .stderr(predicate::str::contains(
+            "Assuming that it is still submitted",
+        ))
+        .stdout(predicate::str::is_match("(?m)^one +0 +2 +0 +0")?);
+
+    // --offline does not execute squeue.
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "one", "--offline"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", temp.path())
+        .env("PATH", bin.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^dir0 +unknown +test/42")?);
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn status_queue_retry() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 2);
+    temp.child(".config")
+        .child("row")
+        .child("clusters.toml")
+        .write_str(
+            r#"
+[[cluster]]
+name = "test"
+identify.always = false
+scheduler = "slurm"
+[[cluster.partition]]
+name = "shared"
+"#,
+        )?;
+
+    let bin = temp.child("bin");
+    bin.create_dir_all()?;
+    let add_executable = |name: &str, contents: &str| -> std::io::Result<()> {
+        let path = bin.child(name);
+        fs::write(path.path(), contents)?;
+        fs::set_permissions(path.path(), fs::Permissions::from_mode(0o755))
+    };
+    add_executable("sbatch", "#!/bin/sh\ncat > /dev/null\necho 42\n")?;
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", temp.path())
+        .env("PATH", bin.path())
+        .assert()
+        .success();
+
+    // The first query returns garbage, the second reports that the job finished.
+    let attempts = temp.child("attempts");
+    add_executable(
+        "squeue",
+        &format!(
+            "#!/bin/sh\necho >> {0}\nif [ $(wc -l < {0}) -eq 1 ]; then echo 'slurm_load_jobs error'; fi\n",
+            attempts.path().display()
+        ),
+    )?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "status", "--action", "one"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", bin.path().display()))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Retrying"))
+        .stdout(predicate::str::is_match(
+            "(?m)^Action +Completed +Submitted",
+        )?)
+        .stdout(predicate::str::is_match("(?m)^one +0 +0 +2 +0")?);
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn status_queue_timeout() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 2);
+    temp.child(".config")
+        .child("row")
+        .child("clusters.toml")
+        .write_str(
+            r#"
+[[cluster]]
+name = "test"
+identify.always = false
+scheduler = "slurm"
+[[cluster.partition]]
+name = "shared"
+"#,
+        )?;
+
+    let bin = temp.child("bin");
+    bin.create_dir_all()?;
+    let add_executable = |name: &str, contents: &str| -> std::io::Result<()> {
+        let path = bin.child(name);
+        fs::write(path.path(), contents)?;
+        fs::set_permissions(path.path(), fs::Permissions::from_mode(0o755))
+    };
+    add_executable("sbatch", "#!/bin/sh\ncat > /dev/null\necho 42\n")?;
+    add_executable("squeue", "#!/bin/sh\nexec sleep 60\n")?;
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", bin.path().display()))
+        .assert()
+        .success();
+
+    Command::cargo_bin("row")?
+        .args(["show", "status", "--action", "one", "--queue-timeout", "1"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", temp.path())
+        .env("PATH", format!("{}:/usr/bin:/bin", bin.path().display()))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "squeue did not finish within 1 seconds",
+        ))
+        .stdout(predicate::str::is_match("(?m)^Action +Completed +Unknown")?)
+        .stdout(predicate::str::is_match("(?m)^one +0 +2 +0 +0")?);
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn submit_sbatch_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 2);
+    temp.child(".config")
+        .child("row")
+        .child("clusters.toml")
+        .write_str(
+            r#"
+[[cluster]]
+name = "test"
+identify.always = false
+scheduler = "slurm"
+[[cluster.partition]]
+name = "shared"
+"#,
+        )?;
+
+    let bin = temp.child("bin");
+    bin.create_dir_all()?;
+    let add_executable = |name: &str, contents: &str| -> std::io::Result<()> {
+        let path = bin.child(name);
+        fs::write(path.path(), contents)?;
+        fs::set_permissions(path.path(), fs::Permissions::from_mode(0o755))
+    };
+    add_executable(
+        "sbatch",
+        "#!/bin/sh\ncat > /dev/null\n>&2 echo 'sbatch: error: Batch job submission failed: \
+         Invalid account or account/partition combination specified'\nexit 1\n",
+    )?;
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one", "dir0"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", temp.path())
+        .env("PATH", bin.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "sbatch rejected the job for action 'one': partition 'shared' requires an account",
+        ))
+        .stderr(predicate::str::contains(
+            "Invalid account or account/partition",
+        ));
+
+    // sbatch may accept the job with a warning.
+    add_executable(
+        "sbatch",
+        "#!/bin/sh\ncat > /dev/null\n>&2 echo 'sbatch: Warning: job will be delayed'\necho 42\n",
+    )?;
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one", "dir0"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", temp.path())
+        .env("PATH", bin.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "sbatch: Warning: job will be delayed",
+        ));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn show_cluster_why() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    temp.child(".config")
+        .child("row")
+        .child("clusters.toml")
+        .write_str(
+            r#"
+[[cluster]]
+name = "first"
+identify.by_environment = ["_ROW_TEST_CLUSTER", "first"]
+scheduler = "bash"
+[[cluster.partition]]
+name = "shared"
+
+[[cluster]]
+name = "second"
+identify.always = true
+scheduler = "bash"
+[[cluster.partition]]
+name = "shared"
+"#,
+        )?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "cluster", "--why", "--no-header"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env_remove("ROW_CLUSTER")
+        .env("_ROW_TEST_CLUSTER", "other")
+        .env("ROW_HOME", temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            "(?m)^first +no +\\$_ROW_TEST_CLUSTER is 'other', not 'first'$",
+        )?)
+        .stdout(predicate::str::is_match(
+            "(?m)^second +yes +always = true$",
+        )?)
+        .stdout(predicate::str::is_match(
+            "(?m)^none +no +not checked: 'second' matched first$",
+        )?);
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn wait() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 4);
+
+    Command::cargo_bin("row")?
+        .args(["wait", "-a", "one", "--until", "eligible"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    Command::cargo_bin("row")?
+        .args(["wait", "-a", "two", "--until", "eligible"])
+        .args(["--timeout", "1s", "--interval", "1"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Timed out after 1 second: 4 directories",
+        ));
+
+    Command::cargo_bin("row")?
+        .args(["wait", "-a", "two", "--until", "eligible"])
+        .args(["--timeout", "1s", "--interval", "1"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .env("ROW_TIME_FORMAT", "clock")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Timed out after 00:00:01: 4 directories",
+        ));
+
+    Command::cargo_bin("row")?
+        .args(["wait", "-a", "missing"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn mv_rm() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 4);
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one", "dir0"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    Command::cargo_bin("row")?
+        .args(["alias", "add", "first", "dir0"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    Command::cargo_bin("row")?
+        .args(["mv", "first", "renamed"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    temp.child("workspace")
+        .child("dir0")
+        .assert(predicate::path::missing());
+    temp.child("workspace")
+        .child("renamed")
+        .child("one")
+        .assert(predicate::path::is_file());
+
+    Command::cargo_bin("row")?
+        .args([
+            "show",
+            "directories",
+            "--action",
+            "one",
+            "--completed",
+            "--short",
+        ])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout("renamed\n");
+
+    Command::cargo_bin("row")?
+        .args(["alias", "list"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^first +renamed")?);
+
+    Command::cargo_bin("row")?
+        .args(["mv", "dir1", "dir2"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+
+    Command::cargo_bin("row")?
+        .args(["mv", "dir1", "a/b"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not a valid directory name"));
+
+    Command::cargo_bin("row")?
+        .args(["rm", "--dry-run", "dir1", "first"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout("dir1\nrenamed\n");
+
+    Command::cargo_bin("row")?
+        .args(["rm", "dir1"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .env_remove("ROW_YES")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Pass `--yes` to confirm the removal when the session is not interactive.",
+        ));
+
+    Command::cargo_bin("row")?
+        .args(["rm", "-"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .env_remove("ROW_YES")
+        .write_stdin("dir1\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "when reading directories from stdin",
+        ));
+
+    temp.child("workspace")
+        .child("dir1")
+        .assert(predicate::path::is_dir());
+
+    Command::cargo_bin("row")?
+        .args(["rm", "--yes", "dir1", "first"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    temp.child("workspace")
+        .child("dir1")
+        .assert(predicate::path::missing());
+    temp.child("workspace")
+        .child("renamed")
+        .assert(predicate::path::missing());
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "one", "--short"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout("dir2\ndir3\n");
+
+    Command::cargo_bin("row")?
+        .args(["alias", "list"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("first").not());
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn mv_rm_submitted() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 2);
+    temp.child(".config")
+        .child("row")
+        .child("clusters.toml")
+        .write_str(
+            r#"
+[[cluster]]
+name = "test"
+identify.always = false
+scheduler = "slurm"
+[[cluster.partition]]
+name = "shared"
+"#,
+        )?;
+
+    let bin = temp.child("bin");
+    bin.create_dir_all()?;
+    let add_executable = |name: &str, contents: &str| -> std::io::Result<()> {
+        let path = bin.child(name);
+        fs::write(path.path(), contents)?;
+        fs::set_permissions(path.path(), fs::Permissions::from_mode(0o755))
+    };
+    add_executable("sbatch", "#!/bin/sh\ncat > /dev/null\necho 42\n")?;
+    add_executable("squeue", "#!/bin/sh\necho 42\n")?;
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one", "dir0"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", temp.path())
+        .env("PATH", bin.path())
+        .assert()
+        .success();
+
+    Command::cargo_bin("row")?
+        .args(["mv", "dir0", "renamed"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", temp.path())
+        .env("PATH", bin.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Directory 'dir0' is submitted for action 'one'.",
+        ));
+
+    Command::cargo_bin("row")?
+        .args(["rm", "--yes", "dir0", "dir1"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", temp.path())
+        .env("PATH", bin.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Directory 'dir0' is submitted for action 'one'.",
+        ));
+
+    temp.child("workspace")
+        .child("dir1")
+        .assert(predicate::path::is_dir());
+
+    Command::cargo_bin("row")?
+        .args(["mv", "--force", "dir0", "renamed"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", temp.path())
+        .env("PATH", bin.path())
+        .assert()
+        .success();
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "one"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", temp.path())
+        .env("PATH", bin.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            "(?m)^renamed +submitted +test/42",
+        )?);
+
+    Command::cargo_bin("row")?
+        .args(["rm", "--yes", "--force", "renamed"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", temp.path())
+        .env("PATH", bin.path())
+        .assert()
+        .success();
+
+    temp.child("workspace")
+        .child("renamed")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn sweep() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 1);
+    temp.child("sweep.toml").write_str(
+        r#"
+name = "n{/n}_T{/T}"
+
+[product]
+n = [1, 2]
+T = [0.5, 1.0]
+"#,
+    )?;
+
+    Command::cargo_bin("row")?
+        .args(["sweep", "--dry-run", "sweep.toml"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout("n1_T0.5\nn2_T0.5\nn1_T1.0\nn2_T1.0\n");
+
+    temp.child("workspace")
+        .child("n1_T0.5")
+        .assert(predicate::path::missing());
+
+    Command::cargo_bin("row")?
+        .args(["sweep", "sweep.toml"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout("Created 4 new directories (0 already exist).\n");
+
+    temp.child("workspace")
+        .child("n2_T1.0")
+        .child("v.json")
+        .assert(predicate::path::is_file());
+
+    Command::cargo_bin("row")?
+        .args(["sweep", "sweep.toml"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout("Created 0 new directories (4 already exist).\n");
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "one", "--short"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("n2_T0.5"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn submit_command_override() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    temp.child("workspace").child("dir0").create_dir_all()?;
+    temp.child("submit.sh").write_str(
+        r#"#!/bin/sh
+cp "$1" submitted.sh || exit 1
+echo "Submitted batch job 7"
+"#,
+    )?;
+    temp.child("workflow.toml").write_str(
+        r#"
+[[action]]
+name = "one"
+command = "touch workspace/{directory}/one"
+products = ["one"]
+submit_command_override = "sh submit.sh {script}"
+
+[[action]]
+name = "two"
+command = "touch workspace/{directory}/two"
+products = ["two"]
+submit_command_override = "echo 'site policy' >&2; exit 3 # {script}"
+"#,
+    )?;
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one"])
+        .args(["--cluster", "slurm-cpu"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    temp.child("submitted.sh")
+        .assert(predicate::str::contains("#SBATCH --job-name=one"));
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "two"])
+        .args(["--cluster", "slurm-cpu"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("site policy"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn fake_scheduler() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 2);
+    let fake = temp.child("fake");
+
+    Command::cargo_bin("row")?
+        .args(["debug", "fake-scheduler", "install"])
+        .arg(fake.path())
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("export PATH="));
+
+    fake.child("sbatch").assert(predicate::path::is_file());
+    fake.child("queue").assert("");
+
+    let path = format!(
+        "{}:{}",
+        fake.path().display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one"])
+        .args(["--cluster", "slurm-cpu"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .env("PATH", &path)
+        .assert()
+        .success();
+
+    fake.child("queue").assert("1\n");
+    fake.child("jobs")
+        .child("1.sh")
+        .assert(predicate::str::contains("#SBATCH --constraint=\"fake\""));
+
+    Command::cargo_bin("row")?
+        .args([
+            "show",
+            "directories",
+            "--action",
+            "one",
+            "--submitted",
+            "--short",
+        ])
+        .args(["--cluster", "slurm-cpu"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .env("PATH", &path)
+        .assert()
+        .success()
+        .stdout("dir0\ndir1\n");
+
+    Command::cargo_bin("row")?
+        .args([
+            "show",
+            "status",
+            "--action",
+            "one",
+            "--output",
+            "status.json",
+        ])
+        .args(["--cluster", "slurm-cpu"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .env("PATH", &path)
+        .assert()
+        .success();
+
+    temp.child("status.json").assert(predicate::str::contains(
+        r#""Submitted jobs": [{"cluster":"slurm-cpu","directory":"dir0","job_id":1},{"cluster":"slurm-cpu","directory":"dir1","job_id":1}]"#,
+    ));
+
+    Command::new(fake.child("scancel").path())
+        .arg("1")
+        .assert()
+        .success();
+    fake.child("queue").assert("");
+
+    Command::cargo_bin("row")?
+        .args([
+            "show",
+            "directories",
+            "--action",
+            "one",
+            "--eligible",
+            "--short",
+        ])
+        .args(["--cluster", "slurm-cpu"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .env("PATH", &path)
+        .assert()
+        .success()
+        .stdout("dir0\ndir1\n");
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one"])
+        .args(["--cluster", "slurm-cpu"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .env("PATH", &path)
+        .env("ROW_FAKE_SBATCH_ERROR", "Batch job submission failed")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Batch job submission failed"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn project_option() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 2);
+    let elsewhere = TempDir::new()?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .current_dir(elsewhere.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure();
+
+    temp.child("workspace/dir1/one").touch()?;
+
+    Command::cargo_bin("row")?
+        .args(["scan", "--project"])
+        .arg(temp.path())
+        .args(["--cluster", "none"])
+        .current_dir(elsewhere.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    Command::cargo_bin("row")?
+        .args([
+            "show",
+            "directories",
+            "--action",
+            "one",
+            "--completed",
+            "--short",
+        ])
+        .args(["--cluster", "none"])
+        .current_dir(elsewhere.path())
+        .env("ROW_HOME", "/not/a/path")
+        .env("ROW_PROJECT", temp.path())
+        .assert()
+        .success()
+        .stdout("dir1\n");
+
+    Command::cargo_bin("row")?
+        .args(["show", "status", "--project", "/not/a/path"])
+        .args(["--cluster", "none"])
+        .current_dir(elsewhere.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Unable to change to the project directory",
+        ));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn workspace_option() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 2);
+    temp.child("other/dirA/v.json").write_str("{\"v\": 1}")?;
+    temp.child("other/dirA/one").touch()?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "one", "--short"])
+        .args(["--workspace", "other"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout("dirA\n");
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "one", "--short"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout("dir0\ndir1\n");
+
+    Command::cargo_bin("row")?
+        .args([
+            "show",
+            "directories",
+            "--action",
+            "one",
+            "--completed",
+            "--short",
+        ])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .env("ROW_WORKSPACE", "other")
+        .assert()
+        .success()
+        .stdout("dirA\n");
+
+    temp.child(DATA_DIRECTORY_NAME)
+        .child(DIRECTORY_CACHE_FILE_NAME)
+        .assert(predicate::str::contains("dirA").not());
+    temp.child(DATA_DIRECTORY_NAME)
+        .child("workspaces")
+        .assert(predicate::path::is_dir());
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn table_max_width() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 0);
+    temp.child("workspace/a_very_long_directory_name/v.json")
+        .write_str(r#"{"v": "a long value"}"#)?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "one", "--value", "/v"])
+        .args(["--max-width", "30"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "a_very_l… eligible        \"a long…\n",
+        ));
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "one", "--value", "/v"])
+        .args(["--max-width", "30", "--wrap"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "a_very_lo eligible        \"a long \nng_direct                   value\"\nory_name\n",
+        ));
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "one", "--value", "/v"])
+        .args(["--output", "directories.tsv"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    temp.child("directories.tsv").assert(
+        "Directory\tStatus\tJob ID\t/v\na_very_long_directory_name\teligible\t\t\"a long value\"\n",
+    );
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn quiet_exit_codes() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 2);
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one", "--quiet"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    temp.child("workspace/dir0/one")
+        .assert(predicate::path::exists());
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one", "--quiet"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .code(5)
+        .stdout("")
+        .stderr("");
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    temp.child("workflow.toml").write_str(
+        r#"
+[[action]]
+name = "one"
+command = "c"
+previous_actions = ["missing"]
+"#,
+    )?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "status", "--quiet"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("missing"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn cache_dir() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 2);
+    let cache = TempDir::new()?;
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one"])
+        .args(["--cluster", "none"])
+        .arg("--cache-dir")
+        .arg(cache.path())
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    // The cache files are in a subdirectory named by a hash of the project root.
+    temp.child(".row").assert(predicate::path::missing());
+    let subdirectories = fs::read_dir(cache.path())?.collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(subdirectories.len(), 1);
+    assert!(subdirectories[0].path().join("directories.json").is_file());
+
+    Command::cargo_bin("row")?
+        .args(["show", "status", "--short", "--action", "one"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .env("ROW_CACHE_DIR", cache.path())
+        .assert()
+        .success()
+        .stdout("one\t2\t0\t0\t0\n");
+    temp.child(".row").assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn submit_account() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 1);
+    temp.child(".config")
+        .child("row")
+        .child("clusters.toml")
+        .write_str(
+            r#"
+[[cluster]]
+name = "test"
+identify.always = false
+scheduler = "slurm"
+[[cluster.partition]]
+name = "shared"
+account_suffix = "-cpu"
+"#,
+        )?;
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one", "--dry-run"])
+        .args(["--account", "mine"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("#SBATCH --account=mine-cpu\n"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn export() -> Result<(), Box<dyn std::error::Error>> {
     let temp = TempDir::new()?;
-    temp.child(DATA_DIRECTORY_NAME).touch()?;
+    let _ = setup_sample_workflow(&temp, 2);
 
-    cmd.args(["init"]).arg(".").current_dir(temp.path());
-    cmd.assert()
+    Command::cargo_bin("row")?
+        .args(["export", "bundle.tar"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    let bundle = String::from_utf8_lossy(&fs::read(temp.child("bundle.tar").path())?).into_owned();
+    for name in [
+        "row-export/workflow.toml",
+        "row-export/clusters.toml",
+        "row-export/launchers.toml",
+        "row-export/values.json",
+        "row-export/completed.json",
+        "row-export/submitted.json",
+    ] {
+        assert!(bundle.contains(name), "{name} is not in the bundle");
+    }
+    assert!(bundle.contains(r#""dir1": {"#));
+
+    Command::cargo_bin("row")?
+        .args(["export", "--anonymize", "anonymous.tar"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    let bundle =
+        String::from_utf8_lossy(&fs::read(temp.child("anonymous.tar").path())?).into_owned();
+    assert!(!bundle.contains("row-export/values.json"));
+    assert!(bundle.contains("row-export/completed.json"));
+    assert!(!bundle.contains(r#""dir1""#));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn import() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 4);
+    complete_action("one", &temp, 2)?;
+
+    for args in [
+        vec!["export", "bundle.tar"],
+        vec!["export", "--anonymize", "anonymous.tar"],
+    ] {
+        Command::cargo_bin("row")?
+            .args(args)
+            .args(["--cluster", "none"])
+            .current_dir(temp.path())
+            .env("ROW_HOME", "/not/a/path")
+            .assert()
+            .success();
+    }
+
+    fs::remove_dir_all(temp.child(".row").path())?;
+    for i in 0..2 {
+        fs::remove_file(
+            temp.child("workspace")
+                .child(format!("dir{i}"))
+                .child("one")
+                .path(),
+        )?;
+    }
+
+    Command::cargo_bin("row")?
+        .args(["import", "bundle.tar"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "Imported 2 completions, 0 submitted",
+        ));
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^one +2 +0 +2 +0")?);
+
+    Command::cargo_bin("row")?
+        .args(["import", "anonymous.tar"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
         .failure()
-        .stderr(predicate::str::contains("cache directory"))
-        .stderr(predicate::str::contains("already exists"));
+        .stderr(predicate::str::contains("anonymized"));
 
     Ok(())
 }
 
 #[test]
 #[parallel]
-fn init() -> Result<(), Box<dyn std::error::Error>> {
-    let mut cmd = Command::cargo_bin("row")?;
+fn show_progress() -> Result<(), Box<dyn std::error::Error>> {
     let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 4);
+    complete_action("one", &temp, 2)?;
 
-    cmd.args(["init"]).arg(".").current_dir(temp.path());
-    cmd.assert().success();
+    Command::cargo_bin("row")?
+        .args(["show", "progress"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            "(?m)^ +4 +2 CPU-hours +8 CPU-hours +25.0%$",
+        )?);
 
-    temp.child("workspace").assert(predicate::path::is_dir());
-    temp.child("workflow.toml")
-        .assert(predicate::path::is_file());
+    Command::cargo_bin("row")?
+        .args(["show", "progress", "--by", "/v2"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            "(?m)^0 +2 +2 CPU-hours +4 CPU-hours +50.0%$",
+        )?)
+        .stdout(predicate::str::is_match(
+            "(?m)^1 +2 +0 CPU-hours +4 CPU-hours +0.0%$",
+        )?)
+        .stdout(predicate::str::is_match("(?m)^All +4 .* +25.0%$")?);
+
+    Command::cargo_bin("row")?
+        .args(["show", "progress", "--action", "one"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m) +50.0%$")?);
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn suggest_names() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 2);
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "twoo"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Action 'twoo' not found in the workflow.",
+        ))
+        .stderr(predicate::str::contains("Did you mean 'two'?"));
+
+    Command::cargo_bin("row")?
+        .args(["rm", "--yes", "dri1"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Directory 'dri1' not found in workspace.",
+        ))
+        .stderr(predicate::str::contains("Did you mean 'dir1'?"));
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "two", "compress"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Did you mean").not());
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn previous_actions_threshold() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 4);
+    temp.child("workflow.toml").write_str(
+        r#"
+[workspace]
+value_file = "v.json"
+
+[[action]]
+name = "one"
+command = "touch workspace/{directory}/one"
+products = ["one"]
+
+[[action]]
+name = "analyze"
+command = "echo {directories}"
+previous_actions = [{ action = "one", fraction = 0.75 }]
+"#,
+    )?;
+    complete_action("one", &temp, 2)?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^analyze +0 +0 +0 +4")?);
+
+    complete_action("one", &temp, 3)?;
+
+    Command::cargo_bin("row")?
+        .arg("scan")
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success();
+
+    Command::cargo_bin("row")?
+        .args(["show", "status"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("(?m)^analyze +0 +0 +3 +1")?);
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn directories_set_operations() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 6);
+    temp.child("a.txt").write_str("dir0\ndir1\ndir2\ndir3\n")?;
+    temp.child("b.txt").write_str("dir2\ndir3\ndir4\n\n")?;
+    temp.child("c.txt").write_str("dir3\n")?;
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "one", "--short"])
+        .args(["--intersect", "a.txt", "--intersect", "-"])
+        .args(["--difference", "c.txt"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .write_stdin("dir1\ndir2\ndir3\n")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout("dir1\ndir2\n");
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "dir5"])
+        .args(["--union", "b.txt", "--difference", "c.txt"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout("dir2\ndir4\ndir5\n");
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "one", "--short"])
+        .args(["--union", "b.txt", "--union", "c.txt"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout("dir2\ndir3\ndir4\n");
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "-", "--intersect", "-"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .write_stdin("dir0\ndir1\n")
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .success()
+        .stdout("dir0\ndir1\n");
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--union", "missing.txt"])
+        .args(["--cluster", "none"])
+        .current_dir(temp.path())
+        .env("ROW_HOME", "/not/a/path")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("missing.txt"));
+
+    Ok(())
+}
+
+#[test]
+#[parallel]
+fn job_url_template() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new()?;
+    let _ = setup_sample_workflow(&temp, 2);
+    temp.child(".config")
+        .child("row")
+        .child("clusters.toml")
+        .write_str(
+            r#"
+[[cluster]]
+name = "test"
+identify.always = false
+scheduler = "slurm"
+job_url_template = "https://portal/jobs/{job_id}"
+[[cluster.partition]]
+name = "shared"
+"#,
+        )?;
+
+    let bin = temp.child("bin");
+    bin.create_dir_all()?;
+    let add_executable = |name: &str, contents: &str| -> std::io::Result<()> {
+        let path = bin.child(name);
+        fs::write(path.path(), contents)?;
+        fs::set_permissions(path.path(), fs::Permissions::from_mode(0o755))
+    };
+    add_executable("sbatch", "#!/bin/sh\ncat > /dev/null\necho 42\n")?;
+    add_executable("squeue", "#!/bin/sh\necho 42\n")?;
+
+    Command::cargo_bin("row")?
+        .args(["submit", "--action", "one", "dir0"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", temp.path())
+        .env("PATH", bin.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Row submitted job 42 (https://portal/jobs/42).",
+        ));
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "one"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env("ROW_COLOR", "always")
+        .env("ROW_HOME", temp.path())
+        .env("PATH", bin.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\x1b]8;;https://portal/jobs/42\x1b\\test/42\x1b]8;;\x1b\\",
+        ));
+
+    Command::cargo_bin("row")?
+        .args(["show", "directories", "--action", "one"])
+        .args(["--cluster", "test"])
+        .current_dir(temp.path())
+        .env_remove("ROW_COLOR")
+        .env_remove("CLICOLOR")
+        .env("ROW_HOME", temp.path())
+        .env("PATH", bin.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("test/42"))
+        .stdout(predicate::str::contains("\x1b]8;;").not());
 
     Ok(())
 }